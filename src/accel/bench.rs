@@ -0,0 +1,65 @@
+// micro benchmarks run as ignored tests, in release builds with
+// cargo test --release -- --ignored bench_ --nocapture
+use super::super::math::{Point3D, Ray, ScalarF, Vec3D};
+use super::super::shapes::Sphere;
+use cgmath::InnerSpace;
+use rand::rngs::StdRng;
+use rand::Rng;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+// fastest of `runs` timings, the one least disturbed by other work
+pub fn best_of<R, F: FnMut() -> R>(runs: usize, mut f: F) -> Duration {
+    (0..runs)
+        .map(|_| {
+            let start = Instant::now();
+            black_box(f());
+            start.elapsed()
+        })
+        .min()
+        .unwrap()
+}
+
+pub fn report(name: &str, baseline: (&str, Duration), candidate: (&str, Duration)) {
+    println!(
+        "{}: {} {:?}, {} {:?}, {:.2}x",
+        name,
+        baseline.0,
+        baseline.1,
+        candidate.0,
+        candidate.1,
+        baseline.1.as_secs_f64() / candidate.1.as_secs_f64()
+    );
+}
+
+pub fn random_spheres(rng: &mut StdRng, count: usize, extent: ScalarF) -> Vec<Sphere> {
+    (0..count)
+        .map(|_| Sphere {
+            center: Point3D::new(
+                rng.gen_range(-extent..extent),
+                rng.gen_range(-extent..extent),
+                rng.gen_range(-extent..extent),
+            ),
+            radius: rng.gen_range(0.05..1.0),
+        })
+        .collect()
+}
+
+// rays from anywhere in the cube of half size `extent` in random directions
+pub fn random_rays(rng: &mut StdRng, count: usize, extent: ScalarF) -> Vec<Ray> {
+    (0..count)
+        .map(|_| Ray {
+            origin: Point3D::new(
+                rng.gen_range(-extent..extent),
+                rng.gen_range(-extent..extent),
+                rng.gen_range(-extent..extent),
+            ),
+            direction: Vec3D::new(
+                rng.gen_range(-1.0..1.0),
+                rng.gen_range(-1.0..1.0),
+                rng.gen_range(-1.0..1.0),
+            )
+            .normalize(),
+        })
+        .collect()
+}
@@ -0,0 +1,795 @@
+use super::super::common::HitRecord;
+use super::super::math::{Aabb, Point3D, Ray, ScalarF};
+use serde::Deserialize;
+
+const MAX_PRIMITIVES_IN_LEAF: usize = 4;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum BvhNode {
+    Interior {
+        bounds: Aabb,
+        left: usize,
+        right: usize,
+    },
+    Leaf {
+        bounds: Aabb,
+        start: usize,
+        count: usize,
+    },
+}
+
+impl BvhNode {
+    pub fn bounds(&self) -> &Aabb {
+        match self {
+            BvhNode::Interior { bounds, .. } => bounds,
+            BvhNode::Leaf { bounds, .. } => bounds,
+        }
+    }
+}
+
+// binary bounding volume hierarchy over primitives given by their bounding
+// boxes, leaves refer to ranges of `primitives` which holds the indices of
+// the primitives in the order of the tree
+#[derive(Debug)]
+pub struct Bvh {
+    pub nodes: Vec<BvhNode>,
+    pub primitives: Vec<usize>,
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+pub enum SplitMethod {
+    Midpoint,
+    #[default]
+    Sah,
+    SahSweep, // exact sah over every primitive boundary rather than bins
+}
+
+// relative costs of visiting a node and intersecting a primitive, used by
+// the surface area heuristic
+#[derive(Debug, Clone, Copy, Deserialize)]
+pub struct BvhConfig {
+    #[serde(default = "default_cost_traverse")]
+    pub cost_traverse: f64,
+    #[serde(default = "default_cost_intersect")]
+    pub cost_intersect: f64,
+    #[serde(default)]
+    pub split_method: SplitMethod,
+    // nodes with more primitives build their two subtrees in parallel
+    #[serde(default = "default_parallel_threshold")]
+    pub parallel_threshold: usize,
+}
+
+fn default_cost_traverse() -> f64 {
+    0.125
+}
+
+fn default_cost_intersect() -> f64 {
+    1.0
+}
+
+fn default_parallel_threshold() -> usize {
+    1024
+}
+
+impl Default for BvhConfig {
+    fn default() -> Self {
+        Self {
+            cost_traverse: default_cost_traverse(),
+            cost_intersect: default_cost_intersect(),
+            split_method: SplitMethod::Sah,
+            parallel_threshold: default_parallel_threshold(),
+        }
+    }
+}
+
+const N_BINS: usize = 12;
+
+#[derive(Clone, Copy)]
+struct Bin {
+    bounds: Aabb,
+    count: usize,
+}
+
+// builds the tree top down, centroids are computed once up front and
+// `scratch_buffer` holds the suffix bounds of the sah sweep so that it is
+// allocated only once per builder. large subtrees are built by builders of
+// their own on the halves of `primitives` and spliced back in, so the nodes
+// come out in the same order as in a serial build
+struct BvhBuilder<'a> {
+    bounds: &'a [Aabb],
+    centroids: &'a [Point3D],
+    config: &'a BvhConfig,
+    scratch_buffer: Vec<Aabb>,
+    nodes: Vec<BvhNode>,
+    primitives: &'a mut [usize],
+}
+
+impl<'a> BvhBuilder<'a> {
+    fn new(
+        bounds: &'a [Aabb],
+        centroids: &'a [Point3D],
+        config: &'a BvhConfig,
+        primitives: &'a mut [usize],
+    ) -> Self {
+        Self {
+            bounds,
+            centroids,
+            config,
+            scratch_buffer: Vec::with_capacity(primitives.len()),
+            nodes: Vec::new(),
+            primitives,
+        }
+    }
+
+    // builds the subtree over all of `primitives` and returns its nodes
+    // with indices relative to the subtree
+    fn build_subtree(mut self) -> Vec<BvhNode> {
+        self.build(0, self.primitives.len());
+        self.nodes
+    }
+
+    // appends nodes built by another builder on primitives from `start`
+    fn append_subtree(&mut self, nodes: Vec<BvhNode>, start: usize) -> usize {
+        let offset = self.nodes.len();
+        self.nodes.extend(nodes.into_iter().map(|node| match node {
+            BvhNode::Interior {
+                bounds,
+                left,
+                right,
+            } => BvhNode::Interior {
+                bounds,
+                left: left + offset,
+                right: right + offset,
+            },
+            BvhNode::Leaf {
+                bounds,
+                start: leaf_start,
+                count,
+            } => BvhNode::Leaf {
+                bounds,
+                start: leaf_start + start,
+                count,
+            },
+        }));
+        offset
+    }
+
+    fn build(&mut self, start: usize, end: usize) -> usize {
+        let bounds = self.bounds;
+        let node_bounds = self.primitives[start..end]
+            .iter()
+            .fold(Aabb::empty(), |aabb, &i| aabb.union(&bounds[i]));
+        let count = end - start;
+
+        let centroid_bounds = self.primitives[start..end]
+            .iter()
+            .fold(Aabb::empty(), |aabb, &i| {
+                let c = self.centroids[i];
+                aabb.union(&Aabb::new(c, c))
+            });
+        let extent = centroid_bounds.max - centroid_bounds.min;
+        let axis = if extent.x >= extent.y && extent.x >= extent.z {
+            0
+        } else if extent.y >= extent.z {
+            1
+        } else {
+            2
+        };
+
+        let mid = if count <= MAX_PRIMITIVES_IN_LEAF || extent[axis] <= 0.0 {
+            None
+        } else {
+            match self.config.split_method {
+                SplitMethod::Midpoint => {
+                    Some(self.split_midpoint(&centroid_bounds, axis, start, end))
+                }
+                SplitMethod::Sah => self.split_sah(&node_bounds, &centroid_bounds, start, end),
+                SplitMethod::SahSweep => {
+                    self.split_sah_sweep(&node_bounds, &centroid_bounds, start, end)
+                }
+            }
+        };
+        let mid = match mid {
+            Some(mid) => mid,
+            None => {
+                self.nodes.push(BvhNode::Leaf {
+                    bounds: node_bounds,
+                    start,
+                    count,
+                });
+                return self.nodes.len() - 1;
+            }
+        };
+
+        let index = self.nodes.len();
+        self.nodes.push(BvhNode::Leaf {
+            bounds: node_bounds,
+            start,
+            count,
+        });
+        let (left, right) = if count > self.config.parallel_threshold {
+            let (bounds, centroids, config) = (self.bounds, self.centroids, self.config);
+            let (left_primitives, right_primitives) =
+                self.primitives[start..end].split_at_mut(mid - start);
+            let (left_nodes, right_nodes) = rayon::join(
+                || BvhBuilder::new(bounds, centroids, config, left_primitives).build_subtree(),
+                || BvhBuilder::new(bounds, centroids, config, right_primitives).build_subtree(),
+            );
+            (
+                self.append_subtree(left_nodes, start),
+                self.append_subtree(right_nodes, mid),
+            )
+        } else {
+            let left = self.build(start, mid);
+            (left, self.build(mid, end))
+        };
+        self.nodes[index] = BvhNode::Interior {
+            bounds: node_bounds,
+            left,
+            right,
+        };
+        index
+    }
+
+    // splits at the midpoint of the centroid bounds, returns the partition point
+    fn split_midpoint(
+        &mut self,
+        centroid_bounds: &Aabb,
+        axis: usize,
+        start: usize,
+        end: usize,
+    ) -> usize {
+        let mid_point = (centroid_bounds.min[axis] + centroid_bounds.max[axis]) * 0.5;
+        let mut mid = start;
+        for i in start..end {
+            if self.centroids[self.primitives[i]][axis] < mid_point {
+                self.primitives.swap(i, mid);
+                mid += 1;
+            }
+        }
+        if mid == start || mid == end {
+            // all centroids on one side, fall back to equal counts
+            self.sort_by_centroid(axis, start, end);
+            mid = start + (end - start) / 2;
+        }
+        mid
+    }
+
+    // bins the centroids along each axis and picks the plane with the
+    // lowest surface area heuristic cost, returns None when keeping the
+    // primitives in a leaf is cheaper
+    fn split_sah(
+        &mut self,
+        node_bounds: &Aabb,
+        centroid_bounds: &Aabb,
+        start: usize,
+        end: usize,
+    ) -> Option<usize> {
+        let (bounds, centroids, config) = (self.bounds, self.centroids, self.config);
+        let count = end - start;
+        let parent_area = node_bounds.surface_area();
+        let bin_index = |i: usize, axis: usize| {
+            let offset = (centroids[i][axis] - centroid_bounds.min[axis])
+                / (centroid_bounds.max[axis] - centroid_bounds.min[axis]);
+            ((offset * N_BINS as ScalarF) as usize).min(N_BINS - 1)
+        };
+
+        // (cost, axis, last bin on the left)
+        let mut best: Option<(ScalarF, usize, usize)> = None;
+        for axis in 0..3 {
+            if centroid_bounds.max[axis] <= centroid_bounds.min[axis] {
+                continue;
+            }
+
+            let mut bins = [Bin {
+                bounds: Aabb::empty(),
+                count: 0,
+            }; N_BINS];
+            for &i in &self.primitives[start..end] {
+                let bin = &mut bins[bin_index(i, axis)];
+                bin.bounds = bin.bounds.union(&bounds[i]);
+                bin.count += 1;
+            }
+
+            // sweep from the right to get the right hand side of every plane
+            let mut right_area = [0.0; N_BINS - 1];
+            let mut right_count = [0; N_BINS - 1];
+            let mut right_bounds = Aabb::empty();
+            let mut right_total = 0;
+            for split in (0..N_BINS - 1).rev() {
+                right_bounds = right_bounds.union(&bins[split + 1].bounds);
+                right_total += bins[split + 1].count;
+                right_area[split] = right_bounds.surface_area();
+                right_count[split] = right_total;
+            }
+
+            let mut left_bounds = Aabb::empty();
+            let mut left_total = 0;
+            for split in 0..N_BINS - 1 {
+                left_bounds = left_bounds.union(&bins[split].bounds);
+                left_total += bins[split].count;
+                if left_total == 0 || right_count[split] == 0 {
+                    continue;
+                }
+                let cost = config.cost_traverse as ScalarF
+                    + (left_total as ScalarF * left_bounds.surface_area()
+                        + right_count[split] as ScalarF * right_area[split])
+                        / parent_area
+                        * config.cost_intersect as ScalarF;
+                if best.is_none_or(|(best_cost, _, _)| cost < best_cost) {
+                    best = Some((cost, axis, split));
+                }
+            }
+        }
+
+        let (cost, axis, split) = best?;
+        if cost >= count as ScalarF * config.cost_intersect as ScalarF {
+            return None;
+        }
+
+        let mut mid = start;
+        for i in start..end {
+            if bin_index(self.primitives[i], axis) <= split {
+                self.primitives.swap(i, mid);
+                mid += 1;
+            }
+        }
+        Some(mid)
+    }
+
+    fn sort_by_centroid(&mut self, axis: usize, start: usize, end: usize) {
+        let centroids = self.centroids;
+        self.primitives[start..end]
+            .sort_by(|&a, &b| centroids[a][axis].total_cmp(&centroids[b][axis]));
+    }
+
+    // sorts the primitives along each axis and sweeps over them, the left
+    // bounds grow with the sweep and the right ones come from the suffix
+    // bounds in the scratch buffer, so every axis costs one sort and two
+    // linear passes. returns None when a leaf is cheaper
+    fn split_sah_sweep(
+        &mut self,
+        node_bounds: &Aabb,
+        centroid_bounds: &Aabb,
+        start: usize,
+        end: usize,
+    ) -> Option<usize> {
+        let count = end - start;
+        let parent_area = node_bounds.surface_area();
+
+        // (cost, axis, primitives on the left)
+        let mut best: Option<(ScalarF, usize, usize)> = None;
+        for axis in 0..3 {
+            if centroid_bounds.max[axis] <= centroid_bounds.min[axis] {
+                continue;
+            }
+            self.sort_by_centroid(axis, start, end);
+
+            self.scratch_buffer.clear();
+            self.scratch_buffer.resize(count, Aabb::empty());
+            let mut right_bounds = Aabb::empty();
+            for i in (0..count).rev() {
+                right_bounds = right_bounds.union(&self.bounds[self.primitives[start + i]]);
+                self.scratch_buffer[i] = right_bounds;
+            }
+
+            let mut left_bounds = Aabb::empty();
+            for left_count in 1..count {
+                left_bounds =
+                    left_bounds.union(&self.bounds[self.primitives[start + left_count - 1]]);
+                let right_count = count - left_count;
+                let cost = self.config.cost_traverse as ScalarF
+                    + (left_count as ScalarF * left_bounds.surface_area()
+                        + right_count as ScalarF * self.scratch_buffer[left_count].surface_area())
+                        / parent_area
+                        * self.config.cost_intersect as ScalarF;
+                if best.is_none_or(|(best_cost, _, _)| cost < best_cost) {
+                    best = Some((cost, axis, left_count));
+                }
+            }
+        }
+
+        let (cost, axis, left_count) = best?;
+        if cost >= count as ScalarF * self.config.cost_intersect as ScalarF {
+            return None;
+        }
+        self.sort_by_centroid(axis, start, end);
+        Some(start + left_count)
+    }
+}
+
+impl Bvh {
+    pub fn new(bounds: &[Aabb], config: &BvhConfig) -> Self {
+        let centroids: Vec<Point3D> = bounds.iter().map(|aabb| aabb.centroid()).collect();
+        let mut primitives: Vec<usize> = (0..bounds.len()).collect();
+        let nodes = if bounds.is_empty() {
+            Vec::new()
+        } else {
+            BvhBuilder::new(bounds, &centroids, config, &mut primitives).build_subtree()
+        };
+        Bvh { nodes, primitives }
+    }
+
+    pub fn root(&self) -> Option<usize> {
+        if self.nodes.is_empty() {
+            None
+        } else {
+            Some(0)
+        }
+    }
+
+    // the scene traverses the qbvh, this is kept as the reference traversal
+    // `intersect_primitive` is called with a primitive index and the current
+    // parametric range, and returns the primitive's hit record if any
+    #[allow(dead_code)]
+    pub fn intersect<'a, F>(
+        &self,
+        ray: &Ray,
+        t_min: ScalarF,
+        t_max: ScalarF,
+        mut intersect_primitive: F,
+    ) -> Option<HitRecord<'a>>
+    where
+        F: FnMut(usize, ScalarF, ScalarF) -> Option<HitRecord<'a>>,
+    {
+        let mut hit_record: Option<HitRecord> = None;
+        let mut closest_so_far = t_max;
+
+        let mut stack: Vec<usize> = self.root().into_iter().collect();
+        while let Some(index) = stack.pop() {
+            let node = &self.nodes[index];
+            #[cfg(feature = "simd")]
+            let hit = node.bounds().hit_simd(ray, t_min, closest_so_far);
+            #[cfg(not(feature = "simd"))]
+            let hit = node.bounds().hit(ray, t_min, closest_so_far);
+            if !hit {
+                continue;
+            }
+            match node {
+                BvhNode::Interior { left, right, .. } => {
+                    stack.push(*right);
+                    stack.push(*left);
+                }
+                BvhNode::Leaf { start, count, .. } => {
+                    for &primitive in &self.primitives[*start..*start + *count] {
+                        if let Some(hit) = intersect_primitive(primitive, t_min, closest_so_far) {
+                            closest_so_far = hit.t;
+                            hit_record = Some(hit);
+                        }
+                    }
+                }
+            }
+        }
+
+        hit_record
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::accel::bench::{best_of, report};
+    use crate::math::Vec3D;
+    use cgmath::{Array, EuclideanSpace, InnerSpace};
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    // nodes whose boxes the ray passes through, without closest hit culling
+    fn count_visits(bvh: &Bvh, ray: &Ray) -> usize {
+        let mut visits = 0;
+        let mut stack: Vec<usize> = bvh.root().into_iter().collect();
+        while let Some(index) = stack.pop() {
+            visits += 1;
+            if let BvhNode::Interior { left, right, .. } = &bvh.nodes[index] {
+                for &child in &[*left, *right] {
+                    if bvh.nodes[child].bounds().hit(ray, 0.0, ScalarF::MAX) {
+                        stack.push(child);
+                    }
+                }
+            }
+        }
+        visits
+    }
+
+    // bounds of a node and its children, built by evaluating the sah of
+    // every split from scratch
+    struct NaiveNode {
+        bounds: Aabb,
+        children: Option<Box<(NaiveNode, NaiveNode)>>,
+    }
+
+    fn build_naive(bounds: &[Aabb], primitives: Vec<usize>, config: &BvhConfig) -> NaiveNode {
+        let union = |items: &[usize]| {
+            items
+                .iter()
+                .fold(Aabb::empty(), |aabb, &i| aabb.union(&bounds[i]))
+        };
+        let node_bounds = union(&primitives);
+        let centroid_bounds = primitives.iter().fold(Aabb::empty(), |aabb, &i| {
+            let c = bounds[i].centroid();
+            aabb.union(&Aabb::new(c, c))
+        });
+        let extent = centroid_bounds.max - centroid_bounds.min;
+        let leaf = NaiveNode {
+            bounds: node_bounds,
+            children: None,
+        };
+        if primitives.len() <= MAX_PRIMITIVES_IN_LEAF || extent.x.max(extent.y).max(extent.z) <= 0.0
+        {
+            return leaf;
+        }
+
+        let sorted = |axis: usize| {
+            let mut sorted = primitives.clone();
+            sorted.sort_by(|&a, &b| {
+                bounds[a].centroid()[axis].total_cmp(&bounds[b].centroid()[axis])
+            });
+            sorted
+        };
+        let mut best: Option<(ScalarF, usize, usize)> = None;
+        for axis in (0..3).filter(|&axis| extent[axis] > 0.0) {
+            let sorted = sorted(axis);
+            for left_count in 1..sorted.len() {
+                let (left, right) = sorted.split_at(left_count);
+                let cost = config.cost_traverse as ScalarF
+                    + (left.len() as ScalarF * union(left).surface_area()
+                        + right.len() as ScalarF * union(right).surface_area())
+                        / node_bounds.surface_area()
+                        * config.cost_intersect as ScalarF;
+                if best.is_none_or(|(best_cost, _, _)| cost < best_cost) {
+                    best = Some((cost, axis, left_count));
+                }
+            }
+        }
+        let (cost, axis, left_count) = best.unwrap();
+        if cost >= primitives.len() as ScalarF * config.cost_intersect as ScalarF {
+            return leaf;
+        }
+        let mut left = sorted(axis);
+        let right = left.split_off(left_count);
+        NaiveNode {
+            bounds: node_bounds,
+            children: Some(Box::new((
+                build_naive(bounds, left, config),
+                build_naive(bounds, right, config),
+            ))),
+        }
+    }
+
+    #[test]
+    fn test_bvh_matches_brute_force() {
+        use crate::shapes::{Shape, Sphere};
+
+        let mut rng = StdRng::seed_from_u64(11);
+        let spheres: Vec<Sphere> = (0..2000)
+            .map(|_| Sphere {
+                center: Point3D::new(
+                    rng.gen_range(-20.0..20.0),
+                    rng.gen_range(-20.0..20.0),
+                    rng.gen_range(-20.0..20.0),
+                ),
+                radius: rng.gen_range(0.05..1.0),
+            })
+            .collect();
+        let bounds: Vec<Aabb> = spheres.iter().map(|s| s.bounding_box()).collect();
+
+        for split_method in [
+            SplitMethod::Midpoint,
+            SplitMethod::Sah,
+            SplitMethod::SahSweep,
+        ] {
+            let bvh = Bvh::new(
+                &bounds,
+                &BvhConfig {
+                    split_method,
+                    ..BvhConfig::default()
+                },
+            );
+            let mut hit_count = 0;
+            for _ in 0..500 {
+                let ray = Ray {
+                    origin: Point3D::new(
+                        rng.gen_range(-25.0..25.0),
+                        rng.gen_range(-25.0..25.0),
+                        rng.gen_range(-25.0..25.0),
+                    ),
+                    direction: Vec3D::new(
+                        rng.gen_range(-1.0..1.0),
+                        rng.gen_range(-1.0..1.0),
+                        rng.gen_range(-1.0..1.0),
+                    )
+                    .normalize(),
+                };
+                let expected = spheres
+                    .iter()
+                    .filter_map(|sphere| sphere.intersect(&ray, 0.001, ScalarF::MAX))
+                    .map(|hit| hit.t)
+                    .min_by(ScalarF::total_cmp);
+                let actual = bvh
+                    .intersect(&ray, 0.001, ScalarF::MAX, |i, t_min, t_max| {
+                        spheres[i].intersect(&ray, t_min, t_max)
+                    })
+                    .map(|hit| hit.t);
+                assert_eq!(expected, actual);
+                hit_count += expected.is_some() as usize;
+            }
+            assert!(hit_count > 0);
+        }
+    }
+
+    #[test]
+    fn test_sah_sweep_matches_naive() {
+        let mut rng = StdRng::seed_from_u64(3);
+        let bounds: Vec<Aabb> = (0..500)
+            .map(|_| {
+                let center = Point3D::new(
+                    rng.gen_range(-10.0..10.0),
+                    rng.gen_range(-10.0..10.0),
+                    rng.gen_range(-10.0..10.0),
+                );
+                let radius = Vec3D::from_value(rng.gen_range(0.05..1.0));
+                Aabb::new(center - radius, center + radius)
+            })
+            .collect();
+        let config = BvhConfig {
+            split_method: SplitMethod::SahSweep,
+            ..BvhConfig::default()
+        };
+        let bvh = Bvh::new(&bounds, &config);
+        let naive = build_naive(&bounds, (0..bounds.len()).collect(), &config);
+
+        // compare both trees level by level
+        let mut level = vec![(0, &naive)];
+        let mut depth = 0;
+        while !level.is_empty() {
+            let mut next = Vec::new();
+            for (index, naive_node) in level {
+                assert_eq!(
+                    bvh.nodes[index].bounds(),
+                    &naive_node.bounds,
+                    "depth {}",
+                    depth
+                );
+                match (&bvh.nodes[index], &naive_node.children) {
+                    (BvhNode::Interior { left, right, .. }, Some(children)) => {
+                        next.push((*left, &children.0));
+                        next.push((*right, &children.1));
+                    }
+                    (BvhNode::Leaf { .. }, None) => {}
+                    _ => panic!("different node kinds at depth {}", depth),
+                }
+            }
+            level = next;
+            depth += 1;
+        }
+        assert!(depth > 5);
+    }
+
+    #[test]
+    fn test_sah_visits_fewer_nodes() {
+        // 67k long thin triangles (think grass blades) whose boxes overlap a
+        // lot, the midpoint split handles them poorly
+        let mut rng = StdRng::seed_from_u64(7);
+        let bounds: Vec<Aabb> = (0..67_000)
+            .map(|_| {
+                let v0 = Point3D::new(
+                    rng.gen_range(-10.0..10.0),
+                    rng.gen_range(-10.0..10.0),
+                    rng.gen_range(-10.0..10.0),
+                );
+                let v1 = v0 + Vec3D::new(rng.gen_range(-5.0..5.0), 0.0, 0.0);
+                let v2 = v0 + Vec3D::new(0.0, 0.05, 0.0);
+                Aabb::from_points(&[v0, v1, v2])
+            })
+            .collect();
+
+        let midpoint = Bvh::new(
+            &bounds,
+            &BvhConfig {
+                split_method: SplitMethod::Midpoint,
+                ..BvhConfig::default()
+            },
+        );
+        let sah = Bvh::new(&bounds, &BvhConfig::default());
+        assert_eq!(sah.nodes[0].bounds(), midpoint.nodes[0].bounds());
+
+        let mut midpoint_visits = 0;
+        let mut sah_visits = 0;
+        for _ in 0..1000 {
+            let origin = Point3D::origin()
+                + Vec3D::new(
+                    rng.gen_range(-1.0..1.0),
+                    rng.gen_range(-1.0..1.0),
+                    rng.gen_range(-1.0..1.0),
+                )
+                .normalize()
+                    * 40.0;
+            let target = Point3D::new(
+                rng.gen_range(-5.0..5.0),
+                rng.gen_range(-5.0..5.0),
+                rng.gen_range(-5.0..5.0),
+            );
+            let ray = Ray {
+                origin,
+                direction: (target - origin).normalize(),
+            };
+            midpoint_visits += count_visits(&midpoint, &ray);
+            sah_visits += count_visits(&sah, &ray);
+        }
+
+        assert!(
+            (sah_visits as ScalarF) < 0.8 * midpoint_visits as ScalarF,
+            "sah: {}, midpoint: {}",
+            sah_visits,
+            midpoint_visits
+        );
+    }
+
+    // boxes of small triangles scattered through a cube
+    fn triangle_bounds(rng: &mut StdRng, count: usize) -> Vec<Aabb> {
+        (0..count)
+            .map(|_| {
+                let v0 = Point3D::new(
+                    rng.gen_range(-10.0..10.0),
+                    rng.gen_range(-10.0..10.0),
+                    rng.gen_range(-10.0..10.0),
+                );
+                let mut vertex = || {
+                    v0 + Vec3D::new(
+                        rng.gen_range(-0.2..0.2),
+                        rng.gen_range(-0.2..0.2),
+                        rng.gen_range(-0.2..0.2),
+                    )
+                };
+                let (v1, v2) = (vertex(), vertex());
+                Aabb::from_points(&[v0, v1, v2])
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_parallel_build_matches_serial() {
+        let mut rng = StdRng::seed_from_u64(5);
+        let bounds = triangle_bounds(&mut rng, 100_000);
+
+        for split_method in [SplitMethod::Midpoint, SplitMethod::Sah] {
+            let config = |parallel_threshold| BvhConfig {
+                split_method,
+                parallel_threshold,
+                ..BvhConfig::default()
+            };
+            let serial = Bvh::new(&bounds, &config(usize::MAX));
+            let parallel = Bvh::new(&bounds, &config(1024));
+            assert!(serial.nodes.len() > 1000);
+            assert_eq!(serial.nodes, parallel.nodes);
+            assert_eq!(serial.primitives, parallel.primitives);
+        }
+    }
+
+    #[test]
+    #[ignore] // a benchmark, see accel/bench.rs
+    fn bench_parallel_build() {
+        let bounds = triangle_bounds(&mut StdRng::seed_from_u64(5), 1_000_000);
+        let config = |parallel_threshold| BvhConfig {
+            parallel_threshold,
+            ..BvhConfig::default()
+        };
+        let serial = best_of(3, || Bvh::new(&bounds, &config(usize::MAX)));
+        let parallel = best_of(3, || Bvh::new(&bounds, &config(1024)));
+        report(
+            &format!("bvh build on {} threads", rayon::current_num_threads()),
+            ("serial", serial),
+            ("parallel", parallel),
+        );
+    }
+
+    #[test]
+    fn test_bvh_config_defaults() {
+        // tables written before the costs were tunable still parse
+        let config: BvhConfig = toml::from_str("parallel_threshold = 16").unwrap();
+        let default = BvhConfig::default();
+        assert_eq!(config.cost_traverse, default.cost_traverse);
+        assert_eq!(config.cost_intersect, default.cost_intersect);
+        assert_eq!(config.parallel_threshold, 16);
+    }
+}
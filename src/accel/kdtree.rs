@@ -0,0 +1,482 @@
+use super::super::common::HitRecord;
+use super::super::math::{Aabb, Ray, ScalarF};
+use serde::Deserialize;
+
+// deepest the tree gets, traversal keeps its pending nodes in a stack of
+// this size
+const MAX_DEPTH: usize = 64;
+
+#[derive(Debug, Clone, Copy)]
+enum KdNode {
+    // the child below the split follows the node, `above` is the index of
+    // the other one
+    Interior {
+        axis: usize,
+        split: ScalarF,
+        above: usize,
+    },
+    Leaf {
+        start: usize,
+        count: usize,
+    },
+}
+
+// kd-tree over primitives given by their bounding boxes, split planes are
+// placed at box boundaries by the surface area heuristic. primitives
+// straddling a plane are referenced from both sides, so leaves refer to
+// ranges of `primitives` that may repeat indices
+#[derive(Debug)]
+pub struct KdTree {
+    nodes: Vec<KdNode>,
+    primitives: Vec<usize>,
+    bounds: Aabb,
+}
+
+// costs of stepping through a node and intersecting a primitive, splits
+// that leave one side empty get their cost lowered by `empty_bonus`
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(default)]
+pub struct KdTreeConfig {
+    pub cost_traverse: f64,
+    pub cost_intersect: f64,
+    pub empty_bonus: f64,
+    pub max_primitives_in_leaf: usize,
+    pub max_depth: Option<usize>, // 8 + 1.3 log2(n) when not given
+}
+
+impl Default for KdTreeConfig {
+    fn default() -> Self {
+        Self {
+            cost_traverse: 1.0,
+            cost_intersect: 80.0,
+            empty_bonus: 0.5,
+            max_primitives_in_leaf: 1,
+            max_depth: None,
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+struct BoundEdge {
+    t: ScalarF,
+    primitive: usize,
+    starting: bool,
+}
+
+struct KdTreeBuilder<'a> {
+    bounds: &'a [Aabb],
+    config: &'a KdTreeConfig,
+    nodes: Vec<KdNode>,
+    primitives: Vec<usize>,
+}
+
+impl KdTreeBuilder<'_> {
+    fn push_leaf(&mut self, primitives: &[usize]) {
+        self.nodes.push(KdNode::Leaf {
+            start: self.primitives.len(),
+            count: primitives.len(),
+        });
+        self.primitives.extend_from_slice(primitives);
+    }
+
+    // `bad_refines` counts the splits on the way down that cost more than
+    // the leaf they replaced, a few are allowed in case the ones below pay off
+    fn build(
+        &mut self,
+        node_bounds: &Aabb,
+        primitives: &[usize],
+        depth: usize,
+        bad_refines: usize,
+    ) {
+        let count = primitives.len();
+        if count <= self.config.max_primitives_in_leaf || depth == 0 {
+            self.push_leaf(primitives);
+            return;
+        }
+
+        let extent = node_bounds.max - node_bounds.min;
+        let total_area = node_bounds.surface_area();
+        let leaf_cost = self.config.cost_intersect as ScalarF * count as ScalarF;
+        let mut axis = if extent.x >= extent.y && extent.x >= extent.z {
+            0
+        } else if extent.y >= extent.z {
+            1
+        } else {
+            2
+        };
+
+        // (cost, axis, index of the edge the plane goes through), trying the
+        // other axes when no plane falls inside the node along the longest
+        let mut best: Option<(ScalarF, usize, usize)> = None;
+        let mut edges = Vec::with_capacity(2 * count);
+        for _ in 0..3 {
+            edges.clear();
+            for &primitive in primitives {
+                let aabb = &self.bounds[primitive];
+                edges.push(BoundEdge {
+                    t: aabb.min[axis],
+                    primitive,
+                    starting: true,
+                });
+                edges.push(BoundEdge {
+                    t: aabb.max[axis],
+                    primitive,
+                    starting: false,
+                });
+            }
+            // boxes starting on a plane come before the ones ending there
+            edges.sort_by(|a, b| a.t.total_cmp(&b.t).then(b.starting.cmp(&a.starting)));
+
+            let (other0, other1) = ((axis + 1) % 3, (axis + 2) % 3);
+            let (mut below, mut above) = (0, count);
+            for (i, edge) in edges.iter().enumerate() {
+                if !edge.starting {
+                    above -= 1;
+                }
+                if edge.t > node_bounds.min[axis] && edge.t < node_bounds.max[axis] {
+                    let area = |length: ScalarF| {
+                        2.0 * (extent[other0] * extent[other1]
+                            + length * (extent[other0] + extent[other1]))
+                    };
+                    let p_below = area(edge.t - node_bounds.min[axis]) / total_area;
+                    let p_above = area(node_bounds.max[axis] - edge.t) / total_area;
+                    let bonus = if below == 0 || above == 0 {
+                        self.config.empty_bonus as ScalarF
+                    } else {
+                        0.0
+                    };
+                    let cost = self.config.cost_traverse as ScalarF
+                        + self.config.cost_intersect as ScalarF
+                            * (1.0 - bonus)
+                            * (p_below * below as ScalarF + p_above * above as ScalarF);
+                    if best.is_none_or(|(best_cost, _, _)| cost < best_cost) {
+                        best = Some((cost, axis, i));
+                    }
+                }
+                if edge.starting {
+                    below += 1;
+                }
+            }
+            if best.is_some() {
+                break;
+            }
+            axis = (axis + 1) % 3;
+        }
+
+        let (cost, axis, split_edge) = match best {
+            Some(best) => best,
+            None => {
+                self.push_leaf(primitives);
+                return;
+            }
+        };
+        let bad_refines = bad_refines + (cost > leaf_cost) as usize;
+        if (cost > 4.0 * leaf_cost && count < 16) || bad_refines == 3 {
+            self.push_leaf(primitives);
+            return;
+        }
+
+        // the search stopped at the first axis with a plane, `edges` still
+        // holds that axis
+        let split = edges[split_edge].t;
+        let below: Vec<usize> = edges[..split_edge]
+            .iter()
+            .filter(|edge| edge.starting)
+            .map(|edge| edge.primitive)
+            .collect();
+        let above: Vec<usize> = edges[split_edge + 1..]
+            .iter()
+            .filter(|edge| !edge.starting)
+            .map(|edge| edge.primitive)
+            .collect();
+
+        let index = self.nodes.len();
+        self.nodes.push(KdNode::Leaf { start: 0, count: 0 });
+        let mut below_bounds = *node_bounds;
+        below_bounds.max[axis] = split;
+        self.build(&below_bounds, &below, depth - 1, bad_refines);
+        let mut above_bounds = *node_bounds;
+        above_bounds.min[axis] = split;
+        let above_index = self.nodes.len();
+        self.build(&above_bounds, &above, depth - 1, bad_refines);
+        self.nodes[index] = KdNode::Interior {
+            axis,
+            split,
+            above: above_index,
+        };
+    }
+}
+
+impl KdTree {
+    pub fn new(bounds: &[Aabb], config: &KdTreeConfig) -> Self {
+        let max_depth = config
+            .max_depth
+            .unwrap_or_else(|| {
+                (8.0 + 1.3 * (bounds.len().max(1) as ScalarF).log2()).round() as usize
+            })
+            .min(MAX_DEPTH - 1);
+        let mut builder = KdTreeBuilder {
+            bounds,
+            config,
+            nodes: Vec::new(),
+            primitives: Vec::new(),
+        };
+        let tree_bounds = bounds
+            .iter()
+            .fold(Aabb::empty(), |tree, aabb| tree.union(aabb));
+        if !bounds.is_empty() {
+            let primitives: Vec<usize> = (0..bounds.len()).collect();
+            builder.build(&tree_bounds, &primitives, max_depth, 0);
+        }
+        KdTree {
+            nodes: builder.nodes,
+            primitives: builder.primitives,
+            bounds: tree_bounds,
+        }
+    }
+
+    // walks the nodes the ray passes front to back, stopping once the
+    // closest hit lies before the next node
+    pub fn intersect<'a, F>(
+        &self,
+        ray: &Ray,
+        t_min: ScalarF,
+        t_max: ScalarF,
+        mut intersect_primitive: F,
+    ) -> Option<HitRecord<'a>>
+    where
+        F: FnMut(usize, ScalarF, ScalarF) -> Option<HitRecord<'a>>,
+    {
+        if self.nodes.is_empty() {
+            return None;
+        }
+        let (mut node_min, mut node_max) = self.bounds.intersect(ray, t_min, t_max)?;
+        let mut hit_record: Option<HitRecord> = None;
+        let mut closest_so_far = t_max;
+
+        let mut stack = [(0, 0.0, 0.0); MAX_DEPTH];
+        let mut stack_size = 0;
+        let mut index = 0;
+        loop {
+            if closest_so_far < node_min {
+                break;
+            }
+            match self.nodes[index] {
+                KdNode::Interior { axis, split, above } => {
+                    let (origin, direction) = (ray.origin[axis], ray.direction[axis]);
+                    let below_first = origin < split || (origin == split && direction <= 0.0);
+                    let (first, second) = if below_first {
+                        (index + 1, above)
+                    } else {
+                        (above, index + 1)
+                    };
+                    let t_plane = (split - origin) / direction;
+                    if direction == 0.0 || t_plane > node_max || t_plane <= 0.0 {
+                        index = first;
+                    } else if t_plane < node_min {
+                        index = second;
+                    } else {
+                        stack[stack_size] = (second, t_plane, node_max);
+                        stack_size += 1;
+                        index = first;
+                        node_max = t_plane;
+                    }
+                }
+                KdNode::Leaf { start, count } => {
+                    for &primitive in &self.primitives[start..start + count] {
+                        if let Some(hit) = intersect_primitive(primitive, t_min, closest_so_far) {
+                            closest_so_far = hit.t;
+                            hit_record = Some(hit);
+                        }
+                    }
+                    if stack_size == 0 {
+                        break;
+                    }
+                    stack_size -= 1;
+                    (index, node_min, node_max) = stack[stack_size];
+                }
+            }
+        }
+        hit_record
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::accel::bench::{best_of, random_rays, random_spheres, report};
+    use crate::accel::{Accel, AccelStructure};
+    use crate::math::{tolerance, Point3D, Vec3D};
+    use crate::shapes::{Shape, Sphere};
+    use cgmath::InnerSpace;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    fn check_against_brute_force(spheres: &[Sphere], rng: &mut StdRng) {
+        let bounds: Vec<Aabb> = spheres.iter().map(|s| s.bounding_box()).collect();
+        let kdtree = KdTree::new(&bounds, &KdTreeConfig::default());
+        assert!(kdtree.nodes.len() > 1);
+
+        let mut hit_count = 0;
+        for i in 0..1000 {
+            let mut direction = Vec3D::new(
+                rng.gen_range(-1.0..1.0),
+                rng.gen_range(-1.0..1.0),
+                rng.gen_range(-1.0..1.0),
+            );
+            // some rays run parallel to the split planes of an axis
+            if i % 4 == 0 {
+                direction[i % 3] = 0.0;
+            }
+            let ray = Ray {
+                origin: Point3D::new(
+                    rng.gen_range(-25.0..25.0),
+                    rng.gen_range(-25.0..25.0),
+                    rng.gen_range(-25.0..25.0),
+                ),
+                direction: direction.normalize(),
+            };
+            let expected = spheres
+                .iter()
+                .filter_map(|sphere| sphere.intersect(&ray, 0.001, ScalarF::MAX))
+                .map(|hit| hit.t)
+                .min_by(ScalarF::total_cmp);
+            let actual = kdtree
+                .intersect(&ray, 0.001, ScalarF::MAX, |i, t_min, t_max| {
+                    spheres[i].intersect(&ray, t_min, t_max)
+                })
+                .map(|hit| hit.t);
+            assert_eq!(expected, actual);
+            hit_count += expected.is_some() as usize;
+        }
+        assert!(hit_count > 0);
+    }
+
+    #[test]
+    fn test_kdtree_matches_brute_force() {
+        let mut rng = StdRng::seed_from_u64(13);
+        let scattered: Vec<Sphere> = (0..2000)
+            .map(|_| Sphere {
+                center: Point3D::new(
+                    rng.gen_range(-20.0..20.0),
+                    rng.gen_range(-20.0..20.0),
+                    rng.gen_range(-20.0..20.0),
+                ),
+                radius: rng.gen_range(0.05..1.0),
+            })
+            .collect();
+        check_against_brute_force(&scattered, &mut rng);
+
+        // a uniform grid whose boxes touch, split planes land on shared faces
+        let grid: Vec<Sphere> = (0..1000)
+            .map(|i| Sphere {
+                center: Point3D::new(
+                    (i % 10) as ScalarF * 2.0 - 9.0,
+                    (i / 10 % 10) as ScalarF * 2.0 - 9.0,
+                    (i / 100) as ScalarF * 2.0 - 9.0,
+                ),
+                radius: 1.0,
+            })
+            .collect();
+        check_against_brute_force(&grid, &mut rng);
+    }
+
+    #[test]
+    fn test_accel_structure_config() {
+        let structure: AccelStructure = toml::from_str(
+            r#"
+            type = "KdTree"
+            cost_intersect = 20.0
+            "#,
+        )
+        .unwrap();
+        let sphere = Sphere {
+            center: Point3D::new(0.0, 0.0, -5.0),
+            radius: 1.0,
+        };
+        let accel = Accel::new(&[sphere.bounding_box()], &structure);
+        assert!(matches!(accel, Accel::KdTree(_)));
+        let ray = Ray {
+            origin: Point3D::new(0.0, 0.0, 0.0),
+            direction: Vec3D::new(0.0, 0.0, -1.0),
+        };
+        let hit = accel.intersect(&ray, 0.001, ScalarF::MAX, |_, t_min, t_max| {
+            sphere.intersect(&ray, t_min, t_max)
+        });
+        assert!((hit.unwrap().t - 4.0).abs() < tolerance(1e-9));
+
+        let empty = KdTree::new(&[], &KdTreeConfig::default());
+        assert!(empty
+            .intersect(&ray, 0.001, ScalarF::MAX, |_, t_min, t_max| {
+                sphere.intersect(&ray, t_min, t_max)
+            })
+            .is_none());
+    }
+
+    #[test]
+    #[ignore] // a benchmark, see accel/bench.rs
+    fn bench_kdtree_traversal() {
+        use crate::accel::{BvhConfig, Qbvh};
+        use crate::shapes::Triangle;
+        use std::sync::Arc;
+
+        let mut rng = StdRng::seed_from_u64(13);
+        // a wavy grid of 183 x 183 quads, 67k triangles of the same size
+        let n = 183;
+        let vertex = |i: usize, j: usize| {
+            let (x, y) = (i as ScalarF * 0.1 - 9.15, j as ScalarF * 0.1 - 9.15);
+            Point3D::new(x, y, (x * 0.7).sin() * (y * 0.5).cos())
+        };
+        let mut triangles = Vec::new();
+        for i in 0..n {
+            for j in 0..n {
+                let (a, b, c, d) = (
+                    vertex(i, j),
+                    vertex(i + 1, j),
+                    vertex(i + 1, j + 1),
+                    vertex(i, j + 1),
+                );
+                triangles.push(Triangle {
+                    vertices: [a, b, c],
+                    uvs: None,
+                });
+                triangles.push(Triangle {
+                    vertices: [a, c, d],
+                    uvs: None,
+                });
+            }
+        }
+        let grid: Vec<Arc<dyn Shape>> = triangles
+            .into_iter()
+            .map(|t| Arc::new(t) as Arc<dyn Shape>)
+            .collect();
+        let scattered: Vec<Arc<dyn Shape>> = random_spheres(&mut rng, 67_000, 20.0)
+            .into_iter()
+            .map(|s| Arc::new(s) as Arc<dyn Shape>)
+            .collect();
+        let rays = random_rays(&mut rng, 100_000, 25.0);
+
+        for (name, shapes) in [("triangle grid", &grid), ("scattered spheres", &scattered)] {
+            let bounds: Vec<Aabb> = shapes.iter().map(|s| s.bounding_box()).collect();
+            let qbvh = Qbvh::new(&bounds, &BvhConfig::default());
+            let kdtree = KdTree::new(&bounds, &KdTreeConfig::default());
+            let traverse = |intersect: &dyn Fn(&Ray) -> Option<ScalarF>| {
+                best_of(3, || rays.iter().filter_map(intersect).count())
+            };
+            let bvh_time = traverse(&|ray| {
+                qbvh.intersect(ray, 0.001, ScalarF::MAX, |i, t_min, t_max| {
+                    shapes[i].intersect(ray, t_min, t_max)
+                })
+                .map(|hit| hit.t)
+            });
+            let kdtree_time = traverse(&|ray| {
+                kdtree
+                    .intersect(ray, 0.001, ScalarF::MAX, |i, t_min, t_max| {
+                        shapes[i].intersect(ray, t_min, t_max)
+                    })
+                    .map(|hit| hit.t)
+            });
+            report(name, ("bvh", bvh_time), ("kd-tree", kdtree_time));
+        }
+    }
+}
@@ -0,0 +1,71 @@
+#[cfg(test)]
+mod bench;
+mod bvh;
+mod kdtree;
+mod qbvh;
+#[cfg(feature = "simd")]
+mod simd;
+mod spatial_hash;
+
+use super::common::HitRecord;
+use super::math::{Aabb, Ray, ScalarF};
+pub use bvh::{Bvh, BvhConfig};
+pub use kdtree::{KdTree, KdTreeConfig};
+pub use qbvh::Qbvh;
+use serde::Deserialize;
+pub use spatial_hash::{SpatialHashGrid, SpatialHashGridConfig};
+
+// which structure the scene looks its bounded objects up in
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(tag = "type")]
+pub enum AccelStructure {
+    Bvh(BvhConfig),
+    // the same tree collapsed to four children per node
+    Qbvh(BvhConfig),
+    KdTree(KdTreeConfig),
+    SpatialHashGrid(SpatialHashGridConfig),
+}
+
+impl Default for AccelStructure {
+    fn default() -> Self {
+        AccelStructure::Bvh(BvhConfig::default())
+    }
+}
+
+pub enum Accel {
+    Bvh(Bvh),
+    Qbvh(Qbvh),
+    KdTree(KdTree),
+    SpatialHashGrid(SpatialHashGrid),
+}
+
+impl Accel {
+    pub fn new(bounds: &[Aabb], structure: &AccelStructure) -> Self {
+        match structure {
+            AccelStructure::Bvh(config) => Accel::Bvh(Bvh::new(bounds, config)),
+            AccelStructure::Qbvh(config) => Accel::Qbvh(Qbvh::new(bounds, config)),
+            AccelStructure::KdTree(config) => Accel::KdTree(KdTree::new(bounds, config)),
+            AccelStructure::SpatialHashGrid(config) => {
+                Accel::SpatialHashGrid(SpatialHashGrid::new(bounds, config))
+            }
+        }
+    }
+
+    pub fn intersect<'a, F>(
+        &self,
+        ray: &Ray,
+        t_min: ScalarF,
+        t_max: ScalarF,
+        intersect_primitive: F,
+    ) -> Option<HitRecord<'a>>
+    where
+        F: FnMut(usize, ScalarF, ScalarF) -> Option<HitRecord<'a>>,
+    {
+        match self {
+            Accel::Bvh(bvh) => bvh.intersect(ray, t_min, t_max, intersect_primitive),
+            Accel::Qbvh(qbvh) => qbvh.intersect(ray, t_min, t_max, intersect_primitive),
+            Accel::KdTree(kdtree) => kdtree.intersect(ray, t_min, t_max, intersect_primitive),
+            Accel::SpatialHashGrid(grid) => grid.intersect(ray, t_min, t_max, intersect_primitive),
+        }
+    }
+}
@@ -0,0 +1,370 @@
+use super::super::common::HitRecord;
+use super::super::math::{Aabb, Ray, ScalarF, Vec3D};
+use super::bvh::{Bvh, BvhConfig, BvhNode};
+
+const EMPTY_CHILD: i32 = i32::MIN;
+
+// children are encoded as: >= 0 an interior node index, -(i + 1) the i-th
+// leaf, EMPTY_CHILD an unused slot whose bounds are empty. the child boxes
+// are stored per axis so all four slabs of an axis load together
+#[derive(Debug, Clone)]
+pub struct QbvhNode {
+    pub min: [[ScalarF; 4]; 3],
+    pub max: [[ScalarF; 4]; 3],
+    pub children: [i32; 4],
+}
+
+impl QbvhNode {
+    pub fn new() -> Self {
+        let empty = Aabb::empty();
+        QbvhNode {
+            min: [[empty.min.x; 4]; 3],
+            max: [[empty.max.x; 4]; 3],
+            children: [EMPTY_CHILD; 4],
+        }
+    }
+
+    pub fn set_bounds(&mut self, slot: usize, bounds: &Aabb) {
+        for axis in 0..3 {
+            self.min[axis][slot] = bounds.min[axis];
+            self.max[axis][slot] = bounds.max[axis];
+        }
+    }
+
+    // entry distances of the four child boxes and which of them the ray
+    // hits, the slab test of `Aabb::intersect` run on all four at once
+    pub fn intersect(
+        &self,
+        ray: &Ray,
+        inv_d: &Vec3D,
+        t_min: ScalarF,
+        t_max: ScalarF,
+    ) -> ([ScalarF; 4], [bool; 4]) {
+        let mut t_enter = [t_min; 4];
+        let mut t_exit = [t_max; 4];
+        for axis in 0..3 {
+            let (near, far) = if inv_d[axis] >= 0.0 {
+                (&self.min[axis], &self.max[axis])
+            } else {
+                (&self.max[axis], &self.min[axis])
+            };
+            for lane in 0..4 {
+                // max and min skip NaN, so degenerate slabs leave the range
+                // untouched
+                let t_near = (near[lane] - ray.origin[axis]) * inv_d[axis];
+                let t_far = (far[lane] - ray.origin[axis]) * inv_d[axis];
+                t_enter[lane] = t_enter[lane].max(t_near);
+                t_exit[lane] = t_exit[lane].min(t_far);
+            }
+        }
+        let mut hits = [false; 4];
+        for lane in 0..4 {
+            hits[lane] = t_enter[lane] <= t_exit[lane];
+        }
+        (t_enter, hits)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct QbvhLeaf {
+    start: usize,
+    count: usize,
+}
+
+// four-wide bounding volume hierarchy, built by collapsing a binary BVH so
+// each node tests up to four child boxes at once
+#[derive(Debug)]
+pub struct Qbvh {
+    nodes: Vec<QbvhNode>,
+    leaves: Vec<QbvhLeaf>,
+    primitives: Vec<usize>,
+}
+
+impl Qbvh {
+    pub fn new(bounds: &[Aabb], config: &BvhConfig) -> Self {
+        Self::from_bvh(&Bvh::new(bounds, config))
+    }
+
+    pub fn from_bvh(bvh: &Bvh) -> Self {
+        let mut qbvh = Qbvh {
+            nodes: Vec::new(),
+            leaves: Vec::new(),
+            primitives: bvh.primitives.clone(),
+        };
+        if let Some(root) = bvh.root() {
+            qbvh.collapse(bvh, root);
+        }
+        qbvh
+    }
+
+    fn push_leaf(&mut self, start: usize, count: usize) -> i32 {
+        self.leaves.push(QbvhLeaf { start, count });
+        -(self.leaves.len() as i32)
+    }
+
+    // pulls the grandchildren of a binary node up into one 4-child node
+    fn collapse(&mut self, bvh: &Bvh, index: usize) -> usize {
+        let mut grandchildren: Vec<usize> = Vec::with_capacity(4);
+        match &bvh.nodes[index] {
+            BvhNode::Interior { left, right, .. } => {
+                for &child in &[*left, *right] {
+                    match &bvh.nodes[child] {
+                        BvhNode::Interior { left, right, .. } => {
+                            grandchildren.push(*left);
+                            grandchildren.push(*right);
+                        }
+                        BvhNode::Leaf { .. } => grandchildren.push(child),
+                    }
+                }
+            }
+            BvhNode::Leaf { .. } => grandchildren.push(index),
+        }
+
+        let node_index = self.nodes.len();
+        self.nodes.push(QbvhNode::new());
+
+        for (slot, &child) in grandchildren.iter().enumerate() {
+            let encoded = match &bvh.nodes[child] {
+                BvhNode::Interior { .. } => self.collapse(bvh, child) as i32,
+                BvhNode::Leaf { start, count, .. } => self.push_leaf(*start, *count),
+            };
+            self.nodes[node_index].set_bounds(slot, bvh.nodes[child].bounds());
+            self.nodes[node_index].children[slot] = encoded;
+        }
+
+        node_index
+    }
+
+    pub fn intersect<'a, F>(
+        &self,
+        ray: &Ray,
+        t_min: ScalarF,
+        t_max: ScalarF,
+        mut intersect_primitive: F,
+    ) -> Option<HitRecord<'a>>
+    where
+        F: FnMut(usize, ScalarF, ScalarF) -> Option<HitRecord<'a>>,
+    {
+        let mut hit_record: Option<HitRecord> = None;
+        let mut closest_so_far = t_max;
+
+        if self.nodes.is_empty() {
+            return None;
+        }
+
+        // stack entries carry the entry distance of their box so that
+        // subtrees behind the closest hit can be skipped
+        let inv_d = Vec3D::new(
+            1.0 / ray.direction.x,
+            1.0 / ray.direction.y,
+            1.0 / ray.direction.z,
+        );
+        let mut stack: Vec<(i32, ScalarF)> = vec![(0, t_min)];
+        while let Some((child, t_enter)) = stack.pop() {
+            if t_enter > closest_so_far {
+                continue;
+            }
+
+            if child < 0 {
+                let leaf = self.leaves[(-child - 1) as usize];
+                for &primitive in &self.primitives[leaf.start..leaf.start + leaf.count] {
+                    if let Some(hit) = intersect_primitive(primitive, t_min, closest_so_far) {
+                        closest_so_far = hit.t;
+                        hit_record = Some(hit);
+                    }
+                }
+                continue;
+            }
+
+            let node = &self.nodes[child as usize];
+            #[cfg(feature = "simd")]
+            let (t_enter, hit) = node.intersect_simd(ray, &inv_d, t_min, closest_so_far);
+            #[cfg(not(feature = "simd"))]
+            let (t_enter, hit) = node.intersect(ray, &inv_d, t_min, closest_so_far);
+            let mut hits: [(i32, ScalarF); 4] = [(EMPTY_CHILD, 0.0); 4];
+            let mut hit_count = 0;
+            for lane in 0..4 {
+                // empty slots never hit, their boxes are inside out
+                if hit[lane] {
+                    hits[hit_count] = (node.children[lane], t_enter[lane]);
+                    hit_count += 1;
+                }
+            }
+
+            // push the farthest first so the nearest child is visited next
+            hits[..hit_count].sort_by(|a, b| b.1.total_cmp(&a.1));
+            stack.extend_from_slice(&hits[..hit_count]);
+        }
+
+        hit_record
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::accel::bench::{best_of, random_rays, random_spheres, report};
+    use crate::accel::{Accel, AccelStructure};
+    use crate::math::{tolerance, Point3D, Vec3D};
+    use crate::shapes::{Shape, Sphere};
+    use cgmath::InnerSpace;
+    use rand::{Rng, SeedableRng};
+
+    #[test]
+    fn test_qbvh_matches_bvh() {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(42);
+        let spheres: Vec<Sphere> = (0..10000)
+            .map(|_| Sphere {
+                center: Point3D::new(
+                    rng.gen_range(-50.0..50.0),
+                    rng.gen_range(-50.0..50.0),
+                    rng.gen_range(-50.0..50.0),
+                ),
+                radius: rng.gen_range(0.05..1.0),
+            })
+            .collect();
+        let bounds: Vec<Aabb> = spheres.iter().map(|s| s.bounding_box()).collect();
+
+        let bvh = Bvh::new(&bounds, &BvhConfig::default());
+        let qbvh = Qbvh::from_bvh(&bvh);
+
+        let mut hit_count = 0;
+        for _ in 0..2000 {
+            let ray = Ray {
+                origin: Point3D::new(
+                    rng.gen_range(-60.0..60.0),
+                    rng.gen_range(-60.0..60.0),
+                    rng.gen_range(-60.0..60.0),
+                ),
+                direction: Vec3D::new(
+                    rng.gen_range(-1.0..1.0),
+                    rng.gen_range(-1.0..1.0),
+                    rng.gen_range(-1.0..1.0),
+                )
+                .normalize(),
+            };
+            let intersect = |i: usize, t_min: ScalarF, t_max: ScalarF| {
+                spheres[i]
+                    .intersect(&ray, t_min, t_max)
+                    .map(|hit| HitRecord { shape: None, ..hit })
+            };
+
+            let expected = bvh.intersect(&ray, 0.001, ScalarF::MAX, intersect);
+            let actual = qbvh.intersect(&ray, 0.001, ScalarF::MAX, intersect);
+            match (expected, actual) {
+                (None, None) => {}
+                (Some(expected), Some(actual)) => {
+                    assert_eq!(expected.t, actual.t);
+                    assert_eq!(expected.p, actual.p);
+                    hit_count += 1;
+                }
+                _ => panic!("qbvh and bvh disagree on whether the ray hits"),
+            }
+        }
+        assert!(hit_count > 0);
+    }
+
+    #[test]
+    fn test_qbvh_single_leaf() {
+        let sphere = Sphere {
+            center: Point3D::new(0.0, 0.0, -5.0),
+            radius: 1.0,
+        };
+        let qbvh = Qbvh::new(&[sphere.bounding_box()], &BvhConfig::default());
+        let ray = Ray {
+            origin: Point3D::new(0.0, 0.0, 0.0),
+            direction: Vec3D::new(0.0, 0.0, -1.0),
+        };
+        let hit = qbvh.intersect(&ray, 0.001, ScalarF::MAX, |_, t_min, t_max| {
+            sphere.intersect(&ray, t_min, t_max)
+        });
+        assert!((hit.unwrap().t - 4.0).abs() < tolerance(1e-9));
+
+        let empty = Qbvh::new(&[], &BvhConfig::default());
+        assert!(empty
+            .intersect(&ray, 0.001, ScalarF::MAX, |_, t_min, t_max| {
+                sphere.intersect(&ray, t_min, t_max)
+            })
+            .is_none());
+    }
+
+    #[test]
+    #[ignore] // a benchmark, see accel/bench.rs
+    fn bench_qbvh_traversal() {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(42);
+        let spheres = random_spheres(&mut rng, 10000, 50.0);
+        let bounds: Vec<Aabb> = spheres.iter().map(|s| s.bounding_box()).collect();
+        let bvh = Bvh::new(&bounds, &BvhConfig::default());
+        let qbvh = Qbvh::from_bvh(&bvh);
+        let rays = random_rays(&mut rng, 100_000, 60.0);
+
+        let traverse = |intersect: &dyn Fn(&Ray) -> Option<ScalarF>| {
+            best_of(5, || rays.iter().filter_map(intersect).count())
+        };
+        let binary = traverse(&|ray| {
+            bvh.intersect(ray, 0.001, ScalarF::MAX, |i, t_min, t_max| {
+                spheres[i].intersect(ray, t_min, t_max)
+            })
+            .map(|hit| hit.t)
+        });
+        let quad = traverse(&|ray| {
+            qbvh.intersect(ray, 0.001, ScalarF::MAX, |i, t_min, t_max| {
+                spheres[i].intersect(ray, t_min, t_max)
+            })
+            .map(|hit| hit.t)
+        });
+        report("qbvh traversal", ("bvh", binary), ("qbvh", quad));
+    }
+
+    #[test]
+    fn test_qbvh_node_matches_aabb() {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(7);
+        let spheres = random_spheres(&mut rng, 4, 3.0);
+        let mut node = QbvhNode::new();
+        for (slot, sphere) in spheres.iter().take(3).enumerate() {
+            node.set_bounds(slot, &sphere.bounding_box());
+        }
+        let mut hits = 0;
+        for ray in random_rays(&mut rng, 2000, 4.0) {
+            let inv_d = Vec3D::new(
+                1.0 / ray.direction.x,
+                1.0 / ray.direction.y,
+                1.0 / ray.direction.z,
+            );
+            let (t_enter, hit) = node.intersect(&ray, &inv_d, 0.001, 10.0);
+            for (slot, sphere) in spheres.iter().take(3).enumerate() {
+                let expected = sphere.bounding_box().intersect(&ray, 0.001, 10.0);
+                assert_eq!(hit[slot], expected.is_some());
+                if let Some((t0, _)) = expected {
+                    assert_eq!(t_enter[slot], t0);
+                    hits += 1;
+                }
+            }
+            // the unused slot never hits
+            assert!(!hit[3]);
+        }
+        assert!(hits > 0);
+    }
+
+    #[test]
+    fn test_qbvh_accel_structure() {
+        let sphere = Sphere {
+            center: Point3D::new(0.0, 0.0, -5.0),
+            radius: 1.0,
+        };
+        let bounds = [sphere.bounding_box()];
+        let default = Accel::new(&bounds, &AccelStructure::default());
+        assert!(matches!(default, Accel::Bvh(_)));
+        let structure: AccelStructure = toml::from_str(r#"type = "Qbvh""#).unwrap();
+        let accel = Accel::new(&bounds, &structure);
+        assert!(matches!(accel, Accel::Qbvh(_)));
+        let ray = Ray {
+            origin: Point3D::new(0.0, 0.0, 0.0),
+            direction: Vec3D::new(0.0, 0.0, -1.0),
+        };
+        let hit = accel.intersect(&ray, 0.001, ScalarF::MAX, |_, t_min, t_max| {
+            sphere.intersect(&ray, t_min, t_max)
+        });
+        assert!((hit.unwrap().t - 4.0).abs() < tolerance(1e-9));
+    }
+}
@@ -0,0 +1,250 @@
+use super::super::math::{Aabb, Ray, ScalarF, Vec3D};
+use super::qbvh::QbvhNode;
+
+// the slab test of `Aabb::intersect` with the three axes in one 256-bit
+// register. the fourth lane spans everything and never narrows the range
+impl Aabb {
+    pub fn intersect_simd(
+        &self,
+        ray: &Ray,
+        t_min: ScalarF,
+        t_max: ScalarF,
+    ) -> Option<(ScalarF, ScalarF)> {
+        // the lanes hold f64, single precision builds take the scalar test
+        #[cfg(all(target_arch = "x86_64", not(feature = "f32_math")))]
+        if is_x86_feature_detected!("avx2") {
+            // SAFETY: the cpu supports avx2
+            return unsafe { intersect_avx2(self, ray, t_min, t_max) };
+        }
+        self.intersect(ray, t_min, t_max)
+    }
+
+    pub fn hit_simd(&self, ray: &Ray, t_min: ScalarF, t_max: ScalarF) -> bool {
+        self.intersect_simd(ray, t_min, t_max).is_some()
+    }
+}
+
+#[cfg(all(target_arch = "x86_64", not(feature = "f32_math")))]
+#[target_feature(enable = "avx2")]
+unsafe fn intersect_avx2(
+    aabb: &Aabb,
+    ray: &Ray,
+    t_min: ScalarF,
+    t_max: ScalarF,
+) -> Option<(ScalarF, ScalarF)> {
+    use std::arch::x86_64::*;
+
+    // _mm256_set_pd takes the lanes from the highest down
+    let min = _mm256_set_pd(ScalarF::NEG_INFINITY, aabb.min.z, aabb.min.y, aabb.min.x);
+    let max = _mm256_set_pd(ScalarF::INFINITY, aabb.max.z, aabb.max.y, aabb.max.x);
+    let origin = _mm256_set_pd(0.0, ray.origin.z, ray.origin.y, ray.origin.x);
+    let direction = _mm256_set_pd(1.0, ray.direction.z, ray.direction.y, ray.direction.x);
+
+    let inv_d = _mm256_div_pd(_mm256_set1_pd(1.0), direction);
+    let t0 = _mm256_mul_pd(_mm256_sub_pd(min, origin), inv_d);
+    let t1 = _mm256_mul_pd(_mm256_sub_pd(max, origin), inv_d);
+    let positive = _mm256_cmp_pd::<_CMP_GE_OQ>(inv_d, _mm256_setzero_pd());
+    let t_near = _mm256_blendv_pd(t1, t0, positive);
+    let t_far = _mm256_blendv_pd(t0, t1, positive);
+
+    // max and min return their second operand when the first is NaN, so
+    // degenerate slabs leave the range untouched like the scalar test
+    let mut near = [0.0; 4];
+    let mut far = [0.0; 4];
+    _mm256_storeu_pd(
+        near.as_mut_ptr(),
+        _mm256_max_pd(t_near, _mm256_set1_pd(t_min)),
+    );
+    _mm256_storeu_pd(
+        far.as_mut_ptr(),
+        _mm256_min_pd(t_far, _mm256_set1_pd(t_max)),
+    );
+    let t_enter = near[0].max(near[1]).max(near[2]);
+    let t_exit = far[0].min(far[1]).min(far[2]);
+    if t_enter > t_exit {
+        return None;
+    }
+    Some((t_enter, t_exit))
+}
+
+// the four child boxes of a QBVH node in one pass, a register per axis holds
+// the same slab of all four children
+impl QbvhNode {
+    pub fn intersect_simd(
+        &self,
+        ray: &Ray,
+        inv_d: &Vec3D,
+        t_min: ScalarF,
+        t_max: ScalarF,
+    ) -> ([ScalarF; 4], [bool; 4]) {
+        #[cfg(all(target_arch = "x86_64", not(feature = "f32_math")))]
+        if is_x86_feature_detected!("avx2") {
+            // SAFETY: the cpu supports avx2
+            return unsafe { node_intersect_avx2(self, ray, inv_d, t_min, t_max) };
+        }
+        self.intersect(ray, inv_d, t_min, t_max)
+    }
+}
+
+#[cfg(all(target_arch = "x86_64", not(feature = "f32_math")))]
+#[target_feature(enable = "avx2")]
+unsafe fn node_intersect_avx2(
+    node: &QbvhNode,
+    ray: &Ray,
+    inv_d: &Vec3D,
+    t_min: ScalarF,
+    t_max: ScalarF,
+) -> ([ScalarF; 4], [bool; 4]) {
+    use std::arch::x86_64::*;
+
+    let mut t_enter = _mm256_set1_pd(t_min);
+    let mut t_exit = _mm256_set1_pd(t_max);
+    for axis in 0..3 {
+        let (near, far) = if inv_d[axis] >= 0.0 {
+            (&node.min[axis], &node.max[axis])
+        } else {
+            (&node.max[axis], &node.min[axis])
+        };
+        let origin = _mm256_set1_pd(ray.origin[axis]);
+        let inv = _mm256_set1_pd(inv_d[axis]);
+        let t_near = _mm256_mul_pd(_mm256_sub_pd(_mm256_loadu_pd(near.as_ptr()), origin), inv);
+        let t_far = _mm256_mul_pd(_mm256_sub_pd(_mm256_loadu_pd(far.as_ptr()), origin), inv);
+        // max and min return their second operand when the first is NaN
+        t_enter = _mm256_max_pd(t_near, t_enter);
+        t_exit = _mm256_min_pd(t_far, t_exit);
+    }
+    let mask = _mm256_movemask_pd(_mm256_cmp_pd::<_CMP_LE_OQ>(t_enter, t_exit));
+    let mut enter = [0.0; 4];
+    _mm256_storeu_pd(enter.as_mut_ptr(), t_enter);
+    (enter, std::array::from_fn(|lane| mask & (1 << lane) != 0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::accel::bench::{best_of, random_rays, report};
+    use crate::math::{Point3D, Vec3D};
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+    use std::hint::black_box;
+
+    #[test]
+    fn test_simd_matches_scalar() {
+        let mut rng = StdRng::seed_from_u64(17);
+        let coordinate = |rng: &mut StdRng| {
+            // snap some values to the box faces and axes to hit the edge cases
+            match rng.gen_range(0..8) {
+                0 => 0.0,
+                1 => 1.0,
+                _ => rng.gen_range(-3.0..3.0),
+            }
+        };
+        let mut hits = 0;
+        for _ in 0..10_000 {
+            let a = Point3D::new(
+                coordinate(&mut rng),
+                coordinate(&mut rng),
+                coordinate(&mut rng),
+            );
+            let b = Point3D::new(
+                coordinate(&mut rng),
+                coordinate(&mut rng),
+                coordinate(&mut rng),
+            );
+            let aabb = Aabb::from_points(&[a, b]);
+            let ray = Ray {
+                origin: Point3D::new(
+                    coordinate(&mut rng),
+                    coordinate(&mut rng),
+                    coordinate(&mut rng),
+                ),
+                direction: Vec3D::new(
+                    coordinate(&mut rng),
+                    coordinate(&mut rng),
+                    coordinate(&mut rng),
+                ),
+            };
+            let t_max = rng.gen_range(0.5..10.0);
+            let expected = aabb.intersect(&ray, 0.001, t_max);
+            assert_eq!(aabb.intersect_simd(&ray, 0.001, t_max), expected);
+            assert_eq!(aabb.hit_simd(&ray, 0.001, t_max), expected.is_some());
+            hits += expected.is_some() as usize;
+        }
+        assert!(hits > 500);
+    }
+
+    #[test]
+    #[ignore] // a benchmark, see accel/bench.rs
+    fn bench_simd_slab_test() {
+        let mut rng = StdRng::seed_from_u64(17);
+        let pairs: Vec<(Aabb, Ray)> = random_rays(&mut rng, 10_000, 3.0)
+            .into_iter()
+            .map(|ray| {
+                let mut corner = || {
+                    Point3D::new(
+                        rng.gen_range(-3.0..3.0),
+                        rng.gen_range(-3.0..3.0),
+                        rng.gen_range(-3.0..3.0),
+                    )
+                };
+                (Aabb::from_points(&[corner(), corner()]), ray)
+            })
+            .collect();
+        let time = |hit: &dyn Fn(&Aabb, &Ray) -> bool| {
+            best_of(5, || {
+                (0..100)
+                    .map(|_| {
+                        pairs
+                            .iter()
+                            .filter(|(aabb, ray)| hit(black_box(aabb), ray))
+                            .count()
+                    })
+                    .sum::<usize>()
+            })
+        };
+        let scalar = time(&|aabb, ray| aabb.intersect(ray, 0.001, 10.0).is_some());
+        let simd = time(&|aabb, ray| aabb.hit_simd(ray, 0.001, 10.0));
+        report("slab test", ("scalar", scalar), ("simd", simd));
+    }
+
+    #[test]
+    fn test_node_simd_matches_scalar() {
+        let mut rng = StdRng::seed_from_u64(23);
+        let coordinate = |rng: &mut StdRng| match rng.gen_range(0..8) {
+            0 => 0.0,
+            1 => 1.0,
+            _ => rng.gen_range(-3.0..3.0),
+        };
+        let point =
+            |rng: &mut StdRng| Point3D::new(coordinate(rng), coordinate(rng), coordinate(rng));
+        let mut hits = 0;
+        for _ in 0..2000 {
+            let mut node = QbvhNode::new();
+            for slot in 0..rng.gen_range(1..=4) {
+                node.set_bounds(
+                    slot,
+                    &Aabb::from_points(&[point(&mut rng), point(&mut rng)]),
+                );
+            }
+            let ray = Ray {
+                origin: point(&mut rng),
+                direction: point(&mut rng) - Point3D::new(0.0, 0.0, 0.0),
+            };
+            let inv_d = Vec3D::new(
+                1.0 / ray.direction.x,
+                1.0 / ray.direction.y,
+                1.0 / ray.direction.z,
+            );
+            let (t_enter, hit) = node.intersect(&ray, &inv_d, 0.001, 5.0);
+            let (simd_enter, simd_hit) = node.intersect_simd(&ray, &inv_d, 0.001, 5.0);
+            assert_eq!(simd_hit, hit);
+            for lane in 0..4 {
+                if hit[lane] {
+                    assert_eq!(simd_enter[lane], t_enter[lane]);
+                    hits += 1;
+                }
+            }
+        }
+        assert!(hits > 200);
+    }
+}
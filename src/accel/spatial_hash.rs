@@ -0,0 +1,250 @@
+use super::super::common::HitRecord;
+use super::super::math::{Aabb, Ray, ScalarF};
+use serde::Deserialize;
+use std::collections::{HashMap, HashSet};
+
+// uniform grid whose occupied cells live in a hash map, so inserting a
+// primitive only touches the cells its box overlaps and nothing is rebuilt.
+// suits scenes whose geometry changes between frames better than
+// refitting a bvh, as long as `cell_size` is near the size of the
+// primitives, huge boxes take many cells
+#[derive(Debug)]
+pub struct SpatialHashGrid {
+    cells: HashMap<(i32, i32, i32), Vec<usize>>,
+    cell_size: ScalarF,
+    bounds: Aabb, // of everything inserted, where traversal starts and ends
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+pub struct SpatialHashGridConfig {
+    pub cell_size: f64,
+}
+
+impl SpatialHashGrid {
+    pub fn new(bounds: &[Aabb], config: &SpatialHashGridConfig) -> Self {
+        let mut grid = SpatialHashGrid {
+            cells: HashMap::new(),
+            cell_size: config.cell_size as ScalarF,
+            bounds: Aabb::empty(),
+        };
+        for (id, aabb) in bounds.iter().enumerate() {
+            grid.insert(id, *aabb);
+        }
+        grid
+    }
+
+    fn cell(&self, p: ScalarF) -> i32 {
+        (p / self.cell_size).floor() as i32
+    }
+
+    pub fn insert(&mut self, id: usize, bounds: Aabb) {
+        let (min, max) = (bounds.min, bounds.max);
+        for x in self.cell(min.x)..=self.cell(max.x) {
+            for y in self.cell(min.y)..=self.cell(max.y) {
+                for z in self.cell(min.z)..=self.cell(max.z) {
+                    self.cells.entry((x, y, z)).or_default().push(id);
+                }
+            }
+        }
+        self.bounds = self.bounds.union(&bounds);
+    }
+
+    // steps through the cells the ray passes with a 3d dda, stopping once
+    // the closest hit lies before the next cell. primitives spanning several
+    // cells are only tested the first time
+    pub fn intersect<'a, F>(
+        &self,
+        ray: &Ray,
+        t_min: ScalarF,
+        t_max: ScalarF,
+        mut intersect_primitive: F,
+    ) -> Option<HitRecord<'a>>
+    where
+        F: FnMut(usize, ScalarF, ScalarF) -> Option<HitRecord<'a>>,
+    {
+        if self.cells.is_empty() {
+            return None;
+        }
+        let (t_enter, t_exit) = self.bounds.intersect(ray, t_min, t_max)?;
+        let mut hit_record: Option<HitRecord> = None;
+        let mut closest_so_far = t_max;
+        let mut tested = HashSet::new();
+
+        // rounding may put the entry point just outside the grid
+        let entry = ray.at(t_enter);
+        let (mut cell, mut step, mut t_next, mut t_delta) = ([0; 3], [0; 3], [0.0; 3], [0.0; 3]);
+        let mut last = [0; 3];
+        for axis in 0..3 {
+            let first = self.cell(self.bounds.min[axis]);
+            last[axis] = self.cell(self.bounds.max[axis]);
+            cell[axis] = self.cell(entry[axis]).clamp(first, last[axis]);
+            let direction = ray.direction[axis];
+            if direction == 0.0 {
+                step[axis] = 0;
+                t_next[axis] = ScalarF::INFINITY;
+                t_delta[axis] = ScalarF::INFINITY;
+                continue;
+            }
+            step[axis] = if direction > 0.0 { 1 } else { -1 };
+            let boundary = (cell[axis] + (direction > 0.0) as i32) as ScalarF * self.cell_size;
+            t_next[axis] = (boundary - ray.origin[axis]) / direction;
+            t_delta[axis] = self.cell_size / direction.abs();
+            if step[axis] < 0 {
+                last[axis] = first;
+            }
+        }
+
+        loop {
+            if let Some(ids) = self.cells.get(&(cell[0], cell[1], cell[2])) {
+                for &id in ids {
+                    if !tested.insert(id) {
+                        continue;
+                    }
+                    if let Some(hit) = intersect_primitive(id, t_min, closest_so_far) {
+                        closest_so_far = hit.t;
+                        hit_record = Some(hit);
+                    }
+                }
+            }
+
+            let axis = (0..3)
+                .min_by(|&a, &b| t_next[a].total_cmp(&t_next[b]))
+                .unwrap();
+            let t_cell_exit = t_next[axis];
+            if closest_so_far <= t_cell_exit || t_cell_exit > t_exit || cell[axis] == last[axis] {
+                break;
+            }
+            cell[axis] += step[axis];
+            t_next[axis] += t_delta[axis];
+        }
+        hit_record
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::accel::{Accel, AccelStructure};
+    use crate::math::{tolerance, Point3D, Vec3D};
+    use crate::shapes::{Shape, Sphere};
+    use cgmath::InnerSpace;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    fn linear_scan(spheres: &[Sphere], ray: &Ray) -> Option<ScalarF> {
+        spheres
+            .iter()
+            .filter_map(|sphere| sphere.intersect(ray, 0.001, ScalarF::MAX))
+            .map(|hit| hit.t)
+            .min_by(ScalarF::total_cmp)
+    }
+
+    #[test]
+    fn test_spatial_hash_grid_matches_linear_scan() {
+        let mut rng = StdRng::seed_from_u64(29);
+        let spheres: Vec<Sphere> = (0..100)
+            .map(|_| Sphere {
+                center: Point3D::new(
+                    rng.gen_range(-10.0..10.0),
+                    rng.gen_range(-10.0..10.0),
+                    rng.gen_range(-10.0..10.0),
+                ),
+                radius: rng.gen_range(0.1..1.5),
+            })
+            .collect();
+        let bounds: Vec<Aabb> = spheres.iter().map(|s| s.bounding_box()).collect();
+        let grid = SpatialHashGrid::new(&bounds, &SpatialHashGridConfig { cell_size: 2.0 });
+        let intersect = |ray: &Ray| {
+            grid.intersect(ray, 0.001, ScalarF::MAX, |i, t_min, t_max| {
+                spheres[i].intersect(ray, t_min, t_max)
+            })
+            .map(|hit| hit.t)
+        };
+
+        // every sphere is found by a ray aimed inside it
+        for sphere in &spheres {
+            let origin = Point3D::new(
+                rng.gen_range(-30.0..30.0),
+                rng.gen_range(-30.0..30.0),
+                rng.gen_range(-30.0..30.0),
+            );
+            let target = sphere.center + Vec3D::new(0.5 * sphere.radius, 0.0, 0.0);
+            let ray = Ray {
+                origin,
+                direction: (target - origin).normalize(),
+            };
+            let expected = linear_scan(&spheres, &ray);
+            assert!(expected.is_some());
+            assert_eq!(intersect(&ray), expected);
+        }
+
+        let mut hit_count = 0;
+        for i in 0..1000 {
+            let mut direction = Vec3D::new(
+                rng.gen_range(-1.0..1.0),
+                rng.gen_range(-1.0..1.0),
+                rng.gen_range(-1.0..1.0),
+            );
+            // some rays run along the cell faces of an axis
+            if i % 4 == 0 {
+                direction[i % 3] = 0.0;
+            }
+            let ray = Ray {
+                origin: Point3D::new(
+                    rng.gen_range(-15.0..15.0),
+                    rng.gen_range(-15.0..15.0),
+                    rng.gen_range(-15.0..15.0),
+                ),
+                direction: direction.normalize(),
+            };
+            let expected = linear_scan(&spheres, &ray);
+            assert_eq!(intersect(&ray), expected);
+            hit_count += expected.is_some() as usize;
+        }
+        assert!(hit_count > 0);
+    }
+
+    #[test]
+    fn test_spatial_hash_grid_insert() {
+        let structure: AccelStructure = toml::from_str(
+            r#"
+            type = "SpatialHashGrid"
+            cell_size = 1.0
+            "#,
+        )
+        .unwrap();
+        let accel = Accel::new(&[], &structure);
+        assert!(matches!(accel, Accel::SpatialHashGrid(_)));
+        let Accel::SpatialHashGrid(mut grid) = accel else {
+            unreachable!()
+        };
+
+        let ray = Ray {
+            origin: Point3D::new(0.0, 0.0, 0.0),
+            direction: Vec3D::new(0.0, 0.0, -1.0),
+        };
+        let spheres = [
+            Sphere {
+                center: Point3D::new(0.0, 0.0, -5.0),
+                radius: 1.0,
+            },
+            Sphere {
+                center: Point3D::new(0.0, 0.0, -2.5),
+                radius: 0.5,
+            },
+        ];
+        let intersect = |grid: &SpatialHashGrid| {
+            grid.intersect(&ray, 0.001, ScalarF::MAX, |i, t_min, t_max| {
+                spheres[i].intersect(&ray, t_min, t_max)
+            })
+            .map(|hit| hit.t)
+        };
+        assert!(intersect(&grid).is_none());
+
+        // the grid grows with what is inserted, nearer spheres take over
+        grid.insert(0, spheres[0].bounding_box());
+        assert!((intersect(&grid).unwrap() - 4.0).abs() < tolerance(1e-9));
+        grid.insert(1, spheres[1].bounding_box());
+        assert!((intersect(&grid).unwrap() - 2.0).abs() < tolerance(1e-9));
+    }
+}
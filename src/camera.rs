@@ -1,12 +1,26 @@
-use super::math::{Point3D, Point3DConfig, Ray, Vec3D, Vec3DConfig};
+use super::math::consts::PI;
+use super::math::{Point3D, Point3DConfig, Ray, ScalarF, Vec3D, Vec3DConfig};
+use super::sampler::Sampler;
+use super::shapes::concentric_sample_disk;
 use cgmath::InnerSpace;
 use serde::Deserialize;
-use std::f64::consts::PI;
 use std::fmt::Debug;
 use std::sync::Arc;
 
 pub trait Camera: Sync + Send + Debug {
-    fn create_ray(&self, s: f64, t: f64) -> Ray;
+    fn create_ray(&self, s: ScalarF, t: ScalarF) -> Ray;
+
+    // cameras with a lens draw the point the ray passes through from the
+    // sampler, the others ignore it
+    fn create_ray_with_sampler(&self, s: ScalarF, t: ScalarF, _sampler: &mut dyn Sampler) -> Ray {
+        self.create_ray(s, t)
+    }
+
+    // whether the lens projects anything onto (s, t), pixels it doesn't
+    // cover stay black instead of being traced
+    fn covers(&self, _s: ScalarF, _t: ScalarF) -> bool {
+        true
+    }
 }
 
 #[derive(Debug)]
@@ -18,7 +32,13 @@ pub struct PerspectiveCamera {
 }
 
 impl PerspectiveCamera {
-    pub fn new(look_from: Point3D, look_at: Point3D, vup: Vec3D, vfov: f64, aspect: f64) -> Self {
+    pub fn new(
+        look_from: Point3D,
+        look_at: Point3D,
+        vup: Vec3D,
+        vfov: ScalarF,
+        aspect: ScalarF,
+    ) -> Self {
         let theta = vfov * PI / 180.0;
         let half_height = (theta / 2.0).tan();
         let half_width = aspect * half_height;
@@ -35,7 +55,7 @@ impl PerspectiveCamera {
 }
 
 impl Camera for PerspectiveCamera {
-    fn create_ray(&self, s: f64, t: f64) -> Ray {
+    fn create_ray(&self, s: ScalarF, t: ScalarF) -> Ray {
         Ray {
             origin: self.origin,
             direction: (self.lower_left_corner + s * self.horizontal + t * self.vertical
@@ -54,10 +74,232 @@ pub struct PerspectiveCameraConfig {
     aspect: f64,
 }
 
+// perspective camera with a lens of diameter `aperture`, only the plane at
+// `focus_distance` along the viewing direction is sharp
+#[derive(Debug)]
+pub struct ThinLensCamera {
+    pinhole: PerspectiveCamera,
+    forward: Vec3D,
+    right: Vec3D,
+    up: Vec3D,
+    lens_radius: ScalarF,
+    focus_distance: ScalarF,
+}
+
+impl ThinLensCamera {
+    pub fn new(
+        look_from: Point3D,
+        look_at: Point3D,
+        vup: Vec3D,
+        vfov: ScalarF,
+        aspect: ScalarF,
+        aperture: ScalarF,
+        focus_distance: ScalarF,
+    ) -> Self {
+        let forward = (look_at - look_from).normalize();
+        let right = forward.cross(vup).normalize();
+        let up = right.cross(forward);
+        Self {
+            pinhole: PerspectiveCamera::new(look_from, look_at, vup, vfov, aspect),
+            forward,
+            right,
+            up,
+            lens_radius: aperture / 2.0,
+            focus_distance,
+        }
+    }
+}
+
+impl Camera for ThinLensCamera {
+    // through the centre of the lens
+    fn create_ray(&self, s: ScalarF, t: ScalarF) -> Ray {
+        self.pinhole.create_ray(s, t)
+    }
+
+    fn create_ray_with_sampler(&self, s: ScalarF, t: ScalarF, sampler: &mut dyn Sampler) -> Ray {
+        let ray = self.pinhole.create_ray(s, t);
+        if self.lens_radius <= 0.0 {
+            return ray;
+        }
+
+        // every ray through the lens meets the pinhole ray on the focus plane
+        let focus_point = ray.at(self.focus_distance / ray.direction.dot(self.forward));
+        let (x, y) = concentric_sample_disk(sampler.get_2d());
+        let origin = ray.origin + (self.right * x + self.up * y) * self.lens_radius;
+        Ray {
+            origin,
+            direction: (focus_point - origin).normalize(),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct ThinLensCameraConfig {
+    look_from: Point3DConfig,
+    look_at: Point3DConfig,
+    vup: Vec3DConfig,
+    vfov: f64,
+    aspect: f64,
+    aperture: f64,
+    focus_distance: f64,
+}
+
+// parallel projection, every ray shares the viewing direction and the
+// origins cover a `2 * half_width` by `2 * half_height` rectangle
+#[derive(Debug)]
+pub struct OrthographicCamera {
+    origin: Point3D,
+    direction: Vec3D,
+    right: Vec3D,
+    up: Vec3D,
+    half_width: ScalarF,
+    half_height: ScalarF,
+}
+
+impl OrthographicCamera {
+    pub fn new(
+        look_from: Point3D,
+        look_at: Point3D,
+        vup: Vec3D,
+        width: ScalarF,
+        height: ScalarF,
+    ) -> Self {
+        let direction = (look_at - look_from).normalize();
+        let right = direction.cross(vup).normalize();
+        let up = right.cross(direction);
+        Self {
+            origin: look_from,
+            direction,
+            right,
+            up,
+            half_width: width / 2.0,
+            half_height: height / 2.0,
+        }
+    }
+}
+
+impl Camera for OrthographicCamera {
+    fn create_ray(&self, s: ScalarF, t: ScalarF) -> Ray {
+        Ray {
+            origin: self.origin
+                + (2.0 * s - 1.0) * self.half_width * self.right
+                + (2.0 * t - 1.0) * self.half_height * self.up,
+            direction: self.direction,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct OrthographicCameraConfig {
+    look_from: Point3DConfig,
+    look_at: Point3DConfig,
+    vup: Vec3DConfig,
+    width: f64, // extent of the view in world units
+    height: f64,
+}
+
+// equirectangular projection over the full sphere of directions, s sweeps
+// the azimuth starting at -x and t the polar angle with t = 1 looking up +y
+#[derive(Debug)]
+pub struct PanoramicCamera {
+    origin: Point3D,
+}
+
+impl PanoramicCamera {
+    pub fn new(origin: Point3D) -> Self {
+        Self { origin }
+    }
+}
+
+impl Camera for PanoramicCamera {
+    fn create_ray(&self, s: ScalarF, t: ScalarF) -> Ray {
+        let phi = 2.0 * PI * s;
+        let theta = PI * (1.0 - t);
+        Ray {
+            origin: self.origin,
+            direction: Vec3D::new(
+                -theta.sin() * phi.cos(),
+                theta.cos(),
+                theta.sin() * phi.sin(),
+            ),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct PanoramicCameraConfig {
+    origin: Point3DConfig,
+}
+
+// equisolid fisheye lens, the image disk inscribed in the frame covers
+// `fov_deg` and the distance from its center grows like 2 sin(theta / 2).
+// pixels outside the disk aren't covered and render black
+#[derive(Debug)]
+pub struct FisheyeCamera {
+    origin: Point3D,
+    forward: Vec3D,
+    right: Vec3D,
+    up: Vec3D,
+    fov_deg: ScalarF,
+}
+
+impl FisheyeCamera {
+    pub fn new(look_from: Point3D, look_at: Point3D, vup: Vec3D, fov_deg: ScalarF) -> Self {
+        let forward = (look_at - look_from).normalize();
+        let right = forward.cross(vup).normalize();
+        let up = right.cross(forward);
+        Self {
+            origin: look_from,
+            forward,
+            right,
+            up,
+            fov_deg,
+        }
+    }
+}
+
+impl Camera for FisheyeCamera {
+    fn create_ray(&self, s: ScalarF, t: ScalarF) -> Ray {
+        let x = 2.0 * s - 1.0;
+        let y = 2.0 * t - 1.0;
+        // outside the disk the direction continues past the fov, `covers`
+        // keeps these rays from being traced
+        let r = (x * x + y * y).sqrt();
+
+        // the disk edge (r = 1) maps to theta = fov / 2
+        let max_theta = self.fov_deg.to_radians() / 2.0;
+        let theta = 2.0 * (r * (max_theta / 2.0).sin()).clamp(-1.0, 1.0).asin();
+        let (cos_phi, sin_phi) = if r > 0.0 { (x / r, y / r) } else { (1.0, 0.0) };
+        Ray {
+            origin: self.origin,
+            direction: (self.forward * theta.cos()
+                + (self.right * cos_phi + self.up * sin_phi) * theta.sin())
+            .normalize(),
+        }
+    }
+
+    fn covers(&self, s: ScalarF, t: ScalarF) -> bool {
+        let (x, y) = (2.0 * s - 1.0, 2.0 * t - 1.0);
+        x * x + y * y <= 1.0
+    }
+}
+
+#[derive(Deserialize)]
+pub struct FisheyeCameraConfig {
+    look_from: Point3DConfig,
+    look_at: Point3DConfig,
+    vup: Vec3DConfig,
+    fov: f64, // in degrees
+}
+
 #[derive(Deserialize)]
 #[serde(tag = "type")]
 pub enum CameraConfig {
     Perspective(PerspectiveCameraConfig),
+    Orthographic(OrthographicCameraConfig),
+    Panoramic(PanoramicCameraConfig),
+    Fisheye(FisheyeCameraConfig),
+    ThinLens(ThinLensCameraConfig),
 }
 
 impl CameraConfig {
@@ -67,8 +309,33 @@ impl CameraConfig {
                 config.look_from.to_point(),
                 config.look_at.to_point(),
                 config.vup.to_vec3(),
-                config.vfov,
-                config.aspect,
+                config.vfov as ScalarF,
+                config.aspect as ScalarF,
+            )),
+            CameraConfig::Orthographic(config) => Arc::new(OrthographicCamera::new(
+                config.look_from.to_point(),
+                config.look_at.to_point(),
+                config.vup.to_vec3(),
+                config.width as ScalarF,
+                config.height as ScalarF,
+            )),
+            CameraConfig::Panoramic(config) => {
+                Arc::new(PanoramicCamera::new(config.origin.to_point()))
+            }
+            CameraConfig::Fisheye(config) => Arc::new(FisheyeCamera::new(
+                config.look_from.to_point(),
+                config.look_at.to_point(),
+                config.vup.to_vec3(),
+                config.fov as ScalarF,
+            )),
+            CameraConfig::ThinLens(config) => Arc::new(ThinLensCamera::new(
+                config.look_from.to_point(),
+                config.look_at.to_point(),
+                config.vup.to_vec3(),
+                config.vfov as ScalarF,
+                config.aspect as ScalarF,
+                config.aperture as ScalarF,
+                config.focus_distance as ScalarF,
             )),
         }
     }
@@ -77,7 +344,8 @@ impl CameraConfig {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::math::{point_approx_eq, vec3_approx_eq};
+    use crate::math::{point_approx_eq, tolerance, vec3_approx_eq};
+    use crate::sampler::RandomSampler;
 
     #[test]
     fn test_perspective_camera() {
@@ -92,12 +360,150 @@ mod tests {
         assert!(point_approx_eq(
             ray.origin,
             Point3D::new(0.0, 0.0, 0.0),
-            1e-6
+            tolerance(1e-6)
         ));
         assert!(vec3_approx_eq(
             ray.direction,
             Vec3D::new(0.0, 0.0, -1.0),
-            1e-6
+            tolerance(1e-6)
+        ));
+    }
+
+    #[test]
+    fn test_orthographic_camera() {
+        let camera = OrthographicCamera::new(
+            Point3D::new(1.0, 2.0, 3.0),
+            Point3D::new(1.0, 2.0, -1.0),
+            Vec3D::new(0.0, 1.0, 0.0),
+            4.0,
+            2.0,
+        );
+        let center = camera.create_ray(0.5, 0.5);
+        assert!(point_approx_eq(
+            center.origin,
+            Point3D::new(1.0, 2.0, 3.0),
+            tolerance(1e-9)
         ));
+        assert!(vec3_approx_eq(
+            center.direction,
+            Vec3D::new(0.0, 0.0, -1.0),
+            tolerance(1e-9)
+        ));
+
+        let (s1, s2, t) = (0.9, 0.2, 0.3);
+        let ray1 = camera.create_ray(s1, t);
+        let ray2 = camera.create_ray(s2, t);
+        assert!(vec3_approx_eq(
+            ray1.direction,
+            ray2.direction,
+            tolerance(1e-12)
+        ));
+        assert!(vec3_approx_eq(
+            ray1.origin - ray2.origin,
+            2.0 * (s1 - s2) * camera.half_width * camera.right,
+            tolerance(1e-9)
+        ));
+        assert!(vec3_approx_eq(
+            camera.right,
+            Vec3D::new(1.0, 0.0, 0.0),
+            tolerance(1e-9)
+        ));
+    }
+
+    #[test]
+    fn test_panoramic_camera() {
+        let camera = PanoramicCamera::new(Point3D::new(1.0, 2.0, 3.0));
+        assert!(vec3_approx_eq(
+            camera.create_ray(0.0, 0.5).direction,
+            Vec3D::new(-1.0, 0.0, 0.0),
+            tolerance(1e-9)
+        ));
+        assert!(vec3_approx_eq(
+            camera.create_ray(0.5, 0.5).direction,
+            Vec3D::new(1.0, 0.0, 0.0),
+            tolerance(1e-9)
+        ));
+        assert!(vec3_approx_eq(
+            camera.create_ray(0.3, 1.0).direction,
+            Vec3D::new(0.0, 1.0, 0.0),
+            tolerance(1e-9)
+        ));
+
+        for i in 0..=10 {
+            for j in 0..=10 {
+                let ray = camera.create_ray(i as ScalarF / 10.0, j as ScalarF / 10.0);
+                assert!((ray.direction.magnitude() - 1.0).abs() < tolerance(1e-9));
+                assert!(point_approx_eq(ray.origin, camera.origin, tolerance(1e-12)));
+            }
+        }
+    }
+
+    #[test]
+    fn test_fisheye_camera() {
+        for fov_deg in [90.0, 180.0, 220.0] {
+            let camera = FisheyeCamera::new(
+                Point3D::new(0.0, 0.0, 0.0),
+                Point3D::new(0.0, 0.0, -1.0),
+                Vec3D::new(0.0, 1.0, 0.0),
+                fov_deg,
+            );
+            assert!(vec3_approx_eq(
+                camera.create_ray(0.5, 0.5).direction,
+                camera.forward,
+                tolerance(1e-9)
+            ));
+
+            // points on the inscribed disk edge
+            for (s, t) in [(1.0, 0.5), (0.5, 0.0), (0.5 + 0.5 * 0.6, 0.5 + 0.5 * 0.8)] {
+                let direction = camera.create_ray(s, t).direction;
+                let angle = direction.dot(camera.forward).acos().to_degrees();
+                assert!((angle - fov_deg / 2.0).abs() < tolerance(1e-6));
+                assert!((direction.magnitude() - 1.0).abs() < tolerance(1e-9));
+            }
+
+            // corners are outside of the disk and not covered
+            assert!(camera.covers(0.5, 0.5) && camera.covers(1.0, 0.5));
+            assert!(!camera.covers(0.0, 0.0) && !camera.covers(0.95, 0.95));
+        }
+    }
+
+    #[test]
+    fn test_thin_lens_camera() {
+        let (look_from, look_at) = (Point3D::new(1.0, 2.0, 3.0), Point3D::new(0.0, 0.0, -2.0));
+        let vup = Vec3D::new(0.0, 1.0, 0.0);
+        let pinhole = PerspectiveCamera::new(look_from, look_at, vup, 60.0, 1.5);
+        let mut sampler = RandomSampler::new(1);
+
+        // without an aperture it is the pinhole camera
+        let camera = ThinLensCamera::new(look_from, look_at, vup, 60.0, 1.5, 0.0, 4.0);
+        for (s, t) in [(0.5, 0.5), (0.1, 0.9), (0.7, 0.2)] {
+            let expected = pinhole.create_ray(s, t);
+            let ray = camera.create_ray_with_sampler(s, t, &mut sampler);
+            assert_eq!(ray.origin, expected.origin);
+            assert_eq!(ray.direction, expected.direction);
+        }
+
+        // rays leave from all over the lens and meet again on the focus plane
+        let camera = ThinLensCamera::new(look_from, look_at, vup, 60.0, 1.5, 0.5, 4.0);
+        for (s, t) in [(0.5, 0.5), (0.1, 0.9), (0.7, 0.2)] {
+            let center = pinhole.create_ray(s, t);
+            let focus_point = center.at(4.0 / center.direction.dot(camera.forward));
+            let mut max_offset: ScalarF = 0.0;
+            for _ in 0..100 {
+                let ray = camera.create_ray_with_sampler(s, t, &mut sampler);
+                let offset = ray.origin - look_from;
+                assert!(offset.magnitude() <= 0.25 + tolerance(1e-12));
+                assert!(offset.dot(camera.forward).abs() < tolerance(1e-12));
+                max_offset = max_offset.max(offset.magnitude());
+
+                let distance = (focus_point - ray.origin).magnitude();
+                assert!(point_approx_eq(
+                    ray.at(distance),
+                    focus_point,
+                    tolerance(1e-9)
+                ));
+            }
+            assert!(max_offset > 0.2);
+        }
     }
 }
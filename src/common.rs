@@ -1,12 +1,44 @@
-use super::math::{Point3D, Vec3D};
+use super::math::{Point3D, Ray, ScalarF, Vec3D};
 use super::object::Object;
 use super::shapes::Shape;
+use cgmath::InnerSpace;
 
+#[derive(Clone)]
 pub struct HitRecord<'a> {
-    pub t: f64,
+    pub t: ScalarF,
     pub p: Point3D,
-    pub normal: Vec3D,
+    pub normal: Vec3D,    // on the side of the ray, see `set_face_normal`
+    pub front_face: bool, // whether the ray hit the outside of the surface
+    pub uv: Option<(ScalarF, ScalarF)>, // surface parameterization, if the shape has one
+    pub tangent: Option<Vec3D>, // direction of increasing u
+    pub bitangent: Option<Vec3D>, // direction of increasing v
+    pub vertex_color: Option<Vec3D>, // interpolated colour of mesh vertices
 
     pub shape: Option<&'a dyn Shape>,
     pub object: Option<&'a Object>,
+    pub object_id: usize, // index of `object` in the scene
+    // index of refraction around a dielectric object, set by tracers that
+    // follow nested media. air when none
+    pub exterior_ior: Option<ScalarF>,
+}
+
+impl HitRecord<'_> {
+    // `outward_normal` points out of the surface, the stored normal is
+    // flipped to face the ray when it hits the inside
+    pub fn set_face_normal(&mut self, ray: &Ray, outward_normal: Vec3D) {
+        self.front_face = ray.direction.dot(outward_normal) < 0.0;
+        self.normal = if self.front_face {
+            outward_normal
+        } else {
+            -outward_normal
+        };
+    }
+
+    pub fn outward_normal(&self) -> Vec3D {
+        if self.front_face {
+            self.normal
+        } else {
+            -self.normal
+        }
+    }
 }
@@ -0,0 +1,218 @@
+use super::math::ScalarF;
+
+// piecewise constant density over [0, 1) with one bin per value, sampled
+// by inverting its cumulative distribution (pbrt's Distribution1D)
+#[derive(Debug, Clone)]
+pub struct Distribution1D {
+    pub func: Vec<ScalarF>,
+    pub cdf: Vec<ScalarF>, // one more entry than `func`, from 0 to 1
+    pub func_integral: ScalarF,
+}
+
+impl Distribution1D {
+    pub fn new(values: &[ScalarF]) -> Self {
+        let n = values.len();
+        let func: Vec<ScalarF> = values.iter().map(|v| v.abs()).collect();
+        let mut cdf = vec![0.0; n + 1];
+        for i in 0..n {
+            cdf[i + 1] = cdf[i] + func[i] / n as ScalarF;
+        }
+        let func_integral = cdf[n];
+        for (i, c) in cdf.iter_mut().enumerate() {
+            // all zero, every bin is as likely
+            *c = if func_integral > 0.0 {
+                *c / func_integral
+            } else {
+                i as ScalarF / n as ScalarF
+            };
+        }
+        Self {
+            func,
+            cdf,
+            func_integral,
+        }
+    }
+
+    pub fn count(&self) -> usize {
+        self.func.len()
+    }
+
+    // bin with cdf[i] <= u < cdf[i + 1], zero width bins are never returned
+    fn offset(&self, u: ScalarF) -> usize {
+        self.cdf
+            .partition_point(|&c| c <= u)
+            .saturating_sub(1)
+            .min(self.count() - 1)
+    }
+
+    fn bin_density(&self, offset: usize) -> ScalarF {
+        if self.func_integral > 0.0 {
+            self.func[offset] / self.func_integral
+        } else {
+            0.0
+        }
+    }
+
+    // point in [0, 1), its density and the bin it fell in
+    pub fn sample_continuous(&self, u: ScalarF) -> (ScalarF, ScalarF, usize) {
+        let offset = self.offset(u);
+        let x = (offset as ScalarF + self.remap(u, offset)) / self.count() as ScalarF;
+        (x, self.bin_density(offset), offset)
+    }
+
+    // bin index, its probability and `u` stretched back to [0, 1) within
+    // the bin so it can be used again
+    pub fn sample_discrete(&self, u: ScalarF) -> (usize, ScalarF, ScalarF) {
+        let offset = self.offset(u);
+        (
+            offset,
+            self.bin_density(offset) / self.count() as ScalarF,
+            self.remap(u, offset),
+        )
+    }
+
+    // density of `sample_continuous` returning `x`
+    pub fn pdf(&self, x: ScalarF) -> ScalarF {
+        let offset = ((x * self.count() as ScalarF) as usize).min(self.count() - 1);
+        self.bin_density(offset)
+    }
+
+    fn remap(&self, u: ScalarF, offset: usize) -> ScalarF {
+        let width = self.cdf[offset + 1] - self.cdf[offset];
+        if width > 0.0 {
+            ((u - self.cdf[offset]) / width).clamp(0.0, 1.0 - ScalarF::EPSILON)
+        } else {
+            0.0
+        }
+    }
+}
+
+// density over [0, 1)^2 from a grid of `nu` by `nv` values stored row by
+// row, v picks the row from the marginal and u the column within it
+#[derive(Debug, Clone)]
+pub struct Distribution2D {
+    pub conditionals: Vec<Distribution1D>,
+    pub marginal: Distribution1D,
+}
+
+impl Distribution2D {
+    pub fn new(data: &[ScalarF], nu: usize, nv: usize) -> Self {
+        assert_eq!(data.len(), nu * nv);
+        let conditionals: Vec<Distribution1D> = data.chunks(nu).map(Distribution1D::new).collect();
+        let row_integrals: Vec<ScalarF> = conditionals.iter().map(|c| c.func_integral).collect();
+        Self {
+            conditionals,
+            marginal: Distribution1D::new(&row_integrals),
+        }
+    }
+
+    pub fn sample_continuous(&self, u0: ScalarF, u1: ScalarF) -> ((ScalarF, ScalarF), ScalarF) {
+        let (v, pdf_v, row) = self.marginal.sample_continuous(u1);
+        let (u, pdf_u, _) = self.conditionals[row].sample_continuous(u0);
+        ((u, v), pdf_u * pdf_v)
+    }
+
+    pub fn pdf(&self, u: ScalarF, v: ScalarF) -> ScalarF {
+        let row =
+            ((v * self.conditionals.len() as ScalarF) as usize).min(self.conditionals.len() - 1);
+        self.marginal.pdf(v) * self.conditionals[row].pdf(u)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::tolerance;
+    use crate::sampler::{RandomSampler, Sampler};
+
+    #[test]
+    fn test_distribution_1d() {
+        let values = [1.0, 0.0, 3.0, 2.0, 4.0];
+        let distribution = Distribution1D::new(&values);
+        assert!((distribution.func_integral - 2.0).abs() < tolerance(1e-12));
+        assert_eq!(distribution.cdf.len(), 6);
+        assert!((distribution.cdf[5] - 1.0).abs() < tolerance(1e-12));
+
+        // bins are drawn as often as their value says, checked with a
+        // chi-squared test at the 0.1% level for 3 degrees of freedom
+        crate::rng::init_thread_local_rng(7);
+        let mut sampler = RandomSampler::new(1);
+        let n = 100_000;
+        let mut histogram = [0usize; 5];
+        for _ in 0..n {
+            let (index, probability, u) = distribution.sample_discrete(sampler.get_1d());
+            assert!((probability - values[index] / 10.0).abs() < tolerance(1e-12));
+            assert!((0.0..1.0).contains(&u));
+            histogram[index] += 1;
+        }
+        assert_eq!(histogram[1], 0);
+        let chi_squared: ScalarF = histogram
+            .iter()
+            .zip(&values)
+            .filter(|(_, &value)| value > 0.0)
+            .map(|(&count, &value)| {
+                let expected = n as ScalarF * value / 10.0;
+                (count as ScalarF - expected).powi(2) / expected
+            })
+            .sum();
+        assert!(chi_squared < 16.27, "chi squared: {}", chi_squared);
+
+        // continuous samples carry the density of where they land
+        for _ in 0..1000 {
+            let (x, pdf, offset) = distribution.sample_continuous(sampler.get_1d());
+            assert!((0.0..1.0).contains(&x));
+            assert_eq!(offset, (x * 5.0) as usize);
+            assert!((pdf - distribution.pdf(x)).abs() < tolerance(1e-12));
+            assert!((pdf - values[offset] / 2.0).abs() < tolerance(1e-12));
+        }
+
+        // nothing to sample, it falls back to uniform bins of no density
+        let empty = Distribution1D::new(&[0.0, 0.0]);
+        assert_eq!(empty.sample_discrete(0.75).0, 1);
+        assert_eq!(empty.pdf(0.3), 0.0);
+    }
+
+    // counts of 2d samples landing in each cell of a `n` by `n` grid
+    fn histogram(distribution: &Distribution2D, n: usize, samples: usize) -> Vec<usize> {
+        crate::rng::init_thread_local_rng(8);
+        let mut sampler = RandomSampler::new(1);
+        let mut counts = vec![0; n * n];
+        for _ in 0..samples {
+            let (u0, u1) = sampler.get_2d();
+            let ((u, v), pdf) = distribution.sample_continuous(u0, u1);
+            assert!((pdf - distribution.pdf(u, v)).abs() < tolerance(1e-9) * pdf.max(1.0));
+            let (x, y) = ((u * n as ScalarF) as usize, (v * n as ScalarF) as usize);
+            counts[y * n + x] += 1;
+        }
+        counts
+    }
+
+    #[test]
+    fn test_distribution_2d() {
+        // a solid panorama covers the square evenly, chi-squared at the
+        // 0.1% level for 99 degrees of freedom
+        let solid = Distribution2D::new(&[0.5; 40 * 20], 40, 20);
+        let samples = 100_000;
+        let expected = samples as ScalarF / 100.0;
+        let chi_squared: ScalarF = histogram(&solid, 10, samples)
+            .iter()
+            .map(|&count| (count as ScalarF - expected).powi(2) / expected)
+            .sum();
+        assert!(chi_squared < 148.2, "chi squared: {}", chi_squared);
+        assert!((solid.pdf(0.3, 0.7) - 1.0).abs() < tolerance(1e-12));
+
+        // a single bright pixel takes nearly all of the samples
+        let (nu, nv) = (20, 10);
+        let mut data = vec![1e-3; nu * nv];
+        data[3 * nu + 17] = 1e4;
+        let bright = Distribution2D::new(&data, nu, nv);
+        let counts = histogram(&bright, 10, 10_000);
+        // pixel (17, 3) lies in cell (8, 3) of the 10 by 10 grid
+        assert!(counts[3 * 10 + 8] as ScalarF > 0.99 * 10_000.0);
+        let pixel_pdf = bright.pdf(17.5 / nu as ScalarF, 3.5 / nv as ScalarF);
+        assert!(pixel_pdf / (nu * nv) as ScalarF > 0.99);
+
+        let black = Distribution2D::new(&[0.0; 6], 3, 2);
+        assert_eq!(black.pdf(0.5, 0.5), 0.0);
+    }
+}
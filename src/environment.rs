@@ -0,0 +1,169 @@
+use super::light::EnvironmentSample;
+use super::math::consts::PI;
+use super::math::{ScalarF, Vec3D};
+use super::sampler::Sampler;
+use cgmath::{InnerSpace, VectorSpace};
+use serde::Deserialize;
+use std::fmt::Debug;
+
+// radiance arriving from directions in which rays leave the scene. maps
+// that are importance sampled also give next event estimation directions,
+// the others are only seen by escaping rays
+pub trait Environment: Send + Sync + Debug {
+    fn sample_direction(&self, direction: Vec3D) -> Vec3D;
+
+    // solid angle density of `sample_li`, 0 where it never samples
+    fn pdf(&self, _direction: Vec3D) -> ScalarF {
+        0.0
+    }
+
+    fn sample_li(&self, _sampler: &mut dyn Sampler) -> Option<EnvironmentSample> {
+        None
+    }
+}
+
+// equirectangular radiance map in the layout of `HdrEnvironmentLight`,
+// filtered bilinearly. it is not sampled as a light
+#[derive(Debug)]
+pub struct HdrEnvironment {
+    pub image: Vec<Vec3D>, // row major, the first row looks up
+    pub width: u32,
+    pub height: u32,
+    pub rotation_deg: ScalarF, // around +y
+}
+
+#[derive(Deserialize)]
+pub struct HdrEnvironmentConfig {
+    pub file: String,
+    pub rotation_deg: Option<f64>,
+}
+
+impl HdrEnvironment {
+    pub fn load(path: &str, rotation_deg: ScalarF) -> Result<Self, String> {
+        let (image, width, height) = load_radiance_image(path)?;
+        Ok(Self {
+            image,
+            width,
+            height,
+            rotation_deg,
+        })
+    }
+
+    fn pixel(&self, x: usize, y: usize) -> Vec3D {
+        self.image[y * self.width as usize + x]
+    }
+}
+
+impl Environment for HdrEnvironment {
+    fn sample_direction(&self, direction: Vec3D) -> Vec3D {
+        let (u, v) = direction_to_uv(direction, self.rotation_deg);
+        let (w, h) = (self.width as usize, self.height as usize);
+        // between the pixel centres, wrapping around in azimuth and clamped
+        // at the poles
+        let x = (u * w as ScalarF - 0.5).rem_euclid(w as ScalarF);
+        let y = (v * h as ScalarF - 0.5).clamp(0.0, (h - 1) as ScalarF);
+        let (x0, y0) = (x as usize % w, y as usize);
+        let (x1, y1) = ((x0 + 1) % w, (y0 + 1).min(h - 1));
+        let (fx, fy) = (x - x.floor(), y - y.floor());
+        let top = self.pixel(x0, y0).lerp(self.pixel(x1, y0), fx);
+        let bottom = self.pixel(x0, y1).lerp(self.pixel(x1, y1), fx);
+        top.lerp(bottom, fy)
+    }
+}
+
+impl HdrEnvironmentConfig {
+    pub fn to_environment(&self) -> Result<HdrEnvironment, String> {
+        HdrEnvironment::load(&self.file, self.rotation_deg.unwrap_or(0.0) as ScalarF)
+    }
+}
+
+// u sweeps the azimuth starting at -x and v the polar angle from +y, the
+// same layout the panoramic camera renders
+pub fn direction_to_uv(direction: Vec3D, rotation_deg: ScalarF) -> (ScalarF, ScalarF) {
+    let direction = direction.normalize();
+    let theta = direction.y.clamp(-1.0, 1.0).acos();
+    let phi = direction.z.atan2(-direction.x) - rotation_deg.to_radians();
+    (phi.rem_euclid(2.0 * PI) / (2.0 * PI), theta / PI)
+}
+
+// radiance .hdr files are decoded directly since going through
+// DynamicImage clamps them to ldr, other formats are taken as they load.
+// returns the row major pixels with the width and height
+pub fn load_radiance_image(path: &str) -> Result<(Vec<Vec3D>, u32, u32), String> {
+    let error = |e: image::ImageError| format!("Failed to load environment map {}: {}", path, e);
+    let (pixels, width, height): (Vec<[f32; 3]>, u32, u32) = if path.ends_with(".hdr") {
+        let file = std::fs::File::open(path).map_err(|e| error(e.into()))?;
+        let decoder =
+            image::codecs::hdr::HdrDecoder::new(std::io::BufReader::new(file)).map_err(error)?;
+        let metadata = decoder.metadata();
+        let pixels = decoder.read_image_hdr().map_err(error)?;
+        (
+            pixels.into_iter().map(|p| p.0).collect(),
+            metadata.width,
+            metadata.height,
+        )
+    } else {
+        let image = image::open(path).map_err(error)?.to_rgb32f();
+        let (width, height) = image.dimensions();
+        (image.pixels().map(|p| p.0).collect(), width, height)
+    };
+    let image = pixels
+        .iter()
+        .map(|p| Vec3D::new(p[0] as ScalarF, p[1] as ScalarF, p[2] as ScalarF))
+        .collect();
+    Ok((image, width, height))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::{tolerance, vec3_approx_eq};
+
+    #[test]
+    fn test_hdr_environment_bilinear() {
+        // 4 x 2 pixels, red grows along each row and green down the columns
+        let image = (0..8)
+            .map(|i| Vec3D::new((i % 4) as ScalarF, (i / 4) as ScalarF, 1.0))
+            .collect();
+        let environment = HdrEnvironment {
+            image,
+            width: 4,
+            height: 2,
+            rotation_deg: 0.0,
+        };
+        let at = |u: ScalarF, v: ScalarF| {
+            let phi = 2.0 * PI * u;
+            let theta = PI * v;
+            environment.sample_direction(Vec3D::new(
+                -theta.sin() * phi.cos(),
+                theta.cos(),
+                theta.sin() * phi.sin(),
+            ))
+        };
+
+        // pixel centres give the pixels, the middle between them averages
+        assert!(vec3_approx_eq(
+            at(0.375, 0.25),
+            Vec3D::new(1.0, 0.0, 1.0),
+            tolerance(1e-9)
+        ));
+        assert!(vec3_approx_eq(
+            at(0.5, 0.5),
+            Vec3D::new(1.5, 0.5, 1.0),
+            tolerance(1e-9)
+        ));
+        // the seam at u = 0 blends the last and first columns
+        assert!(vec3_approx_eq(
+            at(0.0, 0.75),
+            Vec3D::new(1.5, 1.0, 1.0),
+            tolerance(1e-9)
+        ));
+        // rows are clamped towards the poles
+        assert!(vec3_approx_eq(
+            at(0.125, 0.01),
+            Vec3D::new(0.0, 0.0, 1.0),
+            tolerance(1e-9)
+        ));
+        assert_eq!(environment.pdf(Vec3D::unit_y()), 0.0);
+    }
+}
@@ -0,0 +1,53 @@
+use std::fmt;
+
+// errors caused by the user's input, reported instead of panicking
+#[derive(Debug)]
+pub enum RaytracerError {
+    ConfigParse(String),
+    SceneLoad(String),
+    MeshLoad(String),
+    UnsupportedFormat(String),
+    Output(String), // writing the rendered image failed
+}
+
+impl fmt::Display for RaytracerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RaytracerError::ConfigParse(message) => write!(f, "Invalid config: {}", message),
+            RaytracerError::SceneLoad(message) => write!(f, "Failed to load scene: {}", message),
+            RaytracerError::MeshLoad(message) => write!(f, "Failed to load mesh: {}", message),
+            RaytracerError::UnsupportedFormat(message) => {
+                write!(f, "Unsupported format: {}", message)
+            }
+            RaytracerError::Output(message) => write!(f, "Failed to write output: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for RaytracerError {}
+
+impl From<toml::de::Error> for RaytracerError {
+    fn from(error: toml::de::Error) -> Self {
+        RaytracerError::ConfigParse(error.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scene::SceneConfig;
+
+    #[test]
+    fn test_missing_field_is_config_parse_error() {
+        let error: RaytracerError = toml::from_str::<SceneConfig>("objects = []")
+            .err()
+            .unwrap()
+            .into();
+        assert!(matches!(error, RaytracerError::ConfigParse(_)));
+        let message = error.to_string();
+        assert!(message.contains("camera"), "{}", message);
+
+        let error = crate::shapes::load_mesh("scene.fbx").unwrap_err();
+        assert!(matches!(error, RaytracerError::UnsupportedFormat(_)));
+    }
+}
@@ -0,0 +1,523 @@
+use super::distribution::Distribution2D;
+use super::environment::{direction_to_uv, load_radiance_image, Environment};
+use super::error::RaytracerError;
+use super::math::consts::PI;
+use super::math::{Aabb, Point3D, Point3DConfig, ScalarF, Vec3D, Vec3DConfig};
+use super::sampler::Sampler;
+use cgmath::InnerSpace;
+use serde::Deserialize;
+use std::sync::Arc;
+
+pub struct EnvironmentSample {
+    pub direction: Vec3D,
+    pub radiance: Vec3D,
+    pub pdf: ScalarF, // solid angle density
+}
+
+// light arriving at a reference point, `direction` points towards the light
+pub struct LightSample {
+    pub direction: Vec3D,
+    pub distance: ScalarF, // infinite for lights at infinity
+    pub radiance: Vec3D,
+    pub pdf: ScalarF, // solid angle density, 1 for delta lights
+}
+
+// analytic lights have no surface, rays never hit them by chance so they only
+// contribute through next event estimation
+pub trait Light: Send + Sync {
+    fn sample_li(&self, ref_point: Point3D, sampler: &mut dyn Sampler) -> LightSample;
+    fn power(&self) -> Vec3D; // total emitted flux, weights the choice among lights
+}
+
+#[derive(Debug)]
+pub struct PointLight {
+    pub position: Point3D,
+    pub intensity: Vec3D,
+}
+
+#[derive(Deserialize)]
+pub struct PointLightConfig {
+    pub position: Point3DConfig,
+    pub intensity: Vec3DConfig,
+}
+
+impl Light for PointLight {
+    fn sample_li(&self, ref_point: Point3D, _sampler: &mut dyn Sampler) -> LightSample {
+        let w = self.position - ref_point;
+        let distance2 = w.magnitude2();
+        let distance = distance2.sqrt();
+        LightSample {
+            direction: w / distance,
+            distance,
+            radiance: self.intensity / distance2,
+            pdf: 1.0,
+        }
+    }
+
+    fn power(&self) -> Vec3D {
+        4.0 * PI * self.intensity
+    }
+}
+
+// point light restricted to a cone around `direction`, fading out between
+// the inner and the outer cone
+#[derive(Debug)]
+pub struct SpotLight {
+    pub position: Point3D,
+    pub direction: Vec3D, // axis of the cone, pointing away from the light
+    pub intensity: Vec3D,
+    pub inner_cone_deg: ScalarF,
+    pub outer_cone_deg: ScalarF,
+}
+
+#[derive(Deserialize)]
+pub struct SpotLightConfig {
+    pub position: Point3DConfig,
+    pub direction: Vec3DConfig,
+    pub intensity: Vec3DConfig,
+    pub inner_cone_deg: f64,
+    pub outer_cone_deg: f64,
+}
+
+impl SpotLight {
+    // 1 inside the inner cone, 0 outside the outer one and a quintic
+    // smoothstep in between, `w` points away from the light
+    fn falloff(&self, w: Vec3D) -> ScalarF {
+        let cos_theta = self.direction.dot(w);
+        let cos_inner = self.inner_cone_deg.to_radians().cos();
+        let cos_outer = self.outer_cone_deg.to_radians().cos();
+        if cos_theta >= cos_inner {
+            return 1.0;
+        }
+        if cos_theta <= cos_outer {
+            return 0.0;
+        }
+        let t = (cos_theta - cos_outer) / (cos_inner - cos_outer);
+        t * t * t * (t * (6.0 * t - 15.0) + 10.0)
+    }
+}
+
+impl Light for SpotLight {
+    fn sample_li(&self, ref_point: Point3D, _sampler: &mut dyn Sampler) -> LightSample {
+        let w = self.position - ref_point;
+        let distance2 = w.magnitude2();
+        let distance = distance2.sqrt();
+        let direction = w / distance;
+        LightSample {
+            direction,
+            distance,
+            radiance: self.intensity * (self.falloff(-direction) / distance2),
+            pdf: 1.0,
+        }
+    }
+
+    fn power(&self) -> Vec3D {
+        // the falloff region is counted at half intensity
+        let cos_inner = self.inner_cone_deg.to_radians().cos();
+        let cos_outer = self.outer_cone_deg.to_radians().cos();
+        2.0 * PI * (1.0 - 0.5 * (cos_inner + cos_outer)) * self.intensity
+    }
+}
+
+// parallel light coming from infinitely far away, e.g. the sun
+#[derive(Debug)]
+pub struct DirectionalLight {
+    pub direction: Vec3D, // the light travels along it
+    pub irradiance: Vec3D,
+    pub world_radius: ScalarF, // of a sphere bounding the scene, for the power
+}
+
+#[derive(Deserialize)]
+pub struct DirectionalLightConfig {
+    pub direction: Vec3DConfig,
+    pub irradiance: Vec3DConfig,
+}
+
+impl Light for DirectionalLight {
+    fn sample_li(&self, _ref_point: Point3D, _sampler: &mut dyn Sampler) -> LightSample {
+        LightSample {
+            direction: -self.direction,
+            distance: ScalarF::INFINITY,
+            radiance: self.irradiance,
+            pdf: 1.0,
+        }
+    }
+
+    fn power(&self) -> Vec3D {
+        // everything crossing the disk the scene casts its shadow on
+        PI * self.world_radius * self.world_radius * self.irradiance
+    }
+}
+
+// running sums of `values` normalized to end at 1, None if they are all zero
+pub fn build_cdf(values: &[ScalarF]) -> Option<Vec<ScalarF>> {
+    let mut cdf = Vec::with_capacity(values.len());
+    let mut sum = 0.0;
+    for value in values {
+        sum += value;
+        cdf.push(sum);
+    }
+    if sum <= 0.0 {
+        return None;
+    }
+    cdf.iter_mut().for_each(|c| *c /= sum);
+    Some(cdf)
+}
+
+pub fn sample_cdf(cdf: &[ScalarF], u: ScalarF) -> usize {
+    cdf.partition_point(|&c| c <= u).min(cdf.len() - 1)
+}
+
+// equirectangular radiance map around the scene, u sweeps the azimuth
+// starting at -x and v the polar angle from +y, the same layout the
+// panoramic camera renders. directions are importance sampled by luminance
+#[derive(Debug)]
+pub struct HdrEnvironmentLight {
+    pub image: Vec<Vec3D>, // row major, the first row looks up
+    pub width: u32,
+    pub height: u32,
+    pub rotation_deg: ScalarF, // around +y
+
+    // over the pixels by luminance * sin(theta), empty for a black image
+    distribution: Distribution2D,
+}
+
+#[derive(Deserialize)]
+pub struct HdrEnvironmentLightConfig {
+    pub file: String,
+    pub rotation_deg: Option<f64>,
+}
+
+pub fn luminance(color: Vec3D) -> ScalarF {
+    0.2126 * color.x + 0.7152 * color.y + 0.0722 * color.z
+}
+
+impl HdrEnvironmentLight {
+    pub fn new(image: Vec<Vec3D>, width: u32, height: u32, rotation_deg: ScalarF) -> Self {
+        assert_eq!(image.len(), (width * height) as usize);
+        let (w, h) = (width as usize, height as usize);
+
+        let weights: Vec<ScalarF> = (0..w * h)
+            .map(|i| {
+                let theta = PI * ((i / w) as ScalarF + 0.5) / h as ScalarF;
+                luminance(image[i]).max(0.0) * theta.sin()
+            })
+            .collect();
+
+        Self {
+            distribution: Distribution2D::new(&weights, w, h),
+            image,
+            width,
+            height,
+            rotation_deg,
+        }
+    }
+
+    pub fn load(path: &str, rotation_deg: ScalarF) -> Result<Self, String> {
+        let (image, width, height) = load_radiance_image(path)?;
+        Ok(Self::new(image, width, height, rotation_deg))
+    }
+
+    fn direction_to_uv(&self, direction: Vec3D) -> (ScalarF, ScalarF) {
+        direction_to_uv(direction, self.rotation_deg)
+    }
+
+    fn uv_to_direction(&self, u: ScalarF, v: ScalarF) -> Vec3D {
+        let phi = 2.0 * PI * u + self.rotation_deg.to_radians();
+        let theta = PI * v;
+        Vec3D::new(
+            -theta.sin() * phi.cos(),
+            theta.cos(),
+            theta.sin() * phi.sin(),
+        )
+    }
+
+    fn pixel_index(&self, u: ScalarF, v: ScalarF) -> usize {
+        let x = ((u * self.width as ScalarF) as usize).min(self.width as usize - 1);
+        let y = ((v * self.height as ScalarF) as usize).min(self.height as usize - 1);
+        y * self.width as usize + x
+    }
+}
+
+// radiance is looked up without filtering so that it stays constant over
+// the pixels the sampling distribution is built from
+impl Environment for HdrEnvironmentLight {
+    fn sample_direction(&self, direction: Vec3D) -> Vec3D {
+        let (u, v) = self.direction_to_uv(direction);
+        self.image[self.pixel_index(u, v)]
+    }
+
+    fn pdf(&self, direction: Vec3D) -> ScalarF {
+        let (u, v) = self.direction_to_uv(direction);
+        // from the direction, with f32 math PI * v may round past pi
+        let direction = direction.normalize();
+        let sin_theta = direction.x.hypot(direction.z);
+        if sin_theta <= 0.0 {
+            return 0.0;
+        }
+        // density over the unit square, then over the sphere
+        self.distribution.pdf(u, v) / (2.0 * PI * PI * sin_theta)
+    }
+
+    fn sample_li(&self, sampler: &mut dyn Sampler) -> Option<EnvironmentSample> {
+        if self.distribution.marginal.func_integral <= 0.0 {
+            return None;
+        }
+        let (u0, u1) = sampler.get_2d();
+        let ((u, v), _) = self.distribution.sample_continuous(u0, u1);
+        let direction = self.uv_to_direction(u, v);
+        let pdf = self.pdf(direction);
+        if pdf <= 0.0 {
+            return None;
+        }
+        Some(EnvironmentSample {
+            direction,
+            radiance: self.sample_direction(direction),
+            pdf,
+        })
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type")]
+pub enum LightConfig {
+    HdrEnvironment(HdrEnvironmentLightConfig),
+    Point(PointLightConfig),
+    Directional(DirectionalLightConfig),
+    Spot(SpotLightConfig),
+}
+
+impl LightConfig {
+    pub fn to_environment(&self) -> Result<Option<Arc<HdrEnvironmentLight>>, RaytracerError> {
+        match self {
+            LightConfig::HdrEnvironment(config) => Ok(Some(Arc::new(
+                HdrEnvironmentLight::load(
+                    &config.file,
+                    config.rotation_deg.unwrap_or(0.0) as ScalarF,
+                )
+                .map_err(RaytracerError::SceneLoad)?,
+            ))),
+            _ => Ok(None),
+        }
+    }
+
+    // `world_bounds` encloses the finite objects of the scene
+    pub fn to_light(&self, world_bounds: &Aabb) -> Option<Arc<dyn Light>> {
+        match self {
+            LightConfig::HdrEnvironment(_) => None,
+            LightConfig::Point(config) => Some(Arc::new(PointLight {
+                position: config.position.to_point(),
+                intensity: config.intensity.to_vec3(),
+            })),
+            LightConfig::Spot(config) => Some(Arc::new(SpotLight {
+                position: config.position.to_point(),
+                direction: config.direction.to_vec3().normalize(),
+                intensity: config.intensity.to_vec3(),
+                inner_cone_deg: config.inner_cone_deg as ScalarF,
+                outer_cone_deg: config.outer_cone_deg.max(config.inner_cone_deg) as ScalarF,
+            })),
+            LightConfig::Directional(config) => {
+                let world_radius = if world_bounds.is_finite() {
+                    (world_bounds.max - world_bounds.min).magnitude() * 0.5
+                } else {
+                    1.0
+                };
+                Some(Arc::new(DirectionalLight {
+                    direction: config.direction.to_vec3().normalize(),
+                    irradiance: config.irradiance.to_vec3(),
+                    world_radius,
+                }))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::{tolerance, vec3_approx_eq};
+    use crate::sampler::RandomSampler;
+    use rand::Rng;
+
+    fn test_environment(rotation_deg: ScalarF) -> HdrEnvironmentLight {
+        let mut rng = rand::thread_rng();
+        let (width, height) = (32, 16);
+        let mut image: Vec<Vec3D> = (0..width * height)
+            .map(|_| Vec3D::new(rng.gen(), rng.gen(), rng.gen()))
+            .collect();
+        image[5 * width as usize + 20] = Vec3D::new(200.0, 150.0, 100.0); // the sun
+        HdrEnvironmentLight::new(image, width, height, rotation_deg)
+    }
+
+    #[test]
+    fn test_point_light() {
+        let light = PointLight {
+            position: Point3D::new(1.0, 4.0, -2.0),
+            intensity: Vec3D::new(8.0, 4.0, 2.0),
+        };
+        let mut sampler = RandomSampler::new(1);
+        let sample = light.sample_li(Point3D::new(1.0, 2.0, -2.0), &mut sampler);
+        assert!(vec3_approx_eq(
+            sample.direction,
+            Vec3D::new(0.0, 1.0, 0.0),
+            tolerance(1e-9)
+        ));
+        assert!((sample.distance - 2.0).abs() < tolerance(1e-9));
+        assert!(vec3_approx_eq(
+            sample.radiance,
+            Vec3D::new(2.0, 1.0, 0.5),
+            tolerance(1e-9)
+        ));
+        assert!(vec3_approx_eq(
+            light.power(),
+            4.0 * PI * light.intensity,
+            tolerance(1e-9)
+        ));
+    }
+
+    #[test]
+    fn test_spot_light() {
+        let light = SpotLight {
+            position: Point3D::new(0.0, 2.0, 0.0),
+            direction: Vec3D::new(0.0, -1.0, 0.0),
+            intensity: Vec3D::new(4.0, 4.0, 4.0),
+            inner_cone_deg: 30.0,
+            outer_cone_deg: 45.0,
+        };
+        let mut sampler = RandomSampler::new(1);
+        // point on the floor seen from the light at `angle_deg` off the axis
+        let at = |angle_deg: ScalarF| Point3D::new(2.0 * angle_deg.to_radians().tan(), 0.0, 0.0);
+        let mut irradiance = |angle_deg: ScalarF| {
+            let ref_point = at(angle_deg);
+            let sample = light.sample_li(ref_point, &mut sampler);
+            assert!(vec3_approx_eq(
+                sample.direction,
+                (light.position - ref_point).normalize(),
+                tolerance(1e-9)
+            ));
+            assert!(
+                (sample.distance - (light.position - ref_point).magnitude()).abs()
+                    < tolerance(1e-9)
+            );
+            sample.radiance.x * sample.distance * sample.distance
+        };
+
+        assert!((irradiance(0.0) - 4.0).abs() < tolerance(1e-9));
+        assert!((irradiance(30.0) - 4.0).abs() < tolerance(1e-9));
+        let fading = irradiance(37.5);
+        assert!(fading > 0.0 && fading < 4.0);
+        assert!(irradiance(45.0).abs() < tolerance(1e-9));
+        assert!(irradiance(60.0).abs() < tolerance(1e-9));
+    }
+
+    #[test]
+    fn test_directional_light() {
+        let config: LightConfig = toml::from_str(
+            r#"
+            type = "Directional"
+            direction = { x = 0.0, y = -2.0, z = 0.0 }
+            irradiance = { x = 3.0, y = 3.0, z = 3.0 }
+            "#,
+        )
+        .unwrap();
+        let bounds = Aabb::new(Point3D::new(-1.0, -1.0, -1.0), Point3D::new(1.0, 1.0, 1.0));
+        let light = config.to_light(&bounds).unwrap();
+        let mut sampler = RandomSampler::new(1);
+        for ref_point in [Point3D::new(0.0, 0.0, 0.0), Point3D::new(5.0, -3.0, 1.0)] {
+            let sample = light.sample_li(ref_point, &mut sampler);
+            assert!(vec3_approx_eq(
+                sample.direction,
+                Vec3D::new(0.0, 1.0, 0.0),
+                tolerance(1e-9)
+            ));
+            assert!(sample.distance.is_infinite());
+            assert!(vec3_approx_eq(
+                sample.radiance,
+                Vec3D::new(3.0, 3.0, 3.0),
+                tolerance(1e-9)
+            ));
+        }
+        // radius sqrt(3) around the unit cube
+        assert!((light.power().x - 9.0 * PI).abs() < tolerance(1e-9));
+    }
+
+    #[test]
+    fn test_environment_directions() {
+        for rotation_deg in [0.0, 37.0] {
+            let environment = test_environment(rotation_deg);
+            for (u, v) in [(0.1, 0.2), (0.5, 0.5), (0.9, 0.7)] {
+                let direction = environment.uv_to_direction(u, v);
+                let (u2, v2) = environment.direction_to_uv(direction);
+                assert!((u - u2).abs() < tolerance(1e-9) && (v - v2).abs() < tolerance(1e-9));
+            }
+        }
+
+        let environment = test_environment(0.0);
+        assert!(vec3_approx_eq(
+            environment.uv_to_direction(0.0, 0.5),
+            Vec3D::new(-1.0, 0.0, 0.0),
+            tolerance(1e-9)
+        ));
+        assert_eq!(
+            environment.sample_direction(Vec3D::new(0.0, 1.0, 1e-3)),
+            environment.image[(environment.width / 4) as usize]
+        );
+    }
+
+    #[test]
+    fn test_environment_importance_sampling() {
+        let environment = test_environment(20.0);
+        let mut sampler = RandomSampler::new(1);
+        let n = 100_000;
+
+        let mut sampled = Vec3D::new(0.0, 0.0, 0.0);
+        for _ in 0..n {
+            let sample = environment.sample_li(&mut sampler).unwrap();
+            assert!(
+                (sample.pdf - environment.pdf(sample.direction)).abs()
+                    < tolerance(1e-9) * sample.pdf
+            );
+            sampled += sample.radiance / sample.pdf;
+        }
+        sampled /= n as ScalarF;
+
+        // total incoming radiance integrated exactly over the pixels
+        let mut expected = Vec3D::new(0.0, 0.0, 0.0);
+        let (w, h) = (environment.width as usize, environment.height as usize);
+        for y in 0..h {
+            let theta0 = PI * y as ScalarF / h as ScalarF;
+            let theta1 = PI * (y + 1) as ScalarF / h as ScalarF;
+            let solid_angle = 2.0 * PI / w as ScalarF * (theta0.cos() - theta1.cos());
+            for x in 0..w {
+                expected += environment.image[y * w + x] * solid_angle;
+            }
+        }
+
+        assert!(
+            vec3_approx_eq(sampled, expected, 0.02 * expected.magnitude()),
+            "sampled: {:?}, expected: {:?}",
+            sampled,
+            expected
+        );
+    }
+
+    #[test]
+    fn test_load_hdr_environment() {
+        let path = std::env::temp_dir().join("rust_ray_tracer_test_environment.hdr");
+        let pixels: Vec<image::Rgb<f32>> =
+            (0..8).map(|i| image::Rgb([i as f32, 0.5, 2.0])).collect();
+        let file = std::fs::File::create(&path).unwrap();
+        image::codecs::hdr::HdrEncoder::new(file)
+            .encode(&pixels, 4, 2)
+            .unwrap();
+
+        let environment = HdrEnvironmentLight::load(path.to_str().unwrap(), 0.0).unwrap();
+        assert_eq!((environment.width, environment.height), (4, 2));
+        assert!(vec3_approx_eq(
+            environment.image[3],
+            Vec3D::new(3.0, 0.5, 2.0),
+            1e-2
+        ));
+        std::fs::remove_file(&path).unwrap();
+    }
+}
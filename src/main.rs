@@ -1,17 +1,28 @@
+mod accel;
 mod camera;
 mod common;
+mod distribution;
+mod environment;
+mod error;
+mod light;
 mod material;
 mod math;
 mod object;
+mod output;
 mod renderer;
+mod rng;
 mod sampler;
 mod scene;
 mod shapes;
+mod sobol_matrices;
+mod texture;
 mod tracers;
+mod volume;
 
 use clap::Parser;
-use log::info;
-use renderer::{render, RenderConfig};
+use error::RaytracerError;
+use log::{error, info};
+use renderer::{render, render_layers, render_linear, RenderConfig};
 use scene::{Scene, SceneConfig};
 use std::fs;
 
@@ -38,14 +49,62 @@ fn main() {
 
     info!("RustRayTracer started.");
     let args = Args::parse();
+    if let Err(error) = run(&args) {
+        error!("{}", error);
+        eprintln!("Error: {}", error);
+        std::process::exit(1);
+    }
+}
+
+fn read_config<T: serde::de::DeserializeOwned>(path: &str) -> Result<T, RaytracerError> {
+    let text = fs::read_to_string(path)
+        .map_err(|e| RaytracerError::ConfigParse(format!("cannot read {}: {}", path, e)))?;
+    toml::from_str(&text).map_err(|e| RaytracerError::ConfigParse(format!("{}: {}", path, e)))
+}
+
+fn run(args: &Args) -> Result<(), RaytracerError> {
+    let render_config: RenderConfig = read_config(&args.render_config)?;
+    let scene_config: SceneConfig = read_config(&args.scene_config)?;
+    let scene = Scene::from_config(&scene_config)?;
+    let output_error = |e: image::ImageError| RaytracerError::Output(e.to_string());
+    let (width, height) = (render_config.image.width, render_config.image.height);
+    if args.output.ends_with(".exr") {
+        // enabled passes become layers of the same file
+        if render_config.has_passes() {
+            let layers = render_layers(&render_config, &scene);
+            output::save_multilayer_exr(&args.output, &layers, width, height)
+        } else {
+            let pixels = render_linear(&render_config, &scene);
+            output::save_exr(&args.output, &pixels, width, height)
+        }
+        .map_err(RaytracerError::Output)?;
+    } else {
+        let result = render(&render_config, &scene);
+        result.image.save(&args.output).map_err(output_error)?;
 
-    let render_config: RenderConfig =
-        toml::from_str(&fs::read_to_string(args.render_config).unwrap())
-            .expect("Failed to parse render config file");
-    let scene_config: SceneConfig = toml::from_str(&fs::read_to_string(args.scene_config).unwrap())
-        .expect("Failed to parse scene config file");
-    let scene = Scene::from_config(&scene_config);
-    let img = render(&render_config, &scene);
-    img.save(&args.output).unwrap();
+        // passes are saved next to the image, e.g. out_albedo.png for out.png.
+        // exr output holds them as layers instead
+        let (stem, extension) = args
+            .output
+            .rsplit_once('.')
+            .unwrap_or((args.output.as_str(), "png"));
+        let pass_path = |name: &str| format!("{}_{}.{}", stem, name, extension);
+        if let Some(albedo) = result.albedo {
+            albedo.save(pass_path("albedo")).map_err(output_error)?;
+        }
+        if let Some(normal) = result.normal {
+            normal.save(pass_path("normal")).map_err(output_error)?;
+        }
+        if let Some(depth) = result.depth {
+            depth.save(pass_path("depth")).map_err(output_error)?;
+        }
+        if let Some(variance) = result.variance {
+            variance.save(pass_path("variance")).map_err(output_error)?;
+        }
+        if let Some(sample_count) = result.sample_count {
+            sample_count.save(pass_path("spp")).map_err(output_error)?;
+        }
+    }
     info!("Image saved to {}.", args.output);
+    Ok(())
 }
@@ -1,21 +1,29 @@
+use super::common::HitRecord;
+use super::error::RaytracerError;
+use super::light::luminance;
+use super::math::consts::{FRAC_1_PI, PI};
 use super::math::{
-    fresnel, reflect, refract, spherical_to_world, Point3D, Ray, Vec3D, Vec3DConfig,
+    compute_tbn, fresnel, reflect, refract, spawn_ray, spherical_to_world, thin_film_reflectance,
+    transform_vec3, Onb, Ray, ScalarF, Vec3D, Vec3DConfig,
 };
 use super::sampler::Sampler;
-use cgmath::{Array, InnerSpace, Zero};
+use super::texture::{
+    ConstantTexture, ImageTexture, Texture, TextureConfig, TextureContext, TextureOrValue,
+    TextureOrValueConfig,
+};
+use cgmath::{Array, ElementWise, InnerSpace, VectorSpace, Zero};
 use log::warn;
 use serde::Deserialize;
-use std::f64::consts::{FRAC_1_PI, PI};
 use std::fmt::Debug;
 use std::sync::Arc;
 
 pub struct ScatterResult {
     pub ray: Ray,
-    pub pdf: f64,
+    pub pdf: ScalarF,
 }
 
 impl ScatterResult {
-    pub fn new(ray: Ray, pdf: f64) -> Self {
+    pub fn new(ray: Ray, pdf: ScalarF) -> Self {
         Self { ray, pdf }
     }
 }
@@ -24,15 +32,76 @@ pub trait Material: Sync + Send + Debug {
     fn scatter(
         &self,
         ray_in: &Ray,
-        hit_point: Point3D,
-        normal: Vec3D,
+        hit: &HitRecord,
         sampler: &mut dyn Sampler,
     ) -> Option<ScatterResult>;
 
-    fn bxdf(&self, ray_in: &Ray, ray_out: &Ray, hit_point: Point3D, normal: Vec3D) -> Vec3D;
+    fn bxdf(&self, ray_in: &Ray, ray_out: &Ray, hit: &HitRecord) -> Vec3D;
+
+    // solid angle density of `scatter` producing `ray_out`, zero for specular materials
+    fn pdf(&self, ray_in: &Ray, ray_out: &Ray, hit: &HitRecord) -> ScalarF;
+
+    // whether scattering happens in discrete directions only
+    fn is_specular(&self) -> bool {
+        false
+    }
+
+    // every discrete direction a specular material scatters `ray_in` into,
+    // lets deterministic tracers follow all of them instead of sampling one.
+    // empty when the material doesn't know them
+    fn specular_directions(&self, _ray_in: &Ray, _hit: &HitRecord) -> Vec<Vec3D> {
+        Vec::new()
+    }
+
     fn emission(&self) -> Vec3D {
         Vec3D::zero()
     }
+    // radiance emitted back along `ray_in` where it hit the surface, only
+    // differs from `emission` for materials that tell the sides apart
+    fn emitted(&self, _ray_in: &Ray, _hit: &HitRecord) -> Vec3D {
+        self.emission()
+    }
+
+    // index of refraction of the inside for materials that refract, lets
+    // tracers keep track of the media rays travel through
+    fn ior(&self) -> Option<ScalarF> {
+        None
+    }
+}
+
+// normal on the side of the incoming ray
+fn facing_normal(ray_in: &Ray, normal: Vec3D) -> Vec3D {
+    if ray_in.direction.dot(normal) > 0.0 {
+        -normal
+    } else {
+        normal
+    }
+}
+
+// whether both rays are on the same side of the surface, i.e. a reflection
+fn same_hemisphere(ray_in: &Ray, ray_out: &Ray, normal: Vec3D) -> bool {
+    ray_in.direction.dot(normal) * ray_out.direction.dot(normal) < 0.0
+}
+
+fn cosine_hemisphere_pdf(ray_out: &Ray, normal: Vec3D) -> ScalarF {
+    ray_out.direction.normalize().dot(normal).max(0.0) * FRAC_1_PI
+}
+
+// cosine weighted direction around the normal on the side of `ray_in`
+fn cosine_hemisphere_scatter(
+    ray_in: &Ray,
+    hit: &HitRecord,
+    sampler: &mut dyn Sampler,
+) -> Option<ScatterResult> {
+    let normal = facing_normal(ray_in, hit.normal);
+    let (u, v) = sampler.get_2d();
+    let theta = (1.0 - u).sqrt().acos();
+    let phi = 2.0 * PI * v;
+
+    let new_direction = spherical_to_world(theta, phi, normal);
+    let new_ray = spawn_ray(hit.p, hit.normal, new_direction);
+    let pdf = new_direction.dot(normal) * FRAC_1_PI;
+    Some(ScatterResult::new(new_ray, pdf))
 }
 
 #[derive(Debug, Clone)]
@@ -41,14 +110,18 @@ pub struct Emissive {
 }
 
 impl Material for Emissive {
-    fn scatter(&self, _: &Ray, _: Point3D, _: Vec3D, _: &mut dyn Sampler) -> Option<ScatterResult> {
+    fn scatter(&self, _: &Ray, _: &HitRecord, _: &mut dyn Sampler) -> Option<ScatterResult> {
         None
     }
 
-    fn bxdf(&self, _: &Ray, _: &Ray, _: Point3D, _: Vec3D) -> Vec3D {
+    fn bxdf(&self, _: &Ray, _: &Ray, _: &HitRecord) -> Vec3D {
         Vec3D::zero()
     }
 
+    fn pdf(&self, _: &Ray, _: &Ray, _: &HitRecord) -> ScalarF {
+        0.0
+    }
+
     fn emission(&self) -> Vec3D {
         self.color
     }
@@ -61,64 +134,241 @@ pub struct EmissiveConfig {
 
 #[derive(Debug, Clone)]
 pub struct Lambertian {
-    pub albedo: Vec3D,
+    pub albedo: Arc<dyn Texture>,
 }
 
 impl Material for Lambertian {
     fn scatter(
         &self,
-        _: &Ray,
-        hit_point: Point3D,
-        normal: Vec3D,
+        ray_in: &Ray,
+        hit: &HitRecord,
+        sampler: &mut dyn Sampler,
+    ) -> Option<ScatterResult> {
+        cosine_hemisphere_scatter(ray_in, hit, sampler)
+    }
+
+    fn bxdf(&self, ray_in: &Ray, ray_out: &Ray, hit: &HitRecord) -> Vec3D {
+        if !same_hemisphere(ray_in, ray_out, hit.normal) {
+            return Vec3D::zero();
+        }
+        self.albedo.sample_at(&TextureContext::from_hit(hit)) * FRAC_1_PI
+    }
+
+    fn pdf(&self, ray_in: &Ray, ray_out: &Ray, hit: &HitRecord) -> ScalarF {
+        cosine_hemisphere_pdf(ray_out, facing_normal(ray_in, hit.normal))
+    }
+}
+
+// a flat `albedo` colour or an image read from `albedo_texture`
+#[derive(Deserialize)]
+pub struct LambertianConfig {
+    pub albedo: Option<Vec3DConfig>,
+    pub albedo_texture: Option<String>,
+}
+
+impl LambertianConfig {
+    pub fn from_albedo(albedo: Vec3DConfig) -> Self {
+        Self {
+            albedo: Some(albedo),
+            albedo_texture: None,
+        }
+    }
+
+    fn to_albedo(&self) -> Result<Arc<dyn Texture>, RaytracerError> {
+        match (&self.albedo_texture, &self.albedo) {
+            (Some(path), _) => Ok(Arc::new(
+                ImageTexture::load(path).map_err(RaytracerError::SceneLoad)?,
+            )),
+            (None, Some(albedo)) => Ok(Arc::new(ConstantTexture {
+                color: albedo.to_vec3(),
+            })),
+            (None, None) => Err(RaytracerError::ConfigParse(
+                "Lambertian material needs an albedo or an albedo_texture".to_string(),
+            )),
+        }
+    }
+}
+
+// rough diffuse surface made of lambertian v-shaped facets, `roughness` is
+// the standard deviation of the facet angle in radians. retro-reflects
+// towards grazing views where lambertian surfaces look too dark
+#[derive(Debug, Clone)]
+pub struct OrenNayar {
+    pub albedo: Vec3D,
+    pub roughness: ScalarF,
+}
+
+#[derive(Deserialize)]
+pub struct OrenNayarConfig {
+    pub albedo: Vec3DConfig,
+    pub roughness: f64,
+}
+
+impl Material for OrenNayar {
+    fn scatter(
+        &self,
+        ray_in: &Ray,
+        hit: &HitRecord,
+        sampler: &mut dyn Sampler,
+    ) -> Option<ScatterResult> {
+        cosine_hemisphere_scatter(ray_in, hit, sampler)
+    }
+
+    fn bxdf(&self, ray_in: &Ray, ray_out: &Ray, hit: &HitRecord) -> Vec3D {
+        if !same_hemisphere(ray_in, ray_out, hit.normal) {
+            return Vec3D::zero();
+        }
+        let normal = facing_normal(ray_in, hit.normal);
+        let wi = -ray_in.direction.normalize();
+        let wo = ray_out.direction.normalize();
+        let cos_i = wi.dot(normal).clamp(0.0, 1.0);
+        let cos_o = wo.dot(normal).clamp(0.0, 1.0);
+        let sin_i = (1.0 - cos_i * cos_i).sqrt();
+        let sin_o = (1.0 - cos_o * cos_o).sqrt();
+
+        let sigma2 = self.roughness * self.roughness;
+        let a = 1.0 - 0.5 * sigma2 / (sigma2 + 0.33);
+        let b = 0.45 * sigma2 / (sigma2 + 0.09);
+
+        // cosine of the azimuth between the directions
+        let cos_phi = if sin_i > 1e-6 && sin_o > 1e-6 {
+            ((wi - normal * cos_i).dot(wo - normal * cos_o) / (sin_i * sin_o)).max(0.0)
+        } else {
+            0.0
+        };
+        // sin(alpha) tan(beta) with alpha the larger and beta the smaller angle
+        let (sin_alpha, tan_beta) = if cos_i > cos_o {
+            (sin_o, sin_i / cos_i)
+        } else {
+            (sin_i, sin_o / cos_o.max(1e-6))
+        };
+        self.albedo * FRAC_1_PI * (a + b * cos_phi * sin_alpha * tan_beta)
+    }
+
+    fn pdf(&self, ray_in: &Ray, ray_out: &Ray, hit: &HitRecord) -> ScalarF {
+        cosine_hemisphere_pdf(ray_out, facing_normal(ray_in, hit.normal))
+    }
+}
+
+// diffuse surface coloured by the mesh vertices, hits without a vertex
+// colour use the `fallback` albedo
+#[derive(Debug, Clone)]
+pub struct VertexColorMaterial {
+    pub fallback: Lambertian,
+}
+
+#[derive(Deserialize)]
+pub struct VertexColorMaterialConfig {
+    pub fallback: Option<Vec3DConfig>, // grey by default
+}
+
+impl Material for VertexColorMaterial {
+    fn scatter(
+        &self,
+        ray_in: &Ray,
+        hit: &HitRecord,
+        sampler: &mut dyn Sampler,
+    ) -> Option<ScatterResult> {
+        self.fallback.scatter(ray_in, hit, sampler)
+    }
+
+    fn bxdf(&self, ray_in: &Ray, ray_out: &Ray, hit: &HitRecord) -> Vec3D {
+        match hit.vertex_color {
+            Some(color) if same_hemisphere(ray_in, ray_out, hit.normal) => color * FRAC_1_PI,
+            Some(_) => Vec3D::zero(),
+            None => self.fallback.bxdf(ray_in, ray_out, hit),
+        }
+    }
+
+    fn pdf(&self, ray_in: &Ray, ray_out: &Ray, hit: &HitRecord) -> ScalarF {
+        self.fallback.pdf(ray_in, ray_out, hit)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct NormalMappedLambertian {
+    pub albedo: Arc<dyn Texture>,
+    pub normal_map: Arc<dyn Texture>, // tangent-space normals encoded in [0, 1]
+}
+
+#[derive(Deserialize)]
+pub struct NormalMappedLambertianConfig {
+    pub albedo: TextureConfig,
+    pub normal_map: TextureConfig,
+}
+
+// world space normal read from a tangent space normal map encoded in [0, 1]
+fn normal_from_map(normal_map: &dyn Texture, hit: &HitRecord) -> Vec3D {
+    let tangent = hit.tangent.unwrap_or_else(|| Onb::from_w(hit.normal).u);
+    let tbn = compute_tbn(hit.normal, tangent);
+    let mut local =
+        normal_map.sample_at(&TextureContext::from_hit(hit)) * 2.0 - Vec3D::new(1.0, 1.0, 1.0);
+    // mirrored uv layouts have a left handed frame
+    if hit
+        .bitangent
+        .is_some_and(|bitangent| bitangent.dot(hit.normal.cross(tangent)) < 0.0)
+    {
+        local.y = -local.y;
+    }
+    transform_vec3(tbn, local).normalize()
+}
+
+impl NormalMappedLambertian {
+    fn perturbed_normal(&self, hit: &HitRecord) -> Vec3D {
+        normal_from_map(&*self.normal_map, hit)
+    }
+}
+
+impl Material for NormalMappedLambertian {
+    fn scatter(
+        &self,
+        ray_in: &Ray,
+        hit: &HitRecord,
         sampler: &mut dyn Sampler,
     ) -> Option<ScatterResult> {
+        let normal = facing_normal(ray_in, self.perturbed_normal(hit));
         let (u, v) = sampler.get_2d();
         let theta = (1.0 - u).sqrt().acos();
         let phi = 2.0 * PI * v;
 
         let new_direction = spherical_to_world(theta, phi, normal);
-        let new_ray = Ray {
-            origin: hit_point,
-            direction: new_direction,
-        };
+        let new_ray = spawn_ray(hit.p, hit.normal, new_direction);
         let pdf = new_direction.dot(normal) * FRAC_1_PI;
         Some(ScatterResult::new(new_ray, pdf))
     }
 
-    fn bxdf(&self, _: &Ray, _: &Ray, _: Point3D, _: Vec3D) -> Vec3D {
-        self.albedo * FRAC_1_PI
+    fn bxdf(&self, ray_in: &Ray, ray_out: &Ray, hit: &HitRecord) -> Vec3D {
+        if !same_hemisphere(ray_in, ray_out, hit.normal) {
+            return Vec3D::zero();
+        }
+        self.albedo.sample_at(&TextureContext::from_hit(hit)) * FRAC_1_PI
     }
-}
 
-#[derive(Deserialize)]
-pub struct LambertianConfig {
-    pub albedo: Vec3DConfig,
+    fn pdf(&self, ray_in: &Ray, ray_out: &Ray, hit: &HitRecord) -> ScalarF {
+        cosine_hemisphere_pdf(ray_out, facing_normal(ray_in, self.perturbed_normal(hit)))
+    }
 }
 
 #[derive(Debug, Clone)]
 pub struct PhongSpecular {
     pub specular: Vec3D,
-    pub shininess: f64,
+    pub shininess: ScalarF,
 }
 
 impl Material for PhongSpecular {
     fn scatter(
         &self,
         ray_in: &Ray,
-        hit_point: Point3D,
-        normal: Vec3D,
+        hit: &HitRecord,
         sampler: &mut dyn Sampler,
     ) -> Option<ScatterResult> {
-        let reflected = reflect(ray_in.direction, normal);
+        let reflected = reflect(ray_in.direction, hit.normal);
         let (u, v) = sampler.get_2d();
         let theta = u.powf(1.0 / (self.shininess + 1.0)).acos();
         let phi = 2.0 * PI * v;
 
         let new_direction = spherical_to_world(theta, phi, reflected);
-        let new_ray = Ray {
-            origin: hit_point,
-            direction: new_direction,
-        };
+        let new_ray = spawn_ray(hit.p, hit.normal, new_direction);
         let pdf = new_direction.dot(reflected).powf(self.shininess)
             * (self.shininess + 1.0)
             * FRAC_1_PI
@@ -126,8 +376,8 @@ impl Material for PhongSpecular {
         Some(ScatterResult::new(new_ray, pdf))
     }
 
-    fn bxdf(&self, ray_in: &Ray, ray_out: &Ray, _: Point3D, normal: Vec3D) -> Vec3D {
-        let reflected = reflect(ray_in.direction, normal);
+    fn bxdf(&self, ray_in: &Ray, ray_out: &Ray, hit: &HitRecord) -> Vec3D {
+        let reflected = reflect(ray_in.direction, hit.normal);
         let cos_theta = reflected.dot(ray_out.direction);
         if cos_theta < 0.0 {
             Vec3D::zero()
@@ -139,6 +389,16 @@ impl Material for PhongSpecular {
                 * cos_theta.powf(self.shininess)
         }
     }
+
+    fn pdf(&self, ray_in: &Ray, ray_out: &Ray, hit: &HitRecord) -> ScalarF {
+        let reflected = reflect(ray_in.direction, hit.normal);
+        let cos_theta = reflected.dot(ray_out.direction);
+        if cos_theta < 0.0 {
+            0.0
+        } else {
+            cos_theta.powf(self.shininess) * (self.shininess + 1.0) * FRAC_1_PI * 0.5
+        }
+    }
 }
 
 #[derive(Deserialize)]
@@ -154,35 +414,38 @@ pub struct IdealReflector {}
 pub struct IdealReflectorConfig {}
 
 impl Material for IdealReflector {
-    fn scatter(
-        &self,
-        ray_in: &Ray,
-        hit_point: Point3D,
-        normal: Vec3D,
-        _: &mut dyn Sampler,
-    ) -> Option<ScatterResult> {
-        let reflected = reflect(ray_in.direction, normal);
-        let new_ray = Ray {
-            origin: hit_point,
-            direction: reflected,
-        };
+    fn scatter(&self, ray_in: &Ray, hit: &HitRecord, _: &mut dyn Sampler) -> Option<ScatterResult> {
+        let reflected = reflect(ray_in.direction, hit.normal);
+        let new_ray = spawn_ray(hit.p, hit.normal, reflected);
         Some(ScatterResult::new(new_ray, 1.0))
     }
 
-    fn bxdf(&self, ray_in: &Ray, ray_out: &Ray, _: Point3D, normal: Vec3D) -> Vec3D {
-        let reflected = reflect(ray_in.direction, normal);
-        let cos_theta = ray_out.direction.dot(normal);
+    fn bxdf(&self, ray_in: &Ray, ray_out: &Ray, hit: &HitRecord) -> Vec3D {
+        let reflected = reflect(ray_in.direction, hit.normal);
+        let cos_theta = ray_out.direction.dot(hit.normal);
         if cos_theta > 1e-6 && (ray_out.direction - reflected).magnitude2() < 1e-6 {
             Vec3D::new(1.0, 1.0, 1.0) / cos_theta
         } else {
             Vec3D::zero()
         }
     }
+
+    fn pdf(&self, _: &Ray, _: &Ray, _: &HitRecord) -> ScalarF {
+        0.0
+    }
+
+    fn is_specular(&self) -> bool {
+        true
+    }
+
+    fn specular_directions(&self, ray_in: &Ray, hit: &HitRecord) -> Vec<Vec3D> {
+        vec![reflect(ray_in.direction, hit.normal)]
+    }
 }
 
 #[derive(Debug, Clone)]
 pub struct IdealDielectric {
-    pub ior: f64, // index of refraction
+    pub ior: ScalarF, // index of refraction
 }
 
 #[derive(Deserialize)]
@@ -194,24 +457,21 @@ impl Material for IdealDielectric {
     fn scatter(
         &self,
         ray_in: &Ray,
-        hit_point: Point3D,
-        normal: Vec3D,
+        hit: &HitRecord,
         sampler: &mut dyn Sampler,
     ) -> Option<ScatterResult> {
-        let mut outward_normal = normal; // normal pointing out of the surface
-
-        // check if ray is inside the object
-        let mut eta_i = 1.0;
-        let mut eta_t = self.ior;
-        if ray_in.direction.dot(normal) > 0.0 {
-            eta_i = self.ior;
-            eta_t = 1.0;
-            outward_normal = -normal;
-        }
+        // the normal faces the ray, eta swaps when it leaves the object
+        let normal = hit.normal;
+        let exterior_ior = hit.exterior_ior.unwrap_or(1.0);
+        let (eta_i, eta_t) = if hit.front_face {
+            (exterior_ior, self.ior)
+        } else {
+            (self.ior, exterior_ior)
+        };
         let eta = eta_i / eta_t;
 
         let unit_direction = ray_in.direction.normalize();
-        let cos_theta = (-unit_direction).dot(outward_normal);
+        let cos_theta = (-unit_direction).dot(normal);
         let r = sampler.get_1d();
         let reflectance = fresnel(cos_theta, eta_i, eta_t);
         if reflectance > 1.0 {
@@ -219,42 +479,34 @@ impl Material for IdealDielectric {
         }
         if r < reflectance {
             // reflect
-            let reflected = reflect(unit_direction, outward_normal);
-            let new_ray = Ray {
-                origin: hit_point,
-                direction: reflected,
-            };
+            let reflected = reflect(unit_direction, normal);
+            let new_ray = spawn_ray(hit.p, hit.normal, reflected);
             return Some(ScatterResult::new(new_ray, reflectance));
         } else {
             // refract
-            let refracted = refract(unit_direction, outward_normal, eta);
+            let refracted = refract(unit_direction, normal, eta);
             if refracted.is_none() {
                 return None;
             }
             let refracted = refracted.unwrap();
-            let new_ray = Ray {
-                origin: hit_point,
-                direction: refracted,
-            };
+            let new_ray = spawn_ray(hit.p, hit.normal, refracted);
             return Some(ScatterResult::new(new_ray, 1.0 - reflectance));
         }
     }
 
-    fn bxdf(&self, ray_in: &Ray, ray_out: &Ray, _: Point3D, normal: Vec3D) -> Vec3D {
-        let mut outward_normal = normal; // normal pointing out of the surface
-
-        // check if ray is inside the object
-        let mut eta_i = 1.0;
-        let mut eta_t = self.ior;
-        if ray_in.direction.dot(normal) > 0.0 {
-            eta_i = self.ior;
-            eta_t = 1.0;
-            outward_normal = -normal;
-        }
+    fn bxdf(&self, ray_in: &Ray, ray_out: &Ray, hit: &HitRecord) -> Vec3D {
+        // the normal faces the ray, eta swaps when it leaves the object
+        let normal = hit.normal;
+        let exterior_ior = hit.exterior_ior.unwrap_or(1.0);
+        let (eta_i, eta_t) = if hit.front_face {
+            (exterior_ior, self.ior)
+        } else {
+            (self.ior, exterior_ior)
+        };
         let eta = eta_i / eta_t;
 
-        let cos_theta_i = ray_in.direction.dot(normal).abs();
-        let cos_theta_t = ray_out.direction.dot(normal).abs();
+        let cos_theta_i = ray_in.direction.dot(hit.normal).abs();
+        let cos_theta_t = ray_out.direction.dot(hit.normal).abs();
         if cos_theta_t < 1e-6 {
             return Vec3D::zero();
         }
@@ -262,8 +514,8 @@ impl Material for IdealDielectric {
         let reflectance = fresnel(cos_theta_i, eta_i, eta_t);
         let transmittance = 1.0 - reflectance;
 
-        let reflect_dir = reflect(ray_in.direction, outward_normal);
-        let refract_dir = refract(ray_in.direction, outward_normal, eta).unwrap_or(Vec3D::zero());
+        let reflect_dir = reflect(ray_in.direction, normal);
+        let refract_dir = refract(ray_in.direction, normal, eta).unwrap_or(Vec3D::zero());
 
         let mut bxdf = Vec3D::zero();
         if (reflect_dir - ray_out.direction).magnitude2() < 1e-6 {
@@ -281,35 +533,1980 @@ impl Material for IdealDielectric {
 
         bxdf
     }
+
+    fn pdf(&self, _: &Ray, _: &Ray, _: &HitRecord) -> ScalarF {
+        0.0
+    }
+
+    fn is_specular(&self) -> bool {
+        true
+    }
+
+    fn ior(&self) -> Option<ScalarF> {
+        Some(self.ior)
+    }
+
+    fn specular_directions(&self, ray_in: &Ray, hit: &HitRecord) -> Vec<Vec3D> {
+        let exterior_ior = hit.exterior_ior.unwrap_or(1.0);
+        let normal = hit.normal;
+        let eta = if hit.front_face {
+            exterior_ior / self.ior
+        } else {
+            self.ior / exterior_ior
+        };
+        let unit_direction = ray_in.direction.normalize();
+        let mut directions = vec![reflect(unit_direction, normal)];
+        // no refraction under total internal reflection
+        directions.extend(refract(unit_direction, normal, eta));
+        directions
+    }
+}
+
+// any material shaded with the normal from a tangent space normal map
+#[derive(Debug, Clone)]
+pub struct NormalMappedMaterial {
+    pub base: Arc<dyn Material>,
+    pub normal_map: Arc<dyn Texture>,
 }
 
 #[derive(Deserialize)]
-#[serde(tag = "type")]
-pub enum MaterialConfig {
-    Emissive(EmissiveConfig),
-    Lambertian(LambertianConfig),
-    PhongSpecular(PhongSpecularConfig),
-    IdealReflector(IdealReflectorConfig),
-    IdealDielectric(IdealDielectricConfig),
+pub struct NormalMappedMaterialConfig {
+    pub base: Box<MaterialConfig>,
+    pub normal_map: TextureConfig,
 }
 
-impl MaterialConfig {
-    pub fn to_material(&self) -> Arc<dyn Material> {
-        match self {
-            MaterialConfig::Emissive(config) => Arc::new(Emissive {
-                color: config.color.to_vec3(),
-            }),
-            MaterialConfig::Lambertian(config) => Arc::new(Lambertian {
-                albedo: config.albedo.to_vec3(),
-            }),
-            MaterialConfig::PhongSpecular(config) => Arc::new(PhongSpecular {
-                specular: config.specular.to_vec3(),
-                shininess: config.shininess,
-            }),
-            MaterialConfig::IdealReflector(_) => Arc::new(IdealReflector {}),
-            MaterialConfig::IdealDielectric(config) => {
-                Arc::new(IdealDielectric { ior: config.ior })
+impl NormalMappedMaterial {
+    fn shading_hit<'a>(&self, hit: &HitRecord<'a>) -> HitRecord<'a> {
+        HitRecord {
+            normal: normal_from_map(&*self.normal_map, hit),
+            ..hit.clone()
+        }
+    }
+}
+
+impl Material for NormalMappedMaterial {
+    fn scatter(
+        &self,
+        ray_in: &Ray,
+        hit: &HitRecord,
+        sampler: &mut dyn Sampler,
+    ) -> Option<ScatterResult> {
+        self.base.scatter(ray_in, &self.shading_hit(hit), sampler)
+    }
+
+    fn bxdf(&self, ray_in: &Ray, ray_out: &Ray, hit: &HitRecord) -> Vec3D {
+        self.base.bxdf(ray_in, ray_out, &self.shading_hit(hit))
+    }
+
+    fn pdf(&self, ray_in: &Ray, ray_out: &Ray, hit: &HitRecord) -> ScalarF {
+        self.base.pdf(ray_in, ray_out, &self.shading_hit(hit))
+    }
+
+    fn is_specular(&self) -> bool {
+        self.base.is_specular()
+    }
+
+    fn specular_directions(&self, ray_in: &Ray, hit: &HitRecord) -> Vec<Vec3D> {
+        self.base
+            .specular_directions(ray_in, &self.shading_hit(hit))
+    }
+
+    fn emission(&self) -> Vec3D {
+        self.base.emission()
+    }
+}
+
+// partially transparent surface, rays pass straight through with
+// probability 1 - alpha and hit the base material otherwise
+#[derive(Debug, Clone)]
+pub struct AlphaMaskedMaterial {
+    pub base: Arc<dyn Material>,
+    pub alpha: ScalarF, // opacity
+}
+
+#[derive(Deserialize)]
+pub struct AlphaMaskedMaterialConfig {
+    pub base: Box<MaterialConfig>,
+    pub alpha: f64,
+}
+
+impl AlphaMaskedMaterial {
+    fn passes_through(ray_in: &Ray, ray_out: &Ray) -> bool {
+        (ray_in.direction.normalize() - ray_out.direction.normalize()).magnitude2() < 1e-6
+    }
+}
+
+impl Material for AlphaMaskedMaterial {
+    fn scatter(
+        &self,
+        ray_in: &Ray,
+        hit: &HitRecord,
+        sampler: &mut dyn Sampler,
+    ) -> Option<ScatterResult> {
+        if sampler.get_1d() >= self.alpha {
+            let new_ray = spawn_ray(hit.p, hit.normal, ray_in.direction);
+            return Some(ScatterResult::new(new_ray, 1.0 - self.alpha));
+        }
+
+        self.base
+            .scatter(ray_in, hit, sampler)
+            .map(|result| ScatterResult::new(result.ray, result.pdf * self.alpha))
+    }
+
+    fn bxdf(&self, ray_in: &Ray, ray_out: &Ray, hit: &HitRecord) -> Vec3D {
+        if AlphaMaskedMaterial::passes_through(ray_in, ray_out) {
+            let cos_theta = ray_out.direction.dot(hit.normal).abs();
+            if cos_theta < 1e-6 {
+                return Vec3D::zero();
             }
+            return Vec3D::from_value(1.0 - self.alpha) / cos_theta;
+        }
+        self.base.bxdf(ray_in, ray_out, hit) * self.alpha
+    }
+
+    fn pdf(&self, ray_in: &Ray, ray_out: &Ray, hit: &HitRecord) -> ScalarF {
+        self.base.pdf(ray_in, ray_out, hit) * self.alpha
+    }
+
+    fn is_specular(&self) -> bool {
+        self.base.is_specular()
+    }
+
+    fn emission(&self) -> Vec3D {
+        self.base.emission() * self.alpha
+    }
+}
+
+// wavelengths the red, green and blue channels stand for
+const RGB_WAVELENGTHS_NM: [ScalarF; 3] = [650.0, 510.0, 440.0];
+
+// iridescent coating on any material. reflections off the base are reweighed
+// by the interference of a thin film on top of it relative to the bare
+// surface, so a film of zero thickness leaves the base as it is
+#[derive(Debug, Clone)]
+pub struct ThinFilmMaterial {
+    pub base: Arc<dyn Material>,
+    pub film_ior: ScalarF,
+    pub film_thickness_nm: ScalarF,
+    pub substrate_ior: ScalarF, // the base's own index, only the film's tint uses it
+}
+
+#[derive(Deserialize)]
+pub struct ThinFilmMaterialConfig {
+    pub base: Box<MaterialConfig>,
+    pub film_ior: f64,
+    pub film_thickness_nm: f64,
+    #[serde(default = "default_substrate_ior")]
+    pub substrate_ior: f64,
+}
+
+fn default_substrate_ior() -> f64 {
+    1.5
+}
+
+impl ThinFilmMaterial {
+    // film over bare reflectance per channel, at `cos_theta` to the
+    // microfacet the reflection happens on
+    fn film_tint(&self, cos_theta: ScalarF) -> Vec3D {
+        let bare = fresnel(cos_theta, 1.0, self.substrate_ior);
+        if bare <= 1e-12 {
+            return Vec3D::from_value(1.0);
+        }
+        let tint = |wavelength_nm: ScalarF| {
+            thin_film_reflectance(
+                cos_theta,
+                self.film_ior,
+                self.film_thickness_nm,
+                self.substrate_ior,
+                wavelength_nm,
+            ) / bare
+        };
+        let [r, g, b] = RGB_WAVELENGTHS_NM;
+        Vec3D::new(tint(r), tint(g), tint(b))
+    }
+}
+
+impl Material for ThinFilmMaterial {
+    fn scatter(
+        &self,
+        ray_in: &Ray,
+        hit: &HitRecord,
+        sampler: &mut dyn Sampler,
+    ) -> Option<ScatterResult> {
+        self.base.scatter(ray_in, hit, sampler)
+    }
+
+    fn bxdf(&self, ray_in: &Ray, ray_out: &Ray, hit: &HitRecord) -> Vec3D {
+        let bxdf = self.base.bxdf(ray_in, ray_out, hit);
+        if !same_hemisphere(ray_in, ray_out, hit.normal) {
+            return bxdf;
         }
+        let (wi, wo) = (-ray_in.direction.normalize(), ray_out.direction.normalize());
+        let half = (wi + wo).normalize();
+        bxdf.mul_element_wise(self.film_tint(wi.dot(half).abs().min(1.0)))
+    }
+
+    fn pdf(&self, ray_in: &Ray, ray_out: &Ray, hit: &HitRecord) -> ScalarF {
+        self.base.pdf(ray_in, ray_out, hit)
+    }
+
+    fn is_specular(&self) -> bool {
+        self.base.is_specular()
+    }
+
+    fn specular_directions(&self, ray_in: &Ray, hit: &HitRecord) -> Vec<Vec3D> {
+        self.base.specular_directions(ray_in, hit)
+    }
+
+    fn emission(&self) -> Vec3D {
+        self.base.emission()
+    }
+}
+
+// blend of two materials, `weight` picks how much of `mat_b` shows through
+// the luminance of its texture. scattering picks one of the two at random
+// while bxdf and pdf are the weighted sums over both
+#[derive(Debug, Clone)]
+pub struct MixMaterial {
+    pub mat_a: Arc<dyn Material>,
+    pub mat_b: Arc<dyn Material>,
+    pub weight: Arc<dyn Texture>,
+}
+
+#[derive(Deserialize)]
+pub struct MixMaterialConfig {
+    pub mat_a: Box<MaterialConfig>,
+    pub mat_b: Box<MaterialConfig>,
+    pub weight: TextureConfig,
+}
+
+impl MixMaterial {
+    fn weight_at(&self, hit: &HitRecord) -> ScalarF {
+        luminance(self.weight.sample_at(&TextureContext::from_hit(hit))).clamp(0.0, 1.0)
+    }
+}
+
+impl Material for MixMaterial {
+    fn scatter(
+        &self,
+        ray_in: &Ray,
+        hit: &HitRecord,
+        sampler: &mut dyn Sampler,
+    ) -> Option<ScatterResult> {
+        let w = self.weight_at(hit);
+        let (chosen, probability) = if sampler.get_1d() < w {
+            (&self.mat_b, w)
+        } else {
+            (&self.mat_a, 1.0 - w)
+        };
+        let result = chosen.scatter(ray_in, hit, sampler)?;
+
+        // specular lobes carry their own discrete probability, continuous
+        // ones could also have come from the other material
+        let pdf = if chosen.is_specular() {
+            result.pdf * probability
+        } else {
+            self.pdf(ray_in, &result.ray, hit)
+        };
+        Some(ScatterResult::new(result.ray, pdf))
+    }
+
+    fn bxdf(&self, ray_in: &Ray, ray_out: &Ray, hit: &HitRecord) -> Vec3D {
+        let w = self.weight_at(hit);
+        self.mat_a.bxdf(ray_in, ray_out, hit) * (1.0 - w)
+            + self.mat_b.bxdf(ray_in, ray_out, hit) * w
+    }
+
+    fn pdf(&self, ray_in: &Ray, ray_out: &Ray, hit: &HitRecord) -> ScalarF {
+        let w = self.weight_at(hit);
+        self.mat_a.pdf(ray_in, ray_out, hit) * (1.0 - w) + self.mat_b.pdf(ray_in, ray_out, hit) * w
+    }
+
+    fn is_specular(&self) -> bool {
+        self.mat_a.is_specular() && self.mat_b.is_specular()
+    }
+
+    fn specular_directions(&self, ray_in: &Ray, hit: &HitRecord) -> Vec<Vec3D> {
+        let mut directions = self.mat_a.specular_directions(ray_in, hit);
+        directions.extend(self.mat_b.specular_directions(ray_in, hit));
+        directions
+    }
+}
+
+// smooth lacquer over any material. a colourless GGX lobe reflects the share
+// Schlick's Fresnel gives the coat, the rest reaches the base below it.
+// Fresnel is taken at the viewing angle so both shares add up to one
+#[derive(Debug, Clone)]
+pub struct ClearcoatMaterial {
+    pub base: Arc<dyn Material>,
+    pub clearcoat_roughness: ScalarF,
+    pub clearcoat_ior: ScalarF,
+}
+
+#[derive(Deserialize)]
+pub struct ClearcoatMaterialConfig {
+    pub base: Box<MaterialConfig>,
+    pub clearcoat_roughness: f64,
+    #[serde(default = "default_clearcoat_ior")]
+    pub clearcoat_ior: f64,
+}
+
+fn default_clearcoat_ior() -> f64 {
+    1.5
+}
+
+impl ClearcoatMaterial {
+    fn coat(&self) -> GgxMaterial {
+        GgxMaterial {
+            albedo: TextureOrValue::Value(Vec3D::from_value(1.0)),
+            roughness: TextureOrValue::Value(self.clearcoat_roughness),
+            metallic: TextureOrValue::Value(1.0),
+        }
+    }
+
+    // share of the light the coat reflects when seen from `cos_theta`
+    fn coat_fresnel(&self, cos_theta: ScalarF) -> ScalarF {
+        let f0 = ((self.clearcoat_ior - 1.0) / (self.clearcoat_ior + 1.0)).powi(2);
+        f0 + (1.0 - f0) * schlick_weight(cos_theta)
+    }
+
+    fn coat_fresnel_at(&self, ray_in: &Ray, hit: &HitRecord) -> ScalarF {
+        let normal = facing_normal(ray_in, hit.normal);
+        self.coat_fresnel(-ray_in.direction.normalize().dot(normal))
+    }
+}
+
+impl Material for ClearcoatMaterial {
+    fn scatter(
+        &self,
+        ray_in: &Ray,
+        hit: &HitRecord,
+        sampler: &mut dyn Sampler,
+    ) -> Option<ScatterResult> {
+        let f = self.coat_fresnel_at(ray_in, hit);
+        if sampler.get_1d() < f {
+            let result = self.coat().scatter(ray_in, hit, sampler)?;
+            let pdf = self.pdf(ray_in, &result.ray, hit);
+            return Some(ScatterResult::new(result.ray, pdf));
+        }
+
+        let result = self.base.scatter(ray_in, hit, sampler)?;
+        let pdf = if self.base.is_specular() {
+            result.pdf * (1.0 - f)
+        } else {
+            self.pdf(ray_in, &result.ray, hit)
+        };
+        Some(ScatterResult::new(result.ray, pdf))
+    }
+
+    fn bxdf(&self, ray_in: &Ray, ray_out: &Ray, hit: &HitRecord) -> Vec3D {
+        let f = self.coat_fresnel_at(ray_in, hit);
+        self.coat().bxdf(ray_in, ray_out, hit) * f
+            + self.base.bxdf(ray_in, ray_out, hit) * (1.0 - f)
+    }
+
+    fn pdf(&self, ray_in: &Ray, ray_out: &Ray, hit: &HitRecord) -> ScalarF {
+        let f = self.coat_fresnel_at(ray_in, hit);
+        self.coat().pdf(ray_in, ray_out, hit) * f + self.base.pdf(ray_in, ray_out, hit) * (1.0 - f)
+    }
+
+    fn specular_directions(&self, ray_in: &Ray, hit: &HitRecord) -> Vec<Vec3D> {
+        self.base.specular_directions(ray_in, hit)
+    }
+
+    fn emission(&self) -> Vec3D {
+        self.base.emission()
+    }
+}
+
+// different materials on the two sides of a surface, the front is the side
+// the outward normal points to
+#[derive(Debug, Clone)]
+pub struct TwoSidedMaterial {
+    pub front: Arc<dyn Material>,
+    pub back: Arc<dyn Material>,
+}
+
+#[derive(Deserialize)]
+pub struct TwoSidedMaterialConfig {
+    pub front: Box<MaterialConfig>,
+    pub back: Box<MaterialConfig>,
+}
+
+impl TwoSidedMaterial {
+    fn side(&self, ray_in: &Ray, hit: &HitRecord) -> &Arc<dyn Material> {
+        // lights sampled without a ray only know their outward normal
+        if ray_in.direction.dot(hit.outward_normal()) < 0.0 {
+            &self.front
+        } else {
+            &self.back
+        }
+    }
+}
+
+impl Material for TwoSidedMaterial {
+    fn scatter(
+        &self,
+        ray_in: &Ray,
+        hit: &HitRecord,
+        sampler: &mut dyn Sampler,
+    ) -> Option<ScatterResult> {
+        self.side(ray_in, hit).scatter(ray_in, hit, sampler)
+    }
+
+    fn bxdf(&self, ray_in: &Ray, ray_out: &Ray, hit: &HitRecord) -> Vec3D {
+        self.side(ray_in, hit).bxdf(ray_in, ray_out, hit)
+    }
+
+    fn pdf(&self, ray_in: &Ray, ray_out: &Ray, hit: &HitRecord) -> ScalarF {
+        self.side(ray_in, hit).pdf(ray_in, ray_out, hit)
+    }
+
+    fn is_specular(&self) -> bool {
+        self.front.is_specular() && self.back.is_specular()
+    }
+
+    fn specular_directions(&self, ray_in: &Ray, hit: &HitRecord) -> Vec<Vec3D> {
+        self.side(ray_in, hit).specular_directions(ray_in, hit)
+    }
+
+    // without a hit there is no side, sampled lights emit like the front
+    fn emission(&self) -> Vec3D {
+        self.front.emission()
+    }
+
+    fn emitted(&self, ray_in: &Ray, hit: &HitRecord) -> Vec3D {
+        self.side(ray_in, hit).emitted(ray_in, hit)
+    }
+}
+
+// microfacet reflection with the GGX (Trowbridge-Reitz) distribution, all
+// parameters may vary over the surface. `metallic` blends the reflectance at
+// normal incidence from the 4% of dielectrics to `albedo`, directions are
+// drawn from the normals visible from the incoming ray
+#[derive(Debug, Clone)]
+pub struct GgxMaterial {
+    pub albedo: TextureOrValue<Vec3D>,
+    pub roughness: TextureOrValue<ScalarF>,
+    pub metallic: TextureOrValue<ScalarF>,
+}
+
+#[derive(Deserialize)]
+pub struct GgxMaterialConfig {
+    pub albedo: TextureOrValueConfig<Vec3DConfig>,
+    pub roughness: TextureOrValueConfig<f64>,
+    // a metal by default, `albedo` is then the reflectance at normal incidence
+    #[serde(default = "default_metallic")]
+    pub metallic: TextureOrValueConfig<f64>,
+}
+
+// the config of the former GgxSpecular material, which `Ggx` replaced
+#[allow(dead_code)]
+pub type GgxSpecularConfig = GgxMaterialConfig;
+
+fn default_metallic() -> TextureOrValueConfig<f64> {
+    TextureOrValueConfig::Value(1.0)
+}
+
+impl GgxMaterial {
+    fn alpha(&self, hit: &HitRecord) -> ScalarF {
+        let roughness = self.roughness.sample_at(&TextureContext::from_hit(hit));
+        (roughness * roughness).max(1e-4)
+    }
+
+    fn f0(&self, hit: &HitRecord) -> Vec3D {
+        let context = TextureContext::from_hit(hit);
+        Vec3D::from_value(0.04).lerp(
+            self.albedo.sample_at(&context),
+            self.metallic.sample_at(&context),
+        )
+    }
+
+    fn distribution(cos_theta_h: ScalarF, alpha: ScalarF) -> ScalarF {
+        let alpha2 = alpha * alpha;
+        // not (alpha2 - 1) cos^2 + 1, with f32 math alpha2 is lost next to 1
+        let cos2 = cos_theta_h * cos_theta_h;
+        let d = cos2 * alpha2 + (1.0 - cos2).max(0.0);
+        alpha2 * FRAC_1_PI / (d * d)
+    }
+
+    // Smith masking term for one direction
+    fn g1(cos_theta: ScalarF, alpha: ScalarF) -> ScalarF {
+        let alpha2 = alpha * alpha;
+        2.0 * cos_theta / (cos_theta + (alpha2 + (1.0 - alpha2) * cos_theta * cos_theta).sqrt())
+    }
+
+    // Smith auxiliary function, `g1` is 1 / (1 + lambda)
+    fn lambda(cos_theta: ScalarF, alpha: ScalarF) -> ScalarF {
+        let cos2 = cos_theta * cos_theta;
+        let tan2 = (1.0 - cos2).max(0.0) / cos2;
+        ((1.0 + alpha * alpha * tan2).sqrt() - 1.0) / 2.0
+    }
+
+    // height correlated masking and shadowing
+    fn g2(cos_theta_i: ScalarF, cos_theta_o: ScalarF, alpha: ScalarF) -> ScalarF {
+        1.0 / (1.0 + Self::lambda(cos_theta_i, alpha) + Self::lambda(cos_theta_o, alpha))
+    }
+
+    // heitz 2018, a normal visible from `v`, both in the frame of the
+    // surface with z up
+    fn sample_visible_normal(v: Vec3D, alpha: ScalarF, u: ScalarF, w: ScalarF) -> Vec3D {
+        let vh = Vec3D::new(alpha * v.x, alpha * v.y, v.z).normalize();
+        let length2 = vh.x * vh.x + vh.y * vh.y;
+        let t1 = if length2 > 0.0 {
+            Vec3D::new(-vh.y, vh.x, 0.0) / length2.sqrt()
+        } else {
+            Vec3D::unit_x()
+        };
+        let t2 = vh.cross(t1);
+
+        // a disk sample, squeezed onto the projection of the visible half
+        let r = u.sqrt();
+        let phi = 2.0 * PI * w;
+        let p1 = r * phi.cos();
+        let s = 0.5 * (1.0 + vh.z);
+        let p2 = (1.0 - s) * (1.0 - p1 * p1).sqrt() + s * r * phi.sin();
+        let nh = t1 * p1 + t2 * p2 + vh * (1.0 - p1 * p1 - p2 * p2).max(0.0).sqrt();
+        Vec3D::new(alpha * nh.x, alpha * nh.y, nh.z.max(0.0)).normalize()
+    }
+}
+
+impl Material for GgxMaterial {
+    fn scatter(
+        &self,
+        ray_in: &Ray,
+        hit: &HitRecord,
+        sampler: &mut dyn Sampler,
+    ) -> Option<ScatterResult> {
+        let normal = facing_normal(ray_in, hit.normal);
+        let onb = Onb::from_w(normal);
+        let wi = -ray_in.direction.normalize();
+        let v = onb.world(wi);
+        if v.z <= 0.0 {
+            return None;
+        }
+
+        let (u, w) = sampler.get_2d();
+        let h = GgxMaterial::sample_visible_normal(v, self.alpha(hit), u, w);
+        if h.z <= 0.0 {
+            return None;
+        }
+        let half = onb.local(h);
+        let new_direction = reflect(-wi, half);
+        if new_direction.dot(normal) <= 0.0 {
+            return None;
+        }
+        let new_ray = spawn_ray(hit.p, hit.normal, new_direction);
+        let pdf = self.pdf(ray_in, &new_ray, hit);
+        Some(ScatterResult::new(new_ray, pdf))
+    }
+
+    fn bxdf(&self, ray_in: &Ray, ray_out: &Ray, hit: &HitRecord) -> Vec3D {
+        let normal = facing_normal(ray_in, hit.normal);
+        let wi = -ray_in.direction.normalize();
+        let wo = ray_out.direction.normalize();
+        let cos_theta_i = wi.dot(normal);
+        let cos_theta_o = wo.dot(normal);
+        if cos_theta_i <= 1e-6 || cos_theta_o <= 1e-6 {
+            return Vec3D::zero();
+        }
+
+        let alpha = self.alpha(hit);
+        let half = (wi + wo).normalize();
+        let d = GgxMaterial::distribution(half.dot(normal), alpha);
+        let g = GgxMaterial::g2(cos_theta_i, cos_theta_o, alpha);
+        let f0 = self.f0(hit);
+        let f = f0 + (Vec3D::from_value(1.0) - f0) * schlick_weight(wi.dot(half));
+
+        f * (d * g / (4.0 * cos_theta_i * cos_theta_o))
+    }
+
+    // D(h) G1(wi) / (4 cos(theta_i)), the visible normal density moved to
+    // the reflected direction
+    fn pdf(&self, ray_in: &Ray, ray_out: &Ray, hit: &HitRecord) -> ScalarF {
+        let normal = facing_normal(ray_in, hit.normal);
+        let wi = -ray_in.direction.normalize();
+        let wo = ray_out.direction.normalize();
+        let cos_theta_i = wi.dot(normal);
+        if cos_theta_i <= 0.0 || wo.dot(normal) <= 0.0 {
+            return 0.0;
+        }
+
+        let alpha = self.alpha(hit);
+        let half = (wi + wo).normalize();
+        let g1 = 1.0 / (1.0 + GgxMaterial::lambda(cos_theta_i, alpha));
+        GgxMaterial::distribution(half.dot(normal), alpha) * g1 / (4.0 * cos_theta_i)
+    }
+}
+
+// microfacet reflection with the Beckmann distribution, an older
+// alternative to GGX with shorter highlight tails
+#[derive(Debug, Clone)]
+pub struct BeckmannMaterial {
+    pub albedo: Vec3D, // reflectance at normal incidence
+    pub roughness: ScalarF,
+}
+
+#[derive(Deserialize)]
+pub struct BeckmannMaterialConfig {
+    pub albedo: Vec3DConfig,
+    pub roughness: f64,
+}
+
+impl BeckmannMaterial {
+    fn alpha(&self) -> ScalarF {
+        (self.roughness * self.roughness).max(1e-4)
+    }
+
+    fn distribution(cos_theta_h: ScalarF, alpha: ScalarF) -> ScalarF {
+        if cos_theta_h <= 0.0 {
+            return 0.0;
+        }
+        let cos2 = cos_theta_h * cos_theta_h;
+        let tan2 = (1.0 - cos2) / cos2;
+        let alpha2 = alpha * alpha;
+        (-tan2 / alpha2).exp() * FRAC_1_PI / (alpha2 * cos2 * cos2)
+    }
+
+    // Walter et al.'s rational fit of the Smith masking term
+    fn g1(cos_theta: ScalarF, alpha: ScalarF) -> ScalarF {
+        let tan_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt() / cos_theta;
+        if tan_theta == 0.0 {
+            return 1.0;
+        }
+        let a = 1.0 / (alpha * tan_theta);
+        if a >= 1.6 {
+            return 1.0;
+        }
+        (3.535 * a + 2.181 * a * a) / (1.0 + 2.276 * a + 2.577 * a * a)
+    }
+}
+
+impl Material for BeckmannMaterial {
+    fn scatter(
+        &self,
+        ray_in: &Ray,
+        hit: &HitRecord,
+        sampler: &mut dyn Sampler,
+    ) -> Option<ScatterResult> {
+        let normal = facing_normal(ray_in, hit.normal);
+        let alpha = self.alpha();
+
+        // sample the half vector proportional to D(h) * cos(theta_h)
+        let (u, v) = sampler.get_2d();
+        let tan2_theta_h = -alpha * alpha * (1.0 - u).ln();
+        let theta_h = tan2_theta_h.sqrt().atan();
+        let half = spherical_to_world(theta_h, 2.0 * PI * v, normal);
+
+        let new_direction = reflect(ray_in.direction.normalize(), half);
+        if new_direction.dot(normal) <= 0.0 {
+            return None;
+        }
+        let new_ray = spawn_ray(hit.p, hit.normal, new_direction);
+        let pdf = self.pdf(ray_in, &new_ray, hit);
+        Some(ScatterResult::new(new_ray, pdf))
+    }
+
+    fn bxdf(&self, ray_in: &Ray, ray_out: &Ray, hit: &HitRecord) -> Vec3D {
+        let normal = facing_normal(ray_in, hit.normal);
+        let wi = -ray_in.direction.normalize();
+        let wo = ray_out.direction.normalize();
+        let cos_theta_i = wi.dot(normal);
+        let cos_theta_o = wo.dot(normal);
+        if cos_theta_i <= 1e-6 || cos_theta_o <= 1e-6 {
+            return Vec3D::zero();
+        }
+
+        let alpha = self.alpha();
+        let half = (wi + wo).normalize();
+        let d = BeckmannMaterial::distribution(half.dot(normal), alpha);
+        let g = BeckmannMaterial::g1(cos_theta_i, alpha) * BeckmannMaterial::g1(cos_theta_o, alpha);
+        let f = self.albedo + (Vec3D::from_value(1.0) - self.albedo) * schlick_weight(wi.dot(half));
+
+        f * (d * g / (4.0 * cos_theta_i * cos_theta_o))
+    }
+
+    fn pdf(&self, ray_in: &Ray, ray_out: &Ray, hit: &HitRecord) -> ScalarF {
+        let normal = facing_normal(ray_in, hit.normal);
+        let wi = -ray_in.direction.normalize();
+        let wo = ray_out.direction.normalize();
+        if wo.dot(normal) <= 0.0 {
+            return 0.0;
+        }
+
+        let half = (wi + wo).normalize();
+        let cos_theta_h = half.dot(normal);
+        BeckmannMaterial::distribution(cos_theta_h, self.alpha()) * cos_theta_h
+            / (4.0 * wo.dot(half).abs())
+    }
+}
+
+// Burley's principled BSDF: diffuse with a subsurface approximation and
+// sheen, an anisotropic GGX specular lobe and an isotropic clearcoat lobe
+#[derive(Debug, Clone)]
+pub struct DisneyBsdf {
+    pub base_color: Vec3D,
+    pub metallic: ScalarF,
+    pub roughness: ScalarF,
+    pub specular: ScalarF,
+    pub specular_tint: ScalarF,
+    pub anisotropic: ScalarF,
+    pub sheen: ScalarF,
+    pub sheen_tint: ScalarF,
+    pub clearcoat: ScalarF,
+    pub clearcoat_gloss: ScalarF,
+    pub subsurface: ScalarF,
+}
+
+#[derive(Deserialize)]
+pub struct DisneyBsdfConfig {
+    pub base_color: Vec3DConfig,
+    #[serde(default)]
+    pub metallic: f64,
+    #[serde(default = "default_disney_roughness")]
+    pub roughness: f64,
+    #[serde(default = "default_disney_specular")]
+    pub specular: f64,
+    #[serde(default)]
+    pub specular_tint: f64,
+    #[serde(default)]
+    pub anisotropic: f64,
+    #[serde(default)]
+    pub sheen: f64,
+    #[serde(default = "default_disney_sheen_tint")]
+    pub sheen_tint: f64,
+    #[serde(default)]
+    pub clearcoat: f64,
+    #[serde(default = "default_disney_clearcoat_gloss")]
+    pub clearcoat_gloss: f64,
+    #[serde(default)]
+    pub subsurface: f64,
+}
+
+fn default_disney_roughness() -> f64 {
+    0.5
+}
+
+fn default_disney_specular() -> f64 {
+    0.5
+}
+
+fn default_disney_sheen_tint() -> f64 {
+    0.5
+}
+
+fn default_disney_clearcoat_gloss() -> f64 {
+    1.0
+}
+
+fn schlick_weight(cos_theta: ScalarF) -> ScalarF {
+    (1.0 - cos_theta).clamp(0.0, 1.0).powi(5)
+}
+
+// orthonormal shading frame around `normal`, aligned with the surface
+// tangent when the shape provides one
+fn shading_frame(hit: &HitRecord, normal: Vec3D) -> (Vec3D, Vec3D, Vec3D) {
+    let tangent = hit
+        .tangent
+        .map(|tangent| tangent - normal * normal.dot(tangent))
+        .filter(|tangent| tangent.magnitude2() > 1e-12);
+    match tangent {
+        Some(tangent) => {
+            let tangent = tangent.normalize();
+            (tangent, normal.cross(tangent), normal)
+        }
+        None => {
+            let onb = Onb::from_w(normal);
+            (onb.u, onb.v, onb.w)
+        }
+    }
+}
+
+// lobes the sampling routine chooses from
+#[derive(Clone, Copy)]
+enum DisneyLobe {
+    Diffuse,
+    Specular,
+    Clearcoat,
+}
+
+impl DisneyBsdf {
+    fn tint(&self) -> Vec3D {
+        let lum = luminance(self.base_color);
+        if lum > 0.0 {
+            self.base_color / lum
+        } else {
+            Vec3D::from_value(1.0)
+        }
+    }
+
+    fn specular_color(&self) -> Vec3D {
+        let tinted = Vec3D::from_value(1.0).lerp(self.tint(), self.specular_tint);
+        (tinted * (self.specular * 0.08)).lerp(self.base_color, self.metallic)
+    }
+
+    // (alpha_x, alpha_y) of the specular lobe
+    fn specular_alpha(&self) -> (ScalarF, ScalarF) {
+        let aspect = (1.0 - 0.9 * self.anisotropic).sqrt();
+        let r2 = self.roughness * self.roughness;
+        ((r2 / aspect).max(1e-3), (r2 * aspect).max(1e-3))
+    }
+
+    fn clearcoat_alpha(&self) -> ScalarF {
+        0.1 + (0.001 - 0.1) * self.clearcoat_gloss
+    }
+
+    // anisotropic GGX in the local frame
+    fn gtr2(h: Vec3D, ax: ScalarF, ay: ScalarF) -> ScalarF {
+        let d = (h.x / ax).powi(2) + (h.y / ay).powi(2) + h.z * h.z;
+        FRAC_1_PI / (ax * ay * d * d)
+    }
+
+    // Smith masking for anisotropic GGX in the local frame
+    fn smith_g_aniso(w: Vec3D, ax: ScalarF, ay: ScalarF) -> ScalarF {
+        let cos2 = w.z * w.z;
+        if cos2 <= 0.0 {
+            return 0.0;
+        }
+        let tan2 = ((w.x * ax).powi(2) + (w.y * ay).powi(2)) / cos2;
+        2.0 / (1.0 + (1.0 + tan2).sqrt())
+    }
+
+    // Berry distribution used by the clearcoat
+    fn gtr1(cos_theta_h: ScalarF, alpha: ScalarF) -> ScalarF {
+        let alpha2 = alpha * alpha;
+        let t = 1.0 + (alpha2 - 1.0) * cos_theta_h * cos_theta_h;
+        (alpha2 - 1.0) / (PI * alpha2.ln() * t)
+    }
+
+    // probabilities of sampling the diffuse, specular and clearcoat lobes,
+    // roughly proportional to their reflectance seen from `cos_theta_v`
+    fn lobe_weights(&self, cos_theta_v: ScalarF) -> [ScalarF; 3] {
+        let fresnel = schlick_weight(cos_theta_v);
+        let diffuse = (1.0 - self.metallic) * luminance(self.base_color);
+        let specular = luminance(self.specular_color().lerp(Vec3D::from_value(1.0), fresnel));
+        let clearcoat = 0.25 * self.clearcoat * (0.04 + 0.96 * fresnel);
+        let total = diffuse + specular + clearcoat;
+        if total <= 0.0 {
+            return [1.0, 0.0, 0.0];
+        }
+        [diffuse / total, specular / total, clearcoat / total]
+    }
+
+    // value and solid angle density in the local frame (z is the facing
+    // normal, `v` looks towards the viewer and `l` towards the light)
+    fn evaluate(&self, v: Vec3D, l: Vec3D) -> (Vec3D, ScalarF) {
+        if v.z <= 1e-6 || l.z <= 1e-6 {
+            return (Vec3D::zero(), 0.0);
+        }
+        let h = (v + l).normalize();
+        let cos_d = l.dot(h);
+        let fl = schlick_weight(l.z);
+        let fv = schlick_weight(v.z);
+        let fh = schlick_weight(cos_d);
+
+        // diffuse, blended with the Hanrahan-Krueger subsurface approximation
+        let fd90 = 0.5 + 2.0 * cos_d * cos_d * self.roughness;
+        let fd = (1.0 + (fd90 - 1.0) * fl) * (1.0 + (fd90 - 1.0) * fv);
+        let fss90 = cos_d * cos_d * self.roughness;
+        let fss = (1.0 + (fss90 - 1.0) * fl) * (1.0 + (fss90 - 1.0) * fv);
+        let ss = 1.25 * (fss * (1.0 / (l.z + v.z) - 0.5) + 0.5);
+        let diffuse = self.base_color * (FRAC_1_PI * (fd + (ss - fd) * self.subsurface));
+
+        let sheen_color = Vec3D::from_value(1.0).lerp(self.tint(), self.sheen_tint);
+        let sheen = sheen_color * (fh * self.sheen);
+
+        let (ax, ay) = self.specular_alpha();
+        let spec_d = DisneyBsdf::gtr2(h, ax, ay);
+        let spec_f = self.specular_color().lerp(Vec3D::from_value(1.0), fh);
+        let spec_g = DisneyBsdf::smith_g_aniso(l, ax, ay) * DisneyBsdf::smith_g_aniso(v, ax, ay);
+        let specular = spec_f * (spec_d * spec_g / (4.0 * l.z * v.z));
+
+        let cc_alpha = self.clearcoat_alpha();
+        let cc_d = DisneyBsdf::gtr1(h.z, cc_alpha);
+        let cc_f = 0.04 + 0.96 * fh;
+        let cc_g = GgxMaterial::g1(l.z, 0.25) * GgxMaterial::g1(v.z, 0.25);
+        let clearcoat = 0.25 * self.clearcoat * cc_d * cc_f * cc_g / (4.0 * l.z * v.z);
+
+        let value =
+            (diffuse + sheen) * (1.0 - self.metallic) + specular + Vec3D::from_value(clearcoat);
+
+        let [w_diffuse, w_specular, w_clearcoat] = self.lobe_weights(v.z);
+        let jacobian = 4.0 * cos_d.abs();
+        let pdf = w_diffuse * l.z * FRAC_1_PI
+            + w_specular * spec_d * h.z / jacobian
+            + w_clearcoat * cc_d * h.z / jacobian;
+
+        (value, pdf)
+    }
+
+    fn sample_lobe(&self, lobe: DisneyLobe, v: Vec3D, u: ScalarF, w: ScalarF) -> Vec3D {
+        match lobe {
+            DisneyLobe::Diffuse => {
+                let r = u.sqrt();
+                let phi = 2.0 * PI * w;
+                Vec3D::new(r * phi.cos(), r * phi.sin(), (1.0 - u).max(0.0).sqrt())
+            }
+            DisneyLobe::Specular => {
+                // half vector proportional to D(h) * cos(theta_h)
+                let (ax, ay) = self.specular_alpha();
+                let phi = (ay * (2.0 * PI * w).sin()).atan2(ax * (2.0 * PI * w).cos());
+                let alpha2 = 1.0 / ((phi.cos() / ax).powi(2) + (phi.sin() / ay).powi(2));
+                let tan2_theta = alpha2 * u / (1.0 - u).max(1e-12);
+                let cos_theta = 1.0 / (1.0 + tan2_theta).sqrt();
+                let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+                let h = Vec3D::new(sin_theta * phi.cos(), sin_theta * phi.sin(), cos_theta);
+                reflect(-v, h)
+            }
+            DisneyLobe::Clearcoat => {
+                let alpha2 = self.clearcoat_alpha().powi(2);
+                let cos2_theta = (1.0 - alpha2.powf(1.0 - u)) / (1.0 - alpha2);
+                let cos_theta = cos2_theta.clamp(0.0, 1.0).sqrt();
+                let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+                let phi = 2.0 * PI * w;
+                let h = Vec3D::new(sin_theta * phi.cos(), sin_theta * phi.sin(), cos_theta);
+                reflect(-v, h)
+            }
+        }
+    }
+
+    fn to_local(ray: &Ray, frame: (Vec3D, Vec3D, Vec3D)) -> Vec3D {
+        let d = ray.direction.normalize();
+        Vec3D::new(d.dot(frame.0), d.dot(frame.1), d.dot(frame.2))
+    }
+}
+
+impl Material for DisneyBsdf {
+    fn scatter(
+        &self,
+        ray_in: &Ray,
+        hit: &HitRecord,
+        sampler: &mut dyn Sampler,
+    ) -> Option<ScatterResult> {
+        let frame = shading_frame(hit, facing_normal(ray_in, hit.normal));
+        let v = -DisneyBsdf::to_local(ray_in, frame);
+
+        let [w_diffuse, w_specular, _] = self.lobe_weights(v.z);
+        let r = sampler.get_1d();
+        let lobe = if r < w_diffuse {
+            DisneyLobe::Diffuse
+        } else if r < w_diffuse + w_specular {
+            DisneyLobe::Specular
+        } else {
+            DisneyLobe::Clearcoat
+        };
+        let (u, w) = sampler.get_2d();
+        let l = self.sample_lobe(lobe, v, u, w);
+        if l.z <= 0.0 {
+            return None;
+        }
+
+        // the density of the whole mixture keeps the estimate unbiased
+        // whichever lobe was picked
+        let (_, pdf) = self.evaluate(v, l);
+        let new_ray = spawn_ray(
+            hit.p,
+            hit.normal,
+            frame.0 * l.x + frame.1 * l.y + frame.2 * l.z,
+        );
+        Some(ScatterResult::new(new_ray, pdf))
+    }
+
+    fn bxdf(&self, ray_in: &Ray, ray_out: &Ray, hit: &HitRecord) -> Vec3D {
+        let frame = shading_frame(hit, facing_normal(ray_in, hit.normal));
+        let v = -DisneyBsdf::to_local(ray_in, frame);
+        let l = DisneyBsdf::to_local(ray_out, frame);
+        self.evaluate(v, l).0
+    }
+
+    fn pdf(&self, ray_in: &Ray, ray_out: &Ray, hit: &HitRecord) -> ScalarF {
+        let frame = shading_frame(hit, facing_normal(ray_in, hit.normal));
+        let v = -DisneyBsdf::to_local(ray_in, frame);
+        let l = DisneyBsdf::to_local(ray_out, frame);
+        self.evaluate(v, l).1
+    }
+}
+
+// subsurface scattering reduced to its total diffuse reflectance under
+// jensen et al.'s dipole model, i.e. light leaves where it entered. lacks
+// the blurring of a real bssrdf but gets the colour of translucent media
+#[derive(Debug)]
+pub struct DipoleSss {
+    reflectance: Vec3D,
+}
+
+#[derive(Deserialize)]
+pub struct DipoleSssConfig {
+    pub sigma_a: Vec3DConfig,
+    pub sigma_s_prime: Vec3DConfig,
+    #[serde(default = "default_dipole_eta")]
+    pub eta: f64,
+}
+
+fn default_dipole_eta() -> f64 {
+    1.3
+}
+
+impl DipoleSss {
+    // from the absorption and reduced scattering coefficients and the
+    // relative index of refraction
+    pub fn new(sigma_a: Vec3D, sigma_s_prime: Vec3D, eta: ScalarF) -> Self {
+        // fresnel diffuse reflectance fit, and the boundary term it implies
+        let fdr = -1.440 / (eta * eta) + 0.710 / eta + 0.668 + 0.0636 * eta;
+        let a = (1.0 + fdr) / (1.0 - fdr);
+        let rd = |sigma_a: ScalarF, sigma_s_prime: ScalarF| {
+            let albedo = sigma_s_prime / (sigma_s_prime + sigma_a);
+            let root = (3.0 * (1.0 - albedo)).sqrt();
+            0.5 * albedo * (1.0 + (-4.0 / 3.0 * a * root).exp()) * (-root).exp()
+        };
+        Self {
+            reflectance: Vec3D::new(
+                rd(sigma_a.x, sigma_s_prime.x),
+                rd(sigma_a.y, sigma_s_prime.y),
+                rd(sigma_a.z, sigma_s_prime.z),
+            ),
+        }
+    }
+}
+
+impl Material for DipoleSss {
+    fn scatter(
+        &self,
+        ray_in: &Ray,
+        hit: &HitRecord,
+        sampler: &mut dyn Sampler,
+    ) -> Option<ScatterResult> {
+        let normal = facing_normal(ray_in, hit.normal);
+        let (u, v) = sampler.get_2d();
+        let direction = spherical_to_world((1.0 - u).sqrt().acos(), 2.0 * PI * v, normal);
+        let pdf = direction.dot(normal) * FRAC_1_PI;
+        Some(ScatterResult::new(
+            spawn_ray(hit.p, hit.normal, direction),
+            pdf,
+        ))
+    }
+
+    fn bxdf(&self, ray_in: &Ray, ray_out: &Ray, hit: &HitRecord) -> Vec3D {
+        if !same_hemisphere(ray_in, ray_out, hit.normal) {
+            return Vec3D::zero();
+        }
+        self.reflectance * FRAC_1_PI
+    }
+
+    fn pdf(&self, ray_in: &Ray, ray_out: &Ray, hit: &HitRecord) -> ScalarF {
+        cosine_hemisphere_pdf(ray_out, facing_normal(ray_in, hit.normal))
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type")]
+pub enum MaterialConfig {
+    Emissive(EmissiveConfig),
+    Lambertian(LambertianConfig),
+    PhongSpecular(PhongSpecularConfig),
+    IdealReflector(IdealReflectorConfig),
+    IdealDielectric(IdealDielectricConfig),
+    #[serde(alias = "GgxSpecular")]
+    Ggx(GgxMaterialConfig),
+    NormalMappedLambertian(NormalMappedLambertianConfig),
+    NormalMapped(NormalMappedMaterialConfig),
+    AlphaMasked(AlphaMaskedMaterialConfig),
+    Disney(DisneyBsdfConfig),
+    DipoleSss(DipoleSssConfig),
+    ThinFilm(ThinFilmMaterialConfig),
+    Mix(MixMaterialConfig),
+    Clearcoat(ClearcoatMaterialConfig),
+    TwoSided(TwoSidedMaterialConfig),
+    Beckmann(BeckmannMaterialConfig),
+    VertexColor(VertexColorMaterialConfig),
+    OrenNayar(OrenNayarConfig),
+}
+
+impl MaterialConfig {
+    pub fn to_material(&self) -> Result<Arc<dyn Material>, RaytracerError> {
+        Ok(match self {
+            MaterialConfig::Emissive(config) => Arc::new(Emissive {
+                color: config.color.to_vec3(),
+            }),
+            MaterialConfig::Lambertian(config) => Arc::new(Lambertian {
+                albedo: config.to_albedo()?,
+            }),
+            MaterialConfig::PhongSpecular(config) => Arc::new(PhongSpecular {
+                specular: config.specular.to_vec3(),
+                shininess: config.shininess as ScalarF,
+            }),
+            MaterialConfig::IdealReflector(_) => Arc::new(IdealReflector {}),
+            MaterialConfig::IdealDielectric(config) => Arc::new(IdealDielectric {
+                ior: config.ior as ScalarF,
+            }),
+            MaterialConfig::Ggx(config) => Arc::new(GgxMaterial {
+                albedo: config.albedo.to_texture_or_value()?,
+                roughness: config.roughness.to_texture_or_value()?,
+                metallic: config.metallic.to_texture_or_value()?,
+            }),
+            MaterialConfig::NormalMappedLambertian(config) => Arc::new(NormalMappedLambertian {
+                albedo: config.albedo.to_texture()?,
+                normal_map: config.normal_map.to_texture()?,
+            }),
+            MaterialConfig::NormalMapped(config) => Arc::new(NormalMappedMaterial {
+                base: config.base.to_material()?,
+                normal_map: config.normal_map.to_texture()?,
+            }),
+            MaterialConfig::AlphaMasked(config) => Arc::new(AlphaMaskedMaterial {
+                base: config.base.to_material()?,
+                alpha: config.alpha as ScalarF,
+            }),
+            MaterialConfig::Disney(config) => Arc::new(DisneyBsdf {
+                base_color: config.base_color.to_vec3(),
+                metallic: config.metallic as ScalarF,
+                roughness: config.roughness as ScalarF,
+                specular: config.specular as ScalarF,
+                specular_tint: config.specular_tint as ScalarF,
+                anisotropic: config.anisotropic as ScalarF,
+                sheen: config.sheen as ScalarF,
+                sheen_tint: config.sheen_tint as ScalarF,
+                clearcoat: config.clearcoat as ScalarF,
+                clearcoat_gloss: config.clearcoat_gloss as ScalarF,
+                subsurface: config.subsurface as ScalarF,
+            }),
+            MaterialConfig::DipoleSss(config) => Arc::new(DipoleSss::new(
+                config.sigma_a.to_vec3(),
+                config.sigma_s_prime.to_vec3(),
+                config.eta as ScalarF,
+            )),
+            MaterialConfig::ThinFilm(config) => Arc::new(ThinFilmMaterial {
+                base: config.base.to_material()?,
+                film_ior: config.film_ior as ScalarF,
+                film_thickness_nm: config.film_thickness_nm as ScalarF,
+                substrate_ior: config.substrate_ior as ScalarF,
+            }),
+            MaterialConfig::Mix(config) => Arc::new(MixMaterial {
+                mat_a: config.mat_a.to_material()?,
+                mat_b: config.mat_b.to_material()?,
+                weight: config.weight.to_texture()?,
+            }),
+            MaterialConfig::Clearcoat(config) => Arc::new(ClearcoatMaterial {
+                base: config.base.to_material()?,
+                clearcoat_roughness: config.clearcoat_roughness as ScalarF,
+                clearcoat_ior: config.clearcoat_ior as ScalarF,
+            }),
+            MaterialConfig::TwoSided(config) => Arc::new(TwoSidedMaterial {
+                front: config.front.to_material()?,
+                back: config.back.to_material()?,
+            }),
+            MaterialConfig::Beckmann(config) => Arc::new(BeckmannMaterial {
+                albedo: config.albedo.to_vec3(),
+                roughness: config.roughness as ScalarF,
+            }),
+            MaterialConfig::VertexColor(config) => Arc::new(VertexColorMaterial {
+                fallback: Lambertian {
+                    albedo: Arc::new(ConstantTexture {
+                        color: config
+                            .fallback
+                            .as_ref()
+                            .map_or(Vec3D::from_value(0.5), |fallback| fallback.to_vec3()),
+                    }),
+                },
+            }),
+            MaterialConfig::OrenNayar(config) => Arc::new(OrenNayar {
+                albedo: config.albedo.to_vec3(),
+                roughness: config.roughness as ScalarF,
+            }),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::Point3D;
+    use crate::math::{tolerance, vec3_approx_eq};
+    use crate::sampler::RandomSampler;
+    use crate::shapes::{Shape, Sphere, Triangle};
+    use cgmath::EuclideanSpace;
+
+    // roughness grows linearly with u
+    #[derive(Debug)]
+    struct RampTexture {}
+
+    impl Texture for RampTexture {
+        fn sample(&self, uv: (ScalarF, ScalarF)) -> Vec3D {
+            Vec3D::from_value(uv.0)
+        }
+    }
+
+    #[test]
+    fn test_ggx_roughness_texture() {
+        let sphere = Sphere {
+            center: Point3D::origin(),
+            radius: 1.0,
+        };
+        let material = GgxMaterial {
+            albedo: TextureOrValue::Value(Vec3D::from_value(0.9)),
+            roughness: TextureOrValue::Texture(Arc::new(RampTexture {})),
+            metallic: TextureOrValue::Value(1.0),
+        };
+
+        // ratio of the reflection 15 degrees off the mirror direction to the
+        // peak, i.e. how wide the highlight is at the point hit at `azimuth`
+        let highlight_width = |azimuth: ScalarF| {
+            let p = Vec3D::new(azimuth.cos(), 0.0, azimuth.sin());
+            // slightly off the center, rays through it are missed by rounding
+            let ray_in = Ray {
+                origin: Point3D::from_vec(p * 3.0 + Vec3D::new(0.0, 1e-3, 0.0)),
+                direction: -p,
+            };
+            let hit = sphere.intersect(&ray_in, 0.001, ScalarF::MAX).unwrap();
+            let mirror = reflect(ray_in.direction, hit.normal);
+            let out = |angle: ScalarF| Ray {
+                origin: hit.p,
+                direction: mirror * angle.cos() + Vec3D::unit_y() * angle.sin(),
+            };
+            let peak = material.bxdf(&ray_in, &out(0.0), &hit).x;
+            let off = material
+                .bxdf(&ray_in, &out(ScalarF::to_radians(15.0)), &hit)
+                .x;
+            (hit.uv.unwrap().0, off / peak)
+        };
+
+        let (u_smooth, width_smooth) = highlight_width(0.8 * PI);
+        let (u_rough, width_rough) = highlight_width(-0.8 * PI);
+        assert!((u_smooth - 0.1).abs() < tolerance(1e-6));
+        assert!((u_rough - 0.9).abs() < tolerance(1e-6));
+        assert!(width_smooth < 1e-3);
+        assert!(width_rough > 0.5);
+    }
+
+    #[test]
+    fn test_normal_map_flat() {
+        let material = NormalMappedLambertian {
+            albedo: Arc::new(ConstantTexture {
+                color: Vec3D::from_value(0.5),
+            }),
+            normal_map: Arc::new(ConstantTexture {
+                color: Vec3D::new(0.5, 0.5, 1.0),
+            }),
+        };
+        let sphere = Sphere {
+            center: Point3D::origin(),
+            radius: 1.0,
+        };
+
+        for direction in [
+            Vec3D::new(1.0, 0.2, 0.1),
+            Vec3D::new(-0.3, 0.4, 1.0),
+            Vec3D::new(0.0, 1.0, 0.0), // next to the pole
+        ] {
+            let direction = direction.normalize();
+            let ray = Ray {
+                origin: Point3D::from_vec(direction * 3.0 + Vec3D::new(1e-3, 0.0, 0.0)),
+                direction: -direction,
+            };
+            let hit = sphere.intersect(&ray, 0.001, ScalarF::MAX).unwrap();
+            assert!(vec3_approx_eq(
+                material.perturbed_normal(&hit),
+                hit.normal,
+                tolerance(1e-9)
+            ));
+        }
+    }
+
+    #[test]
+    fn test_normal_mapped_material() {
+        let vertices = [
+            Point3D::new(0.0, 0.0, 0.0),
+            Point3D::new(2.0, 0.0, 0.0),
+            Point3D::new(0.0, 1.0, 0.0),
+        ];
+        let base: Arc<dyn Material> = Arc::new(Lambertian {
+            albedo: Arc::new(ConstantTexture {
+                color: Vec3D::from_value(0.5),
+            }),
+        });
+        let normal_mapped = |encoded: Vec3D| NormalMappedMaterial {
+            base: base.clone(),
+            normal_map: Arc::new(ConstantTexture { color: encoded }),
+        };
+        let ray_in = Ray {
+            origin: Point3D::new(0.5, 0.25, 1.0),
+            direction: Vec3D::new(0.0, 0.0, -1.0),
+        };
+        let ray_out = |direction: Vec3D| Ray {
+            origin: Point3D::new(0.5, 0.25, 0.0),
+            direction: direction.normalize(),
+        };
+
+        // straight up in tangent space keeps the base material as is
+        let triangle = Triangle {
+            vertices,
+            uvs: Some([(0.0, 0.0), (1.0, 0.0), (0.0, 1.0)]),
+        };
+        let hit = triangle.intersect(&ray_in, 0.001, ScalarF::MAX).unwrap();
+        let flat = normal_mapped(Vec3D::new(0.5, 0.5, 1.0));
+        for direction in [Vec3D::new(0.3, 0.1, 1.0), Vec3D::new(-1.0, 0.0, 0.1)] {
+            assert!(vec3_approx_eq(
+                flat.bxdf(&ray_in, &ray_out(direction), &hit),
+                base.bxdf(&ray_in, &ray_out(direction), &hit),
+                tolerance(1e-9)
+            ));
+        }
+
+        // tilted towards +v, also when the uvs are mirrored
+        let tilted = normal_mapped(Vec3D::new(0.5, 0.8, 0.9));
+        for uvs in [
+            [(0.0, 0.0), (1.0, 0.0), (0.0, 1.0)],
+            [(1.0, 0.0), (0.0, 0.0), (1.0, 1.0)],
+        ] {
+            let triangle = Triangle {
+                vertices,
+                uvs: Some(uvs),
+            };
+            let hit = triangle.intersect(&ray_in, 0.001, ScalarF::MAX).unwrap();
+            assert!(vec3_approx_eq(
+                tilted.shading_hit(&hit).normal,
+                Vec3D::new(0.0, 0.6, 0.8),
+                tolerance(1e-9)
+            ));
+            // above the surface but below the shading normal's hemisphere
+            let grazing = ray_out(Vec3D::new(0.0, -1.0, 0.1));
+            assert!(!base.bxdf(&ray_in, &grazing, &hit).is_zero());
+            assert!(tilted.bxdf(&ray_in, &grazing, &hit).is_zero());
+        }
+    }
+
+    fn disney_test_materials() -> Vec<DisneyBsdf> {
+        let base = DisneyBsdf {
+            base_color: Vec3D::new(0.8, 0.4, 0.2),
+            metallic: 0.0,
+            roughness: 0.5,
+            specular: 0.5,
+            specular_tint: 0.0,
+            anisotropic: 0.0,
+            sheen: 0.0,
+            sheen_tint: 0.5,
+            clearcoat: 0.0,
+            clearcoat_gloss: 1.0,
+            subsurface: 0.0,
+        };
+        vec![
+            base.clone(),
+            DisneyBsdf {
+                metallic: 1.0,
+                roughness: 0.3,
+                anisotropic: 0.8,
+                ..base.clone()
+            },
+            DisneyBsdf {
+                roughness: 0.8,
+                sheen: 1.0,
+                clearcoat: 1.0,
+                clearcoat_gloss: 0.5,
+                subsurface: 0.7,
+                ..base
+            },
+        ]
+    }
+
+    #[test]
+    fn test_disney_sampling() {
+        let sphere = Sphere {
+            center: Point3D::origin(),
+            radius: 1.0,
+        };
+        let direction = Vec3D::new(1.0, 0.5, 0.3).normalize();
+        let ray_in = Ray {
+            origin: Point3D::from_vec(direction * 3.0 + Vec3D::new(0.0, 1e-3, 0.0)),
+            direction: -direction,
+        };
+        let hit = sphere.intersect(&ray_in, 0.001, ScalarF::MAX).unwrap();
+        crate::rng::init_thread_local_rng(3);
+        let mut sampler = RandomSampler::new(1);
+        let n = 100_000;
+
+        for material in disney_test_materials() {
+            // directional albedo, importance sampled
+            let mut sampled = Vec3D::zero();
+            for _ in 0..n {
+                if let Some(result) = material.scatter(&ray_in, &hit, &mut sampler) {
+                    let pdf = material.pdf(&ray_in, &result.ray, &hit);
+                    assert!((pdf - result.pdf).abs() <= tolerance(1e-9) * pdf.max(1.0));
+                    let cos_theta = result.ray.direction.dot(hit.normal).abs();
+                    sampled += material.bxdf(&ray_in, &result.ray, &hit) * cos_theta / result.pdf;
+                }
+            }
+            sampled /= n as ScalarF;
+
+            // and with uniform hemisphere samples
+            let mut uniform = Vec3D::zero();
+            for _ in 0..n {
+                let (u, v) = sampler.get_2d();
+                let ray_out = Ray {
+                    origin: hit.p,
+                    direction: spherical_to_world(u.acos(), 2.0 * PI * v, hit.normal),
+                };
+                let cos_theta = ray_out.direction.dot(hit.normal);
+                uniform += material.bxdf(&ray_in, &ray_out, &hit) * (cos_theta * 2.0 * PI);
+            }
+            uniform /= n as ScalarF;
+
+            assert!(
+                vec3_approx_eq(sampled, uniform, 0.03),
+                "sampled: {:?}, uniform: {:?}",
+                sampled,
+                uniform
+            );
+            assert!(sampled.x < 1.0 && sampled.y < 1.0 && sampled.z < 1.0);
+        }
+    }
+
+    #[test]
+    fn test_dipole_sss() {
+        let sphere = Sphere {
+            center: Point3D::origin(),
+            radius: 1.0,
+        };
+        let direction = Vec3D::new(0.3, 1.0, -0.2).normalize();
+        let ray_in = Ray {
+            origin: Point3D::from_vec(direction * 3.0 + Vec3D::new(1e-3, 0.0, 0.0)),
+            direction: -direction,
+        };
+        let hit = sphere.intersect(&ray_in, 0.001, ScalarF::MAX).unwrap();
+        crate::rng::init_thread_local_rng(5);
+        let mut sampler = RandomSampler::new(1);
+
+        // skim milk like coefficients in mm^-1, then with a third of the
+        // absorption
+        let sigma_s_prime = Vec3D::new(0.70, 1.22, 1.90);
+        let sigma_a = Vec3D::new(0.0014, 0.0025, 0.0142);
+        let mut previous: Option<Vec3D> = None;
+        for scale in [3.0, 1.0, 1.0 / 3.0] {
+            let material = DipoleSss::new(sigma_a * scale, sigma_s_prime, 1.3);
+
+            // directional albedo never exceeds one
+            let n = 10_000;
+            let mut albedo = Vec3D::zero();
+            for _ in 0..n {
+                let result = material.scatter(&ray_in, &hit, &mut sampler).unwrap();
+                let cos_theta = result.ray.direction.dot(hit.normal).abs();
+                albedo += material.bxdf(&ray_in, &result.ray, &hit) * cos_theta / result.pdf;
+            }
+            albedo /= n as ScalarF;
+            assert!(albedo.x <= 1.0 && albedo.y <= 1.0 && albedo.z <= 1.0);
+            assert!(vec3_approx_eq(
+                albedo,
+                material.reflectance,
+                tolerance(1e-9)
+            ));
+
+            // and less absorption lets more light back out
+            if let Some(previous) = previous {
+                assert!(albedo.x > previous.x && albedo.y > previous.y && albedo.z > previous.z);
+            }
+            previous = Some(albedo);
+        }
+    }
+
+    #[test]
+    fn test_thin_film() {
+        let sphere = Sphere {
+            center: Point3D::origin(),
+            radius: 1.0,
+        };
+        let direction = Vec3D::new(0.2, 1.0, 0.1).normalize();
+        let ray_in = Ray {
+            origin: Point3D::from_vec(direction * 3.0 + Vec3D::new(1e-3, 0.0, 0.0)),
+            direction: -direction,
+        };
+        let hit = sphere.intersect(&ray_in, 0.001, ScalarF::MAX).unwrap();
+        let ray_out = Ray {
+            origin: hit.p,
+            direction: reflect(ray_in.direction, hit.normal),
+        };
+        let base: Arc<dyn Material> = Arc::new(GgxMaterial {
+            albedo: TextureOrValue::Value(Vec3D::from_value(0.9)),
+            roughness: TextureOrValue::Value(0.3),
+            metallic: TextureOrValue::Value(1.0),
+        });
+        let coated = |film_thickness_nm: ScalarF| ThinFilmMaterial {
+            base: base.clone(),
+            film_ior: 1.33,
+            film_thickness_nm,
+            substrate_ior: 1.5,
+        };
+
+        // no film, no change
+        let expected = base.bxdf(&ray_in, &ray_out, &hit);
+        let bxdf = coated(0.0).bxdf(&ray_in, &ray_out, &hit);
+        assert!(vec3_approx_eq(bxdf, expected, tolerance(1e-12)));
+
+        // red goes from its brightest to its darkest when the round trip
+        // through the film grows by half a wavelength
+        let cos_i = (-ray_in.direction).dot(hit.normal);
+        let cos_film = (1.0 - (1.0 - cos_i * cos_i) / (1.33 * 1.33)).sqrt();
+        let half_wave = 650.0 / (2.0 * 1.33 * cos_film);
+        let maximum = coated(half_wave).bxdf(&ray_in, &ray_out, &hit);
+        let minimum = coated(1.5 * half_wave).bxdf(&ray_in, &ray_out, &hit);
+        assert!((maximum.x - expected.x).abs() < tolerance(1e-9));
+        assert!(minimum.x < 0.5 * maximum.x);
+        // while the other channels are out of step with it
+        assert!((minimum.y - maximum.y).abs() > 0.05 * expected.y);
+    }
+
+    #[test]
+    fn test_mix_material() {
+        let sphere = Sphere {
+            center: Point3D::origin(),
+            radius: 1.0,
+        };
+        let direction = Vec3D::new(0.5, 1.0, 0.2).normalize();
+        let ray_in = Ray {
+            origin: Point3D::from_vec(direction * 3.0 + Vec3D::new(1e-3, 0.0, 0.0)),
+            direction: -direction,
+        };
+        let hit = sphere.intersect(&ray_in, 0.001, ScalarF::MAX).unwrap();
+        let diffuse: Arc<dyn Material> = Arc::new(Lambertian {
+            albedo: Arc::new(ConstantTexture {
+                color: Vec3D::new(0.9, 0.5, 0.2),
+            }),
+        });
+        let metal: Arc<dyn Material> = Arc::new(GgxMaterial {
+            albedo: TextureOrValue::Value(Vec3D::from_value(0.95)),
+            roughness: TextureOrValue::Value(0.2),
+            metallic: TextureOrValue::Value(1.0),
+        });
+        let mix = |w: ScalarF| MixMaterial {
+            mat_a: diffuse.clone(),
+            mat_b: metal.clone(),
+            weight: Arc::new(ConstantTexture {
+                color: Vec3D::from_value(w),
+            }),
+        };
+        crate::rng::init_thread_local_rng(9);
+        let mut sampler = RandomSampler::new(1);
+
+        // either end is just one of the two materials
+        for _ in 0..100 {
+            let (u, v) = sampler.get_2d();
+            let ray_out = Ray {
+                origin: hit.p,
+                direction: spherical_to_world(u.acos(), 2.0 * PI * v, hit.normal),
+            };
+            for (w, material) in [(0.0, &diffuse), (1.0, &metal)] {
+                let bxdf = mix(w).bxdf(&ray_in, &ray_out, &hit);
+                assert!(vec3_approx_eq(
+                    bxdf,
+                    material.bxdf(&ray_in, &ray_out, &hit),
+                    tolerance(1e-12)
+                ));
+                let pdf = mix(w).pdf(&ray_in, &ray_out, &hit);
+                assert!((pdf - material.pdf(&ray_in, &ray_out, &hit)).abs() < tolerance(1e-12));
+            }
+        }
+
+        // in between the albedo is the blend of both albedos, below one
+        let albedo = |material: &dyn Material, sampler: &mut dyn Sampler| {
+            let n = 50_000;
+            let mut albedo = Vec3D::zero();
+            for _ in 0..n {
+                if let Some(result) = material.scatter(&ray_in, &hit, sampler) {
+                    let cos_theta = result.ray.direction.dot(hit.normal).abs();
+                    albedo += material.bxdf(&ray_in, &result.ray, &hit) * cos_theta / result.pdf;
+                }
+            }
+            albedo / n as ScalarF
+        };
+        let blended = albedo(&mix(0.3), &mut sampler);
+        let expected = albedo(&*diffuse, &mut sampler) * 0.7 + albedo(&*metal, &mut sampler) * 0.3;
+        assert!(
+            vec3_approx_eq(blended, expected, 0.02),
+            "blended: {:?}, expected: {:?}",
+            blended,
+            expected
+        );
+        assert!(blended.x <= 1.0 && blended.y <= 1.0 && blended.z <= 1.0);
+    }
+
+    #[test]
+    fn test_clearcoat() {
+        let sphere = Sphere {
+            center: Point3D::origin(),
+            radius: 1.0,
+        };
+        let coated = |color: ScalarF, clearcoat_ior: ScalarF| ClearcoatMaterial {
+            base: Arc::new(Lambertian {
+                albedo: Arc::new(ConstantTexture {
+                    color: Vec3D::from_value(color),
+                }),
+            }),
+            clearcoat_roughness: 0.1,
+            clearcoat_ior,
+        };
+
+        // at grazing angles the coat reflects nearly everything, whatever
+        // lies below it
+        let (black, white) = (coated(0.0, 1.5), coated(1.0, 1.5));
+        assert!(white.coat_fresnel(1e-4) > 0.999);
+        let direction = Vec3D::new(1.0, 0.0, 0.0);
+        let ray_in = Ray {
+            origin: Point3D::new(-3.0, 1.0 - 1e-5, 0.0),
+            direction,
+        };
+        let hit = sphere.intersect(&ray_in, 0.001, ScalarF::MAX).unwrap();
+        let normal = facing_normal(&ray_in, hit.normal);
+        let ray_out = Ray {
+            origin: hit.p,
+            direction: reflect(direction, normal),
+        };
+        let (dark, bright) = (
+            black.bxdf(&ray_in, &ray_out, &hit),
+            white.bxdf(&ray_in, &ray_out, &hit),
+        );
+        assert!(vec3_approx_eq(dark, bright, 0.02 * bright.x));
+        assert!(dark.x > 0.0);
+
+        // head on a low index coat barely covers the base
+        let faint = coated(0.8, 1.1);
+        let ray_in = Ray {
+            origin: Point3D::new(1e-3, 3.0, 0.0),
+            direction: Vec3D::new(0.0, -1.0, 0.0),
+        };
+        let hit = sphere.intersect(&ray_in, 0.001, ScalarF::MAX).unwrap();
+        let ray_out = Ray {
+            origin: hit.p,
+            direction: Vec3D::new(0.6, 0.8, 0.0),
+        };
+        let bxdf = faint.bxdf(&ray_in, &ray_out, &hit);
+        let base = faint.base.bxdf(&ray_in, &ray_out, &hit);
+        assert!(faint.coat_fresnel_at(&ray_in, &hit) < 0.01);
+        assert!(vec3_approx_eq(bxdf, base, 0.01 * base.x));
+
+        // sampling agrees with the density and doesn't create energy
+        crate::rng::init_thread_local_rng(10);
+        let mut sampler = RandomSampler::new(1);
+        let material = coated(0.9, 1.5);
+        let n = 20_000;
+        let mut albedo = 0.0;
+        for _ in 0..n {
+            if let Some(result) = material.scatter(&ray_in, &hit, &mut sampler) {
+                let pdf = material.pdf(&ray_in, &result.ray, &hit);
+                assert!((pdf - result.pdf).abs() <= tolerance(1e-9) * pdf.max(1.0));
+                let cos_theta = result.ray.direction.dot(hit.normal).abs();
+                albedo += material.bxdf(&ray_in, &result.ray, &hit).x * cos_theta / result.pdf;
+            }
+        }
+        albedo /= n as ScalarF;
+        assert!(albedo > 0.8 && albedo < 1.0, "albedo: {}", albedo);
+    }
+
+    #[test]
+    fn test_two_sided() {
+        let sphere = Sphere {
+            center: Point3D::origin(),
+            radius: 1.0,
+        };
+        let (red, blue) = (Vec3D::new(1.0, 0.0, 0.0), Vec3D::new(0.0, 0.0, 1.0));
+        let material = TwoSidedMaterial {
+            front: Arc::new(Emissive { color: red }),
+            back: Arc::new(Emissive { color: blue }),
+        };
+
+        let outside = Ray {
+            origin: Point3D::new(1e-3, 3.0, 0.0),
+            direction: Vec3D::new(0.0, -1.0, 0.0),
+        };
+        let hit = sphere.intersect(&outside, 0.001, ScalarF::MAX).unwrap();
+        assert!(vec3_approx_eq(
+            material.emitted(&outside, &hit),
+            red,
+            tolerance(1e-12)
+        ));
+
+        let inside = Ray {
+            origin: Point3D::origin(),
+            direction: Vec3D::new(0.0, -1.0, 0.0),
+        };
+        let hit = sphere.intersect(&inside, 0.001, ScalarF::MAX).unwrap();
+        assert!(vec3_approx_eq(
+            material.emitted(&inside, &hit),
+            blue,
+            tolerance(1e-12)
+        ));
+    }
+
+    #[test]
+    fn test_beckmann() {
+        let sphere = Sphere {
+            center: Point3D::origin(),
+            radius: 1.0,
+        };
+        crate::rng::init_thread_local_rng(11);
+        let mut sampler = RandomSampler::new(1);
+
+        // without roughness every sample is the mirror direction
+        let mirror = BeckmannMaterial {
+            albedo: Vec3D::from_value(1.0),
+            roughness: 0.0,
+        };
+        let ray_in = Ray {
+            origin: Point3D::new(2.0, 3.0, 1e-3),
+            direction: Vec3D::new(-2.0, -2.0, 0.0).normalize(),
+        };
+        let hit = sphere.intersect(&ray_in, 0.001, ScalarF::MAX).unwrap();
+        let expected = reflect(ray_in.direction, hit.normal);
+        for _ in 0..100 {
+            let result = mirror.scatter(&ray_in, &hit, &mut sampler).unwrap();
+            assert!(vec3_approx_eq(result.ray.direction, expected, 1e-3));
+        }
+
+        // a rough white surface doesn't reflect more than it receives
+        let material = BeckmannMaterial {
+            albedo: Vec3D::from_value(1.0),
+            roughness: 0.6,
+        };
+        for _ in 0..100 {
+            let (u, v) = sampler.get_2d();
+            let direction = spherical_to_world(u.acos(), 2.0 * PI * v, Vec3D::unit_y());
+            let ray_in = Ray {
+                origin: Point3D::from_vec(direction * 3.0 + Vec3D::new(1e-3, 0.0, 0.0)),
+                direction: -direction,
+            };
+            let hit = match sphere.intersect(&ray_in, 0.001, ScalarF::MAX) {
+                Some(hit) => hit,
+                None => continue,
+            };
+            let n = 2000;
+            let mut albedo = 0.0;
+            for _ in 0..n {
+                if let Some(result) = material.scatter(&ray_in, &hit, &mut sampler) {
+                    let cos_theta = result.ray.direction.dot(hit.normal).abs();
+                    albedo += material.bxdf(&ray_in, &result.ray, &hit).x * cos_theta / result.pdf;
+                }
+            }
+            albedo /= n as ScalarF;
+            assert!(albedo <= 1.02, "albedo: {}", albedo);
+        }
+    }
+    #[test]
+    fn test_vertex_color_material() {
+        let sphere = Sphere {
+            center: Point3D::origin(),
+            radius: 1.0,
+        };
+        let config: MaterialConfig = toml::from_str("type = \"VertexColor\"").unwrap();
+        let material = config.to_material().unwrap();
+        let ray_in = Ray {
+            origin: Point3D::new(1e-3, 3.0, 0.0),
+            direction: Vec3D::new(0.0, -1.0, 0.0),
+        };
+        let ray_out = Ray {
+            origin: Point3D::new(0.0, 1.0, 0.0),
+            direction: Vec3D::new(0.0, 1.0, 0.0),
+        };
+        let mut hit = sphere.intersect(&ray_in, 0.001, ScalarF::MAX).unwrap();
+        let grey = material.bxdf(&ray_in, &ray_out, &hit);
+        assert!(vec3_approx_eq(
+            grey,
+            Vec3D::from_value(0.5 * FRAC_1_PI),
+            tolerance(1e-12)
+        ));
+
+        hit.vertex_color = Some(Vec3D::new(0.2, 0.4, 0.6));
+        let color = material.bxdf(&ray_in, &ray_out, &hit);
+        assert!(vec3_approx_eq(
+            color,
+            Vec3D::new(0.2, 0.4, 0.6) * FRAC_1_PI,
+            tolerance(1e-12)
+        ));
+    }
+
+    #[test]
+    fn test_oren_nayar() {
+        let sphere = Sphere {
+            center: Point3D::origin(),
+            radius: 1.0,
+        };
+        let albedo = Vec3D::new(0.8, 0.6, 0.4);
+        let smooth = OrenNayar {
+            albedo,
+            roughness: 0.0,
+        };
+        let rough = OrenNayar {
+            albedo,
+            roughness: 0.5,
+        };
+        let lambertian = Lambertian {
+            albedo: Arc::new(ConstantTexture { color: albedo }),
+        };
+
+        crate::rng::init_thread_local_rng(12);
+        let mut sampler = RandomSampler::new(1);
+        for _ in 0..200 {
+            let (u, v) = sampler.get_2d();
+            let direction = spherical_to_world(u.acos(), 2.0 * PI * v, Vec3D::unit_y());
+            let ray_in = Ray {
+                origin: Point3D::from_vec(direction * 3.0 + Vec3D::new(1e-3, 0.0, 0.0)),
+                direction: -direction,
+            };
+            let hit = match sphere.intersect(&ray_in, 0.001, ScalarF::MAX) {
+                Some(hit) => hit,
+                None => continue,
+            };
+            let ray_out = smooth.scatter(&ray_in, &hit, &mut sampler).unwrap();
+
+            // without roughness it is lambertian
+            let expected = lambertian.bxdf(&ray_in, &ray_out.ray, &hit);
+            assert!(vec3_approx_eq(
+                smooth.bxdf(&ray_in, &ray_out.ray, &hit),
+                expected,
+                tolerance(1e-12)
+            ));
+
+            let value = rough.bxdf(&ray_in, &ray_out.ray, &hit);
+            assert!(value.is_finite() && value.x >= 0.0);
+        }
+
+        // finite at grazing angles, and brighter than lambertian when
+        // looking back towards the light
+        let hit = sphere
+            .intersect(
+                &Ray {
+                    origin: Point3D::new(0.0, 3.0, 0.0),
+                    direction: Vec3D::new(0.0, -1.0, 0.0),
+                },
+                0.001,
+                ScalarF::MAX,
+            )
+            .unwrap();
+        let grazing = Vec3D::new(1.0, 1e-9, 0.0).normalize();
+        let ray_in = Ray {
+            origin: hit.p + grazing,
+            direction: -grazing,
+        };
+        let ray_out = Ray {
+            origin: hit.p,
+            direction: grazing,
+        };
+        let value = rough.bxdf(&ray_in, &ray_out, &hit);
+        assert!(value.is_finite() && value.x > albedo.x * FRAC_1_PI);
+    }
+
+    #[test]
+    fn test_ggx_metallic() {
+        let sphere = Sphere {
+            center: Point3D::origin(),
+            radius: 1.0,
+        };
+        crate::rng::init_thread_local_rng(12);
+        let mut sampler = RandomSampler::new(1);
+
+        // without roughness every sample is close to the mirror direction,
+        // GGX has long tails even then
+        let mirror = GgxMaterial {
+            albedo: TextureOrValue::Value(Vec3D::from_value(1.0)),
+            roughness: TextureOrValue::Value(0.0),
+            metallic: TextureOrValue::Value(1.0),
+        };
+        let ray_in = Ray {
+            origin: Point3D::new(2.0, 3.0, 1e-3),
+            direction: Vec3D::new(-2.0, -2.0, 0.0).normalize(),
+        };
+        let hit = sphere.intersect(&ray_in, 0.001, ScalarF::MAX).unwrap();
+        let expected = reflect(ray_in.direction, hit.normal);
+        for _ in 0..100 {
+            let result = mirror.scatter(&ray_in, &hit, &mut sampler).unwrap();
+            assert!(vec3_approx_eq(result.ray.direction, expected, 1e-2));
+            assert!(result.pdf.is_finite());
+        }
+
+        for metallic in [0.0, 1.0] {
+            let material = GgxMaterial {
+                albedo: TextureOrValue::Value(Vec3D::from_value(1.0)),
+                roughness: TextureOrValue::Value(0.5),
+                metallic: TextureOrValue::Value(metallic),
+            };
+            for _ in 0..20 {
+                let (u, v) = sampler.get_2d();
+                let direction = spherical_to_world(u.acos(), 2.0 * PI * v, Vec3D::unit_y());
+                let ray_in = Ray {
+                    origin: Point3D::from_vec(direction * 3.0 + Vec3D::new(1e-3, 0.0, 0.0)),
+                    direction: -direction,
+                };
+                let hit = match sphere.intersect(&ray_in, 0.001, ScalarF::MAX) {
+                    Some(hit) => hit,
+                    None => continue,
+                };
+                let n = 2000;
+                let mut albedo = 0.0;
+                for _ in 0..n {
+                    if let Some(result) = material.scatter(&ray_in, &hit, &mut sampler) {
+                        let pdf = material.pdf(&ray_in, &result.ray, &hit);
+                        assert!((result.pdf - pdf).abs() < tolerance(1e-9) * pdf.max(1.0));
+                        let cos_theta = result.ray.direction.dot(hit.normal).abs();
+                        albedo +=
+                            material.bxdf(&ray_in, &result.ray, &hit).x * cos_theta / result.pdf;
+                    }
+                }
+                albedo /= n as ScalarF;
+                // a white metal loses only what the single scattering model
+                // drops, a dielectric keeps little more than its 4%
+                if metallic == 1.0 {
+                    assert!(albedo <= 1.02 && albedo > 0.8, "albedo: {}", albedo);
+                } else {
+                    assert!(albedo < 0.3, "albedo: {}", albedo);
+                }
+            }
+        }
+
+        // configs without `metallic` are metals
+        let config: MaterialConfig = toml::from_str(
+            "type = \"Ggx\"\nalbedo = { x = 0.5, y = 0.5, z = 0.5 }\nroughness = 0.5",
+        )
+        .unwrap();
+        let MaterialConfig::Ggx(config) = config else {
+            panic!("expected a Ggx config")
+        };
+        assert!(matches!(config.metallic, TextureOrValueConfig::Value(m) if m == 1.0));
+        // scenes written for GgxSpecular still load
+        let config: MaterialConfig = toml::from_str(
+            "type = \"GgxSpecular\"\nalbedo = { x = 0.5, y = 0.5, z = 0.5 }\nroughness = 0.5",
+        )
+        .unwrap();
+        assert!(matches!(config, MaterialConfig::Ggx(_)));
+    }
+
+    #[test]
+    fn test_material_config_errors() {
+        let material = |config: &str| {
+            toml::from_str::<MaterialConfig>(config)
+                .unwrap()
+                .to_material()
+        };
+        assert!(material(r#"type = "Lambertian""#)
+            .is_err_and(|e| matches!(e, RaytracerError::ConfigParse(_))));
+        assert!(material(
+            r#"
+            type = "Lambertian"
+            albedo_texture = "missing_texture.png"
+            "#
+        )
+        .is_err_and(|e| matches!(e, RaytracerError::SceneLoad(_))));
+        // nested materials and textures hand their errors up
+        assert!(material(
+            r#"
+            type = "Mix"
+            mat_a = { type = "Lambertian", albedo = { x = 0.5, y = 0.5, z = 0.5 } }
+            mat_b = { type = "Lambertian" }
+            weight = { type = "Constant", color = { x = 0.5, y = 0.5, z = 0.5 } }
+            "#
+        )
+        .is_err());
+        assert!(material(
+            r#"
+            type = "Ggx"
+            albedo = { type = "Image", path = "missing_texture.png" }
+            roughness = 0.5
+            "#
+        )
+        .is_err_and(|e| matches!(e, RaytracerError::SceneLoad(_))));
     }
 }
@@ -1,11 +1,23 @@
-use cgmath::{ElementWise, InnerSpace, Matrix4, Point2, Point3, Vector3, Vector4};
+use cgmath::{Deg, InnerSpace, Matrix4, Point2, Point3, SquareMatrix, Vector3, Vector4, Zero};
 use serde::Deserialize;
 
-pub type Vec3D = Vector3<f64>;
-pub type Vec4D = Vector4<f64>;
+// precision of the arithmetic, the `f32_math` feature trades accuracy for
+// speed. configs are always read as f64 and converted when loaded
+#[cfg(not(feature = "f32_math"))]
+pub type ScalarF = f64;
+#[cfg(feature = "f32_math")]
+pub type ScalarF = f32;
+#[cfg(feature = "f32_math")]
+pub use std::f32::consts;
+#[cfg(not(feature = "f32_math"))]
+pub use std::f64::consts;
+
+pub type Vec3D = Vector3<ScalarF>;
+pub type Vec4D = Vector4<ScalarF>;
+pub type Point2D = Point2<ScalarF>;
 pub type Point2U = Point2<u32>;
-pub type Point3D = Point3<f64>;
-pub type Matrix4D = Matrix4<f64>;
+pub type Point3D = Point3<ScalarF>;
+pub type Matrix4D = Matrix4<ScalarF>;
 
 #[derive(Deserialize)]
 pub struct Vec3DConfig {
@@ -15,8 +27,12 @@ pub struct Vec3DConfig {
 }
 
 impl Vec3DConfig {
+    pub fn new(x: f64, y: f64, z: f64) -> Self {
+        Self { x, y, z }
+    }
+
     pub fn to_vec3(&self) -> Vec3D {
-        Vec3D::new(self.x, self.y, self.z)
+        Vec3D::new(self.x as ScalarF, self.y as ScalarF, self.z as ScalarF)
     }
 }
 
@@ -29,7 +45,7 @@ pub struct Point3DConfig {
 
 impl Point3DConfig {
     pub fn to_point(&self) -> Point3D {
-        Point3D::new(self.x, self.y, self.z)
+        Point3D::new(self.x as ScalarF, self.y as ScalarF, self.z as ScalarF)
     }
 }
 
@@ -55,10 +71,14 @@ pub struct Matrix4DConfig {
 
 impl Matrix4DConfig {
     pub fn to_matrix(&self) -> Matrix4D {
-        Matrix4D::new(
-            self.m11, self.m12, self.m13, self.m14, self.m21, self.m22, self.m23, self.m24,
-            self.m31, self.m32, self.m33, self.m34, self.m41, self.m42, self.m43, self.m44,
-        )
+        // the same column major order as `Matrix4D::new`
+        let columns = [
+            [self.m11, self.m12, self.m13, self.m14],
+            [self.m21, self.m22, self.m23, self.m24],
+            [self.m31, self.m32, self.m33, self.m34],
+            [self.m41, self.m42, self.m43, self.m44],
+        ];
+        Matrix4D::from(columns.map(|column| column.map(|v| v as ScalarF)))
     }
 }
 
@@ -89,7 +109,114 @@ const EYE_MATRIX4D: Matrix4D = Matrix4D {
     },
 };
 
-pub fn unwrap_matrix4d_config_to_matrix4d(config: Option<&Matrix4DConfig>) -> Matrix4D {
+// composes a transform one step at a time. every step is multiplied on the
+// right, so it acts on points before the steps added earlier, like the
+// transforms of nested scene nodes
+#[derive(Debug, Clone, Copy)]
+pub struct TransformBuilder {
+    matrix: Matrix4D,
+}
+
+impl TransformBuilder {
+    pub fn new() -> Self {
+        Self {
+            matrix: Matrix4D::identity(),
+        }
+    }
+
+    fn then(self, step: Matrix4D) -> Self {
+        Self {
+            matrix: self.matrix * step,
+        }
+    }
+
+    pub fn translate(self, x: ScalarF, y: ScalarF, z: ScalarF) -> Self {
+        self.then(Matrix4D::from_translation(Vec3D::new(x, y, z)))
+    }
+
+    pub fn rotate_x(self, deg: ScalarF) -> Self {
+        self.then(Matrix4D::from_angle_x(Deg(deg)))
+    }
+
+    pub fn rotate_y(self, deg: ScalarF) -> Self {
+        self.then(Matrix4D::from_angle_y(Deg(deg)))
+    }
+
+    pub fn rotate_z(self, deg: ScalarF) -> Self {
+        self.then(Matrix4D::from_angle_z(Deg(deg)))
+    }
+
+    pub fn rotate_axis(self, axis: Vec3D, deg: ScalarF) -> Self {
+        self.then(Matrix4D::from_axis_angle(axis.normalize(), Deg(deg)))
+    }
+
+    pub fn scale(self, sx: ScalarF, sy: ScalarF, sz: ScalarF) -> Self {
+        self.then(Matrix4D::from_nonuniform_scale(sx, sy, sz))
+    }
+
+    pub fn build(&self) -> Matrix4D {
+        self.matrix
+    }
+}
+
+impl Default for TransformBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type")]
+pub enum TransformStepConfig {
+    Translate { x: f64, y: f64, z: f64 },
+    RotateX { degrees: f64 },
+    RotateY { degrees: f64 },
+    RotateZ { degrees: f64 },
+    RotateAxis { axis: Vec3DConfig, degrees: f64 },
+    Scale { x: f64, y: f64, z: f64 },
+}
+
+// either the 16 entries of the matrix or a list of steps for the
+// `TransformBuilder`, e.g. [{ type = "RotateY", degrees = 30.0 }]
+#[derive(Deserialize)]
+#[serde(untagged)]
+pub enum TransformConfig {
+    Matrix(Matrix4DConfig),
+    Steps(Vec<TransformStepConfig>),
+}
+
+impl TransformConfig {
+    pub fn to_matrix(&self) -> Matrix4D {
+        match self {
+            TransformConfig::Matrix(config) => config.to_matrix(),
+            TransformConfig::Steps(steps) => steps
+                .iter()
+                .fold(TransformBuilder::new(), |builder, step| match step {
+                    TransformStepConfig::Translate { x, y, z } => {
+                        builder.translate(*x as ScalarF, *y as ScalarF, *z as ScalarF)
+                    }
+                    TransformStepConfig::RotateX { degrees } => {
+                        builder.rotate_x(*degrees as ScalarF)
+                    }
+                    TransformStepConfig::RotateY { degrees } => {
+                        builder.rotate_y(*degrees as ScalarF)
+                    }
+                    TransformStepConfig::RotateZ { degrees } => {
+                        builder.rotate_z(*degrees as ScalarF)
+                    }
+                    TransformStepConfig::RotateAxis { axis, degrees } => {
+                        builder.rotate_axis(axis.to_vec3(), *degrees as ScalarF)
+                    }
+                    TransformStepConfig::Scale { x, y, z } => {
+                        builder.scale(*x as ScalarF, *y as ScalarF, *z as ScalarF)
+                    }
+                })
+                .build(),
+        }
+    }
+}
+
+pub fn unwrap_matrix4d_config_to_matrix4d(config: Option<&TransformConfig>) -> Matrix4D {
     match config {
         Some(config) => config.to_matrix(),
         None => EYE_MATRIX4D,
@@ -114,10 +241,56 @@ pub fn transform_vec3(m: Matrix4D, v: Vec3D) -> Vec3D {
     Vec3D::new(u.x, u.y, u.z)
 }
 
-pub fn max_component(v: Vec3D) -> f64 {
+pub fn max_component(v: Vec3D) -> ScalarF {
     v.x.max(v.y).max(v.z)
 }
 
+// distance relative to 1 + |t| that steps past a surface crossing found at t,
+// far above the rounding of either precision
+#[cfg(not(feature = "f32_math"))]
+pub const CROSSING_EPSILON: ScalarF = 1e-9;
+#[cfg(feature = "f32_math")]
+pub const CROSSING_EPSILON: ScalarF = 1e-5;
+
+// moves an intersection point off its surface along `n` (wächter and binder,
+// ray tracing gems ch. 6). the offset is a fixed number of ulps of every
+// coordinate, or a fixed distance close to the origin where ulps vanish,
+// so rays leaving on the side of `n` can start at t = 0
+pub fn offset_ray_origin(p: Point3D, n: Vec3D) -> Point3D {
+    const ORIGIN: ScalarF = 1.0 / 32.0;
+    // the paper's constants are for f32, f64 has 29 more mantissa bits
+    #[cfg(not(feature = "f32_math"))]
+    const FLOAT_SCALE: ScalarF = 1.0 / (1u64 << 32) as ScalarF;
+    #[cfg(not(feature = "f32_math"))]
+    const INT_SCALE: ScalarF = (1u64 << 20) as ScalarF;
+    #[cfg(feature = "f32_math")]
+    const FLOAT_SCALE: ScalarF = 1.0 / 65536.0;
+    #[cfg(feature = "f32_math")]
+    const INT_SCALE: ScalarF = 256.0;
+    let offset = |p: ScalarF, n: ScalarF| {
+        if p.abs() < ORIGIN {
+            return p + FLOAT_SCALE * n;
+        }
+        let ulps = (INT_SCALE * n) as i64;
+        let bits = p.to_bits() as i64;
+        ScalarF::from_bits(if p < 0.0 { bits - ulps } else { bits + ulps } as _)
+    };
+    Point3D::new(offset(p.x, n.x), offset(p.y, n.y), offset(p.z, n.z))
+}
+
+// ray leaving the surface at `p` with normal `normal`, on the side `direction` points to
+pub fn spawn_ray(p: Point3D, normal: Vec3D, direction: Vec3D) -> Ray {
+    let n = if normal.dot(direction) < 0.0 {
+        -normal
+    } else {
+        normal
+    };
+    Ray {
+        origin: offset_ray_origin(p, n),
+        direction,
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Ray {
     pub origin: Point3D,
@@ -125,16 +298,216 @@ pub struct Ray {
 }
 
 impl Ray {
-    pub fn at(&self, t: f64) -> Point3D {
+    pub fn at(&self, t: ScalarF) -> Point3D {
         self.origin + t * self.direction
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Aabb {
+    pub min: Point3D,
+    pub max: Point3D,
+}
+
+// outward normals of the faces where a ray enters and leaves a box
+pub struct SlabCrossing {
+    pub t_enter: ScalarF,
+    pub enter_normal: Vec3D,
+    pub t_exit: ScalarF,
+    pub exit_normal: Vec3D,
+}
+
+pub fn axis_normal(axis: usize, sign: ScalarF) -> Vec3D {
+    let mut normal = Vec3D::zero();
+    normal[axis] = sign;
+    normal
+}
+
+impl Aabb {
+    pub fn new(min: Point3D, max: Point3D) -> Self {
+        Self { min, max }
+    }
+
+    pub fn empty() -> Self {
+        Self {
+            min: Point3D::new(ScalarF::INFINITY, ScalarF::INFINITY, ScalarF::INFINITY),
+            max: Point3D::new(
+                ScalarF::NEG_INFINITY,
+                ScalarF::NEG_INFINITY,
+                ScalarF::NEG_INFINITY,
+            ),
+        }
+    }
+
+    // sentinel for shapes with infinite extent, like planes
+    pub fn infinite() -> Self {
+        Self {
+            min: Point3D::new(
+                ScalarF::NEG_INFINITY,
+                ScalarF::NEG_INFINITY,
+                ScalarF::NEG_INFINITY,
+            ),
+            max: Point3D::new(ScalarF::INFINITY, ScalarF::INFINITY, ScalarF::INFINITY),
+        }
+    }
+
+    pub fn from_points(points: &[Point3D]) -> Self {
+        points
+            .iter()
+            .fold(Aabb::empty(), |aabb, p| aabb.union(&Aabb::new(*p, *p)))
+    }
+
+    pub fn is_finite(&self) -> bool {
+        self.min.x.is_finite()
+            && self.min.y.is_finite()
+            && self.min.z.is_finite()
+            && self.max.x.is_finite()
+            && self.max.y.is_finite()
+            && self.max.z.is_finite()
+    }
+
+    pub fn corners(&self) -> [Point3D; 8] {
+        let (min, max) = (self.min, self.max);
+        [0, 1, 2, 3, 4, 5, 6, 7].map(|i| {
+            Point3D::new(
+                if i & 1 == 0 { min.x } else { max.x },
+                if i & 2 == 0 { min.y } else { max.y },
+                if i & 4 == 0 { min.z } else { max.z },
+            )
+        })
+    }
+
+    pub fn centroid(&self) -> Point3D {
+        Point3D::new(
+            (self.min.x + self.max.x) * 0.5,
+            (self.min.y + self.max.y) * 0.5,
+            (self.min.z + self.max.z) * 0.5,
+        )
+    }
+
+    pub fn surface_area(&self) -> ScalarF {
+        let d = self.max - self.min;
+        if d.x < 0.0 || d.y < 0.0 || d.z < 0.0 {
+            return 0.0; // empty box
+        }
+        2.0 * (d.x * d.y + d.y * d.z + d.z * d.x)
+    }
+
+    pub fn union(&self, other: &Aabb) -> Aabb {
+        Aabb {
+            min: Point3D::new(
+                self.min.x.min(other.min.x),
+                self.min.y.min(other.min.y),
+                self.min.z.min(other.min.z),
+            ),
+            max: Point3D::new(
+                self.max.x.max(other.max.x),
+                self.max.y.max(other.max.y),
+                self.max.z.max(other.max.z),
+            ),
+        }
+    }
+
+    // slab test, remembering which slabs were entered last and left first.
+    // their faces are the ones the line of the ray crosses
+    pub fn slabs(&self, ray: &Ray) -> Option<SlabCrossing> {
+        let mut crossing = SlabCrossing {
+            t_enter: ScalarF::NEG_INFINITY,
+            enter_normal: Vec3D::zero(),
+            t_exit: ScalarF::INFINITY,
+            exit_normal: Vec3D::zero(),
+        };
+        for axis in 0..3 {
+            let inv_d = 1.0 / ray.direction[axis];
+            let t0 = (self.min[axis] - ray.origin[axis]) * inv_d;
+            let t1 = (self.max[axis] - ray.origin[axis]) * inv_d;
+            let (t_near, t_far, sign) = if inv_d >= 0.0 {
+                (t0, t1, 1.0)
+            } else {
+                (t1, t0, -1.0)
+            };
+            // NaN comparisons are false, so degenerate slabs leave the range untouched
+            if t_near > crossing.t_enter {
+                crossing.t_enter = t_near;
+                crossing.enter_normal = axis_normal(axis, -sign);
+            }
+            if t_far < crossing.t_exit {
+                crossing.t_exit = t_far;
+                crossing.exit_normal = axis_normal(axis, sign);
+            }
+        }
+        if crossing.t_enter > crossing.t_exit {
+            return None;
+        }
+        Some(crossing)
+    }
+
+    // parametric range of the ray inside the box
+    pub fn intersect(
+        &self,
+        ray: &Ray,
+        t_min: ScalarF,
+        t_max: ScalarF,
+    ) -> Option<(ScalarF, ScalarF)> {
+        let crossing = self.slabs(ray)?;
+        let (t0, t1) = (crossing.t_enter.max(t_min), crossing.t_exit.min(t_max));
+        if t0 > t1 {
+            return None;
+        }
+        Some((t0, t1))
+    }
+
+    #[cfg_attr(feature = "simd", allow(dead_code))] // replaced by `hit_simd`
+    pub fn hit(&self, ray: &Ray, t_min: ScalarF, t_max: ScalarF) -> bool {
+        self.intersect(ray, t_min, t_max).is_some()
+    }
+}
+
+// multiple importance sampling weights of strategy f taking `n_f` samples
+// with density `pdf_f` against strategy g
+pub fn balance_heuristic(n_f: usize, pdf_f: ScalarF, n_g: usize, pdf_g: ScalarF) -> ScalarF {
+    let f = n_f as ScalarF * pdf_f;
+    let g = n_g as ScalarF * pdf_g;
+    if f + g == 0.0 {
+        return 0.0;
+    }
+    f / (f + g)
+}
+
+pub fn power_heuristic(n_f: usize, pdf_f: ScalarF, n_g: usize, pdf_g: ScalarF) -> ScalarF {
+    let f = n_f as ScalarF * pdf_f;
+    let g = n_g as ScalarF * pdf_g;
+    if f == 0.0 && g == 0.0 {
+        return 0.0;
+    }
+    f * f / (f * f + g * g)
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+pub enum MisHeuristic {
+    Power,
+    #[default]
+    Balance,
+}
+
+pub fn mis_weight(
+    h: MisHeuristic,
+    n_f: usize,
+    pdf_f: ScalarF,
+    n_g: usize,
+    pdf_g: ScalarF,
+) -> ScalarF {
+    match h {
+        MisHeuristic::Power => power_heuristic(n_f, pdf_f, n_g, pdf_g),
+        MisHeuristic::Balance => balance_heuristic(n_f, pdf_f, n_g, pdf_g),
+    }
+}
+
 pub fn reflect(v: Vec3D, n: Vec3D) -> Vec3D {
     v - n * 2.0 * v.dot(n)
 }
 
-pub fn refract(v: Vec3D, n: Vec3D, eta: f64) -> Option<Vec3D> {
+pub fn refract(v: Vec3D, n: Vec3D, eta: ScalarF) -> Option<Vec3D> {
     let cos_theta1 = (-v).dot(n);
     let sin2_theta1 = 1.0 - cos_theta1 * cos_theta1;
     let sin2_theta2 = sin2_theta1 * eta * eta;
@@ -145,7 +518,7 @@ pub fn refract(v: Vec3D, n: Vec3D, eta: f64) -> Option<Vec3D> {
     Some(eta * v + (eta * cos_theta1 - cos_theta2) * n)
 }
 
-pub fn fresnel(cos_i: f64, eta_i: f64, eta_t: f64) -> f64 {
+pub fn fresnel(cos_i: ScalarF, eta_i: ScalarF, eta_t: ScalarF) -> ScalarF {
     // eta_i is the index of refraction of the medium the ray is coming from
     // eta_t is the index of refraction of the medium the ray is entering
     let sin2_t = (eta_i / eta_t) * (eta_i / eta_t) * (1.0 - cos_i * cos_i);
@@ -159,41 +532,128 @@ pub fn fresnel(cos_i: f64, eta_i: f64, eta_t: f64) -> f64 {
     (r_ortho * r_ortho + r_parallel * r_parallel) / 2.0
 }
 
-fn local_coordinate_system(normal: Vec3D) -> (Vec3D, Vec3D, Vec3D) {
-    let w = normal;
-    let a = if w.x.abs() > 0.9 {
-        Vec3D::new(0.0, 1.0, 0.0)
-    } else {
-        Vec3D::new(1.0, 0.0, 0.0)
+// reflectance of a dielectric film of `film_ior` and `thickness_nm` on a
+// substrate of `substrate_ior`, seen from air at `wavelength_nm`. the airy sum
+// of all the rays bouncing inside the film, averaged over both polarizations
+pub fn thin_film_reflectance(
+    cos_i: ScalarF,
+    film_ior: ScalarF,
+    thickness_nm: ScalarF,
+    substrate_ior: ScalarF,
+    wavelength_nm: ScalarF,
+) -> ScalarF {
+    let sin2_i = 1.0 - cos_i * cos_i;
+    let cos_film = (1.0 - sin2_i / (film_ior * film_ior)).max(0.0).sqrt();
+    let cos_substrate = (1.0 - sin2_i / (substrate_ior * substrate_ior))
+        .max(0.0)
+        .sqrt();
+    // phase difference between consecutive rays leaving the film
+    let delta = 4.0 * consts::PI * film_ior * thickness_nm * cos_film / wavelength_nm;
+
+    let airy = |r01: ScalarF, r12: ScalarF| {
+        let cross = 2.0 * r01 * r12 * delta.cos();
+        (r01 * r01 + r12 * r12 + cross) / (1.0 + r01 * r01 * r12 * r12 + cross)
+    };
+    let s = |n_i: ScalarF, cos_i: ScalarF, n_t: ScalarF, cos_t: ScalarF| {
+        (n_i * cos_i - n_t * cos_t) / (n_i * cos_i + n_t * cos_t)
     };
-    let u = w.cross(a).normalize();
-    let v = w.cross(u).normalize();
-    (u, v, w)
+    let p = |n_i: ScalarF, cos_i: ScalarF, n_t: ScalarF, cos_t: ScalarF| {
+        (n_t * cos_i - n_i * cos_t) / (n_t * cos_i + n_i * cos_t)
+    };
+    let r_s = airy(
+        s(1.0, cos_i, film_ior, cos_film),
+        s(film_ior, cos_film, substrate_ior, cos_substrate),
+    );
+    let r_p = airy(
+        p(1.0, cos_i, film_ior, cos_film),
+        p(film_ior, cos_film, substrate_ior, cos_substrate),
+    );
+    (r_s + r_p) / 2.0
 }
 
-pub fn spherical_to_world(theta: f64, phi: f64, normal: Vec3D) -> Vec3D {
-    let (u, v, w) = local_coordinate_system(normal);
-    u.mul_element_wise(theta.sin() * phi.cos())
-        + v.mul_element_wise(theta.sin() * phi.sin())
-        + w.mul_element_wise(theta.cos())
+// orthonormal basis with `w` along a normal, `u` and `v` span the plane
+// orthogonal to it
+#[derive(Debug, Clone, Copy)]
+pub struct Onb {
+    pub u: Vec3D,
+    pub v: Vec3D,
+    pub w: Vec3D,
 }
 
+impl Onb {
+    pub fn from_w(normal: Vec3D) -> Self {
+        let w = normal;
+        let a = if w.x.abs() > 0.9 {
+            Vec3D::new(0.0, 1.0, 0.0)
+        } else {
+            Vec3D::new(1.0, 0.0, 0.0)
+        };
+        let u = w.cross(a).normalize();
+        let v = w.cross(u).normalize();
+        Self { u, v, w }
+    }
+
+    // local coordinates to world space
+    pub fn local(&self, v: Vec3D) -> Vec3D {
+        self.u * v.x + self.v * v.y + self.w * v.z
+    }
+
+    // world space to local coordinates
+    pub fn world(&self, v: Vec3D) -> Vec3D {
+        Vec3D::new(v.dot(self.u), v.dot(self.v), v.dot(self.w))
+    }
+
+    // unit vector at polar angle `theta` from w and azimuth `phi` from u
+    pub fn spherical_local(theta: ScalarF, phi: ScalarF) -> Vec3D {
+        Vec3D::new(
+            theta.sin() * phi.cos(),
+            theta.sin() * phi.sin(),
+            theta.cos(),
+        )
+    }
+}
+
+pub fn spherical_to_world(theta: ScalarF, phi: ScalarF, normal: Vec3D) -> Vec3D {
+    Onb::from_w(normal).local(Onb::spherical_local(theta, phi))
+}
+
+// rotation taking tangent space (tangent, bitangent, normal) to world space,
+// the tangent is made orthogonal to the normal first
+pub fn compute_tbn(normal: Vec3D, tangent: Vec3D) -> Matrix4D {
+    let tangent = (tangent - normal * normal.dot(tangent)).normalize();
+    let bitangent = normal.cross(tangent);
+    Matrix4D::from_cols(
+        tangent.extend(0.0),
+        bitangent.extend(0.0),
+        normal.extend(0.0),
+        Vec4D::new(0.0, 0.0, 0.0, 1.0),
+    )
+}
+
+// `epsilon` for f64 math, loosened to a thousand ulps of 1 when that is
+// tighter than the precision in use can resolve
 #[cfg(test)]
-pub fn vec3_approx_eq(v1: Vec3D, v2: Vec3D, epsilon: f64) -> bool {
+#[allow(clippy::unnecessary_cast)] // a no-op unless built with f32_math
+pub fn tolerance(epsilon: f64) -> ScalarF {
+    epsilon.max(1000.0 * ScalarF::EPSILON as f64) as ScalarF
+}
+
+#[cfg(test)]
+pub fn vec3_approx_eq(v1: Vec3D, v2: Vec3D, epsilon: ScalarF) -> bool {
     (v1 - v2).magnitude() < epsilon
 }
 
 #[cfg(test)]
-pub fn point_approx_eq(p1: Point3D, p2: Point3D, epsilon: f64) -> bool {
+pub fn point_approx_eq(p1: Point3D, p2: Point3D, epsilon: ScalarF) -> bool {
     (p1 - p2).magnitude() < epsilon
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::math::consts::PI;
     use approx::assert_abs_diff_eq;
     use rand::Rng;
-    use std::f64::consts::PI;
 
     #[test]
     fn test_ray_at() {
@@ -206,6 +666,30 @@ mod tests {
         assert_eq!(p, Point3D::new(1.0, 0.0, 0.0));
     }
 
+    #[test]
+    fn test_offset_ray_origin() {
+        let mut rng = rand::thread_rng();
+        for scale in [1e-3, 1.0, 1e3, 1e6] {
+            for _ in 0..100 {
+                let p = Point3D::new(
+                    rng.gen_range(-scale..scale),
+                    rng.gen_range(-scale..scale),
+                    rng.gen_range(-scale..scale),
+                );
+                let n = Vec3D::new(
+                    rng.gen_range(-1.0..1.0),
+                    rng.gen_range(-1.0..1.0),
+                    rng.gen_range(-1.0..1.0),
+                )
+                .normalize();
+                // always ends up on the side of the normal, and stays close
+                let offset = offset_ray_origin(p, n) - p;
+                assert!(offset.dot(n) > 0.0);
+                assert!(offset.magnitude() < tolerance(1e-8) * scale.max(1.0));
+            }
+        }
+    }
+
     #[test]
     fn test_reflect() {
         let mut rng = rand::thread_rng();
@@ -219,7 +703,7 @@ mod tests {
             assert!(v.dot(n) <= 0.0);
             let r = reflect(v, n);
             assert!(r.dot(n) >= 0.0);
-            vec3_approx_eq(n, (r - v).normalize(), 1e-6);
+            vec3_approx_eq(n, (r - v).normalize(), tolerance(1e-6));
         }
     }
 
@@ -284,17 +768,58 @@ mod tests {
     }
 
     #[test]
-    fn test_local_coordinate_system() {
+    fn test_thin_film_reflectance() {
+        let (film, substrate, red) = (1.33, 1.5, 650.0);
+        for cos_i in [1.0, 0.8, 0.3] {
+            // without a film only the substrate reflects
+            assert_abs_diff_eq!(
+                thin_film_reflectance(cos_i, film, 0.0, substrate, red),
+                fresnel(cos_i, 1.0, substrate),
+                epsilon = tolerance(1e-12)
+            );
+        }
+
+        // both interfaces reflect with the same sign, a half wavelength of
+        // round trip path cancels them and a whole one restores them
+        let quarter_wave = red / (4.0 * film);
+        let bare = thin_film_reflectance(1.0, film, 0.0, substrate, red);
+        let minimum = thin_film_reflectance(1.0, film, quarter_wave, substrate, red);
+        let maximum = thin_film_reflectance(1.0, film, 2.0 * quarter_wave, substrate, red);
+        assert_abs_diff_eq!(maximum, bare, epsilon = tolerance(1e-12));
+        assert!(minimum < 0.5 * maximum);
+        for thickness in [50.0, 100.0, 300.0, 500.0] {
+            let r = thin_film_reflectance(1.0, film, thickness, substrate, red);
+            assert!(r >= minimum - tolerance(1e-12) && r <= maximum + tolerance(1e-12));
+        }
+    }
+
+    #[test]
+    fn test_onb() {
         let mut rng = rand::thread_rng();
-        for _ in 0..10 {
-            let n = Vec3D::new(rng.gen(), rng.gen(), rng.gen()).normalize();
-            let (u, v, w) = local_coordinate_system(n);
-            assert_abs_diff_eq!(u.dot(v), 0.0, epsilon = 1e-6);
-            assert_abs_diff_eq!(v.dot(w), 0.0, epsilon = 1e-6);
-            assert_abs_diff_eq!(w.dot(u), 0.0, epsilon = 1e-6);
-            assert_abs_diff_eq!(u.magnitude(), 1.0, epsilon = 1e-6);
-            assert_abs_diff_eq!(v.magnitude(), 1.0, epsilon = 1e-6);
-            assert_abs_diff_eq!(w.magnitude(), 1.0, epsilon = 1e-6);
+        for _ in 0..100 {
+            let n = Vec3D::new(
+                rng.gen_range(-1.0..1.0),
+                rng.gen_range(-1.0..1.0),
+                rng.gen_range(-1.0..1.0),
+            )
+            .normalize();
+            let onb = Onb::from_w(n);
+            let (u, v, w) = (onb.u, onb.v, onb.w);
+            assert_abs_diff_eq!(u.dot(v), 0.0, epsilon = tolerance(1e-6));
+            assert_abs_diff_eq!(v.dot(w), 0.0, epsilon = tolerance(1e-6));
+            assert_abs_diff_eq!(w.dot(u), 0.0, epsilon = tolerance(1e-6));
+            assert_abs_diff_eq!(u.magnitude(), 1.0, epsilon = tolerance(1e-6));
+            assert_abs_diff_eq!(v.magnitude(), 1.0, epsilon = tolerance(1e-6));
+            assert_abs_diff_eq!(w.magnitude(), 1.0, epsilon = tolerance(1e-6));
+
+            // the two conversions undo each other
+            let x = Vec3D::new(rng.gen(), rng.gen(), rng.gen());
+            assert!(vec3_approx_eq(onb.world(onb.local(x)), x, tolerance(1e-9)));
+            assert!(vec3_approx_eq(
+                onb.local(Vec3D::unit_z()),
+                n,
+                tolerance(1e-9)
+            ));
         }
     }
 
@@ -306,8 +831,185 @@ mod tests {
             let theta = rng.gen_range(0.0..PI);
             let phi = rng.gen_range(0.0..2.0 * PI);
             let v = spherical_to_world(theta, phi, n);
-            assert_abs_diff_eq!(v.magnitude(), 1.0, epsilon = 1e-6);
-            assert_abs_diff_eq!(v.dot(n), theta.cos(), epsilon = 1e-6);
+            assert_abs_diff_eq!(v.magnitude(), 1.0, epsilon = tolerance(1e-6));
+            assert_abs_diff_eq!(v.dot(n), theta.cos(), epsilon = tolerance(1e-6));
+        }
+    }
+
+    #[test]
+    fn test_aabb_surface_area_and_centroid() {
+        let aabb = Aabb::new(Point3D::new(-1.0, 0.0, 2.0), Point3D::new(1.0, 3.0, 6.0));
+        assert_abs_diff_eq!(
+            aabb.surface_area(),
+            2.0 * (6.0 + 12.0 + 8.0),
+            epsilon = tolerance(1e-12)
+        );
+        assert!(point_approx_eq(
+            aabb.centroid(),
+            Point3D::new(0.0, 1.5, 4.0),
+            tolerance(1e-12)
+        ));
+        assert_eq!(Aabb::empty().surface_area(), 0.0);
+    }
+
+    // reference box intersection through the six face planes
+    fn box_faces_hit(aabb: &Aabb, ray: &Ray, t_min: ScalarF, t_max: ScalarF) -> bool {
+        let inside = |p: Point3D, skip: usize| {
+            (0..3).all(|axis| {
+                axis == skip
+                    || (p[axis] >= aabb.min[axis] - tolerance(1e-9)
+                        && p[axis] <= aabb.max[axis] + tolerance(1e-9))
+            })
+        };
+        if t_min <= 0.0 && inside(ray.origin, 3) {
+            return true;
+        }
+        (0..3).any(|axis| {
+            [aabb.min[axis], aabb.max[axis]].iter().any(|&plane| {
+                let t = (plane - ray.origin[axis]) / ray.direction[axis];
+                t >= t_min && t <= t_max && inside(ray.at(t), axis)
+            })
+        })
+    }
+
+    #[test]
+    fn test_aabb_hit() {
+        let mut rng = rand::thread_rng();
+        let aabb = Aabb::new(Point3D::new(-1.0, -2.0, -0.5), Point3D::new(2.0, 1.0, 0.5));
+        for _ in 0..1000 {
+            let ray = Ray {
+                origin: Point3D::new(
+                    rng.gen_range(-5.0..5.0),
+                    rng.gen_range(-5.0..5.0),
+                    rng.gen_range(-5.0..5.0),
+                ),
+                direction: Vec3D::new(
+                    rng.gen_range(-1.0..1.0),
+                    rng.gen_range(-1.0..1.0),
+                    rng.gen_range(-1.0..1.0),
+                )
+                .normalize(),
+            };
+            assert_eq!(
+                aabb.hit(&ray, 0.0, ScalarF::MAX),
+                box_faces_hit(&aabb, &ray, 0.0, ScalarF::MAX)
+            );
+        }
+    }
+
+    #[test]
+    fn test_aabb_slabs() {
+        let aabb = Aabb::new(Point3D::new(-1.0, -2.0, -0.5), Point3D::new(2.0, 1.0, 0.5));
+        // enters through the -x face and leaves through the +z face
+        let ray = Ray {
+            origin: Point3D::new(-3.0, 0.0, -0.2),
+            direction: Vec3D::new(1.0, 0.0, 0.25).normalize(),
+        };
+        let crossing = aabb.slabs(&ray).unwrap();
+        assert!(vec3_approx_eq(
+            crossing.enter_normal,
+            Vec3D::new(-1.0, 0.0, 0.0),
+            tolerance(1e-12)
+        ));
+        assert!(vec3_approx_eq(
+            crossing.exit_normal,
+            Vec3D::new(0.0, 0.0, 1.0),
+            tolerance(1e-12)
+        ));
+        assert!(point_approx_eq(
+            ray.at(crossing.t_enter),
+            Point3D::new(-1.0, 0.0, 0.3),
+            tolerance(1e-9)
+        ));
+        assert!(point_approx_eq(
+            ray.at(crossing.t_exit),
+            Point3D::new(-0.2, 0.0, 0.5),
+            tolerance(1e-9)
+        ));
+
+        // the range is clipped to the ray's
+        let (t0, t1) = aabb.intersect(&ray, 0.0, ScalarF::MAX).unwrap();
+        assert_eq!((t0, t1), (crossing.t_enter, crossing.t_exit));
+        let (t0, _) = aabb.intersect(&ray, 2.5, ScalarF::MAX).unwrap();
+        assert_eq!(t0, 2.5);
+        assert!(aabb.intersect(&ray, 0.0, 1.0).is_none());
+    }
+
+    #[test]
+    fn test_mis_heuristics() {
+        let mut rng = rand::thread_rng();
+        for _ in 0..100 {
+            let p = rng.gen_range(1e-3..100.0);
+            assert_abs_diff_eq!(power_heuristic(1, p, 1, p), 0.5, epsilon = tolerance(1e-12));
+            assert_abs_diff_eq!(
+                balance_heuristic(1, p, 1, p),
+                0.5,
+                epsilon = tolerance(1e-12)
+            );
+
+            let (n_f, n_g) = (rng.gen_range(1..5), rng.gen_range(1..5));
+            let (pdf_f, pdf_g) = (rng.gen_range(0.0..10.0), rng.gen_range(0.0..10.0));
+            for h in [MisHeuristic::Power, MisHeuristic::Balance] {
+                let sum =
+                    mis_weight(h, n_f, pdf_f, n_g, pdf_g) + mis_weight(h, n_g, pdf_g, n_f, pdf_f);
+                assert_abs_diff_eq!(sum, 1.0, epsilon = tolerance(1e-12));
+            }
+        }
+        assert_eq!(power_heuristic(1, 0.0, 1, 0.0), 0.0);
+        assert_eq!(balance_heuristic(1, 0.0, 1, 0.0), 0.0);
+    }
+
+    #[test]
+    fn test_transform_builder() {
+        let matrix = TransformBuilder::new()
+            .rotate_x(90.0)
+            .translate(0.0, 1.0, 0.0)
+            .build();
+        let rotation = Matrix4D::new(
+            1.0, 0.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, -1.0, 0.0, 0.0, 0.0, 0.0, 0.0, 1.0,
+        );
+        let translation = Matrix4D::new(
+            1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 1.0, 0.0, 1.0,
+        );
+        let expected = rotation * translation;
+        for (a, b) in [
+            (matrix.x, expected.x),
+            (matrix.y, expected.y),
+            (matrix.z, expected.z),
+            (matrix.w, expected.w),
+        ] {
+            assert!((a - b).magnitude() < tolerance(1e-12));
+        }
+        // the translation is applied first, then it is rotated onto z
+        let p = transform_point3(matrix, Point3D::new(0.0, 0.0, 0.0));
+        assert!(point_approx_eq(
+            p,
+            Point3D::new(0.0, 0.0, 1.0),
+            tolerance(1e-12)
+        ));
+
+        #[derive(Deserialize)]
+        struct Config {
+            steps: TransformConfig,
+            matrix: TransformConfig,
+        }
+        let config: Config = toml::from_str(
+            r#"
+            steps = [
+                { type = "RotateX", degrees = 90.0 },
+                { type = "Translate", x = 0.0, y = 1.0, z = 0.0 },
+            ]
+            matrix = { m11 = 1.0, m12 = 0.0, m13 = 0.0, m14 = 0.0, m21 = 0.0, m22 = 0.0, m23 = 1.0, m24 = 0.0, m31 = 0.0, m32 = -1.0, m33 = 0.0, m34 = 0.0, m41 = 0.0, m42 = 0.0, m43 = 1.0, m44 = 1.0 }
+            "#,
+        )
+        .unwrap();
+        for config in [config.steps, config.matrix] {
+            let p = transform_point3(config.to_matrix(), Point3D::new(0.0, 0.0, 0.0));
+            assert!(point_approx_eq(
+                p,
+                Point3D::new(0.0, 0.0, 1.0),
+                tolerance(1e-12)
+            ));
         }
     }
 }
@@ -1,6 +1,7 @@
 use super::common::HitRecord;
+use super::error::RaytracerError;
 use super::material::{Material, MaterialConfig};
-use super::math::Ray;
+use super::math::{Ray, ScalarF};
 use super::shapes::{Shape, ShapeConfig};
 use serde::Deserialize;
 use std::sync::Arc;
@@ -8,10 +9,11 @@ use std::sync::Arc;
 pub struct Object {
     pub shape: Arc<dyn Shape>,
     pub material: Arc<dyn Material>,
+    pub id: usize, // index in the scene's objects
 }
 
 impl Object {
-    pub fn intersect(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+    pub fn intersect(&self, ray: &Ray, t_min: ScalarF, t_max: ScalarF) -> Option<HitRecord> {
         let hit_record = self.shape.intersect(ray, t_min, t_max);
         if hit_record.is_none() {
             return None;
@@ -19,6 +21,7 @@ impl Object {
 
         let mut hit_record = hit_record.unwrap();
         hit_record.object = Some(self);
+        hit_record.object_id = self.id;
         Some(hit_record)
     }
 }
@@ -26,14 +29,36 @@ impl Object {
 #[derive(Deserialize)]
 pub struct ObjectConfig {
     pub shape: ShapeConfig,
-    pub material: MaterialConfig,
+    // may be left out for obj meshes whose faces all have an mtl material
+    pub material: Option<MaterialConfig>,
 }
 
 impl ObjectConfig {
-    pub fn to_object(&self) -> Object {
-        Object {
-            shape: self.shape.to_shape(),
-            material: self.material.to_material(),
-        }
+    // meshes give one object per material of their file, `material` is used
+    // for the faces without one
+    pub fn to_objects(&self) -> Result<Vec<Object>, RaytracerError> {
+        let parts = match &self.shape {
+            ShapeConfig::Mesh(config) => config.to_parts()?,
+            shape => vec![(shape.to_shape()?, None)],
+        };
+        parts
+            .into_iter()
+            .map(|(shape, material)| {
+                let material = match (material, &self.material) {
+                    (Some(material), _) => material.to_material()?,
+                    (None, Some(material)) => material.to_material()?,
+                    (None, None) => {
+                        return Err(RaytracerError::SceneLoad(
+                            "object has no material".to_string(),
+                        ))
+                    }
+                };
+                Ok(Object {
+                    shape,
+                    material,
+                    id: 0,
+                })
+            })
+            .collect()
     }
 }
@@ -0,0 +1,117 @@
+use super::super::math::{ScalarF, Vec3D};
+use super::super::renderer::PixelSamples;
+use std::fs;
+
+const MAGIC: &[u8; 4] = b"RRTC";
+
+// samples taken so far through every pixel, so an interrupted render can
+// pick up where it stopped and a finished one can take more samples. the
+// header holds the image size and the samples per pixel of the render the
+// pixels with samples were finished by
+pub struct Checkpoint {
+    pub width: u32,
+    pub height: u32,
+    pub spp_so_far: u64,
+    pub pixels: Vec<PixelSamples>,
+}
+
+const PIXEL_BYTES: usize = 6 * 8 + 4;
+
+impl Checkpoint {
+    pub fn new(width: u32, height: u32, spp_so_far: u64) -> Self {
+        let pixel_count = width as usize * height as usize;
+        Self {
+            width,
+            height,
+            spp_so_far,
+            pixels: vec![PixelSamples::new(); pixel_count],
+        }
+    }
+
+    // whether a render of the image with `samples_per_pixel` can go on from it
+    pub fn matches(&self, width: u32, height: u32, samples_per_pixel: u64) -> bool {
+        self.width == width && self.height == height && self.spp_so_far <= samples_per_pixel
+    }
+
+    // little endian header followed by the running mean and the sums of
+    // squared differences from it as f64 and the count as u32 of every
+    // pixel, in either precision
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        let mut bytes = Vec::with_capacity(20 + self.pixels.len() * PIXEL_BYTES);
+        bytes.extend_from_slice(MAGIC);
+        bytes.extend_from_slice(&self.width.to_le_bytes());
+        bytes.extend_from_slice(&self.height.to_le_bytes());
+        bytes.extend_from_slice(&self.spp_so_far.to_le_bytes());
+        for pixel in &self.pixels {
+            for v in [pixel.mean, pixel.m2] {
+                #[allow(clippy::unnecessary_cast)] // a no-op unless built with f32_math
+                for c in [v.x, v.y, v.z] {
+                    bytes.extend_from_slice(&(c as f64).to_le_bytes());
+                }
+            }
+            bytes.extend_from_slice(&(pixel.count as u32).to_le_bytes());
+        }
+
+        // written aside and moved over the old one, a kill while writing
+        // leaves the previous checkpoint intact
+        let partial = format!("{}.partial", path);
+        fs::write(&partial, bytes).map_err(|e| format!("Failed to write {}: {}", partial, e))?;
+        fs::rename(&partial, path).map_err(|e| format!("Failed to write {}: {}", path, e))
+    }
+
+    pub fn load(path: &str) -> Result<Self, String> {
+        let bytes = fs::read(path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+        let invalid = || format!("{} is not a checkpoint", path);
+        if bytes.len() < 20 || &bytes[..4] != MAGIC {
+            return Err(invalid());
+        }
+        let u32_at = |i: usize| u32::from_le_bytes(bytes[i..i + 4].try_into().unwrap());
+        let f64_at = |i: usize| f64::from_le_bytes(bytes[i..i + 8].try_into().unwrap()) as ScalarF;
+        let (width, height) = (u32_at(4), u32_at(8));
+        let spp_so_far = u64::from_le_bytes(bytes[12..20].try_into().unwrap());
+
+        let mut checkpoint = Checkpoint::new(width, height, spp_so_far);
+        if bytes.len() != 20 + checkpoint.pixels.len() * PIXEL_BYTES {
+            return Err(invalid());
+        }
+        let vec3_at = |i: usize| Vec3D::new(f64_at(i), f64_at(i + 8), f64_at(i + 16));
+        for (i, pixel) in checkpoint.pixels.iter_mut().enumerate() {
+            let offset = 20 + i * PIXEL_BYTES;
+            *pixel = PixelSamples {
+                count: u32_at(offset + 48) as usize,
+                mean: vec3_at(offset),
+                m2: vec3_at(offset + 24),
+            };
+        }
+        Ok(checkpoint)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_checkpoint_round_trip() {
+        let mut checkpoint = Checkpoint::new(3, 2, 64);
+        for (i, pixel) in checkpoint.pixels.iter_mut().enumerate() {
+            pixel.add(Vec3D::new(i as ScalarF * 0.1, 1e-300, -2.5));
+            pixel.add(Vec3D::new(3.0, 0.0, i as ScalarF));
+        }
+
+        let path = std::env::temp_dir().join("rust_ray_tracer_test_checkpoint.bin");
+        let path = path.to_str().unwrap();
+        checkpoint.save(path).unwrap();
+        let loaded = Checkpoint::load(path).unwrap();
+        assert_eq!(loaded.spp_so_far, 64);
+        assert!(loaded.matches(3, 2, 64));
+        assert!(loaded.matches(3, 2, 128));
+        assert!(!loaded.matches(3, 2, 32));
+        assert!(!loaded.matches(2, 3, 64));
+        assert_eq!(loaded.pixels, checkpoint.pixels);
+
+        std::fs::write(path, b"RRTC").unwrap();
+        assert!(Checkpoint::load(path).is_err());
+        std::fs::remove_file(path).unwrap();
+    }
+}
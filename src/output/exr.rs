@@ -0,0 +1,209 @@
+use super::super::math::Vec3D;
+use ::exr::prelude::{
+    AnyChannel, AnyChannels, Encoding, FlatSamples, Image, ImageAttributes, IntegerBounds, Layer,
+    LayerAttributes, SmallVec, Vec2, WritableImage,
+};
+use std::collections::HashMap;
+
+#[allow(clippy::unnecessary_cast)] // a no-op unless built with f32_math
+fn rgb_channels(pixels: &[Vec3D]) -> Vec<AnyChannel<FlatSamples>> {
+    let r = pixels.iter().map(|p| p.x as f32).collect();
+    let g = pixels.iter().map(|p| p.y as f32).collect();
+    let b = pixels.iter().map(|p| p.z as f32).collect();
+    vec![
+        AnyChannel::new("R", FlatSamples::F32(r)),
+        AnyChannel::new("G", FlatSamples::F32(g)),
+        AnyChannel::new("B", FlatSamples::F32(b)),
+    ]
+}
+
+fn layer_from_channels(
+    attributes: LayerAttributes,
+    channels: Vec<AnyChannel<FlatSamples>>,
+    width: u32,
+    height: u32,
+) -> Layer<AnyChannels<FlatSamples>> {
+    let channels: SmallVec<[AnyChannel<FlatSamples>; 4]> = SmallVec::from_vec(channels);
+    Layer::new(
+        Vec2(width as usize, height as usize),
+        attributes,
+        Encoding::FAST_LOSSLESS,
+        AnyChannels::sort(channels),
+    )
+}
+
+fn layer_from_pixels(
+    name: &str,
+    pixels: &[Vec3D],
+    width: u32,
+    height: u32,
+) -> Layer<AnyChannels<FlatSamples>> {
+    let mut channels = rgb_channels(pixels);
+    channels.push(AnyChannel::new(
+        "A",
+        FlatSamples::F32(vec![1.0_f32; pixels.len()]),
+    ));
+    layer_from_channels(LayerAttributes::named(name), channels, width, height)
+}
+
+// linear 32 bit float rgb image, written as is without any post processing
+pub fn save_exr(path: &str, pixels: &[Vec3D], width: u32, height: u32) -> Result<(), String> {
+    let pixel_count = width as usize * height as usize;
+    if pixels.len() != pixel_count {
+        return Err(format!(
+            "Image has {} pixels, expected {}",
+            pixels.len(),
+            pixel_count
+        ));
+    }
+
+    let layer = layer_from_channels(
+        LayerAttributes::default(),
+        rgb_channels(pixels),
+        width,
+        height,
+    );
+    Image::from_layer(layer)
+        .write()
+        .to_file(path)
+        .map_err(|e| format!("Failed to write {}: {}", path, e))
+}
+
+pub fn save_multilayer_exr(
+    path: &str,
+    layers: &HashMap<String, Vec<Vec3D>>,
+    width: u32,
+    height: u32,
+) -> Result<(), String> {
+    let pixel_count = width as usize * height as usize;
+    let mut names: Vec<&String> = layers.keys().collect();
+    names.sort();
+
+    let mut exr_layers = Vec::new();
+    for name in names {
+        let pixels = &layers[name];
+        if pixels.len() != pixel_count {
+            return Err(format!(
+                "Layer {} has {} pixels, expected {}",
+                name,
+                pixels.len(),
+                pixel_count
+            ));
+        }
+        exr_layers.push(layer_from_pixels(name, pixels, width, height));
+    }
+
+    let attributes = ImageAttributes::new(IntegerBounds::from_dimensions(Vec2(
+        width as usize,
+        height as usize,
+    )));
+    Image::from_layers(attributes, exr_layers)
+        .write()
+        .to_file(path)
+        .map_err(|e| format!("Failed to write {}: {}", path, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::ScalarF;
+    use ::exr::prelude::{read, ReadChannels, ReadLayers};
+
+    #[test]
+    fn test_save_exr() {
+        let (width, height) = (16, 8);
+        let pixels: Vec<Vec3D> = (0..width * height)
+            .map(|i| {
+                let (x, y) = ((i % width) as ScalarF, (i / width) as ScalarF);
+                Vec3D::new(x / 15.0, y * 3.7, 1e-3 + 250.0 * x * y)
+            })
+            .collect();
+
+        let path = std::env::temp_dir().join("rust_ray_tracer_test_gradient.exr");
+        let path = path.to_str().unwrap();
+        save_exr(path, &pixels, width, height).unwrap();
+        assert!(save_exr(path, &pixels[1..], width, height).is_err());
+
+        let image = read()
+            .no_deep_data()
+            .largest_resolution_level()
+            .all_channels()
+            .first_valid_layer()
+            .all_attributes()
+            .from_file(path)
+            .unwrap();
+        let layer = &image.layer_data;
+        assert_eq!(layer.size, Vec2(width as usize, height as usize));
+        assert_eq!(layer.channel_data.list.len(), 3);
+        let channel = |channel_name: &str| -> Vec<f32> {
+            layer
+                .channel_data
+                .list
+                .iter()
+                .find(|c| c.name.to_string() == channel_name)
+                .unwrap()
+                .sample_data
+                .values_as_f32()
+                .collect()
+        };
+        let (r, g, b) = (channel("R"), channel("G"), channel("B"));
+        #[allow(clippy::unnecessary_cast)] // a no-op unless built with f32_math
+        for (i, p) in pixels.iter().enumerate() {
+            assert!((r[i] - p.x as f32).abs() <= f32::EPSILON * p.x.abs() as f32);
+            assert!((g[i] - p.y as f32).abs() <= f32::EPSILON * p.y.abs() as f32);
+            assert!((b[i] - p.z as f32).abs() <= f32::EPSILON * p.z.abs() as f32);
+        }
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_save_multilayer_exr() {
+        let (width, height) = (4, 3);
+        let beauty: Vec<Vec3D> = (0..width * height)
+            .map(|i| Vec3D::new(i as ScalarF * 0.5, 2.0, 100.25))
+            .collect();
+        let normal: Vec<Vec3D> = (0..width * height)
+            .map(|i| Vec3D::new(0.0, -(i as ScalarF), 1.0))
+            .collect();
+        let mut layers = HashMap::new();
+        layers.insert("beauty".to_string(), beauty.clone());
+        layers.insert("normal".to_string(), normal.clone());
+
+        let path = std::env::temp_dir().join("rust_ray_tracer_test_multilayer.exr");
+        let path = path.to_str().unwrap();
+        save_multilayer_exr(path, &layers, width, height).unwrap();
+
+        let image = read()
+            .no_deep_data()
+            .largest_resolution_level()
+            .all_channels()
+            .all_layers()
+            .all_attributes()
+            .from_file(path)
+            .unwrap();
+        assert_eq!(image.layer_data.len(), 2);
+        for layer in &image.layer_data {
+            let name = layer.attributes.layer_name.as_ref().unwrap().to_string();
+            let expected = &layers[&name];
+            let channel = |channel_name: &str| -> Vec<f32> {
+                layer
+                    .channel_data
+                    .list
+                    .iter()
+                    .find(|c| c.name.to_string() == channel_name)
+                    .unwrap()
+                    .sample_data
+                    .values_as_f32()
+                    .collect()
+            };
+            let (r, g, b) = (channel("R"), channel("G"), channel("B"));
+            #[allow(clippy::unnecessary_cast)] // a no-op unless built with f32_math
+            for (i, p) in expected.iter().enumerate() {
+                assert!((r[i] - p.x as f32).abs() <= f32::EPSILON * p.x.abs() as f32);
+                assert!((g[i] - p.y as f32).abs() <= f32::EPSILON * p.y.abs() as f32);
+                assert!((b[i] - p.z as f32).abs() <= f32::EPSILON * p.z.abs() as f32);
+            }
+        }
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,5 @@
+mod checkpoint;
+mod exr;
+
+pub use self::checkpoint::Checkpoint;
+pub use self::exr::{save_exr, save_multilayer_exr};
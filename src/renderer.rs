@@ -1,21 +1,129 @@
-use super::math::{Point2U, Vec3D, Vec3DConfig};
-use super::sampler::SamplerConfig;
+mod adaptive;
+mod lut;
+
+use super::light::luminance;
+use super::math::{max_component, Point2U, ScalarF, Vec3D, Vec3DConfig};
+use super::output::Checkpoint;
+use super::rng::{init_thread_local_rng, stream_seed};
+use super::sampler::{Sampler, SamplerConfig};
 use super::scene::Scene;
-use super::tracers::TracerConfig;
-use cgmath::ElementWise;
-use image::{ImageBuffer, RgbImage};
+use super::tracers::{ProgressivePhotonMapTracer, Tracer, TracerConfig};
+use adaptive::{AdaptiveSamplingConfig, StoppingRule};
+use cgmath::{Array, ElementWise, InnerSpace};
+use image::{GrayImage, ImageBuffer, RgbImage};
 use indicatif::{ProgressBar, ProgressStyle};
-use rayon::iter::{IntoParallelIterator, ParallelIterator};
+use log::warn;
+use lut::{deserialize_lut_file, CubeLut};
+use rayon::iter::{IndexedParallelIterator, IntoParallelIterator, ParallelIterator};
+use rayon::slice::ParallelSliceMut;
 use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+pub trait ProgressReporter: Send + Sync {
+    fn update(&self, completed: u64, total: u64);
+    fn finish(&self);
+}
+
+pub struct IndicatifProgressReporter {
+    progress_bar: ProgressBar,
+}
+
+impl IndicatifProgressReporter {
+    pub fn new(total: u64) -> Self {
+        let progress_bar = ProgressBar::new(total);
+        progress_bar.set_style(
+            ProgressStyle::default_bar()
+                .template(
+                    "{spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {percent}% ({eta})",
+                )
+                .expect("Failed to set progress bar style")
+                .progress_chars("#>-"),
+        );
+        Self { progress_bar }
+    }
+}
+
+impl ProgressReporter for IndicatifProgressReporter {
+    fn update(&self, completed: u64, _: u64) {
+        self.progress_bar.set_position(completed);
+    }
+
+    fn finish(&self) {
+        self.progress_bar.finish_with_message("Render complete!");
+    }
+}
 
 #[derive(Deserialize)]
 pub struct RenderConfig {
     pub tracer: TracerConfig,
     pub image: ImageConfig,
     pub sampler: SamplerConfig,
+    pub global_seed: Option<u64>, // makes renders reproducible
     post_processing: PostProcessingConfig,
     performance: PerformanceConfig,
+    #[serde(default)]
+    pub passes: RenderPassesConfig,
+    pub checkpoint: Option<CheckpointConfig>,
+    pub crop: Option<CropConfig>,
+    pub progressive: Option<ProgressiveConfig>,
+    pub adaptive: Option<AdaptiveSamplingConfig>,
+}
+
+// saves the image every `interval_samples` samples per pixel, to
+// `output_template` with `{spp}` standing for the samples taken so far
+#[derive(Deserialize)]
+pub struct ProgressiveConfig {
+    pub interval_samples: usize,
+    pub output_template: String,
+}
+
+// window of the image that is rendered, the maxima are exclusive
+#[derive(Deserialize)]
+pub struct CropConfig {
+    pub x_min: u32,
+    pub y_min: u32,
+    pub x_max: u32,
+    pub y_max: u32,
+}
+
+// where and how often the progress of a render is saved, a render started
+// with an existing checkpoint of the same image resumes from it. one with
+// more samples per pixel than the checkpoint adds to its pixels, which with a
+// seed ends up where a render of that many samples does for samplers whose
+// first samples don't depend on the total count (random, sobol)
+#[derive(Deserialize)]
+pub struct CheckpointConfig {
+    pub path: String,
+    pub interval_secs: u64,
+    #[serde(default)]
+    pub keep: bool, // keeps the checkpoint of a finished render to add samples to later
+}
+
+// auxiliary buffers of the primary hits, e.g. for denoisers
+#[derive(Deserialize, Default)]
+pub struct RenderPassesConfig {
+    #[serde(default)]
+    pub albedo: bool,
+    #[serde(default)]
+    pub normal: bool,
+    #[serde(default)]
+    pub depth: bool,
+    #[serde(default)]
+    pub variance: bool,
+    #[serde(default)]
+    pub sample_count: bool,
+}
+
+pub struct RenderResult {
+    pub image: RgbImage,
+    pub albedo: Option<RgbImage>,
+    pub normal: Option<RgbImage>,
+    pub depth: Option<GrayImage>,
+    pub variance: Option<GrayImage>,
+    pub sample_count: Option<GrayImage>,
 }
 
 #[derive(Deserialize)]
@@ -26,20 +134,135 @@ pub struct ImageConfig {
 
 #[derive(Deserialize)]
 struct PostProcessingConfig {
+    exposure: Option<f64>, // in stops, scales the radiance by 2^exposure
     tone_mapping: Option<String>,
+    // .cube file for the "lut1d" and "lut3d" tone mappings
+    #[serde(
+        default,
+        rename = "lut_file",
+        deserialize_with = "deserialize_lut_file"
+    )]
+    lut: Option<CubeLut>,
     gamma_correction: bool,
+    color_space: Option<String>, // "srgb" or "linear", overrides `gamma_correction`
     white_balance: Option<Vec3DConfig>,
+    lens_distortion: Option<LensDistortionConfig>,
+    firefly_suppression: Option<f64>, // maximum luminance of a single sample
+}
+
+// Brown-Conrady coefficients, k1..k3 radial and p1, p2 tangential
+#[derive(Deserialize)]
+pub struct LensDistortionConfig {
+    pub k1: f64,
+    pub k2: f64,
+    pub k3: f64,
+    pub p1: f64,
+    pub p2: f64,
 }
 
 #[derive(Deserialize)]
 struct PerformanceConfig {
     parallelism: Option<usize>,
+    #[serde(default)]
+    tile_order: TileOrder,
+}
+
+// order tiles are handed out in, the curves keep consecutive tiles close
+// together on screen and so in the scene
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+pub enum TileOrder {
+    #[default]
+    Raster,
+    Morton,
+    Hilbert,
+}
+
+// position of (x, y) along the Z-order curve, the bits of both interleaved
+fn morton_code(x: usize, y: usize) -> u64 {
+    let spread = |v: usize| {
+        let mut v = v as u64 & 0xffff_ffff;
+        v = (v | (v << 16)) & 0x0000_ffff_0000_ffff;
+        v = (v | (v << 8)) & 0x00ff_00ff_00ff_00ff;
+        v = (v | (v << 4)) & 0x0f0f_0f0f_0f0f_0f0f;
+        v = (v | (v << 2)) & 0x3333_3333_3333_3333;
+        (v | (v << 1)) & 0x5555_5555_5555_5555
+    };
+    spread(x) | (spread(y) << 1)
+}
+
+// position of (x, y) along the Hilbert curve filling an `n` by `n` grid,
+// `n` a power of two
+fn hilbert_index(n: usize, x: usize, y: usize) -> u64 {
+    let (mut x, mut y) = (x, y);
+    let mut d = 0;
+    let mut s = n / 2;
+    while s > 0 {
+        let rx = (x & s > 0) as usize;
+        let ry = (y & s > 0) as usize;
+        d += (s * s * ((3 * rx) ^ ry)) as u64;
+        // rotate the quadrant so the curve inside it starts and ends right
+        if ry == 0 {
+            if rx == 1 {
+                x = s - 1 - (x & (s - 1));
+                y = s - 1 - (y & (s - 1));
+            }
+            std::mem::swap(&mut x, &mut y);
+        }
+        x &= s - 1;
+        y &= s - 1;
+        s /= 2;
+    }
+    d
+}
+
+// raster indices of all tiles in the order they should be rendered, grids
+// that aren't powers of two follow the curve of the enclosing one
+fn tile_order_indices(tiles_x: usize, tiles_y: usize, order: TileOrder) -> Vec<usize> {
+    let mut indices: Vec<usize> = (0..tiles_x * tiles_y).collect();
+    let n = tiles_x.max(tiles_y).next_power_of_two();
+    match order {
+        TileOrder::Raster => {}
+        TileOrder::Morton => indices.sort_by_key(|&i| morton_code(i % tiles_x, i / tiles_x)),
+        TileOrder::Hilbert => indices.sort_by_key(|&i| hilbert_index(n, i % tiles_x, i / tiles_x)),
+    }
+    indices
 }
 
 fn reinhard_tone_mapping(color: Vec3D) -> Vec3D {
     color.div_element_wise(color + Vec3D::new(1.0, 1.0, 1.0))
 }
 
+// Narkowicz's fit of the ACES filmic curve, clamped since it levels off
+// slightly above one
+fn aces_tone_mapping(color: Vec3D) -> Vec3D {
+    color.map(|x| {
+        let x = x.max(0.0);
+        (x * (2.51 * x + 0.03) / (x * (2.43 * x + 0.59) + 0.14)).min(1.0)
+    })
+}
+
+// Hable's filmic curve from Uncharted 2 with its usual exposure bias of 2,
+// normalized so that a linear white point of 11.2 maps to one
+fn uncharted2_tone_mapping(color: Vec3D) -> Vec3D {
+    let curve = |x: ScalarF| {
+        let (a, b, c, d, e, f) = (0.15, 0.50, 0.10, 0.20, 0.02, 0.30);
+        (x * (a * x + c * b) + d * e) / (x * (a * x + b) + d * f) - e / f
+    };
+    let white = curve(11.2);
+    color.map(|x| curve(2.0 * x.max(0.0)) / white)
+}
+
+// scales samples brighter than `max_luminance` down to it, trading a little
+// bias for the rare paths that would leave a white speck
+fn suppress_firefly(sample: Vec3D, max_luminance: ScalarF) -> Vec3D {
+    let l = luminance(sample);
+    if l > max_luminance {
+        sample * (max_luminance / l)
+    } else {
+        sample
+    }
+}
+
 fn gamma_correction(color: Vec3D) -> Vec3D {
     color.map(|c| c.powf(1.0 / 2.2))
 }
@@ -48,103 +271,1563 @@ fn white_balance(color: Vec3D, balance: Vec3D) -> Vec3D {
     color.mul_element_wise(balance)
 }
 
+// exposure, white balance and tone mapping work on linear radiance, the
+// color space conversion comes last
 fn post_process(color: Vec3D, config: &PostProcessingConfig) -> Vec3D {
+    let color = if let Some(exposure) = config.exposure {
+        color * (2.0 as ScalarF).powf(exposure as ScalarF)
+    } else {
+        color
+    };
+    let color = if let Some(white_balance_config) = &config.white_balance {
+        white_balance(color, white_balance_config.to_vec3())
+    } else {
+        color
+    };
     let color = if let Some(tone_mapping) = &config.tone_mapping {
         match tone_mapping.as_str() {
             "reinhard" => reinhard_tone_mapping(color),
+            "aces" => aces_tone_mapping(color),
+            "uncharted2" => uncharted2_tone_mapping(color),
+            // a lut of the other kind leaves the color as it is
+            "lut1d" => match &config.lut {
+                Some(CubeLut::OneD(lut)) => lut.apply(color),
+                _ => color,
+            },
+            "lut3d" => match &config.lut {
+                Some(CubeLut::ThreeD(lut)) => lut.apply(color),
+                _ => color,
+            },
             _ => color,
         }
     } else {
         color
     };
-    let color = if config.gamma_correction {
+    let gamma = match config.color_space.as_deref() {
+        Some("srgb") => true,
+        Some("linear") => false,
+        _ => config.gamma_correction,
+    };
+    if gamma {
         gamma_correction(color)
     } else {
         color
+    }
+}
+
+fn bilinear_sample(image: &RgbImage, x: ScalarF, y: ScalarF) -> [ScalarF; 3] {
+    // samples outside of the image are black
+    let texel = |x: i64, y: i64| -> [ScalarF; 3] {
+        if x < 0 || y < 0 || x >= image.width() as i64 || y >= image.height() as i64 {
+            return [0.0; 3];
+        }
+        let pixel = image.get_pixel(x as u32, y as u32);
+        [
+            pixel[0] as ScalarF,
+            pixel[1] as ScalarF,
+            pixel[2] as ScalarF,
+        ]
     };
-    let color = if let Some(white_balance_config) = &config.white_balance {
-        white_balance(color, white_balance_config.to_vec3())
+
+    let x0 = x.floor();
+    let y0 = y.floor();
+    let fx = x - x0;
+    let fy = y - y0;
+    let (x0, y0) = (x0 as i64, y0 as i64);
+    let c00 = texel(x0, y0);
+    let c10 = texel(x0 + 1, y0);
+    let c01 = texel(x0, y0 + 1);
+    let c11 = texel(x0 + 1, y0 + 1);
+
+    let mut color = [0.0; 3];
+    for i in 0..3 {
+        color[i] = (c00[i] * (1.0 - fx) + c10[i] * fx) * (1.0 - fy)
+            + (c01[i] * (1.0 - fx) + c11[i] * fx) * fy;
+    }
+    color
+}
+
+pub fn apply_lens_distortion(image: &RgbImage, config: &LensDistortionConfig) -> RgbImage {
+    let (width, height) = image.dimensions();
+    let cx = (width as ScalarF - 1.0) / 2.0;
+    let cy = (height as ScalarF - 1.0) / 2.0;
+    // normalize by the half diagonal so the corners lie at r = 1
+    let scale = (cx * cx + cy * cy).sqrt().max(1.0);
+    let [k1, k2, k3, p1, p2] =
+        [config.k1, config.k2, config.k3, config.p1, config.p2].map(|k| k as ScalarF);
+
+    ImageBuffer::from_fn(width, height, |px, py| {
+        let x = (px as ScalarF - cx) / scale;
+        let y = (py as ScalarF - cy) / scale;
+        let r2 = x * x + y * y;
+        let radial = 1.0 + k1 * r2 + k2 * r2 * r2 + k3 * r2 * r2 * r2;
+        let xd = x * radial + 2.0 * p1 * x * y + p2 * (r2 + 2.0 * x * x);
+        let yd = y * radial + p1 * (r2 + 2.0 * y * y) + 2.0 * p2 * x * y;
+
+        let color = bilinear_sample(image, cx + xd * scale, cy + yd * scale);
+        image::Rgb(color.map(|c| c.round().clamp(0.0, 255.0) as u8))
+    })
+}
+
+// radiance samples taken through one pixel, with their per channel running
+// mean and variance updated by welford's algorithm
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PixelSamples {
+    pub count: usize,
+    pub mean: Vec3D,
+    pub m2: Vec3D, // sums of squared differences from the mean
+}
+
+impl PixelSamples {
+    pub fn new() -> Self {
+        Self {
+            count: 0,
+            mean: Vec3D::new(0.0, 0.0, 0.0),
+            m2: Vec3D::new(0.0, 0.0, 0.0),
+        }
+    }
+
+    pub fn add(&mut self, sample: Vec3D) {
+        self.count += 1;
+        let delta = sample - self.mean;
+        self.mean += delta / self.count as ScalarF;
+        self.m2 += delta.mul_element_wise(sample - self.mean);
+    }
+
+    pub fn mean(&self) -> Vec3D {
+        self.mean
+    }
+
+    // per channel variance of a single sample
+    pub fn variance(&self) -> Vec3D {
+        if self.count == 0 {
+            return Vec3D::new(0.0, 0.0, 0.0);
+        }
+        self.m2 / self.count as ScalarF
+    }
+
+    // unbiased per channel variance of the mean
+    pub fn mean_variance(&self) -> Vec3D {
+        if self.count < 2 {
+            return Vec3D::new(0.0, 0.0, 0.0);
+        }
+        self.m2 / ((self.count - 1) * self.count) as ScalarF
+    }
+}
+
+// every sample taken through pixel (x, y), after the ones in `samples`
+fn render_pixel(
+    config: &RenderConfig,
+    scene: &Scene,
+    tracer: &mut dyn Tracer,
+    sampler: &mut dyn Sampler,
+    x: usize,
+    y: usize,
+    mut samples: PixelSamples,
+) -> PixelSamples {
+    sampler.start_pixel_sample(Point2U::new(x as u32, y as u32), samples.count);
+    take_samples(
+        config,
+        scene,
+        tracer,
+        sampler,
+        x,
+        y,
+        &mut samples,
+        usize::MAX,
+    );
+    samples
+}
+
+// continues sampling pixel (x, y) until `samples` holds `limit` of them,
+// false once the sampler has no samples left
+#[allow(clippy::too_many_arguments)]
+fn take_samples(
+    config: &RenderConfig,
+    scene: &Scene,
+    tracer: &mut dyn Tracer,
+    sampler: &mut dyn Sampler,
+    x: usize,
+    y: usize,
+    samples: &mut PixelSamples,
+    limit: usize,
+) -> bool {
+    let stopping_rule = StoppingRule::from_config(config);
+    while samples.count < limit {
+        let (u_offset, v_offset) = sampler.get_2d();
+        let u = (x as ScalarF + u_offset + 0.5) / config.image.width as ScalarF;
+        let v = 1.0 - (y as ScalarF + v_offset + 0.5) / config.image.height as ScalarF;
+        let ray = scene.camera.create_ray_with_sampler(u, v, sampler);
+        let mut sample = if scene.camera.covers(u, v) {
+            tracer.trace(&ray, scene, sampler)
+        } else {
+            Vec3D::new(0.0, 0.0, 0.0)
+        };
+        if let Some(max_luminance) = config.post_processing.firefly_suppression {
+            sample = suppress_firefly(sample, max_luminance as ScalarF);
+        }
+        samples.add(sample);
+        if stopping_rule.is_some_and(|rule| rule.converged(samples)) {
+            return false;
+        }
+        if !sampler.start_next_sample() {
+            return false;
+        }
+    }
+    true
+}
+
+pub fn render(config: &RenderConfig, scene: &Scene) -> RenderResult {
+    let pixel_count = config.image.width as u64 * config.image.height as u64;
+    render_with_progress(
+        config,
+        scene,
+        Arc::new(IndicatifProgressReporter::new(pixel_count)),
+    )
+}
+
+pub fn render_with_progress(
+    config: &RenderConfig,
+    scene: &Scene,
+    reporter: Arc<dyn ProgressReporter>,
+) -> RenderResult {
+    let samples = render_samples_with_progress(config, scene, reporter);
+    let pixels: Vec<Vec3D> = samples.iter().map(PixelSamples::mean).collect();
+    let image = to_ldr_image(config, &pixels);
+    let passes = &config.passes;
+    let (albedo, normal, depth) = if passes.albedo || passes.normal || passes.depth {
+        render_passes(config, scene)
     } else {
-        color
+        (None, None, None)
     };
-    color
+    RenderResult {
+        image,
+        albedo,
+        normal,
+        depth,
+        variance: passes.variance.then(|| variance_image(config, &samples)),
+        sample_count: saves_sample_count(config).then(|| sample_count_image(config, &samples)),
+    }
+}
+
+fn saves_sample_count(config: &RenderConfig) -> bool {
+    config.passes.sample_count
+        || config
+            .adaptive
+            .as_ref()
+            .is_some_and(|adaptive| adaptive.output_sample_map)
+}
+
+impl RenderConfig {
+    // whether anything is saved besides the image
+    pub fn has_passes(&self) -> bool {
+        let passes = &self.passes;
+        passes.albedo
+            || passes.normal
+            || passes.depth
+            || passes.variance
+            || saves_sample_count(self)
+    }
+}
+
+// log(1 + variance) of the noisiest channel, scaled so the noisiest pixel
+// is white
+fn variance_image(config: &RenderConfig, samples: &[PixelSamples]) -> GrayImage {
+    let noise: Vec<ScalarF> = samples
+        .iter()
+        .map(|samples| max_component(samples.variance()).ln_1p())
+        .collect();
+    let max_noise = noise.iter().fold(0.0, |max: ScalarF, &n| max.max(n));
+    GrayImage::from_fn(config.image.width, config.image.height, |x, y| {
+        let n = noise[(y * config.image.width + x) as usize];
+        let brightness = if max_noise > 0.0 { n / max_noise } else { 0.0 };
+        image::Luma([(brightness * 255.0).round() as u8])
+    })
+}
+
+// samples taken per pixel, scaled so the most sampled pixel is white
+fn sample_count_image(config: &RenderConfig, samples: &[PixelSamples]) -> GrayImage {
+    let max_count = samples
+        .iter()
+        .map(|samples| samples.count)
+        .max()
+        .unwrap_or(0);
+    GrayImage::from_fn(config.image.width, config.image.height, |x, y| {
+        let count = samples[(y * config.image.width + x) as usize].count;
+        let brightness = if max_count > 0 {
+            count as ScalarF / max_count as ScalarF
+        } else {
+            0.0
+        };
+        image::Luma([(brightness * 255.0).round() as u8])
+    })
 }
 
-pub fn render(config: &RenderConfig, scene: &Scene) -> RgbImage {
+// samples averaged for the albedo of materials that aren't diffuse
+const ALBEDO_SAMPLES: usize = 16;
+
+// casts one ray through the centre of every pixel and records what it hits.
+// the albedo is the share of light the surface reflects towards the camera,
+// escaped rays show the background. the normal is the shading normal and
+// depth the distance along the ray, infinite for misses. passes that aren't
+// enabled are none
+struct LinearPasses {
+    albedo: Option<Vec<Vec3D>>,
+    normal: Option<Vec<Vec3D>>,
+    depth: Option<Vec<ScalarF>>,
+}
+
+fn render_linear_passes(config: &RenderConfig, scene: &Scene) -> LinearPasses {
+    let (width, height) = (config.image.width, config.image.height);
+    let passes = &config.passes;
+    let pixel_count = (width * height) as usize;
+    let albedo = Mutex::new(vec![Vec3D::new(0.0, 0.0, 0.0); pixel_count]);
+    let normal = Mutex::new(vec![Vec3D::new(0.0, 0.0, 0.0); pixel_count]);
+    let depth = Mutex::new(vec![ScalarF::INFINITY; pixel_count]);
+
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(config.performance.parallelism.unwrap_or(1))
+        .build()
+        .unwrap();
+    pool.install(|| {
+        (0..height).into_par_iter().for_each(|y| {
+            if let Some(global_seed) = config.global_seed {
+                init_thread_local_rng(stream_seed(global_seed, y as u64));
+            }
+            let mut sampler = config.sampler.to_sampler();
+            for x in 0..width {
+                if !in_crop(config, x as usize, y as usize) {
+                    continue;
+                }
+                let i = (y * width + x) as usize;
+                let u = (x as ScalarF + 0.5) / width as ScalarF;
+                let v = 1.0 - (y as ScalarF + 0.5) / height as ScalarF;
+                // the same lens sample for every pass, so they line up with a
+                // defocused beauty image
+                sampler.start_pixel(Point2U::new(x, y));
+                let ray = scene.camera.create_ray_with_sampler(u, v, &mut *sampler);
+                if !scene.camera.covers(u, v) {
+                    continue;
+                }
+                let hit = scene.intersect(&ray);
+
+                if passes.albedo {
+                    let color = match &hit {
+                        Some(hit) => {
+                            let material = &hit.object.unwrap().material;
+                            let mut color = Vec3D::new(0.0, 0.0, 0.0);
+                            for _ in 0..ALBEDO_SAMPLES {
+                                if let Some(result) = material.scatter(&ray, hit, &mut *sampler) {
+                                    let cos_theta = result.ray.direction.dot(hit.normal).abs();
+                                    color += material.bxdf(&ray, &result.ray, hit) * cos_theta
+                                        / result.pdf;
+                                }
+                            }
+                            color / ALBEDO_SAMPLES as ScalarF
+                        }
+                        None => scene.background_radiance(ray.direction),
+                    };
+                    albedo.lock().unwrap()[i] = color;
+                }
+                if let Some(hit) = &hit {
+                    normal.lock().unwrap()[i] = hit.normal;
+                    depth.lock().unwrap()[i] = hit.t;
+                }
+            }
+        });
+    });
+
+    LinearPasses {
+        albedo: passes.albedo.then(|| albedo.into_inner().unwrap()),
+        normal: passes.normal.then(|| normal.into_inner().unwrap()),
+        depth: passes.depth.then(|| depth.into_inner().unwrap()),
+    }
+}
+
+// the passes as 8 bit images. normals map [-1, 1] to [0, 255] and depth is
+// brightest up close, fading to black at the farthest hit. misses are black
+fn render_passes(
+    config: &RenderConfig,
+    scene: &Scene,
+) -> (Option<RgbImage>, Option<RgbImage>, Option<GrayImage>) {
+    let (width, height) = (config.image.width, config.image.height);
+    let passes = render_linear_passes(config, scene);
+    let albedo = passes.albedo.map(|albedo| {
+        RgbImage::from_fn(width, height, |x, y| {
+            to_rgb(albedo[(y * width + x) as usize])
+        })
+    });
+    let normal = passes.normal.map(|normal| {
+        RgbImage::from_fn(width, height, |x, y| {
+            let n = normal[(y * width + x) as usize];
+            if n == Vec3D::new(0.0, 0.0, 0.0) {
+                image::Rgb([0, 0, 0])
+            } else {
+                to_rgb((n + Vec3D::new(1.0, 1.0, 1.0)) * 0.5)
+            }
+        })
+    });
+    let depth = passes.depth.map(|depth| {
+        let farthest = depth
+            .iter()
+            .filter(|t| t.is_finite())
+            .fold(0.0, |max: ScalarF, &t| max.max(t));
+        GrayImage::from_fn(width, height, |x, y| {
+            let t = depth[(y * width + x) as usize];
+            if t.is_finite() && farthest > 0.0 {
+                image::Luma([(255.0 * (1.0 - t / farthest)).round() as u8])
+            } else {
+                image::Luma([0])
+            }
+        })
+    });
+    (albedo, normal, depth)
+}
+
+// linear layers for a multilayer exr: the radiance as "beauty" and every
+// enabled pass. depth and the sample count fill all three channels, and
+// the depth of misses is zero
+pub fn render_layers(config: &RenderConfig, scene: &Scene) -> HashMap<String, Vec<Vec3D>> {
+    let pixel_count = config.image.width as u64 * config.image.height as u64;
+    let samples = render_samples_with_progress(
+        config,
+        scene,
+        Arc::new(IndicatifProgressReporter::new(pixel_count)),
+    );
+    let mut layers = HashMap::new();
+    layers.insert(
+        "beauty".to_string(),
+        samples.iter().map(PixelSamples::mean).collect(),
+    );
+
+    let passes = &config.passes;
+    if passes.albedo || passes.normal || passes.depth {
+        let linear = render_linear_passes(config, scene);
+        if let Some(albedo) = linear.albedo {
+            layers.insert("albedo".to_string(), albedo);
+        }
+        if let Some(normal) = linear.normal {
+            layers.insert("normal".to_string(), normal);
+        }
+        if let Some(depth) = linear.depth {
+            let depth = depth
+                .iter()
+                .map(|&t| Vec3D::from_value(if t.is_finite() { t } else { 0.0 }))
+                .collect();
+            layers.insert("depth".to_string(), depth);
+        }
+    }
+    if passes.variance {
+        layers.insert(
+            "variance".to_string(),
+            samples.iter().map(PixelSamples::variance).collect(),
+        );
+    }
+    if saves_sample_count(config) {
+        let counts = samples
+            .iter()
+            .map(|samples| Vec3D::from_value(samples.count as ScalarF))
+            .collect();
+        layers.insert("sample_count".to_string(), counts);
+    }
+    layers
+}
+
+// linear radiance of every pixel in row major order, before any post
+// processing. used as is for hdr output
+pub fn render_linear(config: &RenderConfig, scene: &Scene) -> Vec<Vec3D> {
+    let pixel_count = config.image.width as u64 * config.image.height as u64;
+    render_linear_with_progress(
+        config,
+        scene,
+        Arc::new(IndicatifProgressReporter::new(pixel_count)),
+    )
+}
+
+pub fn render_linear_with_progress(
+    config: &RenderConfig,
+    scene: &Scene,
+    reporter: Arc<dyn ProgressReporter>,
+) -> Vec<Vec3D> {
+    render_samples_with_progress(config, scene, reporter)
+        .iter()
+        .map(PixelSamples::mean)
+        .collect()
+}
+
+fn render_samples_with_progress(
+    config: &RenderConfig,
+    scene: &Scene,
+    reporter: Arc<dyn ProgressReporter>,
+) -> Vec<PixelSamples> {
+    if let TracerConfig::ProgressivePhotonMap(ppm_config) = &config.tracer {
+        return render_photon_mapped(config, scene, &ppm_config.to_tracer(), reporter);
+    }
+    if let Some(progressive) = &config.progressive {
+        return render_progressive(config, scene, progressive, reporter);
+    }
+
     let parallelism = config.performance.parallelism.unwrap_or(1);
-    rayon::ThreadPoolBuilder::new()
+    let pool = rayon::ThreadPoolBuilder::new()
         .num_threads(parallelism)
-        .build_global()
-        .unwrap();
-
-    let pixel_count = config.image.width as usize * config.image.height as usize;
-    let progress_bar = Arc::new(ProgressBar::new(pixel_count as u64));
-    progress_bar.set_style(
-        ProgressStyle::default_bar()
-            .template(
-                "{spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {percent}% ({eta})",
-            )
-            .expect("Failed to set progress bar style")
-            .progress_chars("#>-"),
-    );
-    let pb = progress_bar.clone();
+        .build()
+        .unwrap();
+
+    let width = config.image.width as usize;
+    let pixel_count = config.image.width as u64 * config.image.height as u64;
+    let completed = AtomicU64::new(0);
 
     let tile_size = 16;
     let tiles_x = (config.image.width as usize + tile_size - 1) / tile_size;
     let tiles_y = (config.image.height as usize + tile_size - 1) / tile_size;
-    let tile_count = tiles_x * tiles_y;
-    let img = Arc::new(Mutex::new(ImageBuffer::new(
-        config.image.width,
-        config.image.height,
-    )));
-
-    (0..tile_count)
-        .into_par_iter()
-        .for_each_with(pb, |pb, tile_index| {
+    let pixels = Arc::new(Mutex::new(vec![PixelSamples::new(); pixel_count as usize]));
+
+    // a checkpoint of fewer samples per pixel is only read, the pixels it
+    // holds are sampled further and saved in a new one
+    let samples_per_pixel = config.sampler.to_sampler().samples_per_pixel() as u64;
+    let (checkpoint, previous) = match &config.checkpoint {
+        Some(checkpoint_config) => {
+            let loaded = load_checkpoint(config, &checkpoint_config.path);
+            if loaded.spp_so_far == samples_per_pixel {
+                (Some(Mutex::new(loaded)), None)
+            } else {
+                let (width, height) = (config.image.width, config.image.height);
+                let checkpoint = Checkpoint::new(width, height, samples_per_pixel);
+                (Some(Mutex::new(checkpoint)), Some(loaded))
+            }
+        }
+        None => (None, None),
+    };
+    let last_save = Mutex::new(Instant::now());
+
+    pool.install(|| {
+        let order = tile_order_indices(tiles_x, tiles_y, config.performance.tile_order);
+        order.into_par_iter().for_each(|tile_index| {
             let tile_x = tile_index % tiles_x;
             let tile_y = tile_index / tiles_x;
             let x_start = tile_x * tile_size;
             let y_start = tile_y * tile_size;
             let x_end = (x_start + tile_size).min(config.image.width as usize);
             let y_end = (y_start + tile_size).min(config.image.height as usize);
+            let tile_pixel_count = ((x_end - x_start) * (y_end - y_start)) as u64;
+
+            // only the part of the tile inside the crop window is rendered,
+            // the rest is counted as done and stays black
+            let (x_start, x_end, y_start, y_end) = match &config.crop {
+                Some(crop) => (
+                    x_start.max(crop.x_min as usize),
+                    x_end.min(crop.x_max as usize),
+                    y_start.max(crop.y_min as usize),
+                    y_end.min(crop.y_max as usize),
+                ),
+                None => (x_start, x_end, y_start, y_end),
+            };
+            let (x_end, y_end) = (x_end.max(x_start), y_end.max(y_start));
+            let skipped = tile_pixel_count - ((x_end - x_start) * (y_end - y_start)) as u64;
+            if skipped > 0 {
+                let done = completed.fetch_add(skipped, Ordering::Relaxed) + skipped;
+                reporter.update(done, pixel_count);
+            }
 
+            let tile_pixels =
+                || (y_start..y_end).flat_map(|y| (x_start..x_end).map(move |x| y * width + x));
+
+            // tiles finished before the checkpoint was written are taken
+            // from it as they are
+            if let Some(checkpoint) = &checkpoint {
+                let checkpoint = checkpoint.lock().unwrap();
+                if tile_pixels().all(|i| checkpoint.pixels[i].count > 0) {
+                    let mut pixels = pixels.lock().unwrap();
+                    for i in tile_pixels() {
+                        pixels[i] = checkpoint.pixels[i];
+                    }
+                    let rendered = tile_pixel_count - skipped;
+                    let done = completed.fetch_add(rendered, Ordering::Relaxed) + rendered;
+                    reporter.update(done, pixel_count);
+                    return;
+                }
+            }
+
+            let resumed = previous
+                .as_ref()
+                .filter(|previous| tile_pixels().all(|i| previous.pixels[i].count > 0));
             let mut tracer = config.tracer.to_tracer();
             let mut sampler = config.sampler.to_sampler();
-            let spp = sampler.samples_per_pixel();
+            let mut tile = Vec::new();
             for y in y_start..y_end {
                 for x in x_start..x_end {
-                    sampler.start_pixel(Point2U::new(x as u32, y as u32));
-                    let mut color = Vec3D::new(0.0, 0.0, 0.0);
-                    loop {
-                        let (u_offset, v_offset) = sampler.get_2d();
-                        let u = (x as f64 + u_offset + 0.5) / config.image.width as f64;
-                        let v = 1.0 - (y as f64 + v_offset + 0.5) / config.image.height as f64;
-                        let ray = scene.camera.create_ray(u, v);
-                        color += tracer.trace(&ray, scene, &mut *sampler);
-                        if !sampler.start_next_sample() {
-                            break;
-                        }
+                    // seeded per pixel, so a pixel comes out the same whatever
+                    // order tiles are handed to threads in and whatever else
+                    // is rendered around it
+                    if let Some(global_seed) = config.global_seed {
+                        init_thread_local_rng(stream_seed(global_seed, (y * width + x) as u64));
+                        sampler = config.sampler.to_sampler();
                     }
-                    color /= spp as f64;
-                    color = post_process(color, &config.post_processing);
-                    let mut img = img.lock().unwrap();
-                    let img_pixel = img.get_pixel_mut(x as u32, y as u32);
-                    *img_pixel = image::Rgb([
-                        (color.x * 255.0).min(255.0) as u8,
-                        (color.y * 255.0).min(255.0) as u8,
-                        (color.z * 255.0).min(255.0) as u8,
-                    ]);
-
-                    pb.inc(1);
+                    let taken = resumed.map_or(PixelSamples::new(), |previous| {
+                        previous.pixels[y * width + x]
+                    });
+                    let samples =
+                        render_pixel(config, scene, &mut *tracer, &mut *sampler, x, y, taken);
+                    pixels.lock().unwrap()[y * width + x] = samples;
+                    tile.push((y * width + x, samples));
+
+                    let done = completed.fetch_add(1, Ordering::Relaxed) + 1;
+                    reporter.update(done, pixel_count);
+                }
+            }
+
+            // only whole tiles are recorded, a resumed render redoes the
+            // unfinished ones with the same seeds
+            if let (Some(checkpoint), Some(checkpoint_config)) = (&checkpoint, &config.checkpoint) {
+                let mut checkpoint = checkpoint.lock().unwrap();
+                for (i, samples) in tile {
+                    checkpoint.pixels[i] = samples;
+                }
+                let mut last_save = last_save.lock().unwrap();
+                if last_save.elapsed() >= Duration::from_secs(checkpoint_config.interval_secs) {
+                    if let Err(e) = checkpoint.save(&checkpoint_config.path) {
+                        warn!("{}", e);
+                    }
+                    *last_save = Instant::now();
                 }
             }
         });
-    Arc::try_unwrap(progress_bar)
-        .expect("Failed to unwrap progress bar")
-        .finish_with_message("Render complete!");
+    });
+    reporter.finish();
 
-    Arc::try_unwrap(img)
-        .expect("Failed to unwrap image")
+    // a finished render has nothing left to resume, but may be added to
+    if let (Some(checkpoint), Some(checkpoint_config)) = (&checkpoint, &config.checkpoint) {
+        if checkpoint_config.keep {
+            if let Err(e) = checkpoint.lock().unwrap().save(&checkpoint_config.path) {
+                warn!("{}", e);
+            }
+        } else {
+            let _ = std::fs::remove_file(&checkpoint_config.path);
+        }
+    }
+
+    Arc::try_unwrap(pixels)
+        .expect("Failed to unwrap pixels")
         .into_inner()
         .unwrap()
 }
+
+// `template` with `{spp}` replaced by the sample count, padded with zeros to
+// as many digits as the final count has so the files sort in order
+fn progressive_output_path(template: &str, spp: usize, total_spp: usize) -> String {
+    let digits = total_spp.to_string().len().max(3);
+    template.replace("{spp}", &format!("{:0digits$}", spp, digits = digits))
+}
+
+fn in_crop(config: &RenderConfig, x: usize, y: usize) -> bool {
+    config.crop.as_ref().is_none_or(|crop| {
+        (crop.x_min as usize..crop.x_max as usize).contains(&x)
+            && (crop.y_min as usize..crop.y_max as usize).contains(&y)
+    })
+}
+
+// renders the whole image in rounds of `interval_samples` samples per pixel
+// and saves it after every round. the sampler of every pixel is kept between
+// rounds, so the image after k samples is the one a render of k samples
+// gives as long as the first samples don't depend on the total count
+fn render_progressive(
+    config: &RenderConfig,
+    scene: &Scene,
+    progressive: &ProgressiveConfig,
+    reporter: Arc<dyn ProgressReporter>,
+) -> Vec<PixelSamples> {
+    if config.checkpoint.is_some() {
+        warn!("Progressive renders don't write checkpoints");
+    }
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(config.performance.parallelism.unwrap_or(1))
+        .build()
+        .unwrap();
+
+    let width = config.image.width as usize;
+    let pixel_count = width * config.image.height as usize;
+    let total_spp = config.sampler.to_sampler().samples_per_pixel();
+    let interval = progressive.interval_samples.max(1);
+    let rounds = total_spp.div_ceil(interval);
+    let completed = AtomicU64::new(0);
+    let total = (pixel_count * rounds) as u64;
+
+    let mut pixels = vec![PixelSamples::new(); pixel_count];
+    let mut samplers: Vec<Option<Box<dyn Sampler>>> = (0..pixel_count).map(|_| None).collect();
+    let mut active: Vec<bool> = (0..pixel_count)
+        .map(|i| in_crop(config, i % width, i / width))
+        .collect();
+    for round in 1..=rounds {
+        let limit = (round * interval).min(total_spp);
+        pool.install(|| {
+            pixels
+                .par_chunks_mut(width)
+                .zip(samplers.par_chunks_mut(width))
+                .zip(active.par_chunks_mut(width))
+                .enumerate()
+                .for_each(|(y, ((pixels, samplers), active))| {
+                    let mut tracer = config.tracer.to_tracer();
+                    for x in 0..width {
+                        if !active[x] {
+                            continue;
+                        }
+                        let sampler = samplers[x].get_or_insert_with(|| {
+                            if let Some(global_seed) = config.global_seed {
+                                init_thread_local_rng(stream_seed(
+                                    global_seed,
+                                    (y * width + x) as u64,
+                                ));
+                            }
+                            let mut sampler = config.sampler.to_sampler();
+                            sampler.start_pixel(Point2U::new(x as u32, y as u32));
+                            sampler
+                        });
+                        active[x] = take_samples(
+                            config,
+                            scene,
+                            &mut *tracer,
+                            &mut **sampler,
+                            x,
+                            y,
+                            &mut pixels[x],
+                            limit,
+                        );
+                        // finished pixels don't need their sampler any more
+                        if !active[x] {
+                            samplers[x] = None;
+                        }
+                    }
+                    let done = completed.fetch_add(width as u64, Ordering::Relaxed);
+                    reporter.update(done + width as u64, total);
+                });
+        });
+
+        let means: Vec<Vec3D> = pixels.iter().map(PixelSamples::mean).collect();
+        let path = progressive_output_path(&progressive.output_template, limit, total_spp);
+        if let Err(e) = to_ldr_image(config, &means).save(&path) {
+            warn!("Failed to save {}: {}", path, e);
+        }
+    }
+    reporter.finish();
+    pixels
+}
+
+// the checkpoint at `path` if it was written for this render, an empty one
+// otherwise
+fn load_checkpoint(config: &RenderConfig, path: &str) -> Checkpoint {
+    let (width, height) = (config.image.width, config.image.height);
+    let samples_per_pixel = config.sampler.to_sampler().samples_per_pixel() as u64;
+    match Checkpoint::load(path) {
+        Ok(checkpoint) if checkpoint.matches(width, height, samples_per_pixel) => checkpoint,
+        Ok(_) => {
+            warn!("Ignoring checkpoint {} of a different render", path);
+            Checkpoint::new(width, height, samples_per_pixel)
+        }
+        Err(_) => Checkpoint::new(width, height, samples_per_pixel),
+    }
+}
+
+// photon passes gather around the visible points of the whole image at once,
+// so the camera rays of every pixel are collected before tracing
+fn render_photon_mapped(
+    config: &RenderConfig,
+    scene: &Scene,
+    tracer: &ProgressivePhotonMapTracer,
+    reporter: Arc<dyn ProgressReporter>,
+) -> Vec<PixelSamples> {
+    if let Some(global_seed) = config.global_seed {
+        init_thread_local_rng(stream_seed(global_seed, 0));
+    }
+    let (width, height) = (config.image.width, config.image.height);
+    let mut sampler = config.sampler.to_sampler();
+    let mut rays = Vec::new();
+    let mut covered = Vec::new();
+    let mut pixel_ends = Vec::new();
+    for y in 0..height {
+        for x in 0..width {
+            // pixels outside the crop get no rays and stay black
+            if !in_crop(config, x as usize, y as usize) {
+                pixel_ends.push(rays.len());
+                continue;
+            }
+            sampler.start_pixel(Point2U::new(x, y));
+            loop {
+                let (u_offset, v_offset) = sampler.get_2d();
+                let u = (x as ScalarF + u_offset + 0.5) / width as ScalarF;
+                let v = 1.0 - (y as ScalarF + v_offset + 0.5) / height as ScalarF;
+                rays.push(scene.camera.create_ray_with_sampler(u, v, sampler.as_mut()));
+                covered.push(scene.camera.covers(u, v));
+                if !sampler.start_next_sample() {
+                    break;
+                }
+            }
+            pixel_ends.push(rays.len());
+        }
+    }
+
+    let mut colors = tracer.estimate(&rays, scene, &mut *sampler);
+    for (color, &covered) in colors.iter_mut().zip(&covered) {
+        if !covered {
+            *color = Vec3D::new(0.0, 0.0, 0.0);
+        }
+    }
+    let mut pixels = Vec::with_capacity(pixel_ends.len());
+    let mut start = 0;
+    for &end in &pixel_ends {
+        let mut samples = PixelSamples::new();
+        for &color in &colors[start..end] {
+            samples.add(match config.post_processing.firefly_suppression {
+                Some(max_luminance) => suppress_firefly(color, max_luminance as ScalarF),
+                None => color,
+            });
+        }
+        pixels.push(samples);
+        start = end;
+    }
+    let pixel_count = width as u64 * height as u64;
+    reporter.update(pixel_count, pixel_count);
+    reporter.finish();
+
+    pixels
+}
+
+// post processed 8 bit image of the linear `pixels`
+pub fn to_ldr_image(config: &RenderConfig, pixels: &[Vec3D]) -> RgbImage {
+    let width = config.image.width;
+    let img = ImageBuffer::from_fn(width, config.image.height, |x, y| {
+        let color = pixels[(y * width + x) as usize];
+        to_rgb(post_process(color, &config.post_processing))
+    });
+    apply_image_post_processing(img, &config.post_processing)
+}
+
+fn to_rgb(color: Vec3D) -> image::Rgb<u8> {
+    image::Rgb([
+        (color.x * 255.0).min(255.0) as u8,
+        (color.y * 255.0).min(255.0) as u8,
+        (color.z * 255.0).min(255.0) as u8,
+    ])
+}
+
+fn apply_image_post_processing(img: RgbImage, config: &PostProcessingConfig) -> RgbImage {
+    match &config.lens_distortion {
+        Some(lens_distortion) => apply_lens_distortion(&img, lens_distortion),
+        None => img,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::{tolerance, vec3_approx_eq};
+    use crate::scene::SceneConfig;
+    use cgmath::Array;
+    use std::sync::atomic::AtomicUsize;
+
+    const TEST_RENDER_CONFIG: &str = r#"
+        [tracer]
+        type = "mcpt"
+        min_depth = 2
+        max_depth = 4
+
+        [image]
+        width = 20
+        height = 20
+
+        [sampler]
+        type = "Random"
+        samples_per_pixel = 1
+
+        [post_processing]
+        gamma_correction = false
+
+        [performance]
+        parallelism = 2
+    "#;
+
+    const TEST_SCENE_CONFIG: &str = r#"
+        [camera]
+        type = "Perspective"
+        look_from = { x = 0.0, y = 0.0, z = 5.0 }
+        look_at = { x = 0.0, y = 0.0, z = 0.0 }
+        vup = { x = 0.0, y = 1.0, z = 0.0 }
+        vfov = 40.0
+        aspect = 1.0
+
+        [[objects]]
+        [objects.shape]
+        type = "Sphere"
+        center = { x = 0.0, y = 0.0, z = 0.0 }
+        radius = 1.0
+        [objects.material]
+        type = "Emissive"
+        color = { x = 1.0, y = 1.0, z = 1.0 }
+    "#;
+
+    struct RecordingReporter {
+        updates: Mutex<Vec<(u64, u64)>>,
+        finishes: AtomicUsize,
+    }
+
+    impl ProgressReporter for RecordingReporter {
+        fn update(&self, completed: u64, total: u64) {
+            self.updates.lock().unwrap().push((completed, total));
+        }
+
+        fn finish(&self) {
+            self.finishes.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    fn recording_reporter() -> Arc<RecordingReporter> {
+        Arc::new(RecordingReporter {
+            updates: Mutex::new(Vec::new()),
+            finishes: AtomicUsize::new(0),
+        })
+    }
+
+    #[test]
+    fn test_render_with_progress() {
+        let render_config: RenderConfig = toml::from_str(TEST_RENDER_CONFIG).unwrap();
+        let scene_config: SceneConfig = toml::from_str(TEST_SCENE_CONFIG).unwrap();
+        let scene = Scene::from_config(&scene_config).unwrap();
+
+        let reporter = recording_reporter();
+        render_with_progress(&render_config, &scene, reporter.clone());
+
+        let updates = reporter.updates.lock().unwrap();
+        assert!(updates
+            .iter()
+            .any(|&(completed, total)| completed > 0 && completed < total));
+        assert!(updates.iter().all(|&(_, total)| total == 400));
+        assert_eq!(
+            updates.iter().map(|&(completed, _)| completed).max(),
+            Some(400)
+        );
+        assert_eq!(reporter.finishes.load(Ordering::SeqCst), 1);
+    }
+
+    fn test_image() -> RgbImage {
+        ImageBuffer::from_fn(33, 21, |x, y| {
+            image::Rgb([(x * 7) as u8, (y * 11) as u8, ((x * y) % 256) as u8])
+        })
+    }
+
+    #[test]
+    fn test_lens_distortion_identity() {
+        let image = test_image();
+        let config = LensDistortionConfig {
+            k1: 0.0,
+            k2: 0.0,
+            k3: 0.0,
+            p1: 0.0,
+            p2: 0.0,
+        };
+        assert_eq!(apply_lens_distortion(&image, &config), image);
+    }
+
+    #[test]
+    fn test_lens_distortion_barrel() {
+        let image = test_image();
+        let config = LensDistortionConfig {
+            k1: 0.3,
+            k2: 0.0,
+            k3: 0.0,
+            p1: 0.0,
+            p2: 0.0,
+        };
+        let distorted = apply_lens_distortion(&image, &config);
+
+        // the centre is fixed
+        assert_eq!(distorted.get_pixel(16, 10), image.get_pixel(16, 10));
+        // off-centre pixels sample from farther out, so the red gradient
+        // (proportional to x) grows to the right and shrinks to the left
+        assert!(distorted.get_pixel(28, 10)[0] > image.get_pixel(28, 10)[0]);
+        assert!(distorted.get_pixel(4, 10)[0] < image.get_pixel(4, 10)[0]);
+        assert!(distorted.get_pixel(16, 18)[1] > image.get_pixel(16, 18)[1]);
+    }
+
+    #[test]
+    fn test_adaptive_sampling() {
+        let render_config: RenderConfig = toml::from_str(&TEST_RENDER_CONFIG.replace(
+            "type = \"Random\"\n        samples_per_pixel = 1",
+            "type = \"Adaptive\"\n        min_samples = 8\n        max_samples = 256\n        threshold = 0.05",
+        ))
+        .unwrap();
+        let scene_config: SceneConfig = toml::from_str(TEST_SCENE_CONFIG).unwrap();
+        let scene = Scene::from_config(&scene_config).unwrap();
+        init_thread_local_rng(1);
+        let mut tracer = render_config.tracer.to_tracer();
+        let mut sampler = render_config.sampler.to_sampler();
+
+        // inside the emissive sphere every sample sees the same radiance
+        let inside = render_pixel(
+            &render_config,
+            &scene,
+            &mut *tracer,
+            &mut *sampler,
+            9,
+            9,
+            PixelSamples::new(),
+        );
+        assert_eq!(inside.count, 8);
+        assert!((inside.mean().x - 1.0).abs() < tolerance(1e-9));
+
+        // about half of pixel (5, 5) is covered by the silhouette
+        let edge = render_pixel(
+            &render_config,
+            &scene,
+            &mut *tracer,
+            &mut *sampler,
+            5,
+            5,
+            PixelSamples::new(),
+        )
+        .count;
+        assert!(edge > 200, "{} samples on the edge", edge);
+    }
+
+    #[test]
+    fn test_global_seed_reproducible() {
+        let render_config: RenderConfig =
+            toml::from_str(&format!("global_seed = 42\n{}", TEST_RENDER_CONFIG)).unwrap();
+        let scene_config: SceneConfig = toml::from_str(TEST_SCENE_CONFIG).unwrap();
+        let scene = Scene::from_config(&scene_config).unwrap();
+
+        let render = || render_with_progress(&render_config, &scene, recording_reporter());
+        assert_eq!(render().image.into_raw(), render().image.into_raw());
+    }
+
+    #[test]
+    fn test_aces_tone_mapping() {
+        let mapped = aces_tone_mapping(Vec3D::from_value(0.18));
+        assert!((mapped.x - 0.2669).abs() < 1e-4);
+
+        let mut previous = 0.0;
+        for i in 0..=10_000 {
+            let x = i as ScalarF * 0.01;
+            let y = aces_tone_mapping(Vec3D::from_value(x)).x;
+            assert!((0.0..=1.0).contains(&y));
+            assert!(y >= previous);
+            previous = y;
+        }
+        assert_eq!(aces_tone_mapping(Vec3D::from_value(1e9)).x, 1.0);
+    }
+
+    #[test]
+    fn test_uncharted2_tone_mapping() {
+        assert!(uncharted2_tone_mapping(Vec3D::from_value(0.0)).x.abs() < tolerance(1e-12));
+        assert!((uncharted2_tone_mapping(Vec3D::from_value(5.6)).x - 1.0).abs() < tolerance(1e-12));
+
+        // both filmic curves keep white below one, with their own contrast
+        let aces = aces_tone_mapping(Vec3D::from_value(1.0));
+        let uncharted2 = uncharted2_tone_mapping(Vec3D::from_value(1.0));
+        for mapped in [aces, uncharted2] {
+            assert!(mapped.x > 0.0 && mapped.x < 1.0);
+        }
+        assert!((aces.x - uncharted2.x).abs() > 0.05);
+    }
+
+    #[test]
+    fn test_post_process_exposure() {
+        let config = |extra: &str| -> PostProcessingConfig {
+            toml::from_str(&format!("gamma_correction = false\n{}", extra)).unwrap()
+        };
+        let color = Vec3D::new(0.2, 0.5, 0.1);
+        let base = post_process(color, &config(""));
+        assert!(vec3_approx_eq(base, color, tolerance(1e-12)));
+
+        // one stop doubles the radiance reaching the tone mapping
+        let brighter = post_process(color, &config("exposure = 1.0"));
+        assert!((luminance(brighter) - 2.0 * luminance(base)).abs() < tolerance(1e-12));
+        let darker = post_process(color, &config("exposure = -2.0"));
+        assert!(vec3_approx_eq(darker, color / 4.0, tolerance(1e-12)));
+
+        // exposure and white balance come before the curve
+        let mapped = post_process(
+            color,
+            &config("exposure = 1.0\ntone_mapping = \"reinhard\"\nwhite_balance = { x = 1.0, y = 2.0, z = 1.0 }"),
+        );
+        let expected = reinhard_tone_mapping(Vec3D::new(0.4, 2.0, 0.2));
+        assert!(vec3_approx_eq(mapped, expected, tolerance(1e-12)));
+
+        // the color space takes over from `gamma_correction`
+        let srgb = post_process(color, &config("color_space = \"srgb\""));
+        assert!(vec3_approx_eq(
+            srgb,
+            gamma_correction(color),
+            tolerance(1e-12)
+        ));
+        let linear: PostProcessingConfig =
+            toml::from_str("gamma_correction = true\ncolor_space = \"linear\"").unwrap();
+        assert!(vec3_approx_eq(
+            post_process(color, &linear),
+            color,
+            tolerance(1e-12)
+        ));
+    }
+
+    #[test]
+    fn test_post_process_lut_file() {
+        let path = std::env::temp_dir().join("rust_ray_tracer_test_contrast.cube");
+        std::fs::write(
+            &path,
+            "LUT_1D_SIZE 5\n0 0 0\n0.1 0.1 0.1\n0.5 0.5 0.5\n0.9 0.9 0.9\n1 1 1\n",
+        )
+        .unwrap();
+        let config = |tone_mapping: &str| -> PostProcessingConfig {
+            toml::from_str(&format!(
+                "gamma_correction = false\ntone_mapping = \"{}\"\nlut_file = {:?}",
+                tone_mapping,
+                path.to_str().unwrap()
+            ))
+            .unwrap()
+        };
+        let shadow = Vec3D::from_value(0.2);
+        assert!(post_process(shadow, &config("lut1d")).x < 0.2);
+        // the file holds a 1d lut
+        assert!(vec3_approx_eq(
+            post_process(shadow, &config("lut3d")),
+            shadow,
+            tolerance(1e-12)
+        ));
+
+        let missing = toml::from_str::<PostProcessingConfig>(
+            "gamma_correction = false\nlut_file = \"missing.cube\"",
+        );
+        assert!(missing.err().unwrap().to_string().contains("missing.cube"));
+    }
+
+    #[test]
+    fn test_tile_order_indices() {
+        // every tile exactly once, whatever the shape of the grid
+        for order in [TileOrder::Raster, TileOrder::Morton, TileOrder::Hilbert] {
+            for (tiles_x, tiles_y) in [(4, 4), (5, 3), (1, 7)] {
+                let mut indices = tile_order_indices(tiles_x, tiles_y, order);
+                indices.sort();
+                assert_eq!(indices, (0..tiles_x * tiles_y).collect::<Vec<_>>());
+            }
+        }
+
+        // Z-order finishes each 2x2 block before moving on
+        let morton = tile_order_indices(4, 4, TileOrder::Morton);
+        assert_eq!(morton[..8], [0, 1, 4, 5, 2, 3, 6, 7]);
+        assert_eq!(morton[15], 15);
+
+        // consecutive Hilbert tiles are always neighbours
+        let hilbert = tile_order_indices(4, 4, TileOrder::Hilbert);
+        for pair in hilbert.windows(2) {
+            let (a, b) = (pair[0], pair[1]);
+            let distance = (a % 4).abs_diff(b % 4) + (a / 4).abs_diff(b / 4);
+            assert_eq!(distance, 1);
+        }
+    }
+
+    #[test]
+    fn test_albedo_pass() {
+        let render_config: RenderConfig = toml::from_str(&format!(
+            "{}\n        [passes]\n        albedo = true\n        depth = true",
+            TEST_RENDER_CONFIG
+        ))
+        .unwrap();
+        let scene_config: SceneConfig = toml::from_str(
+            &TEST_SCENE_CONFIG
+                .replace(
+                    "type = \"Emissive\"\n        color = { x = 1.0, y = 1.0, z = 1.0 }",
+                    "type = \"Lambertian\"\n        albedo = { x = 0.8, y = 0.4, z = 0.2 }",
+                )
+                .replace(
+                    "[[objects]]",
+                    "[background]\n        type = \"Constant\"\n        color = { x = 0.0, y = 0.5, z = 1.0 }\n\n        [[objects]]",
+                ),
+        )
+        .unwrap();
+        let scene = Scene::from_config(&scene_config).unwrap();
+        let result = render_with_progress(&render_config, &scene, recording_reporter());
+        assert!(result.normal.is_none());
+
+        let albedo = result.albedo.unwrap();
+        assert_eq!(albedo.get_pixel(10, 10), &to_rgb(Vec3D::new(0.8, 0.4, 0.2)));
+        assert_eq!(albedo.get_pixel(0, 0), &to_rgb(Vec3D::new(0.0, 0.5, 1.0)));
+
+        let depth = result.depth.unwrap();
+        assert!(depth.get_pixel(10, 10)[0] > 0);
+        assert_eq!(depth.get_pixel(0, 0)[0], 0);
+    }
+
+    #[test]
+    fn test_tracer_from_config() {
+        let with_tracer = |tracer: &str| -> RenderConfig {
+            let base = TEST_RENDER_CONFIG.replace(
+                "[tracer]\n        type = \"mcpt\"\n        min_depth = 2\n        max_depth = 4\n",
+                "",
+            );
+            toml::from_str(&format!("global_seed = 7\ntracer = {}\n{}", tracer, base)).unwrap()
+        };
+        let inline = with_tracer(r#"{ type = "mcpt", min_depth = 2, max_depth = 6 }"#);
+        match &inline.tracer {
+            TracerConfig::MonteCarloPathTracer(config) => {
+                assert_eq!((config.min_depth, config.max_depth), (2, 6))
+            }
+            _ => panic!("tracer should be mcpt"),
+        }
+        let scene_config: SceneConfig = toml::from_str(TEST_SCENE_CONFIG).unwrap();
+        let scene = Scene::from_config(&scene_config).unwrap();
+        let mean = |pixels: &[Vec3D]| pixels.iter().sum::<Vec3D>().x / pixels.len() as ScalarF;
+
+        // the sphere only emits, every tracer sees it the same
+        let pixels = render_linear_with_progress(&inline, &scene, recording_reporter());
+        let table: RenderConfig = toml::from_str(&format!(
+            "global_seed = 7\n{}",
+            TEST_RENDER_CONFIG.replace("max_depth = 4", "max_depth = 6")
+        ))
+        .unwrap();
+        assert_eq!(
+            pixels,
+            render_linear_with_progress(&table, &scene, recording_reporter())
+        );
+        let whitted = with_tracer(r#"{ type = "whitted", max_depth = 6 }"#);
+        let whitted_pixels = render_linear_with_progress(&whitted, &scene, recording_reporter());
+        assert!(mean(&pixels) > 0.1);
+        assert!((mean(&pixels) - mean(&whitted_pixels)).abs() < tolerance(1e-12));
+    }
+
+    #[test]
+    fn test_checkpoint_resume() {
+        let path = std::env::temp_dir().join("rust_ray_tracer_test_resume.bin");
+        let path = path.to_str().unwrap().to_string();
+        let with_spp = |spp: usize, checkpoint: bool| -> RenderConfig {
+            let base = TEST_RENDER_CONFIG.replace(
+                "samples_per_pixel = 1",
+                &format!("samples_per_pixel = {}", spp),
+            );
+            let checkpoint = if checkpoint {
+                format!(
+                    "\n        [checkpoint]\n        path = {:?}\n        interval_secs = 0\n        keep = true",
+                    path
+                )
+            } else {
+                String::new()
+            };
+            toml::from_str(&format!("global_seed = 3\n{}{}", base, checkpoint)).unwrap()
+        };
+        let scene_config: SceneConfig = toml::from_str(TEST_SCENE_CONFIG).unwrap();
+        let scene = Scene::from_config(&scene_config).unwrap();
+        let expected =
+            render_linear_with_progress(&with_spp(20, false), &scene, recording_reporter());
+
+        // a finished render of 10 samples per pixel taken to 20
+        let _ = std::fs::remove_file(&path);
+        render_linear_with_progress(&with_spp(10, true), &scene, recording_reporter());
+        let mut checkpoint = Checkpoint::load(&path).unwrap();
+        assert_eq!(checkpoint.spp_so_far, 10);
+        assert!(checkpoint.pixels.iter().all(|pixel| pixel.count == 10));
+        // marks the first pixel to tell its samples were taken over
+        checkpoint.pixels[0].mean += Vec3D::new(2.0, 0.0, 0.0);
+        checkpoint.save(&path).unwrap();
+        let pixels = render_linear_with_progress(&with_spp(20, true), &scene, recording_reporter());
+        assert!(
+            (pixels[0] - expected[0] - Vec3D::new(1.0, 0.0, 0.0)).magnitude() < tolerance(1e-12)
+        );
+        for (pixel, expected) in pixels.iter().zip(&expected).skip(1) {
+            assert!((pixel - expected).magnitude() < tolerance(1e-12));
+        }
+        let mut checkpoint = Checkpoint::load(&path).unwrap();
+        assert_eq!(checkpoint.spp_so_far, 20);
+        assert!(checkpoint.pixels.iter().all(|pixel| pixel.count == 20));
+
+        // tiles of a checkpoint with as many samples are not rendered again
+        checkpoint.pixels[0].mean = Vec3D::new(10.0, 0.0, 0.0);
+        checkpoint.save(&path).unwrap();
+        let pixels = render_linear_with_progress(&with_spp(20, true), &scene, recording_reporter());
+        assert_eq!(pixels[0], Vec3D::new(10.0, 0.0, 0.0));
+
+        // but those of another image or of more samples are ignored
+        Checkpoint::new(20, 20, 40).save(&path).unwrap();
+        let pixels = render_linear_with_progress(&with_spp(20, true), &scene, recording_reporter());
+        assert_eq!(pixels[0], expected[0]);
+        Checkpoint::new(10, 40, 20).save(&path).unwrap();
+        let pixels = render_linear_with_progress(&with_spp(20, true), &scene, recording_reporter());
+        assert_eq!(pixels[0], expected[0]);
+
+        // without `keep` a finished render removes it
+        let config = with_spp(20, true);
+        let config = RenderConfig {
+            checkpoint: Some(CheckpointConfig {
+                path: path.clone(),
+                interval_secs: 0,
+                keep: false,
+            }),
+            ..config
+        };
+        render_linear_with_progress(&config, &scene, recording_reporter());
+        assert!(!std::path::Path::new(&path).exists());
+    }
+
+    #[test]
+    fn test_crop() {
+        let base = TEST_RENDER_CONFIG
+            .replace("width = 20", "width = 64")
+            .replace("height = 20", "height = 64")
+            .replace("samples_per_pixel = 1", "samples_per_pixel = 16");
+        let full: RenderConfig = toml::from_str(&format!("global_seed = 5\n{}", base)).unwrap();
+        let cropped: RenderConfig = toml::from_str(&format!(
+            "global_seed = 5\n{}\n        [crop]\n        x_min = 42\n        y_min = 37\n        x_max = 43\n        y_max = 38",
+            base
+        ))
+        .unwrap();
+        let scene_config: SceneConfig =
+            toml::from_str(&TEST_SCENE_CONFIG.replace("radius = 1.0", "radius = 0.7")).unwrap();
+        let scene = Scene::from_config(&scene_config).unwrap();
+        let reporter = recording_reporter();
+
+        let expected = render_linear_with_progress(&full, &scene, reporter.clone());
+        let pixels = render_linear_with_progress(&cropped, &scene, reporter.clone());
+        let index = 37 * 64 + 42;
+        assert!(expected[index].x > 0.0 && expected[index].x < 1.0);
+        for (i, pixel) in pixels.iter().enumerate() {
+            if i == index {
+                assert_eq!(*pixel, expected[index]);
+            } else {
+                assert_eq!(*pixel, Vec3D::new(0.0, 0.0, 0.0));
+            }
+        }
+        let updates = reporter.updates.lock().unwrap();
+        assert_eq!(updates.last().map(|&(completed, _)| completed), Some(4096));
+    }
+
+    #[test]
+    fn test_variance_pass() {
+        let render_config: RenderConfig = toml::from_str(&format!(
+            "{}\n        [passes]\n        variance = true\n        sample_count = true",
+            TEST_RENDER_CONFIG.replace("samples_per_pixel = 1", "samples_per_pixel = 64")
+        ))
+        .unwrap();
+        let scene_config: SceneConfig = toml::from_str(TEST_SCENE_CONFIG).unwrap();
+        let scene = Scene::from_config(&scene_config).unwrap();
+        let reporter = recording_reporter();
+
+        // the emissive sphere looks the same to every sample, its outline
+        // is where they disagree
+        let samples = render_samples_with_progress(&render_config, &scene, reporter.clone());
+        assert!(max_component(samples[10 * 20 + 10].variance()) < tolerance(1e-12));
+        assert!(max_component(samples[5 * 20 + 5].variance()) > 0.1);
+
+        let result = render_with_progress(&render_config, &scene, reporter);
+        let variance = result.variance.unwrap();
+        assert_eq!(variance.get_pixel(10, 10)[0], 0);
+        assert!(variance.get_pixel(5, 5)[0] > 0);
+        let sample_count = result.sample_count.unwrap();
+        assert!(sample_count.pixels().all(|pixel| pixel[0] == 255));
+    }
+
+    #[test]
+    fn test_variance_adaptive_sampling() {
+        let render_config: RenderConfig = toml::from_str(&format!(
+            "{}\n        [adaptive]\n        warmup_spp = 8\n        variance_threshold = 1e-4\n        output_sample_map = true",
+            TEST_RENDER_CONFIG.replace("samples_per_pixel = 1", "samples_per_pixel = 64")
+        ))
+        .unwrap();
+        let scene_config: SceneConfig = toml::from_str(TEST_SCENE_CONFIG).unwrap();
+        let scene = Scene::from_config(&scene_config).unwrap();
+        let reporter = recording_reporter();
+
+        // the flat inside of the sphere converges once warmed up, its
+        // outline keeps sampling
+        let samples = render_samples_with_progress(&render_config, &scene, reporter.clone());
+        assert_eq!(samples[10 * 20 + 10].count, 8);
+        assert!(samples[5 * 20 + 5].count > 8);
+
+        let result = render_with_progress(&render_config, &scene, reporter);
+        let sample_count = result.sample_count.unwrap();
+        assert!(sample_count.get_pixel(10, 10)[0] < sample_count.get_pixel(5, 5)[0]);
+    }
+
+    #[test]
+    fn test_firefly_suppression() {
+        let dim = Vec3D::new(1e-3, 2e-3, 1e-3);
+        let mut clamped = PixelSamples::new();
+        let mut unclamped = PixelSamples::new();
+        for _ in 0..63 {
+            clamped.add(suppress_firefly(dim, 10.0));
+            unclamped.add(dim);
+        }
+        let firefly = Vec3D::new(1e6, 1e6, 1e6);
+        clamped.add(suppress_firefly(firefly, 10.0));
+        unclamped.add(firefly);
+
+        // the outlier blows the pixel out unless it is clamped
+        assert!(to_rgb(unclamped.mean()) == image::Rgb([255, 255, 255]));
+        assert!(luminance(clamped.mean()) < 0.2);
+        assert!((luminance(suppress_firefly(firefly, 10.0)) - 10.0).abs() < tolerance(1e-9));
+
+        // samples within the limit keep all their brightness
+        for sample in [dim, Vec3D::new(5.0, 0.5, 12.0), Vec3D::new(0.0, 0.0, 0.0)] {
+            assert_eq!(suppress_firefly(sample, 10.0), sample);
+        }
+    }
+
+    #[test]
+    fn test_progressive_output_path() {
+        assert_eq!(
+            progressive_output_path("output_{spp}spp.png", 16, 64),
+            "output_016spp.png"
+        );
+        assert_eq!(
+            progressive_output_path("frames/{spp}.png", 16, 1024),
+            "frames/0016.png"
+        );
+        assert_eq!(progressive_output_path("same.png", 16, 64), "same.png");
+    }
+
+    #[test]
+    fn test_progressive_render() {
+        let dir = std::env::temp_dir();
+        let template = dir.join("rust_ray_tracer_test_progressive_{spp}.png");
+        let render_config = |spp: usize, progressive: bool| -> RenderConfig {
+            let mut config = format!(
+                "global_seed = 9\n{}",
+                TEST_RENDER_CONFIG.replace(
+                    "samples_per_pixel = 1",
+                    &format!("samples_per_pixel = {}", spp)
+                )
+            );
+            if progressive {
+                config += &format!(
+                    "\n        [progressive]\n        interval_samples = 3\n        output_template = {:?}",
+                    template.to_str().unwrap()
+                );
+            }
+            toml::from_str(&config).unwrap()
+        };
+        let scene_config: SceneConfig = toml::from_str(TEST_SCENE_CONFIG).unwrap();
+        let scene = Scene::from_config(&scene_config).unwrap();
+
+        // rounds of 3, 6 and the remaining 2 samples
+        let progressive = render_config(8, true);
+        let samples = render_samples_with_progress(&progressive, &scene, recording_reporter());
+        let fresh =
+            render_samples_with_progress(&render_config(8, false), &scene, recording_reporter());
+        assert_eq!(samples, fresh);
+        for spp in [3, 6, 8] {
+            let path = dir.join(format!("rust_ray_tracer_test_progressive_{:03}.png", spp));
+            let saved = image::open(&path).unwrap().to_rgb8();
+            let fresh = render_linear_with_progress(
+                &render_config(spp, false),
+                &scene,
+                recording_reporter(),
+            );
+            assert_eq!(saved, to_ldr_image(&progressive, &fresh));
+            std::fs::remove_file(path).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_fisheye_corners_black() {
+        let render_config: RenderConfig = toml::from_str(&format!(
+            "{}\n        [passes]\n        albedo = true",
+            TEST_RENDER_CONFIG
+        ))
+        .unwrap();
+        let scene_config: SceneConfig = toml::from_str(
+            &TEST_SCENE_CONFIG
+                .replace("type = \"Perspective\"", "type = \"Fisheye\"")
+                .replace("vfov = 40.0\n        aspect = 1.0", "fov = 180.0")
+                .replace(
+                    "[[objects]]",
+                    "[background]\n        type = \"Constant\"\n        color = { x = 0.0, y = 0.5, z = 1.0 }\n\n        [[objects]]",
+                ),
+        )
+        .unwrap();
+        let scene = Scene::from_config(&scene_config).unwrap();
+        let result = render(&render_config, &scene);
+
+        // the background surrounds the sphere inside the image circle only
+        let black = image::Rgb([0, 0, 0]);
+        for image in [&result.image, result.albedo.as_ref().unwrap()] {
+            assert_eq!(image.get_pixel(0, 0), &black);
+            assert_eq!(image.get_pixel(19, 19), &black);
+            assert_ne!(image.get_pixel(10, 1), &black);
+        }
+    }
+
+    #[test]
+    fn test_render_layers() {
+        let render_config: RenderConfig = toml::from_str(&format!(
+            "{}\n        [passes]\n        albedo = true\n        depth = true\n        sample_count = true",
+            TEST_RENDER_CONFIG
+        ))
+        .unwrap();
+        assert!(render_config.has_passes());
+        let scene_config: SceneConfig = toml::from_str(&TEST_SCENE_CONFIG.replace(
+            "type = \"Emissive\"\n        color = { x = 1.0, y = 1.0, z = 1.0 }",
+            "type = \"Lambertian\"\n        albedo = { x = 0.8, y = 0.4, z = 0.2 }",
+        ))
+        .unwrap();
+        let scene = Scene::from_config(&scene_config).unwrap();
+        let layers = render_layers(&render_config, &scene);
+        let mut names: Vec<&str> = layers.keys().map(String::as_str).collect();
+        names.sort();
+        assert_eq!(names, ["albedo", "beauty", "depth", "sample_count"]);
+
+        // the passes stay linear, unlike their 8 bit images
+        let center = 10 * 20 + 10;
+        assert!(vec3_approx_eq(
+            layers["albedo"][center],
+            Vec3D::new(0.8, 0.4, 0.2),
+            tolerance(1e-9)
+        ));
+        assert!((layers["depth"][center].x - 4.0).abs() < 0.01);
+        assert_eq!(layers["depth"][0], Vec3D::from_value(0.0));
+        assert_eq!(layers["sample_count"][0], Vec3D::from_value(1.0));
+
+        let without: RenderConfig = toml::from_str(TEST_RENDER_CONFIG).unwrap();
+        assert!(!without.has_passes());
+    }
+
+    #[test]
+    fn test_crop_passes_and_photon_map() {
+        let crop = "\n        [crop]\n        x_min = 8\n        y_min = 8\n        x_max = 12\n        y_max = 12";
+        let render_config: RenderConfig = toml::from_str(&format!(
+            "{}\n        [passes]\n        albedo = true\n        depth = true{}",
+            TEST_RENDER_CONFIG, crop
+        ))
+        .unwrap();
+        // the background would show up in the albedo of every pixel around
+        // the sphere
+        let scene_config: SceneConfig = toml::from_str(&TEST_SCENE_CONFIG.replace(
+            "[[objects]]",
+            "[background]\n        type = \"Constant\"\n        color = { x = 0.0, y = 0.5, z = 1.0 }\n\n        [[objects]]",
+        ))
+        .unwrap();
+        let scene = Scene::from_config(&scene_config).unwrap();
+        let inside = |x: u32, y: u32| (8..12).contains(&x) && (8..12).contains(&y);
+
+        let result = render_with_progress(&render_config, &scene, recording_reporter());
+        let albedo = result.albedo.unwrap();
+        let depth = result.depth.unwrap();
+        for (x, y, pixel) in albedo.enumerate_pixels() {
+            if !inside(x, y) {
+                assert_eq!(pixel, &image::Rgb([0, 0, 0]));
+                assert_eq!(depth.get_pixel(x, y)[0], 0);
+            }
+        }
+        assert!(depth.get_pixel(10, 10)[0] > 0);
+
+        let scene = Scene::from_config(&toml::from_str(TEST_SCENE_CONFIG).unwrap()).unwrap();
+
+        let ppm: RenderConfig = toml::from_str(&format!(
+            "{}{}",
+            TEST_RENDER_CONFIG.replace(
+                "type = \"mcpt\"\n        min_depth = 2\n        max_depth = 4",
+                "type = \"ppm\"\n        photons_per_pass = 100\n        num_passes = 2"
+            ),
+            crop
+        ))
+        .unwrap();
+        let pixels = render_linear_with_progress(&ppm, &scene, recording_reporter());
+        for (i, pixel) in pixels.iter().enumerate() {
+            assert_eq!(pixel.x > 0.0, inside(i as u32 % 20, i as u32 / 20));
+        }
+    }
+}
@@ -0,0 +1,145 @@
+use super::super::math::{max_component, ScalarF};
+use super::super::sampler::SamplerConfig;
+use super::{PixelSamples, RenderConfig};
+use serde::Deserialize;
+
+// stops sampling a pixel once the variance of its mean drops to
+// `variance_threshold`, after at least `warmup_spp` samples. works with any
+// sampler, whose samples per pixel stay the upper limit
+#[derive(Deserialize)]
+pub struct AdaptiveSamplingConfig {
+    pub warmup_spp: usize,
+    pub variance_threshold: f64, // of the noisiest channel of the pixel mean
+    #[serde(default)]
+    pub output_sample_map: bool, // saves the samples taken per pixel, like `passes.sample_count`
+}
+
+// what ends the sampling of a pixel before its sampler runs out, set by
+// `[adaptive]` or by an Adaptive sampler. both judge the running variance
+// the pixel's samples keep
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StoppingRule {
+    // the noisiest channel of the mean varies by at most `threshold`
+    Variance {
+        warmup_spp: usize,
+        threshold: ScalarF,
+    },
+    // the 95% confidence interval of the noisiest channel is narrower than
+    // `threshold` times the brightest one
+    ConfidenceInterval {
+        min_samples: usize,
+        threshold: ScalarF,
+    },
+}
+
+impl StoppingRule {
+    // `[adaptive]` takes precedence over an Adaptive sampler's threshold
+    pub fn from_config(config: &RenderConfig) -> Option<Self> {
+        if let Some(adaptive) = &config.adaptive {
+            return Some(StoppingRule::Variance {
+                warmup_spp: adaptive.warmup_spp,
+                threshold: adaptive.variance_threshold as ScalarF,
+            });
+        }
+        match &config.sampler {
+            SamplerConfig::Adaptive(sampler) => Some(StoppingRule::ConfidenceInterval {
+                min_samples: sampler.min_samples,
+                threshold: sampler.threshold as ScalarF,
+            }),
+            _ => None,
+        }
+    }
+
+    pub fn converged(&self, samples: &PixelSamples) -> bool {
+        match *self {
+            StoppingRule::Variance {
+                warmup_spp,
+                threshold,
+            } => {
+                samples.count >= warmup_spp.max(2)
+                    && max_component(samples.mean_variance()) <= threshold
+            }
+            StoppingRule::ConfidenceInterval {
+                min_samples,
+                threshold,
+            } => {
+                if samples.count < min_samples.max(2) {
+                    return false;
+                }
+                let half_width = 1.96 * max_component(samples.mean_variance()).sqrt();
+                let brightest = max_component(samples.mean.map(ScalarF::abs));
+                // black pixels only converge once they stop varying
+                half_width <= threshold * brightest.max(1e-6)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+#[cfg(not(feature = "f32_math"))] // the 1e6 offset leaves f32 no digits to spare
+mod tests {
+    use super::*;
+    use crate::math::{tolerance, vec3_approx_eq, Vec3D};
+    use cgmath::{Array, ElementWise};
+
+    #[test]
+    fn test_pixel_samples_variance() {
+        let samples: Vec<Vec3D> = (0..100)
+            .map(|i| {
+                let i = i as ScalarF;
+                Vec3D::new(1e6 + (i * 0.37).sin(), i, (i * 1.3).cos().powi(2))
+            })
+            .collect();
+        let n = samples.len() as ScalarF;
+        let mean = samples.iter().sum::<Vec3D>() / n;
+        let variance = samples
+            .iter()
+            .map(|s| (s - mean).mul_element_wise(s - mean))
+            .sum::<Vec3D>()
+            / (n - 1.0);
+
+        let mut pixel = PixelSamples::new();
+        for &sample in &samples {
+            pixel.add(sample);
+        }
+        assert_eq!(pixel.count, 100);
+        assert!(vec3_approx_eq(pixel.mean(), mean, tolerance(1e-9)));
+        // the large offset of the first channel doesn't cost precision
+        assert!(vec3_approx_eq(
+            pixel.variance(),
+            variance * (n - 1.0) / n,
+            tolerance(1e-9)
+        ));
+        assert!(vec3_approx_eq(
+            pixel.mean_variance(),
+            variance / n,
+            tolerance(1e-9)
+        ));
+
+        let rule = StoppingRule::Variance {
+            warmup_spp: 200,
+            threshold: 1.0,
+        };
+        assert!(!rule.converged(&pixel));
+        let rule = StoppingRule::Variance {
+            warmup_spp: 50,
+            threshold: 10.0,
+        };
+        assert!(rule.converged(&pixel));
+
+        // flickering between black and white stays far from the relative
+        // width, a constant colour reaches it as soon as it may
+        let rule = StoppingRule::ConfidenceInterval {
+            min_samples: 8,
+            threshold: 0.05,
+        };
+        let mut flicker = PixelSamples::new();
+        let mut constant = PixelSamples::new();
+        for i in 0..8 {
+            flicker.add(Vec3D::from_value((i % 2) as ScalarF));
+            constant.add(Vec3D::new(0.2, 0.0, 0.7));
+            assert_eq!(rule.converged(&constant), i == 7);
+        }
+        assert!(!rule.converged(&flicker));
+    }
+}
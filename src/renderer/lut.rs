@@ -0,0 +1,231 @@
+use super::super::math::{ScalarF, Vec3D};
+use serde::{Deserialize, Deserializer};
+
+// per channel curve sampled at `size` evenly spaced inputs over the domain
+#[derive(Debug, Clone)]
+pub struct LutToneMapper {
+    pub lut: Vec<Vec3D>,
+    pub size: usize,
+    domain: (Vec3D, Vec3D),
+}
+
+// color cube with `size` samples along each axis, red changing fastest as
+// the .cube format stores it
+#[derive(Debug, Clone)]
+pub struct Lut3dToneMapper {
+    pub lut: Vec<[f32; 3]>,
+    pub size: usize,
+    domain: (Vec3D, Vec3D),
+}
+
+#[derive(Debug, Clone)]
+pub enum CubeLut {
+    OneD(LutToneMapper),
+    ThreeD(Lut3dToneMapper),
+}
+
+// position of `color` in the domain scaled to the lut's sample indices,
+// clamped to its bounds
+fn lut_coordinates(color: Vec3D, domain: &(Vec3D, Vec3D), size: usize) -> Vec3D {
+    let (min, max) = domain;
+    let scale = (size - 1) as ScalarF;
+    Vec3D::new(
+        ((color.x - min.x) / (max.x - min.x)).clamp(0.0, 1.0) * scale,
+        ((color.y - min.y) / (max.y - min.y)).clamp(0.0, 1.0) * scale,
+        ((color.z - min.z) / (max.z - min.z)).clamp(0.0, 1.0) * scale,
+    )
+}
+
+// lower sample index and the weight of the upper one
+fn split(position: ScalarF, size: usize) -> (usize, ScalarF) {
+    let i = (position.floor() as usize).min(size - 2);
+    (i, position - i as ScalarF)
+}
+
+impl LutToneMapper {
+    pub fn apply(&self, color: Vec3D) -> Vec3D {
+        let position = lut_coordinates(color, &self.domain, self.size);
+        let channel = |axis: usize| {
+            let (i, f) = split(position[axis], self.size);
+            self.lut[i][axis] * (1.0 - f) + self.lut[i + 1][axis] * f
+        };
+        Vec3D::new(channel(0), channel(1), channel(2))
+    }
+}
+
+impl Lut3dToneMapper {
+    fn at(&self, r: usize, g: usize, b: usize) -> Vec3D {
+        let [x, y, z] = self.lut[(b * self.size + g) * self.size + r];
+        Vec3D::new(x as ScalarF, y as ScalarF, z as ScalarF)
+    }
+
+    // trilinear between the eight samples around the color
+    pub fn apply(&self, color: Vec3D) -> Vec3D {
+        let position = lut_coordinates(color, &self.domain, self.size);
+        let (r, fr) = split(position.x, self.size);
+        let (g, fg) = split(position.y, self.size);
+        let (b, fb) = split(position.z, self.size);
+        let lerp = |a: Vec3D, b: Vec3D, t: ScalarF| a * (1.0 - t) + b * t;
+        let along_r = |g: usize, b: usize| lerp(self.at(r, g, b), self.at(r + 1, g, b), fr);
+        let along_g = |b: usize| lerp(along_r(g, b), along_r(g + 1, b), fg);
+        lerp(along_g(b), along_g(b + 1), fb)
+    }
+}
+
+impl CubeLut {
+    // the .cube text format, with `LUT_1D_SIZE` or `LUT_3D_SIZE` and an
+    // optional `DOMAIN_MIN` and `DOMAIN_MAX` ahead of the samples
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let triple = |words: &[&str]| -> Result<Vec3D, String> {
+            let values = words
+                .iter()
+                .map(|w| w.parse::<f64>().map(|v| v as ScalarF))
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|e| e.to_string())?;
+            match values[..] {
+                [x, y, z] => Ok(Vec3D::new(x, y, z)),
+                _ => Err(format!("expected 3 values, got {}", values.len())),
+            }
+        };
+        let (mut size_1d, mut size_3d) = (None, None);
+        let mut domain = (Vec3D::new(0.0, 0.0, 0.0), Vec3D::new(1.0, 1.0, 1.0));
+        let mut samples = Vec::new();
+        for (number, line) in text.lines().enumerate() {
+            let words: Vec<&str> = line.split_whitespace().collect();
+            let error = |e: String| format!("line {}: {}", number + 1, e);
+            let size = || words.get(1).and_then(|w| w.parse::<usize>().ok());
+            match words.first() {
+                None => {}
+                Some(word) if word.starts_with('#') => {}
+                Some(&"TITLE") => {}
+                Some(&"LUT_1D_SIZE") => {
+                    size_1d = Some(size().ok_or_else(|| error("bad size".into()))?)
+                }
+                Some(&"LUT_3D_SIZE") => {
+                    size_3d = Some(size().ok_or_else(|| error("bad size".into()))?)
+                }
+                Some(&"DOMAIN_MIN") => domain.0 = triple(&words[1..]).map_err(error)?,
+                Some(&"DOMAIN_MAX") => domain.1 = triple(&words[1..]).map_err(error)?,
+                Some(_) => samples.push(triple(&words).map_err(error)?),
+            }
+        }
+
+        let (size, expected) = match (size_1d, size_3d) {
+            (Some(size), None) => (size, size),
+            (None, Some(size)) => (size, size * size * size),
+            _ => return Err("expected one of LUT_1D_SIZE and LUT_3D_SIZE".into()),
+        };
+        if size < 2 {
+            return Err(format!("size {} is below 2", size));
+        }
+        if samples.len() != expected {
+            return Err(format!(
+                "expected {} samples, got {}",
+                expected,
+                samples.len()
+            ));
+        }
+        Ok(if size_1d.is_some() {
+            CubeLut::OneD(LutToneMapper {
+                lut: samples,
+                size,
+                domain,
+            })
+        } else {
+            #[allow(clippy::unnecessary_cast)] // a no-op unless built with f32_math
+            let lut = samples
+                .iter()
+                .map(|s| [s.x as f32, s.y as f32, s.z as f32])
+                .collect();
+            CubeLut::ThreeD(Lut3dToneMapper { lut, size, domain })
+        })
+    }
+
+    pub fn load(path: &str) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+        Self::parse(&text).map_err(|e| format!("{}: {}", path, e))
+    }
+}
+
+// `lut_file` is read along with the config, so a broken lut is reported
+// before rendering
+pub fn deserialize_lut_file<'de, D>(deserializer: D) -> Result<Option<CubeLut>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let path = Option::<String>::deserialize(deserializer)?;
+    path.map(|path| CubeLut::load(&path))
+        .transpose()
+        .map_err(serde::de::Error::custom)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::{tolerance, vec3_approx_eq};
+
+    #[test]
+    fn test_identity_3d_lut() {
+        let size = 33;
+        let step = |i: usize| i as f64 / (size - 1) as f64;
+        let mut text = format!(
+            "TITLE \"identity\"\n# red changes fastest\nLUT_3D_SIZE {}\n",
+            size
+        );
+        for b in 0..size {
+            for g in 0..size {
+                for r in 0..size {
+                    text += &format!("{} {} {}\n", step(r), step(g), step(b));
+                }
+            }
+        }
+        let lut = match CubeLut::parse(&text).unwrap() {
+            CubeLut::ThreeD(lut) => lut,
+            CubeLut::OneD(_) => panic!("expected a 3d lut"),
+        };
+        let tolerance = 1.0 / (2.0 * size as ScalarF);
+        for color in [
+            Vec3D::new(0.0, 0.0, 0.0),
+            Vec3D::new(1.0, 1.0, 1.0),
+            Vec3D::new(0.18, 0.5, 0.9),
+            Vec3D::new(0.73, 0.02, 0.41),
+        ] {
+            assert!(vec3_approx_eq(lut.apply(color), color, tolerance));
+        }
+        // outside the domain the edge of the cube is used
+        assert!(vec3_approx_eq(
+            lut.apply(Vec3D::new(2.0, -1.0, 0.5)),
+            Vec3D::new(1.0, 0.0, 0.5),
+            tolerance
+        ));
+
+        assert!(CubeLut::parse("LUT_3D_SIZE 2\n0 0 0\n").is_err());
+        assert!(CubeLut::parse("0 0 0\n1 1 1\n").is_err());
+    }
+
+    #[test]
+    fn test_contrast_1d_lut() {
+        // an s-curve through mid grey
+        let text = "LUT_1D_SIZE 5\n0 0 0\n0.1 0.1 0.1\n0.5 0.5 0.5\n0.9 0.9 0.9\n1 1 1\n";
+        let lut = match CubeLut::parse(text).unwrap() {
+            CubeLut::OneD(lut) => lut,
+            CubeLut::ThreeD(_) => panic!("expected a 1d lut"),
+        };
+        let shadow = lut.apply(Vec3D::new(0.2, 0.2, 0.2));
+        assert!((shadow.x - 0.08).abs() < tolerance(1e-9));
+        let highlight = lut.apply(Vec3D::new(0.8, 0.8, 0.8));
+        assert!(highlight.x > 0.8);
+        assert!(vec3_approx_eq(
+            lut.apply(Vec3D::new(0.5, 0.0, 1.0)),
+            Vec3D::new(0.5, 0.0, 1.0),
+            tolerance(1e-9)
+        ));
+
+        // a doubled domain halves the inputs before the lookup
+        let scaled = CubeLut::parse(&format!("DOMAIN_MAX 2 2 2\n{}", text)).unwrap();
+        let CubeLut::OneD(scaled) = scaled else {
+            unreachable!()
+        };
+        assert!((scaled.apply(Vec3D::new(0.4, 0.4, 0.4)).x - 0.08).abs() < tolerance(1e-9));
+    }
+}
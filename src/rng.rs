@@ -0,0 +1,62 @@
+use super::math::ScalarF;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::cell::RefCell;
+
+thread_local! {
+    static RNG: RefCell<StdRng> = RefCell::new(StdRng::from_entropy());
+}
+
+// reseeds the calling thread's rng, a seed of 0 draws a fresh one from entropy
+pub fn init_thread_local_rng(seed: u64) {
+    let rng = if seed == 0 {
+        StdRng::from_entropy()
+    } else {
+        StdRng::seed_from_u64(seed)
+    };
+    RNG.with(|cell| *cell.borrow_mut() = rng);
+}
+
+pub fn with_rng<T>(f: impl FnOnce(&mut StdRng) -> T) -> T {
+    RNG.with(|cell| f(&mut cell.borrow_mut()))
+}
+
+// independent generator for long lived consumers (e.g. samplers), forked
+// from the thread-local one so it follows its seed
+pub fn fork_rng() -> StdRng {
+    with_rng(|rng| StdRng::seed_from_u64(rng.gen()))
+}
+
+// uniform in [0, 1), drawn as f64 in either precision so a seed gives the
+// same numbers with f32_math. the min keeps f32 rounding below 1
+pub fn uniform(rng: &mut StdRng) -> ScalarF {
+    (rng.gen::<f64>() as ScalarF).min(1.0 - ScalarF::EPSILON / 2.0)
+}
+
+// derives the seed of one stream (thread, tile, ...) from a global seed,
+// never returns 0 so the result can't fall back to entropy
+pub fn stream_seed(global_seed: u64, stream: u64) -> u64 {
+    // splitmix64 finalizer
+    let mut z = global_seed ^ stream.wrapping_add(1).wrapping_mul(0x9e3779b97f4a7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^= z >> 31;
+    z.max(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_thread_local_rng_seed() {
+        init_thread_local_rng(42);
+        let a: Vec<u64> = (0..8).map(|_| with_rng(|rng| rng.gen())).collect();
+        init_thread_local_rng(42);
+        let b: Vec<u64> = (0..8).map(|_| with_rng(|rng| rng.gen())).collect();
+        assert_eq!(a, b);
+
+        assert_ne!(stream_seed(42, 0), stream_seed(42, 1));
+        assert_ne!(stream_seed(42, 0), stream_seed(43, 0));
+    }
+}
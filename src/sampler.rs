@@ -1,18 +1,39 @@
-use super::math::Point2U;
+use super::math::{Point2U, ScalarF};
+use super::rng::{fork_rng, stream_seed, uniform};
+use super::sobol_matrices::sobol_matrices;
 use rand::prelude::SliceRandom;
-use rand::Rng;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
 use serde::Deserialize;
 
-pub trait Sampler {
+pub trait Sampler: Send {
     fn start_pixel(&mut self, p: Point2U);
-    fn get_1d(&mut self) -> f64;
-    fn get_2d(&mut self) -> (f64, f64);
+    // picks pixel p up at sample `index`, to add to samples taken earlier.
+    // the samples after it are the ones the pixel takes without stopping, the
+    // dimensions past the stratified sampler's tables aside
+    fn start_pixel_sample(&mut self, p: Point2U, index: usize) {
+        self.start_pixel(p);
+        for _ in 0..index {
+            self.start_next_sample();
+        }
+    }
+    fn get_1d(&mut self) -> ScalarF;
+    fn get_2d(&mut self) -> (ScalarF, ScalarF);
     fn start_next_sample(&mut self) -> bool;
+    #[allow(dead_code)]
     fn samples_per_pixel(&self) -> usize;
 }
 
+// generator of the random dimensions of one sample of pixel p. it is drawn
+// afresh per sample, so what a sample gets doesn't depend on the ones before
+fn sample_rng(seed: u64, p: Point2U, index: usize) -> StdRng {
+    StdRng::seed_from_u64(stream_seed(seed, pixel_hash(p.x, p.y, index)))
+}
+
 pub struct RandomSampler {
-    rng: rand::rngs::ThreadRng,
+    seed: u64,
+    rng: StdRng,
+    pixel: Point2U,
     samples_per_pixel: usize,
     current_sample: usize,
 }
@@ -24,8 +45,11 @@ pub struct RandomSamplerConfig {
 
 impl RandomSampler {
     pub fn new(samples_per_pixel: usize) -> Self {
+        let mut rng = fork_rng();
         Self {
-            rng: rand::thread_rng(),
+            seed: rng.gen(),
+            rng,
+            pixel: Point2U::new(0, 0),
             samples_per_pixel,
             current_sample: 0,
         }
@@ -33,21 +57,24 @@ impl RandomSampler {
 }
 
 impl Sampler for RandomSampler {
-    fn start_pixel(&mut self, _: Point2U) {
+    fn start_pixel(&mut self, p: Point2U) {
+        self.pixel = p;
         self.current_sample = 0;
+        self.rng = sample_rng(self.seed, p, 0);
     }
 
-    fn get_1d(&mut self) -> f64 {
-        self.rng.gen()
+    fn get_1d(&mut self) -> ScalarF {
+        uniform(&mut self.rng)
     }
 
-    fn get_2d(&mut self) -> (f64, f64) {
-        (self.rng.gen(), self.rng.gen())
+    fn get_2d(&mut self) -> (ScalarF, ScalarF) {
+        (uniform(&mut self.rng), uniform(&mut self.rng))
     }
 
     fn start_next_sample(&mut self) -> bool {
-        if self.current_sample < self.samples_per_pixel {
+        if self.current_sample < self.samples_per_pixel - 1 {
             self.current_sample += 1;
+            self.rng = sample_rng(self.seed, self.pixel, self.current_sample);
             true
         } else {
             false
@@ -63,11 +90,11 @@ pub struct StratifiedSampler {
     samples_per_pixel: usize,
     x_strata: usize,
     y_strata: usize,
-    samples_1d: Vec<Vec<f64>>,
-    samples_2d: Vec<Vec<(f64, f64)>>,
+    samples_1d: Vec<Vec<ScalarF>>,
+    samples_2d: Vec<Vec<(ScalarF, ScalarF)>>,
     current_sample_index: usize,
     current_dimension: usize,
-    rng: rand::rngs::ThreadRng,
+    rng: StdRng,
 }
 
 #[derive(Deserialize)]
@@ -98,14 +125,15 @@ impl StratifiedSampler {
             samples_2d: vec![vec![(0.0, 0.0); samples_per_pixel]; dimensions],
             current_sample_index: 0,
             current_dimension: 0,
-            rng: rand::thread_rng(),
+            rng: fork_rng(),
         }
     }
 
     fn generate_samples(&mut self) {
         for dim_samples in self.samples_1d.iter_mut() {
             for i in 0..self.samples_per_pixel {
-                dim_samples[i] = (i as f64 + self.rng.gen::<f64>()) / self.samples_per_pixel as f64;
+                dim_samples[i] =
+                    (i as ScalarF + uniform(&mut self.rng)) / self.samples_per_pixel as ScalarF;
             }
             dim_samples.shuffle(&mut self.rng);
         }
@@ -115,8 +143,8 @@ impl StratifiedSampler {
                 let x_cell = i % self.x_strata;
                 let y_cell = i / self.x_strata;
 
-                let x = (x_cell as f64 + self.rng.gen::<f64>()) / self.x_strata as f64;
-                let y = (y_cell as f64 + self.rng.gen::<f64>()) / self.y_strata as f64;
+                let x = (x_cell as ScalarF + uniform(&mut self.rng)) / self.x_strata as ScalarF;
+                let y = (y_cell as ScalarF + uniform(&mut self.rng)) / self.y_strata as ScalarF;
 
                 dim_samples[i] = (x, y);
             }
@@ -132,18 +160,18 @@ impl Sampler for StratifiedSampler {
         self.generate_samples();
     }
 
-    fn get_1d(&mut self) -> f64 {
+    fn get_1d(&mut self) -> ScalarF {
         if self.current_dimension >= self.samples_1d.len() {
-            return self.rng.gen();
+            return uniform(&mut self.rng);
         }
         let sample = self.samples_1d[self.current_dimension][self.current_sample_index];
         self.current_dimension += 1;
         sample
     }
 
-    fn get_2d(&mut self) -> (f64, f64) {
+    fn get_2d(&mut self) -> (ScalarF, ScalarF) {
         if self.current_dimension >= self.samples_2d.len() {
-            return (self.rng.gen(), self.rng.gen());
+            return (uniform(&mut self.rng), uniform(&mut self.rng));
         }
         let sample = self.samples_2d[self.current_dimension][self.current_sample_index];
         self.current_dimension += 1;
@@ -165,11 +193,330 @@ impl Sampler for StratifiedSampler {
     }
 }
 
+const HALTON_PRIMES: [u32; 32] = [
+    2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47, 53, 59, 61, 67, 71, 73, 79, 83, 89, 97,
+    101, 103, 107, 109, 113, 127, 131,
+];
+
+const ONE_MINUS_EPSILON: ScalarF = 1.0 - ScalarF::EPSILON / 2.0;
+
+fn mix_bits(mut v: u64) -> u64 {
+    v ^= v >> 31;
+    v = v.wrapping_mul(0x7fb5d329728ea185);
+    v ^= v >> 27;
+    v = v.wrapping_mul(0x81dadef4bc2dd44d);
+    v ^= v >> 33;
+    v
+}
+
+pub fn pixel_hash(x: u32, y: u32, dimension: usize) -> u64 {
+    mix_bits(x as u64 ^ mix_bits(y as u64 ^ mix_bits(dimension as u64)))
+}
+
+enum DigitScramble {
+    // random xor of the reversed bits, used for base 2
+    Xor(u32),
+    // one random permutation of the digits per digit position, used for odd bases
+    Permutation {
+        base: u32,
+        digits: Vec<Vec<u32>>,
+        tail: ScalarF,
+    },
+}
+
+impl DigitScramble {
+    fn new(base: u32, seed: u64, sample_count: usize) -> Self {
+        if base == 2 {
+            return DigitScramble::Xor(seed as u32);
+        }
+
+        let mut state = seed;
+        let mut next_random = || {
+            state = mix_bits(state.wrapping_add(0x9e3779b97f4a7c15));
+            state
+        };
+
+        // enough digits to reach 32 bits of precision, but only the digits
+        // that sample indices can reach need a full permutation; the others
+        // always permute a zero digit and are folded into a constant tail
+        let digit_count = (32.0 / (base as ScalarF).log2()).ceil() as usize;
+        let mut index_digits = 0;
+        let mut reachable = 1;
+        while reachable < sample_count {
+            reachable *= base as usize;
+            index_digits += 1;
+        }
+        let index_digits = index_digits.min(digit_count);
+
+        let digits = (0..index_digits)
+            .map(|_| {
+                // Fisher-Yates shuffle
+                let mut permutation: Vec<u32> = (0..base).collect();
+                for i in (1..base as usize).rev() {
+                    permutation.swap(i, (next_random() % (i as u64 + 1)) as usize);
+                }
+                permutation
+            })
+            .collect();
+
+        let inv_base = 1.0 / base as ScalarF;
+        let mut inv_base_n = 1.0;
+        let mut tail = 0.0;
+        for _ in index_digits..digit_count {
+            inv_base_n *= inv_base;
+            tail += (next_random() % base as u64) as ScalarF * inv_base_n;
+        }
+
+        DigitScramble::Permutation { base, digits, tail }
+    }
+
+    fn radical_inverse(&self, index: u64) -> ScalarF {
+        match self {
+            DigitScramble::Xor(scramble) => {
+                let bits = (index as u32).reverse_bits() ^ scramble;
+                (bits as ScalarF / (1u64 << 32) as ScalarF).min(ONE_MINUS_EPSILON)
+            }
+            DigitScramble::Permutation { base, digits, tail } => {
+                let base = *base as u64;
+                let inv_base = 1.0 / base as ScalarF;
+                let mut inv_base_n = 1.0;
+                let mut index = index;
+                let mut result = 0.0;
+                for permutation in digits {
+                    let digit = index % base;
+                    index /= base;
+                    inv_base_n *= inv_base;
+                    result += permutation[digit as usize] as ScalarF * inv_base_n;
+                }
+                (result + tail * inv_base_n).min(ONE_MINUS_EPSILON)
+            }
+        }
+    }
+}
+
+pub struct HaltonSampler {
+    samples_per_pixel: usize,
+    current_sample_index: usize,
+    current_dimension: usize,
+    scrambles: Vec<DigitScramble>,
+    rotations: Vec<ScalarF>, // Cranley-Patterson offsets of the pixel, one per dimension
+    pixel: Point2U,
+    rng_seed: u64,
+    rng: StdRng, // for the dimensions past the primes
+}
+
+#[derive(Deserialize)]
+pub struct HaltonSamplerConfig {
+    pub samples_per_pixel: usize,
+}
+
+impl HaltonSampler {
+    pub fn new(samples_per_pixel: usize) -> Self {
+        let mut rng = fork_rng();
+        Self {
+            samples_per_pixel,
+            current_sample_index: 0,
+            current_dimension: 0,
+            scrambles: Vec::new(),
+            rotations: Vec::new(),
+            pixel: Point2U::new(0, 0),
+            rng_seed: rng.gen(),
+            rng,
+        }
+    }
+
+    fn sample_dimension(&mut self) -> ScalarF {
+        if self.current_dimension >= self.scrambles.len() {
+            return uniform(&mut self.rng);
+        }
+        let sample = self.scrambles[self.current_dimension]
+            .radical_inverse(self.current_sample_index as u64)
+            + self.rotations[self.current_dimension];
+        self.current_dimension += 1;
+        // toroidal shift, the points keep their spacing
+        if sample >= 1.0 {
+            (sample - 1.0).min(ONE_MINUS_EPSILON)
+        } else {
+            sample
+        }
+    }
+}
+
+impl Sampler for HaltonSampler {
+    fn start_pixel(&mut self, p: Point2U) {
+        self.pixel = p;
+        self.current_sample_index = 0;
+        self.current_dimension = 0;
+        self.rng = sample_rng(self.rng_seed, p, 0);
+        // the scrambles only depend on the pixel, so they are computed once per pixel
+        self.scrambles = HALTON_PRIMES
+            .iter()
+            .enumerate()
+            .map(|(dimension, &base)| {
+                DigitScramble::new(
+                    base,
+                    pixel_hash(p.x, p.y, dimension),
+                    self.samples_per_pixel,
+                )
+            })
+            .collect();
+        // hashed apart from the scrambles' seeds
+        self.rotations = (0..HALTON_PRIMES.len())
+            .map(|dimension| {
+                let hash = pixel_hash(p.x, p.y, dimension + HALTON_PRIMES.len());
+                (hash >> 11) as ScalarF / (1u64 << 53) as ScalarF
+            })
+            .collect();
+    }
+
+    fn get_1d(&mut self) -> ScalarF {
+        self.sample_dimension()
+    }
+
+    fn get_2d(&mut self) -> (ScalarF, ScalarF) {
+        (self.sample_dimension(), self.sample_dimension())
+    }
+
+    fn start_next_sample(&mut self) -> bool {
+        if self.current_sample_index < self.samples_per_pixel - 1 {
+            self.current_sample_index += 1;
+            self.rng = sample_rng(self.rng_seed, self.pixel, self.current_sample_index);
+            self.current_dimension = 0;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn samples_per_pixel(&self) -> usize {
+        self.samples_per_pixel
+    }
+}
+
+// nested uniform scrambling: every bit is flipped depending on a hash of the
+// bits above it, which randomly permutes each level of the binary tree of
+// intervals while keeping the net properties of the sequence
+fn owen_scramble(v: u32, seed: u64) -> u32 {
+    let mut v = v;
+    if seed & 1 == 1 {
+        v ^= 1 << 31;
+    }
+    for b in 1..32 {
+        let mask = !0u32 << (32 - b);
+        if mix_bits((v & mask) as u64 ^ seed) & (1 << b) != 0 {
+            v ^= 1 << (31 - b);
+        }
+    }
+    v
+}
+
+pub struct SobolSampler {
+    samples_per_pixel: usize,
+    seed: u64,
+    matrices: &'static [[u32; 32]],
+    pixel: Point2U,
+    current_sample_index: usize,
+    current_dimension: usize,
+    rng_seed: u64,
+    rng: StdRng, // for the dimensions past the table
+}
+
+#[derive(Deserialize)]
+pub struct SobolSamplerConfig {
+    pub samples_per_pixel: usize,
+    pub seed: Option<u64>,
+}
+
+impl SobolSampler {
+    pub fn new(samples_per_pixel: usize, seed: u64) -> Self {
+        let mut rng = fork_rng();
+        Self {
+            samples_per_pixel,
+            seed,
+            matrices: sobol_matrices(),
+            pixel: Point2U::new(0, 0),
+            current_sample_index: 0,
+            current_dimension: 0,
+            rng_seed: rng.gen(),
+            rng,
+        }
+    }
+
+    fn sample_dimension(&mut self) -> ScalarF {
+        if self.current_dimension >= self.matrices.len() {
+            return uniform(&mut self.rng);
+        }
+        let mut index = self.current_sample_index as u32;
+        let mut v = 0;
+        for column in &self.matrices[self.current_dimension] {
+            if index == 0 {
+                break;
+            }
+            if index & 1 == 1 {
+                v ^= column;
+            }
+            index >>= 1;
+        }
+        // the scramble of each pixel and dimension, hashed on the fly as most
+        // paths use only a few of the dimensions
+        let scramble_seed =
+            mix_bits(pixel_hash(self.pixel.x, self.pixel.y, self.current_dimension) ^ self.seed);
+        let v = owen_scramble(v, scramble_seed);
+        self.current_dimension += 1;
+        (v as ScalarF / (1u64 << 32) as ScalarF).min(ONE_MINUS_EPSILON)
+    }
+}
+
+impl Sampler for SobolSampler {
+    fn start_pixel(&mut self, p: Point2U) {
+        self.current_sample_index = 0;
+        self.current_dimension = 0;
+        self.pixel = p;
+        self.rng = sample_rng(self.rng_seed, p, 0);
+    }
+
+    fn get_1d(&mut self) -> ScalarF {
+        self.sample_dimension()
+    }
+
+    fn get_2d(&mut self) -> (ScalarF, ScalarF) {
+        (self.sample_dimension(), self.sample_dimension())
+    }
+
+    fn start_next_sample(&mut self) -> bool {
+        if self.current_sample_index < self.samples_per_pixel - 1 {
+            self.current_sample_index += 1;
+            self.rng = sample_rng(self.rng_seed, self.pixel, self.current_sample_index);
+            self.current_dimension = 0;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn samples_per_pixel(&self) -> usize {
+        self.samples_per_pixel
+    }
+}
+
+// random samples, of which a pixel takes at least `min_samples` and stops
+// once the 95% confidence interval of its mean is narrow enough, see
+// `StoppingRule` in the renderer
+#[derive(Deserialize)]
+pub struct AdaptiveSamplerConfig {
+    pub min_samples: usize,
+    pub max_samples: usize,
+    pub threshold: f64, // relative half-width of the confidence interval
+}
+
 #[derive(Deserialize)]
 #[serde(tag = "type")]
 pub enum SamplerConfig {
     Random(RandomSamplerConfig),
     Stratified(StratifiedSamplerConfig),
+    Halton(HaltonSamplerConfig),
+    Sobol(SobolSamplerConfig),
+    Adaptive(AdaptiveSamplerConfig),
 }
 
 impl SamplerConfig {
@@ -182,6 +529,301 @@ impl SamplerConfig {
                 config.y_strata,
                 4,
             )),
+            SamplerConfig::Halton(config) => Box::new(HaltonSampler::new(config.samples_per_pixel)),
+            SamplerConfig::Sobol(config) => Box::new(SobolSampler::new(
+                config.samples_per_pixel,
+                config.seed.unwrap_or(0),
+            )),
+            SamplerConfig::Adaptive(config) => {
+                assert!(
+                    0 < config.min_samples && config.min_samples <= config.max_samples,
+                    "0 < min_samples <= max_samples"
+                );
+                Box::new(RandomSampler::new(config.max_samples))
+            }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::tolerance;
+    use crate::sobol_matrices::SOBOL_DIMENSIONS;
+
+    fn radical_inverse(base: u32, mut index: u64) -> ScalarF {
+        let base = base as u64;
+        let inv_base = 1.0 / base as ScalarF;
+        let mut inv_base_n = 1.0;
+        let mut reversed_digits = 0;
+        while index > 0 {
+            let next = index / base;
+            let digit = index - next * base;
+            reversed_digits = reversed_digits * base + digit;
+            inv_base_n *= inv_base;
+            index = next;
+        }
+        (reversed_digits as ScalarF * inv_base_n).min(ONE_MINUS_EPSILON)
+    }
+
+    fn correlation(a: &[ScalarF], b: &[ScalarF]) -> ScalarF {
+        let n = a.len() as ScalarF;
+        let mean_a = a.iter().sum::<ScalarF>() / n;
+        let mean_b = b.iter().sum::<ScalarF>() / n;
+        let mut cov = 0.0;
+        let mut var_a = 0.0;
+        let mut var_b = 0.0;
+        for (x, y) in a.iter().zip(b.iter()) {
+            cov += (x - mean_a) * (y - mean_b);
+            var_a += (x - mean_a) * (x - mean_a);
+            var_b += (y - mean_b) * (y - mean_b);
+        }
+        cov / (var_a * var_b).sqrt()
+    }
+
+    fn halton_samples(p: Point2U, dimension: usize, count: usize) -> Vec<ScalarF> {
+        let mut sampler = HaltonSampler::new(count);
+        sampler.start_pixel(p);
+        let mut samples = Vec::new();
+        loop {
+            for _ in 0..dimension {
+                sampler.get_1d();
+            }
+            samples.push(sampler.get_1d());
+            if !sampler.start_next_sample() {
+                break;
+            }
+        }
+        samples
+    }
+
+    #[test]
+    fn test_halton_scrambling_decorrelates_pixels() {
+        let count = 64;
+        let mut total_correlation = 0.0;
+        let mut pairs = 0;
+        for (dimension, &base) in HALTON_PRIMES.iter().enumerate().take(4) {
+            let unscrambled: Vec<ScalarF> = (0..count as u64)
+                .map(|i| radical_inverse(base, i))
+                .collect();
+            // without scrambling every pixel gets exactly the same sequence
+            assert!((correlation(&unscrambled, &unscrambled) - 1.0).abs() < tolerance(1e-9));
+
+            for x in 0..8 {
+                let a = halton_samples(Point2U::new(x, 0), dimension, count);
+                let b = halton_samples(Point2U::new(x + 1, 0), dimension, count);
+                assert_ne!(a, b);
+                total_correlation += correlation(&a, &b);
+                pairs += 1;
+            }
+        }
+        assert!((total_correlation / pairs as ScalarF).abs() < 0.2);
+    }
+
+    #[test]
+    fn test_halton_scrambling_no_clusters() {
+        let size = 64;
+        let offsets: Vec<Vec<(ScalarF, ScalarF)>> = (0..size)
+            .map(|y| {
+                (0..size)
+                    .map(|x| {
+                        let mut sampler = HaltonSampler::new(1);
+                        sampler.start_pixel(Point2U::new(x as u32, y as u32));
+                        sampler.get_2d()
+                    })
+                    .collect()
+            })
+            .collect();
+
+        // a 3x3 neighborhood whose samples all fall into the same quadrant of
+        // their pixels is a cluster (unscrambled Halton always produces one)
+        let quadrant = |(u, v): (ScalarF, ScalarF)| (u >= 0.5, v >= 0.5);
+        for y in 1..size - 1 {
+            for x in 1..size - 1 {
+                let first = quadrant(offsets[y - 1][x - 1]);
+                let clustered = (y - 1..=y + 1)
+                    .all(|j| (x - 1..=x + 1).all(|i| quadrant(offsets[j][i]) == first));
+                assert!(!clustered, "clustered samples around pixel ({}, {})", x, y);
+            }
+        }
+    }
+
+    #[test]
+    fn test_halton_rotation_keeps_stratification() {
+        // the 64 scrambled base 2 points of a pixel are evenly spaced, the
+        // rotation only moves them around the unit circle
+        let mut first_points = Vec::new();
+        for x in 0..4 {
+            let mut points = halton_samples(Point2U::new(x, 5), 0, 64);
+            first_points.push(points[0]);
+            points.sort_by(ScalarF::total_cmp);
+            for k in 0..64 {
+                let gap = (points[(k + 1) % 64] - points[k]).rem_euclid(1.0);
+                assert!((gap - 1.0 / 64.0).abs() < tolerance(1e-9), "gap {}", gap);
+            }
+            assert!(points.iter().all(|&u| (0.0..1.0).contains(&u)));
+        }
+        first_points.dedup();
+        assert_eq!(first_points.len(), 4);
+    }
+
+    // largest difference between the fraction of points inside a box
+    // anchored at the origin and the area of that box, over a grid of corners
+    fn star_discrepancy(points: &[(ScalarF, ScalarF)]) -> ScalarF {
+        let grid = 64;
+        let mut discrepancy: ScalarF = 0.0;
+        for i in 1..=grid {
+            for j in 1..=grid {
+                let (a, b) = (
+                    i as ScalarF / grid as ScalarF,
+                    j as ScalarF / grid as ScalarF,
+                );
+                let inside = points.iter().filter(|&&(u, v)| u < a && v < b).count();
+                discrepancy =
+                    discrepancy.max((inside as ScalarF / points.len() as ScalarF - a * b).abs());
+            }
+        }
+        discrepancy
+    }
+
+    fn collect_2d(sampler: &mut dyn Sampler) -> Vec<(ScalarF, ScalarF)> {
+        sampler.start_pixel(Point2U::new(3, 7));
+        let mut points = Vec::new();
+        loop {
+            points.push(sampler.get_2d());
+            if !sampler.start_next_sample() {
+                break;
+            }
+        }
+        points
+    }
+
+    #[test]
+    fn test_halton_discrepancy() {
+        crate::rng::init_thread_local_rng(7);
+        let halton = collect_2d(&mut HaltonSampler::new(64));
+        let mut random = collect_2d(&mut RandomSampler::new(64));
+        random.truncate(64);
+        assert_eq!(halton.len(), 64);
+        assert!(
+            star_discrepancy(&halton) < star_discrepancy(&random),
+            "halton: {}, random: {}",
+            star_discrepancy(&halton),
+            star_discrepancy(&random)
+        );
+    }
+
+    // chi-squared statistic of the points binned into a `bins` x `bins` grid
+    fn chi_squared(points: &[(ScalarF, ScalarF)], bins: usize) -> ScalarF {
+        let mut counts = vec![0usize; bins * bins];
+        for &(u, v) in points {
+            counts[(v * bins as ScalarF) as usize * bins + (u * bins as ScalarF) as usize] += 1;
+        }
+        let expected = points.len() as ScalarF / (bins * bins) as ScalarF;
+        counts
+            .iter()
+            .map(|&count| (count as ScalarF - expected).powi(2) / expected)
+            .sum()
+    }
+
+    #[test]
+    fn test_sobol_uniformity() {
+        crate::rng::init_thread_local_rng(7);
+        let sobol = collect_2d(&mut SobolSampler::new(256, 0));
+        let mut random = collect_2d(&mut RandomSampler::new(256));
+        random.truncate(256);
+        assert_eq!(sobol.len(), 256);
+
+        // the first two dimensions form a (0, 8, 2)-net, which scrambling
+        // keeps: one point in every cell of a 16 x 16 grid
+        assert_eq!(chi_squared(&sobol, 16), 0.0);
+        assert!(chi_squared(&sobol, 8) < chi_squared(&random, 8));
+
+        // other pixels get other scrambles of the same net
+        let mut sampler = SobolSampler::new(256, 0);
+        sampler.start_pixel(Point2U::new(4, 7));
+        let other = sampler.get_2d();
+        assert_ne!(other, sobol[0]);
+        assert_eq!(
+            chi_squared(&collect_2d(&mut SobolSampler::new(256, 1)), 16),
+            0.0
+        );
+
+        // every dimension on its own has one point per 1 / 256 interval, up
+        // to the last one of the table
+        assert_eq!(SOBOL_DIMENSIONS, 1024);
+        let mut sampler = SobolSampler::new(256, 0);
+        sampler.start_pixel(Point2U::new(0, 0));
+        let mut counts = vec![vec![0; 256]; SOBOL_DIMENSIONS];
+        let mut last_pair = Vec::new();
+        loop {
+            let values: Vec<ScalarF> = (0..SOBOL_DIMENSIONS).map(|_| sampler.get_1d()).collect();
+            for (dimension_counts, value) in counts.iter_mut().zip(&values) {
+                dimension_counts[(value * 256.0) as usize] += 1;
+            }
+            last_pair.push((values[SOBOL_DIMENSIONS - 2], values[SOBOL_DIMENSIONS - 1]));
+            if !sampler.start_next_sample() {
+                break;
+            }
+        }
+        assert!(counts.iter().flatten().all(|&count| count == 1));
+        // and the last two dimensions still stratify an 8 x 8 grid together
+        assert_eq!(chi_squared(&last_pair, 8), 0.0);
+    }
+
+    // rms error of estimating the integral of a gaussian bump over the unit
+    // square with `n` samples, over the first 16 pixels
+    fn gaussian_rms_error(mut sampler: impl FnMut(usize) -> Box<dyn Sampler>, n: usize) -> ScalarF {
+        let f = |x: ScalarF| (-8.0 * (x - 0.5) * (x - 0.5)).exp();
+        let reference = (0..100_000)
+            .map(|i| f((i as ScalarF + 0.5) / 100_000.0))
+            .sum::<ScalarF>()
+            / 100_000.0;
+        let reference = reference * reference;
+        let squared_errors: ScalarF = (0..16)
+            .map(|pixel| {
+                let mut sampler = sampler(n);
+                sampler.start_pixel(Point2U::new(pixel % 4, pixel / 4));
+                let mut sum = 0.0;
+                for _ in 0..n {
+                    let (u, v) = sampler.get_2d();
+                    sum += f(u) * f(v);
+                    sampler.start_next_sample();
+                }
+                (sum / n as ScalarF - reference).powi(2)
+            })
+            .sum();
+        (squared_errors / 16.0).sqrt()
+    }
+
+    #[test]
+    fn test_sobol_converges_faster_than_random() {
+        crate::rng::init_thread_local_rng(7);
+        let sobol = |n| Box::new(SobolSampler::new(n, 0)) as Box<dyn Sampler>;
+        let random = |n| Box::new(RandomSampler::new(n)) as Box<dyn Sampler>;
+        let (sobol_64, sobol_1024) = (
+            gaussian_rms_error(sobol, 64),
+            gaussian_rms_error(sobol, 1024),
+        );
+        let (random_64, random_1024) = (
+            gaussian_rms_error(random, 64),
+            gaussian_rms_error(random, 1024),
+        );
+
+        // monte carlo error falls as 1 / sqrt(n), 4x over 16x the samples
+        assert!(
+            sobol_1024 < random_1024 / 4.0,
+            "{} {}",
+            sobol_1024,
+            random_1024
+        );
+        assert!(
+            sobol_64 / sobol_1024 > 2.0 * random_64 / random_1024,
+            "sobol: {} -> {}, random: {} -> {}",
+            sobol_64,
+            sobol_1024,
+            random_64,
+            random_1024
+        );
+    }
+}
@@ -1,48 +1,729 @@
+use super::accel::{Accel, AccelStructure, BvhConfig};
 use super::camera::{Camera, CameraConfig};
 use super::common::HitRecord;
-use super::math::Ray;
+use super::environment::{Environment, HdrEnvironmentConfig};
+use super::error::RaytracerError;
+use super::light::{build_cdf, luminance, sample_cdf, Light, LightConfig};
+use super::material::{Material, MaterialConfig};
+use super::math::consts::PI;
+use super::math::{
+    unwrap_matrix4d_config_to_matrix4d, Aabb, Matrix4D, Ray, ScalarF, TransformConfig, Vec3D,
+    Vec3DConfig,
+};
 use super::object::{Object, ObjectConfig};
+use super::shapes::{Shape, ShapeConfig};
+use super::volume::{HomogeneousVolume, HomogeneousVolumeConfig};
+use cgmath::{InnerSpace, SquareMatrix, VectorSpace, Zero};
+use log::warn;
 use serde::Deserialize;
 use std::sync::Arc;
 
+#[derive(Debug, Clone)]
+pub struct GradientBackground {
+    pub top: Vec3D,
+    pub bottom: Vec3D,
+}
+
+// radiance of rays leaving the scene. environments importance sampled as
+// lights also give next event estimation directions
+#[derive(Debug, Clone)]
+pub enum Background {
+    Constant(Vec3D),
+    Gradient(GradientBackground),
+    Environment(Arc<dyn Environment>),
+}
+
+impl Background {
+    pub fn sample(&self, direction: Vec3D) -> Vec3D {
+        match self {
+            Background::Constant(color) => *color,
+            Background::Gradient(gradient) => {
+                let w = ((direction.y + 1.0) * 0.5).clamp(0.0, 1.0);
+                gradient.bottom.lerp(gradient.top, w)
+            }
+            Background::Environment(environment) => environment.sample_direction(direction),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct ConstantBackgroundConfig {
+    pub color: Vec3DConfig,
+}
+
+#[derive(Deserialize)]
+pub struct GradientBackgroundConfig {
+    pub top: Vec3DConfig,
+    pub bottom: Vec3DConfig,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type")]
+pub enum BackgroundConfig {
+    Constant(ConstantBackgroundConfig),
+    Gradient(GradientBackgroundConfig),
+    Environment(HdrEnvironmentConfig), // seen by escaping rays, not sampled as a light
+}
+
+impl BackgroundConfig {
+    pub fn to_background(&self) -> Result<Background, RaytracerError> {
+        Ok(match self {
+            BackgroundConfig::Constant(config) => Background::Constant(config.color.to_vec3()),
+            BackgroundConfig::Gradient(config) => Background::Gradient(GradientBackground {
+                top: config.top.to_vec3(),
+                bottom: config.bottom.to_vec3(),
+            }),
+            BackgroundConfig::Environment(config) => Background::Environment(Arc::new(
+                config.to_environment().map_err(RaytracerError::SceneLoad)?,
+            )),
+        })
+    }
+}
+
+// node of a hierarchical scene description, its transform is relative to
+// the parent and nodes without a material inherit the closest ancestor's
+pub struct SceneNode {
+    pub name: String,
+    pub transform: Matrix4D,
+    pub children: Vec<SceneNode>,
+    pub shape: Option<Arc<dyn Shape>>,
+    pub material: Option<Arc<dyn Material>>,
+}
+
+#[derive(Deserialize)]
+pub struct SceneNodeConfig {
+    name: String,
+    transform: Option<TransformConfig>,
+    #[serde(default)]
+    children: Vec<SceneNodeConfig>,
+    shape: Option<ShapeConfig>,
+    material: Option<MaterialConfig>,
+}
+
+impl SceneNodeConfig {
+    pub fn to_node(&self) -> Result<SceneNode, RaytracerError> {
+        Ok(SceneNode {
+            name: self.name.clone(),
+            transform: unwrap_matrix4d_config_to_matrix4d(self.transform.as_ref()),
+            children: self
+                .children
+                .iter()
+                .map(|child| child.to_node())
+                .collect::<Result<_, _>>()?,
+            shape: self
+                .shape
+                .as_ref()
+                .map(|shape| shape.to_shape())
+                .transpose()?,
+            material: self
+                .material
+                .as_ref()
+                .map(|material| material.to_material())
+                .transpose()?,
+        })
+    }
+}
+
 pub struct Scene {
     pub camera: Arc<dyn Camera>,
     pub objects: Vec<Object>,
+    pub background: Background,
+    pub lights: Vec<usize>, // emissive objects whose surface can be sampled
+    pub analytic_lights: Vec<Arc<dyn Light>>,
+    pub medium: Option<Arc<HomogeneousVolume>>, // fills the space between surfaces, path tracers only
+
+    // lights are picked proportional to their power, area lights first and
+    // analytic lights after them. None when nothing emits
+    light_cdf: Option<Vec<ScalarF>>,
+
+    // objects with finite bounds are looked up through the acceleration
+    // structure, the others (e.g. planes) are tested one by one
+    accel: Accel,
+    bounded_objects: Vec<usize>,
+    unbounded_objects: Vec<usize>,
+
+    // number of `intersect` calls, lets tests check how far rays travel
+    #[cfg(test)]
+    pub intersect_count: std::sync::atomic::AtomicUsize,
 }
 
 #[derive(Deserialize)]
 pub struct SceneConfig {
     camera: CameraConfig,
+    #[serde(default)]
     objects: Vec<ObjectConfig>,
+    graph: Option<SceneNodeConfig>,
+    background: Option<BackgroundConfig>,
+    #[serde(default)]
+    lights: Vec<LightConfig>,
+    bvh: Option<BvhConfig>,
+    accel: Option<AccelStructure>, // takes precedence over `bvh`
+    medium: Option<HomogeneousVolumeConfig>,
+}
+
+// assembles a scene in code, the way `Scene::from_config` does from toml.
+// objects get their ids in the order they were added
+pub struct SceneBuilder {
+    camera: Option<Arc<dyn Camera>>,
+    objects: Vec<Object>,
+    background: Background,
+    analytic_lights: Vec<Arc<dyn Light>>,
+    medium: Option<Arc<HomogeneousVolume>>,
+    accel: AccelStructure,
+}
+
+impl SceneBuilder {
+    pub fn new() -> Self {
+        Self {
+            camera: None,
+            objects: Vec::new(),
+            background: Background::Constant(Vec3D::zero()),
+            analytic_lights: Vec::new(),
+            medium: None,
+            accel: AccelStructure::default(),
+        }
+    }
+
+    pub fn camera(mut self, c: Arc<dyn Camera>) -> Self {
+        self.camera = Some(c);
+        self
+    }
+
+    pub fn object(mut self, shape: Arc<dyn Shape>, material: Arc<dyn Material>) -> Self {
+        self.objects.push(Object {
+            shape,
+            material,
+            id: 0,
+        });
+        self
+    }
+
+    pub fn background(mut self, color: Vec3D) -> Self {
+        self.background = Background::Constant(color);
+        self
+    }
+
+    pub fn environment(mut self, environment: Arc<dyn Environment>) -> Self {
+        self.background = Background::Environment(environment);
+        self
+    }
+
+    // any kind of background, e.g. a gradient
+    pub fn background_kind(mut self, background: Background) -> Self {
+        self.background = background;
+        self
+    }
+
+    pub fn analytic_light(mut self, light: Arc<dyn Light>) -> Self {
+        self.analytic_lights.push(light);
+        self
+    }
+
+    pub fn medium(mut self, medium: HomogeneousVolume) -> Self {
+        self.medium = Some(Arc::new(medium));
+        self
+    }
+
+    pub fn bvh(mut self, bvh: BvhConfig) -> Self {
+        self.accel = AccelStructure::Bvh(bvh);
+        self
+    }
+
+    pub fn accel(mut self, accel: AccelStructure) -> Self {
+        self.accel = accel;
+        self
+    }
+
+    pub fn build(self) -> Scene {
+        let mut objects = self.objects;
+        for (id, object) in objects.iter_mut().enumerate() {
+            object.id = id;
+        }
+
+        let lights: Vec<usize> = objects
+            .iter()
+            .enumerate()
+            .filter(|(_, object)| {
+                object.material.emission().magnitude() > 1e-6 && object.shape.area().is_finite()
+            })
+            .map(|(i, _)| i)
+            .collect();
+
+        let mut bounds = Vec::new();
+        let mut bounded_objects = Vec::new();
+        let mut unbounded_objects = Vec::new();
+        for (i, object) in objects.iter().enumerate() {
+            let aabb = object.shape.bounding_box();
+            if aabb.is_finite() {
+                bounds.push(aabb);
+                bounded_objects.push(i);
+            } else {
+                unbounded_objects.push(i);
+            }
+        }
+
+        // flux of a lambertian emitter is pi * radiance * area
+        let light_powers: Vec<ScalarF> = lights
+            .iter()
+            .map(|&i| {
+                let object = &objects[i];
+                PI * luminance(object.material.emission()) * object.shape.area()
+            })
+            .chain(
+                self.analytic_lights
+                    .iter()
+                    .map(|light| luminance(light.power())),
+            )
+            .collect();
+
+        Scene {
+            camera: self.camera.expect("Scene has no camera"),
+            objects,
+            background: self.background,
+            lights,
+            analytic_lights: self.analytic_lights,
+            medium: self.medium,
+            light_cdf: build_cdf(&light_powers),
+            accel: Accel::new(&bounds, &self.accel),
+            bounded_objects,
+            unbounded_objects,
+            #[cfg(test)]
+            intersect_count: std::sync::atomic::AtomicUsize::new(0),
+        }
+    }
+}
+
+impl Default for SceneBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Scene {
-    pub fn from_config(config: &SceneConfig) -> Scene {
-        let camera = config.camera.to_camera();
+    pub fn from_config(config: &SceneConfig) -> Result<Scene, RaytracerError> {
+        let mut builder = SceneBuilder::new().camera(config.camera.to_camera());
+
+        let mut objects: Vec<Object> = Vec::new();
+        for object_config in &config.objects {
+            objects.extend(object_config.to_objects()?);
+        }
+        if let Some(graph_config) = &config.graph {
+            objects.extend(Scene::flatten(&graph_config.to_node()?)?);
+        }
+        // distant lights are sized after the bounded part of the scene
+        let world_bounds = objects
+            .iter()
+            .map(|object| object.shape.bounding_box())
+            .filter(|aabb| aabb.is_finite())
+            .fold(Aabb::empty(), |world, aabb| world.union(&aabb));
+        for object in objects {
+            builder = builder.object(object.shape, object.material);
+        }
+
+        let mut has_environment = false;
+        if let Some(background) = &config.background {
+            builder = match background.to_background()? {
+                Background::Constant(color) => builder.background(color),
+                Background::Environment(environment) => {
+                    has_environment = true;
+                    builder.environment(environment)
+                }
+                background => builder.background_kind(background),
+            };
+        }
+        // environment lights take the place of the background
+        for light_config in &config.lights {
+            if let Some(light) = light_config.to_environment()? {
+                if has_environment {
+                    warn!("Multiple environments, only the last one is used");
+                }
+                has_environment = true;
+                builder = builder.environment(light);
+            }
+        }
+
+        for light_config in &config.lights {
+            if let Some(light) = light_config.to_light(&world_bounds) {
+                builder = builder.analytic_light(light);
+            }
+        }
+
+        if let Some(medium) = &config.medium {
+            builder = builder.medium(medium.to_volume());
+        }
+        builder = match config.accel {
+            Some(accel) => builder.accel(accel),
+            None => builder.bvh(config.bvh.unwrap_or_default()),
+        };
+        Ok(builder.build())
+    }
 
+    pub fn flatten(node: &SceneNode) -> Result<Vec<Object>, RaytracerError> {
         let mut objects = Vec::new();
+        Scene::flatten_node(node, &Matrix4D::identity(), None, &mut objects)?;
+        Ok(objects)
+    }
 
-        for object_config in &config.objects {
-            objects.push(object_config.to_object());
+    fn flatten_node(
+        node: &SceneNode,
+        parent_transform: &Matrix4D,
+        parent_material: Option<&Arc<dyn Material>>,
+        objects: &mut Vec<Object>,
+    ) -> Result<(), RaytracerError> {
+        let transform = parent_transform * node.transform;
+        let material = node.material.as_ref().or(parent_material);
+
+        if let Some(shape) = &node.shape {
+            let material = material.ok_or_else(|| {
+                RaytracerError::SceneLoad(format!(
+                    "scene node {} has a shape but no material",
+                    node.name
+                ))
+            })?;
+            objects.push(Object {
+                shape: shape.transform(&transform),
+                material: material.clone(),
+                id: 0,
+            });
         }
 
-        Scene {
-            camera: camera,
-            objects: objects,
+        for child in &node.children {
+            Scene::flatten_node(child, &transform, material, objects)?;
+        }
+        Ok(())
+    }
+
+    // picks one of `lights` followed by `analytic_lights` by power, returns
+    // its index in that order and the probability of choosing it
+    pub fn sample_light_index(&self, u: ScalarF) -> Option<(usize, ScalarF)> {
+        let cdf = self.light_cdf.as_ref()?;
+        let index = sample_cdf(cdf, u);
+        Some((index, self.light_selection_pdf(index)))
+    }
+
+    pub fn light_selection_pdf(&self, index: usize) -> ScalarF {
+        match &self.light_cdf {
+            Some(cdf) if index == 0 => cdf[0],
+            Some(cdf) => cdf[index] - cdf[index - 1],
+            None => 0.0,
+        }
+    }
+
+    // radiance seen by rays leaving the scene in `direction`
+    pub fn background_radiance(&self, direction: Vec3D) -> Vec3D {
+        self.background.sample(direction)
+    }
+
+    pub fn environment(&self) -> Option<&Arc<dyn Environment>> {
+        match &self.background {
+            Background::Environment(environment) => Some(environment),
+            _ => None,
         }
     }
 
+    // rays leaving a surface start off it already, see `spawn_ray`
     pub fn intersect(&self, ray: &Ray) -> Option<HitRecord> {
+        #[cfg(test)]
+        self.intersect_count
+            .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        self.intersect_bounded(ray, 0.0, ScalarF::MAX)
+    }
+
+    pub fn intersect_bounded(
+        &self,
+        ray: &Ray,
+        t_min: ScalarF,
+        t_max: ScalarF,
+    ) -> Option<HitRecord<'_>> {
         let mut hit_record: Option<HitRecord> = None;
-        let mut closest_so_far = f64::MAX;
+        let mut closest_so_far = t_max;
 
-        for object in &self.objects {
-            if let Some(temp_rec) = object.intersect(&ray, 0.001, closest_so_far) {
+        for &i in &self.unbounded_objects {
+            if let Some(temp_rec) = self.objects[i].intersect(ray, t_min, closest_so_far) {
                 closest_so_far = temp_rec.t;
                 hit_record = Some(temp_rec);
             }
         }
 
+        if let Some(temp_rec) =
+            self.accel
+                .intersect(ray, t_min, closest_so_far, |i, t_min, t_max| {
+                    self.objects[self.bounded_objects[i]].intersect(ray, t_min, t_max)
+                })
+        {
+            hit_record = Some(temp_rec);
+        }
+
         hit_record
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::camera::PerspectiveCamera;
+    use crate::material::Lambertian;
+    use crate::math::{tolerance, vec3_approx_eq, Point3D};
+    use crate::shapes::Sphere;
+    use crate::texture::ConstantTexture;
+    use cgmath::{Deg, EuclideanSpace, Transform};
+
+    #[test]
+    fn test_gradient_background() {
+        let background = Background::Gradient(GradientBackground {
+            top: Vec3D::new(0.5, 0.7, 1.0),
+            bottom: Vec3D::new(1.0, 1.0, 1.0),
+        });
+        assert!(vec3_approx_eq(
+            background.sample(Vec3D::new(0.0, -1.0, 0.0)),
+            Vec3D::new(1.0, 1.0, 1.0),
+            tolerance(1e-6)
+        ));
+        assert!(vec3_approx_eq(
+            background.sample(Vec3D::new(0.0, 1.0, 0.0)),
+            Vec3D::new(0.5, 0.7, 1.0),
+            tolerance(1e-6)
+        ));
+        assert!(vec3_approx_eq(
+            background.sample(Vec3D::new(1.0, 0.0, 0.0)),
+            Vec3D::new(0.75, 0.85, 1.0),
+            tolerance(1e-6)
+        ));
+    }
+
+    #[test]
+    fn test_background_config() {
+        let config: BackgroundConfig = toml::from_str(
+            r#"
+            type = "Gradient"
+            top = { x = 0.0, y = 0.0, z = 1.0 }
+            bottom = { x = 1.0, y = 0.0, z = 0.0 }
+            "#,
+        )
+        .unwrap();
+        let background = config.to_background().unwrap();
+        assert!(vec3_approx_eq(
+            background.sample(Vec3D::new(0.0, 1.0, 0.0)),
+            Vec3D::new(0.0, 0.0, 1.0),
+            tolerance(1e-6)
+        ));
+
+        let scene = SceneBuilder::new()
+            .camera(Arc::new(PerspectiveCamera::new(
+                Point3D::new(0.0, 0.0, 5.0),
+                Point3D::origin(),
+                Vec3D::unit_y(),
+                60.0,
+                1.0,
+            )))
+            .background_kind(background)
+            .build();
+        assert!(vec3_approx_eq(
+            scene.background_radiance(Vec3D::new(0.0, -1.0, 0.0)),
+            Vec3D::new(1.0, 0.0, 0.0),
+            tolerance(1e-6)
+        ));
+    }
+
+    #[test]
+    fn test_scene_graph_flatten() {
+        let material: Arc<dyn Material> = Arc::new(Lambertian {
+            albedo: Arc::new(ConstantTexture {
+                color: Vec3D::new(0.5, 0.5, 0.5),
+            }),
+        });
+        let unit_sphere = || -> Arc<dyn Shape> {
+            Arc::new(Sphere {
+                center: Point3D::origin(),
+                radius: 1.0,
+            })
+        };
+
+        let root_transform = Matrix4D::from_translation(Vec3D::new(10.0, 0.0, 0.0))
+            * Matrix4D::from_angle_y(Deg(90.0));
+        let child_transform = Matrix4D::from_translation(Vec3D::new(0.0, 0.0, 3.0));
+        let root = SceneNode {
+            name: "root".to_string(),
+            transform: root_transform,
+            children: vec![SceneNode {
+                name: "child".to_string(),
+                transform: child_transform,
+                children: Vec::new(),
+                shape: Some(unit_sphere()),
+                material: None,
+            }],
+            shape: Some(unit_sphere()),
+            material: Some(material),
+        };
+
+        let objects = Scene::flatten(&root).unwrap();
+        assert_eq!(objects.len(), 2);
+
+        let center = |object: &Object| {
+            let aabb = object.shape.bounding_box();
+            Point3D::from_vec((aabb.min.to_vec() + aabb.max.to_vec()) / 2.0)
+        };
+        assert!(vec3_approx_eq(
+            center(&objects[0]).to_vec(),
+            Vec3D::new(10.0, 0.0, 0.0),
+            tolerance(1e-9)
+        ));
+        // rotating (0, 0, 3) by 90 degrees around y gives (3, 0, 0)
+        let expected = (root_transform * child_transform).transform_point(Point3D::origin());
+        assert!(vec3_approx_eq(
+            expected.to_vec(),
+            Vec3D::new(13.0, 0.0, 0.0),
+            tolerance(1e-9)
+        ));
+        assert!(vec3_approx_eq(
+            center(&objects[1]).to_vec(),
+            expected.to_vec(),
+            tolerance(1e-9)
+        ));
+    }
+
+    #[test]
+    fn test_scene_builder() {
+        let material: Arc<dyn Material> = Arc::new(Lambertian {
+            albedo: Arc::new(ConstantTexture {
+                color: Vec3D::new(0.5, 0.5, 0.5),
+            }),
+        });
+        let scene = SceneBuilder::new()
+            .camera(Arc::new(PerspectiveCamera::new(
+                Point3D::new(0.0, 0.0, 5.0),
+                Point3D::origin(),
+                Vec3D::unit_y(),
+                60.0,
+                1.0,
+            )))
+            .object(
+                Arc::new(Sphere {
+                    center: Point3D::new(-1.0, 0.0, 0.0),
+                    radius: 0.5,
+                }),
+                material.clone(),
+            )
+            .object(
+                Arc::new(Sphere {
+                    center: Point3D::new(1.0, 0.0, 0.0),
+                    radius: 0.5,
+                }),
+                material,
+            )
+            .background(Vec3D::new(0.1, 0.2, 0.3))
+            .build();
+
+        assert_eq!(scene.objects.len(), 2);
+        assert_eq!(scene.objects[1].id, 1);
+        assert!(scene.lights.is_empty());
+        let ray = Ray {
+            origin: Point3D::new(1.0, 0.0, 5.0),
+            direction: Vec3D::new(0.0, 0.0, -1.0),
+        };
+        assert_eq!(scene.intersect(&ray).unwrap().object_id, 1);
+        assert!(vec3_approx_eq(
+            scene.background_radiance(Vec3D::unit_x()),
+            Vec3D::new(0.1, 0.2, 0.3),
+            tolerance(1e-12)
+        ));
+    }
+
+    #[test]
+    #[cfg(not(feature = "f32_math"))] // the distances don't fit in f32
+    fn test_intersect_beyond_f32_range() {
+        let material: Arc<dyn Material> = Arc::new(Lambertian {
+            albedo: Arc::new(ConstantTexture {
+                color: Vec3D::new(0.5, 0.5, 0.5),
+            }),
+        });
+        // nearest point at t = 1e39, past f32::MAX
+        let scene = SceneBuilder::new()
+            .camera(Arc::new(PerspectiveCamera::new(
+                Point3D::origin(),
+                Point3D::new(0.0, 0.0, -1.0),
+                Vec3D::unit_y(),
+                60.0,
+                1.0,
+            )))
+            .object(
+                Arc::new(Sphere {
+                    center: Point3D::new(0.0, 0.0, -1.1e39),
+                    radius: 1e38,
+                }),
+                material,
+            )
+            .build();
+        let ray = Ray {
+            origin: Point3D::origin(),
+            direction: Vec3D::new(0.0, 0.0, -1.0),
+        };
+        let hit = scene.intersect(&ray).unwrap();
+        assert!((hit.t / 1e39 - 1.0).abs() < tolerance(1e-6));
+        assert!(scene.intersect_bounded(&ray, 0.0, 1e38).is_none());
+    }
+
+    #[test]
+    fn test_environment_background_config() {
+        let path = std::env::temp_dir().join("rust_ray_tracer_test_environment_background.png");
+        image::RgbImage::from_pixel(4, 2, image::Rgb([255, 0, 0]))
+            .save(&path)
+            .unwrap();
+        let config: BackgroundConfig = toml::from_str(&format!(
+            "type = \"Environment\"\nfile = {:?}",
+            path.to_str().unwrap()
+        ))
+        .unwrap();
+        let background = config.to_background().unwrap();
+        assert!(matches!(background, Background::Environment(_)));
+        assert!(vec3_approx_eq(
+            background.sample(Vec3D::new(0.3, 0.5, -0.2)),
+            Vec3D::new(1.0, 0.0, 0.0),
+            tolerance(1e-6)
+        ));
+
+        let missing: BackgroundConfig =
+            toml::from_str("type = \"Environment\"\nfile = \"missing.hdr\"").unwrap();
+        assert!(matches!(
+            missing.to_background(),
+            Err(RaytracerError::SceneLoad(_))
+        ));
+    }
+
+    #[test]
+    fn test_obj_object_materials() {
+        let directory = std::env::temp_dir().join("rust_ray_tracer_test_obj_object_materials");
+        std::fs::create_dir_all(&directory).unwrap();
+        std::fs::write(directory.join("quad.mtl"), "newmtl light\nKe 4 4 4\n").unwrap();
+        let path = directory.join("quad.obj");
+        std::fs::write(
+            &path,
+            "mtllib quad.mtl\nv 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\n\
+             f 1 2 3\nusemtl light\nf 1 3 4\n",
+        )
+        .unwrap();
+        let shape = format!(
+            "[shape]\ntype = \"Mesh\"\nfile = {:?}\n",
+            path.to_str().unwrap()
+        );
+
+        // the configured material fills in for the faces without one
+        let config: ObjectConfig = toml::from_str(&format!(
+            "{}[material]\ntype = \"Lambertian\"\nalbedo = {{ x = 0.5, y = 0.5, z = 0.5 }}\n",
+            shape
+        ))
+        .unwrap();
+        let objects = config.to_objects().unwrap();
+        assert_eq!(objects.len(), 2);
+        assert_eq!(objects[0].material.emission(), Vec3D::new(0.0, 0.0, 0.0));
+        assert_eq!(objects[1].material.emission(), Vec3D::new(4.0, 4.0, 4.0));
+
+        let config: ObjectConfig = toml::from_str(&shape).unwrap();
+        assert!(matches!(
+            config.to_objects(),
+            Err(RaytracerError::SceneLoad(_))
+        ));
+
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+}
@@ -0,0 +1,271 @@
+use super::super::common::HitRecord;
+use super::super::math::{
+    axis_normal, transform_point3, unwrap_matrix4d_config_to_matrix4d, Aabb, Matrix4D, Point3D,
+    Point3DConfig, Ray, ScalarF, TransformConfig,
+};
+use super::super::sampler::Sampler;
+use super::instance::InstancedShape;
+use super::shape::{Shape, ShapeSample};
+use serde::Deserialize;
+use std::sync::Arc;
+
+// solid axis aligned box between `min` and `max`
+#[derive(Debug, Clone)]
+pub struct AabbShape {
+    pub min: Point3D,
+    pub max: Point3D,
+}
+
+#[derive(Deserialize)]
+pub struct AabbShapeConfig {
+    pub min: Point3DConfig,
+    pub max: Point3DConfig,
+    pub transform: Option<TransformConfig>,
+}
+
+impl AabbShape {
+    // areas of the faces orthogonal to x, y and z
+    fn face_areas(&self) -> [ScalarF; 3] {
+        let d = self.max - self.min;
+        [d.y * d.z, d.x * d.z, d.x * d.y]
+    }
+}
+
+impl Shape for AabbShape {
+    fn intersect(&self, ray: &Ray, t_min: ScalarF, t_max: ScalarF) -> Option<HitRecord<'_>> {
+        let crossing = self.bounding_box().slabs(ray)?;
+
+        // from inside the box the ray leaves through the exit face
+        let (t, normal) = if crossing.t_enter >= t_min && crossing.t_enter <= t_max {
+            (crossing.t_enter, crossing.enter_normal)
+        } else if crossing.t_exit >= t_min && crossing.t_exit <= t_max {
+            (crossing.t_exit, crossing.exit_normal)
+        } else {
+            return None;
+        };
+
+        let mut hit = HitRecord {
+            t,
+            p: ray.at(t),
+            normal,
+            front_face: true,
+            uv: None,
+            tangent: None,
+            bitangent: None,
+            vertex_color: None,
+            shape: Some(self as &dyn Shape),
+            object: None,
+            object_id: 0,
+            exterior_ior: None,
+        };
+        hit.set_face_normal(ray, normal);
+        Some(hit)
+    }
+
+    fn transform(&self, transform: &Matrix4D) -> Arc<dyn Shape> {
+        // scales, mirrors and quarter turns map every axis onto a single
+        // one and keep the box axis aligned, other rotations go through an
+        // instance
+        let axis_aligned = [transform.x, transform.y, transform.z]
+            .iter()
+            .all(|column| {
+                [column.x, column.y, column.z]
+                    .iter()
+                    .filter(|c| c.abs() > ScalarF::EPSILON)
+                    .count()
+                    == 1
+            });
+        if !axis_aligned {
+            return Arc::new(InstancedShape::new(Arc::new(self.clone()), *transform));
+        }
+        let corners = self
+            .bounding_box()
+            .corners()
+            .map(|corner| transform_point3(*transform, corner));
+        let bounds = Aabb::from_points(&corners);
+        Arc::new(AabbShape {
+            min: bounds.min,
+            max: bounds.max,
+        })
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        Aabb::new(self.min, self.max)
+    }
+
+    fn area(&self) -> ScalarF {
+        2.0 * self.face_areas().iter().sum::<ScalarF>()
+    }
+
+    fn sample(&self, sampler: &mut dyn Sampler) -> Option<ShapeSample> {
+        // pick one of the six faces proportionally to its area
+        let areas = self.face_areas();
+        let mut target = sampler.get_1d() * self.area();
+        let mut face = 5;
+        for i in 0..6 {
+            if target < areas[i / 2] {
+                face = i;
+                break;
+            }
+            target -= areas[i / 2];
+        }
+
+        let (axis, sign) = (face / 2, if face % 2 == 0 { -1.0 } else { 1.0 });
+        let (s, t) = sampler.get_2d();
+        let (u, v) = ((axis + 1) % 3, (axis + 2) % 3);
+        let mut p = self.min;
+        p[axis] = if sign < 0.0 {
+            self.min[axis]
+        } else {
+            self.max[axis]
+        };
+        p[u] += s * (self.max[u] - self.min[u]);
+        p[v] += t * (self.max[v] - self.min[v]);
+        Some(ShapeSample {
+            p,
+            normal: axis_normal(axis, sign),
+            pdf: 1.0 / self.area(),
+        })
+    }
+
+    fn supports_intersect_all(&self) -> bool {
+        true
+    }
+
+    fn intersect_all(&self, ray: &Ray) -> Vec<(ScalarF, ScalarF)> {
+        self.bounding_box()
+            .intersect(ray, ScalarF::NEG_INFINITY, ScalarF::INFINITY)
+            .into_iter()
+            .collect()
+    }
+}
+
+impl AabbShapeConfig {
+    pub fn to_shape(&self) -> Arc<dyn Shape> {
+        AabbShape {
+            min: self.min.to_point(),
+            max: self.max.to_point(),
+        }
+        .transform(&unwrap_matrix4d_config_to_matrix4d(self.transform.as_ref()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::{tolerance, vec3_approx_eq, Vec3D};
+    use crate::sampler::RandomSampler;
+    use approx::assert_abs_diff_eq;
+    use cgmath::{InnerSpace, Matrix4, Rad};
+
+    fn test_box() -> AabbShape {
+        AabbShape {
+            min: Point3D::new(-1.0, 0.0, 2.0),
+            max: Point3D::new(1.0, 0.5, 4.0),
+        }
+    }
+
+    #[test]
+    fn test_aabb_shape_intersect() {
+        let shape = test_box();
+
+        // through the top and the bottom face
+        let ray = Ray {
+            origin: Point3D::new(0.2, 3.0, 3.0),
+            direction: Vec3D::new(0.1, -1.0, 0.2).normalize(),
+        };
+        let hit = shape.intersect(&ray, 1e-6, ScalarF::MAX).unwrap();
+        assert_abs_diff_eq!(hit.p.y, 0.5, epsilon = tolerance(1e-9));
+        assert!(vec3_approx_eq(
+            hit.normal,
+            Vec3D::new(0.0, 1.0, 0.0),
+            tolerance(1e-9)
+        ));
+        let ray = Ray {
+            origin: Point3D::new(-0.5, -2.0, 2.5),
+            direction: Vec3D::new(0.0, 1.0, 0.1).normalize(),
+        };
+        let hit = shape.intersect(&ray, 1e-6, ScalarF::MAX).unwrap();
+        assert_abs_diff_eq!(hit.p.y, 0.0, epsilon = tolerance(1e-9));
+        assert!(vec3_approx_eq(
+            hit.normal,
+            Vec3D::new(0.0, -1.0, 0.0),
+            tolerance(1e-9)
+        ));
+
+        // from inside out through the far side face
+        let ray = Ray {
+            origin: Point3D::new(0.0, 0.25, 3.0),
+            direction: Vec3D::new(0.0, 0.0, 1.0),
+        };
+        let hit = shape.intersect(&ray, 1e-6, ScalarF::MAX).unwrap();
+        assert_abs_diff_eq!(hit.t, 1.0, epsilon = tolerance(1e-9));
+        assert!(!hit.front_face);
+        assert!(vec3_approx_eq(
+            hit.normal,
+            Vec3D::new(0.0, 0.0, -1.0),
+            tolerance(1e-9)
+        ));
+        assert!(vec3_approx_eq(
+            hit.outward_normal(),
+            Vec3D::new(0.0, 0.0, 1.0),
+            tolerance(1e-9)
+        ));
+
+        // passing above the box
+        let ray = Ray {
+            origin: Point3D::new(-3.0, 0.6, 3.0),
+            direction: Vec3D::new(1.0, 0.0, 0.0),
+        };
+        assert!(shape.intersect(&ray, 1e-6, ScalarF::MAX).is_none());
+    }
+
+    #[test]
+    fn test_aabb_shape_sample() {
+        let shape = test_box();
+        crate::rng::init_thread_local_rng(1);
+        let mut sampler = RandomSampler::new(1);
+        let mut on_top = 0;
+        for _ in 0..1000 {
+            let sample = shape.sample(&mut sampler).unwrap();
+            let ray = Ray {
+                origin: sample.p + sample.normal,
+                direction: -sample.normal,
+            };
+            let hit = shape.intersect(&ray, 1e-6, ScalarF::MAX).unwrap();
+            assert_abs_diff_eq!(hit.t, 1.0, epsilon = tolerance(1e-9));
+            assert!(vec3_approx_eq(hit.normal, sample.normal, tolerance(1e-9)));
+            if sample.normal.y > 0.5 {
+                on_top += 1;
+            }
+        }
+        // top face is 4 out of 12 square units
+        assert!((on_top as ScalarF / 1000.0 - 4.0 / 12.0).abs() < 0.05);
+
+        // a quarter turn around y swaps the x and z extents
+        let rotated = shape.transform(&Matrix4::from_angle_y(Rad(crate::math::consts::FRAC_PI_2)));
+        let bounds = rotated.bounding_box();
+        assert!(vec3_approx_eq(
+            bounds.max - bounds.min,
+            Vec3D::new(2.0, 0.5, 2.0),
+            tolerance(1e-9)
+        ));
+
+        // turned by 45 degrees the corners are cut off, a box widened to
+        // their bounds would still be hit there
+        let rotated = shape.transform(&Matrix4::from_angle_y(Rad(crate::math::consts::FRAC_PI_4)));
+        let bounds = rotated.bounding_box();
+        let ray = Ray {
+            origin: Point3D::new(bounds.max.x - 0.05, 5.0, bounds.max.z - 0.05),
+            direction: Vec3D::new(0.0, -1.0, 0.0),
+        };
+        assert!(rotated.intersect(&ray, 1e-6, ScalarF::MAX).is_none());
+        let center = bounds.centroid();
+        let ray = Ray {
+            origin: Point3D::new(center.x, 5.0, center.z),
+            direction: Vec3D::new(0.0, -1.0, 0.0),
+        };
+        let hit = rotated.intersect(&ray, 1e-6, ScalarF::MAX).unwrap();
+        assert_abs_diff_eq!(hit.t, 4.5, epsilon = tolerance(1e-9));
+    }
+}
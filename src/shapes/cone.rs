@@ -0,0 +1,321 @@
+use super::super::common::HitRecord;
+use super::super::math::consts::PI;
+use super::super::math::{
+    transform_point3, transform_vec3, unwrap_matrix4d_config_to_matrix4d, Aabb, Matrix4D, Onb,
+    Point3D, Point3DConfig, Ray, ScalarF, TransformConfig, Vec3D, Vec3DConfig,
+};
+use super::super::sampler::Sampler;
+use super::shape::{closed_shape_intervals, Shape, ShapeSample};
+use cgmath::InnerSpace;
+use serde::Deserialize;
+use std::sync::Arc;
+
+// cone opening from `apex` along `axis`, closed by a disk `height` away
+#[derive(Debug)]
+pub struct Cone {
+    pub apex: Point3D,
+    pub axis: Vec3D, // unit length, from the apex to the base
+    pub half_angle_deg: ScalarF,
+    pub height: ScalarF,
+}
+
+#[derive(Deserialize)]
+pub struct ConeConfig {
+    pub apex: Point3DConfig,
+    pub axis: Vec3DConfig,
+    pub half_angle_deg: f64,
+    pub height: f64,
+    pub transform: Option<TransformConfig>,
+}
+
+impl Cone {
+    fn base_radius(&self) -> ScalarF {
+        self.height * self.half_angle_deg.to_radians().tan()
+    }
+
+    fn lateral_area(&self) -> ScalarF {
+        PI * self.base_radius() * self.height / self.half_angle_deg.to_radians().cos()
+    }
+
+    fn base_area(&self) -> ScalarF {
+        PI * self.base_radius() * self.base_radius()
+    }
+
+    // outward normal of the slanted surface at `w` from the apex, it lies in
+    // the plane of `w` and the axis. the apex itself faces away from the base
+    fn lateral_normal(&self, w: Vec3D) -> Vec3D {
+        let cos2 = self.half_angle_deg.to_radians().cos().powi(2);
+        let normal = w * cos2 - self.axis * w.dot(self.axis);
+        if normal.magnitude2() < 1e-18 {
+            return -self.axis;
+        }
+        normal.normalize()
+    }
+
+    // nearest hit on the slanted surface, as (t, normal)
+    fn intersect_lateral(
+        &self,
+        ray: &Ray,
+        t_min: ScalarF,
+        t_max: ScalarF,
+    ) -> Option<(ScalarF, Vec3D)> {
+        // points w from the apex on the double cone satisfy
+        // (w . axis)^2 = |w|^2 cos^2(half angle)
+        let cos2 = self.half_angle_deg.to_radians().cos().powi(2);
+        let co = ray.origin - self.apex;
+        let (dv, cv) = (ray.direction.dot(self.axis), co.dot(self.axis));
+        let a = dv * dv - cos2 * ray.direction.magnitude2();
+        let half_b = dv * cv - cos2 * ray.direction.dot(co);
+        let c = cv * cv - cos2 * co.magnitude2();
+        // allowance for rounding at the apex, f32 math needs far more
+        let slack = (1e-9 as ScalarF).max(16.0 * ScalarF::EPSILON);
+
+        let roots = if a.abs() < 1e-12 {
+            // parallel to the slant, a single crossing
+            if half_b.abs() < 1e-12 {
+                return None;
+            }
+            [-c / (2.0 * half_b); 2]
+        } else {
+            let mut discriminant = half_b * half_b - a * c;
+            if discriminant < 0.0 {
+                // rays through the apex touch it with a double root which
+                // rounding may push below zero
+                if discriminant < -slack * half_b * half_b {
+                    return None;
+                }
+                discriminant = 0.0;
+            }
+            let sqrtd = discriminant.sqrt();
+            let (t0, t1) = ((-half_b - sqrtd) / a, (-half_b + sqrtd) / a);
+            [t0.min(t1), t0.max(t1)]
+        };
+
+        for t in roots {
+            if t < t_min || t > t_max {
+                continue;
+            }
+            // only the nappe opening towards the base, up to the base
+            let w = co + ray.direction * t;
+            let h = w.dot(self.axis);
+            if (-slack..=self.height).contains(&h) {
+                return Some((t, self.lateral_normal(w)));
+            }
+        }
+        None
+    }
+
+    fn intersect_base(
+        &self,
+        ray: &Ray,
+        t_min: ScalarF,
+        t_max: ScalarF,
+    ) -> Option<(ScalarF, Vec3D)> {
+        let denominator = ray.direction.dot(self.axis);
+        if denominator.abs() < 1e-12 {
+            return None;
+        }
+        let base_center = self.apex + self.axis * self.height;
+        let t = (base_center - ray.origin).dot(self.axis) / denominator;
+        if t < t_min || t > t_max {
+            return None;
+        }
+        let r = self.base_radius();
+        if (ray.at(t) - base_center).magnitude2() > r * r {
+            return None;
+        }
+        Some((t, self.axis))
+    }
+}
+
+impl Shape for Cone {
+    fn intersect(&self, ray: &Ray, t_min: ScalarF, t_max: ScalarF) -> Option<HitRecord<'_>> {
+        let lateral = self.intersect_lateral(ray, t_min, t_max);
+        let t_max = lateral.map_or(t_max, |(t, _)| t);
+        let (t, normal) = self.intersect_base(ray, t_min, t_max).or(lateral)?;
+
+        let mut hit = HitRecord {
+            t,
+            p: ray.at(t),
+            normal,
+            front_face: true,
+            uv: None,
+            tangent: None,
+            bitangent: None,
+            vertex_color: None,
+            shape: Some(self as &dyn Shape),
+            object: None,
+            object_id: 0,
+            exterior_ior: None,
+        };
+        hit.set_face_normal(ray, normal);
+        Some(hit)
+    }
+
+    fn transform(&self, transform: &Matrix4D) -> Arc<dyn Shape> {
+        // scaling along the axis stretches the height, the angle is kept
+        let axis = transform_vec3(*transform, self.axis);
+        Arc::new(Cone {
+            apex: transform_point3(*transform, self.apex),
+            axis: axis.normalize(),
+            half_angle_deg: self.half_angle_deg,
+            height: self.height * axis.magnitude(),
+        })
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        // the apex and the base disk
+        let a = self.axis;
+        let r = self.base_radius();
+        let extent = Vec3D::new(
+            r * (1.0 - a.x * a.x).max(0.0).sqrt(),
+            r * (1.0 - a.y * a.y).max(0.0).sqrt(),
+            r * (1.0 - a.z * a.z).max(0.0).sqrt(),
+        );
+        let base_center = self.apex + a * self.height;
+        Aabb::new(base_center - extent, base_center + extent)
+            .union(&Aabb::new(self.apex, self.apex))
+    }
+
+    fn area(&self) -> ScalarF {
+        self.lateral_area() + self.base_area()
+    }
+
+    fn sample(&self, sampler: &mut dyn Sampler) -> Option<ShapeSample> {
+        // pick the slant or the base proportionally to their areas
+        let Onb { u, v, .. } = Onb::from_w(self.axis);
+        let area = self.area();
+        let target = sampler.get_1d() * area;
+        let (s, t) = sampler.get_2d();
+        let phi = 2.0 * PI * s;
+        let radial = u * phi.cos() + v * phi.sin();
+        let r = self.base_radius();
+
+        let (p, normal) = if target < self.lateral_area() {
+            // the circumference grows linearly away from the apex
+            let x = t.sqrt();
+            let w = self.axis * (x * self.height) + radial * (x * r);
+            (self.apex + w, self.lateral_normal(w))
+        } else {
+            let w = self.axis * self.height + radial * (r * t.sqrt());
+            (self.apex + w, self.axis)
+        };
+        Some(ShapeSample {
+            p,
+            normal,
+            pdf: 1.0 / area,
+        })
+    }
+
+    fn supports_intersect_all(&self) -> bool {
+        true
+    }
+
+    fn intersect_all(&self, ray: &Ray) -> Vec<(ScalarF, ScalarF)> {
+        closed_shape_intervals(self, ray)
+    }
+}
+
+impl ConeConfig {
+    pub fn to_shape(&self) -> Arc<dyn Shape> {
+        Cone {
+            apex: self.apex.to_point(),
+            axis: self.axis.to_vec3().normalize(),
+            half_angle_deg: self.half_angle_deg as ScalarF,
+            height: self.height as ScalarF,
+        }
+        .transform(&unwrap_matrix4d_config_to_matrix4d(self.transform.as_ref()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::{point_approx_eq, tolerance, vec3_approx_eq};
+    use crate::sampler::RandomSampler;
+    use approx::assert_abs_diff_eq;
+
+    fn test_cone() -> Cone {
+        Cone {
+            apex: Point3D::new(-1.0, 0.5, 2.0),
+            axis: Vec3D::new(0.0, -1.0, 1.0).normalize(),
+            half_angle_deg: 30.0,
+            height: 2.0,
+        }
+    }
+
+    #[test]
+    fn test_cone_intersect() {
+        let cone = test_cone();
+        let Onb { u, v, w: axis } = Onb::from_w(cone.axis);
+
+        // aimed at the apex from outside, behind it. the double root there is
+        // only as good as the square root of the precision
+        let apex_tolerance = (1e-6 as ScalarF).max(50.0 * ScalarF::EPSILON.sqrt());
+        for offset in [u * 0.5, v * 3.0, u * 1.0 + v * 1.0] {
+            let origin = cone.apex - axis * 2.0 + offset;
+            let ray = Ray {
+                origin,
+                direction: (cone.apex - origin).normalize(),
+            };
+            let hit = cone.intersect(&ray, 1e-6, ScalarF::MAX).unwrap();
+            assert!(point_approx_eq(hit.p, cone.apex, apex_tolerance));
+        }
+
+        // onto the inside of the base disk
+        let base_center = cone.apex + axis * cone.height;
+        let ray = Ray {
+            origin: base_center + axis * 3.0 + u * 0.7 + v * 0.4,
+            direction: -axis,
+        };
+        let hit = cone.intersect(&ray, 1e-6, ScalarF::MAX).unwrap();
+        assert_abs_diff_eq!(hit.t, 3.0, epsilon = tolerance(1e-9));
+        assert!(vec3_approx_eq(hit.normal, axis, tolerance(1e-9)));
+
+        // across the slant halfway down, the normal leans towards the apex
+        let origin = cone.apex + axis + u * 5.0;
+        let ray = Ray {
+            origin,
+            direction: -u,
+        };
+        let hit = cone.intersect(&ray, 1e-6, ScalarF::MAX).unwrap();
+        let r = ScalarF::to_radians(30.0).tan();
+        assert_abs_diff_eq!(hit.t, 5.0 - r, epsilon = tolerance(1e-9));
+        let expected = u * ScalarF::to_radians(30.0).cos() - axis * ScalarF::to_radians(30.0).sin();
+        assert!(vec3_approx_eq(hit.normal, expected, tolerance(1e-9)));
+
+        // past the base and beside the slant
+        let ray = Ray {
+            origin: cone.apex + axis * 2.1 + u * 5.0,
+            direction: -u,
+        };
+        assert!(cone.intersect(&ray, 1e-6, ScalarF::MAX).is_none());
+        let ray = Ray {
+            origin: cone.apex + axis + u * (r + 0.01) + v * 5.0,
+            direction: -v,
+        };
+        assert!(cone.intersect(&ray, 1e-6, ScalarF::MAX).is_none());
+    }
+
+    #[test]
+    fn test_cone_sample() {
+        let cone = test_cone();
+        let bounds = cone.bounding_box();
+        let mut sampler = RandomSampler::new(1);
+        for _ in 0..1000 {
+            let sample = cone.sample(&mut sampler).unwrap();
+            assert_abs_diff_eq!(sample.pdf, 1.0 / cone.area(), epsilon = tolerance(1e-12));
+            assert!(bounds.union(&Aabb::new(sample.p, sample.p)) == bounds);
+
+            // the point lies on the surface and the normal agrees with a ray
+            // shot back at it
+            let ray = Ray {
+                origin: sample.p + sample.normal,
+                direction: -sample.normal,
+            };
+            let hit = cone.intersect(&ray, 1e-6, ScalarF::MAX).unwrap();
+            assert_abs_diff_eq!(hit.t, 1.0, epsilon = tolerance(1e-6));
+            assert!(vec3_approx_eq(hit.normal, sample.normal, tolerance(1e-6)));
+        }
+    }
+}
@@ -0,0 +1,369 @@
+use super::super::common::HitRecord;
+use super::super::error::RaytracerError;
+use super::super::math::{
+    unwrap_matrix4d_config_to_matrix4d, Aabb, Matrix4D, Point3D, Ray, ScalarF, TransformConfig,
+    CROSSING_EPSILON,
+};
+use super::super::sampler::Sampler;
+use super::shape::{Shape, ShapeConfig, ShapeSample};
+use serde::Deserialize;
+use std::sync::Arc;
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+pub enum CsgOp {
+    Union,
+    Intersection,
+    Difference, // left minus right
+}
+
+// boolean combination of two closed shapes, both children must support
+// intersect_all
+pub struct CsgShape {
+    pub op: CsgOp,
+    pub left: Arc<dyn Shape>,
+    pub right: Arc<dyn Shape>,
+}
+
+#[derive(Deserialize)]
+pub struct CsgShapeConfig {
+    pub op: CsgOp,
+    pub left: Box<ShapeConfig>,
+    pub right: Box<ShapeConfig>,
+    pub transform: Option<TransformConfig>,
+}
+
+// where the combined shape's surface is crossed along a ray
+struct Boundary {
+    t: ScalarF,
+    entering: bool,
+    from_left: bool,
+}
+
+impl CsgOp {
+    fn inside(&self, in_left: bool, in_right: bool) -> bool {
+        match self {
+            CsgOp::Union => in_left || in_right,
+            CsgOp::Intersection => in_left && in_right,
+            CsgOp::Difference => in_left && !in_right,
+        }
+    }
+}
+
+impl CsgShape {
+    // sweeps the crossings of both children in order and keeps those that
+    // change whether the ray is inside the combination
+    fn boundaries(&self, ray: &Ray) -> Vec<Boundary> {
+        let mut events = vec![];
+        for (intervals, from_left) in [
+            (self.left.intersect_all(ray), true),
+            (self.right.intersect_all(ray), false),
+        ] {
+            for (t0, t1) in intervals {
+                events.push((t0, true, from_left));
+                events.push((t1, false, from_left));
+            }
+        }
+        events.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        let (mut in_left, mut in_right) = (false, false);
+        let mut boundaries = vec![];
+        for (t, entering, from_left) in events {
+            let was_inside = self.op.inside(in_left, in_right);
+            if from_left {
+                in_left = entering;
+            } else {
+                in_right = entering;
+            }
+            let inside = self.op.inside(in_left, in_right);
+            if inside != was_inside {
+                boundaries.push(Boundary {
+                    t,
+                    entering: inside,
+                    from_left,
+                });
+            }
+        }
+        boundaries
+    }
+}
+
+impl Shape for CsgShape {
+    fn intersect(&self, ray: &Ray, t_min: ScalarF, t_max: ScalarF) -> Option<HitRecord<'_>> {
+        for boundary in self.boundaries(ray) {
+            if boundary.t < t_min || boundary.t > t_max {
+                continue;
+            }
+
+            // the child that owns the boundary gives the surface details, its
+            // normal is flipped where the child is carved out
+            let child = if boundary.from_left {
+                &self.left
+            } else {
+                &self.right
+            };
+            let epsilon = CROSSING_EPSILON * (1.0 + boundary.t.abs());
+            let hit = match child.intersect(ray, boundary.t - epsilon, boundary.t + epsilon) {
+                Some(hit) => hit,
+                None => continue,
+            };
+            let outward_normal = if hit.front_face == boundary.entering {
+                hit.outward_normal()
+            } else {
+                -hit.outward_normal()
+            };
+            let mut hit = HitRecord {
+                t: boundary.t,
+                p: ray.at(boundary.t),
+                ..hit
+            };
+            hit.set_face_normal(ray, outward_normal);
+            return Some(hit);
+        }
+        None
+    }
+
+    fn supports_intersect_all(&self) -> bool {
+        true
+    }
+
+    fn intersect_all(&self, ray: &Ray) -> Vec<(ScalarF, ScalarF)> {
+        let mut intervals = vec![];
+        let mut start = None;
+        for boundary in self.boundaries(ray) {
+            if boundary.entering {
+                start = Some(boundary.t);
+            } else if let Some(t0) = start.take() {
+                intervals.push((t0, boundary.t));
+            }
+        }
+        intervals
+    }
+
+    fn transform(&self, transform: &Matrix4D) -> Arc<dyn Shape> {
+        Arc::new(CsgShape {
+            op: self.op,
+            left: self.left.transform(transform),
+            right: self.right.transform(transform),
+        })
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        let (left, right) = (self.left.bounding_box(), self.right.bounding_box());
+        match self.op {
+            CsgOp::Union => left.union(&right),
+            CsgOp::Intersection => Aabb::new(
+                Point3D::new(
+                    left.min.x.max(right.min.x),
+                    left.min.y.max(right.min.y),
+                    left.min.z.max(right.min.z),
+                ),
+                Point3D::new(
+                    left.max.x.min(right.max.x),
+                    left.max.y.min(right.max.y),
+                    left.max.z.min(right.max.z),
+                ),
+            ),
+            CsgOp::Difference => left,
+        }
+    }
+
+    // an upper bound, the exact area would need the curves where the
+    // children cut each other
+    fn area(&self) -> ScalarF {
+        self.left.area() + self.right.area()
+    }
+
+    fn sample(&self, _: &mut dyn Sampler) -> Option<ShapeSample> {
+        None
+    }
+}
+
+impl CsgShapeConfig {
+    pub fn to_shape(&self) -> Result<Arc<dyn Shape>, RaytracerError> {
+        let (left, right) = (self.left.to_shape()?, self.right.to_shape()?);
+        // the children are combined through the ranges they enclose
+        if !left.supports_intersect_all() || !right.supports_intersect_all() {
+            return Err(RaytracerError::SceneLoad(
+                "CSG children must be closed shapes".to_string(),
+            ));
+        }
+        Ok(CsgShape {
+            op: self.op,
+            left,
+            right,
+        }
+        .transform(&unwrap_matrix4d_config_to_matrix4d(self.transform.as_ref())))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::{tolerance, vec3_approx_eq, Vec3D};
+    use crate::shapes::{AabbShape, Cylinder, Sphere};
+    use approx::assert_abs_diff_eq;
+
+    fn spheres(op: CsgOp) -> CsgShape {
+        CsgShape {
+            op,
+            left: Arc::new(Sphere {
+                center: Point3D::new(0.0, 0.0, 0.0),
+                radius: 1.0,
+            }),
+            right: Arc::new(Sphere {
+                center: Point3D::new(0.0, 0.0, 1.0),
+                radius: 0.5,
+            }),
+        }
+    }
+
+    #[test]
+    fn test_csg_difference() {
+        let shape = spheres(CsgOp::Difference);
+
+        // down the z axis the ray first meets the bite taken out of the
+        // big sphere, its normal points into the hole
+        let ray = Ray {
+            origin: Point3D::new(0.0, 0.0, 5.0),
+            direction: Vec3D::new(0.0, 0.0, -1.0),
+        };
+        let hit = shape.intersect(&ray, 1e-6, ScalarF::MAX).unwrap();
+        assert_abs_diff_eq!(hit.t, 4.5, epsilon = tolerance(1e-9));
+        assert!(vec3_approx_eq(
+            hit.normal,
+            Vec3D::new(0.0, 0.0, 1.0),
+            tolerance(1e-9)
+        ));
+        assert_eq!(shape.intersect_all(&ray).len(), 1);
+
+        // only the small sphere is in the way, the hole lets the ray through
+        let ray = Ray {
+            origin: Point3D::new(3.0, 0.0, 1.3),
+            direction: Vec3D::new(-1.0, 0.0, 0.0),
+        };
+        assert!(shape.intersect(&ray, 1e-6, ScalarF::MAX).is_none());
+        assert!(shape.intersect_all(&ray).is_empty());
+
+        // outside the hole it is an ordinary sphere
+        let ray = Ray {
+            origin: Point3D::new(3.0, 0.0, 0.0),
+            direction: Vec3D::new(-1.0, 0.0, 0.0),
+        };
+        let hit = shape.intersect(&ray, 1e-6, ScalarF::MAX).unwrap();
+        assert_abs_diff_eq!(hit.t, 2.0, epsilon = tolerance(1e-9));
+        assert!(vec3_approx_eq(
+            hit.normal,
+            Vec3D::new(1.0, 0.0, 0.0),
+            tolerance(1e-9)
+        ));
+    }
+
+    #[test]
+    fn test_csg_union_intersection() {
+        let ray = Ray {
+            origin: Point3D::new(0.0, 0.0, 5.0),
+            direction: Vec3D::new(0.0, 0.0, -1.0),
+        };
+
+        // the overlap merges into a single stretch
+        let union = spheres(CsgOp::Union);
+        let intervals = union.intersect_all(&ray);
+        assert_eq!(intervals.len(), 1);
+        assert_abs_diff_eq!(intervals[0].0, 3.5, epsilon = tolerance(1e-9));
+        assert_abs_diff_eq!(intervals[0].1, 6.0, epsilon = tolerance(1e-9));
+
+        // only the lens shared by both spheres remains
+        let intersection = spheres(CsgOp::Intersection);
+        let hit = intersection.intersect(&ray, 1e-6, ScalarF::MAX).unwrap();
+        assert_abs_diff_eq!(hit.t, 4.0, epsilon = tolerance(1e-9));
+        assert!(vec3_approx_eq(
+            hit.normal,
+            Vec3D::new(0.0, 0.0, 1.0),
+            tolerance(1e-9)
+        ));
+        let hit = intersection.intersect(&ray, 4.1, ScalarF::MAX).unwrap();
+        assert_abs_diff_eq!(hit.t, 4.5, epsilon = tolerance(1e-9));
+        assert!(!hit.front_face);
+        assert!(vec3_approx_eq(
+            hit.outward_normal(),
+            Vec3D::new(0.0, 0.0, -1.0),
+            tolerance(1e-9)
+        ));
+
+        // nested combinations only see the intervals of their children
+        let nested = CsgShape {
+            op: CsgOp::Difference,
+            left: Arc::new(union),
+            right: Arc::new(intersection),
+        };
+        let intervals = nested.intersect_all(&ray);
+        assert_eq!(intervals.len(), 2);
+        assert_abs_diff_eq!(intervals[0].1, 4.0, epsilon = tolerance(1e-9));
+        assert_abs_diff_eq!(intervals[1].0, 4.5, epsilon = tolerance(1e-9));
+    }
+
+    #[test]
+    fn test_csg_drilled_box() {
+        // a box with a cylinder drilled through it along y
+        let shape = CsgShape {
+            op: CsgOp::Difference,
+            left: Arc::new(AabbShape {
+                min: Point3D::new(-1.0, -1.0, -1.0),
+                max: Point3D::new(1.0, 1.0, 1.0),
+            }),
+            right: Arc::new(Cylinder {
+                center: Point3D::new(0.0, 0.0, 0.0),
+                radius: 0.5,
+                height: 3.0,
+                axis: Vec3D::new(0.0, 1.0, 0.0),
+            }),
+        };
+
+        let ray = Ray {
+            origin: Point3D::new(0.2, 5.0, 0.1),
+            direction: Vec3D::new(0.0, -1.0, 0.0),
+        };
+        assert!(shape.intersect(&ray, 1e-6, ScalarF::MAX).is_none());
+
+        // across the hole, in through the side and out into the bore
+        let ray = Ray {
+            origin: Point3D::new(5.0, 0.0, 0.0),
+            direction: Vec3D::new(-1.0, 0.0, 0.0),
+        };
+        let hit = shape.intersect(&ray, 1e-6, ScalarF::MAX).unwrap();
+        assert_abs_diff_eq!(hit.t, 4.0, epsilon = tolerance(1e-9));
+        let hit = shape.intersect(&ray, 4.1, ScalarF::MAX).unwrap();
+        assert_abs_diff_eq!(hit.t, 4.5, epsilon = tolerance(1e-9));
+        assert!(!hit.front_face);
+        assert!(vec3_approx_eq(
+            hit.outward_normal(),
+            Vec3D::new(-1.0, 0.0, 0.0),
+            tolerance(1e-9)
+        ));
+        assert_eq!(shape.intersect_all(&ray).len(), 2);
+    }
+
+    #[test]
+    fn test_csg_config_needs_closed_children() {
+        let config = |right: &str| {
+            toml::from_str::<CsgShapeConfig>(&format!(
+                r#"
+                op = "Difference"
+                left = {{ type = "Sphere", center = {{ x = 0.0, y = 0.0, z = 0.0 }}, radius = 1.0 }}
+                right = {}
+                "#,
+                right
+            ))
+            .unwrap()
+        };
+        let sphere = r#"{ type = "Sphere", center = { x = 0.0, y = 0.0, z = 1.0 }, radius = 0.5 }"#;
+        let plane = r#"{ type = "Plane", point = { x = 0.0, y = 0.0, z = 0.0 }, normal = { x = 0.0, y = 1.0, z = 0.0 } }"#;
+        assert!(config(sphere).to_shape().is_ok());
+        // a plane has no inside, it is rejected when the scene loads
+        // instead of when a ray first reaches it
+        assert!(matches!(
+            config(plane).to_shape(),
+            Err(RaytracerError::SceneLoad(_))
+        ));
+    }
+}
@@ -0,0 +1,347 @@
+use super::super::common::HitRecord;
+use super::super::math::consts::PI;
+use super::super::math::{
+    transform_point3, transform_vec3, unwrap_matrix4d_config_to_matrix4d, Aabb, Matrix4D, Onb,
+    Point3D, Point3DConfig, Ray, ScalarF, TransformConfig, Vec3D, Vec3DConfig,
+};
+use super::super::sampler::Sampler;
+use super::shape::{closed_shape_intervals, Shape, ShapeSample};
+use cgmath::InnerSpace;
+use serde::Deserialize;
+use std::sync::Arc;
+
+// closed cylinder around `axis` through `center`, which lies halfway
+// between the two caps
+#[derive(Debug)]
+pub struct Cylinder {
+    pub center: Point3D,
+    pub radius: ScalarF,
+    pub height: ScalarF,
+    pub axis: Vec3D, // unit length
+}
+
+#[derive(Deserialize)]
+pub struct CylinderConfig {
+    pub center: Point3DConfig,
+    pub radius: f64,
+    pub height: f64,
+    pub axis: Vec3DConfig,
+    pub transform: Option<TransformConfig>,
+}
+
+impl Cylinder {
+    fn barrel_area(&self) -> ScalarF {
+        2.0 * PI * self.radius * self.height
+    }
+
+    fn cap_area(&self) -> ScalarF {
+        PI * self.radius * self.radius
+    }
+
+    // nearest hit on the barrel, as (t, normal)
+    fn intersect_barrel(
+        &self,
+        ray: &Ray,
+        t_min: ScalarF,
+        t_max: ScalarF,
+    ) -> Option<(ScalarF, Vec3D)> {
+        // the quadratic of the infinite cylinder in the plane orthogonal to
+        // the axis
+        let oc = ray.origin - self.center;
+        let d = ray.direction - self.axis * ray.direction.dot(self.axis);
+        let o = oc - self.axis * oc.dot(self.axis);
+        let a = d.magnitude2();
+        if a < 1e-12 {
+            return None; // parallel to the axis
+        }
+        let half_b = o.dot(d);
+        let c = o.magnitude2() - self.radius * self.radius;
+        let discriminant = half_b * half_b - a * c;
+        if discriminant < 0.0 {
+            return None;
+        }
+
+        let sqrtd = discriminant.sqrt();
+        for t in [(-half_b - sqrtd) / a, (-half_b + sqrtd) / a] {
+            if t < t_min || t > t_max {
+                continue;
+            }
+            let h = (oc + ray.direction * t).dot(self.axis);
+            if h.abs() <= self.height * 0.5 {
+                let normal = (o + d * t) / self.radius;
+                return Some((t, normal));
+            }
+        }
+        None
+    }
+
+    // nearest hit on either cap, as (t, normal)
+    fn intersect_caps(
+        &self,
+        ray: &Ray,
+        t_min: ScalarF,
+        t_max: ScalarF,
+    ) -> Option<(ScalarF, Vec3D)> {
+        let denominator = ray.direction.dot(self.axis);
+        if denominator.abs() < 1e-12 {
+            return None;
+        }
+
+        let oc = ray.origin - self.center;
+        let mut closest: Option<(ScalarF, Vec3D)> = None;
+        for sign in [-1.0, 1.0] {
+            let t = (sign * self.height * 0.5 - oc.dot(self.axis)) / denominator;
+            if t < t_min || t > t_max || closest.is_some_and(|(closest_t, _)| t > closest_t) {
+                continue;
+            }
+            let w = oc + ray.direction * t;
+            let radial = w - self.axis * w.dot(self.axis);
+            if radial.magnitude2() <= self.radius * self.radius {
+                closest = Some((t, self.axis * sign));
+            }
+        }
+        closest
+    }
+}
+
+impl Shape for Cylinder {
+    fn intersect(&self, ray: &Ray, t_min: ScalarF, t_max: ScalarF) -> Option<HitRecord<'_>> {
+        let barrel = self.intersect_barrel(ray, t_min, t_max);
+        let t_max = barrel.map_or(t_max, |(t, _)| t);
+        let (t, normal) = self.intersect_caps(ray, t_min, t_max).or(barrel)?;
+
+        let mut hit = HitRecord {
+            t,
+            p: ray.at(t),
+            normal,
+            front_face: true,
+            uv: None,
+            tangent: None,
+            bitangent: None,
+            vertex_color: None,
+            shape: Some(self as &dyn Shape),
+            object: None,
+            object_id: 0,
+            exterior_ior: None,
+        };
+        hit.set_face_normal(ray, normal);
+        Some(hit)
+    }
+
+    fn transform(&self, transform: &Matrix4D) -> Arc<dyn Shape> {
+        // scaling along the axis stretches the height, the radius is kept
+        let axis = transform_vec3(*transform, self.axis);
+        Arc::new(Cylinder {
+            center: transform_point3(*transform, self.center),
+            radius: self.radius,
+            height: self.height * axis.magnitude(),
+            axis: axis.normalize(),
+        })
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        // caps are disks, their extent along each world axis is
+        // radius * sin of the angle between that axis and the cylinder axis
+        let a = self.axis;
+        let extent = Vec3D::new(
+            self.height * 0.5 * a.x.abs() + self.radius * (1.0 - a.x * a.x).max(0.0).sqrt(),
+            self.height * 0.5 * a.y.abs() + self.radius * (1.0 - a.y * a.y).max(0.0).sqrt(),
+            self.height * 0.5 * a.z.abs() + self.radius * (1.0 - a.z * a.z).max(0.0).sqrt(),
+        );
+        Aabb::new(self.center - extent, self.center + extent)
+    }
+
+    fn area(&self) -> ScalarF {
+        self.barrel_area() + 2.0 * self.cap_area()
+    }
+
+    fn sample(&self, sampler: &mut dyn Sampler) -> Option<ShapeSample> {
+        // pick the barrel or one of the caps proportionally to their areas
+        let Onb { u, v, .. } = Onb::from_w(self.axis);
+        let area = self.area();
+        let target = sampler.get_1d() * area;
+        let (s, t) = sampler.get_2d();
+        let phi = 2.0 * PI * s;
+        let radial = u * phi.cos() + v * phi.sin();
+
+        let (p, normal) = if target < self.barrel_area() {
+            let h = (t - 0.5) * self.height;
+            (self.center + self.axis * h + radial * self.radius, radial)
+        } else {
+            let sign = if target < self.barrel_area() + self.cap_area() {
+                -1.0
+            } else {
+                1.0
+            };
+            let r = self.radius * t.sqrt();
+            (
+                self.center + self.axis * (sign * self.height * 0.5) + radial * r,
+                self.axis * sign,
+            )
+        };
+        Some(ShapeSample {
+            p,
+            normal,
+            pdf: 1.0 / area,
+        })
+    }
+
+    fn supports_intersect_all(&self) -> bool {
+        true
+    }
+
+    fn intersect_all(&self, ray: &Ray) -> Vec<(ScalarF, ScalarF)> {
+        closed_shape_intervals(self, ray)
+    }
+}
+
+impl CylinderConfig {
+    pub fn to_shape(&self) -> Arc<dyn Shape> {
+        Cylinder {
+            center: self.center.to_point(),
+            radius: self.radius as ScalarF,
+            height: self.height as ScalarF,
+            axis: self.axis.to_vec3().normalize(),
+        }
+        .transform(&unwrap_matrix4d_config_to_matrix4d(self.transform.as_ref()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::{tolerance, vec3_approx_eq};
+    use crate::sampler::RandomSampler;
+    use approx::assert_abs_diff_eq;
+
+    fn test_cylinder() -> Cylinder {
+        Cylinder {
+            center: Point3D::new(1.0, 2.0, 3.0),
+            radius: 0.5,
+            height: 2.0,
+            axis: Vec3D::new(1.0, 1.0, 0.0).normalize(),
+        }
+    }
+
+    #[test]
+    fn test_cylinder_intersect() {
+        let cylinder = test_cylinder();
+        let Onb { u, v, w: axis } = Onb::from_w(cylinder.axis);
+
+        // along the axis onto the top cap
+        let ray = Ray {
+            origin: cylinder.center + axis * 5.0 + u * 0.2,
+            direction: -axis,
+        };
+        let hit = cylinder.intersect(&ray, 1e-6, ScalarF::MAX).unwrap();
+        assert_abs_diff_eq!(hit.t, 4.0, epsilon = tolerance(1e-9));
+        assert!(vec3_approx_eq(hit.normal, axis, tolerance(1e-9)));
+        // from below onto the bottom cap
+        let ray = Ray {
+            origin: cylinder.center - axis * 5.0,
+            direction: axis,
+        };
+        let hit = cylinder.intersect(&ray, 1e-6, ScalarF::MAX).unwrap();
+        assert!(vec3_approx_eq(hit.normal, -axis, tolerance(1e-9)));
+
+        // across the barrel, the normal points radially outwards
+        let ray = Ray {
+            origin: cylinder.center + axis * 0.3 + u * 3.0,
+            direction: -u,
+        };
+        let hit = cylinder.intersect(&ray, 1e-6, ScalarF::MAX).unwrap();
+        assert_abs_diff_eq!(hit.t, 2.5, epsilon = tolerance(1e-9));
+        assert!(vec3_approx_eq(hit.normal, u, tolerance(1e-9)));
+        // from inside it leaves through the far side
+        let ray = Ray {
+            origin: cylinder.center,
+            direction: v,
+        };
+        let hit = cylinder.intersect(&ray, 1e-6, ScalarF::MAX).unwrap();
+        assert_abs_diff_eq!(hit.t, 0.5, epsilon = tolerance(1e-9));
+
+        // just outside of the barrel, and past the end of the cylinder
+        let ray = Ray {
+            origin: cylinder.center + u * 0.5001 + v * 3.0,
+            direction: -v,
+        };
+        assert!(cylinder.intersect(&ray, 1e-6, ScalarF::MAX).is_none());
+        let ray = Ray {
+            origin: cylinder.center + axis * 1.01 + u * 3.0,
+            direction: -u,
+        };
+        assert!(cylinder.intersect(&ray, 1e-6, ScalarF::MAX).is_none());
+    }
+
+    #[test]
+    fn test_cylinder_sample() {
+        let cylinder = test_cylinder();
+        let bounds = cylinder.bounding_box();
+        crate::rng::init_thread_local_rng(1);
+        let mut sampler = RandomSampler::new(1);
+        let mut on_caps = 0;
+        for _ in 0..1000 {
+            let sample = cylinder.sample(&mut sampler).unwrap();
+            assert_abs_diff_eq!(
+                sample.pdf,
+                1.0 / cylinder.area(),
+                epsilon = tolerance(1e-12)
+            );
+            assert!(bounds.union(&Aabb::new(sample.p, sample.p)) == bounds);
+
+            // the point lies on the surface and the normal agrees with a ray
+            // shot back at it
+            let ray = Ray {
+                origin: sample.p + sample.normal,
+                direction: -sample.normal,
+            };
+            let hit = cylinder.intersect(&ray, 1e-6, ScalarF::MAX).unwrap();
+            assert_abs_diff_eq!(hit.t, 1.0, epsilon = tolerance(1e-6));
+            assert!(vec3_approx_eq(hit.normal, sample.normal, tolerance(1e-6)));
+            if sample.normal.dot(cylinder.axis).abs() > 0.5 {
+                on_caps += 1;
+            }
+        }
+        // the caps make up a fifth of the area
+        assert!((on_caps as ScalarF / 1000.0 - 0.2).abs() < 0.05);
+    }
+
+    #[test]
+    fn test_cylinder_transform() {
+        let cylinder = Cylinder {
+            center: Point3D::new(0.0, 0.0, 0.0),
+            radius: 1.0,
+            height: 2.0,
+            axis: Vec3D::new(0.0, 0.0, 1.0),
+        };
+        // moved up and stretched along its axis
+        let transform = Matrix4D::from_translation(Vec3D::new(0.0, 5.0, 0.0))
+            * Matrix4D::from_nonuniform_scale(1.0, 1.0, 3.0);
+        let shape = cylinder.transform(&transform);
+
+        let ray = Ray {
+            origin: Point3D::new(0.0, 5.0, 10.0),
+            direction: Vec3D::new(0.0, 0.0, -1.0),
+        };
+        let hit = shape.intersect(&ray, 1e-6, ScalarF::MAX).unwrap();
+        assert_abs_diff_eq!(hit.t, 7.0, epsilon = tolerance(1e-9));
+        assert!(vec3_approx_eq(
+            hit.normal,
+            Vec3D::new(0.0, 0.0, 1.0),
+            tolerance(1e-9)
+        ));
+
+        let ray = Ray {
+            origin: Point3D::new(-5.0, 5.0, 2.5),
+            direction: Vec3D::new(1.0, 0.0, 0.0),
+        };
+        let hit = shape.intersect(&ray, 1e-6, ScalarF::MAX).unwrap();
+        assert_abs_diff_eq!(hit.t, 4.0, epsilon = tolerance(1e-9));
+        assert!(vec3_approx_eq(
+            hit.normal,
+            Vec3D::new(-1.0, 0.0, 0.0),
+            tolerance(1e-9)
+        ));
+        assert_abs_diff_eq!(shape.bounding_box().max.z, 3.0, epsilon = tolerance(1e-9));
+    }
+}
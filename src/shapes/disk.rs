@@ -0,0 +1,192 @@
+use super::super::common::HitRecord;
+use super::super::math::consts::PI;
+use super::super::math::{
+    transform_point3, transform_vec3, unwrap_matrix4d_config_to_matrix4d, Aabb, Matrix4D, Onb,
+    Point3D, Point3DConfig, Ray, ScalarF, TransformConfig, Vec3D, Vec3DConfig,
+};
+use super::super::sampler::Sampler;
+use super::shape::{Shape, ShapeSample};
+use cgmath::InnerSpace;
+use serde::Deserialize;
+use std::sync::Arc;
+
+// half thickness of the bounding box, keeps it from collapsing to a plane
+const DISK_BOUNDS_EPSILON: ScalarF = 1e-4;
+
+// flat circle of `radius` around `center`, perpendicular to `normal`
+#[derive(Debug)]
+pub struct Disk {
+    pub center: Point3D,
+    pub normal: Vec3D,
+    pub radius: ScalarF,
+}
+
+#[derive(Deserialize)]
+pub struct DiskConfig {
+    pub center: Point3DConfig,
+    pub normal: Vec3DConfig,
+    pub radius: f64,
+    pub transform: Option<TransformConfig>,
+}
+
+// maps the unit square onto the unit disk keeping areas and adjacency, the
+// square's concentric squares become concentric circles
+pub fn concentric_sample_disk((u, v): (ScalarF, ScalarF)) -> (ScalarF, ScalarF) {
+    let (x, y) = (2.0 * u - 1.0, 2.0 * v - 1.0);
+    if x == 0.0 && y == 0.0 {
+        return (0.0, 0.0);
+    }
+    let (r, theta) = if x.abs() > y.abs() {
+        (x, PI / 4.0 * (y / x))
+    } else {
+        (y, PI / 2.0 - PI / 4.0 * (x / y))
+    };
+    (r * theta.cos(), r * theta.sin())
+}
+
+impl Shape for Disk {
+    fn intersect(&self, ray: &Ray, t_min: ScalarF, t_max: ScalarF) -> Option<HitRecord<'_>> {
+        let denominator = self.normal.dot(ray.direction);
+        if denominator.abs() < 1e-6 {
+            return None;
+        }
+
+        let t = (self.center - ray.origin).dot(self.normal) / denominator;
+        if t < t_min || t > t_max {
+            return None;
+        }
+        let p = ray.at(t);
+        if (p - self.center).magnitude2() > self.radius * self.radius {
+            return None;
+        }
+
+        let mut hit = HitRecord {
+            t,
+            p,
+            normal: self.normal,
+            front_face: true,
+            uv: None,
+            tangent: None,
+            bitangent: None,
+            vertex_color: None,
+            shape: Some(self as &dyn Shape),
+            object: None,
+            object_id: 0,
+            exterior_ior: None,
+        };
+        hit.set_face_normal(ray, self.normal);
+        Some(hit)
+    }
+
+    fn transform(&self, transform: &Matrix4D) -> Arc<dyn Shape> {
+        Arc::new(Disk {
+            center: transform_point3(*transform, self.center),
+            normal: transform_vec3(*transform, self.normal).normalize(),
+            radius: self.radius,
+        })
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        let n = self.normal;
+        let extent = Vec3D::new(
+            self.radius * (1.0 - n.x * n.x).max(0.0).sqrt() + DISK_BOUNDS_EPSILON * n.x.abs(),
+            self.radius * (1.0 - n.y * n.y).max(0.0).sqrt() + DISK_BOUNDS_EPSILON * n.y.abs(),
+            self.radius * (1.0 - n.z * n.z).max(0.0).sqrt() + DISK_BOUNDS_EPSILON * n.z.abs(),
+        );
+        Aabb::new(self.center - extent, self.center + extent)
+    }
+
+    fn area(&self) -> ScalarF {
+        PI * self.radius * self.radius
+    }
+
+    fn sample(&self, sampler: &mut dyn Sampler) -> Option<ShapeSample> {
+        let Onb { u, v, .. } = Onb::from_w(self.normal);
+        let (x, y) = concentric_sample_disk(sampler.get_2d());
+        Some(ShapeSample {
+            p: self.center + (u * x + v * y) * self.radius,
+            normal: self.normal,
+            pdf: 1.0 / self.area(),
+        })
+    }
+}
+
+impl DiskConfig {
+    pub fn to_shape(&self) -> Arc<dyn Shape> {
+        Disk {
+            center: self.center.to_point(),
+            normal: self.normal.to_vec3().normalize(),
+            radius: self.radius as ScalarF,
+        }
+        .transform(&unwrap_matrix4d_config_to_matrix4d(self.transform.as_ref()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::{point_approx_eq, tolerance, vec3_approx_eq};
+    use crate::sampler::RandomSampler;
+    use approx::assert_abs_diff_eq;
+
+    fn test_disk() -> Disk {
+        Disk {
+            center: Point3D::new(0.5, -1.0, 2.0),
+            normal: Vec3D::new(1.0, 2.0, -1.0).normalize(),
+            radius: 1.5,
+        }
+    }
+
+    #[test]
+    fn test_disk_intersect() {
+        let disk = test_disk();
+        let Onb { u, v, w: n } = Onb::from_w(disk.normal);
+
+        // straight down the normal onto the centre
+        let ray = Ray {
+            origin: disk.center + n * 2.0,
+            direction: -n,
+        };
+        let hit = disk.intersect(&ray, 1e-6, ScalarF::MAX).unwrap();
+        assert_abs_diff_eq!(hit.t, 2.0, epsilon = tolerance(1e-9));
+        assert!(point_approx_eq(hit.p, disk.center, tolerance(1e-9)));
+        assert!(vec3_approx_eq(hit.normal, n, tolerance(1e-9)));
+
+        // just inside and just outside of the rim
+        let direction = (-n * 2.0 + u * 0.3).normalize();
+        for (offset, inside) in [(disk.radius - 0.001, true), (disk.radius + 0.001, false)] {
+            let target = disk.center + (u * 0.6 + v * 0.8) * offset;
+            let ray = Ray {
+                origin: target - direction * 3.0,
+                direction,
+            };
+            assert_eq!(disk.intersect(&ray, 1e-6, ScalarF::MAX).is_some(), inside);
+        }
+    }
+
+    #[test]
+    fn test_disk_sample() {
+        let disk = test_disk();
+        let bounds = disk.bounding_box();
+        crate::rng::init_thread_local_rng(1);
+        let mut sampler = RandomSampler::new(1);
+        let mut inner = 0;
+        for _ in 0..1000 {
+            let sample = disk.sample(&mut sampler).unwrap();
+            assert_abs_diff_eq!(sample.pdf, 1.0 / disk.area(), epsilon = tolerance(1e-12));
+            assert_abs_diff_eq!(
+                (sample.p - disk.center).dot(disk.normal),
+                0.0,
+                epsilon = tolerance(1e-9)
+            );
+            assert!(bounds.union(&Aabb::new(sample.p, sample.p)) == bounds);
+            let r = (sample.p - disk.center).magnitude();
+            assert!(r <= disk.radius + tolerance(1e-9));
+            if r < disk.radius * 0.5 {
+                inner += 1;
+            }
+        }
+        // uniform in area, a quarter falls within half the radius
+        assert!((inner as ScalarF / 1000.0 - 0.25).abs() < 0.05);
+    }
+}
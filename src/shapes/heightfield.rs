@@ -0,0 +1,407 @@
+use super::super::common::HitRecord;
+use super::super::error::RaytracerError;
+use super::super::light::{build_cdf, sample_cdf};
+use super::super::math::{
+    transform_point3, transform_vec3, unwrap_matrix4d_config_to_matrix4d, Aabb, Matrix4D, Point3D,
+    Ray, ScalarF, TransformConfig, Vec3D,
+};
+use super::super::sampler::Sampler;
+use super::shape::{Shape, ShapeSample};
+use super::triangle::{triangle_area, triangle_intersect, triangle_sample};
+use cgmath::InnerSpace;
+use serde::Deserialize;
+use std::sync::Arc;
+
+// terrain over a regular grid of `width` x `height` elevation samples, the
+// grid spans x and z from `origin` and elevations rise along y. every cell
+// is split into two triangles along its diagonal
+#[derive(Debug)]
+pub struct Heightfield {
+    pub data: Vec<ScalarF>, // row major, rows run along z
+    pub width: usize,
+    pub height: usize,
+    pub origin: Point3D,
+    pub cell_size: ScalarF,
+    pub height_scale: ScalarF,
+
+    cell_cdf: Option<Vec<ScalarF>>, // by cell area, None for degenerate grids
+}
+
+#[derive(Deserialize)]
+pub struct HeightfieldConfig {
+    pub file: String, // greyscale image, black is at the origin's height
+    pub cell_size: f64,
+    pub height_scale: f64,
+    pub transform: Option<TransformConfig>,
+}
+
+impl Heightfield {
+    pub fn new(
+        data: Vec<ScalarF>,
+        width: usize,
+        height: usize,
+        origin: Point3D,
+        cell_size: ScalarF,
+        height_scale: ScalarF,
+    ) -> Self {
+        assert!(
+            width >= 2 && height >= 2,
+            "heightfield needs at least 2x2 samples"
+        );
+        assert_eq!(data.len(), width * height);
+        let mut heightfield = Self {
+            data,
+            width,
+            height,
+            origin,
+            cell_size,
+            height_scale,
+            cell_cdf: None,
+        };
+        let areas: Vec<ScalarF> = (0..(width - 1) * (height - 1))
+            .map(|cell| heightfield.cell_area(cell % (width - 1), cell / (width - 1)))
+            .collect();
+        heightfield.cell_cdf = build_cdf(&areas);
+        heightfield
+    }
+
+    // elevations from the luminance of an image, scaled to [0, 1]
+    pub fn load(path: &str, cell_size: ScalarF, height_scale: ScalarF) -> Result<Self, String> {
+        let image = image::open(path)
+            .map_err(|e| format!("Failed to load heightfield {}: {}", path, e))?
+            .to_luma16();
+        let (width, height) = (image.width() as usize, image.height() as usize);
+        if width < 2 || height < 2 {
+            return Err(format!(
+                "Heightfield {} needs at least 2x2 pixels, got {}x{}",
+                path, width, height
+            ));
+        }
+        let data = image.pixels().map(|p| p[0] as ScalarF / 65535.0).collect();
+        Ok(Self::new(
+            data,
+            width,
+            height,
+            Point3D::new(0.0, 0.0, 0.0),
+            cell_size,
+            height_scale,
+        ))
+    }
+
+    fn vertex(&self, i: usize, j: usize) -> Point3D {
+        self.origin
+            + Vec3D::new(
+                i as ScalarF * self.cell_size,
+                self.data[j * self.width + i] * self.height_scale,
+                j as ScalarF * self.cell_size,
+            )
+    }
+
+    // both triangles of cell (i, j), wound so that their normals face +y
+    fn cell_triangles(&self, i: usize, j: usize) -> [[Point3D; 3]; 2] {
+        let v00 = self.vertex(i, j);
+        let v10 = self.vertex(i + 1, j);
+        let v01 = self.vertex(i, j + 1);
+        let v11 = self.vertex(i + 1, j + 1);
+        [[v00, v11, v10], [v00, v01, v11]]
+    }
+
+    fn cell_area(&self, i: usize, j: usize) -> ScalarF {
+        self.cell_triangles(i, j)
+            .iter()
+            .map(|[v0, v1, v2]| triangle_area(*v0, *v1, *v2))
+            .sum()
+    }
+
+    fn intersect_cell(
+        &self,
+        i: usize,
+        j: usize,
+        ray: &Ray,
+        t_min: ScalarF,
+        t_max: ScalarF,
+    ) -> Option<(ScalarF, Vec3D)> {
+        let mut closest: Option<(ScalarF, Vec3D)> = None;
+        for [v0, v1, v2] in self.cell_triangles(i, j) {
+            let t_max = closest.map_or(t_max, |(t, _)| t);
+            if let Some((t, _, _)) = triangle_intersect(v0, v1, v2, ray, t_min, t_max) {
+                closest = Some((t, (v1 - v0).cross(v2 - v0).normalize()));
+            }
+        }
+        closest
+    }
+
+    // cell index containing `x` along one grid axis, clamped to the grid
+    fn cell_index(&self, x: ScalarF, cells: usize) -> usize {
+        ((x / self.cell_size).floor().max(0.0) as usize).min(cells - 1)
+    }
+}
+
+impl Shape for Heightfield {
+    fn intersect(&self, ray: &Ray, t_min: ScalarF, t_max: ScalarF) -> Option<HitRecord<'_>> {
+        let (t_enter, t_exit) = self.bounding_box().intersect(ray, t_min, t_max)?;
+
+        // walk the cells below the ray in order with a 2d dda over xz, the
+        // first cell holding a hit holds the nearest one
+        let (cells_x, cells_z) = (self.width - 1, self.height - 1);
+        let start = ray.at(t_enter) - self.origin;
+        let (mut i, mut j) = (
+            self.cell_index(start.x, cells_x),
+            self.cell_index(start.z, cells_z),
+        );
+        let axis_step = |d: ScalarF, cell: usize, o: ScalarF| -> (ScalarF, ScalarF) {
+            // distance to the next cell border along the ray, and between
+            // consecutive borders
+            if d == 0.0 {
+                return (ScalarF::INFINITY, ScalarF::INFINITY);
+            }
+            let border = if d > 0.0 { cell + 1 } else { cell };
+            let border = border as ScalarF * self.cell_size;
+            ((border - o) / d, self.cell_size / d.abs())
+        };
+        let o = ray.origin - self.origin;
+        let (mut next_x, delta_x) = axis_step(ray.direction.x, i, o.x);
+        let (mut next_z, delta_z) = axis_step(ray.direction.z, j, o.z);
+
+        loop {
+            if let Some((t, normal)) = self.intersect_cell(i, j, ray, t_min, t_max) {
+                let p = ray.at(t);
+                let local = p - self.origin;
+                let mut hit = HitRecord {
+                    t,
+                    p,
+                    normal,
+                    front_face: true,
+                    uv: Some((
+                        local.x / (cells_x as ScalarF * self.cell_size),
+                        local.z / (cells_z as ScalarF * self.cell_size),
+                    )),
+                    tangent: None,
+                    bitangent: None,
+                    vertex_color: None,
+                    shape: Some(self as &dyn Shape),
+                    object: None,
+                    object_id: 0,
+                    exterior_ior: None,
+                };
+                hit.set_face_normal(ray, normal);
+                return Some(hit);
+            }
+
+            if next_x.min(next_z) > t_exit {
+                return None;
+            }
+            if next_x < next_z {
+                if ray.direction.x > 0.0 && i + 1 < cells_x {
+                    i += 1;
+                } else if ray.direction.x < 0.0 && i > 0 {
+                    i -= 1;
+                } else {
+                    return None;
+                }
+                next_x += delta_x;
+            } else {
+                if ray.direction.z > 0.0 && j + 1 < cells_z {
+                    j += 1;
+                } else if ray.direction.z < 0.0 && j > 0 {
+                    j -= 1;
+                } else {
+                    return None;
+                }
+                next_z += delta_z;
+            }
+        }
+    }
+
+    fn transform(&self, transform: &Matrix4D) -> Arc<dyn Shape> {
+        // the grid stays axis aligned, only the origin and the scales of x
+        // and y are taken from the transform
+        let scale = transform_vec3(*transform, Vec3D::new(1.0, 0.0, 0.0)).magnitude();
+        let height_scale = transform_vec3(*transform, Vec3D::new(0.0, 1.0, 0.0)).magnitude();
+        Arc::new(Heightfield::new(
+            self.data.clone(),
+            self.width,
+            self.height,
+            transform_point3(*transform, self.origin),
+            self.cell_size * scale,
+            self.height_scale * height_scale,
+        ))
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        let (lo, hi) = self.data.iter().fold(
+            (ScalarF::INFINITY, ScalarF::NEG_INFINITY),
+            |(lo, hi), &h| (lo.min(h), hi.max(h)),
+        );
+        let (lo, hi) = (
+            (lo * self.height_scale).min(hi * self.height_scale),
+            (lo * self.height_scale).max(hi * self.height_scale),
+        );
+        let extent = Vec3D::new(
+            (self.width - 1) as ScalarF * self.cell_size,
+            0.0,
+            (self.height - 1) as ScalarF * self.cell_size,
+        );
+        Aabb::new(
+            self.origin + Vec3D::new(0.0, lo, 0.0),
+            self.origin + extent + Vec3D::new(0.0, hi, 0.0),
+        )
+    }
+
+    fn area(&self) -> ScalarF {
+        (0..self.height - 1)
+            .flat_map(|j| (0..self.width - 1).map(move |i| (i, j)))
+            .map(|(i, j)| self.cell_area(i, j))
+            .sum()
+    }
+
+    fn sample(&self, sampler: &mut dyn Sampler) -> Option<ShapeSample> {
+        let cdf = self.cell_cdf.as_ref()?;
+        let cell = sample_cdf(cdf, sampler.get_1d());
+        let (i, j) = (cell % (self.width - 1), cell / (self.width - 1));
+
+        // then one of its two triangles by area
+        let triangles = self.cell_triangles(i, j);
+        let areas = triangles.map(|[v0, v1, v2]| triangle_area(v0, v1, v2));
+        let [v0, v1, v2] = if sampler.get_1d() * (areas[0] + areas[1]) < areas[0] {
+            triangles[0]
+        } else {
+            triangles[1]
+        };
+        Some(ShapeSample {
+            p: triangle_sample(v0, v1, v2, sampler.get_2d()),
+            normal: (v1 - v0).cross(v2 - v0).normalize(),
+            pdf: 1.0 / self.area(),
+        })
+    }
+}
+
+impl HeightfieldConfig {
+    pub fn to_shape(&self) -> Result<Arc<dyn Shape>, RaytracerError> {
+        let heightfield = Heightfield::load(
+            &self.file,
+            self.cell_size as ScalarF,
+            self.height_scale as ScalarF,
+        )
+        .map_err(RaytracerError::SceneLoad)?;
+        Ok(heightfield.transform(&unwrap_matrix4d_config_to_matrix4d(self.transform.as_ref())))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::{tolerance, vec3_approx_eq};
+    use crate::sampler::RandomSampler;
+    use approx::assert_abs_diff_eq;
+
+    #[test]
+    fn test_heightfield_flat() {
+        let heightfield = Heightfield::new(
+            vec![0.0; 5 * 4],
+            5,
+            4,
+            Point3D::new(1.0, -0.5, 2.0),
+            0.5,
+            3.0,
+        );
+
+        // slanted rays from above land on the plane of the origin
+        for (x, z) in [(1.1, 2.1), (2.3, 3.4), (2.95, 2.05)] {
+            let target = Point3D::new(x, -0.5, z);
+            let direction = Vec3D::new(0.3, -1.0, -0.2).normalize();
+            let ray = Ray {
+                origin: target - direction * 4.0,
+                direction,
+            };
+            let hit = heightfield.intersect(&ray, 1e-6, ScalarF::MAX).unwrap();
+            assert_abs_diff_eq!(hit.t, 4.0, epsilon = tolerance(1e-9));
+            assert_abs_diff_eq!(hit.p.y, -0.5, epsilon = tolerance(1e-9));
+            assert!(vec3_approx_eq(
+                hit.normal,
+                Vec3D::new(0.0, 1.0, 0.0),
+                tolerance(1e-9)
+            ));
+        }
+
+        // beside the grid
+        let ray = Ray {
+            origin: Point3D::new(3.1, 1.0, 2.5),
+            direction: Vec3D::new(0.0, -1.0, 0.0),
+        };
+        assert!(heightfield.intersect(&ray, 1e-6, ScalarF::MAX).is_none());
+        assert_abs_diff_eq!(heightfield.area(), 2.0 * 1.5, epsilon = tolerance(1e-9));
+    }
+
+    #[test]
+    fn test_heightfield_slope() {
+        // elevation rises by one cell size per column, a 45 degree slope
+        let (width, height) = (8, 3);
+        let data = (0..width * height)
+            .map(|k| (k % width) as ScalarF)
+            .collect();
+        let heightfield =
+            Heightfield::new(data, width, height, Point3D::new(0.0, 0.0, 0.0), 1.0, 1.0);
+
+        // skimming low over the slope uphill crosses several cells before
+        // it meets the surface where it reaches the ray's height
+        let ray = Ray {
+            origin: Point3D::new(-1.0, 5.5, 1.2),
+            direction: Vec3D::new(1.0, 0.0, 0.1).normalize(),
+        };
+        let hit = heightfield.intersect(&ray, 1e-6, ScalarF::MAX).unwrap();
+        assert_abs_diff_eq!(hit.p.x, 5.5, epsilon = tolerance(1e-9));
+        let expected = Vec3D::new(-1.0, 1.0, 0.0).normalize();
+        assert!(vec3_approx_eq(hit.normal, expected, tolerance(1e-9)));
+
+        // over the top the ray passes by
+        let ray = Ray {
+            origin: Point3D::new(-1.0, 7.5, 1.2),
+            direction: Vec3D::new(1.0, 0.0, 0.1).normalize(),
+        };
+        assert!(heightfield.intersect(&ray, 1e-6, ScalarF::MAX).is_none());
+
+        crate::rng::init_thread_local_rng(1);
+        let mut sampler = RandomSampler::new(1);
+        for _ in 0..100 {
+            let sample = heightfield.sample(&mut sampler).unwrap();
+            assert_abs_diff_eq!(sample.p.y, sample.p.x, epsilon = tolerance(1e-9));
+            assert!(vec3_approx_eq(sample.normal, expected, tolerance(1e-9)));
+        }
+    }
+
+    #[test]
+    fn test_load_heightfield() {
+        let path = std::env::temp_dir().join("rust_ray_tracer_test_heightfield.png");
+        let image = image::GrayImage::from_fn(3, 2, |x, y| image::Luma([(x * 100 + y * 50) as u8]));
+        image.save(&path).unwrap();
+
+        let heightfield = Heightfield::load(path.to_str().unwrap(), 2.0, 10.0).unwrap();
+        assert_eq!((heightfield.width, heightfield.height), (3, 2));
+        assert_abs_diff_eq!(
+            heightfield.data[4],
+            150.0 / 255.0,
+            epsilon = tolerance(1e-9)
+        );
+        let bounds = heightfield.bounding_box();
+        assert_abs_diff_eq!(bounds.max.x, 4.0, epsilon = tolerance(1e-9));
+        assert_abs_diff_eq!(
+            bounds.max.y,
+            10.0 * 250.0 / 255.0,
+            epsilon = tolerance(1e-9)
+        );
+        assert_abs_diff_eq!(bounds.max.z, 2.0, epsilon = tolerance(1e-9));
+
+        // a missing image fails the scene instead of panicking
+        let config = HeightfieldConfig {
+            file: "missing_heightfield.png".to_string(),
+            cell_size: 1.0,
+            height_scale: 1.0,
+            transform: None,
+        };
+        assert!(matches!(
+            config.to_shape(),
+            Err(RaytracerError::SceneLoad(_))
+        ));
+    }
+}
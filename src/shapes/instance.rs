@@ -0,0 +1,228 @@
+use super::super::common::HitRecord;
+use super::super::error::RaytracerError;
+use super::super::math::{
+    transform_point3, transform_vec3, Aabb, Matrix4D, Ray, ScalarF, TransformConfig,
+};
+use super::super::sampler::Sampler;
+use super::shape::{Shape, ShapeConfig, ShapeSample};
+use cgmath::{InnerSpace, Matrix, SquareMatrix};
+use serde::Deserialize;
+use std::sync::Arc;
+
+// places a shared shape into the world without copying it, rays are moved
+// into the shape's object space instead of the shape into the world
+pub struct InstancedShape {
+    pub shape: Arc<dyn Shape>,
+    pub object_to_world: Matrix4D,
+    pub world_to_object: Matrix4D,
+}
+
+#[derive(Deserialize)]
+pub struct InstancedShapeConfig {
+    pub shape: Box<ShapeConfig>,
+    pub transform: TransformConfig,
+}
+
+impl InstancedShape {
+    pub fn new(shape: Arc<dyn Shape>, object_to_world: Matrix4D) -> Self {
+        let world_to_object = object_to_world
+            .invert()
+            .expect("instance transform is not invertible");
+        Self {
+            shape,
+            object_to_world,
+            world_to_object,
+        }
+    }
+
+    // the object space direction is renormalized, so distances along it
+    // differ from world ones by the returned scale
+    fn object_ray(&self, ray: &Ray) -> (Ray, ScalarF) {
+        let direction = transform_vec3(self.world_to_object, ray.direction);
+        let scale = direction.magnitude();
+        let object_ray = Ray {
+            origin: transform_point3(self.world_to_object, ray.origin),
+            direction: direction / scale,
+        };
+        (object_ray, scale)
+    }
+
+    // surfaces scale by the square of the linear scale, exact for uniform
+    // scaling and an approximation otherwise
+    fn area_scale(&self) -> ScalarF {
+        self.object_to_world.determinant().abs().powf(2.0 / 3.0)
+    }
+}
+
+impl Shape for InstancedShape {
+    fn intersect(&self, ray: &Ray, t_min: ScalarF, t_max: ScalarF) -> Option<HitRecord<'_>> {
+        let (object_ray, scale) = self.object_ray(ray);
+        let hit = self
+            .shape
+            .intersect(&object_ray, t_min * scale, t_max * scale)?;
+
+        // normals go through the inverse transpose to stay perpendicular
+        let normal_matrix = self.world_to_object.transpose();
+        let t = hit.t / scale;
+        Some(HitRecord {
+            t,
+            p: ray.at(t),
+            normal: transform_vec3(normal_matrix, hit.normal).normalize(),
+            // the inverse transpose keeps the sign of the normal against the ray
+            front_face: hit.front_face,
+            uv: hit.uv,
+            tangent: hit
+                .tangent
+                .map(|tangent| transform_vec3(self.object_to_world, tangent).normalize()),
+            bitangent: hit
+                .bitangent
+                .map(|bitangent| transform_vec3(self.object_to_world, bitangent).normalize()),
+            vertex_color: hit.vertex_color,
+            shape: hit.shape,
+            object: None,
+            object_id: 0,
+            exterior_ior: None,
+        })
+    }
+
+    fn transform(&self, transform: &Matrix4D) -> Arc<dyn Shape> {
+        Arc::new(InstancedShape::new(
+            self.shape.clone(),
+            transform * self.object_to_world,
+        ))
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        let bounds = self.shape.bounding_box();
+        if !bounds.is_finite() {
+            return Aabb::infinite();
+        }
+        let corners = bounds
+            .corners()
+            .map(|corner| transform_point3(self.object_to_world, corner));
+        Aabb::from_points(&corners)
+    }
+
+    fn area(&self) -> ScalarF {
+        self.shape.area() * self.area_scale()
+    }
+
+    fn sample(&self, sampler: &mut dyn Sampler) -> Option<ShapeSample> {
+        let sample = self.shape.sample(sampler)?;
+        let normal_matrix = self.world_to_object.transpose();
+        Some(ShapeSample {
+            p: transform_point3(self.object_to_world, sample.p),
+            normal: transform_vec3(normal_matrix, sample.normal).normalize(),
+            pdf: sample.pdf / self.area_scale(),
+        })
+    }
+
+    fn supports_intersect_all(&self) -> bool {
+        self.shape.supports_intersect_all()
+    }
+
+    fn intersect_all(&self, ray: &Ray) -> Vec<(ScalarF, ScalarF)> {
+        let (object_ray, scale) = self.object_ray(ray);
+        self.shape
+            .intersect_all(&object_ray)
+            .into_iter()
+            .map(|(t0, t1)| (t0 / scale, t1 / scale))
+            .collect()
+    }
+}
+
+impl InstancedShapeConfig {
+    pub fn to_shape(&self) -> Result<Arc<dyn Shape>, RaytracerError> {
+        let transform = self.transform.to_matrix();
+        if transform.invert().is_none() {
+            return Err(RaytracerError::SceneLoad(
+                "instance transform is not invertible".to_string(),
+            ));
+        }
+        Ok(Arc::new(InstancedShape::new(
+            self.shape.to_shape()?,
+            transform,
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::{point_approx_eq, tolerance, vec3_approx_eq, Point3D, Vec3D};
+    use crate::sampler::RandomSampler;
+    use crate::shapes::Sphere;
+    use approx::assert_abs_diff_eq;
+    use cgmath::{Matrix4, Rad};
+
+    #[test]
+    fn test_instanced_shape_intersect() {
+        let sphere: Arc<dyn Shape> = Arc::new(Sphere {
+            center: Point3D::new(0.0, 0.0, 0.0),
+            radius: 1.0,
+        });
+        let instance = InstancedShape::new(
+            sphere.clone(),
+            Matrix4::from_translation(Vec3D::new(5.0, 0.0, 0.0)),
+        );
+
+        let ray = Ray {
+            origin: Point3D::new(0.0, 0.0, 0.0),
+            direction: Vec3D::new(1.0, 0.0, 0.0),
+        };
+        let hit = instance.intersect(&ray, 1e-6, ScalarF::MAX).unwrap();
+        assert_abs_diff_eq!(hit.t, 4.0, epsilon = tolerance(1e-9));
+        assert!(point_approx_eq(
+            hit.p,
+            Point3D::new(4.0, 0.0, 0.0),
+            tolerance(1e-9)
+        ));
+        assert!(vec3_approx_eq(
+            hit.normal,
+            Vec3D::new(-1.0, 0.0, 0.0),
+            tolerance(1e-9)
+        ));
+        assert!(instance.intersect(&ray, 1e-6, 3.9).is_none());
+
+        // squashed along y, normals follow the inverse transpose
+        let instance = InstancedShape::new(
+            sphere,
+            Matrix4::from_translation(Vec3D::new(5.0, 0.0, 0.0))
+                * Matrix4::from_angle_z(Rad(0.3))
+                * Matrix4::from_nonuniform_scale(2.0, 0.5, 1.0),
+        );
+        let bounds = instance.bounding_box();
+        crate::rng::init_thread_local_rng(1);
+        let mut sampler = RandomSampler::new(1);
+        for _ in 0..100 {
+            let sample = instance.sample(&mut sampler).unwrap();
+            assert!(bounds.union(&Aabb::new(sample.p, sample.p)) == bounds);
+            let ray = Ray {
+                origin: sample.p + sample.normal * 0.1,
+                direction: -sample.normal,
+            };
+            let hit = instance.intersect(&ray, 1e-6, ScalarF::MAX).unwrap();
+            assert_abs_diff_eq!(hit.t, 0.1, epsilon = tolerance(1e-6));
+            assert!(vec3_approx_eq(hit.normal, sample.normal, tolerance(1e-6)));
+        }
+    }
+
+    #[test]
+    fn test_instanced_shape_config_singular() {
+        let config = |scale: f64| {
+            toml::from_str::<InstancedShapeConfig>(&format!(
+                r#"
+                shape = {{ type = "Sphere", center = {{ x = 0.0, y = 0.0, z = 0.0 }}, radius = 1.0 }}
+                transform = [{{ type = "Scale", x = 1.0, y = {:?}, z = 1.0 }}]
+                "#,
+                scale
+            ))
+            .unwrap()
+        };
+        assert!(config(2.0).to_shape().is_ok());
+        assert!(matches!(
+            config(0.0).to_shape(),
+            Err(RaytracerError::SceneLoad(_))
+        ));
+    }
+}
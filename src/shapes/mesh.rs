@@ -1,13 +1,17 @@
 use super::super::common::HitRecord;
+use super::super::distribution::Distribution1D;
+use super::super::error::RaytracerError;
+use super::super::material::MaterialConfig;
 use super::super::math::{
-    transform_point3, transform_vec3, unwrap_matrix4d_config_to_matrix4d, Matrix4D, Matrix4DConfig,
-    Point3D, Ray, Vec3D,
+    transform_point3, transform_vec3, unwrap_matrix4d_config_to_matrix4d, Aabb, Matrix4D, Point2D,
+    Point3D, Ray, ScalarF, TransformConfig, Vec3D,
 };
+use super::super::sampler::Sampler;
 use super::quadrilateral::quadrilateral_intersect;
-use super::shape::Shape;
-use super::triangle::triangle_intersect;
-use super::utils::load_mesh;
-use cgmath::InnerSpace;
+use super::shape::{Shape, ShapeSample};
+use super::triangle::{triangle_area, triangle_intersect, triangle_sample};
+use super::utils::{load_mesh, load_mesh_parts};
+use cgmath::{InnerSpace, Zero};
 use serde::Deserialize;
 use std::sync::Arc;
 
@@ -16,24 +20,112 @@ pub struct Mesh {
     pub vertices: Vec<Point3D>,
     pub normals: Vec<Vec3D>,
     pub indices: Vec<Vec<usize>>,
+    pub vertex_tex_coords: Option<Vec<Point2D>>, // one per vertex if present
+    pub vertex_colors: Option<Vec<Vec3D>>,       // same
 }
 
+// a mesh shape with the material its file gives it
+pub type MeshPart = (Arc<dyn Shape>, Option<MaterialConfig>);
+
 #[derive(Deserialize)]
 pub struct MeshConfig {
     file: String,
-    transform: Option<Matrix4DConfig>,
+    transform: Option<TransformConfig>,
+    #[serde(default = "default_use_vertex_normals", alias = "use_smooth_normals")]
+    use_vertex_normals: bool,
+}
+
+fn default_use_vertex_normals() -> bool {
+    true
+}
+
+impl Mesh {
+    pub fn transformed(&self, transform: &Matrix4D) -> Mesh {
+        Mesh {
+            vertices: self
+                .vertices
+                .iter()
+                .map(|v| transform_point3(*transform, *v))
+                .collect(),
+            normals: self
+                .normals
+                .iter()
+                .map(|n| transform_vec3(*transform, *n).normalize())
+                .collect(),
+            indices: self.indices.clone(),
+            vertex_tex_coords: self.vertex_tex_coords.clone(),
+            vertex_colors: self.vertex_colors.clone(),
+        }
+    }
+
+    fn face_area(&self, indices: &[usize]) -> ScalarF {
+        let v = |i: usize| self.vertices[indices[i]];
+        match indices.len() {
+            3 => triangle_area(v(0), v(1), v(2)),
+            4 => triangle_area(v(0), v(1), v(2)) + triangle_area(v(0), v(2), v(3)),
+            _ => panic!("Mesh with non-triangle or non-quadrilateral face is not supported"),
+        }
+    }
+
+    fn face_normal(&self, indices: &[usize]) -> Vec3D {
+        let v = |i: usize| self.vertices[indices[i]];
+        (v(1) - v(0)).cross(v(2) - v(0)).normalize()
+    }
+
+    // vertex normals blended with the barycentric `weights`, none for meshes
+    // without vertex normals
+    fn interpolated_normal(&self, indices: &[usize], weights: &[ScalarF; 4]) -> Option<Vec3D> {
+        if self.normals.len() != self.vertices.len() {
+            return None;
+        }
+        let n = indices
+            .iter()
+            .zip(weights)
+            .fold(Vec3D::zero(), |n, (&i, &w)| n + self.normals[i] * w);
+        if n.magnitude2() < 1e-12 {
+            return None;
+        }
+        Some(n.normalize())
+    }
+
+    fn interpolated_uv(
+        &self,
+        indices: &[usize],
+        weights: &[ScalarF; 4],
+    ) -> Option<(ScalarF, ScalarF)> {
+        let tex_coords = self.vertex_tex_coords.as_ref()?;
+        Some(
+            indices
+                .iter()
+                .zip(weights)
+                .fold((0.0, 0.0), |(u, v), (&i, &w)| {
+                    (u + tex_coords[i].x * w, v + tex_coords[i].y * w)
+                }),
+        )
+    }
+
+    fn interpolated_color(&self, indices: &[usize], weights: &[ScalarF; 4]) -> Option<Vec3D> {
+        let colors = self.vertex_colors.as_ref()?;
+        Some(
+            indices
+                .iter()
+                .zip(weights)
+                .fold(Vec3D::zero(), |c, (&i, &w)| c + colors[i] * w),
+        )
+    }
 }
 
 impl Shape for Mesh {
-    fn intersect(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+    fn intersect(&self, ray: &Ray, t_min: ScalarF, t_max: ScalarF) -> Option<HitRecord> {
         let mut hit_record: Option<HitRecord> = None;
         let mut closest_so_far = t_max;
 
         for indices in &self.indices {
-            let (t, p, normal) = match indices.len() {
+            // barycentric weights of the face's vertices at the hit point
+            let (t, weights) = match indices.len() {
                 3 => {
                     // triangle
-                    let (t, _u, _v) = match triangle_intersect(
+                    let (t, u, v) = match triangle_intersect(
                         self.vertices[indices[0]],
                         self.vertices[indices[1]],
                         self.vertices[indices[2]],
@@ -44,16 +136,11 @@ impl Shape for Mesh {
                         Some((t, u, v)) => (t, u, v),
                         None => continue,
                     };
-
-                    let p = ray.at(t);
-                    let normal = (self.vertices[indices[1]] - self.vertices[indices[0]])
-                        .cross(self.vertices[indices[2]] - self.vertices[indices[0]])
-                        .normalize();
-                    (t, p, normal)
+                    (t, [1.0 - u - v, u, v, 0.0])
                 }
                 4 => {
                     // quadrilateral
-                    let (t, _u, _v, _w) = match quadrilateral_intersect(
+                    let (t, u, v, w) = match quadrilateral_intersect(
                         self.vertices[indices[0]],
                         self.vertices[indices[1]],
                         self.vertices[indices[2]],
@@ -65,51 +152,240 @@ impl Shape for Mesh {
                         Some((t, u, v, w)) => (t, u, v, w),
                         None => continue,
                     };
-
-                    let p = ray.at(t);
-                    let normal = (self.vertices[indices[1]] - self.vertices[indices[0]])
-                        .cross(self.vertices[indices[2]] - self.vertices[indices[0]])
-                        .normalize();
-                    (t, p, normal)
+                    (t, [1.0 - u - v - w, u, v, w])
                 }
                 _ => panic!("Mesh with non-triangle or non-quadrilateral face is not supported"),
             };
 
+            let p = ray.at(t);
+            let face_normal = self.face_normal(indices);
+            let normal = match self.interpolated_normal(indices, &weights) {
+                // keep the winding of the face
+                Some(n) if n.dot(face_normal) < 0.0 => -n,
+                Some(n) => n,
+                None => face_normal,
+            };
+
             closest_so_far = t;
-            hit_record = Some(HitRecord {
+            let mut hit = HitRecord {
                 t: t,
                 p: p,
                 normal: normal,
+                front_face: true,
+                uv: self.interpolated_uv(indices, &weights),
+                tangent: None,
+                bitangent: None,
+                vertex_color: self.interpolated_color(indices, &weights),
                 shape: Some(self as &dyn Shape),
                 object: None,
-            });
+                object_id: 0,
+                exterior_ior: None,
+            };
+            // the face tells the sides apart, the shading normal follows it
+            hit.set_face_normal(ray, face_normal);
+            hit.normal = if hit.front_face { normal } else { -normal };
+            hit_record = Some(hit);
         }
 
         hit_record
     }
 
     fn transform(&self, transform: &Matrix4D) -> Arc<dyn Shape> {
-        let mesh = Mesh {
-            vertices: self
-                .vertices
-                .iter()
-                .map(|v| transform_point3(*transform, *v))
-                .collect(),
-            normals: self
-                .normals
-                .iter()
-                .map(|n| transform_vec3(*transform, *n).normalize())
-                .collect(),
-            indices: self.indices.clone(),
+        Arc::new(self.transformed(transform))
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        Aabb::from_points(&self.vertices)
+    }
+
+    fn area(&self) -> ScalarF {
+        self.indices
+            .iter()
+            .map(|indices| self.face_area(indices))
+            .sum()
+    }
+
+    fn sample(&self, sampler: &mut dyn Sampler) -> Option<ShapeSample> {
+        // pick a face proportionally to its area, then a point on the face
+        let areas: Vec<ScalarF> = self
+            .indices
+            .iter()
+            .map(|indices| self.face_area(indices))
+            .collect();
+        let area: ScalarF = areas.iter().sum();
+        if area <= 0.0 {
+            return None;
+        }
+        let (face, _, u) = Distribution1D::new(&areas).sample_discrete(sampler.get_1d());
+        let indices = &self.indices[face];
+
+        // the remapped u picks the half of a quad
+        let v = |i: usize| self.vertices[indices[i]];
+        let p = if indices.len() == 4 && u * areas[face] >= triangle_area(v(0), v(1), v(2)) {
+            triangle_sample(v(0), v(2), v(3), sampler.get_2d())
+        } else {
+            triangle_sample(v(0), v(1), v(2), sampler.get_2d())
         };
-        Arc::new(mesh)
+        Some(ShapeSample {
+            p,
+            normal: self.face_normal(indices),
+            pdf: 1.0 / area,
+        })
     }
 }
 
 impl MeshConfig {
-    pub fn to_shape(&self) -> Arc<dyn Shape> {
-        load_mesh(&self.file)
+    fn prepare(&self, mut mesh: Mesh) -> Arc<dyn Shape> {
+        // without vertex normals the flat face normal is used
+        if !self.use_vertex_normals {
+            mesh.normals.clear();
+        }
+        mesh.transform(&unwrap_matrix4d_config_to_matrix4d(self.transform.as_ref()))
+    }
+
+    pub fn to_shape(&self) -> Result<Arc<dyn Shape>, RaytracerError> {
+        Ok(self.prepare(load_mesh(&self.file)?))
+    }
+
+    // one shape per material of the file, see `load_mesh_parts`
+    pub fn to_parts(&self) -> Result<Vec<MeshPart>, RaytracerError> {
+        Ok(load_mesh_parts(&self.file)?
+            .into_iter()
+            .map(|(mesh, material)| (self.prepare(mesh), material))
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::consts::PI;
+    use crate::math::tolerance;
+
+    // uv sphere of radius 1 with 4 stacks and 6 slices, vertex normals point
+    // away from the centre. returns the vertices of one triangle of the band
+    // above the equator
+    fn write_low_poly_sphere_ply(path: &std::path::Path) -> [Point3D; 3] {
+        let (stacks, slices) = (4, 6);
+        let vertex = |i: usize, j: usize| {
+            let theta = PI * i as ScalarF / stacks as ScalarF;
+            let phi = 2.0 * PI * (j % slices) as ScalarF / slices as ScalarF;
+            Point3D::new(
+                theta.sin() * phi.cos(),
+                theta.sin() * phi.sin(),
+                theta.cos(),
+            )
+        };
+        let mut vertices = Vec::new();
+        for i in 0..=stacks {
+            for j in 0..slices {
+                vertices.push(vertex(i, j));
+            }
+        }
+        let index = |i: usize, j: usize| i * slices + j % slices;
+        let mut faces = Vec::new();
+        for i in 0..stacks {
+            for j in 0..slices {
+                faces.push([index(i, j), index(i + 1, j), index(i + 1, j + 1)]);
+                faces.push([index(i, j), index(i + 1, j + 1), index(i, j + 1)]);
+            }
+        }
+
+        let mut ply = format!(
+            "ply\nformat ascii 1.0\nelement vertex {}\n\
+             property float x\nproperty float y\nproperty float z\n\
+             property float nx\nproperty float ny\nproperty float nz\n\
+             element face {}\nproperty list uint uint vertex_indices\nend_header\n",
+            vertices.len(),
+            faces.len()
+        );
+        for v in &vertices {
+            ply += &format!("{} {} {} {} {} {}\n", v.x, v.y, v.z, v.x, v.y, v.z);
+        }
+        for [a, b, c] in &faces {
+            ply += &format!("3 {} {} {}\n", a, b, c);
+        }
+        std::fs::write(path, ply).unwrap();
+        [vertex(1, 0), vertex(2, 0), vertex(2, 1)]
+    }
+
+    #[test]
+    fn test_mesh_vertex_colors() {
+        let mesh = Mesh {
+            vertices: vec![
+                Point3D::new(0.0, 0.0, 0.0),
+                Point3D::new(1.0, 0.0, 0.0),
+                Point3D::new(0.0, 1.0, 0.0),
+            ],
+            normals: Vec::new(),
+            indices: vec![vec![0, 1, 2]],
+            vertex_tex_coords: None,
+            vertex_colors: Some(vec![
+                Vec3D::new(1.0, 0.0, 0.0),
+                Vec3D::new(0.0, 1.0, 0.0),
+                Vec3D::new(0.0, 0.0, 1.0),
+            ]),
+        };
+        let ray = Ray {
+            origin: Point3D::new(1.0 / 3.0, 1.0 / 3.0, 1.0),
+            direction: Vec3D::new(0.0, 0.0, -1.0),
+        };
+        let color = mesh
+            .intersect(&ray, 1e-6, ScalarF::MAX)
+            .unwrap()
+            .vertex_color;
+        assert!((color.unwrap() - Vec3D::new(1.0, 1.0, 1.0) / 3.0).magnitude() < tolerance(1e-9));
+
+        // and closer to the red vertex it turns red
+        let ray = Ray {
+            origin: Point3D::new(0.1, 0.1, 1.0),
+            ..ray
+        };
+        let color = mesh
+            .intersect(&ray, 1e-6, ScalarF::MAX)
             .unwrap()
-            .transform(&unwrap_matrix4d_config_to_matrix4d(self.transform.as_ref()))
+            .vertex_color;
+        assert!((color.unwrap() - Vec3D::new(0.8, 0.1, 0.1)).magnitude() < tolerance(1e-9));
+    }
+
+    #[test]
+    fn test_mesh_vertex_normals() {
+        let path = std::env::temp_dir().join("rust_ray_tracer_test_low_poly_sphere.ply");
+        let [a, b, c] = write_low_poly_sphere_ply(&path);
+        let config = |extra: &str| -> MeshConfig {
+            toml::from_str(&format!("file = \"{}\"\n{}", path.to_str().unwrap(), extra)).unwrap()
+        };
+
+        // straight at the centre of the triangle
+        let centre = Point3D::new(
+            (a.x + b.x + c.x) / 3.0,
+            (a.y + b.y + c.y) / 3.0,
+            (a.z + b.z + c.z) / 3.0,
+        );
+        let face_normal = (b - a).cross(c - a).normalize();
+        let face_normal = if face_normal.dot(centre - Point3D::new(0.0, 0.0, 0.0)) < 0.0 {
+            -face_normal
+        } else {
+            face_normal
+        };
+        let ray = Ray {
+            origin: centre + face_normal,
+            direction: -face_normal,
+        };
+
+        let smooth = config("").to_shape().unwrap();
+        let hit = smooth.intersect(&ray, 1e-6, ScalarF::MAX).unwrap();
+        assert!((hit.normal.magnitude() - 1.0).abs() < tolerance(1e-9));
+        // the vertex normals average to the direction of the centroid
+        let expected = (centre - Point3D::new(0.0, 0.0, 0.0)).normalize();
+        assert!((hit.normal - expected).magnitude() < tolerance(1e-6));
+        assert!((hit.normal - face_normal).magnitude() > 1e-3);
+
+        let flat = config("use_vertex_normals = false").to_shape().unwrap();
+        let hit = flat.intersect(&ray, 1e-6, ScalarF::MAX).unwrap();
+        assert!((hit.normal.dot(face_normal).abs() - 1.0).abs() < tolerance(1e-9));
+        assert!(!config("use_smooth_normals = false").use_vertex_normals);
+
+        std::fs::remove_file(&path).unwrap();
     }
 }
@@ -1,9 +1,29 @@
+mod aabb_shape;
+mod cone;
+mod csg;
+mod cylinder;
+mod disk;
+mod heightfield;
+mod instance;
 mod mesh;
 mod plane;
 mod quadrilateral;
 mod shape;
 mod sphere;
+mod subdivision;
+mod torus;
 mod triangle;
 mod utils;
 
+#[cfg(test)]
+pub use aabb_shape::AabbShape;
+#[cfg(test)]
+pub use cylinder::Cylinder;
+pub use disk::concentric_sample_disk;
 pub use shape::{Shape, ShapeConfig};
+#[cfg(test)]
+pub use sphere::Sphere;
+#[cfg(test)]
+pub use triangle::Triangle;
+#[cfg(test)]
+pub use utils::load_mesh;
@@ -1,9 +1,10 @@
 use super::super::common::HitRecord;
 use super::super::math::{
-    transform_point3, transform_vec3, unwrap_matrix4d_config_to_matrix4d, Matrix4D, Matrix4DConfig,
-    Point3D, Point3DConfig, Ray, Vec3D, Vec3DConfig,
+    transform_point3, transform_vec3, unwrap_matrix4d_config_to_matrix4d, Aabb, Matrix4D, Point3D,
+    Point3DConfig, Ray, ScalarF, TransformConfig, Vec3D, Vec3DConfig,
 };
-use super::shape::Shape;
+use super::super::sampler::Sampler;
+use super::shape::{Shape, ShapeSample};
 use cgmath::InnerSpace;
 use serde::Deserialize;
 use std::sync::Arc;
@@ -18,11 +19,11 @@ pub struct Plane {
 pub struct PlaneConfig {
     pub point: Point3DConfig,
     pub normal: Vec3DConfig,
-    pub transform: Option<Matrix4DConfig>,
+    pub transform: Option<TransformConfig>,
 }
 
 impl Shape for Plane {
-    fn intersect(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+    fn intersect(&self, ray: &Ray, t_min: ScalarF, t_max: ScalarF) -> Option<HitRecord> {
         let denominator = self.normal.dot(ray.direction);
         if denominator.abs() < 1e-6 {
             return None;
@@ -34,13 +35,22 @@ impl Shape for Plane {
             return None;
         }
 
-        Some(HitRecord {
+        let mut hit = HitRecord {
             t: distance,
             p: ray.at(distance),
             normal: self.normal,
+            front_face: true,
+            uv: None,
+            tangent: None,
+            bitangent: None,
+            vertex_color: None,
             shape: Some(self as &dyn Shape),
             object: None,
-        })
+            object_id: 0,
+            exterior_ior: None,
+        };
+        hit.set_face_normal(ray, self.normal);
+        Some(hit)
     }
 
     fn transform(&self, transform: &Matrix4D) -> Arc<dyn Shape> {
@@ -49,6 +59,18 @@ impl Shape for Plane {
             normal: transform_vec3(*transform, self.normal).normalize(),
         })
     }
+
+    fn bounding_box(&self) -> Aabb {
+        Aabb::infinite()
+    }
+
+    fn area(&self) -> ScalarF {
+        ScalarF::INFINITY
+    }
+
+    fn sample(&self, _: &mut dyn Sampler) -> Option<ShapeSample> {
+        None
+    }
 }
 
 impl PlaneConfig {
@@ -64,7 +86,7 @@ impl PlaneConfig {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::math::vec3_approx_eq;
+    use crate::math::{tolerance, vec3_approx_eq};
     use approx::assert_abs_diff_eq;
     use rand::Rng;
 
@@ -116,8 +138,12 @@ mod tests {
                 assert!(hit.is_none());
             } else {
                 let hit = hit.unwrap();
-                assert_abs_diff_eq!((hit.p - plane.point).dot(normal), 0.0, epsilon = 1e-6);
-                vec3_approx_eq(hit.normal, normal, 1e-6);
+                assert_abs_diff_eq!(
+                    (hit.p - plane.point).dot(normal),
+                    0.0,
+                    epsilon = tolerance(1e-6)
+                );
+                vec3_approx_eq(hit.normal, normal, tolerance(1e-6));
             }
         }
     }
@@ -1,9 +1,11 @@
 use super::super::common::HitRecord;
 use super::super::math::{
-    transform_point3, unwrap_matrix4d_config_to_matrix4d, Matrix4D, Matrix4DConfig, Point3D,
-    Point3DConfig, Ray,
+    transform_point3, unwrap_matrix4d_config_to_matrix4d, Aabb, Matrix4D, Point3D, Point3DConfig,
+    Ray, ScalarF, TransformConfig,
 };
-use super::shape::Shape;
+use super::super::sampler::Sampler;
+use super::shape::{Shape, ShapeSample};
+use super::triangle::{triangle_area, triangle_sample};
 use cgmath::InnerSpace;
 use log::debug;
 use serde::Deserialize;
@@ -17,7 +19,7 @@ pub struct Quadrilateral {
 #[derive(Deserialize)]
 pub struct QuadrilateralConfig {
     pub vertices: [Point3DConfig; 4],
-    pub transform: Option<Matrix4DConfig>,
+    pub transform: Option<TransformConfig>,
 }
 
 pub fn are_points_coplanar(v0: Point3D, v1: Point3D, v2: Point3D, v3: Point3D) -> bool {
@@ -59,9 +61,9 @@ pub fn quadrilateral_intersect(
     v2: Point3D,
     v3: Point3D,
     ray: &Ray,
-    t_min: f64,
-    t_max: f64,
-) -> Option<(f64, f64, f64, f64)> {
+    t_min: ScalarF,
+    t_max: ScalarF,
+) -> Option<(ScalarF, ScalarF, ScalarF, ScalarF)> {
     // reject rays using the barycentric coordinates of
     // the intersection point with respect to t
     let e01 = v1 - v0;
@@ -119,8 +121,8 @@ pub fn quadrilateral_intersect(
     // compute the barycentric coordinates of v2
     let e02 = v2 - v0;
     let n = e01.cross(e03);
-    let mut alpha11: f64 = 0.0;
-    let mut beta11: f64 = 0.0;
+    let mut alpha11: ScalarF = 0.0;
+    let mut beta11: ScalarF = 0.0;
     if n.x.abs() >= n.y.abs() && n.x.abs() >= n.z.abs() {
         alpha11 = (e02.y * e03.z - e02.z * e03.y) / n.x;
         beta11 = (e01.y * e02.z - e01.z * e02.y) / n.x;
@@ -133,8 +135,8 @@ pub fn quadrilateral_intersect(
     }
 
     // compute the bilinear coordinates of the intersection point
-    let mut u: f64;
-    let v: f64;
+    let mut u: ScalarF;
+    let v: ScalarF;
     if (alpha11 - 1.0).abs() < 1e-6 {
         u = alpha;
         if (beta11 - 1.0).abs() < 1e-6 {
@@ -162,8 +164,8 @@ pub fn quadrilateral_intersect(
 }
 
 impl Shape for Quadrilateral {
-    fn intersect(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
-        let (t, _u, _v, _w) = match quadrilateral_intersect(
+    fn intersect(&self, ray: &Ray, t_min: ScalarF, t_max: ScalarF) -> Option<HitRecord> {
+        let (t, w1, w2, w3) = match quadrilateral_intersect(
             self.vertices[0],
             self.vertices[1],
             self.vertices[2],
@@ -180,13 +182,23 @@ impl Shape for Quadrilateral {
         let normal = (self.vertices[1] - self.vertices[0])
             .cross(self.vertices[2] - self.vertices[0])
             .normalize();
-        return Some(HitRecord {
+        let mut hit = HitRecord {
             t: t,
             p: p,
             normal: normal,
+            front_face: true,
+            // bilinear parameters, the vertices sit at (0, 0), (1, 0), (1, 1) and (0, 1)
+            uv: Some((w1 + w2, w2 + w3)),
+            tangent: None,
+            bitangent: None,
+            vertex_color: None,
             shape: Some(self as &dyn Shape),
             object: None,
-        });
+            object_id: 0,
+            exterior_ior: None,
+        };
+        hit.set_face_normal(ray, normal);
+        Some(hit)
     }
 
     fn transform(&self, transform: &Matrix4D) -> Arc<dyn Shape> {
@@ -199,6 +211,31 @@ impl Shape for Quadrilateral {
             ],
         })
     }
+
+    fn bounding_box(&self) -> Aabb {
+        Aabb::from_points(&self.vertices)
+    }
+
+    fn area(&self) -> ScalarF {
+        let [v0, v1, v2, v3] = self.vertices;
+        triangle_area(v0, v1, v2) + triangle_area(v0, v2, v3)
+    }
+
+    fn sample(&self, sampler: &mut dyn Sampler) -> Option<ShapeSample> {
+        // the quadrilateral is convex, so it splits into two triangles
+        let [v0, v1, v2, v3] = self.vertices;
+        let area = self.area();
+        let p = if sampler.get_1d() * area < triangle_area(v0, v1, v2) {
+            triangle_sample(v0, v1, v2, sampler.get_2d())
+        } else {
+            triangle_sample(v0, v2, v3, sampler.get_2d())
+        };
+        Some(ShapeSample {
+            p,
+            normal: (v1 - v0).cross(v2 - v0).normalize(),
+            pdf: 1.0 / area,
+        })
+    }
 }
 
 impl QuadrilateralConfig {
@@ -218,6 +255,7 @@ impl QuadrilateralConfig {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::math::tolerance;
     use crate::shapes::triangle::triangle_intersect;
     use approx::assert_abs_diff_eq;
     use rand::Rng;
@@ -254,11 +292,11 @@ mod tests {
             let v2 = Point3D::new(1.0, -1.0, 0.0);
             if rng.gen::<f32>() < 0.5 {
                 // convex
-                let v3 = Point3D::new(rng.gen::<f64>(), rng.gen::<f64>(), 0.0);
+                let v3 = Point3D::new(rng.gen::<ScalarF>(), rng.gen::<ScalarF>(), 0.0);
                 assert!(are_points_coplanar(v0, v1, v2, v3));
                 assert!(is_quadrilateral_convex(v0, v1, v2, v3));
             } else {
-                let v3 = Point3D::new(-rng.gen::<f64>(), -rng.gen::<f64>(), 0.0);
+                let v3 = Point3D::new(-rng.gen::<ScalarF>(), -rng.gen::<ScalarF>(), 0.0);
                 assert!(are_points_coplanar(v0, v1, v2, v3));
                 assert!(!is_quadrilateral_convex(v0, v1, v2, v3));
             }
@@ -319,9 +357,36 @@ mod tests {
                 assert_abs_diff_eq!(
                     (hit_point_quadrilateral - hit_point_triangle).magnitude(),
                     0.0,
-                    epsilon = 1e-6
+                    epsilon = tolerance(1e-6)
                 );
             }
         }
     }
+
+    #[test]
+    fn test_quadrilateral_uv() {
+        // a trapezoid, v runs along the slanted sides
+        let quad = Quadrilateral {
+            vertices: [
+                Point3D::new(0.0, 0.0, 0.0),
+                Point3D::new(4.0, 0.0, 0.0),
+                Point3D::new(3.0, 2.0, 0.0),
+                Point3D::new(1.0, 2.0, 0.0),
+            ],
+        };
+        for (target, expected) in [
+            (Point3D::new(0.0, 0.0, 0.0), (0.0, 0.0)),
+            (Point3D::new(3.0, 2.0, 0.0), (1.0, 1.0)),
+            (Point3D::new(2.0, 1.0, 0.0), (0.5, 0.5)),
+            (Point3D::new(1.25, 1.0, 0.0), (0.25, 0.5)),
+        ] {
+            let ray = Ray {
+                origin: target + crate::math::Vec3D::new(0.0, 0.0, 1.0),
+                direction: crate::math::Vec3D::new(0.0, 0.0, -1.0),
+            };
+            let (u, v) = quad.intersect(&ray, 0.0, ScalarF::MAX).unwrap().uv.unwrap();
+            assert_abs_diff_eq!(u, expected.0, epsilon = tolerance(1e-9));
+            assert_abs_diff_eq!(v, expected.1, epsilon = tolerance(1e-9));
+        }
+    }
 }
@@ -1,16 +1,66 @@
 use super::super::common::HitRecord;
-use super::super::math::{Matrix4D, Ray};
+use super::super::error::RaytracerError;
+use super::super::math::{Aabb, Matrix4D, Point3D, Ray, ScalarF, Vec3D, CROSSING_EPSILON};
+use super::super::sampler::Sampler;
+use super::aabb_shape::AabbShapeConfig;
+use super::cone::ConeConfig;
+use super::csg::CsgShapeConfig;
+use super::cylinder::CylinderConfig;
+use super::disk::DiskConfig;
+use super::heightfield::HeightfieldConfig;
+use super::instance::InstancedShapeConfig;
 use super::mesh::MeshConfig;
 use super::plane::PlaneConfig;
 use super::quadrilateral::QuadrilateralConfig;
 use super::sphere::SphereConfig;
+use super::subdivision::SubdivisionSurfaceConfig;
+use super::torus::TorusConfig;
 use super::triangle::TriangleConfig;
 use serde::Deserialize;
 use std::sync::Arc;
 
+// point sampled on the surface of a shape, pdf is with respect to area
+pub struct ShapeSample {
+    pub p: Point3D,
+    pub normal: Vec3D,
+    pub pdf: ScalarF,
+}
+
 pub trait Shape: Send + Sync {
-    fn intersect(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord>;
+    fn intersect(&self, ray: &Ray, t_min: ScalarF, t_max: ScalarF) -> Option<HitRecord>;
     fn transform(&self, transform: &Matrix4D) -> Arc<dyn Shape>;
+    fn bounding_box(&self) -> Aabb;
+    fn area(&self) -> ScalarF;
+    // samples uniformly by area, None for shapes that can't be sampled
+    fn sample(&self, sampler: &mut dyn Sampler) -> Option<ShapeSample>;
+    // ascending parametric ranges along the whole line of `ray` that lie
+    // inside the shape, only closed shapes have an inside
+    fn intersect_all(&self, _ray: &Ray) -> Vec<(ScalarF, ScalarF)> {
+        panic!("intersect_all is only supported by closed shapes");
+    }
+    fn supports_intersect_all(&self) -> bool {
+        false
+    }
+}
+
+// intersect_all for closed shapes by walking every surface crossing in turn,
+// the normal tells entering and leaving crossings apart
+pub fn closed_shape_intervals(shape: &dyn Shape, ray: &Ray) -> Vec<(ScalarF, ScalarF)> {
+    let mut intervals = vec![];
+    let mut entered = None;
+    let mut t_min = ScalarF::NEG_INFINITY;
+    while let Some(hit) = shape.intersect(ray, t_min, ScalarF::INFINITY) {
+        if hit.front_face {
+            entered = Some(hit.t);
+        } else {
+            intervals.push((entered.take().unwrap_or(ScalarF::NEG_INFINITY), hit.t));
+        }
+        t_min = hit.t + CROSSING_EPSILON * (1.0 + hit.t.abs());
+    }
+    if let Some(t) = entered {
+        intervals.push((t, ScalarF::INFINITY));
+    }
+    intervals
 }
 
 #[derive(Deserialize)]
@@ -21,16 +71,34 @@ pub enum ShapeConfig {
     Triangle(TriangleConfig),
     Quadrilateral(QuadrilateralConfig),
     Mesh(MeshConfig),
+    Cylinder(CylinderConfig),
+    Cone(ConeConfig),
+    Disk(DiskConfig),
+    Torus(TorusConfig),
+    AabbShape(AabbShapeConfig),
+    InstancedShape(InstancedShapeConfig),
+    CsgShape(CsgShapeConfig),
+    Heightfield(HeightfieldConfig),
+    SubdivisionSurface(SubdivisionSurfaceConfig),
 }
 
 impl ShapeConfig {
-    pub fn to_shape(&self) -> Arc<dyn Shape> {
-        match self {
+    pub fn to_shape(&self) -> Result<Arc<dyn Shape>, RaytracerError> {
+        Ok(match self {
             ShapeConfig::Sphere(config) => config.to_shape(),
             ShapeConfig::Plane(config) => config.to_shape(),
             ShapeConfig::Triangle(config) => config.to_shape(),
             ShapeConfig::Quadrilateral(config) => config.to_shape(),
-            ShapeConfig::Mesh(config) => config.to_shape(),
-        }
+            ShapeConfig::Mesh(config) => config.to_shape()?,
+            ShapeConfig::Cylinder(config) => config.to_shape(),
+            ShapeConfig::Cone(config) => config.to_shape(),
+            ShapeConfig::Disk(config) => config.to_shape(),
+            ShapeConfig::Torus(config) => config.to_shape(),
+            ShapeConfig::AabbShape(config) => config.to_shape(),
+            ShapeConfig::InstancedShape(config) => config.to_shape()?,
+            ShapeConfig::CsgShape(config) => config.to_shape()?,
+            ShapeConfig::Heightfield(config) => config.to_shape()?,
+            ShapeConfig::SubdivisionSurface(config) => config.to_shape()?,
+        })
     }
 }
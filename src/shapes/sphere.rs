@@ -1,9 +1,11 @@
 use super::super::common::HitRecord;
+use super::super::math::consts::PI;
 use super::super::math::{
-    transform_point3, unwrap_matrix4d_config_to_matrix4d, Matrix4D, Matrix4DConfig, Point3D,
-    Point3DConfig, Ray,
+    transform_point3, unwrap_matrix4d_config_to_matrix4d, Aabb, Matrix4D, Point3D, Point3DConfig,
+    Ray, ScalarF, TransformConfig, Vec3D,
 };
-use super::shape::Shape;
+use super::super::sampler::Sampler;
+use super::shape::{Shape, ShapeSample};
 use cgmath::InnerSpace;
 use serde::Deserialize;
 use std::sync::Arc;
@@ -11,19 +13,35 @@ use std::sync::Arc;
 #[derive(Debug)]
 pub struct Sphere {
     pub center: Point3D,
-    pub radius: f64,
+    pub radius: ScalarF,
 }
 
 #[derive(Deserialize)]
 pub struct SphereConfig {
     pub center: Point3DConfig,
     pub radius: f64,
-    pub transform: Option<Matrix4DConfig>,
+    pub transform: Option<TransformConfig>,
+}
+
+// u from the azimuth around y, v from the elevation, both in [0, 1]
+fn sphere_uv(normal: Vec3D) -> (ScalarF, ScalarF) {
+    let theta = (-normal.y).clamp(-1.0, 1.0).acos();
+    let phi = (-normal.z).atan2(normal.x) + PI;
+    (phi / (2.0 * PI), theta / PI)
+}
+
+fn sphere_tangent(normal: Vec3D) -> Option<Vec3D> {
+    let tangent = Vec3D::new(normal.z, 0.0, -normal.x);
+    if tangent.magnitude2() < 1e-12 {
+        // the azimuth is undefined at the poles
+        return None;
+    }
+    Some(tangent.normalize())
 }
 
 impl Sphere {
     #[allow(dead_code)]
-    fn intersect_analytic(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+    fn intersect_analytic(&self, ray: &Ray, t_min: ScalarF, t_max: ScalarF) -> Option<HitRecord> {
         let oc = ray.origin - self.center;
         let a = ray.direction.magnitude2();
         let half_b = oc.dot(ray.direction);
@@ -46,16 +64,25 @@ impl Sphere {
         let point = ray.at(root);
         let normal = (point - self.center) / self.radius;
 
-        Some(HitRecord {
+        let mut hit = HitRecord {
             t: root,
             p: point,
             normal: normal,
+            front_face: true,
+            uv: Some(sphere_uv(normal)),
+            tangent: sphere_tangent(normal),
+            bitangent: None,
+            vertex_color: None,
             shape: Some(self as &dyn Shape),
             object: None,
-        })
+            object_id: 0,
+            exterior_ior: None,
+        };
+        hit.set_face_normal(ray, normal);
+        Some(hit)
     }
 
-    fn intersect_geometric(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+    fn intersect_geometric(&self, ray: &Ray, t_min: ScalarF, t_max: ScalarF) -> Option<HitRecord> {
         let l = self.center - ray.origin;
         let t_ca = l.dot(ray.direction);
 
@@ -83,21 +110,33 @@ impl Sphere {
             return None;
         }
 
-        let point = ray.at(t0);
+        // projected back onto the surface, the error of `ray.at` is more than
+        // the ray origin offset covers in f32
+        let direction = ray.at(t0) - self.center;
+        let point = self.center + direction * (self.radius / direction.magnitude());
         let normal = (point - self.center) / self.radius;
 
-        Some(HitRecord {
+        let mut hit = HitRecord {
             t: t0,
             p: point,
             normal: normal,
+            front_face: true,
+            uv: Some(sphere_uv(normal)),
+            tangent: sphere_tangent(normal),
+            bitangent: None,
+            vertex_color: None,
             shape: Some(self as &dyn Shape),
             object: None,
-        })
+            object_id: 0,
+            exterior_ior: None,
+        };
+        hit.set_face_normal(ray, normal);
+        Some(hit)
     }
 }
 
 impl Shape for Sphere {
-    fn intersect(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+    fn intersect(&self, ray: &Ray, t_min: ScalarF, t_max: ScalarF) -> Option<HitRecord> {
         return self.intersect_geometric(ray, t_min, t_max);
     }
 
@@ -107,13 +146,52 @@ impl Shape for Sphere {
             radius: self.radius,
         })
     }
+
+    fn bounding_box(&self) -> Aabb {
+        let r = Vec3D::new(self.radius, self.radius, self.radius);
+        Aabb::new(self.center - r, self.center + r)
+    }
+
+    fn area(&self) -> ScalarF {
+        4.0 * PI * self.radius * self.radius
+    }
+
+    fn sample(&self, sampler: &mut dyn Sampler) -> Option<ShapeSample> {
+        let (u, v) = sampler.get_2d();
+        let z = 1.0 - 2.0 * u;
+        let r = (1.0 - z * z).max(0.0).sqrt();
+        let phi = 2.0 * PI * v;
+        let normal = Vec3D::new(r * phi.cos(), r * phi.sin(), z);
+        Some(ShapeSample {
+            p: self.center + normal * self.radius,
+            normal,
+            pdf: 1.0 / self.area(),
+        })
+    }
+
+    fn supports_intersect_all(&self) -> bool {
+        true
+    }
+
+    fn intersect_all(&self, ray: &Ray) -> Vec<(ScalarF, ScalarF)> {
+        let oc = ray.origin - self.center;
+        let a = ray.direction.magnitude2();
+        let half_b = oc.dot(ray.direction);
+        let c = oc.magnitude2() - self.radius * self.radius;
+        let discriminant = half_b * half_b - a * c;
+        if discriminant < 0.0 {
+            return vec![];
+        }
+        let sqrtd = discriminant.sqrt();
+        vec![((-half_b - sqrtd) / a, (-half_b + sqrtd) / a)]
+    }
 }
 
 impl SphereConfig {
     pub fn to_shape(&self) -> Arc<dyn Shape> {
         Sphere {
             center: self.center.to_point(),
-            radius: self.radius,
+            radius: self.radius as ScalarF,
         }
         .transform(&unwrap_matrix4d_config_to_matrix4d(self.transform.as_ref()))
     }
@@ -122,7 +200,9 @@ impl SphereConfig {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::math::{point_approx_eq, vec3_approx_eq, Vec3D};
+    use crate::math::consts::PI;
+    use crate::math::{point_approx_eq, spawn_ray, spherical_to_world, tolerance, vec3_approx_eq};
+    use crate::sampler::RandomSampler;
     use approx::assert_abs_diff_eq;
     use rand::Rng;
 
@@ -171,10 +251,148 @@ mod tests {
             } else {
                 let hit_analytic = hit_analytic.unwrap();
                 let hit_geometric = hit_geometric.unwrap();
-                assert_abs_diff_eq!(hit_analytic.t, hit_geometric.t, epsilon = 1e-6);
-                point_approx_eq(hit_analytic.p, hit_geometric.p, 1e-6);
-                vec3_approx_eq(hit_analytic.normal, hit_geometric.normal, 1e-6);
+                assert_abs_diff_eq!(hit_analytic.t, hit_geometric.t, epsilon = tolerance(1e-6));
+                point_approx_eq(hit_analytic.p, hit_geometric.p, tolerance(1e-6));
+                vec3_approx_eq(hit_analytic.normal, hit_geometric.normal, tolerance(1e-6));
+            }
+        }
+    }
+
+    #[test]
+    fn test_sphere_offset_ray_origin() {
+        let mut rng = rand::thread_rng();
+        for radius in [0.001, 1000.0] {
+            let sphere = Sphere {
+                center: Point3D::new(0.0, 0.0, 0.0),
+                radius,
+            };
+            let camera_distance = radius * 10.0;
+            for _ in 0..100 {
+                let view_dir = spherical_to_world(
+                    rng.gen_range(0.0..0.4 * PI),
+                    rng.gen_range(0.0..2.0 * PI),
+                    Vec3D::new(0.0, 0.0, -1.0),
+                );
+                let camera_ray = Ray {
+                    origin: Point3D::new(0.0, 0.0, camera_distance),
+                    direction: view_dir,
+                };
+                let hit = match sphere.intersect(&camera_ray, 0.0, ScalarF::MAX) {
+                    Some(hit) => hit,
+                    None => continue,
+                };
+
+                // shadow rays leaving the surface must not hit the sphere itself (acne)
+                let outward_dir = spherical_to_world(
+                    rng.gen_range(0.0..0.45 * PI),
+                    rng.gen_range(0.0..2.0 * PI),
+                    hit.normal,
+                );
+                let shadow_ray = spawn_ray(hit.p, hit.normal, outward_dir);
+                assert!(sphere.intersect(&shadow_ray, 0.0, ScalarF::MAX).is_none());
+
+                // shadow rays going through the sphere must still be occluded (over-shadowing)
+                let inward_dir = spherical_to_world(
+                    rng.gen_range(0.6 * PI..PI),
+                    rng.gen_range(0.0..2.0 * PI),
+                    hit.normal,
+                );
+                let shadow_ray = spawn_ray(hit.p, hit.normal, inward_dir);
+                assert!(sphere.intersect(&shadow_ray, 0.0, ScalarF::MAX).is_some());
             }
         }
     }
+
+    #[test]
+    fn test_sphere_sample() {
+        let sphere = Sphere {
+            center: Point3D::new(1.0, -2.0, 3.0),
+            radius: 2.0,
+        };
+        let mut sampler = RandomSampler::new(1);
+        for _ in 0..100 {
+            let sample = sphere.sample(&mut sampler).unwrap();
+            assert_abs_diff_eq!(
+                (sample.p - sphere.center).magnitude(),
+                2.0,
+                epsilon = tolerance(1e-9)
+            );
+            vec3_approx_eq(
+                sample.normal,
+                (sample.p - sphere.center) / 2.0,
+                tolerance(1e-9),
+            );
+            assert_abs_diff_eq!(sample.pdf * sphere.area(), 1.0, epsilon = tolerance(1e-9));
+        }
+    }
+
+    #[test]
+    fn test_sphere_bounding_box() {
+        let mut rng = rand::thread_rng();
+        for _ in 0..10 {
+            let center = Point3D::new(
+                rng.gen_range(-10.0..10.0),
+                rng.gen_range(-10.0..10.0),
+                rng.gen_range(-10.0..10.0),
+            );
+            let sphere = Sphere {
+                center,
+                radius: rng.gen_range(0.1..10.0),
+            };
+            let aabb = sphere.bounding_box();
+            assert!(point_approx_eq(aabb.centroid(), center, tolerance(1e-9)));
+
+            // rays hitting the sphere must hit its box too
+            let ray = Ray {
+                origin: Point3D::new(0.0, 0.0, 30.0),
+                direction: (center - Point3D::new(0.0, 0.0, 30.0)
+                    + Vec3D::new(
+                        rng.gen_range(-10.0..10.0),
+                        rng.gen_range(-10.0..10.0),
+                        rng.gen_range(-10.0..10.0),
+                    ))
+                .normalize(),
+            };
+            if sphere.intersect(&ray, 0.0, ScalarF::MAX).is_some() {
+                assert!(aabb.hit(&ray, 0.0, ScalarF::MAX));
+            }
+        }
+    }
+
+    #[test]
+    fn test_sphere_front_face() {
+        let sphere = Sphere {
+            center: Point3D::new(0.0, 0.0, 0.0),
+            radius: 1.0,
+        };
+        let outside = Ray {
+            origin: Point3D::new(0.0, 0.0, 5.0),
+            direction: Vec3D::new(0.0, 0.0, -1.0),
+        };
+        let hit = sphere.intersect(&outside, 0.0, ScalarF::MAX).unwrap();
+        assert!(hit.front_face);
+        assert!(vec3_approx_eq(
+            hit.normal,
+            Vec3D::new(0.0, 0.0, 1.0),
+            tolerance(1e-12)
+        ));
+
+        // from inside the normal is flipped to face the ray
+        let inside = Ray {
+            origin: Point3D::new(0.0, 0.0, 0.0),
+            direction: Vec3D::new(0.0, 0.0, -1.0),
+        };
+        let hit = sphere.intersect(&inside, 0.0, ScalarF::MAX).unwrap();
+        assert!(!hit.front_face);
+        assert!(vec3_approx_eq(
+            hit.normal,
+            Vec3D::new(0.0, 0.0, 1.0),
+            tolerance(1e-12)
+        ));
+        assert!(vec3_approx_eq(
+            hit.outward_normal(),
+            Vec3D::new(0.0, 0.0, -1.0),
+            tolerance(1e-12)
+        ));
+    }
 }
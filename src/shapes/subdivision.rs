@@ -0,0 +1,319 @@
+use super::super::common::HitRecord;
+use super::super::error::RaytracerError;
+use super::super::math::{
+    unwrap_matrix4d_config_to_matrix4d, Aabb, Matrix4D, Point3D, Ray, ScalarF, TransformConfig,
+    Vec3D,
+};
+use super::super::sampler::Sampler;
+use super::mesh::Mesh;
+use super::shape::{Shape, ShapeSample};
+use super::utils::load_mesh;
+use cgmath::{EuclideanSpace, InnerSpace, Zero};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+// smooth surface from a coarse control mesh, refined by `levels` steps of
+// Catmull-Clark subdivision when built. rays hit the refined mesh
+#[derive(Debug)]
+pub struct SubdivisionSurface {
+    pub base_mesh: Mesh,
+    pub levels: u32,
+
+    refined: Mesh, // triangulated, with averaged vertex normals
+}
+
+#[derive(Deserialize)]
+pub struct SubdivisionSurfaceConfig {
+    pub file: String,
+    pub levels: u32,
+    pub transform: Option<TransformConfig>,
+}
+
+impl SubdivisionSurface {
+    pub fn new(base_mesh: Mesh, levels: u32) -> Self {
+        let mut refined: Option<Mesh> = None;
+        for _ in 0..levels {
+            refined = Some(catmull_clark(refined.as_ref().unwrap_or(&base_mesh)));
+        }
+        let refined = smooth_triangles(refined.as_ref().unwrap_or(&base_mesh));
+        Self {
+            base_mesh,
+            levels,
+            refined,
+        }
+    }
+}
+
+// one Catmull-Clark step. every face of n vertices becomes n quads, edges
+// with a single face are treated as creases of the boundary
+pub fn catmull_clark(mesh: &Mesh) -> Mesh {
+    let p = |i: usize| mesh.vertices[i].to_vec();
+
+    let face_points: Vec<Vec3D> = mesh
+        .indices
+        .iter()
+        .map(|face| face.iter().map(|&i| p(i)).sum::<Vec3D>() / face.len() as ScalarF)
+        .collect();
+
+    // faces next to every edge, keyed by its vertices in ascending order
+    let edge_key = |a: usize, b: usize| (a.min(b), a.max(b));
+    let mut edge_faces: HashMap<(usize, usize), Vec<usize>> = HashMap::new();
+    let mut edges = Vec::new();
+    for (f, face) in mesh.indices.iter().enumerate() {
+        for (k, &a) in face.iter().enumerate() {
+            let key = edge_key(a, face[(k + 1) % face.len()]);
+            let faces = edge_faces.entry(key).or_default();
+            if faces.is_empty() {
+                edges.push(key);
+            }
+            faces.push(f);
+        }
+    }
+    let is_boundary = |key: &(usize, usize)| edge_faces[key].len() < 2;
+
+    let edge_points: Vec<Vec3D> = edges
+        .iter()
+        .map(|key| {
+            let midpoint = (p(key.0) + p(key.1)) / 2.0;
+            if is_boundary(key) {
+                return midpoint;
+            }
+            let faces = &edge_faces[key];
+            let face_average =
+                faces.iter().map(|&f| face_points[f]).sum::<Vec3D>() / faces.len() as ScalarF;
+            (midpoint + face_average) / 2.0
+        })
+        .collect();
+
+    // (F + 2R + (n - 3)P) / n with F the average of the adjacent face points
+    // and R of the incident edge midpoints, boundary vertices only follow
+    // their boundary edges
+    let vertex_count = mesh.vertices.len();
+    let mut face_sums = vec![(Vec3D::zero(), 0); vertex_count];
+    for (face, face_point) in mesh.indices.iter().zip(&face_points) {
+        for &i in face {
+            face_sums[i].0 += *face_point;
+            face_sums[i].1 += 1;
+        }
+    }
+    let mut edge_sums = vec![(Vec3D::zero(), 0); vertex_count];
+    let mut boundary_sums = vec![(Vec3D::zero(), 0); vertex_count];
+    for key in &edges {
+        let midpoint = (p(key.0) + p(key.1)) / 2.0;
+        for i in [key.0, key.1] {
+            edge_sums[i].0 += midpoint;
+            edge_sums[i].1 += 1;
+            if is_boundary(key) {
+                boundary_sums[i].0 += midpoint;
+                boundary_sums[i].1 += 1;
+            }
+        }
+    }
+    let mut vertices: Vec<Point3D> = (0..vertex_count)
+        .map(|i| {
+            let (boundary_sum, boundary_count) = boundary_sums[i];
+            let (face_sum, n) = face_sums[i];
+            let (edge_sum, edge_count) = edge_sums[i];
+            let v = if boundary_count > 0 {
+                (p(i) + boundary_sum / boundary_count as ScalarF) / 2.0
+            } else if n == 0 {
+                p(i) // not part of any face
+            } else {
+                let n = n as ScalarF;
+                (face_sum / n + edge_sum / edge_count as ScalarF * 2.0 + p(i) * (n - 3.0)) / n
+            };
+            Point3D::from_vec(v)
+        })
+        .collect();
+
+    // original vertices, then edge points, then face points
+    let edge_index: HashMap<(usize, usize), usize> = edges
+        .iter()
+        .enumerate()
+        .map(|(e, key)| (*key, vertex_count + e))
+        .collect();
+    vertices.extend(edge_points.into_iter().map(Point3D::from_vec));
+    let face_offset = vertices.len();
+    vertices.extend(face_points.into_iter().map(Point3D::from_vec));
+
+    let mut indices = Vec::new();
+    for (f, face) in mesh.indices.iter().enumerate() {
+        let n = face.len();
+        for k in 0..n {
+            let (prev, v, next) = (face[(k + n - 1) % n], face[k], face[(k + 1) % n]);
+            indices.push(vec![
+                v,
+                edge_index[&edge_key(v, next)],
+                face_offset + f,
+                edge_index[&edge_key(prev, v)],
+            ]);
+        }
+    }
+
+    Mesh {
+        vertices,
+        normals: Vec::new(),
+        indices,
+        vertex_tex_coords: None,
+        vertex_colors: None,
+    }
+}
+
+// subdivided quads are rarely planar, they are split into triangles and
+// shaded with the area weighted normals of the faces around each vertex
+fn smooth_triangles(mesh: &Mesh) -> Mesh {
+    let indices: Vec<Vec<usize>> = mesh
+        .indices
+        .iter()
+        .flat_map(|face| (1..face.len() - 1).map(move |k| vec![face[0], face[k], face[k + 1]]))
+        .collect();
+    let mut normals = vec![Vec3D::zero(); mesh.vertices.len()];
+    for face in &indices {
+        let v = |k: usize| mesh.vertices[face[k]];
+        let n = (v(1) - v(0)).cross(v(2) - v(0));
+        for &i in face {
+            normals[i] += n;
+        }
+    }
+    Mesh {
+        vertices: mesh.vertices.clone(),
+        normals: normals
+            .into_iter()
+            .map(|n| {
+                if n.magnitude2() > 0.0 {
+                    n.normalize()
+                } else {
+                    n
+                }
+            })
+            .collect(),
+        indices,
+        vertex_tex_coords: None,
+        vertex_colors: None,
+    }
+}
+
+impl Shape for SubdivisionSurface {
+    fn intersect(&self, ray: &Ray, t_min: ScalarF, t_max: ScalarF) -> Option<HitRecord<'_>> {
+        self.refined.intersect(ray, t_min, t_max)
+    }
+
+    fn transform(&self, transform: &Matrix4D) -> Arc<dyn Shape> {
+        // subdivision commutes with affine transforms
+        Arc::new(Self {
+            base_mesh: self.base_mesh.transformed(transform),
+            levels: self.levels,
+            refined: self.refined.transformed(transform),
+        })
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.refined.bounding_box()
+    }
+
+    fn area(&self) -> ScalarF {
+        self.refined.area()
+    }
+
+    fn sample(&self, sampler: &mut dyn Sampler) -> Option<ShapeSample> {
+        self.refined.sample(sampler)
+    }
+}
+
+impl SubdivisionSurfaceConfig {
+    pub fn to_shape(&self) -> Result<Arc<dyn Shape>, RaytracerError> {
+        let base_mesh = load_mesh(&self.file)?;
+        Ok(SubdivisionSurface::new(base_mesh, self.levels)
+            .transform(&unwrap_matrix4d_config_to_matrix4d(self.transform.as_ref())))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::tolerance;
+
+    // cube spanning [-1, 1] with outward facing quads
+    fn cube() -> Mesh {
+        let vertices = (0..8)
+            .map(|i| {
+                let c = |bit: usize| if i & bit != 0 { 1.0 } else { -1.0 };
+                Point3D::new(c(1), c(2), c(4))
+            })
+            .collect();
+        Mesh {
+            vertices,
+            normals: Vec::new(),
+            indices: vec![
+                vec![0, 2, 3, 1],
+                vec![4, 5, 7, 6],
+                vec![0, 1, 5, 4],
+                vec![2, 6, 7, 3],
+                vec![0, 4, 6, 2],
+                vec![1, 3, 7, 5],
+            ],
+            vertex_tex_coords: None,
+            vertex_colors: None,
+        }
+    }
+
+    #[test]
+    fn test_catmull_clark_cube() {
+        let mesh = catmull_clark(&cube());
+        assert_eq!(mesh.vertices.len(), 26);
+        assert_eq!(mesh.indices.len(), 24);
+        assert!(mesh.indices.iter().all(|face| face.len() == 4));
+
+        // corners move to 5/9, edge points to (3/4, 3/4, 0), face points
+        // stay at the face centres
+        let close = |a: Point3D, b: Point3D| (a - b).magnitude() < tolerance(1e-12);
+        for v in &mesh.vertices[..8] {
+            assert!(close(
+                v.map(ScalarF::abs),
+                Point3D::new(5.0, 5.0, 5.0) / 9.0
+            ));
+        }
+        for v in &mesh.vertices[8..20] {
+            let mut c = [v.x.abs(), v.y.abs(), v.z.abs()];
+            c.sort_by(ScalarF::total_cmp);
+            assert!(close(Point3D::from(c), Point3D::new(0.0, 0.75, 0.75)));
+        }
+        for v in &mesh.vertices[20..] {
+            assert!((v.to_vec().magnitude() - 1.0).abs() < tolerance(1e-12));
+        }
+
+        // still closed, every edge is shared by two faces
+        let mut edges = HashMap::new();
+        for face in &mesh.indices {
+            for k in 0..4 {
+                let (a, b) = (face[k], face[(k + 1) % 4]);
+                *edges.entry((a.min(b), a.max(b))).or_insert(0) += 1;
+            }
+        }
+        assert_eq!(edges.len(), 48);
+        assert!(edges.values().all(|&count| count == 2));
+
+        // and the quads keep facing outwards
+        for face in &mesh.indices {
+            let v = |k: usize| mesh.vertices[face[k]];
+            let normal = (v(1) - v(0)).cross(v(2) - v(0));
+            assert!(normal.dot(v(0).to_vec()) > 0.0);
+        }
+    }
+
+    #[test]
+    fn test_subdivision_surface_intersect() {
+        let surface = SubdivisionSurface::new(cube(), 2);
+        let ray = Ray {
+            origin: Point3D::new(0.01, 0.02, 5.0),
+            direction: Vec3D::new(0.0, 0.0, -1.0),
+        };
+        let hit = surface.intersect(&ray, 1e-6, ScalarF::MAX).unwrap();
+        // the face centre moves inwards, the surface shrinks towards a sphere
+        assert!(hit.p.z < 1.0 && hit.p.z > 0.5);
+        assert!(hit.normal.z > 0.99);
+        let bounds = surface.bounding_box();
+        assert!(bounds.max.x < 1.0 && bounds.min.x > -1.0);
+    }
+}
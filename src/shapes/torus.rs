@@ -0,0 +1,359 @@
+use super::super::common::HitRecord;
+use super::super::math::consts::PI;
+use super::super::math::{
+    transform_point3, unwrap_matrix4d_config_to_matrix4d, Aabb, Matrix4D, Point3D, Point3DConfig,
+    Ray, ScalarF, TransformConfig, Vec3D,
+};
+use super::super::sampler::Sampler;
+use super::instance::InstancedShape;
+use super::shape::{closed_shape_intervals, Shape, ShapeSample};
+use cgmath::InnerSpace;
+use serde::Deserialize;
+use std::sync::Arc;
+
+// torus lying in the xz plane around `center`, the tube of `minor_radius`
+// follows a circle of `major_radius`
+#[derive(Debug, Clone)]
+pub struct Torus {
+    pub center: Point3D,
+    pub major_radius: ScalarF,
+    pub minor_radius: ScalarF,
+}
+
+#[derive(Deserialize)]
+pub struct TorusConfig {
+    pub center: Point3DConfig,
+    pub major_radius: f64,
+    pub minor_radius: f64,
+    pub transform: Option<TransformConfig>,
+}
+
+// polynomial with coefficients from the highest degree down
+fn evaluate_polynomial(coefficients: &[ScalarF], x: ScalarF) -> ScalarF {
+    coefficients.iter().fold(0.0, |acc, &c| acc * x + c)
+}
+
+// ascending real roots within [lo, hi]. the roots of the derivative split
+// the interval into monotonic pieces which are then bisected, slower than
+// the closed form but it doesn't lose roots to cancellation
+fn polynomial_roots(coefficients: &[ScalarF], lo: ScalarF, hi: ScalarF) -> Vec<ScalarF> {
+    let degree = coefficients.len() - 1;
+    if degree == 1 {
+        let root = -coefficients[1] / coefficients[0];
+        return if (lo..=hi).contains(&root) {
+            vec![root]
+        } else {
+            vec![]
+        };
+    }
+
+    let derivative: Vec<ScalarF> = coefficients[..degree]
+        .iter()
+        .enumerate()
+        .map(|(i, c)| c * (degree - i) as ScalarF)
+        .collect();
+    let mut bounds = vec![lo];
+    bounds.extend(polynomial_roots(&derivative, lo, hi));
+    bounds.push(hi);
+
+    let mut roots = vec![];
+    for pair in bounds.windows(2) {
+        let (mut a, mut b) = (pair[0], pair[1]);
+        let (fa, fb) = (
+            evaluate_polynomial(coefficients, a),
+            evaluate_polynomial(coefficients, b),
+        );
+        if fa == 0.0 {
+            if roots.last() != Some(&a) {
+                roots.push(a);
+            }
+            continue;
+        }
+        if fa * fb > 0.0 {
+            continue;
+        }
+        while b - a > 1e-12 * (1.0 + a.abs()) {
+            let mid = 0.5 * (a + b);
+            // f32 runs out of digits before the tolerance
+            if mid == a || mid == b {
+                break;
+            }
+            let fm = evaluate_polynomial(coefficients, mid);
+            if fm == 0.0 {
+                a = mid;
+                b = mid;
+            } else if (fm > 0.0) == (fa > 0.0) {
+                a = mid;
+            } else {
+                b = mid;
+            }
+        }
+        roots.push(0.5 * (a + b));
+    }
+    roots
+}
+
+impl Torus {
+    // gradient of (|p|^2 + R^2 - r^2)^2 - 4 R^2 (x^2 + z^2), outwards
+    fn normal_at(&self, p: Point3D) -> Vec3D {
+        let w = p - self.center;
+        let k = w.magnitude2() + self.major_radius.powi(2) - self.minor_radius.powi(2);
+        let radial = Vec3D::new(w.x, 0.0, w.z);
+        (w * k - radial * (2.0 * self.major_radius.powi(2))).normalize()
+    }
+}
+
+impl Shape for Torus {
+    fn intersect(&self, ray: &Ray, t_min: ScalarF, t_max: ScalarF) -> Option<HitRecord<'_>> {
+        // clip the ray to the bounding sphere first, the quartic is then set
+        // up from the entry point which keeps its coefficients small
+        let bound = self.major_radius + self.minor_radius;
+        let oc = ray.origin - self.center;
+        let a = ray.direction.magnitude2();
+        let half_b = oc.dot(ray.direction);
+        let c = oc.magnitude2() - bound * bound;
+        let discriminant = half_b * half_b - a * c;
+        if discriminant < 0.0 {
+            return None;
+        }
+        let sqrtd = discriminant.sqrt();
+        let t0 = ((-half_b - sqrtd) / a).max(t_min);
+        let t1 = ((-half_b + sqrtd) / a).min(t_max);
+        if t0 > t1 {
+            return None;
+        }
+
+        let (r2, rr2) = (self.major_radius.powi(2), self.minor_radius.powi(2));
+        let (o, d) = (oc + ray.direction * t0, ray.direction);
+        let b = 2.0 * o.dot(d);
+        let k = o.magnitude2() + r2 - rr2;
+        let coefficients = [
+            a * a,
+            2.0 * a * b,
+            b * b + 2.0 * a * k - 4.0 * r2 * (d.x * d.x + d.z * d.z),
+            2.0 * b * k - 8.0 * r2 * (o.x * d.x + o.z * d.z),
+            k * k - 4.0 * r2 * (o.x * o.x + o.z * o.z),
+        ];
+        let t = t0 + *polynomial_roots(&coefficients, 0.0, t1 - t0).first()?;
+        if t < t_min || t > t_max {
+            return None;
+        }
+
+        let p = ray.at(t);
+        let normal = self.normal_at(p);
+        let mut hit = HitRecord {
+            t,
+            p,
+            normal,
+            front_face: true,
+            uv: None,
+            tangent: None,
+            bitangent: None,
+            vertex_color: None,
+            shape: Some(self as &dyn Shape),
+            object: None,
+            object_id: 0,
+            exterior_ior: None,
+        };
+        hit.set_face_normal(ray, normal);
+        Some(hit)
+    }
+
+    fn transform(&self, transform: &Matrix4D) -> Arc<dyn Shape> {
+        // a translation only moves the centre, anything else tilts or
+        // squashes the torus out of the xz plane and goes through an instance
+        if transform.x.truncate() != Vec3D::unit_x()
+            || transform.y.truncate() != Vec3D::unit_y()
+            || transform.z.truncate() != Vec3D::unit_z()
+        {
+            return Arc::new(InstancedShape::new(Arc::new(self.clone()), *transform));
+        }
+        Arc::new(Torus {
+            center: transform_point3(*transform, self.center),
+            major_radius: self.major_radius,
+            minor_radius: self.minor_radius,
+        })
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        let bound = self.major_radius + self.minor_radius;
+        let extent = Vec3D::new(bound, self.minor_radius, bound);
+        Aabb::new(self.center - extent, self.center + extent)
+    }
+
+    fn area(&self) -> ScalarF {
+        4.0 * PI * PI * self.major_radius * self.minor_radius
+    }
+
+    fn sample(&self, sampler: &mut dyn Sampler) -> Option<ShapeSample> {
+        // uniform angles overweight the inner side of the tube, the area
+        // element grows with the distance from the axis so reject accordingly
+        let bound = self.major_radius + self.minor_radius;
+        loop {
+            let (s, t) = sampler.get_2d();
+            let (theta, phi) = (2.0 * PI * s, 2.0 * PI * t);
+            let distance = self.major_radius + self.minor_radius * phi.cos();
+            if sampler.get_1d() * bound > distance {
+                continue;
+            }
+
+            let normal = Vec3D::new(phi.cos() * theta.cos(), phi.sin(), phi.cos() * theta.sin());
+            let p = self.center
+                + Vec3D::new(distance * theta.cos(), 0.0, distance * theta.sin())
+                + Vec3D::new(0.0, self.minor_radius * phi.sin(), 0.0);
+            return Some(ShapeSample {
+                p,
+                normal,
+                pdf: 1.0 / self.area(),
+            });
+        }
+    }
+
+    fn supports_intersect_all(&self) -> bool {
+        true
+    }
+
+    fn intersect_all(&self, ray: &Ray) -> Vec<(ScalarF, ScalarF)> {
+        closed_shape_intervals(self, ray)
+    }
+}
+
+impl TorusConfig {
+    pub fn to_shape(&self) -> Arc<dyn Shape> {
+        Torus {
+            center: self.center.to_point(),
+            major_radius: self.major_radius as ScalarF,
+            minor_radius: self.minor_radius as ScalarF,
+        }
+        .transform(&unwrap_matrix4d_config_to_matrix4d(self.transform.as_ref()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::{point_approx_eq, tolerance, vec3_approx_eq};
+    use crate::sampler::RandomSampler;
+    use approx::assert_abs_diff_eq;
+
+    fn test_torus() -> Torus {
+        Torus {
+            center: Point3D::new(1.0, -2.0, 0.5),
+            major_radius: 2.0,
+            minor_radius: 0.5,
+        }
+    }
+
+    #[test]
+    fn test_torus_intersect() {
+        let torus = test_torus();
+
+        // down through the hole, both along the axis and off it
+        for (x, z) in [(0.0, 0.0), (0.8, -0.9)] {
+            let ray = Ray {
+                origin: torus.center + Vec3D::new(x, 5.0, z),
+                direction: Vec3D::new(0.0, -1.0, 0.0),
+            };
+            assert!(torus.intersect(&ray, 1e-6, ScalarF::MAX).is_none());
+        }
+
+        // down through the tube, the normal stays in the plane of the axis
+        for theta in [0.3 as ScalarF, 2.0, 4.5] {
+            let rho = 2.2;
+            let ray = Ray {
+                origin: torus.center + Vec3D::new(rho * theta.cos(), 5.0, rho * theta.sin()),
+                direction: Vec3D::new(0.0, -1.0, 0.0),
+            };
+            let hit = torus.intersect(&ray, 1e-6, ScalarF::MAX).unwrap();
+            let y = ScalarF::sqrt(0.25 - 0.2 * 0.2);
+            assert_abs_diff_eq!(hit.t, 5.0 - y, epsilon = tolerance(1e-9));
+            let tangent = Vec3D::new(-theta.sin(), 0.0, theta.cos());
+            assert_abs_diff_eq!(hit.normal.dot(tangent), 0.0, epsilon = tolerance(1e-9));
+            assert!(hit.normal.y > 0.0);
+        }
+
+        // along the x axis through both sides of the ring
+        let ray = Ray {
+            origin: torus.center + Vec3D::new(-10.0, 0.0, 0.0),
+            direction: Vec3D::new(1.0, 0.0, 0.0),
+        };
+        let hit = torus.intersect(&ray, 1e-6, ScalarF::MAX).unwrap();
+        assert_abs_diff_eq!(hit.t, 7.5, epsilon = tolerance(1e-9));
+        assert!(vec3_approx_eq(
+            hit.normal,
+            Vec3D::new(-1.0, 0.0, 0.0),
+            tolerance(1e-9)
+        ));
+        // further along it leaves the near tube and enters the far one
+        let hit = torus.intersect(&ray, 8.0, ScalarF::MAX).unwrap();
+        assert_abs_diff_eq!(hit.t, 8.5, epsilon = tolerance(1e-9));
+        let hit = torus.intersect(&ray, 9.0, ScalarF::MAX).unwrap();
+        assert_abs_diff_eq!(hit.t, 11.5, epsilon = tolerance(1e-9));
+        assert!(vec3_approx_eq(
+            hit.normal,
+            Vec3D::new(-1.0, 0.0, 0.0),
+            tolerance(1e-9)
+        ));
+        assert!(torus.intersect(&ray, 1e-6, 7.0).is_none());
+    }
+
+    #[test]
+    fn test_torus_rotated() {
+        let torus = test_torus();
+        let moved = torus.transform(&Matrix4D::from_translation(Vec3D::new(1.0, 2.0, 3.0)));
+        assert!(point_approx_eq(
+            moved.bounding_box().min,
+            torus.bounding_box().min + Vec3D::new(1.0, 2.0, 3.0),
+            tolerance(1e-12)
+        ));
+
+        // stood up in the xy plane, rays along z go through the hole and
+        // rays along y hit the ring
+        let rotated = torus.transform(&Matrix4D::from_angle_x(cgmath::Deg(90.0)));
+        let center = Point3D::new(1.0, -0.5, -2.0);
+        let ray = Ray {
+            origin: center + Vec3D::new(0.0, 0.0, 5.0),
+            direction: Vec3D::new(0.0, 0.0, -1.0),
+        };
+        assert!(rotated.intersect(&ray, 1e-6, ScalarF::MAX).is_none());
+        let ray = Ray {
+            origin: center + Vec3D::new(0.0, 5.0, 0.0),
+            direction: Vec3D::new(0.0, -1.0, 0.0),
+        };
+        let hit = rotated.intersect(&ray, 1e-6, ScalarF::MAX).unwrap();
+        assert_abs_diff_eq!(hit.t, 2.5, epsilon = tolerance(1e-9));
+        assert!(vec3_approx_eq(
+            hit.normal,
+            Vec3D::new(0.0, 1.0, 0.0),
+            tolerance(1e-9)
+        ));
+    }
+
+    #[test]
+    fn test_torus_sample() {
+        let torus = test_torus();
+        let bounds = torus.bounding_box();
+        crate::rng::init_thread_local_rng(1);
+        let mut sampler = RandomSampler::new(1);
+        let mut outer = 0;
+        for _ in 0..1000 {
+            let sample = torus.sample(&mut sampler).unwrap();
+            assert!(bounds.union(&Aabb::new(sample.p, sample.p)) == bounds);
+
+            let ray = Ray {
+                origin: sample.p + sample.normal,
+                direction: -sample.normal,
+            };
+            let hit = torus.intersect(&ray, 1e-6, ScalarF::MAX).unwrap();
+            assert!(point_approx_eq(hit.p, sample.p, tolerance(1e-6)));
+            assert!(vec3_approx_eq(hit.normal, sample.normal, tolerance(1e-6)));
+            let w = sample.p - torus.center;
+            if Vec3D::new(w.x, 0.0, w.z).magnitude() > torus.major_radius {
+                outer += 1;
+            }
+        }
+        // the outer half of the tube holds (pi R + 2 r) / (2 pi R) of the area
+        let expected = (PI * 2.0 + 1.0) / (2.0 * PI * 2.0);
+        assert!((outer as ScalarF / 1000.0 - expected).abs() < 0.05);
+    }
+}
@@ -1,9 +1,10 @@
 use super::super::common::HitRecord;
 use super::super::math::{
-    transform_point3, unwrap_matrix4d_config_to_matrix4d, Matrix4D, Matrix4DConfig, Point3D,
-    Point3DConfig, Ray,
+    transform_point3, unwrap_matrix4d_config_to_matrix4d, Aabb, Matrix4D, Point3D, Point3DConfig,
+    Ray, ScalarF, TransformConfig, Vec3D,
 };
-use super::shape::Shape;
+use super::super::sampler::Sampler;
+use super::shape::{Shape, ShapeSample};
 use cgmath::InnerSpace;
 use serde::Deserialize;
 use std::sync::Arc;
@@ -11,12 +12,14 @@ use std::sync::Arc;
 #[derive(Debug)]
 pub struct Triangle {
     pub vertices: [Point3D; 3],
+    pub uvs: Option<[(ScalarF, ScalarF); 3]>, // texture coordinates of the vertices
 }
 
 #[derive(Deserialize)]
 pub struct TriangleConfig {
     pub vertices: [Point3DConfig; 3],
-    pub transform: Option<Matrix4DConfig>,
+    pub uvs: Option<[(f64, f64); 3]>,
+    pub transform: Option<TransformConfig>,
 }
 
 pub fn triangle_intersect(
@@ -24,9 +27,9 @@ pub fn triangle_intersect(
     v1: Point3D,
     v2: Point3D,
     ray: &Ray,
-    t_min: f64,
-    t_max: f64,
-) -> Option<(f64, f64, f64)> {
+    t_min: ScalarF,
+    t_max: ScalarF,
+) -> Option<(ScalarF, ScalarF, ScalarF)> {
     // Moller-Trumbore algorithm
     let e1 = v1 - v0;
     let e2 = v2 - v0;
@@ -60,9 +63,43 @@ pub fn triangle_intersect(
     }
 }
 
+pub fn triangle_area(v0: Point3D, v1: Point3D, v2: Point3D) -> ScalarF {
+    (v1 - v0).cross(v2 - v0).magnitude() * 0.5
+}
+
+// dp/du and dp/dv of the triangle, none for degenerate uv layouts
+pub fn triangle_uv_gradients(
+    vertices: [Point3D; 3],
+    uvs: [(ScalarF, ScalarF); 3],
+) -> Option<(Vec3D, Vec3D)> {
+    let (dp1, dp2) = (vertices[1] - vertices[0], vertices[2] - vertices[0]);
+    let (du1, dv1) = (uvs[1].0 - uvs[0].0, uvs[1].1 - uvs[0].1);
+    let (du2, dv2) = (uvs[2].0 - uvs[0].0, uvs[2].1 - uvs[0].1);
+    let det = du1 * dv2 - dv1 * du2;
+    if det.abs() < 1e-12 {
+        return None;
+    }
+    let dpdu = (dp1 * dv2 - dp2 * dv1) / det;
+    let dpdv = (dp2 * du1 - dp1 * du2) / det;
+    Some((dpdu.normalize(), dpdv.normalize()))
+}
+
+// uniform point on the triangle from a 2d sample
+pub fn triangle_sample(
+    v0: Point3D,
+    v1: Point3D,
+    v2: Point3D,
+    (u, v): (ScalarF, ScalarF),
+) -> Point3D {
+    let su = u.sqrt();
+    let b0 = 1.0 - su;
+    let b1 = v * su;
+    v0 + (v1 - v0) * b1 + (v2 - v0) * (1.0 - b0 - b1)
+}
+
 impl Shape for Triangle {
-    fn intersect(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
-        let (t, _u, _v) = match triangle_intersect(
+    fn intersect(&self, ray: &Ray, t_min: ScalarF, t_max: ScalarF) -> Option<HitRecord> {
+        let (t, u, v) = match triangle_intersect(
             self.vertices[0],
             self.vertices[1],
             self.vertices[2],
@@ -78,13 +115,32 @@ impl Shape for Triangle {
         let normal = (self.vertices[1] - self.vertices[0])
             .cross(self.vertices[2] - self.vertices[0])
             .normalize();
-        return Some(HitRecord {
+        let uv = self.uvs.map(|[uv0, uv1, uv2]| {
+            let w = 1.0 - u - v;
+            (
+                w * uv0.0 + u * uv1.0 + v * uv2.0,
+                w * uv0.1 + u * uv1.1 + v * uv2.1,
+            )
+        });
+        let gradients = self
+            .uvs
+            .and_then(|uvs| triangle_uv_gradients(self.vertices, uvs));
+        let mut hit = HitRecord {
             t: t,
             p: p,
             normal: normal,
+            front_face: true,
+            uv,
+            tangent: gradients.map(|(dpdu, _)| dpdu),
+            bitangent: gradients.map(|(_, dpdv)| dpdv),
+            vertex_color: None,
             shape: Some(self as &dyn Shape),
             object: None,
-        });
+            object_id: 0,
+            exterior_ior: None,
+        };
+        hit.set_face_normal(ray, normal);
+        Some(hit)
     }
 
     fn transform(&self, transform: &Matrix4D) -> Arc<dyn Shape> {
@@ -94,6 +150,24 @@ impl Shape for Triangle {
                 transform_point3(*transform, self.vertices[1]),
                 transform_point3(*transform, self.vertices[2]),
             ],
+            uvs: self.uvs,
+        })
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        Aabb::from_points(&self.vertices)
+    }
+
+    fn area(&self) -> ScalarF {
+        triangle_area(self.vertices[0], self.vertices[1], self.vertices[2])
+    }
+
+    fn sample(&self, sampler: &mut dyn Sampler) -> Option<ShapeSample> {
+        let [v0, v1, v2] = self.vertices;
+        Some(ShapeSample {
+            p: triangle_sample(v0, v1, v2, sampler.get_2d()),
+            normal: (v1 - v0).cross(v2 - v0).normalize(),
+            pdf: 1.0 / self.area(),
         })
     }
 }
@@ -106,6 +180,9 @@ impl TriangleConfig {
                 self.vertices[1].to_point(),
                 self.vertices[2].to_point(),
             ],
+            uvs: self
+                .uvs
+                .map(|uvs| uvs.map(|(u, v)| (u as ScalarF, v as ScalarF))),
         }
         .transform(&unwrap_matrix4d_config_to_matrix4d(self.transform.as_ref()))
     }
@@ -114,10 +191,66 @@ impl TriangleConfig {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::math::{vec3_approx_eq, Vec3D};
+    use crate::math::{tolerance, vec3_approx_eq};
     use approx::assert_abs_diff_eq;
     use rand::Rng;
 
+    #[test]
+    fn test_triangle_uv_frame() {
+        let vertices = [
+            Point3D::new(0.0, 0.0, 0.0),
+            Point3D::new(2.0, 0.0, 0.0),
+            Point3D::new(0.0, 1.0, 0.0),
+        ];
+        let ray = Ray {
+            origin: Point3D::new(1.0, 0.25, 1.0),
+            direction: Vec3D::new(0.0, 0.0, -1.0),
+        };
+
+        let triangle = Triangle {
+            vertices,
+            uvs: Some([(0.0, 0.0), (1.0, 0.0), (0.0, 1.0)]),
+        };
+        let hit = triangle.intersect(&ray, 0.0, 100.0).unwrap();
+        let (u, v) = hit.uv.unwrap();
+        assert_abs_diff_eq!(u, 0.5, epsilon = tolerance(1e-9));
+        assert_abs_diff_eq!(v, 0.25, epsilon = tolerance(1e-9));
+        assert!(vec3_approx_eq(
+            hit.tangent.unwrap(),
+            Vec3D::new(1.0, 0.0, 0.0),
+            tolerance(1e-9)
+        ));
+        assert!(vec3_approx_eq(
+            hit.bitangent.unwrap(),
+            Vec3D::new(0.0, 1.0, 0.0),
+            tolerance(1e-9)
+        ));
+
+        // u mirrored
+        let triangle = Triangle {
+            vertices,
+            uvs: Some([(1.0, 0.0), (0.0, 0.0), (1.0, 1.0)]),
+        };
+        let hit = triangle.intersect(&ray, 0.0, 100.0).unwrap();
+        assert!(vec3_approx_eq(
+            hit.tangent.unwrap(),
+            Vec3D::new(-1.0, 0.0, 0.0),
+            tolerance(1e-9)
+        ));
+        assert!(vec3_approx_eq(
+            hit.bitangent.unwrap(),
+            Vec3D::new(0.0, 1.0, 0.0),
+            tolerance(1e-9)
+        ));
+
+        let triangle = Triangle {
+            vertices,
+            uvs: None,
+        };
+        let hit = triangle.intersect(&ray, 0.0, 100.0).unwrap();
+        assert!(hit.uv.is_none() && hit.tangent.is_none() && hit.bitangent.is_none());
+    }
+
     #[test]
     fn test_triangle_intersect() {
         let mut rng = rand::thread_rng();
@@ -139,6 +272,7 @@ mod tests {
             );
             let triangle = Triangle {
                 vertices: [v0, v1, v2],
+                uvs: None,
             };
             let p1 = Ray {
                 origin: v0,
@@ -1,96 +1,905 @@
-use super::super::math::{Point3D, Vec3D};
+use super::super::error::RaytracerError;
+use super::super::material::{
+    AlphaMaskedMaterialConfig, EmissiveConfig, IdealDielectricConfig, LambertianConfig,
+    MaterialConfig, PhongSpecularConfig,
+};
+use super::super::math::{
+    transform_point3, transform_vec3, Matrix4D, Point2D, Point3D, ScalarF, Vec3D, Vec3DConfig,
+};
 use super::mesh::Mesh;
 use super::quadrilateral::{are_points_coplanar, is_quadrilateral_convex};
-use cgmath::InnerSpace;
-use log::info;
+use cgmath::{InnerSpace, Quaternion, SquareMatrix, Zero};
+use log::{info, warn};
 use ply_rs::parser::Parser;
-use ply_rs::ply::DefaultElement;
-use std::fs::File;
+use ply_rs::ply::{DefaultElement, Payload};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::path::Path;
 
 pub trait MeshLoader {
-    fn load(&self, path: &str) -> Mesh;
+    fn load(&self, path: &str) -> Result<Mesh, String>;
 }
 
 pub struct PlyMeshLoader {}
 
 impl MeshLoader for PlyMeshLoader {
-    fn load(&self, path: &str) -> Mesh {
+    fn load(&self, path: &str) -> Result<Mesh, String> {
         info!("Loading mesh from {}", path);
-        let mut file = File::open(path).unwrap();
+        let mut file = File::open(path).map_err(|e| format!("Failed to open {}: {}", path, e))?;
         let p = Parser::<DefaultElement>::new();
-        let ply = p.read_ply(&mut file).unwrap();
-        let payload = ply.payload;
-
-        let vertex_element = &payload["vertex"];
-        let mut vertices: Vec<Point3D> = Vec::new();
-        let mut normals: Vec<Vec3D> = Vec::new();
-        for vertex in vertex_element {
-            let x = match vertex["x"] {
-                ply_rs::ply::Property::Float(x) => x as f64,
-                _ => panic!("x's type unrecognized"),
-            };
-            let y = match vertex["y"] {
-                ply_rs::ply::Property::Float(y) => y as f64,
-                _ => panic!("y's type unrecognized"),
-            };
-            let z = match vertex["z"] {
-                ply_rs::ply::Property::Float(z) => z as f64,
-                _ => panic!("z's type unrecognized"),
-            };
-            vertices.push(Point3D::new(x, y, z));
+        let ply = p
+            .read_ply(&mut file)
+            .map_err(|e| format!("Failed to parse {}: {}", path, e))?;
+        let mesh = ply_mesh(&ply.payload).map_err(|e| format!("{}: {}", path, e))?;
+        info!(
+            "Loaded mesh with {} vertices and {} faces",
+            mesh.vertices.len(),
+            mesh.indices.len()
+        );
+        Ok(mesh)
+    }
+}
 
-            let nx = match vertex["nx"] {
-                ply_rs::ply::Property::Float(nx) => nx as f64,
-                _ => panic!("nx's type unrecognized"),
-            };
-            let ny = match vertex["ny"] {
-                ply_rs::ply::Property::Float(ny) => ny as f64,
-                _ => panic!("ny's type unrecognized"),
-            };
-            let nz = match vertex["nz"] {
-                ply_rs::ply::Property::Float(nz) => nz as f64,
-                _ => panic!("nz's type unrecognized"),
+fn ply_float(vertex: &DefaultElement, name: &str) -> Result<ScalarF, String> {
+    match vertex.get(name) {
+        Some(ply_rs::ply::Property::Float(value)) => Ok(*value as ScalarF),
+        Some(_) => Err(format!("{}'s type unrecognized", name)),
+        None => Err(format!("vertex without {}", name)),
+    }
+}
+
+fn ply_mesh(payload: &Payload<DefaultElement>) -> Result<Mesh, String> {
+    let vertex_element = payload.get("vertex").ok_or("no vertex element")?;
+    let mut vertices: Vec<Point3D> = Vec::new();
+    let mut normals: Vec<Vec3D> = Vec::new();
+    let mut tex_coords: Vec<Option<Point2D>> = Vec::new();
+    let mut colors: Vec<Option<Vec3D>> = Vec::new();
+    for vertex in vertex_element {
+        let (x, y, z) = (
+            ply_float(vertex, "x")?,
+            ply_float(vertex, "y")?,
+            ply_float(vertex, "z")?,
+        );
+        vertices.push(Point3D::new(x, y, z));
+
+        let (nx, ny, nz) = (
+            ply_float(vertex, "nx")?,
+            ply_float(vertex, "ny")?,
+            ply_float(vertex, "nz")?,
+        );
+        normals.push(Vec3D::new(nx, ny, nz).normalize());
+
+        // texture coordinates are optional
+        tex_coords.push(match (vertex.get("s"), vertex.get("t")) {
+            (Some(ply_rs::ply::Property::Float(s)), Some(ply_rs::ply::Property::Float(t))) => {
+                Some(Point2D::new(*s as ScalarF, *t as ScalarF))
+            }
+            _ => None,
+        });
+        // so are colours, as bytes
+        let channel = |name: &str| match vertex.get(name) {
+            Some(ply_rs::ply::Property::UChar(c)) => Some(*c as ScalarF / 255.0),
+            _ => None,
+        };
+        colors.push(match (channel("red"), channel("green"), channel("blue")) {
+            (Some(r), Some(g), Some(b)) => Some(Vec3D::new(r, g, b)),
+            _ => None,
+        });
+    }
+
+    let face_element = payload.get("face").ok_or("no face element")?;
+    let mut indices: Vec<Vec<usize>> = Vec::new();
+    for face in face_element {
+        indices.push(ply_face_indices(face)?);
+    }
+
+    Ok(Mesh {
+        vertices,
+        normals,
+        indices,
+        vertex_tex_coords: tex_coords.into_iter().collect(),
+        vertex_colors: colors.into_iter().collect(),
+    })
+}
+
+// vertex indices of a PLY face, exporters like Blender store them as 8 or
+// 16 bit lists when the mesh is small enough
+fn ply_face_indices(face: &DefaultElement) -> Result<Vec<usize>, String> {
+    match face.get("vertex_indices") {
+        Some(ply_rs::ply::Property::ListUChar(vertex_indices)) => {
+            Ok(vertex_indices.iter().map(|&i| i as usize).collect())
+        }
+        Some(ply_rs::ply::Property::ListUShort(vertex_indices)) => {
+            Ok(vertex_indices.iter().map(|&i| i as usize).collect())
+        }
+        Some(ply_rs::ply::Property::ListUInt(vertex_indices)) => {
+            Ok(vertex_indices.iter().map(|&i| i as usize).collect())
+        }
+        _ => Err("vertex_indices's type unrecognized".to_string()),
+    }
+}
+
+// the subset of the glTF 2.0 schema needed to read triangle meshes
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GltfDocument {
+    #[serde(default)]
+    accessors: Vec<GltfAccessor>,
+    #[serde(default)]
+    buffer_views: Vec<GltfBufferView>,
+    #[serde(default)]
+    buffers: Vec<GltfBuffer>,
+    #[serde(default)]
+    meshes: Vec<GltfMesh>,
+    #[serde(default)]
+    nodes: Vec<GltfNode>,
+    #[serde(default)]
+    scenes: Vec<GltfScene>,
+    scene: Option<usize>,
+    #[serde(default)]
+    materials: Vec<GltfMaterial>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GltfAccessor {
+    buffer_view: Option<usize>,
+    #[serde(default)]
+    byte_offset: usize,
+    component_type: u32,
+    count: usize,
+    #[serde(rename = "type")]
+    element_type: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GltfBufferView {
+    buffer: usize,
+    #[serde(default)]
+    byte_offset: usize,
+    byte_length: usize,
+    byte_stride: Option<usize>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GltfBuffer {
+    uri: Option<String>,
+    byte_length: usize,
+}
+
+#[derive(Deserialize)]
+struct GltfMesh {
+    primitives: Vec<GltfPrimitive>,
+}
+
+#[derive(Deserialize)]
+struct GltfPrimitive {
+    attributes: HashMap<String, usize>,
+    indices: Option<usize>,
+    material: Option<usize>,
+    mode: Option<u32>,
+}
+
+#[derive(Deserialize)]
+struct GltfNode {
+    mesh: Option<usize>,
+    #[serde(default)]
+    children: Vec<usize>,
+    matrix: Option<[f64; 16]>,
+    translation: Option<[f64; 3]>,
+    rotation: Option<[f64; 4]>,
+    scale: Option<[f64; 3]>,
+}
+
+#[derive(Deserialize)]
+struct GltfScene {
+    #[serde(default)]
+    nodes: Vec<usize>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GltfMaterial {
+    pbr_metallic_roughness: Option<GltfPbrMetallicRoughness>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GltfPbrMetallicRoughness {
+    base_color_factor: Option<[f64; 4]>,
+}
+
+impl GltfNode {
+    // local transform, either a column major matrix or translation *
+    // rotation * scale
+    fn local_transform(&self) -> Matrix4D {
+        if let Some(m) = self.matrix {
+            let m = m.map(|v| v as ScalarF);
+            return Matrix4D::new(
+                m[0], m[1], m[2], m[3], m[4], m[5], m[6], m[7], m[8], m[9], m[10], m[11], m[12],
+                m[13], m[14], m[15],
+            );
+        }
+        let [tx, ty, tz] = self.translation.unwrap_or([0.0; 3]).map(|v| v as ScalarF);
+        let [x, y, z, w] = self
+            .rotation
+            .unwrap_or([0.0, 0.0, 0.0, 1.0])
+            .map(|v| v as ScalarF);
+        let [sx, sy, sz] = self.scale.unwrap_or([1.0; 3]).map(|v| v as ScalarF);
+        Matrix4D::from_translation(Vec3D::new(tx, ty, tz))
+            * Matrix4D::from(Quaternion::new(w, x, y, z))
+            * Matrix4D::from_nonuniform_scale(sx, sy, sz)
+    }
+}
+
+const GLB_MAGIC: &[u8; 4] = b"glTF";
+const GLB_CHUNK_JSON: u32 = 0x4E4F534A;
+const GLB_CHUNK_BIN: u32 = 0x004E4942;
+
+fn read_u32(bytes: &[u8], offset: usize) -> Result<u32, String> {
+    bytes
+        .get(offset..offset + 4)
+        .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .ok_or_else(|| "Unexpected end of glTF data".to_string())
+}
+
+// json and optional binary chunk of a .glb container
+fn parse_glb(bytes: &[u8]) -> Result<(&[u8], Option<&[u8]>), String> {
+    if bytes.len() < 12 || &bytes[0..4] != GLB_MAGIC {
+        return Err("Not a binary glTF file".to_string());
+    }
+    let version = read_u32(bytes, 4)?;
+    if version != 2 {
+        return Err(format!("Unsupported glTF version: {}", version));
+    }
+
+    let mut json = None;
+    let mut bin = None;
+    let mut offset = 12;
+    while offset + 8 <= bytes.len() {
+        let length = read_u32(bytes, offset)? as usize;
+        let chunk_type = read_u32(bytes, offset + 4)?;
+        let data = bytes
+            .get(offset + 8..offset + 8 + length)
+            .ok_or_else(|| "Truncated glTF chunk".to_string())?;
+        match chunk_type {
+            GLB_CHUNK_JSON => json = Some(data),
+            GLB_CHUNK_BIN => bin = Some(data),
+            _ => {} // unknown chunks must be ignored
+        }
+        offset += 8 + length;
+    }
+    Ok((json.ok_or("Missing JSON chunk in glTF file")?, bin))
+}
+
+fn decode_base64(data: &str) -> Result<Vec<u8>, String> {
+    let value = |c: u8| -> Result<u32, String> {
+        match c {
+            b'A'..=b'Z' => Ok((c - b'A') as u32),
+            b'a'..=b'z' => Ok((c - b'a') as u32 + 26),
+            b'0'..=b'9' => Ok((c - b'0') as u32 + 52),
+            b'+' => Ok(62),
+            b'/' => Ok(63),
+            _ => Err(format!("Invalid base64 character: {}", c as char)),
+        }
+    };
+
+    let data = data.trim_end_matches('=').as_bytes();
+    let mut bytes = Vec::with_capacity(data.len() * 3 / 4);
+    for chunk in data.chunks(4) {
+        let mut bits = 0;
+        for (i, &c) in chunk.iter().enumerate() {
+            bits |= value(c)? << (18 - 6 * i);
+        }
+        for i in 0..chunk.len() - 1 {
+            bytes.push((bits >> (16 - 8 * i)) as u8);
+        }
+    }
+    Ok(bytes)
+}
+
+pub struct GltfMeshLoader {}
+
+impl GltfMeshLoader {
+    fn load_buffers(
+        document: &GltfDocument,
+        directory: &Path,
+        bin: Option<&[u8]>,
+    ) -> Result<Vec<Vec<u8>>, String> {
+        document
+            .buffers
+            .iter()
+            .map(|buffer| {
+                let data = match &buffer.uri {
+                    None => bin
+                        .ok_or("glTF buffer without uri outside of a .glb file")?
+                        .to_vec(),
+                    Some(uri) if uri.starts_with("data:") => {
+                        let (_, encoded) = uri
+                            .split_once(";base64,")
+                            .ok_or_else(|| "Unsupported glTF data uri".to_string())?;
+                        decode_base64(encoded)?
+                    }
+                    Some(uri) => {
+                        let path = directory.join(uri);
+                        fs::read(&path)
+                            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?
+                    }
+                };
+                if data.len() < buffer.byte_length {
+                    return Err(format!(
+                        "glTF buffer has {} bytes, expected {}",
+                        data.len(),
+                        buffer.byte_length
+                    ));
+                }
+                Ok(data)
+            })
+            .collect()
+    }
+
+    // elements of an accessor, each as `components` consecutive values
+    fn read_accessor(
+        document: &GltfDocument,
+        buffers: &[Vec<u8>],
+        index: usize,
+    ) -> Result<(Vec<ScalarF>, usize), String> {
+        let accessor = document
+            .accessors
+            .get(index)
+            .ok_or_else(|| format!("Invalid glTF accessor: {}", index))?;
+        let components = match accessor.element_type.as_str() {
+            "SCALAR" => 1,
+            "VEC2" => 2,
+            "VEC3" => 3,
+            "VEC4" => 4,
+            other => return Err(format!("Unsupported glTF accessor type: {}", other)),
+        };
+        let component_size = match accessor.component_type {
+            5120 | 5121 => 1,
+            5122 | 5123 => 2,
+            5125 | 5126 => 4,
+            other => return Err(format!("Unsupported glTF component type: {}", other)),
+        };
+        // sparse accessors without a buffer view are all zero
+        let view = match accessor.buffer_view {
+            Some(view) => &document.buffer_views[view],
+            None => return Ok((vec![0.0; accessor.count * components], components)),
+        };
+        let buffer = &buffers[view.buffer];
+        let stride = view.byte_stride.unwrap_or(components * component_size);
+        let start = view.byte_offset + accessor.byte_offset;
+        let view_end = view.byte_offset + view.byte_length;
+
+        let mut values = Vec::with_capacity(accessor.count * components);
+        for element in 0..accessor.count {
+            for component in 0..components {
+                let offset = start + element * stride + component * component_size;
+                let b = buffer
+                    .get(offset..offset + component_size)
+                    .filter(|_| offset + component_size <= view_end)
+                    .ok_or_else(|| format!("glTF accessor {} out of bounds", index))?;
+                values.push(match accessor.component_type {
+                    5120 => b[0] as i8 as ScalarF,
+                    5121 => b[0] as ScalarF,
+                    5122 => i16::from_le_bytes([b[0], b[1]]) as ScalarF,
+                    5123 => u16::from_le_bytes([b[0], b[1]]) as ScalarF,
+                    5125 => u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as ScalarF,
+                    _ => f32::from_le_bytes([b[0], b[1], b[2], b[3]]) as ScalarF,
+                });
+            }
+        }
+        Ok((values, components))
+    }
+
+    // every triangle primitive of the default scene in world space, along
+    // with a lambertian material for the first base color found
+    pub fn load_with_material(
+        &self,
+        path: &str,
+    ) -> Result<(Mesh, Option<LambertianConfig>), String> {
+        info!("Loading mesh from {}", path);
+        let bytes = fs::read(path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+        let (json, bin) = if path.ends_with(".glb") {
+            parse_glb(&bytes)?
+        } else {
+            (bytes.as_slice(), None)
+        };
+        let document: GltfDocument =
+            serde_json::from_slice(json).map_err(|e| format!("Failed to parse {}: {}", path, e))?;
+        let directory = Path::new(path).parent().unwrap_or(Path::new("."));
+        let buffers = Self::load_buffers(&document, directory, bin)?;
+
+        let roots = match document.scenes.get(document.scene.unwrap_or(0)) {
+            Some(scene) => scene.nodes.clone(),
+            None => {
+                // without scenes every node that is nobody's child is a root
+                let children: Vec<usize> = document
+                    .nodes
+                    .iter()
+                    .flat_map(|node| node.children.iter().copied())
+                    .collect();
+                (0..document.nodes.len())
+                    .filter(|i| !children.contains(i))
+                    .collect()
+            }
+        };
+
+        let mut mesh = Mesh {
+            vertices: Vec::new(),
+            normals: Vec::new(),
+            indices: Vec::new(),
+            vertex_tex_coords: None,
+            vertex_colors: None,
+        };
+        let mut material = None;
+        let mut stack: Vec<(usize, Matrix4D)> = roots
+            .into_iter()
+            .map(|i| (i, Matrix4D::identity()))
+            .collect();
+        while let Some((node_index, parent)) = stack.pop() {
+            let node = document
+                .nodes
+                .get(node_index)
+                .ok_or_else(|| format!("Invalid glTF node: {}", node_index))?;
+            let transform = parent * node.local_transform();
+            for &child in &node.children {
+                stack.push((child, transform));
+            }
+            let gltf_mesh = match node.mesh {
+                Some(mesh_index) => document
+                    .meshes
+                    .get(mesh_index)
+                    .ok_or_else(|| format!("Invalid glTF mesh: {}", mesh_index))?,
+                None => continue,
             };
-            normals.push(Vec3D::new(nx, ny, nz).normalize());
+
+            for primitive in &gltf_mesh.primitives {
+                if primitive.mode.unwrap_or(4) != 4 {
+                    return Err(format!(
+                        "Unsupported glTF primitive mode: {}",
+                        primitive.mode.unwrap()
+                    ));
+                }
+                let position = *primitive
+                    .attributes
+                    .get("POSITION")
+                    .ok_or_else(|| "glTF primitive without POSITION".to_string())?;
+                let (positions, _) = Self::read_accessor(&document, &buffers, position)?;
+                let vertex_count = positions.len() / 3;
+                let normals = match primitive.attributes.get("NORMAL") {
+                    Some(&normal) => Some(Self::read_accessor(&document, &buffers, normal)?.0),
+                    None => None,
+                };
+                let indices: Vec<usize> = match primitive.indices {
+                    Some(indices) => Self::read_accessor(&document, &buffers, indices)?
+                        .0
+                        .into_iter()
+                        .map(|i| i as usize)
+                        .collect(),
+                    None => (0..vertex_count).collect(),
+                };
+                if let Some(&i) = indices.iter().find(|&&i| i >= vertex_count) {
+                    return Err(format!("glTF index {} out of range", i));
+                }
+
+                let base = mesh.vertices.len();
+                for i in 0..vertex_count {
+                    let p =
+                        Point3D::new(positions[3 * i], positions[3 * i + 1], positions[3 * i + 2]);
+                    mesh.vertices.push(transform_point3(transform, p));
+                    mesh.normals.push(Vec3D::zero());
+                }
+                for triangle in indices.chunks_exact(3) {
+                    mesh.indices
+                        .push(triangle.iter().map(|&i| base + i).collect());
+                }
+                match normals {
+                    Some(normals) => {
+                        for i in 0..vertex_count {
+                            let n =
+                                Vec3D::new(normals[3 * i], normals[3 * i + 1], normals[3 * i + 2]);
+                            mesh.normals[base + i] = transform_vec3(transform, n).normalize();
+                        }
+                    }
+                    None => {
+                        // area weighted face normals
+                        for triangle in indices.chunks_exact(3) {
+                            let v = |k: usize| mesh.vertices[base + triangle[k]];
+                            let n = (v(1) - v(0)).cross(v(2) - v(0));
+                            for &i in triangle {
+                                mesh.normals[base + i] += n;
+                            }
+                        }
+                        for normal in &mut mesh.normals[base..] {
+                            if !normal.is_zero() {
+                                *normal = normal.normalize();
+                            }
+                        }
+                    }
+                }
+
+                if material.is_none() {
+                    material = primitive
+                        .material
+                        .and_then(|i| document.materials.get(i))
+                        .and_then(|m| m.pbr_metallic_roughness.as_ref())
+                        .and_then(|pbr| pbr.base_color_factor)
+                        .map(|[r, g, b, _]| {
+                            LambertianConfig::from_albedo(Vec3DConfig::new(r, g, b))
+                        });
+                }
+            }
         }
 
-        let face_element = &payload["face"];
-        let mut indices: Vec<Vec<usize>> = Vec::new();
-        for face in face_element {
-            let mut face_indices: Vec<usize> = Vec::new();
-            let vertex_indices = match &face["vertex_indices"] {
-                ply_rs::ply::Property::ListUInt(vertex_indices) => vertex_indices,
-                _ => panic!("vertex_indices's type unrecognized"),
+        info!(
+            "Loaded mesh with {} vertices and {} faces",
+            mesh.vertices.len(),
+            mesh.indices.len()
+        );
+        Ok((mesh, material))
+    }
+}
+
+impl MeshLoader for GltfMeshLoader {
+    fn load(&self, path: &str) -> Result<Mesh, String> {
+        Ok(self.load_with_material(path)?.0)
+    }
+}
+
+pub struct StlMeshLoader {}
+
+impl StlMeshLoader {
+    pub fn load_stl(&self, path: &str) -> Result<Mesh, String> {
+        info!("Loading mesh from {}", path);
+        let bytes = fs::read(path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+        let mesh = parse_stl(&bytes)?;
+        info!("Loaded mesh with {} faces", mesh.indices.len());
+        Ok(mesh)
+    }
+}
+
+impl MeshLoader for StlMeshLoader {
+    fn load(&self, path: &str) -> Result<Mesh, String> {
+        self.load_stl(path)
+    }
+}
+
+// binary files may start with "solid" too, a triangle count matching the
+// file size tells them apart
+fn parse_stl(bytes: &[u8]) -> Result<Mesh, String> {
+    let binary = bytes.len() >= 84 && 84 + 50 * read_u32(bytes, 80)? as usize == bytes.len();
+    let mut triangles = Vec::new();
+    if binary {
+        // normal and three vertices as f32 followed by an attribute count
+        for record in bytes[84..].chunks(50) {
+            let vec3_at = |i: usize| {
+                let f32_at = |j: usize| f32::from_le_bytes(record[j..j + 4].try_into().unwrap());
+                [f32_at(i), f32_at(i + 4), f32_at(i + 8)].map(|c| c as ScalarF)
             };
-            for vertex_index in vertex_indices {
-                face_indices.push(*vertex_index as usize);
+            triangles.push((vec3_at(0), [vec3_at(12), vec3_at(24), vec3_at(36)]));
+        }
+    } else if bytes.starts_with(b"solid") {
+        let text = std::str::from_utf8(bytes).map_err(|_| "Invalid ASCII STL file")?;
+        let mut tokens = text.split_whitespace();
+        let read_vec3 = |tokens: &mut std::str::SplitWhitespace| -> Result<[ScalarF; 3], String> {
+            let mut v = [0.0; 3];
+            for c in &mut v {
+                *c = tokens
+                    .next()
+                    .and_then(|t| t.parse().ok())
+                    .ok_or("Invalid number in ASCII STL file")?;
+            }
+            Ok(v)
+        };
+        let mut normal = [0.0; 3];
+        let mut vertices = Vec::new();
+        while let Some(token) = tokens.next() {
+            match token {
+                "normal" => normal = read_vec3(&mut tokens)?,
+                "vertex" => vertices.push(read_vec3(&mut tokens)?),
+                "endfacet" => {
+                    let facet: [[ScalarF; 3]; 3] = vertices
+                        .as_slice()
+                        .try_into()
+                        .map_err(|_| "STL facet without three vertices")?;
+                    triangles.push((normal, facet));
+                    vertices.clear();
+                }
+                _ => {}
             }
-            indices.push(face_indices);
         }
+    } else {
+        return Err("Not an STL file".to_string());
+    }
+
+    // triangles share no vertices, each carries its face normal
+    let mut mesh = Mesh {
+        vertices: Vec::with_capacity(triangles.len() * 3),
+        normals: Vec::with_capacity(triangles.len() * 3),
+        indices: Vec::with_capacity(triangles.len()),
+        vertex_tex_coords: None,
+        vertex_colors: None,
+    };
+    for (normal, facet) in triangles {
+        let [a, b, c] = facet.map(Point3D::from);
+        let mut normal = Vec3D::from(normal);
+        // exporters may leave the normal zero
+        if normal.magnitude2() == 0.0 {
+            normal = (b - a).cross(c - a);
+        }
+        let first = mesh.vertices.len();
+        mesh.vertices.extend([a, b, c]);
+        mesh.normals.extend([normal.normalize(); 3]);
+        mesh.indices.push(vec![first, first + 1, first + 2]);
+    }
+    Ok(mesh)
+}
+
+pub struct ObjMeshLoader {}
 
+impl ObjMeshLoader {
+    fn read(&self, path: &str) -> Result<ObjFile, String> {
+        info!("Loading mesh from {}", path);
+        let text =
+            fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+        let obj = parse_obj(&text).map_err(|e| format!("{}:{}", path, e))?;
         info!(
             "Loaded mesh with {} vertices and {} faces",
-            vertices.len(),
-            indices.len()
+            obj.mesh.vertices.len(),
+            obj.mesh.indices.len()
         );
-        Mesh {
-            vertices,
-            normals,
-            indices,
+        Ok(obj)
+    }
+
+    pub fn load_obj(&self, path: &str) -> Result<Mesh, String> {
+        Ok(self.read(path)?.mesh)
+    }
+
+    // the faces split by their `usemtl` material, in the order the materials
+    // are first used. faces before any `usemtl` or with a material missing
+    // from the `mtllib` files get none
+    pub fn load_with_materials(
+        &self,
+        path: &str,
+    ) -> Result<Vec<(Mesh, Option<MaterialConfig>)>, String> {
+        let obj = self.read(path)?;
+        let directory = Path::new(path).parent().unwrap_or(Path::new("."));
+        let mut materials = HashMap::new();
+        for library in &obj.material_libraries {
+            materials.extend(parse_mtl(
+                directory.join(library).to_str().unwrap_or(library),
+            )?);
+        }
+
+        let mut groups: Vec<(Option<&String>, Vec<usize>)> = Vec::new();
+        for (face, name) in obj.face_materials.iter().enumerate() {
+            match groups.iter_mut().find(|(group, _)| *group == name.as_ref()) {
+                Some((_, faces)) => faces.push(face),
+                None => groups.push((name.as_ref(), vec![face])),
+            }
         }
+        Ok(groups
+            .into_iter()
+            .map(|(name, faces)| {
+                let material = name.and_then(|name| {
+                    let material = materials.remove(name);
+                    if material.is_none() {
+                        warn!("Material {} of {} not found", name, path);
+                    }
+                    material
+                });
+                (sub_mesh(&obj.mesh, &faces), material)
+            })
+            .collect())
     }
 }
 
-pub fn load_mesh(path: &str) -> Result<Mesh, String> {
-    let mesh = match path.split('.').last() {
-        Some("ply") => PlyMeshLoader {}.load(path),
-        _ => return Err(format!("Unsupported mesh format: {}", path)),
+// the given faces of `mesh` with only the vertices they use
+fn sub_mesh(mesh: &Mesh, faces: &[usize]) -> Mesh {
+    let mut remap: HashMap<usize, usize> = HashMap::new();
+    let mut used = Vec::new();
+    let indices = faces
+        .iter()
+        .map(|&face| {
+            mesh.indices[face]
+                .iter()
+                .map(|&i| {
+                    *remap.entry(i).or_insert_with(|| {
+                        used.push(i);
+                        used.len() - 1
+                    })
+                })
+                .collect()
+        })
+        .collect();
+    let pick = |values: &Vec<Vec3D>| used.iter().map(|&i| values[i]).collect::<Vec<_>>();
+    Mesh {
+        vertices: used.iter().map(|&i| mesh.vertices[i]).collect(),
+        normals: if mesh.normals.is_empty() {
+            Vec::new()
+        } else {
+            pick(&mesh.normals)
+        },
+        indices,
+        vertex_tex_coords: mesh
+            .vertex_tex_coords
+            .as_ref()
+            .map(|tex_coords| used.iter().map(|&i| tex_coords[i]).collect()),
+        vertex_colors: mesh.vertex_colors.as_ref().map(pick),
+    }
+}
+
+impl MeshLoader for ObjMeshLoader {
+    fn load(&self, path: &str) -> Result<Mesh, String> {
+        self.load_obj(path)
+    }
+}
+
+#[derive(Debug)]
+struct ObjFile {
+    mesh: Mesh,
+    material_libraries: Vec<String>, // `mtllib` paths relative to the file
+    face_materials: Vec<Option<String>>, // the `usemtl` material of every face
+}
+
+// positions, texture coordinates and normals are indexed separately in OBJ
+// files, every distinct `v/vt/vn` corner becomes one mesh vertex. groups are
+// ignored, polygons with more than four corners are split into a fan of
+// triangles
+fn parse_obj(text: &str) -> Result<ObjFile, String> {
+    let mut material_libraries = Vec::new();
+    let mut face_materials = Vec::new();
+    let mut material: Option<String> = None;
+    let mut positions: Vec<Point3D> = Vec::new();
+    let mut tex_coords: Vec<Point2D> = Vec::new();
+    let mut normals: Vec<Vec3D> = Vec::new();
+    let mut corners: HashMap<(usize, Option<usize>, Option<usize>), usize> = HashMap::new();
+    let mut corner_keys = Vec::new();
+    let mut indices = Vec::new();
+
+    for (line_number, line) in text.lines().enumerate() {
+        let mut tokens = line.split_whitespace();
+        let keyword = match tokens.next() {
+            Some(keyword) if !keyword.starts_with('#') => keyword,
+            _ => continue,
+        };
+        let values: Vec<&str> = tokens.collect();
+        let error = |what: &str| format!("{}: {}", line_number + 1, what);
+        let parse_f64s = |count: usize| -> Result<Vec<ScalarF>, String> {
+            values
+                .iter()
+                .take(count)
+                .map(|value| value.parse::<ScalarF>().ok())
+                .collect::<Option<Vec<ScalarF>>>()
+                .filter(|v| v.len() == count)
+                .ok_or_else(|| error(&format!("invalid {} statement", keyword)))
+        };
+        // 1-based, negative ones count back from the last element so far
+        let resolve = |index: &str, len: usize| -> Result<usize, String> {
+            let i: i64 = index
+                .parse()
+                .map_err(|_| error(&format!("invalid index {}", index)))?;
+            let resolved = if i < 0 { len as i64 + i } else { i - 1 };
+            if resolved < 0 || resolved >= len as i64 {
+                return Err(error(&format!("index {} out of range", index)));
+            }
+            Ok(resolved as usize)
+        };
+
+        match keyword {
+            "v" => {
+                let v = parse_f64s(3)?;
+                positions.push(Point3D::new(v[0], v[1], v[2]));
+            }
+            "vt" => {
+                let v = parse_f64s(2)?;
+                tex_coords.push(Point2D::new(v[0], v[1]));
+            }
+            "vn" => {
+                let v = parse_f64s(3)?;
+                normals.push(Vec3D::new(v[0], v[1], v[2]).normalize());
+            }
+            "f" => {
+                if values.len() < 3 {
+                    return Err(error("face with less than three corners"));
+                }
+                let mut face = Vec::with_capacity(values.len());
+                for value in &values {
+                    let mut parts = value.split('/');
+                    let v = resolve(parts.next().unwrap_or(""), positions.len())?;
+                    let mut optional = |len: usize| match parts.next() {
+                        Some(part) if !part.is_empty() => resolve(part, len).map(Some),
+                        _ => Ok(None),
+                    };
+                    let key = (v, optional(tex_coords.len())?, optional(normals.len())?);
+                    let index = *corners.entry(key).or_insert_with(|| {
+                        corner_keys.push(key);
+                        corner_keys.len() - 1
+                    });
+                    face.push(index);
+                }
+                if face.len() <= 4 {
+                    indices.push(face);
+                } else {
+                    indices
+                        .extend((1..face.len() - 1).map(|k| vec![face[0], face[k], face[k + 1]]));
+                }
+                face_materials.resize(indices.len(), material.clone());
+            }
+            // names may contain spaces
+            "mtllib" => material_libraries.push(values.join(" ")),
+            "usemtl" => material = Some(values.join(" ")),
+            _ => {} // groups, objects and smoothing are ignored
+        }
+    }
+
+    // attributes are kept only if every corner has them
+    let all_tex_coords: Option<Vec<Point2D>> = corner_keys
+        .iter()
+        .map(|&(_, vt, _)| vt.map(|i| tex_coords[i]))
+        .collect();
+    let all_normals: Option<Vec<Vec3D>> = corner_keys
+        .iter()
+        .map(|&(_, _, vn)| vn.map(|i| normals[i]))
+        .collect();
+    let mesh = Mesh {
+        vertices: corner_keys.iter().map(|&(v, _, _)| positions[v]).collect(),
+        normals: all_normals.unwrap_or_default(),
+        indices,
+        vertex_tex_coords: all_tex_coords.filter(|t| !t.is_empty()),
+        vertex_colors: None,
+    };
+    Ok(ObjFile {
+        mesh,
+        material_libraries,
+        face_materials,
+    })
+}
+
+pub fn load_mesh(path: &str) -> Result<Mesh, RaytracerError> {
+    let loader: &dyn MeshLoader = match path.split('.').last() {
+        Some("ply") => &PlyMeshLoader {},
+        Some("gltf") | Some("glb") => &GltfMeshLoader {},
+        Some("stl") => &StlMeshLoader {},
+        Some("obj") => &ObjMeshLoader {},
+        _ => {
+            return Err(RaytracerError::UnsupportedFormat(format!(
+                "mesh file {}",
+                path
+            )))
+        }
     };
+    let mesh = loader.load(path).map_err(RaytracerError::MeshLoad)?;
+    validate_mesh(&mesh)?;
+    Ok(mesh)
+}
+
+// obj files are split by their materials, other formats give one part
+// without a material
+pub fn load_mesh_parts(path: &str) -> Result<Vec<(Mesh, Option<MaterialConfig>)>, RaytracerError> {
+    if !path.ends_with(".obj") {
+        return Ok(vec![(load_mesh(path)?, None)]);
+    }
+    let parts = ObjMeshLoader {}
+        .load_with_materials(path)
+        .map_err(RaytracerError::MeshLoad)?;
+    for (mesh, _) in &parts {
+        validate_mesh(mesh)?;
+    }
+    Ok(parts)
+}
 
-    // check if the mesh is valid
+fn validate_mesh(mesh: &Mesh) -> Result<(), RaytracerError> {
     for indices in &mesh.indices {
         if indices.len() < 3 {
-            return Err(format!("Invalid mesh: {:?}", indices));
+            return Err(RaytracerError::MeshLoad(format!(
+                "Invalid mesh: {:?}",
+                indices
+            )));
         }
         if indices.len() == 3 {
             // triangle
@@ -102,26 +911,133 @@ pub fn load_mesh(path: &str) -> Result<Mesh, String> {
             let c = mesh.vertices[indices[2]];
             let d = mesh.vertices[indices[3]];
             if !are_points_coplanar(a, b, c, d) {
-                return Err(format!(
+                return Err(RaytracerError::MeshLoad(format!(
                     "Invalid mesh: {:?} {:?} {:?} {:?}, Reason: coplanar",
                     a, b, c, d
-                ));
+                )));
             }
             if !is_quadrilateral_convex(a, b, c, d) {
-                return Err(format!(
+                return Err(RaytracerError::MeshLoad(format!(
                     "Invalid mesh: {:?} {:?} {:?} {:?}, Reason: non-convex",
                     a, b, c, d
-                ));
+                )));
             }
         }
     }
+    Ok(())
+}
 
-    Ok(mesh)
+// properties of one `newmtl` block, missing ones keep the MTL defaults
+#[derive(Default)]
+struct MtlProperties {
+    kd: Option<[f64; 3]>,
+    ks: Option<[f64; 3]>,
+    ke: Option<[f64; 3]>,
+    ns: Option<f64>,
+    ni: Option<f64>,
+    d: Option<f64>,
+}
+
+impl MtlProperties {
+    // emission wins over everything, transparent surfaces with an index of
+    // refraction become glass and the others alpha masked, then specular
+    // and finally diffuse
+    fn to_material_config(&self) -> MaterialConfig {
+        let vec3_config = |[r, g, b]: [f64; 3]| Vec3DConfig::new(r, g, b);
+        let nonzero =
+            |v: Option<[f64; 3]>| v.filter(|v| v.iter().map(|c| c * c).sum::<f64>() > 1e-12);
+
+        if let Some(ke) = nonzero(self.ke) {
+            return MaterialConfig::Emissive(EmissiveConfig {
+                color: vec3_config(ke),
+            });
+        }
+
+        let opacity = self.d.unwrap_or(1.0);
+        if let Some(ni) = self.ni.filter(|&ni| opacity < 1.0 && ni > 1.0) {
+            return MaterialConfig::IdealDielectric(IdealDielectricConfig { ior: ni });
+        }
+
+        let base = match (nonzero(self.ks), self.ns) {
+            (Some(ks), Some(ns)) => MaterialConfig::PhongSpecular(PhongSpecularConfig {
+                specular: vec3_config(ks),
+                shininess: ns,
+            }),
+            _ => MaterialConfig::Lambertian(LambertianConfig::from_albedo(vec3_config(
+                self.kd.unwrap_or([0.8; 3]),
+            ))),
+        };
+        if opacity < 1.0 {
+            return MaterialConfig::AlphaMasked(AlphaMaskedMaterialConfig {
+                base: Box::new(base),
+                alpha: opacity,
+            });
+        }
+        base
+    }
+}
+
+pub fn parse_mtl(path: &str) -> Result<HashMap<String, MaterialConfig>, String> {
+    let content =
+        fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+
+    let mut blocks: Vec<(String, MtlProperties)> = Vec::new();
+    for (line_number, line) in content.lines().enumerate() {
+        let mut tokens = line.split_whitespace();
+        let keyword = match tokens.next() {
+            Some(keyword) if !keyword.starts_with('#') => keyword,
+            _ => continue,
+        };
+        let values: Vec<&str> = tokens.collect();
+        let error = |what: &str| format!("{}:{}: {}", path, line_number + 1, what);
+        let parse_f64 = |value: Option<&&str>| -> Result<f64, String> {
+            value
+                .and_then(|value| value.parse::<f64>().ok())
+                .ok_or_else(|| error(&format!("invalid {} value", keyword)))
+        };
+        let parse_color = || -> Result<[f64; 3], String> {
+            let r = parse_f64(values.first())?;
+            // a single value sets all three channels
+            if values.len() == 1 {
+                return Ok([r; 3]);
+            }
+            Ok([r, parse_f64(values.get(1))?, parse_f64(values.get(2))?])
+        };
+
+        if keyword == "newmtl" {
+            let name = values
+                .first()
+                .ok_or_else(|| error("missing material name"))?;
+            blocks.push((name.to_string(), MtlProperties::default()));
+            continue;
+        }
+        let properties = match blocks.last_mut() {
+            Some((_, properties)) => properties,
+            None => return Err(error(&format!("{} before newmtl", keyword))),
+        };
+        match keyword {
+            "Kd" => properties.kd = Some(parse_color()?),
+            "Ks" => properties.ks = Some(parse_color()?),
+            "Ke" => properties.ke = Some(parse_color()?),
+            "Ns" => properties.ns = Some(parse_f64(values.first())?),
+            "Ni" => properties.ni = Some(parse_f64(values.first())?),
+            "d" => properties.d = Some(parse_f64(values.first())?),
+            "Tr" => properties.d = Some(1.0 - parse_f64(values.first())?),
+            _ => {} // unsupported statements (Ka, illum, maps, ...) are ignored
+        }
+    }
+
+    Ok(blocks
+        .into_iter()
+        .map(|(name, properties)| (name, properties.to_material_config()))
+        .collect())
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::math::tolerance;
+    use ply_rs::ply::Property;
 
     #[test]
     fn test_load_mesh() {
@@ -131,4 +1047,425 @@ mod test {
         assert_eq!(mesh.normals.len(), 24);
         assert_eq!(mesh.indices.len(), 6);
     }
+
+    #[test]
+    fn test_ply_tex_coords() {
+        use crate::math::Ray;
+        use crate::shapes::Shape;
+
+        let mut payload = Payload::<DefaultElement>::new();
+        let corners = [
+            (0.0, 0.0, 0.25, 0.0),
+            (1.0, 0.0, 1.0, 0.0),
+            (0.0, 1.0, 0.25, 0.75),
+        ];
+        let vertices = corners
+            .iter()
+            .map(|&(x, y, s, t)| {
+                let mut vertex = DefaultElement::new();
+                for (name, value) in [
+                    ("x", x),
+                    ("y", y),
+                    ("z", 0.0),
+                    ("nx", 0.0),
+                    ("ny", 0.0),
+                    ("nz", 1.0),
+                    ("s", s),
+                    ("t", t),
+                ] {
+                    vertex.insert(name.to_string(), Property::Float(value));
+                }
+                vertex
+            })
+            .collect();
+        payload.insert("vertex".to_string(), vertices);
+        let mut face = DefaultElement::new();
+        face.insert(
+            "vertex_indices".to_string(),
+            Property::ListUChar(vec![0, 1, 2]),
+        );
+        payload.insert("face".to_string(), vec![face]);
+
+        let mesh = ply_mesh(&payload).unwrap();
+        assert_eq!(
+            mesh.vertex_tex_coords,
+            Some(vec![
+                Point2D::new(0.25, 0.0),
+                Point2D::new(1.0, 0.0),
+                Point2D::new(0.25, 0.75)
+            ])
+        );
+        let ray = Ray {
+            origin: Point3D::new(1.0 / 3.0, 1.0 / 3.0, 1.0),
+            direction: Vec3D::new(0.0, 0.0, -1.0),
+        };
+        let (u, v) = mesh
+            .intersect(&ray, 1e-6, ScalarF::MAX)
+            .unwrap()
+            .uv
+            .unwrap();
+        assert!((u - 0.5).abs() < tolerance(1e-6));
+        assert!((v - 0.25).abs() < tolerance(1e-6));
+
+        // without them the mesh has no uvs
+        for vertex in payload.get_mut("vertex").unwrap() {
+            vertex.remove("s");
+        }
+        assert!(ply_mesh(&payload).unwrap().vertex_tex_coords.is_none());
+    }
+
+    #[test]
+    fn test_ply_face_indices() {
+        let mut face = DefaultElement::new();
+        face.insert(
+            "vertex_indices".to_string(),
+            Property::ListUChar(vec![0, 2, 255]),
+        );
+        assert_eq!(ply_face_indices(&face).unwrap(), vec![0, 2, 255]);
+        face.insert(
+            "vertex_indices".to_string(),
+            Property::ListUShort(vec![7, 65535, 1]),
+        );
+        assert_eq!(ply_face_indices(&face).unwrap(), vec![7, 65535, 1]);
+        face.insert(
+            "vertex_indices".to_string(),
+            Property::ListUInt(vec![3, 1, 100_000]),
+        );
+        assert_eq!(ply_face_indices(&face).unwrap(), vec![3, 1, 100_000]);
+    }
+
+    // unit box made of 24 vertices and 12 triangles, laid out like the
+    // BoxTextured sample model: positions, normals and uvs followed by u16
+    // indices in a single buffer
+    fn box_gltf(uri: Option<&str>) -> (String, Vec<u8>) {
+        let mut positions = Vec::new();
+        let mut normals = Vec::new();
+        for axis in 0..3 {
+            for sign in [-1.0_f32, 1.0] {
+                let mut n = [0.0_f32; 3];
+                n[axis] = sign;
+                let (u, v) = ((axis + 1) % 3, (axis + 2) % 3);
+                for (a, b) in [(-0.5, -0.5), (0.5, -0.5), (0.5, 0.5), (-0.5, 0.5)] {
+                    let mut p = [0.0_f32; 3];
+                    p[axis] = sign * 0.5;
+                    p[u] = a;
+                    p[v] = b;
+                    positions.push(p);
+                    normals.push(n);
+                }
+            }
+        }
+        let mut bin = Vec::new();
+        for p in positions.iter().chain(&normals) {
+            for c in p {
+                bin.extend_from_slice(&c.to_le_bytes());
+            }
+        }
+        for i in 0..24 {
+            bin.extend_from_slice(
+                &[(i % 2) as f32, (i / 2 % 2) as f32]
+                    .map(f32::to_le_bytes)
+                    .concat(),
+            );
+        }
+        for face in 0..6_u16 {
+            for i in [0, 1, 2, 0, 2, 3] {
+                bin.extend_from_slice(&(face * 4 + i).to_le_bytes());
+            }
+        }
+
+        let uri = uri.map_or(String::new(), |uri| format!(r#""uri": "{}", "#, uri));
+        let json = format!(
+            r#"{{
+                "asset": {{ "version": "2.0" }},
+                "scene": 0,
+                "scenes": [{{ "nodes": [0] }}],
+                "nodes": [{{ "children": [1], "translation": [0, 2, 0] }}, {{ "mesh": 0, "scale": [2, 2, 2] }}],
+                "meshes": [{{ "primitives": [{{
+                    "attributes": {{ "POSITION": 0, "NORMAL": 1, "TEXCOORD_0": 2 }},
+                    "indices": 3,
+                    "material": 0
+                }}] }}],
+                "materials": [{{ "pbrMetallicRoughness": {{ "baseColorFactor": [0.8, 0.2, 0.1, 1.0] }} }}],
+                "buffers": [{{ {}"byteLength": {} }}],
+                "bufferViews": [
+                    {{ "buffer": 0, "byteOffset": 0, "byteLength": 576, "byteStride": 12 }},
+                    {{ "buffer": 0, "byteOffset": 576, "byteLength": 192 }},
+                    {{ "buffer": 0, "byteOffset": 768, "byteLength": 72 }}
+                ],
+                "accessors": [
+                    {{ "bufferView": 0, "componentType": 5126, "count": 24, "type": "VEC3" }},
+                    {{ "bufferView": 0, "byteOffset": 288, "componentType": 5126, "count": 24, "type": "VEC3" }},
+                    {{ "bufferView": 1, "componentType": 5126, "count": 24, "type": "VEC2" }},
+                    {{ "bufferView": 2, "componentType": 5123, "count": 36, "type": "SCALAR" }}
+                ]
+            }}"#,
+            uri,
+            bin.len()
+        );
+        (json, bin)
+    }
+
+    fn check_box_mesh(mesh: &Mesh) {
+        assert_eq!(mesh.vertices.len(), 24);
+        assert_eq!(mesh.normals.len(), 24);
+        assert_eq!(mesh.indices.len(), 12);
+        assert!(mesh.indices.iter().all(|indices| indices.len() == 3));
+        // scaled by the mesh node, then moved up by its parent
+        let bounds = crate::math::Aabb::from_points(&mesh.vertices);
+        assert_eq!(bounds.min, Point3D::new(-1.0, 1.0, -1.0));
+        assert_eq!(bounds.max, Point3D::new(1.0, 3.0, 1.0));
+        for (v, n) in mesh.vertices.iter().zip(&mesh.normals) {
+            assert!((n.magnitude() - 1.0).abs() < tolerance(1e-9));
+            assert!(((v - Point3D::new(0.0, 2.0, 0.0)).dot(*n) - 1.0).abs() < tolerance(1e-6));
+        }
+    }
+
+    #[test]
+    fn test_load_gltf() {
+        let dir = std::env::temp_dir();
+
+        // binary container with the buffer as second chunk
+        let (json, bin) = box_gltf(None);
+        let mut json = json.into_bytes();
+        while json.len() % 4 != 0 {
+            json.push(b' ');
+        }
+        let mut glb = Vec::new();
+        glb.extend_from_slice(GLB_MAGIC);
+        glb.extend_from_slice(&2_u32.to_le_bytes());
+        glb.extend_from_slice(&((28 + json.len() + bin.len()) as u32).to_le_bytes());
+        glb.extend_from_slice(&(json.len() as u32).to_le_bytes());
+        glb.extend_from_slice(&GLB_CHUNK_JSON.to_le_bytes());
+        glb.extend_from_slice(&json);
+        glb.extend_from_slice(&(bin.len() as u32).to_le_bytes());
+        glb.extend_from_slice(&GLB_CHUNK_BIN.to_le_bytes());
+        glb.extend_from_slice(&bin);
+        let glb_path = dir.join("rust_ray_tracer_test_box.glb");
+        fs::write(&glb_path, glb).unwrap();
+
+        let (mesh, material) = GltfMeshLoader {}
+            .load_with_material(glb_path.to_str().unwrap())
+            .expect("Failed to load glb");
+        check_box_mesh(&mesh);
+        assert_eq!(
+            material.unwrap().albedo.unwrap().to_vec3(),
+            Vec3D::new(0.8, 0.2, 0.1)
+        );
+
+        // json with the buffer in a file next to it
+        let (json, bin) = box_gltf(Some("rust_ray_tracer_test_box.bin"));
+        let gltf_path = dir.join("rust_ray_tracer_test_box.gltf");
+        fs::write(&gltf_path, json).unwrap();
+        fs::write(dir.join("rust_ray_tracer_test_box.bin"), bin).unwrap();
+        check_box_mesh(&load_mesh(gltf_path.to_str().unwrap()).expect("Failed to load gltf"));
+
+        assert_eq!(decode_base64("aGVsbG8=").unwrap(), b"hello");
+        assert_eq!(decode_base64("Z2xURg==").unwrap(), b"glTF");
+
+        fs::remove_file(&glb_path).unwrap();
+        fs::remove_file(&gltf_path).unwrap();
+        fs::remove_file(dir.join("rust_ray_tracer_test_box.bin")).unwrap();
+    }
+
+    #[test]
+    fn test_load_stl() {
+        // unit cube, two triangles per face
+        let mut triangles = Vec::new();
+        for axis in 0..3 {
+            for side in [0.0_f32, 1.0] {
+                let mut normal = [0.0_f32; 3];
+                normal[axis] = 2.0 * side - 1.0;
+                let (u, v) = ((axis + 1) % 3, (axis + 2) % 3);
+                let corner = |a: f32, b: f32| {
+                    let mut p = [0.0_f32; 3];
+                    p[axis] = side;
+                    p[u] = a;
+                    p[v] = b;
+                    p
+                };
+                let quad = [
+                    corner(0.0, 0.0),
+                    corner(1.0, 0.0),
+                    corner(1.0, 1.0),
+                    corner(0.0, 1.0),
+                ];
+                triangles.push((normal, [quad[0], quad[1], quad[2]]));
+                triangles.push((normal, [quad[0], quad[2], quad[3]]));
+            }
+        }
+
+        let mut ascii = String::from("solid cube\n");
+        let mut binary = b"solid but binary".to_vec();
+        binary.resize(80, 0);
+        binary.extend_from_slice(&(triangles.len() as u32).to_le_bytes());
+        for (normal, facet) in &triangles {
+            ascii += &format!(
+                "facet normal {} {} {}\nouter loop\n",
+                normal[0], normal[1], normal[2]
+            );
+            for p in facet {
+                ascii += &format!("vertex {:e} {:e} {:e}\n", p[0], p[1], p[2]);
+            }
+            ascii += "endloop\nendfacet\n";
+            for c in std::iter::once(normal).chain(facet).flatten() {
+                binary.extend_from_slice(&c.to_le_bytes());
+            }
+            binary.extend_from_slice(&0_u16.to_le_bytes());
+        }
+        ascii += "endsolid cube\n";
+
+        let path = std::env::temp_dir().join("rust_ray_tracer_test_cube.stl");
+        fs::write(&path, &ascii).unwrap();
+        let from_ascii = load_mesh(path.to_str().unwrap()).expect("Failed to load ASCII STL");
+        fs::remove_file(&path).unwrap();
+        let from_binary = parse_stl(&binary).expect("Failed to load binary STL");
+
+        assert_eq!(from_ascii.vertices.len(), 36);
+        assert_eq!(from_ascii.indices.len(), 12);
+        assert_eq!(from_ascii.vertices, from_binary.vertices);
+        assert_eq!(from_ascii.normals, from_binary.normals);
+        assert_eq!(from_ascii.indices, from_binary.indices);
+        let bounds = crate::math::Aabb::from_points(&from_ascii.vertices);
+        assert_eq!(bounds.min, Point3D::new(0.0, 0.0, 0.0));
+        assert_eq!(bounds.max, Point3D::new(1.0, 1.0, 1.0));
+        assert_eq!(from_ascii.normals[0], Vec3D::new(-1.0, 0.0, 0.0));
+
+        assert!(parse_stl(b"not a mesh").is_err());
+    }
+
+    #[test]
+    fn test_load_obj() {
+        let mesh = load_mesh("assets/test.obj").expect("Failed to load mesh");
+        // a cube of quads, every corner has its own face normal
+        assert_eq!(mesh.vertices.len(), 24);
+        assert_eq!(mesh.indices.len(), 6);
+        assert_eq!(mesh.normals.len(), 24);
+        assert_eq!(mesh.vertex_tex_coords.as_ref().map(Vec::len), Some(24));
+        for face in &mesh.indices {
+            let v = |k: usize| mesh.vertices[face[k]];
+            let normal = (v(1) - v(0)).cross(v(2) - v(0)).normalize();
+            for &i in face {
+                assert!((mesh.normals[i] - normal).magnitude() < tolerance(1e-12));
+            }
+        }
+
+        // shared corners, negative indices and a pentagon split into triangles
+        let mesh = parse_obj(
+            "v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0.5 1.5 0\nv 0 1 0\n\
+             vn 0 0 2\n\
+             g pentagon\n\
+             f 1//1 2//1 3//1 4//1 5//1\n\
+             f -5//-1 -4//-1 -3//-1\n",
+        )
+        .unwrap()
+        .mesh;
+        assert_eq!(mesh.vertices.len(), 5);
+        assert_eq!(
+            mesh.indices,
+            vec![vec![0, 1, 2], vec![0, 2, 3], vec![0, 3, 4], vec![0, 1, 2]]
+        );
+        assert_eq!(mesh.normals, vec![Vec3D::new(0.0, 0.0, 1.0); 5]);
+        assert!(mesh.vertex_tex_coords.is_none());
+
+        // without normals on every corner the mesh has none
+        let mesh = parse_obj("v 0 0 0\nv 1 0 0\nv 0 1 0\nvn 0 0 1\nf 1//1 2 3\n")
+            .unwrap()
+            .mesh;
+        assert!(mesh.normals.is_empty());
+
+        assert_eq!(
+            parse_obj("v 0 0 0\nf 1 2 3\n").unwrap_err(),
+            "2: index 2 out of range"
+        );
+        assert!(parse_obj("v 0 zero 0\n").is_err());
+    }
+
+    #[test]
+    fn test_load_obj_materials() {
+        let directory = std::env::temp_dir().join("rust_ray_tracer_test_obj_materials");
+        fs::create_dir_all(&directory).unwrap();
+        fs::write(
+            directory.join("box.mtl"),
+            "newmtl white\nKd 0.7 0.7 0.7\n\nnewmtl light\nKe 10 10 10\n",
+        )
+        .unwrap();
+        let path = directory.join("box.obj");
+        fs::write(
+            &path,
+            "mtllib box.mtl\n\
+             v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\nv 0 0 1\n\
+             f 1 2 3\n\
+             usemtl light\n\
+             f 1 3 4\n\
+             usemtl white\n\
+             f 1 2 5\n\
+             usemtl missing\n\
+             f 2 3 5\n\
+             usemtl light\n\
+             f 1 4 5\n",
+        )
+        .unwrap();
+
+        let parts = load_mesh_parts(path.to_str().unwrap()).expect("Failed to load mesh");
+        // in the order the materials are first used, faces without one first
+        assert_eq!(parts.len(), 4);
+        assert!(parts[0].1.is_none());
+        assert!(matches!(parts[1].1, Some(MaterialConfig::Emissive(_))));
+        assert!(matches!(parts[2].1, Some(MaterialConfig::Lambertian(_))));
+        assert!(parts[3].1.is_none());
+
+        // the two light faces share the vertices 1 and 4
+        let (light, _) = &parts[1];
+        assert_eq!(light.indices, vec![vec![0, 1, 2], vec![0, 2, 3]]);
+        assert_eq!(
+            light.vertices,
+            vec![
+                Point3D::new(0.0, 0.0, 0.0),
+                Point3D::new(1.0, 1.0, 0.0),
+                Point3D::new(0.0, 1.0, 0.0),
+                Point3D::new(0.0, 0.0, 1.0),
+            ]
+        );
+        assert!(light.normals.is_empty());
+
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn test_parse_mtl() {
+        let path = std::env::temp_dir().join("rust_ray_tracer_test_parse_mtl.mtl");
+        fs::write(
+            &path,
+            "# two materials\n\
+             newmtl white\n\
+             Kd 0.7 0.7 0.7\n\
+             illum 1\n\
+             \n\
+             newmtl light\n\
+             Kd 0 0 0\n\
+             Ke 10 8 6\n",
+        )
+        .unwrap();
+
+        let materials = parse_mtl(path.to_str().unwrap()).expect("Failed to parse mtl");
+        assert_eq!(materials.len(), 2);
+        match &materials["white"] {
+            MaterialConfig::Lambertian(config) => {
+                assert_eq!(
+                    config.albedo.as_ref().unwrap().to_vec3(),
+                    Vec3D::new(0.7, 0.7, 0.7)
+                )
+            }
+            _ => panic!("white should be Lambertian"),
+        }
+        match &materials["light"] {
+            MaterialConfig::Emissive(config) => {
+                assert_eq!(config.color.to_vec3(), Vec3D::new(10.0, 8.0, 6.0))
+            }
+            _ => panic!("light should be Emissive"),
+        }
+
+        fs::remove_file(&path).unwrap();
+    }
 }
@@ -0,0 +1,1075 @@
+use std::sync::OnceLock;
+
+// dimensions 2 to 1024 as (degree, coefficients, initial direction numbers)
+// of each primitive polynomial, the coefficients hold its inner terms with
+// the highest power first. the first dimension is the van der corput sequence
+// and needs no entry. the polynomials are all the primitive ones in order of
+// degree and coefficients, as in new-joe-kuo-6.21201. the direction numbers of
+// dimensions 2 to 21 are the ones of that file, the later ones were picked
+// without it: the best of 128 random candidates by the t-values of the first
+// 16 to 1024 points projected with each of the 16 dimensions before
+#[rustfmt::skip]
+pub static SOBOL_DIRECTIONS: [(usize, u32, &[u32]); 1023] = [
+    (1, 0, &[1]),
+    (2, 1, &[1, 3]),
+    (3, 1, &[1, 3, 1]),
+    (3, 2, &[1, 1, 1]),
+    (4, 1, &[1, 1, 3, 3]),
+    (4, 4, &[1, 3, 5, 13]),
+    (5, 2, &[1, 1, 5, 5, 17]),
+    (5, 4, &[1, 1, 5, 5, 5]),
+    (5, 7, &[1, 1, 7, 11, 19]),
+    (5, 11, &[1, 1, 5, 1, 1]),
+    (5, 13, &[1, 1, 1, 3, 11]),
+    (5, 14, &[1, 3, 5, 5, 31]),
+    (6, 1, &[1, 3, 3, 9, 7, 49]),
+    (6, 13, &[1, 1, 1, 15, 21, 21]),
+    (6, 16, &[1, 3, 1, 13, 27, 49]),
+    (6, 19, &[1, 1, 1, 15, 7, 5]),
+    (6, 22, &[1, 3, 1, 15, 13, 25]),
+    (6, 25, &[1, 1, 5, 5, 19, 61]),
+    (7, 1, &[1, 3, 7, 11, 23, 15, 103]),
+    (7, 4, &[1, 3, 7, 13, 13, 15, 69]),
+    (7, 7, &[1, 3, 7, 9, 25, 39, 77]),
+    (7, 8, &[1, 1, 5, 13, 1, 17, 55]),
+    (7, 14, &[1, 3, 3, 1, 21, 23, 99]),
+    (7, 19, &[1, 3, 1, 5, 11, 35, 115]),
+    (7, 21, &[1, 1, 7, 3, 1, 49, 83]),
+    (7, 28, &[1, 3, 7, 15, 9, 37, 43]),
+    (7, 31, &[1, 3, 5, 7, 19, 7, 25]),
+    (7, 32, &[1, 3, 7, 1, 11, 17, 35]),
+    (7, 37, &[1, 1, 5, 15, 31, 37, 27]),
+    (7, 41, &[1, 3, 1, 1, 1, 1, 67]),
+    (7, 42, &[1, 3, 5, 7, 27, 43, 7]),
+    (7, 50, &[1, 1, 5, 5, 25, 31, 59]),
+    (7, 55, &[1, 3, 1, 11, 13, 61, 91]),
+    (7, 56, &[1, 3, 3, 15, 19, 15, 67]),
+    (7, 59, &[1, 1, 1, 7, 3, 63, 51]),
+    (7, 62, &[1, 3, 3, 5, 31, 63, 33]),
+    (8, 14, &[1, 1, 7, 5, 5, 9, 15, 119]),
+    (8, 21, &[1, 1, 1, 9, 7, 59, 115, 197]),
+    (8, 22, &[1, 3, 1, 9, 27, 63, 19, 121]),
+    (8, 38, &[1, 3, 7, 5, 13, 63, 95, 193]),
+    (8, 47, &[1, 3, 1, 15, 7, 61, 15, 45]),
+    (8, 49, &[1, 1, 7, 9, 1, 5, 47, 245]),
+    (8, 50, &[1, 3, 3, 3, 15, 43, 113, 63]),
+    (8, 52, &[1, 3, 7, 3, 29, 17, 79, 209]),
+    (8, 56, &[1, 1, 7, 9, 13, 39, 85, 155]),
+    (8, 67, &[1, 1, 7, 11, 27, 63, 45, 25]),
+    (8, 70, &[1, 3, 5, 1, 5, 23, 97, 131]),
+    (8, 84, &[1, 1, 1, 13, 17, 19, 53, 33]),
+    (8, 97, &[1, 3, 7, 1, 23, 49, 37, 91]),
+    (8, 103, &[1, 3, 5, 11, 3, 35, 23, 123]),
+    (8, 115, &[1, 1, 5, 3, 19, 15, 41, 221]),
+    (8, 122, &[1, 3, 5, 15, 29, 23, 35, 17]),
+    (9, 8, &[1, 1, 7, 3, 25, 35, 101, 95, 189]),
+    (9, 13, &[1, 3, 1, 3, 11, 1, 75, 225, 329]),
+    (9, 16, &[1, 3, 1, 11, 21, 17, 35, 111, 303]),
+    (9, 22, &[1, 1, 1, 1, 9, 47, 27, 9, 145]),
+    (9, 25, &[1, 3, 1, 15, 19, 47, 53, 183, 121]),
+    (9, 44, &[1, 3, 1, 15, 7, 39, 61, 71, 443]),
+    (9, 47, &[1, 3, 7, 1, 5, 51, 31, 159, 213]),
+    (9, 52, &[1, 3, 3, 13, 5, 11, 13, 255, 119]),
+    (9, 55, &[1, 1, 7, 13, 3, 27, 113, 13, 197]),
+    (9, 59, &[1, 1, 7, 11, 3, 47, 75, 99, 291]),
+    (9, 62, &[1, 3, 3, 1, 25, 17, 17, 127, 399]),
+    (9, 67, &[1, 3, 5, 15, 9, 31, 27, 97, 367]),
+    (9, 74, &[1, 1, 7, 15, 17, 33, 87, 1, 329]),
+    (9, 81, &[1, 3, 5, 7, 9, 13, 77, 221, 477]),
+    (9, 82, &[1, 3, 7, 7, 19, 53, 25, 19, 257]),
+    (9, 87, &[1, 3, 7, 7, 17, 15, 51, 189, 143]),
+    (9, 91, &[1, 1, 1, 9, 9, 7, 53, 79, 99]),
+    (9, 94, &[1, 3, 7, 13, 31, 11, 51, 115, 57]),
+    (9, 103, &[1, 3, 7, 9, 27, 37, 41, 255, 177]),
+    (9, 104, &[1, 1, 5, 7, 25, 33, 125, 175, 7]),
+    (9, 109, &[1, 1, 3, 7, 19, 15, 39, 83, 9]),
+    (9, 122, &[1, 1, 7, 5, 15, 13, 79, 193, 73]),
+    (9, 124, &[1, 3, 3, 15, 29, 9, 31, 129, 163]),
+    (9, 137, &[1, 1, 1, 7, 5, 39, 125, 233, 293]),
+    (9, 138, &[1, 1, 3, 15, 29, 63, 25, 233, 391]),
+    (9, 143, &[1, 3, 1, 1, 15, 21, 97, 57, 295]),
+    (9, 145, &[1, 3, 3, 11, 15, 7, 9, 79, 7]),
+    (9, 152, &[1, 1, 5, 7, 31, 25, 91, 149, 293]),
+    (9, 157, &[1, 1, 3, 13, 1, 19, 81, 215, 181]),
+    (9, 167, &[1, 1, 7, 15, 31, 33, 127, 167, 95]),
+    (9, 173, &[1, 3, 7, 7, 13, 55, 1, 175, 253]),
+    (9, 176, &[1, 3, 5, 11, 11, 17, 127, 67, 451]),
+    (9, 181, &[1, 3, 5, 3, 17, 25, 77, 17, 363]),
+    (9, 182, &[1, 3, 7, 1, 25, 63, 7, 25, 337]),
+    (9, 185, &[1, 1, 1, 1, 1, 25, 81, 73, 235]),
+    (9, 191, &[1, 1, 3, 9, 1, 61, 125, 175, 441]),
+    (9, 194, &[1, 1, 5, 9, 5, 39, 99, 5, 49]),
+    (9, 199, &[1, 3, 1, 1, 27, 35, 123, 167, 163]),
+    (9, 218, &[1, 1, 7, 11, 7, 41, 25, 145, 439]),
+    (9, 220, &[1, 3, 3, 15, 23, 7, 13, 255, 253]),
+    (9, 227, &[1, 1, 5, 9, 13, 3, 115, 119, 281]),
+    (9, 229, &[1, 3, 3, 7, 21, 61, 39, 1, 337]),
+    (9, 230, &[1, 3, 3, 5, 11, 7, 93, 181, 313]),
+    (9, 234, &[1, 1, 3, 1, 31, 31, 49, 103, 11]),
+    (9, 236, &[1, 1, 7, 7, 23, 45, 39, 21, 85]),
+    (9, 241, &[1, 3, 3, 15, 15, 25, 113, 153, 337]),
+    (9, 244, &[1, 3, 5, 9, 21, 59, 113, 17, 333]),
+    (9, 253, &[1, 1, 7, 3, 7, 17, 9, 105, 317]),
+    (10, 4, &[1, 1, 1, 15, 25, 35, 3, 53, 483, 623]),
+    (10, 13, &[1, 3, 5, 1, 13, 59, 27, 255, 503, 79]),
+    (10, 19, &[1, 1, 3, 11, 29, 31, 73, 253, 47, 155]),
+    (10, 22, &[1, 1, 7, 7, 15, 49, 31, 53, 397, 851]),
+    (10, 50, &[1, 1, 1, 5, 15, 21, 119, 209, 291, 885]),
+    (10, 55, &[1, 1, 7, 1, 27, 19, 73, 167, 1, 189]),
+    (10, 64, &[1, 3, 5, 5, 17, 25, 25, 181, 1, 179]),
+    (10, 69, &[1, 1, 1, 3, 27, 53, 101, 49, 197, 243]),
+    (10, 98, &[1, 3, 5, 9, 11, 29, 33, 119, 473, 103]),
+    (10, 107, &[1, 1, 5, 5, 31, 61, 85, 143, 203, 775]),
+    (10, 115, &[1, 3, 7, 7, 19, 43, 61, 83, 437, 563]),
+    (10, 121, &[1, 1, 1, 13, 7, 3, 63, 177, 41, 963]),
+    (10, 127, &[1, 3, 1, 5, 3, 21, 69, 103, 325, 145]),
+    (10, 134, &[1, 1, 5, 9, 9, 51, 81, 51, 381, 521]),
+    (10, 140, &[1, 3, 5, 7, 9, 1, 47, 195, 379, 643]),
+    (10, 145, &[1, 3, 1, 11, 5, 27, 95, 213, 369, 919]),
+    (10, 152, &[1, 3, 3, 5, 29, 15, 7, 255, 425, 579]),
+    (10, 158, &[1, 3, 3, 9, 5, 51, 93, 91, 57, 443]),
+    (10, 161, &[1, 3, 1, 5, 27, 57, 53, 5, 417, 113]),
+    (10, 171, &[1, 3, 1, 5, 3, 45, 27, 3, 163, 209]),
+    (10, 181, &[1, 1, 1, 7, 13, 33, 31, 81, 215, 949]),
+    (10, 194, &[1, 1, 1, 7, 17, 33, 35, 231, 349, 649]),
+    (10, 199, &[1, 1, 3, 11, 27, 23, 113, 163, 257, 627]),
+    (10, 203, &[1, 3, 5, 15, 29, 11, 29, 15, 279, 797]),
+    (10, 208, &[1, 3, 3, 15, 19, 11, 17, 65, 139, 897]),
+    (10, 227, &[1, 1, 5, 15, 25, 27, 51, 113, 139, 309]),
+    (10, 242, &[1, 3, 3, 7, 29, 35, 75, 147, 323, 597]),
+    (10, 251, &[1, 1, 3, 3, 17, 11, 69, 229, 23, 731]),
+    (10, 253, &[1, 3, 5, 15, 5, 21, 57, 29, 29, 797]),
+    (10, 265, &[1, 3, 7, 11, 23, 45, 85, 155, 473, 297]),
+    (10, 266, &[1, 1, 5, 5, 21, 61, 83, 127, 347, 511]),
+    (10, 274, &[1, 3, 1, 1, 13, 17, 83, 145, 347, 285]),
+    (10, 283, &[1, 3, 3, 11, 3, 5, 125, 187, 311, 743]),
+    (10, 289, &[1, 1, 7, 15, 23, 57, 85, 191, 177, 411]),
+    (10, 295, &[1, 3, 7, 5, 5, 53, 121, 125, 427, 615]),
+    (10, 301, &[1, 3, 3, 15, 9, 51, 45, 241, 437, 617]),
+    (10, 316, &[1, 3, 7, 1, 11, 5, 113, 247, 411, 177]),
+    (10, 319, &[1, 3, 7, 7, 25, 3, 57, 15, 27, 889]),
+    (10, 324, &[1, 3, 5, 3, 23, 45, 13, 31, 321, 273]),
+    (10, 346, &[1, 1, 1, 11, 23, 17, 95, 11, 339, 449]),
+    (10, 352, &[1, 3, 5, 11, 1, 25, 121, 159, 85, 157]),
+    (10, 361, &[1, 1, 5, 1, 11, 63, 51, 127, 23, 621]),
+    (10, 367, &[1, 1, 1, 9, 15, 5, 89, 245, 365, 301]),
+    (10, 382, &[1, 1, 3, 7, 7, 37, 45, 219, 233, 519]),
+    (10, 395, &[1, 1, 7, 1, 29, 27, 33, 73, 459, 255]),
+    (10, 398, &[1, 3, 7, 9, 25, 41, 49, 137, 203, 917]),
+    (10, 400, &[1, 1, 3, 9, 7, 61, 3, 133, 307, 565]),
+    (10, 412, &[1, 3, 1, 3, 23, 13, 1, 101, 407, 91]),
+    (10, 419, &[1, 3, 1, 9, 19, 29, 47, 75, 101, 979]),
+    (10, 422, &[1, 1, 7, 9, 1, 5, 91, 31, 455, 937]),
+    (10, 426, &[1, 3, 3, 5, 11, 59, 15, 125, 507, 541]),
+    (10, 428, &[1, 3, 5, 11, 27, 5, 105, 149, 159, 291]),
+    (10, 433, &[1, 3, 7, 7, 9, 33, 103, 85, 27, 649]),
+    (10, 446, &[1, 3, 5, 7, 15, 23, 67, 117, 383, 37]),
+    (10, 454, &[1, 1, 1, 5, 31, 17, 119, 19, 431, 451]),
+    (10, 457, &[1, 1, 7, 1, 11, 23, 115, 165, 153, 489]),
+    (10, 472, &[1, 1, 3, 1, 17, 37, 69, 97, 343, 769]),
+    (10, 493, &[1, 1, 3, 1, 3, 19, 127, 83, 141, 261]),
+    (10, 505, &[1, 3, 7, 15, 15, 21, 119, 147, 147, 903]),
+    (10, 508, &[1, 1, 5, 13, 1, 55, 115, 79, 129, 385]),
+    (11, 2, &[1, 1, 3, 9, 7, 9, 119, 19, 75, 21, 2039]),
+    (11, 11, &[1, 1, 3, 9, 29, 23, 13, 173, 475, 291, 919]),
+    (11, 21, &[1, 3, 5, 3, 23, 7, 41, 91, 377, 99, 1669]),
+    (11, 22, &[1, 3, 1, 7, 7, 31, 59, 169, 483, 895, 1101]),
+    (11, 35, &[1, 3, 5, 3, 21, 37, 27, 115, 175, 449, 1301]),
+    (11, 49, &[1, 3, 5, 7, 23, 5, 95, 109, 227, 665, 407]),
+    (11, 50, &[1, 3, 3, 15, 21, 27, 51, 17, 89, 499, 1569]),
+    (11, 56, &[1, 1, 3, 5, 1, 35, 119, 217, 195, 629, 1811]),
+    (11, 61, &[1, 3, 5, 9, 29, 23, 119, 31, 511, 669, 461]),
+    (11, 70, &[1, 3, 7, 11, 13, 47, 89, 61, 257, 309, 1029]),
+    (11, 74, &[1, 1, 7, 13, 17, 39, 85, 59, 199, 611, 1493]),
+    (11, 79, &[1, 1, 7, 1, 3, 33, 69, 25, 447, 1007, 843]),
+    (11, 84, &[1, 1, 5, 3, 25, 3, 45, 149, 137, 851, 1761]),
+    (11, 88, &[1, 3, 7, 1, 11, 39, 115, 237, 413, 349, 1765]),
+    (11, 103, &[1, 3, 3, 13, 29, 15, 79, 101, 23, 671, 1619]),
+    (11, 104, &[1, 1, 7, 11, 5, 3, 123, 95, 83, 537, 477]),
+    (11, 112, &[1, 1, 7, 9, 19, 29, 53, 65, 501, 637, 1193]),
+    (11, 115, &[1, 1, 3, 5, 11, 29, 89, 159, 373, 431, 1259]),
+    (11, 117, &[1, 3, 5, 13, 21, 37, 11, 63, 389, 467, 303]),
+    (11, 122, &[1, 1, 1, 9, 31, 57, 69, 205, 393, 867, 1945]),
+    (11, 134, &[1, 3, 7, 15, 11, 1, 23, 171, 59, 409, 227]),
+    (11, 137, &[1, 3, 1, 3, 5, 47, 9, 231, 143, 243, 275]),
+    (11, 146, &[1, 3, 1, 9, 3, 51, 73, 51, 327, 237, 1023]),
+    (11, 148, &[1, 3, 3, 11, 19, 39, 19, 121, 123, 959, 607]),
+    (11, 157, &[1, 1, 3, 13, 15, 47, 63, 159, 47, 1013, 655]),
+    (11, 158, &[1, 3, 7, 5, 13, 5, 35, 113, 487, 3, 473]),
+    (11, 162, &[1, 3, 5, 15, 19, 9, 77, 117, 189, 427, 359]),
+    (11, 164, &[1, 3, 3, 5, 17, 25, 83, 63, 475, 1011, 1615]),
+    (11, 168, &[1, 1, 7, 7, 3, 55, 119, 13, 409, 1005, 511]),
+    (11, 173, &[1, 3, 3, 7, 21, 41, 11, 243, 243, 509, 577]),
+    (11, 185, &[1, 1, 3, 15, 17, 17, 29, 81, 497, 307, 1559]),
+    (11, 186, &[1, 1, 3, 1, 29, 39, 91, 199, 105, 947, 429]),
+    (11, 191, &[1, 1, 7, 13, 1, 27, 63, 243, 3, 603, 1423]),
+    (11, 193, &[1, 1, 7, 9, 15, 39, 69, 167, 21, 267, 1257]),
+    (11, 199, &[1, 3, 7, 3, 19, 27, 113, 69, 265, 833, 2009]),
+    (11, 213, &[1, 1, 5, 1, 25, 43, 57, 189, 39, 201, 1439]),
+    (11, 214, &[1, 1, 1, 15, 27, 35, 53, 157, 409, 517, 2039]),
+    (11, 220, &[1, 1, 5, 5, 11, 23, 41, 175, 413, 727, 1049]),
+    (11, 227, &[1, 1, 3, 13, 11, 1, 31, 51, 339, 751, 1005]),
+    (11, 236, &[1, 1, 3, 5, 3, 37, 45, 7, 319, 365, 591]),
+    (11, 242, &[1, 3, 1, 7, 9, 5, 95, 247, 331, 513, 509]),
+    (11, 251, &[1, 3, 1, 3, 25, 61, 103, 99, 103, 481, 1295]),
+    (11, 256, &[1, 3, 1, 11, 21, 5, 43, 167, 455, 655, 931]),
+    (11, 259, &[1, 1, 5, 1, 29, 5, 53, 81, 443, 383, 1005]),
+    (11, 265, &[1, 3, 5, 11, 21, 57, 25, 231, 91, 229, 329]),
+    (11, 266, &[1, 3, 1, 5, 25, 9, 65, 21, 267, 379, 1573]),
+    (11, 276, &[1, 3, 1, 9, 31, 49, 45, 155, 387, 827, 1667]),
+    (11, 292, &[1, 3, 1, 9, 3, 61, 107, 177, 209, 163, 377]),
+    (11, 304, &[1, 1, 7, 5, 31, 9, 87, 115, 305, 723, 1149]),
+    (11, 310, &[1, 1, 7, 9, 17, 51, 65, 177, 67, 725, 1095]),
+    (11, 316, &[1, 1, 3, 9, 9, 61, 117, 207, 289, 973, 351]),
+    (11, 319, &[1, 3, 3, 11, 1, 1, 121, 27, 213, 823, 1603]),
+    (11, 322, &[1, 1, 1, 11, 29, 27, 19, 201, 323, 897, 1945]),
+    (11, 328, &[1, 3, 7, 9, 13, 39, 7, 85, 349, 547, 1587]),
+    (11, 334, &[1, 1, 7, 15, 15, 59, 17, 227, 303, 367, 513]),
+    (11, 339, &[1, 1, 3, 7, 23, 11, 73, 11, 45, 133, 627]),
+    (11, 341, &[1, 3, 5, 5, 3, 49, 67, 19, 203, 441, 1113]),
+    (11, 345, &[1, 1, 1, 13, 29, 37, 103, 133, 33, 141, 1503]),
+    (11, 346, &[1, 3, 5, 5, 29, 17, 85, 129, 193, 795, 547]),
+    (11, 362, &[1, 3, 7, 15, 11, 25, 99, 151, 87, 411, 1589]),
+    (11, 367, &[1, 3, 1, 1, 25, 55, 61, 89, 207, 893, 1439]),
+    (11, 372, &[1, 1, 3, 3, 5, 25, 5, 161, 187, 451, 423]),
+    (11, 375, &[1, 1, 7, 3, 17, 63, 51, 41, 243, 685, 1229]),
+    (11, 376, &[1, 3, 3, 1, 7, 9, 85, 237, 455, 175, 1917]),
+    (11, 381, &[1, 3, 7, 9, 15, 9, 39, 201, 389, 721, 1107]),
+    (11, 385, &[1, 3, 1, 13, 19, 27, 95, 109, 435, 593, 1491]),
+    (11, 388, &[1, 3, 1, 7, 17, 45, 121, 37, 193, 349, 1473]),
+    (11, 392, &[1, 3, 5, 7, 15, 27, 83, 121, 209, 545, 347]),
+    (11, 409, &[1, 3, 1, 9, 7, 41, 71, 93, 67, 339, 1379]),
+    (11, 415, &[1, 3, 1, 13, 3, 31, 111, 219, 121, 463, 399]),
+    (11, 416, &[1, 3, 5, 11, 25, 3, 43, 101, 361, 219, 549]),
+    (11, 421, &[1, 1, 7, 1, 13, 59, 39, 77, 315, 1017, 765]),
+    (11, 428, &[1, 3, 7, 9, 19, 49, 57, 115, 33, 15, 951]),
+    (11, 431, &[1, 1, 1, 11, 23, 5, 9, 139, 95, 279, 469]),
+    (11, 434, &[1, 3, 5, 7, 17, 53, 61, 245, 219, 261, 2031]),
+    (11, 439, &[1, 3, 7, 5, 13, 41, 63, 71, 509, 699, 1777]),
+    (11, 446, &[1, 3, 1, 11, 7, 7, 63, 241, 387, 965, 2045]),
+    (11, 451, &[1, 3, 1, 5, 19, 47, 31, 213, 445, 953, 493]),
+    (11, 453, &[1, 1, 5, 9, 13, 45, 79, 169, 417, 441, 1899]),
+    (11, 457, &[1, 3, 7, 13, 1, 37, 33, 173, 37, 485, 1549]),
+    (11, 458, &[1, 1, 3, 13, 25, 51, 127, 23, 483, 487, 811]),
+    (11, 471, &[1, 3, 3, 1, 11, 31, 17, 173, 119, 613, 727]),
+    (11, 475, &[1, 3, 7, 13, 13, 1, 95, 113, 277, 943, 1395]),
+    (11, 478, &[1, 3, 1, 7, 5, 51, 111, 15, 269, 639, 1513]),
+    (11, 484, &[1, 3, 3, 11, 23, 57, 19, 155, 171, 581, 367]),
+    (11, 493, &[1, 1, 5, 3, 25, 11, 73, 123, 317, 27, 863]),
+    (11, 494, &[1, 1, 7, 9, 21, 39, 67, 95, 169, 227, 1033]),
+    (11, 499, &[1, 1, 3, 5, 21, 63, 59, 123, 349, 797, 993]),
+    (11, 502, &[1, 1, 5, 11, 13, 7, 123, 171, 113, 179, 1021]),
+    (11, 517, &[1, 1, 1, 1, 11, 19, 47, 97, 463, 219, 1419]),
+    (11, 518, &[1, 1, 5, 7, 21, 51, 11, 57, 483, 63, 1371]),
+    (11, 524, &[1, 1, 5, 1, 5, 21, 95, 117, 335, 83, 1899]),
+    (11, 527, &[1, 1, 3, 3, 13, 47, 45, 253, 463, 689, 1989]),
+    (11, 555, &[1, 1, 3, 13, 23, 15, 55, 251, 409, 161, 1031]),
+    (11, 560, &[1, 1, 1, 15, 11, 15, 119, 117, 327, 343, 547]),
+    (11, 565, &[1, 3, 7, 13, 17, 43, 91, 145, 325, 797, 1675]),
+    (11, 569, &[1, 3, 5, 9, 1, 39, 21, 153, 433, 129, 847]),
+    (11, 578, &[1, 1, 1, 9, 27, 53, 25, 201, 253, 375, 413]),
+    (11, 580, &[1, 3, 1, 11, 13, 51, 59, 129, 329, 69, 867]),
+    (11, 587, &[1, 3, 7, 15, 7, 17, 95, 207, 7, 579, 1531]),
+    (11, 589, &[1, 1, 7, 9, 19, 9, 101, 169, 353, 235, 745]),
+    (11, 590, &[1, 3, 1, 9, 29, 63, 7, 183, 281, 559, 141]),
+    (11, 601, &[1, 3, 3, 11, 17, 19, 107, 77, 229, 731, 891]),
+    (11, 607, &[1, 3, 5, 3, 27, 1, 25, 149, 313, 849, 55]),
+    (11, 611, &[1, 1, 7, 9, 27, 37, 67, 63, 39, 487, 1987]),
+    (11, 614, &[1, 3, 5, 3, 19, 41, 35, 163, 477, 571, 541]),
+    (11, 617, &[1, 1, 7, 13, 1, 1, 87, 239, 309, 621, 1987]),
+    (11, 618, &[1, 1, 5, 5, 9, 59, 119, 203, 137, 219, 1745]),
+    (11, 625, &[1, 3, 5, 3, 3, 59, 71, 37, 439, 853, 277]),
+    (11, 628, &[1, 3, 1, 7, 19, 45, 41, 253, 145, 299, 1179]),
+    (11, 635, &[1, 3, 7, 3, 11, 5, 115, 113, 145, 605, 1225]),
+    (11, 641, &[1, 1, 3, 5, 27, 47, 45, 95, 217, 603, 1941]),
+    (11, 647, &[1, 1, 3, 13, 13, 49, 59, 221, 457, 267, 807]),
+    (11, 654, &[1, 3, 3, 5, 7, 39, 83, 21, 33, 583, 1295]),
+    (11, 659, &[1, 3, 7, 15, 27, 3, 75, 179, 103, 815, 1691]),
+    (11, 662, &[1, 1, 1, 15, 31, 31, 7, 227, 27, 17, 225]),
+    (11, 672, &[1, 3, 1, 3, 25, 49, 101, 1, 65, 533, 301]),
+    (11, 675, &[1, 1, 3, 15, 29, 51, 3, 223, 453, 531, 453]),
+    (11, 682, &[1, 1, 7, 9, 29, 21, 27, 111, 263, 435, 1475]),
+    (11, 684, &[1, 1, 7, 9, 7, 57, 23, 213, 353, 633, 455]),
+    (11, 689, &[1, 1, 1, 5, 9, 35, 7, 15, 395, 325, 671]),
+    (11, 695, &[1, 1, 5, 3, 17, 43, 29, 201, 243, 757, 1283]),
+    (11, 696, &[1, 1, 7, 3, 5, 9, 13, 249, 77, 715, 1613]),
+    (11, 713, &[1, 3, 7, 7, 23, 13, 53, 11, 185, 205, 2043]),
+    (11, 719, &[1, 3, 5, 13, 1, 21, 85, 135, 417, 969, 217]),
+    (11, 724, &[1, 3, 5, 5, 5, 57, 115, 65, 337, 335, 1011]),
+    (11, 733, &[1, 3, 1, 11, 21, 41, 75, 133, 249, 655, 1553]),
+    (11, 734, &[1, 3, 5, 13, 17, 43, 79, 169, 235, 213, 1863]),
+    (11, 740, &[1, 1, 1, 3, 29, 5, 57, 171, 373, 213, 1391]),
+    (11, 747, &[1, 1, 3, 13, 7, 51, 47, 247, 387, 587, 715]),
+    (11, 749, &[1, 1, 5, 1, 29, 9, 31, 75, 59, 327, 587]),
+    (11, 752, &[1, 1, 3, 7, 21, 55, 35, 19, 181, 993, 987]),
+    (11, 755, &[1, 1, 3, 7, 1, 15, 99, 245, 387, 791, 1203]),
+    (11, 762, &[1, 3, 3, 11, 31, 23, 41, 225, 105, 15, 625]),
+    (11, 770, &[1, 1, 5, 9, 23, 63, 85, 103, 373, 183, 1465]),
+    (11, 782, &[1, 1, 7, 7, 25, 27, 123, 77, 57, 65, 1073]),
+    (11, 784, &[1, 1, 3, 11, 5, 27, 67, 105, 261, 825, 1977]),
+    (11, 787, &[1, 1, 7, 5, 9, 57, 37, 167, 485, 121, 1739]),
+    (11, 789, &[1, 1, 1, 13, 31, 57, 19, 235, 269, 99, 1765]),
+    (11, 793, &[1, 3, 5, 5, 3, 43, 51, 151, 507, 637, 1335]),
+    (11, 796, &[1, 1, 5, 7, 5, 21, 29, 243, 157, 803, 709]),
+    (11, 803, &[1, 3, 7, 3, 25, 5, 3, 9, 351, 353, 1689]),
+    (11, 805, &[1, 3, 5, 13, 11, 3, 77, 121, 495, 359, 1285]),
+    (11, 810, &[1, 3, 3, 9, 5, 1, 69, 65, 359, 139, 1339]),
+    (11, 815, &[1, 1, 5, 15, 21, 57, 43, 247, 473, 623, 323]),
+    (11, 824, &[1, 3, 1, 3, 23, 61, 45, 161, 65, 913, 1459]),
+    (11, 829, &[1, 3, 1, 13, 21, 35, 67, 35, 449, 703, 369]),
+    (11, 830, &[1, 3, 7, 3, 7, 5, 21, 189, 411, 689, 1053]),
+    (11, 832, &[1, 1, 7, 15, 27, 23, 13, 237, 117, 411, 1195]),
+    (11, 841, &[1, 3, 1, 7, 3, 49, 117, 51, 333, 181, 1183]),
+    (11, 847, &[1, 1, 7, 15, 15, 49, 11, 19, 61, 1009, 695]),
+    (11, 849, &[1, 3, 7, 15, 21, 47, 17, 159, 237, 945, 469]),
+    (11, 861, &[1, 1, 1, 1, 29, 3, 31, 167, 163, 583, 197]),
+    (11, 871, &[1, 1, 7, 1, 17, 37, 43, 177, 445, 439, 807]),
+    (11, 878, &[1, 1, 7, 13, 3, 21, 23, 145, 175, 787, 449]),
+    (11, 889, &[1, 3, 1, 13, 13, 61, 13, 13, 449, 589, 249]),
+    (11, 892, &[1, 1, 3, 15, 11, 13, 111, 75, 337, 931, 1399]),
+    (11, 901, &[1, 1, 1, 9, 19, 53, 55, 121, 313, 295, 243]),
+    (11, 908, &[1, 3, 5, 1, 1, 29, 85, 123, 57, 407, 453]),
+    (11, 920, &[1, 1, 3, 9, 21, 19, 63, 67, 437, 467, 1671]),
+    (11, 923, &[1, 3, 7, 7, 31, 39, 19, 151, 187, 479, 1675]),
+    (11, 942, &[1, 1, 5, 1, 5, 39, 7, 189, 505, 769, 1713]),
+    (11, 949, &[1, 3, 3, 3, 11, 13, 91, 63, 37, 743, 301]),
+    (11, 950, &[1, 3, 3, 1, 29, 9, 127, 115, 71, 893, 341]),
+    (11, 954, &[1, 1, 1, 7, 1, 53, 77, 49, 173, 257, 1961]),
+    (11, 961, &[1, 3, 7, 15, 7, 39, 115, 173, 17, 765, 979]),
+    (11, 968, &[1, 3, 7, 9, 9, 17, 81, 145, 403, 699, 1163]),
+    (11, 971, &[1, 3, 1, 13, 1, 9, 17, 185, 73, 417, 2047]),
+    (11, 973, &[1, 3, 1, 3, 25, 53, 73, 71, 1, 757, 487]),
+    (11, 979, &[1, 3, 3, 9, 23, 41, 13, 7, 203, 1001, 949]),
+    (11, 982, &[1, 1, 3, 15, 7, 61, 125, 37, 383, 189, 321]),
+    (11, 986, &[1, 1, 7, 11, 5, 13, 41, 67, 451, 963, 1513]),
+    (11, 998, &[1, 1, 5, 5, 27, 13, 23, 81, 89, 147, 1253]),
+    (11, 1001, &[1, 1, 1, 3, 19, 15, 5, 195, 87, 141, 1187]),
+    (11, 1010, &[1, 3, 1, 7, 11, 45, 47, 41, 295, 969, 1825]),
+    (11, 1012, &[1, 1, 7, 3, 17, 41, 51, 153, 305, 757, 35]),
+    (12, 41, &[1, 1, 5, 13, 29, 19, 89, 67, 459, 89, 1723, 811]),
+    (12, 52, &[1, 1, 7, 9, 7, 41, 81, 25, 39, 31, 1207, 3559]),
+    (12, 61, &[1, 1, 7, 5, 15, 49, 51, 53, 115, 521, 1061, 1045]),
+    (12, 62, &[1, 3, 5, 9, 21, 7, 121, 179, 183, 583, 709, 241]),
+    (12, 76, &[1, 1, 5, 13, 17, 35, 87, 219, 79, 581, 1295, 655]),
+    (12, 104, &[1, 1, 5, 13, 3, 61, 45, 53, 417, 377, 437, 3991]),
+    (12, 117, &[1, 3, 5, 3, 19, 5, 13, 191, 333, 593, 831, 2923]),
+    (12, 131, &[1, 3, 5, 13, 7, 19, 67, 1, 127, 99, 1545, 1139]),
+    (12, 143, &[1, 1, 1, 1, 3, 53, 101, 111, 141, 637, 521, 467]),
+    (12, 145, &[1, 1, 1, 5, 9, 27, 53, 35, 413, 731, 927, 3209]),
+    (12, 157, &[1, 1, 7, 3, 15, 11, 5, 97, 325, 793, 1385, 3177]),
+    (12, 167, &[1, 3, 7, 15, 17, 35, 127, 13, 367, 609, 643, 899]),
+    (12, 171, &[1, 1, 5, 1, 27, 41, 93, 25, 397, 359, 541, 345]),
+    (12, 176, &[1, 1, 7, 13, 7, 21, 69, 237, 73, 17, 1609, 111]),
+    (12, 181, &[1, 3, 1, 15, 21, 55, 37, 111, 247, 437, 1339, 779]),
+    (12, 194, &[1, 1, 1, 15, 19, 7, 53, 183, 443, 253, 21, 3139]),
+    (12, 217, &[1, 3, 7, 5, 25, 33, 19, 139, 15, 587, 105, 2377]),
+    (12, 236, &[1, 1, 1, 11, 3, 29, 7, 33, 117, 133, 91, 2677]),
+    (12, 239, &[1, 1, 1, 13, 13, 47, 21, 89, 55, 353, 109, 2673]),
+    (12, 262, &[1, 1, 7, 15, 31, 5, 89, 213, 269, 937, 1759, 2895]),
+    (12, 283, &[1, 3, 1, 5, 13, 53, 55, 115, 495, 271, 1333, 1819]),
+    (12, 286, &[1, 3, 1, 9, 15, 39, 117, 213, 241, 237, 1707, 1947]),
+    (12, 307, &[1, 1, 1, 1, 5, 7, 81, 113, 459, 663, 737, 2329]),
+    (12, 313, &[1, 3, 5, 1, 11, 13, 77, 71, 235, 553, 1943, 855]),
+    (12, 319, &[1, 1, 7, 1, 11, 37, 119, 9, 231, 223, 1665, 3401]),
+    (12, 348, &[1, 3, 7, 15, 31, 19, 67, 201, 65, 911, 745, 1707]),
+    (12, 352, &[1, 1, 3, 1, 5, 45, 41, 165, 427, 853, 1469, 1345]),
+    (12, 357, &[1, 3, 5, 7, 19, 19, 63, 187, 221, 605, 1945, 2653]),
+    (12, 391, &[1, 3, 1, 13, 15, 7, 11, 247, 395, 689, 1299, 1137]),
+    (12, 398, &[1, 3, 5, 7, 5, 49, 27, 109, 165, 329, 969, 1095]),
+    (12, 400, &[1, 1, 5, 9, 5, 47, 11, 71, 75, 843, 1625, 3519]),
+    (12, 412, &[1, 3, 7, 11, 25, 59, 95, 61, 321, 31, 1789, 233]),
+    (12, 415, &[1, 3, 7, 9, 1, 55, 75, 175, 189, 329, 555, 2861]),
+    (12, 422, &[1, 3, 1, 13, 17, 37, 123, 125, 163, 491, 183, 2613]),
+    (12, 440, &[1, 1, 3, 9, 27, 61, 109, 247, 15, 395, 947, 1343]),
+    (12, 460, &[1, 1, 5, 7, 29, 51, 37, 31, 313, 259, 1269, 945]),
+    (12, 465, &[1, 3, 1, 11, 25, 31, 9, 105, 503, 489, 231, 611]),
+    (12, 468, &[1, 1, 7, 11, 25, 13, 67, 207, 161, 967, 183, 2031]),
+    (12, 515, &[1, 1, 1, 9, 15, 31, 9, 47, 387, 551, 1825, 3935]),
+    (12, 536, &[1, 1, 1, 5, 1, 5, 43, 137, 97, 33, 79, 3917]),
+    (12, 539, &[1, 1, 5, 13, 5, 59, 123, 253, 277, 227, 1359, 3141]),
+    (12, 551, &[1, 3, 1, 13, 11, 45, 87, 131, 207, 499, 145, 2285]),
+    (12, 558, &[1, 3, 1, 1, 1, 33, 87, 43, 387, 319, 865, 3393]),
+    (12, 563, &[1, 3, 3, 1, 13, 1, 97, 181, 205, 265, 1089, 2739]),
+    (12, 570, &[1, 3, 7, 7, 21, 29, 5, 69, 75, 753, 1427, 2491]),
+    (12, 595, &[1, 3, 5, 9, 27, 3, 57, 155, 97, 865, 549, 2577]),
+    (12, 598, &[1, 1, 7, 5, 25, 5, 119, 211, 33, 365, 2047, 1533]),
+    (12, 617, &[1, 1, 5, 7, 15, 53, 89, 143, 395, 699, 1117, 3139]),
+    (12, 647, &[1, 1, 5, 3, 1, 27, 121, 35, 97, 627, 1147, 1285]),
+    (12, 654, &[1, 3, 7, 13, 5, 61, 81, 159, 385, 817, 1979, 2989]),
+    (12, 678, &[1, 1, 7, 15, 5, 23, 39, 191, 457, 609, 1769, 2933]),
+    (12, 713, &[1, 1, 5, 13, 17, 29, 21, 203, 41, 339, 1757, 333]),
+    (12, 738, &[1, 1, 3, 9, 7, 27, 121, 207, 15, 511, 467, 2895]),
+    (12, 747, &[1, 1, 1, 11, 23, 23, 117, 81, 339, 551, 313, 2557]),
+    (12, 750, &[1, 3, 1, 9, 31, 23, 33, 151, 77, 67, 1817, 2099]),
+    (12, 757, &[1, 1, 1, 7, 21, 9, 81, 51, 307, 265, 891, 2599]),
+    (12, 772, &[1, 3, 3, 3, 27, 17, 23, 169, 371, 25, 1965, 3743]),
+    (12, 803, &[1, 3, 5, 13, 21, 53, 115, 7, 39, 283, 1735, 561]),
+    (12, 810, &[1, 3, 5, 7, 5, 31, 69, 55, 73, 677, 1987, 169]),
+    (12, 812, &[1, 3, 1, 3, 19, 37, 19, 39, 129, 129, 1503, 373]),
+    (12, 850, &[1, 3, 1, 3, 21, 37, 87, 101, 373, 989, 587, 943]),
+    (12, 862, &[1, 1, 5, 9, 27, 5, 123, 121, 87, 71, 143, 1293]),
+    (12, 906, &[1, 1, 5, 5, 31, 63, 43, 113, 101, 253, 411, 1145]),
+    (12, 908, &[1, 3, 7, 3, 1, 57, 77, 47, 341, 519, 1505, 45]),
+    (12, 929, &[1, 1, 3, 9, 25, 61, 35, 15, 91, 443, 885, 2267]),
+    (12, 930, &[1, 3, 7, 1, 27, 35, 121, 115, 261, 3, 281, 2971]),
+    (12, 954, &[1, 1, 3, 7, 9, 5, 29, 83, 137, 111, 845, 887]),
+    (12, 964, &[1, 3, 1, 11, 1, 23, 15, 5, 279, 789, 433, 2417]),
+    (12, 982, &[1, 1, 5, 1, 13, 31, 109, 45, 179, 149, 181, 693]),
+    (12, 985, &[1, 1, 5, 5, 23, 37, 115, 185, 487, 977, 1381, 345]),
+    (12, 991, &[1, 1, 5, 11, 25, 57, 81, 71, 227, 201, 521, 2421]),
+    (12, 992, &[1, 1, 5, 1, 7, 55, 119, 193, 451, 691, 723, 563]),
+    (12, 1067, &[1, 1, 1, 9, 23, 31, 81, 199, 417, 139, 1357, 2647]),
+    (12, 1070, &[1, 1, 3, 11, 3, 33, 73, 31, 143, 231, 343, 2543]),
+    (12, 1096, &[1, 3, 5, 7, 17, 11, 1, 109, 367, 979, 1695, 1283]),
+    (12, 1099, &[1, 3, 1, 15, 13, 49, 89, 147, 257, 517, 737, 3555]),
+    (12, 1116, &[1, 1, 7, 5, 17, 9, 7, 105, 395, 235, 765, 3551]),
+    (12, 1143, &[1, 3, 7, 11, 19, 55, 101, 241, 395, 263, 949, 2857]),
+    (12, 1165, &[1, 1, 3, 3, 23, 41, 119, 89, 89, 635, 1671, 1111]),
+    (12, 1178, &[1, 1, 7, 15, 23, 15, 127, 79, 293, 881, 1535, 2965]),
+    (12, 1184, &[1, 3, 1, 1, 5, 41, 77, 227, 303, 903, 2025, 2545]),
+    (12, 1202, &[1, 1, 3, 11, 3, 3, 119, 47, 163, 991, 1321, 3653]),
+    (12, 1213, &[1, 3, 7, 1, 17, 53, 71, 223, 253, 381, 865, 1559]),
+    (12, 1221, &[1, 3, 7, 11, 25, 11, 29, 183, 363, 33, 993, 2979]),
+    (12, 1240, &[1, 3, 3, 9, 1, 29, 47, 39, 451, 573, 1985, 4057]),
+    (12, 1246, &[1, 3, 7, 1, 13, 47, 61, 133, 91, 435, 1647, 1223]),
+    (12, 1252, &[1, 1, 5, 1, 29, 37, 9, 39, 483, 543, 1025, 3579]),
+    (12, 1255, &[1, 3, 7, 1, 13, 53, 115, 121, 299, 643, 131, 3485]),
+    (12, 1267, &[1, 1, 1, 1, 1, 29, 123, 143, 37, 739, 621, 3999]),
+    (12, 1293, &[1, 3, 7, 7, 1, 25, 7, 125, 477, 549, 1389, 3607]),
+    (12, 1301, &[1, 1, 5, 13, 15, 3, 85, 79, 409, 531, 41, 417]),
+    (12, 1305, &[1, 3, 1, 9, 17, 39, 37, 241, 207, 623, 771, 3297]),
+    (12, 1332, &[1, 3, 7, 15, 29, 1, 109, 229, 447, 385, 7, 1769]),
+    (12, 1349, &[1, 1, 7, 9, 27, 57, 89, 55, 253, 715, 1561, 3773]),
+    (12, 1384, &[1, 1, 1, 13, 7, 55, 113, 195, 451, 999, 1829, 3203]),
+    (12, 1392, &[1, 3, 7, 5, 21, 51, 1, 23, 37, 483, 423, 1933]),
+    (12, 1402, &[1, 3, 3, 7, 27, 23, 33, 65, 485, 533, 1863, 2591]),
+    (12, 1413, &[1, 1, 1, 9, 21, 61, 63, 203, 339, 115, 233, 1751]),
+    (12, 1417, &[1, 1, 5, 1, 1, 61, 89, 215, 215, 427, 909, 1133]),
+    (12, 1423, &[1, 3, 1, 7, 1, 53, 79, 45, 31, 553, 193, 1341]),
+    (12, 1451, &[1, 3, 3, 3, 17, 33, 69, 195, 397, 921, 313, 563]),
+    (12, 1480, &[1, 1, 7, 1, 19, 1, 33, 197, 1, 893, 1409, 2127]),
+    (12, 1491, &[1, 3, 1, 9, 13, 57, 89, 157, 463, 733, 1143, 1175]),
+    (12, 1503, &[1, 3, 7, 11, 29, 59, 5, 209, 247, 519, 231, 2887]),
+    (12, 1504, &[1, 3, 3, 1, 1, 27, 93, 225, 441, 349, 1253, 3191]),
+    (12, 1513, &[1, 3, 7, 15, 7, 29, 41, 17, 87, 571, 509, 4047]),
+    (12, 1538, &[1, 1, 1, 3, 1, 35, 25, 245, 319, 327, 1335, 871]),
+    (12, 1544, &[1, 1, 3, 5, 23, 43, 57, 231, 425, 173, 857, 2849]),
+    (12, 1547, &[1, 3, 5, 3, 9, 9, 33, 63, 261, 241, 1715, 1753]),
+    (12, 1555, &[1, 3, 7, 15, 17, 9, 87, 25, 437, 421, 165, 2737]),
+    (12, 1574, &[1, 3, 5, 5, 15, 55, 113, 95, 101, 339, 975, 1827]),
+    (12, 1603, &[1, 1, 7, 9, 9, 29, 101, 103, 149, 847, 505, 3767]),
+    (12, 1615, &[1, 3, 1, 15, 7, 27, 91, 113, 423, 897, 1895, 703]),
+    (12, 1618, &[1, 1, 1, 11, 19, 15, 37, 221, 423, 695, 29, 3299]),
+    (12, 1629, &[1, 3, 7, 7, 27, 7, 11, 115, 211, 643, 1873, 3753]),
+    (12, 1634, &[1, 3, 3, 1, 29, 5, 69, 239, 249, 877, 2031, 25]),
+    (12, 1636, &[1, 3, 7, 13, 5, 55, 21, 7, 25, 965, 1293, 1757]),
+    (12, 1639, &[1, 3, 1, 11, 25, 47, 85, 147, 225, 973, 1075, 4031]),
+    (12, 1657, &[1, 1, 1, 13, 11, 35, 15, 165, 475, 71, 1419, 1739]),
+    (12, 1667, &[1, 1, 3, 15, 19, 45, 5, 9, 123, 101, 1475, 3733]),
+    (12, 1681, &[1, 1, 7, 3, 29, 37, 59, 7, 53, 701, 1991, 1821]),
+    (12, 1697, &[1, 3, 3, 5, 17, 37, 103, 41, 165, 113, 647, 2607]),
+    (12, 1704, &[1, 1, 5, 3, 15, 61, 67, 245, 439, 125, 725, 1051]),
+    (12, 1709, &[1, 3, 1, 1, 9, 15, 71, 99, 505, 605, 259, 1533]),
+    (12, 1722, &[1, 1, 7, 9, 3, 21, 25, 183, 123, 35, 875, 309]),
+    (12, 1730, &[1, 1, 3, 3, 1, 33, 25, 233, 495, 357, 1815, 3137]),
+    (12, 1732, &[1, 3, 5, 9, 31, 19, 93, 35, 267, 225, 553, 1009]),
+    (12, 1802, &[1, 1, 3, 11, 3, 27, 81, 253, 125, 343, 973, 3861]),
+    (12, 1804, &[1, 1, 5, 1, 29, 29, 97, 189, 199, 5, 379, 969]),
+    (12, 1815, &[1, 1, 7, 11, 19, 19, 109, 181, 69, 629, 1347, 2661]),
+    (12, 1826, &[1, 1, 7, 11, 15, 57, 39, 203, 235, 805, 73, 1391]),
+    (12, 1832, &[1, 1, 3, 11, 31, 17, 45, 159, 117, 123, 1661, 3543]),
+    (12, 1843, &[1, 3, 1, 13, 15, 3, 117, 225, 357, 161, 1987, 2919]),
+    (12, 1849, &[1, 3, 1, 7, 1, 43, 15, 241, 427, 499, 1071, 1877]),
+    (12, 1863, &[1, 3, 5, 11, 31, 37, 49, 169, 419, 151, 1555, 1751]),
+    (12, 1905, &[1, 1, 7, 13, 25, 59, 121, 95, 263, 867, 1503, 383]),
+    (12, 1928, &[1, 3, 1, 15, 17, 25, 1, 213, 305, 55, 611, 1147]),
+    (12, 1933, &[1, 3, 7, 11, 5, 37, 39, 149, 197, 657, 1841, 3171]),
+    (12, 1939, &[1, 3, 3, 5, 23, 41, 115, 7, 481, 269, 1957, 3621]),
+    (12, 1976, &[1, 1, 1, 7, 5, 5, 123, 217, 205, 39, 113, 3663]),
+    (12, 1996, &[1, 1, 7, 5, 11, 33, 77, 155, 175, 211, 1133, 65]),
+    (12, 2013, &[1, 3, 5, 5, 31, 15, 49, 181, 155, 617, 1291, 1221]),
+    (12, 2014, &[1, 3, 3, 1, 9, 31, 77, 153, 161, 523, 1425, 1903]),
+    (12, 2020, &[1, 3, 3, 1, 21, 19, 125, 209, 263, 369, 1625, 1315]),
+    (13, 13, &[1, 1, 3, 7, 11, 63, 13, 237, 475, 931, 1887, 2435, 4779]),
+    (13, 19, &[1, 3, 5, 3, 3, 59, 43, 193, 107, 219, 1833, 2011, 5261]),
+    (13, 26, &[1, 1, 5, 1, 23, 11, 69, 145, 193, 9, 1775, 2677, 1687]),
+    (13, 41, &[1, 1, 1, 7, 27, 55, 67, 51, 481, 677, 1429, 3997, 5121]),
+    (13, 50, &[1, 3, 3, 9, 29, 47, 5, 231, 179, 105, 1437, 3045, 2151]),
+    (13, 55, &[1, 3, 3, 15, 5, 59, 47, 91, 17, 745, 1081, 1097, 4705]),
+    (13, 69, &[1, 1, 1, 9, 25, 11, 21, 95, 33, 583, 1103, 3313, 2471]),
+    (13, 70, &[1, 3, 5, 15, 31, 39, 105, 105, 317, 895, 1679, 1847, 1949]),
+    (13, 79, &[1, 1, 3, 13, 31, 51, 99, 221, 369, 893, 1513, 3147, 6927]),
+    (13, 82, &[1, 3, 3, 13, 27, 9, 123, 41, 323, 11, 703, 3491, 1947]),
+    (13, 87, &[1, 3, 7, 13, 11, 45, 107, 77, 251, 237, 545, 4035, 3203]),
+    (13, 93, &[1, 1, 1, 13, 13, 29, 23, 211, 271, 527, 1019, 527, 4273]),
+    (13, 94, &[1, 1, 5, 15, 21, 43, 23, 227, 441, 11, 677, 1085, 7533]),
+    (13, 97, &[1, 1, 3, 1, 11, 25, 59, 23, 125, 325, 767, 3029, 7969]),
+    (13, 100, &[1, 3, 7, 9, 19, 1, 89, 69, 269, 107, 1787, 195, 1319]),
+    (13, 112, &[1, 3, 1, 1, 13, 63, 9, 11, 63, 635, 1259, 3559, 4101]),
+    (13, 121, &[1, 3, 3, 9, 29, 35, 127, 195, 447, 437, 1061, 379, 1205]),
+    (13, 134, &[1, 1, 7, 13, 29, 31, 93, 147, 163, 233, 539, 443, 635]),
+    (13, 138, &[1, 1, 5, 9, 11, 61, 121, 3, 233, 975, 915, 1261, 869]),
+    (13, 148, &[1, 1, 7, 1, 25, 57, 75, 13, 49, 667, 647, 2119, 6259]),
+    (13, 151, &[1, 1, 1, 1, 17, 3, 75, 201, 345, 1019, 911, 1439, 1203]),
+    (13, 157, &[1, 3, 7, 11, 3, 31, 111, 237, 275, 505, 37, 615, 2819]),
+    (13, 161, &[1, 3, 3, 15, 1, 59, 39, 55, 135, 43, 265, 733, 5031]),
+    (13, 179, &[1, 3, 1, 3, 7, 25, 15, 167, 229, 273, 1949, 3943, 5407]),
+    (13, 181, &[1, 3, 7, 7, 9, 43, 7, 1, 405, 267, 431, 1169, 5655]),
+    (13, 188, &[1, 3, 7, 3, 9, 17, 67, 223, 435, 661, 579, 3707, 2501]),
+    (13, 196, &[1, 3, 7, 9, 25, 47, 81, 223, 35, 293, 477, 527, 5875]),
+    (13, 203, &[1, 3, 5, 7, 27, 5, 113, 197, 173, 163, 403, 2259, 5513]),
+    (13, 206, &[1, 3, 3, 11, 7, 57, 67, 73, 33, 749, 879, 255, 2127]),
+    (13, 223, &[1, 1, 7, 7, 15, 53, 89, 217, 55, 147, 1657, 3383, 8007]),
+    (13, 224, &[1, 3, 1, 13, 7, 17, 101, 63, 199, 813, 145, 1341, 7379]),
+    (13, 227, &[1, 1, 1, 15, 15, 25, 3, 175, 329, 243, 1765, 3597, 583]),
+    (13, 230, &[1, 3, 5, 1, 17, 45, 125, 121, 265, 1009, 279, 405, 6489]),
+    (13, 239, &[1, 1, 7, 11, 13, 35, 51, 137, 351, 223, 1295, 2031, 7333]),
+    (13, 241, &[1, 3, 5, 9, 13, 61, 75, 193, 261, 783, 1497, 3493, 765]),
+    (13, 248, &[1, 3, 1, 7, 17, 51, 81, 115, 245, 253, 1023, 1773, 695]),
+    (13, 253, &[1, 1, 1, 1, 29, 43, 19, 215, 169, 835, 933, 207, 2911]),
+    (13, 268, &[1, 1, 7, 7, 1, 1, 19, 5, 431, 269, 711, 2625, 1615]),
+    (13, 274, &[1, 1, 1, 1, 27, 23, 47, 195, 193, 617, 403, 901, 6511]),
+    (13, 283, &[1, 1, 3, 3, 5, 57, 113, 195, 265, 443, 1267, 3945, 6879]),
+    (13, 286, &[1, 3, 3, 15, 21, 27, 25, 41, 253, 257, 1831, 481, 237]),
+    (13, 289, &[1, 1, 3, 13, 31, 43, 1, 61, 81, 357, 927, 3361, 6503]),
+    (13, 301, &[1, 3, 1, 3, 17, 9, 71, 187, 433, 801, 1355, 1015, 2671]),
+    (13, 302, &[1, 3, 7, 15, 9, 25, 33, 189, 369, 3, 1273, 1319, 3341]),
+    (13, 316, &[1, 1, 1, 1, 3, 7, 43, 181, 9, 465, 1107, 4067, 6899]),
+    (13, 319, &[1, 3, 1, 11, 5, 63, 17, 55, 175, 923, 1603, 1617, 2571]),
+    (13, 324, &[1, 3, 5, 13, 5, 21, 121, 197, 63, 405, 1847, 3319, 1097]),
+    (13, 331, &[1, 1, 7, 3, 17, 27, 127, 35, 441, 901, 309, 487, 7487]),
+    (13, 333, &[1, 1, 5, 9, 21, 63, 37, 193, 379, 249, 1749, 1683, 2143]),
+    (13, 345, &[1, 3, 1, 11, 3, 17, 109, 51, 137, 525, 511, 2927, 6483]),
+    (13, 351, &[1, 1, 7, 11, 15, 37, 3, 251, 423, 363, 1813, 1679, 1307]),
+    (13, 358, &[1, 3, 3, 11, 27, 39, 75, 171, 413, 459, 1617, 3765, 4261]),
+    (13, 375, &[1, 3, 5, 13, 29, 57, 45, 239, 397, 21, 155, 3765, 2849]),
+    (13, 379, &[1, 1, 7, 13, 1, 11, 115, 105, 451, 361, 121, 1895, 6045]),
+    (13, 381, &[1, 3, 7, 1, 23, 5, 69, 47, 111, 651, 1845, 385, 5747]),
+    (13, 386, &[1, 3, 5, 1, 15, 23, 119, 183, 467, 857, 1767, 253, 7345]),
+    (13, 403, &[1, 1, 7, 5, 31, 47, 67, 69, 313, 693, 841, 341, 6899]),
+    (13, 405, &[1, 3, 3, 3, 1, 35, 43, 1, 397, 357, 1037, 2485, 349]),
+    (13, 419, &[1, 1, 3, 9, 3, 21, 73, 117, 505, 415, 929, 359, 1447]),
+    (13, 426, &[1, 3, 3, 9, 23, 7, 41, 71, 213, 467, 223, 3617, 2543]),
+    (13, 428, &[1, 3, 1, 5, 17, 25, 15, 233, 391, 251, 49, 1453, 6943]),
+    (13, 439, &[1, 3, 1, 5, 9, 39, 75, 15, 365, 481, 1539, 3523, 1033]),
+    (13, 440, &[1, 3, 1, 1, 27, 35, 113, 49, 13, 821, 17, 3807, 6197]),
+    (13, 446, &[1, 1, 1, 15, 23, 5, 37, 255, 105, 5, 191, 2557, 7595]),
+    (13, 451, &[1, 3, 7, 15, 29, 31, 11, 185, 7, 923, 333, 211, 5009]),
+    (13, 454, &[1, 1, 3, 1, 19, 53, 119, 85, 327, 459, 455, 1041, 6955]),
+    (13, 458, &[1, 1, 7, 5, 27, 17, 103, 123, 439, 611, 513, 271, 3017]),
+    (13, 465, &[1, 3, 5, 13, 13, 3, 25, 251, 15, 999, 1687, 1439, 2713]),
+    (13, 468, &[1, 1, 1, 7, 21, 25, 59, 33, 467, 415, 377, 1241, 4725]),
+    (13, 472, &[1, 3, 1, 15, 5, 11, 83, 221, 385, 161, 2037, 713, 4735]),
+    (13, 475, &[1, 3, 5, 3, 13, 49, 9, 183, 469, 289, 1595, 1127, 2997]),
+    (13, 477, &[1, 1, 1, 5, 11, 23, 21, 227, 163, 459, 1457, 3031, 3967]),
+    (13, 496, &[1, 1, 1, 9, 11, 63, 93, 215, 319, 481, 153, 3275, 5491]),
+    (13, 502, &[1, 3, 7, 13, 31, 35, 81, 97, 233, 303, 131, 2057, 3595]),
+    (13, 508, &[1, 1, 7, 7, 15, 29, 29, 169, 191, 993, 365, 1451, 4301]),
+    (13, 517, &[1, 3, 1, 1, 7, 23, 45, 5, 263, 327, 1155, 3011, 1687]),
+    (13, 521, &[1, 3, 5, 7, 11, 3, 53, 75, 167, 27, 121, 1621, 1887]),
+    (13, 527, &[1, 1, 5, 11, 7, 57, 39, 91, 61, 51, 1067, 973, 5875]),
+    (13, 530, &[1, 3, 5, 3, 27, 31, 103, 101, 233, 391, 1379, 1111, 4271]),
+    (13, 532, &[1, 3, 3, 7, 21, 45, 49, 235, 467, 33, 1155, 3935, 3545]),
+    (13, 542, &[1, 3, 7, 5, 17, 61, 67, 65, 499, 211, 1789, 71, 3917]),
+    (13, 552, &[1, 3, 1, 1, 11, 37, 71, 103, 37, 445, 1343, 2803, 1883]),
+    (13, 555, &[1, 1, 3, 13, 15, 3, 123, 29, 485, 61, 147, 3133, 7349]),
+    (13, 560, &[1, 3, 7, 9, 23, 3, 11, 23, 161, 419, 1139, 401, 3385]),
+    (13, 566, &[1, 3, 7, 9, 15, 61, 45, 119, 365, 437, 719, 1689, 611]),
+    (13, 575, &[1, 1, 1, 13, 21, 63, 21, 225, 457, 185, 1665, 871, 703]),
+    (13, 577, &[1, 1, 1, 7, 31, 29, 105, 93, 415, 199, 535, 3433, 1987]),
+    (13, 589, &[1, 1, 7, 3, 11, 33, 83, 131, 503, 471, 1179, 895, 3123]),
+    (13, 590, &[1, 1, 1, 5, 1, 21, 121, 25, 495, 597, 347, 3165, 1775]),
+    (13, 602, &[1, 1, 3, 5, 15, 59, 41, 209, 345, 415, 499, 1749, 2563]),
+    (13, 607, &[1, 3, 5, 15, 31, 53, 93, 63, 23, 767, 155, 3607, 4477]),
+    (13, 608, &[1, 1, 1, 11, 3, 45, 43, 53, 119, 631, 1545, 3025, 525]),
+    (13, 611, &[1, 3, 5, 1, 1, 19, 125, 33, 117, 807, 767, 3667, 1443]),
+    (13, 613, &[1, 3, 5, 13, 9, 13, 61, 243, 293, 485, 1639, 2219, 4325]),
+    (13, 625, &[1, 1, 5, 13, 31, 1, 23, 241, 485, 165, 53, 611, 5249]),
+    (13, 644, &[1, 3, 3, 9, 31, 15, 55, 83, 421, 691, 333, 2417, 2691]),
+    (13, 651, &[1, 3, 1, 9, 29, 63, 109, 9, 7, 133, 513, 835, 1357]),
+    (13, 654, &[1, 3, 3, 5, 17, 47, 125, 121, 389, 317, 1723, 2499, 4383]),
+    (13, 656, &[1, 3, 1, 5, 5, 33, 69, 3, 185, 89, 157, 3691, 785]),
+    (13, 662, &[1, 3, 1, 1, 9, 29, 69, 87, 67, 229, 157, 2223, 743]),
+    (13, 668, &[1, 1, 7, 11, 13, 25, 5, 239, 451, 497, 1313, 1699, 829]),
+    (13, 681, &[1, 1, 5, 7, 29, 37, 57, 159, 141, 323, 1181, 3661, 2735]),
+    (13, 682, &[1, 1, 1, 9, 25, 13, 127, 163, 239, 813, 421, 383, 6711]),
+    (13, 689, &[1, 3, 7, 5, 21, 11, 31, 97, 235, 605, 1717, 757, 5953]),
+    (13, 696, &[1, 1, 7, 7, 7, 59, 85, 9, 203, 115, 1523, 1501, 315]),
+    (13, 699, &[1, 3, 1, 13, 7, 63, 19, 99, 63, 1009, 1655, 383, 7855]),
+    (13, 707, &[1, 1, 1, 9, 19, 55, 67, 61, 271, 103, 1925, 1057, 2709]),
+    (13, 709, &[1, 3, 3, 7, 25, 15, 17, 251, 151, 219, 875, 1987, 5077]),
+    (13, 714, &[1, 1, 7, 9, 9, 37, 63, 181, 57, 753, 183, 2249, 5035]),
+    (13, 716, &[1, 1, 3, 5, 5, 5, 75, 155, 45, 667, 1687, 1681, 7993]),
+    (13, 719, &[1, 1, 1, 5, 5, 53, 127, 17, 499, 595, 1763, 3495, 3337]),
+    (13, 727, &[1, 1, 5, 11, 25, 31, 15, 161, 235, 31, 627, 1133, 3525]),
+    (13, 734, &[1, 3, 5, 9, 23, 11, 59, 21, 265, 939, 1497, 3365, 3967]),
+    (13, 738, &[1, 1, 7, 7, 13, 19, 73, 253, 383, 857, 495, 2403, 2485]),
+    (13, 743, &[1, 3, 5, 15, 17, 33, 91, 253, 135, 605, 2031, 855, 7175]),
+    (13, 747, &[1, 1, 5, 5, 29, 11, 11, 205, 265, 49, 1325, 2923, 4085]),
+    (13, 757, &[1, 1, 1, 3, 31, 33, 105, 73, 421, 707, 1819, 2549, 3787]),
+    (13, 769, &[1, 1, 3, 13, 3, 5, 59, 157, 299, 161, 2007, 3091, 5055]),
+    (13, 770, &[1, 3, 5, 15, 5, 49, 125, 193, 353, 379, 1177, 1645, 1401]),
+    (13, 776, &[1, 3, 1, 5, 15, 11, 97, 27, 111, 371, 1355, 2037, 7983]),
+    (13, 790, &[1, 3, 1, 1, 21, 63, 119, 81, 31, 103, 989, 2273, 5203]),
+    (13, 799, &[1, 3, 7, 7, 17, 49, 35, 129, 287, 3, 253, 1441, 3059]),
+    (13, 805, &[1, 1, 7, 11, 21, 59, 119, 15, 141, 25, 1393, 2881, 5083]),
+    (13, 809, &[1, 3, 7, 7, 5, 41, 51, 217, 283, 935, 345, 2047, 2831]),
+    (13, 812, &[1, 3, 1, 11, 9, 59, 57, 65, 115, 697, 1969, 4027, 4569]),
+    (13, 820, &[1, 3, 3, 3, 17, 21, 65, 171, 113, 49, 1261, 3371, 7355]),
+    (13, 827, &[1, 3, 7, 1, 27, 5, 11, 229, 217, 421, 2017, 3753, 4057]),
+    (13, 829, &[1, 1, 5, 7, 11, 41, 97, 95, 137, 261, 1561, 2825, 8073]),
+    (13, 835, &[1, 3, 1, 11, 19, 9, 97, 79, 35, 903, 1611, 3247, 1005]),
+    (13, 841, &[1, 1, 3, 7, 13, 1, 115, 59, 215, 845, 643, 2303, 5827]),
+    (13, 844, &[1, 1, 7, 5, 15, 21, 89, 87, 123, 725, 1135, 37, 2817]),
+    (13, 856, &[1, 3, 5, 11, 29, 37, 127, 73, 61, 337, 2025, 593, 4017]),
+    (13, 859, &[1, 1, 7, 1, 27, 19, 85, 117, 123, 361, 889, 2341, 3719]),
+    (13, 862, &[1, 1, 3, 15, 11, 47, 57, 101, 317, 939, 919, 2697, 2565]),
+    (13, 865, &[1, 1, 1, 5, 29, 63, 57, 195, 365, 781, 225, 2561, 6023]),
+    (13, 885, &[1, 1, 5, 11, 15, 13, 49, 191, 463, 583, 539, 261, 695]),
+    (13, 890, &[1, 3, 5, 9, 1, 3, 93, 7, 375, 401, 419, 4029, 1017]),
+    (13, 905, &[1, 3, 3, 5, 3, 47, 53, 183, 497, 607, 1469, 3573, 437]),
+    (13, 916, &[1, 3, 3, 13, 3, 23, 31, 131, 19, 707, 623, 3713, 3395]),
+    (13, 925, &[1, 3, 1, 3, 11, 21, 57, 129, 271, 999, 1217, 3159, 4667]),
+    (13, 935, &[1, 1, 5, 11, 21, 53, 105, 87, 63, 791, 1115, 373, 3865]),
+    (13, 939, &[1, 1, 5, 9, 17, 5, 53, 43, 3, 927, 201, 2071, 2507]),
+    (13, 942, &[1, 3, 3, 9, 25, 33, 51, 63, 469, 181, 1747, 1383, 3235]),
+    (13, 949, &[1, 3, 1, 1, 29, 53, 3, 125, 65, 183, 1935, 3849, 4087]),
+    (13, 953, &[1, 1, 1, 13, 25, 61, 67, 49, 115, 957, 811, 2483, 1937]),
+    (13, 956, &[1, 3, 3, 3, 23, 7, 5, 155, 103, 35, 787, 707, 711]),
+    (13, 961, &[1, 1, 1, 7, 7, 47, 43, 151, 73, 725, 1807, 1007, 5587]),
+    (13, 968, &[1, 3, 5, 7, 11, 29, 7, 235, 383, 485, 917, 2245, 425]),
+    (13, 976, &[1, 1, 5, 7, 19, 47, 47, 227, 299, 933, 137, 1051, 6035]),
+    (13, 988, &[1, 1, 5, 1, 15, 51, 123, 249, 265, 7, 1821, 3337, 5215]),
+    (13, 995, &[1, 1, 3, 9, 23, 17, 21, 75, 179, 459, 1053, 1553, 7137]),
+    (13, 997, &[1, 1, 3, 5, 31, 27, 39, 17, 67, 317, 395, 327, 4043]),
+    (13, 1007, &[1, 1, 7, 9, 9, 7, 9, 111, 243, 341, 1077, 827, 6645]),
+    (13, 1015, &[1, 3, 5, 15, 9, 31, 81, 79, 427, 953, 1081, 1213, 103]),
+    (13, 1016, &[1, 1, 3, 13, 15, 39, 91, 59, 377, 693, 489, 717, 2907]),
+    (13, 1027, &[1, 1, 3, 11, 3, 13, 103, 243, 113, 807, 1857, 1265, 373]),
+    (13, 1036, &[1, 3, 1, 3, 3, 49, 67, 141, 327, 223, 1051, 5, 6855]),
+    (13, 1039, &[1, 3, 5, 15, 3, 33, 7, 19, 105, 49, 873, 3277, 5441]),
+    (13, 1041, &[1, 1, 7, 7, 1, 9, 95, 217, 97, 229, 839, 295, 117]),
+    (13, 1048, &[1, 3, 7, 5, 21, 43, 25, 139, 29, 489, 809, 1573, 4559]),
+    (13, 1053, &[1, 3, 5, 7, 19, 21, 33, 187, 43, 843, 1367, 1051, 2731]),
+    (13, 1054, &[1, 1, 5, 11, 19, 3, 77, 81, 415, 781, 457, 2593, 1473]),
+    (13, 1058, &[1, 1, 7, 5, 15, 27, 9, 51, 475, 595, 1801, 1485, 283]),
+    (13, 1075, &[1, 1, 1, 9, 21, 39, 89, 149, 121, 329, 335, 2929, 63]),
+    (13, 1082, &[1, 3, 1, 3, 21, 59, 63, 107, 79, 485, 1077, 2927, 817]),
+    (13, 1090, &[1, 3, 1, 13, 5, 61, 19, 183, 201, 11, 1, 1301, 3265]),
+    (13, 1109, &[1, 1, 7, 11, 3, 55, 73, 133, 277, 395, 1377, 3197, 3821]),
+    (13, 1110, &[1, 3, 3, 3, 7, 19, 63, 15, 427, 453, 1265, 2901, 7269]),
+    (13, 1119, &[1, 1, 1, 1, 25, 59, 59, 59, 105, 977, 1357, 1369, 4813]),
+    (13, 1126, &[1, 3, 7, 3, 11, 57, 95, 51, 395, 971, 1121, 1465, 3969]),
+    (13, 1130, &[1, 1, 7, 9, 17, 57, 17, 83, 79, 331, 291, 1147, 6263]),
+    (13, 1135, &[1, 3, 3, 11, 23, 3, 125, 199, 171, 113, 785, 2047, 5413]),
+    (13, 1137, &[1, 1, 3, 9, 15, 25, 109, 15, 191, 711, 749, 3341, 3487]),
+    (13, 1140, &[1, 1, 7, 15, 7, 27, 49, 165, 271, 323, 591, 4027, 3017]),
+    (13, 1149, &[1, 1, 7, 1, 19, 51, 27, 29, 405, 521, 1373, 2353, 7543]),
+    (13, 1156, &[1, 1, 5, 11, 13, 13, 105, 101, 351, 755, 469, 2135, 1741]),
+    (13, 1159, &[1, 1, 7, 5, 13, 25, 119, 137, 79, 847, 1911, 2021, 1683]),
+    (13, 1160, &[1, 1, 5, 5, 27, 31, 73, 121, 301, 519, 1635, 3361, 6893]),
+    (13, 1165, &[1, 3, 7, 5, 25, 21, 11, 241, 395, 355, 2041, 1673, 7581]),
+    (13, 1173, &[1, 3, 3, 1, 3, 47, 9, 207, 5, 923, 785, 3085, 3807]),
+    (13, 1178, &[1, 1, 1, 13, 13, 53, 107, 181, 149, 343, 679, 1615, 7079]),
+    (13, 1183, &[1, 3, 5, 15, 7, 29, 53, 113, 453, 407, 1031, 3839, 4221]),
+    (13, 1184, &[1, 3, 5, 15, 27, 49, 111, 115, 309, 269, 1753, 443, 6247]),
+    (13, 1189, &[1, 1, 3, 13, 21, 27, 71, 49, 193, 337, 449, 2687, 6463]),
+    (13, 1194, &[1, 3, 1, 15, 15, 37, 95, 63, 487, 385, 847, 213, 4885]),
+    (13, 1211, &[1, 3, 3, 5, 17, 59, 127, 77, 41, 297, 1185, 2503, 6425]),
+    (13, 1214, &[1, 1, 3, 5, 3, 33, 79, 49, 81, 779, 1547, 2021, 4647]),
+    (13, 1216, &[1, 1, 3, 3, 9, 11, 57, 135, 73, 513, 863, 2811, 5691]),
+    (13, 1225, &[1, 3, 1, 1, 15, 5, 1, 161, 427, 505, 2003, 3511, 3635]),
+    (13, 1231, &[1, 1, 1, 11, 21, 39, 81, 69, 393, 627, 649, 2761, 4495]),
+    (13, 1239, &[1, 1, 3, 1, 25, 19, 77, 209, 265, 365, 281, 767, 4639]),
+    (13, 1243, &[1, 1, 5, 3, 29, 49, 23, 231, 399, 591, 1835, 1791, 2727]),
+    (13, 1246, &[1, 3, 3, 11, 29, 23, 61, 201, 47, 933, 353, 3121, 6323]),
+    (13, 1249, &[1, 3, 5, 13, 23, 5, 125, 135, 403, 213, 787, 1765, 7279]),
+    (13, 1259, &[1, 1, 5, 13, 29, 27, 87, 117, 417, 519, 1827, 1745, 4863]),
+    (13, 1273, &[1, 1, 3, 15, 1, 9, 105, 77, 163, 153, 777, 1535, 5993]),
+    (13, 1274, &[1, 3, 3, 3, 3, 41, 105, 195, 465, 371, 1325, 2479, 4469]),
+    (13, 1281, &[1, 3, 5, 3, 3, 57, 57, 45, 355, 819, 1317, 3861, 2547]),
+    (13, 1287, &[1, 3, 3, 9, 7, 27, 13, 7, 13, 155, 1615, 3271, 95]),
+    (13, 1294, &[1, 3, 5, 11, 11, 33, 61, 85, 469, 31, 1863, 353, 2673]),
+    (13, 1296, &[1, 1, 1, 9, 17, 1, 55, 157, 215, 895, 1021, 1937, 7693]),
+    (13, 1305, &[1, 3, 5, 5, 17, 47, 65, 41, 91, 461, 1465, 2583, 7351]),
+    (13, 1306, &[1, 3, 3, 3, 19, 53, 37, 221, 349, 763, 1563, 3269, 53]),
+    (13, 1318, &[1, 1, 3, 3, 15, 43, 121, 23, 209, 841, 1775, 1265, 2215]),
+    (13, 1332, &[1, 1, 1, 5, 9, 11, 39, 39, 9, 625, 1113, 493, 7017]),
+    (13, 1335, &[1, 3, 3, 11, 21, 45, 23, 217, 275, 509, 989, 263, 1721]),
+    (13, 1336, &[1, 1, 1, 9, 29, 47, 89, 197, 89, 47, 91, 1035, 1085]),
+    (13, 1341, &[1, 3, 5, 9, 15, 17, 99, 101, 501, 11, 175, 1985, 5995]),
+    (13, 1342, &[1, 1, 1, 7, 25, 37, 81, 75, 407, 369, 1465, 3135, 3371]),
+    (13, 1362, &[1, 1, 5, 3, 29, 51, 23, 217, 377, 807, 577, 1783, 747]),
+    (13, 1364, &[1, 3, 3, 11, 27, 23, 61, 85, 453, 479, 1951, 3685, 263]),
+    (13, 1368, &[1, 1, 7, 11, 11, 27, 65, 59, 309, 1023, 1201, 2887, 6457]),
+    (13, 1378, &[1, 1, 7, 15, 29, 15, 127, 91, 429, 845, 469, 2827, 3593]),
+    (13, 1387, &[1, 3, 1, 7, 9, 59, 117, 29, 339, 1021, 603, 1875, 6613]),
+    (13, 1389, &[1, 1, 7, 7, 7, 27, 121, 23, 451, 43, 1847, 3823, 569]),
+    (13, 1397, &[1, 3, 7, 11, 25, 39, 5, 29, 33, 965, 889, 2059, 2787]),
+    (13, 1401, &[1, 1, 7, 13, 3, 45, 5, 75, 299, 377, 353, 1635, 2133]),
+    (13, 1408, &[1, 1, 5, 3, 5, 33, 113, 113, 315, 997, 1857, 1979, 7033]),
+    (13, 1418, &[1, 1, 7, 15, 23, 45, 65, 151, 495, 435, 679, 3639, 7497]),
+    (13, 1425, &[1, 3, 5, 13, 19, 13, 55, 209, 175, 413, 1845, 1027, 7379]),
+    (13, 1426, &[1, 3, 7, 7, 27, 51, 13, 191, 345, 49, 1411, 2949, 8133]),
+    (13, 1431, &[1, 1, 3, 13, 3, 17, 91, 17, 333, 959, 407, 2245, 1249]),
+    (13, 1435, &[1, 3, 3, 9, 5, 23, 37, 19, 233, 137, 59, 4085, 3955]),
+    (13, 1441, &[1, 1, 1, 13, 17, 49, 95, 241, 177, 819, 1175, 929, 6895]),
+    (13, 1444, &[1, 3, 1, 3, 11, 13, 119, 25, 179, 79, 1919, 2013, 1685]),
+    (13, 1462, &[1, 3, 5, 3, 11, 1, 97, 123, 153, 313, 1233, 237, 7923]),
+    (13, 1471, &[1, 1, 7, 1, 19, 57, 15, 215, 433, 135, 2013, 3409, 5997]),
+    (13, 1474, &[1, 3, 1, 15, 27, 37, 21, 153, 227, 441, 525, 1063, 1637]),
+    (13, 1483, &[1, 1, 1, 7, 9, 15, 33, 39, 417, 103, 17, 493, 4717]),
+    (13, 1485, &[1, 1, 3, 3, 5, 47, 113, 237, 265, 599, 1677, 2971, 4649]),
+    (13, 1494, &[1, 1, 5, 11, 27, 63, 55, 227, 339, 1, 1287, 2023, 7307]),
+    (13, 1497, &[1, 1, 7, 15, 17, 31, 69, 223, 369, 947, 1901, 2721, 701]),
+    (13, 1516, &[1, 3, 5, 3, 23, 1, 73, 131, 271, 173, 1141, 117, 651]),
+    (13, 1522, &[1, 3, 5, 15, 5, 21, 9, 163, 123, 819, 267, 3821, 705]),
+    (13, 1534, &[1, 3, 3, 7, 31, 33, 25, 43, 441, 983, 1087, 2613, 5797]),
+    (13, 1543, &[1, 1, 1, 11, 9, 59, 127, 43, 435, 225, 1981, 2227, 7617]),
+    (13, 1552, &[1, 3, 5, 9, 21, 25, 37, 93, 233, 281, 587, 2333, 7397]),
+    (13, 1557, &[1, 3, 7, 11, 31, 57, 117, 47, 297, 653, 19, 1183, 539]),
+    (13, 1558, &[1, 3, 3, 11, 1, 35, 115, 177, 3, 955, 1263, 991, 9]),
+    (13, 1567, &[1, 3, 1, 9, 9, 15, 9, 109, 129, 49, 957, 287, 5469]),
+    (13, 1568, &[1, 1, 3, 11, 29, 47, 91, 61, 245, 29, 1189, 3943, 3755]),
+    (13, 1574, &[1, 3, 1, 3, 21, 5, 99, 221, 29, 75, 1471, 1225, 7287]),
+    (13, 1592, &[1, 3, 3, 3, 15, 27, 79, 107, 43, 95, 341, 275, 2895]),
+    (13, 1605, &[1, 3, 1, 11, 3, 35, 1, 201, 371, 797, 49, 3199, 1741]),
+    (13, 1606, &[1, 1, 1, 13, 3, 9, 25, 137, 187, 399, 363, 1441, 455]),
+    (13, 1610, &[1, 1, 3, 1, 7, 53, 3, 251, 173, 309, 771, 2961, 2863]),
+    (13, 1617, &[1, 1, 7, 7, 9, 57, 71, 35, 17, 73, 263, 2089, 2353]),
+    (13, 1623, &[1, 1, 3, 5, 25, 27, 37, 185, 49, 255, 1773, 1305, 7447]),
+    (13, 1630, &[1, 1, 3, 9, 31, 41, 3, 219, 449, 241, 781, 1809, 479]),
+    (13, 1634, &[1, 1, 1, 5, 17, 47, 45, 197, 233, 665, 1331, 3921, 2285]),
+    (13, 1640, &[1, 3, 5, 15, 5, 7, 47, 169, 437, 453, 1565, 2453, 5495]),
+    (13, 1643, &[1, 3, 3, 5, 5, 41, 59, 197, 259, 187, 329, 3141, 5707]),
+    (13, 1648, &[1, 1, 5, 7, 27, 7, 103, 11, 79, 435, 1845, 4025, 1779]),
+    (13, 1651, &[1, 1, 5, 13, 15, 31, 71, 37, 267, 157, 261, 3361, 5883]),
+    (13, 1653, &[1, 1, 5, 15, 29, 63, 101, 163, 471, 709, 1409, 1815, 3897]),
+    (13, 1670, &[1, 1, 3, 7, 15, 11, 39, 145, 231, 347, 1607, 2213, 7751]),
+    (13, 1676, &[1, 1, 1, 9, 29, 31, 57, 147, 99, 919, 983, 3383, 1461]),
+    (13, 1684, &[1, 3, 3, 13, 7, 31, 17, 127, 107, 509, 1609, 3581, 509]),
+    (13, 1687, &[1, 3, 3, 3, 21, 23, 103, 207, 237, 933, 127, 2903, 4565]),
+    (13, 1691, &[1, 1, 5, 11, 5, 33, 49, 9, 133, 981, 2021, 2775, 4995]),
+    (13, 1693, &[1, 3, 5, 11, 17, 41, 9, 13, 147, 433, 1265, 2269, 4685]),
+    (13, 1698, &[1, 1, 3, 15, 9, 11, 93, 49, 109, 661, 1869, 2231, 4551]),
+    (13, 1709, &[1, 1, 7, 5, 19, 49, 103, 155, 303, 729, 565, 137, 2361]),
+    (13, 1715, &[1, 3, 1, 11, 31, 27, 85, 177, 207, 773, 393, 2453, 2425]),
+    (13, 1722, &[1, 1, 7, 3, 9, 25, 39, 217, 355, 281, 1413, 2725, 3401]),
+    (13, 1732, &[1, 1, 1, 5, 1, 61, 69, 225, 27, 863, 1713, 107, 4703]),
+    (13, 1735, &[1, 1, 1, 7, 21, 61, 15, 29, 173, 885, 1011, 3089, 5553]),
+    (13, 1747, &[1, 3, 5, 5, 15, 15, 69, 67, 365, 653, 1143, 2297, 6703]),
+    (13, 1749, &[1, 1, 1, 1, 27, 21, 9, 255, 211, 659, 1193, 1255, 3089]),
+    (13, 1754, &[1, 1, 7, 1, 31, 29, 49, 167, 347, 395, 1271, 2671, 3067]),
+    (13, 1777, &[1, 3, 7, 5, 31, 21, 33, 215, 45, 163, 1175, 4047, 2963]),
+    (13, 1784, &[1, 1, 5, 15, 9, 25, 121, 189, 15, 631, 291, 1201, 235]),
+    (13, 1790, &[1, 3, 5, 1, 15, 41, 107, 175, 361, 93, 1255, 1773, 3887]),
+    (13, 1795, &[1, 1, 1, 11, 5, 21, 53, 117, 231, 1021, 1251, 417, 1003]),
+    (13, 1801, &[1, 3, 7, 13, 17, 29, 71, 151, 19, 921, 1033, 2651, 4119]),
+    (13, 1802, &[1, 1, 3, 9, 21, 57, 95, 197, 321, 457, 1059, 2543, 3099]),
+    (13, 1812, &[1, 1, 7, 9, 21, 31, 5, 181, 43, 397, 705, 1611, 6081]),
+    (13, 1828, &[1, 3, 5, 9, 9, 37, 7, 201, 165, 577, 997, 1141, 2979]),
+    (13, 1831, &[1, 1, 3, 9, 27, 9, 57, 11, 225, 971, 1003, 1965, 6943]),
+    (13, 1837, &[1, 1, 3, 7, 13, 7, 31, 191, 85, 543, 167, 1847, 2693]),
+    (13, 1838, &[1, 1, 7, 11, 7, 45, 27, 235, 145, 421, 131, 3457, 1867]),
+    (13, 1840, &[1, 1, 7, 7, 9, 53, 17, 123, 499, 615, 1425, 3459, 4839]),
+    (13, 1845, &[1, 3, 1, 1, 19, 39, 59, 247, 261, 169, 1533, 4003, 5387]),
+    (13, 1863, &[1, 1, 7, 7, 17, 5, 87, 105, 35, 123, 975, 2141, 6169]),
+    (13, 1864, &[1, 1, 5, 5, 3, 33, 71, 25, 435, 697, 1497, 1117, 2453]),
+    (13, 1867, &[1, 3, 3, 5, 9, 53, 37, 187, 159, 845, 277, 1507, 673]),
+    (13, 1870, &[1, 1, 1, 5, 5, 63, 125, 175, 33, 255, 225, 1213, 6777]),
+    (13, 1877, &[1, 3, 3, 1, 17, 25, 55, 141, 309, 3, 1249, 733, 2049]),
+    (13, 1881, &[1, 3, 7, 11, 23, 37, 101, 141, 263, 101, 805, 3589, 355]),
+    (13, 1884, &[1, 3, 3, 9, 13, 17, 115, 169, 247, 119, 757, 1819, 6873]),
+    (13, 1903, &[1, 3, 1, 3, 23, 23, 97, 203, 293, 431, 1161, 1179, 181]),
+    (13, 1917, &[1, 1, 5, 13, 7, 15, 39, 1, 281, 325, 1405, 3769, 3975]),
+    (13, 1918, &[1, 3, 1, 9, 15, 45, 119, 213, 445, 101, 1003, 209, 957]),
+    (13, 1922, &[1, 1, 5, 5, 25, 61, 1, 53, 91, 615, 1333, 2895, 1527]),
+    (13, 1924, &[1, 3, 5, 3, 27, 51, 61, 147, 187, 181, 1973, 1629, 5479]),
+    (13, 1928, &[1, 1, 3, 9, 13, 53, 25, 207, 445, 813, 95, 259, 7507]),
+    (13, 1931, &[1, 1, 3, 1, 31, 3, 87, 175, 329, 161, 1121, 2509, 4091]),
+    (13, 1951, &[1, 1, 3, 11, 27, 27, 125, 207, 249, 965, 971, 2171, 1437]),
+    (13, 1952, &[1, 1, 7, 13, 11, 41, 43, 223, 465, 907, 1745, 1091, 1969]),
+    (13, 1957, &[1, 3, 1, 5, 1, 19, 41, 207, 225, 465, 1783, 2821, 4235]),
+    (13, 1958, &[1, 3, 7, 7, 25, 1, 79, 187, 417, 345, 533, 3877, 3565]),
+    (13, 1964, &[1, 3, 7, 3, 9, 37, 127, 187, 409, 475, 1593, 135, 3093]),
+    (13, 1967, &[1, 1, 7, 15, 17, 47, 39, 105, 149, 141, 415, 2095, 573]),
+    (13, 1970, &[1, 1, 5, 7, 1, 57, 87, 43, 175, 307, 225, 89, 7977]),
+    (13, 1972, &[1, 3, 7, 3, 17, 29, 43, 181, 329, 177, 559, 1963, 7421]),
+    (13, 1994, &[1, 3, 7, 9, 21, 43, 73, 239, 175, 775, 1811, 1087, 7089]),
+    (13, 2002, &[1, 1, 1, 11, 29, 27, 3, 213, 415, 293, 1549, 2235, 4265]),
+    (13, 2007, &[1, 1, 1, 7, 15, 21, 103, 79, 197, 241, 981, 2673, 1577]),
+    (13, 2008, &[1, 1, 5, 13, 29, 3, 103, 247, 495, 23, 379, 1883, 4859]),
+    (13, 2023, &[1, 3, 3, 7, 7, 57, 79, 47, 17, 275, 499, 3847, 2713]),
+    (13, 2030, &[1, 3, 3, 13, 23, 17, 1, 197, 321, 273, 913, 4017, 7727]),
+    (13, 2035, &[1, 1, 1, 7, 15, 53, 85, 109, 93, 549, 49, 3893, 4941]),
+    (13, 2038, &[1, 1, 1, 5, 7, 51, 23, 209, 129, 865, 2007, 2875, 4391]),
+    (13, 2042, &[1, 3, 5, 3, 5, 11, 109, 55, 453, 667, 1123, 695, 7081]),
+    (13, 2047, &[1, 3, 7, 13, 9, 43, 73, 245, 399, 685, 1571, 929, 833]),
+    (13, 2051, &[1, 1, 5, 5, 27, 11, 109, 39, 323, 899, 163, 4083, 2033]),
+    (13, 2058, &[1, 1, 7, 1, 31, 51, 73, 123, 243, 511, 1619, 1563, 8029]),
+    (13, 2060, &[1, 1, 5, 13, 13, 47, 95, 231, 91, 87, 497, 4057, 3717]),
+    (13, 2071, &[1, 3, 3, 11, 11, 49, 111, 25, 173, 987, 773, 1851, 6929]),
+    (13, 2084, &[1, 1, 5, 3, 3, 29, 99, 27, 435, 135, 661, 3221, 789]),
+    (13, 2087, &[1, 3, 5, 11, 31, 9, 1, 21, 443, 171, 1161, 1401, 7193]),
+    (13, 2099, &[1, 3, 7, 7, 13, 45, 127, 241, 159, 913, 1721, 1169, 3495]),
+    (13, 2108, &[1, 1, 5, 13, 23, 47, 37, 103, 41, 113, 1743, 559, 6011]),
+    (13, 2111, &[1, 3, 7, 11, 15, 45, 59, 243, 207, 849, 1323, 1609, 6113]),
+    (13, 2120, &[1, 1, 1, 9, 21, 11, 83, 7, 499, 89, 1285, 3121, 6865]),
+    (13, 2128, &[1, 3, 3, 7, 19, 13, 69, 177, 259, 537, 1873, 1317, 3621]),
+    (13, 2138, &[1, 3, 1, 3, 13, 3, 53, 37, 405, 227, 1301, 2479, 5231]),
+    (13, 2143, &[1, 3, 7, 11, 19, 35, 57, 151, 3, 117, 501, 1103, 3691]),
+    (13, 2144, &[1, 3, 1, 7, 31, 39, 85, 31, 395, 607, 1865, 1643, 4193]),
+    (13, 2153, &[1, 1, 5, 5, 3, 55, 121, 189, 477, 913, 1287, 2967, 4259]),
+    (13, 2156, &[1, 3, 5, 9, 21, 43, 73, 205, 245, 243, 347, 2237, 7093]),
+    (13, 2162, &[1, 3, 1, 9, 31, 57, 119, 235, 345, 129, 1467, 611, 4055]),
+    (13, 2167, &[1, 3, 7, 9, 5, 27, 83, 87, 13, 427, 2001, 303, 5535]),
+    (13, 2178, &[1, 3, 1, 5, 7, 35, 13, 253, 181, 965, 1023, 2939, 313]),
+    (13, 2183, &[1, 3, 5, 5, 3, 3, 67, 45, 495, 875, 731, 3921, 5719]),
+    (13, 2202, &[1, 3, 3, 15, 5, 27, 13, 231, 287, 953, 769, 385, 5183]),
+    (13, 2211, &[1, 1, 7, 5, 25, 31, 91, 155, 463, 225, 1745, 3531, 7945]),
+    (13, 2214, &[1, 1, 5, 13, 13, 1, 57, 115, 353, 127, 1275, 373, 4857]),
+    (13, 2223, &[1, 1, 5, 1, 9, 7, 5, 45, 209, 73, 135, 3231, 727]),
+    (13, 2225, &[1, 3, 7, 3, 29, 5, 95, 9, 377, 1001, 1171, 873, 7889]),
+    (13, 2232, &[1, 3, 1, 11, 29, 19, 1, 81, 145, 973, 63, 253, 5823]),
+    (13, 2237, &[1, 1, 7, 13, 15, 61, 101, 169, 313, 427, 1521, 1667, 4965]),
+    (13, 2257, &[1, 1, 3, 7, 19, 37, 91, 51, 351, 487, 933, 1479, 4691]),
+    (13, 2260, &[1, 3, 1, 3, 21, 9, 97, 57, 407, 15, 769, 177, 181]),
+    (13, 2267, &[1, 3, 5, 9, 23, 27, 3, 31, 431, 507, 1915, 1355, 5127]),
+    (13, 2274, &[1, 1, 7, 13, 25, 29, 111, 209, 123, 705, 1369, 3239, 1771]),
+    (13, 2276, &[1, 1, 3, 15, 27, 39, 27, 137, 121, 501, 1321, 2523, 4487]),
+    (13, 2285, &[1, 3, 1, 5, 29, 55, 83, 11, 373, 425, 841, 3303, 773]),
+    (13, 2288, &[1, 3, 5, 11, 13, 55, 89, 49, 415, 343, 1363, 1939, 5453]),
+    (13, 2293, &[1, 3, 1, 15, 31, 45, 15, 153, 359, 341, 1005, 1205, 2135]),
+    (13, 2294, &[1, 3, 7, 13, 19, 53, 49, 223, 163, 673, 499, 1179, 7953]),
+    (13, 2297, &[1, 1, 3, 7, 5, 9, 49, 33, 391, 947, 985, 1253, 6825]),
+    (13, 2303, &[1, 3, 7, 1, 29, 53, 93, 125, 341, 1009, 381, 2435, 7143]),
+    (13, 2308, &[1, 1, 3, 5, 1, 39, 103, 229, 453, 551, 909, 541, 3813]),
+    (13, 2311, &[1, 1, 7, 13, 3, 27, 71, 169, 445, 509, 749, 2653, 7337]),
+    (13, 2318, &[1, 3, 7, 1, 11, 47, 103, 217, 465, 813, 191, 2379, 7029]),
+    (13, 2323, &[1, 1, 5, 13, 21, 43, 125, 25, 413, 791, 669, 1113, 1673]),
+    (13, 2332, &[1, 3, 7, 5, 25, 15, 11, 91, 305, 989, 1321, 453, 63]),
+    (13, 2341, &[1, 1, 3, 13, 9, 51, 61, 177, 399, 391, 35, 1195, 1787]),
+    (13, 2345, &[1, 1, 5, 7, 7, 23, 123, 141, 461, 629, 397, 451, 759]),
+    (13, 2348, &[1, 1, 5, 3, 31, 29, 9, 229, 349, 653, 843, 2335, 5137]),
+    (13, 2354, &[1, 3, 1, 15, 5, 35, 15, 41, 423, 837, 1457, 1183, 5197]),
+    (13, 2368, &[1, 1, 1, 1, 21, 7, 33, 237, 243, 11, 119, 2967, 6643]),
+    (13, 2377, &[1, 1, 1, 9, 23, 5, 109, 159, 161, 225, 1911, 1621, 1061]),
+    (13, 2380, &[1, 1, 1, 3, 29, 63, 125, 79, 509, 387, 1181, 2057, 6575]),
+    (13, 2383, &[1, 1, 1, 9, 31, 53, 15, 23, 25, 557, 885, 915, 921]),
+    (13, 2388, &[1, 1, 5, 5, 19, 51, 7, 167, 101, 905, 183, 1655, 1971]),
+    (13, 2395, &[1, 3, 1, 11, 31, 39, 93, 111, 485, 787, 1865, 1045, 1291]),
+    (13, 2397, &[1, 3, 5, 9, 21, 49, 43, 125, 141, 153, 241, 1977, 709]),
+    (13, 2401, &[1, 3, 7, 15, 13, 41, 125, 189, 83, 823, 745, 531, 1583]),
+    (13, 2411, &[1, 3, 3, 3, 11, 49, 37, 203, 283, 897, 1237, 4065, 1743]),
+    (13, 2413, &[1, 1, 5, 1, 11, 43, 45, 33, 81, 75, 1475, 3197, 1935]),
+    (13, 2419, &[1, 3, 7, 9, 1, 15, 73, 145, 461, 889, 861, 2369, 4001]),
+    (13, 2435, &[1, 3, 1, 9, 5, 17, 93, 59, 447, 147, 261, 1485, 141]),
+    (13, 2442, &[1, 1, 5, 11, 19, 51, 61, 41, 465, 523, 1219, 2095, 985]),
+    (13, 2455, &[1, 1, 3, 15, 1, 5, 1, 159, 345, 499, 1311, 3711, 3065]),
+    (13, 2472, &[1, 1, 3, 1, 3, 47, 115, 177, 247, 715, 1583, 519, 4401]),
+    (13, 2478, &[1, 1, 1, 1, 13, 19, 115, 149, 43, 457, 2035, 2133, 1591]),
+    (13, 2490, &[1, 1, 1, 5, 1, 45, 19, 219, 137, 187, 1907, 579, 411]),
+    (13, 2507, &[1, 3, 7, 1, 25, 5, 115, 5, 321, 417, 1105, 811, 2531]),
+    (13, 2509, &[1, 1, 3, 11, 31, 15, 5, 141, 181, 759, 33, 897, 6135]),
+    (13, 2517, &[1, 1, 1, 7, 25, 25, 49, 11, 307, 919, 131, 3345, 7557]),
+    (13, 2524, &[1, 1, 7, 11, 5, 25, 45, 81, 163, 601, 743, 2117, 3733]),
+    (13, 2528, &[1, 1, 7, 13, 15, 39, 31, 195, 169, 363, 375, 2787, 999]),
+    (13, 2531, &[1, 1, 5, 11, 17, 7, 61, 139, 411, 305, 1935, 1669, 1267]),
+    (13, 2538, &[1, 1, 5, 13, 25, 61, 69, 155, 309, 517, 1321, 1571, 7253]),
+    (13, 2545, &[1, 3, 3, 15, 23, 35, 119, 147, 477, 377, 1489, 2343, 2567]),
+    (13, 2546, &[1, 1, 5, 11, 27, 11, 111, 243, 177, 161, 919, 423, 7165]),
+    (13, 2555, &[1, 3, 7, 9, 21, 47, 121, 191, 41, 517, 419, 899, 1393]),
+    (13, 2557, &[1, 3, 7, 11, 9, 55, 35, 227, 115, 1007, 489, 1559, 7315]),
+    (13, 2564, &[1, 1, 1, 5, 31, 53, 49, 207, 381, 573, 451, 2479, 2577]),
+    (13, 2573, &[1, 3, 3, 3, 5, 3, 91, 247, 275, 895, 1551, 1197, 8107]),
+    (13, 2579, &[1, 3, 3, 15, 7, 9, 125, 245, 427, 963, 953, 2939, 7957]),
+    (13, 2592, &[1, 3, 5, 15, 19, 19, 103, 9, 3, 355, 249, 2365, 497]),
+    (13, 2598, &[1, 1, 5, 5, 3, 53, 89, 79, 261, 777, 589, 37, 3167]),
+    (13, 2607, &[1, 3, 5, 7, 15, 59, 95, 235, 399, 121, 2007, 3843, 7709]),
+    (13, 2612, &[1, 3, 7, 3, 31, 13, 109, 15, 371, 463, 1845, 2255, 4337]),
+    (13, 2619, &[1, 1, 3, 9, 7, 47, 35, 65, 205, 943, 493, 1565, 5993]),
+    (13, 2621, &[1, 3, 1, 15, 31, 11, 7, 187, 13, 845, 539, 3475, 3561]),
+    (13, 2627, &[1, 1, 3, 15, 3, 5, 19, 209, 365, 1001, 851, 1459, 6535]),
+    (13, 2633, &[1, 1, 7, 13, 27, 47, 63, 241, 419, 397, 2043, 3905, 2547]),
+    (13, 2636, &[1, 1, 7, 13, 15, 3, 91, 103, 383, 691, 1549, 589, 7085]),
+    (13, 2642, &[1, 3, 1, 15, 15, 49, 75, 157, 57, 677, 1921, 1565, 6405]),
+    (13, 2654, &[1, 3, 3, 7, 31, 37, 75, 161, 157, 639, 1631, 1651, 4875]),
+    (13, 2660, &[1, 3, 7, 1, 31, 27, 59, 141, 213, 869, 281, 4065, 6331]),
+    (13, 2669, &[1, 1, 7, 1, 25, 47, 107, 71, 233, 261, 1345, 19, 4801]),
+    (13, 2675, &[1, 3, 7, 13, 31, 37, 119, 143, 131, 495, 1357, 3973, 8183]),
+    (13, 2684, &[1, 1, 1, 5, 3, 17, 107, 247, 153, 449, 1593, 1861, 7015]),
+    (13, 2694, &[1, 1, 1, 15, 31, 7, 117, 145, 177, 47, 1117, 1395, 3517]),
+    (13, 2703, &[1, 3, 5, 5, 13, 11, 87, 73, 419, 59, 1691, 1663, 4711]),
+    (13, 2706, &[1, 3, 5, 13, 29, 3, 49, 97, 147, 885, 995, 1189, 1865]),
+    (13, 2712, &[1, 1, 1, 1, 27, 41, 125, 131, 405, 429, 1263, 2641, 233]),
+    (13, 2715, &[1, 1, 1, 5, 29, 23, 13, 237, 467, 781, 355, 1549, 7661]),
+    (13, 2722, &[1, 3, 7, 3, 27, 63, 21, 201, 73, 7, 879, 4041, 3609]),
+    (13, 2727, &[1, 1, 1, 9, 23, 41, 27, 121, 287, 347, 391, 1745, 2445]),
+    (13, 2734, &[1, 3, 5, 5, 9, 61, 113, 137, 497, 499, 359, 3375, 3331]),
+    (13, 2742, &[1, 3, 3, 13, 31, 27, 113, 33, 511, 203, 621, 1657, 5725]),
+    (13, 2745, &[1, 3, 7, 15, 9, 17, 31, 205, 489, 555, 1023, 101, 4465]),
+    (13, 2751, &[1, 3, 1, 11, 15, 19, 63, 45, 5, 31, 1213, 3807, 1151]),
+    (13, 2766, &[1, 3, 3, 7, 1, 15, 99, 61, 337, 399, 1813, 3039, 5415]),
+    (13, 2768, &[1, 3, 1, 3, 23, 37, 87, 55, 225, 229, 1317, 3401, 4935]),
+    (13, 2780, &[1, 1, 7, 11, 29, 41, 65, 229, 499, 739, 425, 923, 1641]),
+    (13, 2790, &[1, 1, 5, 1, 19, 55, 3, 195, 3, 307, 847, 2961, 5181]),
+    (13, 2794, &[1, 3, 3, 11, 17, 7, 27, 231, 419, 519, 1481, 2537, 609]),
+    (13, 2796, &[1, 3, 1, 3, 29, 27, 31, 193, 425, 55, 1579, 133, 97]),
+    (13, 2801, &[1, 3, 7, 11, 11, 55, 119, 27, 133, 11, 1779, 3947, 7983]),
+    (13, 2804, &[1, 3, 5, 3, 11, 5, 31, 67, 403, 969, 1589, 3305, 4497]),
+    (13, 2807, &[1, 1, 7, 1, 3, 29, 89, 59, 487, 139, 1235, 3423, 4933]),
+    (13, 2816, &[1, 3, 1, 3, 5, 61, 53, 183, 451, 675, 451, 2183, 4819]),
+    (13, 2821, &[1, 3, 5, 9, 29, 51, 77, 235, 281, 675, 183, 3763, 6313]),
+    (13, 2831, &[1, 1, 5, 13, 9, 35, 65, 81, 469, 433, 459, 303, 1643]),
+    (13, 2834, &[1, 3, 7, 13, 19, 25, 33, 77, 203, 621, 275, 2125, 4623]),
+    (13, 2839, &[1, 3, 3, 9, 7, 47, 7, 33, 347, 653, 417, 1919, 507]),
+    (13, 2845, &[1, 1, 3, 15, 5, 33, 31, 17, 475, 97, 1105, 4055, 6077]),
+    (13, 2852, &[1, 1, 5, 1, 13, 41, 127, 39, 185, 467, 1883, 3861, 3115]),
+    (13, 2856, &[1, 3, 7, 5, 7, 53, 81, 53, 127, 283, 101, 3909, 1595]),
+    (13, 2861, &[1, 3, 1, 3, 3, 19, 15, 165, 213, 289, 1903, 1961, 2947]),
+    (13, 2873, &[1, 3, 1, 11, 17, 39, 25, 255, 81, 169, 657, 2973, 1205]),
+    (13, 2874, &[1, 3, 1, 7, 31, 61, 127, 1, 349, 317, 1321, 3775, 5535]),
+    (13, 2888, &[1, 1, 5, 13, 21, 51, 73, 155, 271, 209, 489, 3335, 4515]),
+    (13, 2893, &[1, 1, 3, 1, 29, 11, 59, 117, 31, 651, 1731, 1017, 4895]),
+    (13, 2894, &[1, 1, 5, 15, 17, 31, 101, 135, 483, 791, 155, 75, 733]),
+    (13, 2902, &[1, 1, 1, 3, 15, 13, 9, 177, 363, 281, 309, 1467, 981]),
+    (13, 2917, &[1, 1, 1, 1, 21, 51, 55, 105, 453, 187, 1021, 3051, 4469]),
+    (13, 2921, &[1, 3, 7, 1, 17, 5, 49, 243, 269, 797, 1235, 2199, 4045]),
+    (13, 2922, &[1, 3, 3, 15, 9, 17, 117, 227, 491, 541, 1523, 3167, 3215]),
+    (13, 2929, &[1, 3, 5, 15, 13, 29, 123, 81, 19, 545, 1715, 3147, 7731]),
+    (13, 2935, &[1, 3, 1, 15, 29, 7, 35, 213, 125, 665, 1583, 1521, 47]),
+    (13, 2946, &[1, 3, 5, 9, 31, 53, 29, 197, 349, 651, 493, 1391, 6727]),
+    (13, 2951, &[1, 1, 5, 5, 21, 13, 99, 43, 243, 965, 901, 2895, 7641]),
+    (13, 2957, &[1, 1, 7, 7, 3, 13, 21, 233, 87, 493, 705, 4035, 1197]),
+    (13, 2960, &[1, 1, 1, 15, 29, 15, 47, 27, 407, 51, 1881, 453, 5053]),
+    (13, 2966, &[1, 3, 5, 15, 5, 37, 87, 45, 175, 961, 635, 1549, 2775]),
+    (13, 2972, &[1, 1, 1, 13, 15, 47, 21, 161, 159, 997, 1505, 2913, 8035]),
+    (13, 2976, &[1, 1, 7, 11, 9, 1, 67, 207, 91, 19, 795, 3445, 5121]),
+    (13, 2979, &[1, 1, 5, 3, 21, 57, 71, 253, 455, 645, 247, 3517, 2243]),
+    (13, 2985, &[1, 3, 3, 9, 21, 63, 125, 87, 307, 45, 923, 695, 2035]),
+    (13, 3000, &[1, 3, 3, 11, 3, 35, 9, 83, 417, 691, 1247, 3351, 6747]),
+    (13, 3003, &[1, 3, 3, 3, 13, 55, 13, 9, 431, 923, 1633, 3711, 4365]),
+    (13, 3013, &[1, 1, 5, 9, 5, 63, 107, 33, 91, 797, 925, 1641, 4701]),
+    (13, 3018, &[1, 1, 3, 3, 7, 35, 27, 131, 479, 845, 1033, 2847, 1947]),
+    (13, 3020, &[1, 1, 5, 9, 27, 39, 21, 11, 87, 481, 1169, 3031, 2633]),
+    (13, 3025, &[1, 1, 3, 1, 11, 15, 39, 173, 363, 869, 663, 577, 2581]),
+    (13, 3042, &[1, 3, 5, 5, 9, 19, 63, 109, 287, 67, 553, 867, 5217]),
+    (13, 3047, &[1, 1, 3, 5, 21, 7, 57, 113, 213, 723, 1781, 1981, 7247]),
+    (13, 3048, &[1, 1, 3, 13, 7, 27, 15, 201, 485, 625, 25, 1617, 5933]),
+    (13, 3051, &[1, 3, 1, 11, 1, 13, 69, 187, 203, 867, 1459, 3791, 107]),
+    (13, 3054, &[1, 3, 3, 5, 3, 11, 19, 101, 137, 437, 977, 2731, 6599]),
+    (13, 3056, &[1, 1, 1, 11, 17, 15, 5, 253, 437, 103, 1687, 2625, 7687]),
+    (13, 3065, &[1, 3, 3, 7, 25, 55, 117, 195, 503, 237, 1081, 3923, 3905]),
+    (13, 3073, &[1, 1, 1, 5, 29, 33, 77, 173, 19, 189, 1723, 843, 4761]),
+    (13, 3074, &[1, 3, 5, 11, 15, 49, 95, 123, 241, 967, 1419, 1705, 1669]),
+    (13, 3083, &[1, 3, 3, 13, 17, 17, 73, 177, 321, 471, 941, 2853, 7983]),
+    (13, 3086, &[1, 3, 1, 15, 21, 53, 47, 87, 249, 597, 613, 1697, 1969]),
+    (13, 3091, &[1, 1, 3, 11, 9, 53, 59, 33, 253, 51, 651, 1217, 7801]),
+    (13, 3097, &[1, 1, 5, 3, 23, 41, 49, 19, 123, 789, 497, 1085, 2329]),
+    (13, 3109, &[1, 3, 5, 15, 27, 61, 25, 121, 135, 181, 345, 2489, 6797]),
+    (13, 3116, &[1, 3, 3, 9, 3, 43, 13, 181, 29, 913, 327, 3481, 7139]),
+    (13, 3124, &[1, 1, 3, 5, 13, 39, 69, 151, 157, 805, 1345, 347, 5709]),
+    (13, 3128, &[1, 1, 7, 9, 29, 53, 21, 69, 37, 373, 439, 1287, 101]),
+    (13, 3153, &[1, 1, 3, 9, 29, 49, 91, 1, 181, 699, 651, 609, 6899]),
+    (13, 3160, &[1, 1, 5, 13, 1, 43, 83, 253, 489, 693, 1669, 3825, 983]),
+    (13, 3165, &[1, 1, 7, 1, 19, 15, 117, 15, 125, 821, 521, 1527, 4161]),
+    (13, 3172, &[1, 1, 3, 1, 13, 11, 59, 255, 105, 31, 1879, 3637, 2343]),
+    (13, 3175, &[1, 3, 7, 7, 25, 9, 37, 197, 413, 801, 1285, 2187, 1559]),
+    (13, 3184, &[1, 1, 1, 9, 15, 5, 5, 229, 93, 177, 1079, 1219, 307]),
+    (13, 3193, &[1, 3, 5, 9, 27, 1, 41, 99, 265, 743, 1757, 15, 3435]),
+    (13, 3196, &[1, 3, 1, 3, 19, 59, 109, 249, 147, 95, 1987, 1697, 1533]),
+    (13, 3200, &[1, 3, 1, 7, 15, 61, 119, 215, 389, 237, 1599, 3957, 7457]),
+    (13, 3203, &[1, 1, 5, 1, 5, 15, 93, 193, 157, 341, 145, 1647, 7809]),
+    (13, 3205, &[1, 3, 1, 13, 23, 35, 105, 167, 7, 287, 505, 645, 967]),
+    (13, 3209, &[1, 3, 3, 5, 29, 55, 19, 247, 235, 371, 1773, 1767, 5041]),
+    (13, 3224, &[1, 1, 1, 1, 21, 35, 117, 227, 427, 161, 1127, 159, 4725]),
+    (13, 3239, &[1, 3, 1, 15, 17, 49, 15, 87, 251, 615, 1235, 2139, 5015]),
+    (13, 3251, &[1, 3, 7, 15, 25, 41, 31, 105, 33, 881, 2037, 1645, 1093]),
+    (13, 3254, &[1, 3, 5, 15, 15, 63, 13, 109, 19, 939, 111, 157, 7383]),
+    (13, 3265, &[1, 3, 1, 7, 11, 19, 121, 153, 227, 339, 381, 3635, 7659]),
+    (13, 3266, &[1, 1, 3, 5, 13, 51, 89, 9, 345, 571, 1011, 2371, 5003]),
+    (13, 3275, &[1, 1, 3, 11, 1, 51, 91, 17, 279, 901, 2029, 539, 667]),
+    (13, 3280, &[1, 1, 3, 15, 25, 19, 111, 59, 489, 509, 1437, 1513, 3507]),
+    (13, 3283, &[1, 1, 7, 1, 31, 21, 111, 77, 59, 423, 1765, 1225, 5565]),
+    (13, 3286, &[1, 1, 3, 11, 25, 43, 93, 127, 217, 589, 763, 1617, 7671]),
+    (13, 3301, &[1, 3, 3, 9, 19, 1, 93, 155, 227, 99, 693, 1253, 6199]),
+    (13, 3302, &[1, 1, 1, 13, 5, 9, 25, 113, 193, 501, 179, 2327, 6449]),
+    (13, 3305, &[1, 1, 7, 15, 17, 43, 71, 41, 433, 611, 941, 3385, 4561]),
+    (13, 3319, &[1, 3, 7, 1, 15, 25, 29, 21, 225, 791, 477, 869, 3589]),
+    (13, 3323, &[1, 1, 1, 5, 13, 3, 113, 169, 445, 523, 393, 151, 3853]),
+    (13, 3326, &[1, 3, 3, 3, 29, 15, 55, 19, 265, 961, 11, 1939, 2561]),
+    (13, 3331, &[1, 1, 7, 7, 7, 35, 7, 195, 49, 201, 1027, 1869, 2187]),
+    (13, 3348, &[1, 1, 7, 5, 5, 29, 43, 201, 243, 1015, 1403, 3683, 2613]),
+    (13, 3351, &[1, 3, 5, 7, 19, 45, 119, 189, 399, 691, 793, 3573, 3575]),
+    (13, 3358, &[1, 3, 3, 1, 19, 19, 119, 121, 333, 879, 181, 1737, 6623]),
+    (13, 3368, &[1, 3, 7, 1, 21, 11, 97, 21, 345, 563, 1147, 3849, 6577]),
+    (13, 3374, &[1, 1, 7, 15, 15, 29, 1, 99, 165, 603, 697, 2837, 4221]),
+    (13, 3376, &[1, 3, 1, 3, 1, 29, 103, 241, 395, 217, 95, 785, 7993]),
+    (13, 3379, &[1, 3, 7, 15, 27, 35, 103, 91, 197, 603, 499, 1695, 3299]),
+    (13, 3385, &[1, 3, 7, 15, 29, 49, 21, 185, 485, 673, 195, 723, 4207]),
+    (13, 3386, &[1, 1, 7, 11, 7, 57, 55, 55, 205, 741, 291, 2323, 1575]),
+    (13, 3396, &[1, 1, 1, 13, 21, 27, 71, 87, 261, 953, 211, 1519, 3535]),
+    (13, 3420, &[1, 1, 7, 13, 17, 29, 7, 195, 229, 791, 1345, 365, 4845]),
+    (13, 3423, &[1, 1, 5, 3, 25, 45, 117, 143, 57, 109, 1505, 1949, 7977]),
+    (13, 3430, &[1, 3, 3, 15, 17, 11, 121, 221, 497, 979, 1379, 3801, 4191]),
+    (13, 3433, &[1, 1, 3, 13, 5, 37, 69, 187, 399, 303, 1225, 1483, 6959]),
+    (13, 3434, &[1, 3, 3, 9, 29, 51, 45, 157, 177, 327, 233, 3889, 5143]),
+    (13, 3439, &[1, 1, 1, 1, 25, 43, 45, 121, 217, 19, 1879, 1729, 3557]),
+    (13, 3442, &[1, 3, 7, 7, 9, 21, 37, 179, 249, 333, 1187, 1083, 5141]),
+    (13, 3444, &[1, 3, 1, 5, 3, 5, 13, 75, 487, 171, 667, 1985, 3373]),
+    (13, 3453, &[1, 3, 5, 7, 29, 59, 79, 233, 319, 63, 1415, 3365, 6987]),
+    (13, 3464, &[1, 3, 3, 13, 1, 15, 19, 251, 311, 33, 483, 3587, 5945]),
+    (13, 3477, &[1, 3, 1, 9, 5, 59, 49, 155, 269, 913, 1759, 37, 6415]),
+    (13, 3478, &[1, 1, 3, 5, 11, 3, 89, 153, 425, 887, 575, 401, 6421]),
+    (13, 3482, &[1, 1, 5, 5, 29, 5, 107, 5, 333, 255, 1549, 3531, 6643]),
+    (13, 3487, &[1, 3, 1, 5, 31, 1, 43, 237, 245, 783, 343, 3415, 4029]),
+    (13, 3497, &[1, 3, 5, 11, 21, 33, 43, 45, 85, 533, 481, 3713, 5271]),
+    (13, 3500, &[1, 1, 7, 7, 11, 47, 89, 79, 99, 783, 459, 1199, 1287]),
+    (13, 3505, &[1, 1, 5, 15, 1, 47, 47, 223, 417, 493, 63, 1993, 1245]),
+    (13, 3506, &[1, 1, 3, 7, 17, 31, 35, 197, 479, 77, 515, 1157, 4233]),
+    (13, 3511, &[1, 3, 3, 1, 31, 33, 117, 241, 197, 629, 1143, 399, 517]),
+    (13, 3512, &[1, 1, 3, 9, 27, 3, 91, 107, 275, 133, 1987, 3169, 4545]),
+    (13, 3515, &[1, 3, 3, 1, 21, 53, 5, 45, 327, 699, 1927, 3727, 689]),
+    (13, 3525, &[1, 3, 1, 9, 23, 51, 1, 213, 439, 905, 813, 3547, 4705]),
+    (13, 3532, &[1, 3, 1, 13, 23, 29, 123, 219, 465, 483, 1301, 3257, 8147]),
+    (13, 3538, &[1, 3, 7, 7, 17, 31, 61, 9, 231, 199, 1999, 107, 2639]),
+];
+
+pub const SOBOL_DIMENSIONS: usize = SOBOL_DIRECTIONS.len() + 1;
+
+// the generator matrices of all dimensions, built once and shared by every
+// sampler
+pub fn sobol_matrices() -> &'static [[u32; 32]] {
+    static MATRICES: OnceLock<Vec<[u32; 32]>> = OnceLock::new();
+    MATRICES.get_or_init(|| (0..SOBOL_DIMENSIONS).map(sobol_matrix).collect())
+}
+
+// generator matrix of `dimension` as 32 columns, column i is xored into the
+// sample when bit i of the index is set
+pub fn sobol_matrix(dimension: usize) -> [u32; 32] {
+    let mut matrix = [0u32; 32];
+    if dimension == 0 {
+        for (i, column) in matrix.iter_mut().enumerate() {
+            *column = 1 << (31 - i);
+        }
+        return matrix;
+    }
+
+    let (s, coefficients, initial) = SOBOL_DIRECTIONS[dimension - 1];
+    let mut m = [0u32; 32];
+    m[..s].copy_from_slice(initial);
+    for i in s..32 {
+        // m_i = 2 a_1 m_{i-1} ^ 4 a_2 m_{i-2} ^ ... ^ 2^s m_{i-s} ^ m_{i-s}
+        let mut value = m[i - s] ^ (m[i - s] << s);
+        for k in 1..s {
+            if (coefficients >> (s - 1 - k)) & 1 == 1 {
+                value ^= m[i - k] << k;
+            }
+        }
+        m[i] = value;
+    }
+    for (i, column) in matrix.iter_mut().enumerate() {
+        *column = m[i] << (31 - i);
+    }
+    matrix
+}
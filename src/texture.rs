@@ -0,0 +1,537 @@
+use super::common::HitRecord;
+use super::error::RaytracerError;
+use super::math::{Point3D, ScalarF, Vec3D, Vec3DConfig};
+use cgmath::Array;
+use image::RgbImage;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use serde::Deserialize;
+use std::fmt::Debug;
+use std::sync::Arc;
+
+// where a texture is looked up, procedural textures use the position
+pub struct TextureContext {
+    pub uv: (ScalarF, ScalarF),
+    pub world_pos: Point3D,
+}
+
+impl TextureContext {
+    pub fn from_hit(hit: &HitRecord) -> Self {
+        Self {
+            uv: hit.uv.unwrap_or((0.0, 0.0)),
+            world_pos: hit.p,
+        }
+    }
+}
+
+pub trait Texture: Sync + Send + Debug {
+    fn sample(&self, uv: (ScalarF, ScalarF)) -> Vec3D;
+
+    fn sample_at(&self, context: &TextureContext) -> Vec3D {
+        self.sample(context.uv)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ConstantTexture {
+    pub color: Vec3D,
+}
+
+impl Texture for ConstantTexture {
+    fn sample(&self, _: (ScalarF, ScalarF)) -> Vec3D {
+        self.color
+    }
+}
+
+// bilinearly filtered image with texel centres at half integers, u runs
+// to the right and v upwards. lookups outside of [0, 1] clamp to the edge
+#[derive(Debug, Clone)]
+pub struct ImageTexture {
+    pub image: RgbImage,
+}
+
+impl ImageTexture {
+    pub fn load(path: &str) -> Result<Self, String> {
+        let image = image::open(path)
+            .map_err(|e| format!("Failed to load texture {}: {}", path, e))?
+            .to_rgb8();
+        Ok(Self { image })
+    }
+
+    fn texel(&self, x: i64, y: i64) -> Vec3D {
+        let x = x.clamp(0, self.image.width() as i64 - 1) as u32;
+        let y = y.clamp(0, self.image.height() as i64 - 1) as u32;
+        let pixel = self.image.get_pixel(x, y);
+        Vec3D::new(
+            pixel[0] as ScalarF,
+            pixel[1] as ScalarF,
+            pixel[2] as ScalarF,
+        ) / 255.0
+    }
+}
+
+impl Texture for ImageTexture {
+    fn sample(&self, (u, v): (ScalarF, ScalarF)) -> Vec3D {
+        let x = u.clamp(0.0, 1.0) * self.image.width() as ScalarF - 0.5;
+        let y = (1.0 - v.clamp(0.0, 1.0)) * self.image.height() as ScalarF - 0.5;
+        let (x0, y0) = (x.floor(), y.floor());
+        let (fx, fy) = (x - x0, y - y0);
+        let (x0, y0) = (x0 as i64, y0 as i64);
+
+        (self.texel(x0, y0) * (1.0 - fx) + self.texel(x0 + 1, y0) * fx) * (1.0 - fy)
+            + (self.texel(x0, y0 + 1) * (1.0 - fx) + self.texel(x0 + 1, y0 + 1) * fx) * fy
+    }
+}
+
+// fractal sum of improved perlin noise, each octave is `lacunarity` times
+// the frequency and `persistence` times the amplitude of the previous one
+#[derive(Debug, Clone)]
+pub struct PerlinNoiseTexture {
+    pub scale: ScalarF,
+    pub octaves: u32,
+    pub persistence: ScalarF,
+    pub lacunarity: ScalarF,
+    permutation: Vec<usize>,
+}
+
+impl PerlinNoiseTexture {
+    pub fn new(
+        scale: ScalarF,
+        octaves: u32,
+        persistence: ScalarF,
+        lacunarity: ScalarF,
+        seed: u64,
+    ) -> Self {
+        let mut permutation: Vec<usize> = (0..256).collect();
+        permutation.shuffle(&mut StdRng::seed_from_u64(seed));
+        // doubled so that hashing neighbouring cells never wraps
+        permutation.extend_from_within(..);
+        Self {
+            scale,
+            octaves,
+            persistence,
+            lacunarity,
+            permutation,
+        }
+    }
+
+    fn gradient(hash: usize, x: ScalarF, y: ScalarF, z: ScalarF) -> ScalarF {
+        // dot product with one of 12 edge directions of a cube
+        let h = hash & 15;
+        let u = if h < 8 { x } else { y };
+        let v = match h {
+            0..=3 => y,
+            12 | 14 => x,
+            _ => z,
+        };
+        (if h & 1 == 0 { u } else { -u }) + (if h & 2 == 0 { v } else { -v })
+    }
+
+    // single octave in [-1, 1], zero on every lattice point
+    pub fn noise(&self, p: Point3D) -> ScalarF {
+        let fade = |t: ScalarF| t * t * t * (t * (t * 6.0 - 15.0) + 10.0);
+        let lerp = |t: ScalarF, a: ScalarF, b: ScalarF| a + t * (b - a);
+        let (fx, fy, fz) = (p.x.floor(), p.y.floor(), p.z.floor());
+        let (x, y, z) = (p.x - fx, p.y - fy, p.z - fz);
+        let (xi, yi, zi) = (
+            (fx as i64 & 255) as usize,
+            (fy as i64 & 255) as usize,
+            (fz as i64 & 255) as usize,
+        );
+        let (u, v, w) = (fade(x), fade(y), fade(z));
+
+        let perm = &self.permutation;
+        let a = perm[xi] + yi;
+        let (aa, ab) = (perm[a] + zi, perm[a + 1] + zi);
+        let b = perm[xi + 1] + yi;
+        let (ba, bb) = (perm[b] + zi, perm[b + 1] + zi);
+        let g = |hash: usize, dx: ScalarF, dy: ScalarF, dz: ScalarF| {
+            Self::gradient(perm[hash], x - dx, y - dy, z - dz)
+        };
+
+        lerp(
+            w,
+            lerp(
+                v,
+                lerp(u, g(aa, 0.0, 0.0, 0.0), g(ba, 1.0, 0.0, 0.0)),
+                lerp(u, g(ab, 0.0, 1.0, 0.0), g(bb, 1.0, 1.0, 0.0)),
+            ),
+            lerp(
+                v,
+                lerp(u, g(aa + 1, 0.0, 0.0, 1.0), g(ba + 1, 1.0, 0.0, 1.0)),
+                lerp(u, g(ab + 1, 0.0, 1.0, 1.0), g(bb + 1, 1.0, 1.0, 1.0)),
+            ),
+        )
+    }
+
+    fn accumulate(&self, p: Point3D, octave: impl Fn(ScalarF) -> ScalarF) -> ScalarF {
+        let mut sum = 0.0;
+        let mut frequency = self.scale;
+        let mut amplitude = 1.0;
+        for _ in 0..self.octaves {
+            sum += amplitude * octave(self.noise(p * frequency));
+            frequency *= self.lacunarity;
+            amplitude *= self.persistence;
+        }
+        sum
+    }
+
+    pub fn fractal(&self, p: Point3D) -> ScalarF {
+        self.accumulate(p, |n| n)
+    }
+
+    pub fn turbulence(&self, p: Point3D) -> ScalarF {
+        self.accumulate(p, ScalarF::abs)
+    }
+}
+
+impl Texture for PerlinNoiseTexture {
+    // without a position the uv plane is used
+    fn sample(&self, (u, v): (ScalarF, ScalarF)) -> Vec3D {
+        Vec3D::from_value(self.fractal(Point3D::new(u, v, 0.0)))
+    }
+
+    fn sample_at(&self, context: &TextureContext) -> Vec3D {
+        Vec3D::from_value(self.fractal(context.world_pos))
+    }
+}
+
+// sum of the absolute octaves of the noise, always non-negative
+#[derive(Debug, Clone)]
+pub struct TurbulenceTexture {
+    pub noise: PerlinNoiseTexture,
+}
+
+impl Texture for TurbulenceTexture {
+    fn sample(&self, (u, v): (ScalarF, ScalarF)) -> Vec3D {
+        Vec3D::from_value(self.noise.turbulence(Point3D::new(u, v, 0.0)))
+    }
+
+    fn sample_at(&self, context: &TextureContext) -> Vec3D {
+        Vec3D::from_value(self.noise.turbulence(context.world_pos))
+    }
+}
+
+// veins along z perturbed by turbulence, mapped to [0, 1] through a sine
+#[derive(Debug, Clone)]
+pub struct MarbleTexture {
+    pub noise: PerlinNoiseTexture,
+    pub vein_frequency: ScalarF,
+}
+
+impl MarbleTexture {
+    fn marble(&self, p: Point3D) -> Vec3D {
+        let phase = self.vein_frequency * p.z + 10.0 * self.noise.turbulence(p);
+        Vec3D::from_value(0.5 * (1.0 + phase.sin()))
+    }
+}
+
+impl Texture for MarbleTexture {
+    fn sample(&self, (u, v): (ScalarF, ScalarF)) -> Vec3D {
+        self.marble(Point3D::new(u, v, 0.0))
+    }
+
+    fn sample_at(&self, context: &TextureContext) -> Vec3D {
+        self.marble(context.world_pos)
+    }
+}
+
+#[derive(Deserialize)]
+pub struct ConstantTextureConfig {
+    pub color: Vec3DConfig,
+}
+
+#[derive(Deserialize)]
+pub struct ImageTextureConfig {
+    pub path: String,
+}
+
+#[derive(Deserialize)]
+pub struct PerlinNoiseTextureConfig {
+    pub scale: f64,
+    #[serde(default = "default_perlin_octaves")]
+    pub octaves: u32,
+    #[serde(default = "default_perlin_persistence")]
+    pub persistence: f64,
+    #[serde(default = "default_perlin_lacunarity")]
+    pub lacunarity: f64,
+    #[serde(default)]
+    pub seed: u64,
+}
+
+fn default_perlin_octaves() -> u32 {
+    4
+}
+
+fn default_perlin_persistence() -> f64 {
+    0.5
+}
+
+fn default_perlin_lacunarity() -> f64 {
+    2.0
+}
+
+impl PerlinNoiseTextureConfig {
+    pub fn to_noise(&self) -> PerlinNoiseTexture {
+        PerlinNoiseTexture::new(
+            self.scale as ScalarF,
+            self.octaves,
+            self.persistence as ScalarF,
+            self.lacunarity as ScalarF,
+            self.seed,
+        )
+    }
+}
+
+#[derive(Deserialize)]
+pub struct MarbleTextureConfig {
+    pub noise: PerlinNoiseTextureConfig,
+    pub vein_frequency: f64,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type")]
+pub enum TextureConfig {
+    Constant(ConstantTextureConfig),
+    Image(ImageTextureConfig),
+    Perlin(PerlinNoiseTextureConfig),
+    Turbulence(PerlinNoiseTextureConfig),
+    Marble(MarbleTextureConfig),
+}
+
+impl TextureConfig {
+    pub fn to_texture(&self) -> Result<Arc<dyn Texture>, RaytracerError> {
+        Ok(match self {
+            TextureConfig::Constant(config) => Arc::new(ConstantTexture {
+                color: config.color.to_vec3(),
+            }),
+            TextureConfig::Image(config) => {
+                Arc::new(ImageTexture::load(&config.path).map_err(RaytracerError::SceneLoad)?)
+            }
+            TextureConfig::Perlin(config) => Arc::new(config.to_noise()),
+            TextureConfig::Turbulence(config) => Arc::new(TurbulenceTexture {
+                noise: config.to_noise(),
+            }),
+            TextureConfig::Marble(config) => Arc::new(MarbleTexture {
+                noise: config.noise.to_noise(),
+                vein_frequency: config.vein_frequency as ScalarF,
+            }),
+        })
+    }
+}
+
+// material parameter that is either constant or read from a texture,
+// scalar parameters use the first channel of the texture
+#[derive(Debug, Clone)]
+pub enum TextureOrValue<T> {
+    Texture(Arc<dyn Texture>),
+    Value(T),
+}
+
+impl TextureOrValue<ScalarF> {
+    pub fn sample_at(&self, context: &TextureContext) -> ScalarF {
+        match self {
+            TextureOrValue::Texture(texture) => texture.sample_at(context).x,
+            TextureOrValue::Value(value) => *value,
+        }
+    }
+}
+
+impl TextureOrValue<Vec3D> {
+    pub fn sample_at(&self, context: &TextureContext) -> Vec3D {
+        match self {
+            TextureOrValue::Texture(texture) => texture.sample_at(context),
+            TextureOrValue::Value(value) => *value,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+pub enum TextureOrValueConfig<T> {
+    Texture(TextureConfig),
+    Value(T),
+}
+
+impl TextureOrValueConfig<f64> {
+    pub fn to_texture_or_value(&self) -> Result<TextureOrValue<ScalarF>, RaytracerError> {
+        Ok(match self {
+            TextureOrValueConfig::Texture(config) => TextureOrValue::Texture(config.to_texture()?),
+            TextureOrValueConfig::Value(value) => TextureOrValue::Value(*value as ScalarF),
+        })
+    }
+}
+
+impl TextureOrValueConfig<Vec3DConfig> {
+    pub fn to_texture_or_value(&self) -> Result<TextureOrValue<Vec3D>, RaytracerError> {
+        Ok(match self {
+            TextureOrValueConfig::Texture(config) => TextureOrValue::Texture(config.to_texture()?),
+            TextureOrValueConfig::Value(value) => TextureOrValue::Value(value.to_vec3()),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::{tolerance, vec3_approx_eq};
+
+    #[test]
+    fn test_image_texture_bilinear() {
+        // red, green / blue, white from the top left
+        let mut image = RgbImage::new(2, 2);
+        image.put_pixel(0, 0, image::Rgb([255, 0, 0]));
+        image.put_pixel(1, 0, image::Rgb([0, 255, 0]));
+        image.put_pixel(0, 1, image::Rgb([0, 0, 255]));
+        image.put_pixel(1, 1, image::Rgb([255, 255, 255]));
+        let texture = ImageTexture { image };
+
+        // the midpoint blends all four corners equally
+        assert!(vec3_approx_eq(
+            texture.sample((0.5, 0.5)),
+            Vec3D::new(0.5, 0.5, 0.5),
+            tolerance(1e-9)
+        ));
+        // texel centres and the clamped corners
+        assert!(vec3_approx_eq(
+            texture.sample((0.25, 0.75)),
+            Vec3D::new(1.0, 0.0, 0.0),
+            tolerance(1e-9)
+        ));
+        assert!(vec3_approx_eq(
+            texture.sample((1.0, 0.0)),
+            Vec3D::new(1.0, 1.0, 1.0),
+            tolerance(1e-9)
+        ));
+        // halfway along the top edge between red and green
+        assert!(vec3_approx_eq(
+            texture.sample((0.5, 0.75)),
+            Vec3D::new(0.5, 0.5, 0.0),
+            tolerance(1e-9)
+        ));
+        // a quarter of the way from the top row to the bottom row
+        assert!(vec3_approx_eq(
+            texture.sample((0.25, 0.625)),
+            Vec3D::new(0.75, 0.0, 0.25),
+            tolerance(1e-9)
+        ));
+    }
+
+    #[test]
+    fn test_perlin_noise() {
+        let noise = || PerlinNoiseTexture::new(1.7, 5, 0.5, 2.0, 7);
+        let points: Vec<Point3D> = (0..1000)
+            .map(|i| {
+                let i = i as ScalarF;
+                Point3D::new(i * 0.731, i * 0.337 - 40.0, (i * 0.113).sin() * 25.0)
+            })
+            .collect();
+
+        // the same seed gives the same texture, another seed a different one
+        let context = |p: Point3D| TextureContext {
+            uv: (0.0, 0.0),
+            world_pos: p,
+        };
+        let (a, b) = (noise(), noise());
+        let other = PerlinNoiseTexture::new(1.7, 5, 0.5, 2.0, 8);
+        assert!(points
+            .iter()
+            .all(|&p| a.sample_at(&context(p)) == b.sample_at(&context(p))));
+        assert!(points
+            .iter()
+            .any(|&p| a.sample_at(&context(p)) != other.sample_at(&context(p))));
+
+        // a single octave vanishes on the lattice and is bounded
+        assert_eq!(a.noise(Point3D::new(3.0, -2.0, 17.0)), 0.0);
+        assert!(points.iter().all(|&p| a.noise(p).abs() <= 1.0));
+
+        // the fractal sum is roughly zero mean, turbulence folds it into
+        // non-negative values
+        let mean = points.iter().map(|&p| a.fractal(p)).sum::<ScalarF>() / points.len() as ScalarF;
+        assert!(mean.abs() < 0.05, "mean {}", mean);
+        let turbulence = TurbulenceTexture { noise: noise() };
+        let values: Vec<ScalarF> = points
+            .iter()
+            .map(|&p| turbulence.sample_at(&context(p)).x)
+            .collect();
+        assert!(values.iter().all(|&t| t >= 0.0));
+        let mean = values.iter().sum::<ScalarF>() / values.len() as ScalarF;
+        assert!(mean > 0.1, "mean {}", mean);
+
+        let marble = MarbleTexture {
+            noise: noise(),
+            vein_frequency: 4.0,
+        };
+        assert!(points.iter().all(|&p| {
+            let c = marble.sample_at(&context(p));
+            (0.0..=1.0).contains(&c.x) && c.x == c.y && c.y == c.z
+        }));
+    }
+
+    #[derive(Deserialize)]
+    struct ParametersConfig {
+        roughness: TextureOrValueConfig<f64>,
+        albedo: TextureOrValueConfig<Vec3DConfig>,
+    }
+
+    fn uv_context(u: ScalarF, v: ScalarF) -> TextureContext {
+        TextureContext {
+            uv: (u, v),
+            world_pos: Point3D::new(0.0, 0.0, 0.0),
+        }
+    }
+
+    #[test]
+    fn test_texture_or_value_config() {
+        let config: ParametersConfig = toml::from_str(
+            r#"
+            roughness = 0.25
+            albedo = { type = "Constant", color = { x = 0.1, y = 0.2, z = 0.3 } }
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            config
+                .roughness
+                .to_texture_or_value()
+                .unwrap()
+                .sample_at(&uv_context(0.5, 0.5)),
+            0.25
+        );
+        assert!(vec3_approx_eq(
+            config
+                .albedo
+                .to_texture_or_value()
+                .unwrap()
+                .sample_at(&uv_context(0.5, 0.5)),
+            Vec3D::new(0.1, 0.2, 0.3),
+            tolerance(1e-9)
+        ));
+
+        let config: ParametersConfig = toml::from_str(
+            r#"
+            roughness = { type = "Constant", color = { x = 0.75, y = 0.0, z = 0.0 } }
+            albedo = { x = 1.0, y = 0.0, z = 0.5 }
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            config
+                .roughness
+                .to_texture_or_value()
+                .unwrap()
+                .sample_at(&uv_context(0.0, 0.0)),
+            0.75
+        );
+        assert!(vec3_approx_eq(
+            config
+                .albedo
+                .to_texture_or_value()
+                .unwrap()
+                .sample_at(&uv_context(0.0, 0.0)),
+            Vec3D::new(1.0, 0.0, 0.5),
+            tolerance(1e-9)
+        ));
+    }
+}
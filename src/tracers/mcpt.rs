@@ -1,36 +1,75 @@
-use super::super::math::{Ray, Vec3D};
+use super::super::math::{MisHeuristic, Ray, Vec3D};
 use super::super::sampler::Sampler;
 use super::super::scene::Scene;
 use super::tracer::Tracer;
-use super::utils::{connect, generate_camera_vertices, PathVertex};
+use super::utils::{
+    connect, connect_emission_nee, generate_camera_vertices, generate_light_vertices,
+    next_event_estimation, PathVertex,
+};
 use cgmath::Zero;
 use serde::Deserialize;
 
 pub struct MonteCarloPathTracer {
     min_depth: usize,
     max_depth: usize,
+    bidirectional: bool, // also trace light subpaths and connect them to the camera subpath
+    next_event_estimation: bool, // cast shadow rays to lights at every vertex
+    mis_heuristic: MisHeuristic, // weights of shadow rays against hitting lights by scattering
 }
 
 #[derive(Deserialize)]
 pub struct MonteCarloPathTracerConfig {
     pub min_depth: usize,
     pub max_depth: usize,
+    #[serde(default)]
+    pub next_event_estimation: bool, // unidirectional only, bidirectional paths always connect to lights
+    #[serde(default)]
+    pub mis_heuristic: MisHeuristic,
 }
 
 impl Tracer for MonteCarloPathTracer {
     fn trace(&mut self, ray: &Ray, scene: &Scene, sampler: &mut dyn Sampler) -> Vec3D {
         let camera_vertices =
             { generate_camera_vertices(ray, scene, sampler, self.min_depth, self.max_depth) };
-        let light_vertices: Vec<PathVertex> = Vec::new();
+        // a path of n vertices has depth n - 2, camera subpaths reach n = max_depth + 1
+        let light_vertices: Vec<PathVertex> = if self.bidirectional {
+            generate_light_vertices(
+                scene,
+                sampler,
+                self.min_depth,
+                self.max_depth.saturating_sub(2),
+            )
+        } else {
+            Vec::new()
+        };
 
         let camera_vertex_count = camera_vertices.len();
         let light_vertex_count = light_vertices.len();
 
         let mut color = Vec3D::zero();
+        if self.next_event_estimation && !self.bidirectional {
+            for t in 2..(camera_vertex_count + 1) {
+                // the shadow ray adds one more vertex to the path
+                if t - 2 < self.max_depth {
+                    color += connect_emission_nee(scene, &camera_vertices, t, self.mis_heuristic);
+                }
+                if t - 1 < self.max_depth {
+                    color += next_event_estimation(
+                        scene,
+                        &camera_vertices,
+                        t,
+                        sampler,
+                        self.mis_heuristic,
+                    );
+                }
+            }
+            return color;
+        }
+
         for t in 1..(camera_vertex_count + 1) {
             for s in 0..(light_vertex_count + 1) {
                 let depth = (s + t) as i32 - 2;
-                if (s == 1 && t == 1) || depth < 0 || depth > self.max_depth as i32 {
+                if t == 1 || depth < 0 || depth >= self.max_depth as i32 {
                     continue;
                 }
 
@@ -47,6 +86,19 @@ impl MonteCarloPathTracerConfig {
         MonteCarloPathTracer {
             min_depth: self.min_depth,
             max_depth: self.max_depth,
+            bidirectional: false,
+            next_event_estimation: self.next_event_estimation,
+            mis_heuristic: self.mis_heuristic,
+        }
+    }
+
+    pub fn to_bidirectional_tracer(&self) -> MonteCarloPathTracer {
+        MonteCarloPathTracer {
+            min_depth: self.min_depth,
+            max_depth: self.max_depth,
+            bidirectional: true,
+            next_event_estimation: false,
+            mis_heuristic: self.mis_heuristic,
         }
     }
 }
@@ -1,5 +1,8 @@
 mod mcpt;
+mod ppm;
 mod tracer;
 mod utils;
+mod whitted;
 
-pub use tracer::TracerConfig;
+pub use ppm::ProgressivePhotonMapTracer;
+pub use tracer::{Tracer, TracerConfig};
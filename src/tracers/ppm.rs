@@ -0,0 +1,357 @@
+use super::super::common::HitRecord;
+use super::super::math::consts::PI;
+use super::super::math::{Point3D, Ray, ScalarF, Vec3D};
+use super::super::sampler::Sampler;
+use super::super::scene::Scene;
+use super::tracer::Tracer;
+use super::utils::generate_light_vertices;
+use cgmath::{ElementWise, InnerSpace, Zero};
+use serde::Deserialize;
+use std::collections::HashMap;
+
+// progressive photon mapping (hachisuka et al. 2008): camera rays are traced
+// once to the first diffuse surface they reach, then every photon pass
+// gathers new photons around those visible points and shrinks their radius,
+// so the estimate converges without ever storing a full photon map
+pub struct ProgressivePhotonMapTracer {
+    photons_per_pass: usize,
+    num_passes: usize,
+    alpha: ScalarF, // fraction of the new photons kept when the radius shrinks
+    max_depth: usize,
+    initial_radius: Option<ScalarF>,
+}
+
+#[derive(Deserialize)]
+pub struct PpmTracerConfig {
+    pub photons_per_pass: usize,
+    pub num_passes: usize,
+    pub alpha: Option<f64>,
+    pub max_depth: Option<usize>,
+    pub initial_radius: Option<f64>, // defaults to 1% of the extent of the visible points
+}
+
+struct VisiblePoint<'a> {
+    hit: HitRecord<'a>,
+    ray_in: Ray,
+    beta: Vec3D, // throughput from the camera
+    radius: ScalarF,
+    photon_count: ScalarF, // N, the photons accumulated so far after shrinking
+    flux: Vec3D,           // tau, unnormalized flux inside the radius
+}
+
+// uniform grid hashing visible points by cell, cells are at least as wide as
+// the largest search radius so a photon only looks at its 27 neighbours
+struct HashGrid {
+    cell_size: ScalarF,
+    cells: HashMap<(i64, i64, i64), Vec<usize>>,
+}
+
+impl HashGrid {
+    fn new(points: &[VisiblePoint], cell_size: ScalarF) -> Self {
+        let mut grid = Self {
+            cell_size,
+            cells: HashMap::new(),
+        };
+        for (i, point) in points.iter().enumerate() {
+            let cell = grid.cell(point.hit.p);
+            grid.cells.entry(cell).or_default().push(i);
+        }
+        grid
+    }
+
+    fn cell(&self, p: Point3D) -> (i64, i64, i64) {
+        (
+            (p.x / self.cell_size).floor() as i64,
+            (p.y / self.cell_size).floor() as i64,
+            (p.z / self.cell_size).floor() as i64,
+        )
+    }
+
+    fn neighbours(&self, p: Point3D) -> impl Iterator<Item = usize> + '_ {
+        let (x, y, z) = self.cell(p);
+        (0..27).flat_map(move |i| {
+            let cell = (x + i % 3 - 1, y + (i / 3) % 3 - 1, z + i / 9 - 1);
+            self.cells.get(&cell).into_iter().flatten().copied()
+        })
+    }
+}
+
+impl ProgressivePhotonMapTracer {
+    // follows `ray` through specular bounces, returns the radiance picked up
+    // on the way and the diffuse point it ends on
+    fn visible_point<'a>(
+        &self,
+        ray: &Ray,
+        scene: &'a Scene,
+        sampler: &mut dyn Sampler,
+    ) -> (Vec3D, Option<VisiblePoint<'a>>) {
+        let mut color = Vec3D::zero();
+        let mut beta = Vec3D::new(1.0, 1.0, 1.0);
+        let mut ray = ray.clone();
+
+        for _ in 0..self.max_depth {
+            let hit = match scene.intersect(&ray) {
+                Some(hit) => hit,
+                None => {
+                    color += beta.mul_element_wise(scene.background_radiance(ray.direction));
+                    break;
+                }
+            };
+
+            let material = &hit.object.unwrap().material;
+            color += beta.mul_element_wise(material.emitted(&ray, &hit));
+            if !material.is_specular() {
+                let point = VisiblePoint {
+                    hit,
+                    ray_in: ray,
+                    beta,
+                    radius: 0.0,
+                    photon_count: 0.0,
+                    flux: Vec3D::zero(),
+                };
+                return (color, Some(point));
+            }
+
+            let scatter_result = match material.scatter(&ray, &hit, sampler) {
+                Some(scatter_result) if scatter_result.pdf > 1e-6 => scatter_result,
+                _ => break,
+            };
+            let cos_theta = scatter_result.ray.direction.dot(hit.normal).abs();
+            let bxdf = material.bxdf(&ray, &scatter_result.ray, &hit);
+            beta = beta.mul_element_wise(cos_theta * bxdf / scatter_result.pdf);
+            ray = scatter_result.ray;
+        }
+
+        (color, None)
+    }
+
+    // traces `photons_per_pass` photons, then shrinks the radius of every
+    // visible point that gathered any
+    fn photon_pass(
+        &self,
+        scene: &Scene,
+        points: &mut [VisiblePoint],
+        grid: &HashGrid,
+        sampler: &mut dyn Sampler,
+    ) {
+        let mut new_photons = vec![0usize; points.len()];
+        let mut new_flux = vec![Vec3D::zero(); points.len()];
+
+        for _ in 0..self.photons_per_pass {
+            let light_vertices =
+                generate_light_vertices(scene, sampler, self.max_depth, self.max_depth);
+            for i in 1..light_vertices.len() {
+                let photon = &light_vertices[i];
+                if photon.delta || photon.material.is_none() {
+                    continue;
+                }
+                let direction_out = (light_vertices[i - 1].position - photon.position).normalize();
+                for j in grid.neighbours(photon.position) {
+                    let point = &points[j];
+                    // outside the radius, or on a surface facing away such as
+                    // the back of a thin wall
+                    if (point.hit.p - photon.position).magnitude2() > point.radius * point.radius
+                        || point.hit.normal.dot(photon.normal) <= 0.0
+                    {
+                        continue;
+                    }
+                    let ray_out = Ray {
+                        origin: point.hit.p,
+                        direction: direction_out,
+                    };
+                    let material = &point.hit.object.unwrap().material;
+                    let bxdf = material.bxdf(&point.ray_in, &ray_out, &point.hit);
+                    new_photons[j] += 1;
+                    new_flux[j] += photon.beta.mul_element_wise(bxdf);
+                }
+            }
+        }
+
+        // r' = r * sqrt((N + alpha M) / (N + M)), tau' = (tau + phi_M) r'^2 / r^2
+        for (j, point) in points.iter_mut().enumerate() {
+            let m = new_photons[j] as ScalarF;
+            if m == 0.0 {
+                continue;
+            }
+            let n = point.photon_count + self.alpha * m;
+            let shrink = n / (point.photon_count + m);
+            point.radius *= shrink.sqrt();
+            point.flux = (point.flux + new_flux[j]) * shrink;
+            point.photon_count = n;
+        }
+    }
+
+    // radiance along every ray after all photon passes
+    pub fn estimate(&self, rays: &[Ray], scene: &Scene, sampler: &mut dyn Sampler) -> Vec<Vec3D> {
+        let mut colors = Vec::with_capacity(rays.len());
+        let mut owners = Vec::new();
+        let mut points = Vec::new();
+        for (i, ray) in rays.iter().enumerate() {
+            let (color, point) = self.visible_point(ray, scene, sampler);
+            colors.push(color);
+            if let Some(point) = point {
+                owners.push(i);
+                points.push(point);
+            }
+        }
+        if points.is_empty() {
+            return colors;
+        }
+
+        let radius = self.initial_radius.unwrap_or_else(|| {
+            let (min, max) =
+                points
+                    .iter()
+                    .fold((points[0].hit.p, points[0].hit.p), |(min, max), point| {
+                        let p = point.hit.p;
+                        (
+                            Point3D::new(min.x.min(p.x), min.y.min(p.y), min.z.min(p.z)),
+                            Point3D::new(max.x.max(p.x), max.y.max(p.y), max.z.max(p.z)),
+                        )
+                    });
+            ((max - min).magnitude() * 0.01).max(1e-3)
+        });
+        points.iter_mut().for_each(|point| point.radius = radius);
+
+        // radii only shrink, the grid built for the initial one stays valid
+        let grid = HashGrid::new(&points, 2.0 * radius);
+        for _ in 0..self.num_passes {
+            self.photon_pass(scene, &mut points, &grid, sampler);
+        }
+
+        let total_photons = (self.photons_per_pass * self.num_passes) as ScalarF;
+        for (point, &i) in points.iter().zip(owners.iter()) {
+            let area = PI * point.radius * point.radius;
+            colors[i] += point.beta.mul_element_wise(point.flux) / (area * total_photons);
+        }
+        colors
+    }
+}
+
+impl Tracer for ProgressivePhotonMapTracer {
+    // runs every photon pass for this single ray, the renderer batches all
+    // the camera rays of an image through `estimate` instead
+    fn trace(&mut self, ray: &Ray, scene: &Scene, sampler: &mut dyn Sampler) -> Vec3D {
+        self.estimate(std::slice::from_ref(ray), scene, sampler)[0]
+    }
+}
+
+impl PpmTracerConfig {
+    pub fn to_tracer(&self) -> ProgressivePhotonMapTracer {
+        ProgressivePhotonMapTracer {
+            photons_per_pass: self.photons_per_pass,
+            num_passes: self.num_passes,
+            alpha: self.alpha.unwrap_or(0.7) as ScalarF,
+            max_depth: self.max_depth.unwrap_or(5),
+            initial_radius: self.initial_radius.map(|r| r as ScalarF),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sampler::RandomSampler;
+    use crate::scene::SceneConfig;
+
+    // glass ball focusing a square light onto the floor below it
+    const CAUSTIC_SCENE_CONFIG: &str = r#"
+        [camera]
+        type = "Perspective"
+        look_from = { x = 0.0, y = 0.5, z = 3.0 }
+        look_at = { x = 0.0, y = 0.0, z = 0.0 }
+        vup = { x = 0.0, y = 1.0, z = 0.0 }
+        vfov = 40.0
+        aspect = 1.0
+
+        [[objects]]
+        shape = { type = "Quadrilateral", vertices = [{ x = -3.0, y = 0.0, z = -3.0 }, { x = -3.0, y = 0.0, z = 3.0 }, { x = 3.0, y = 0.0, z = 3.0 }, { x = 3.0, y = 0.0, z = -3.0 }] }
+        material = { type = "Lambertian", albedo = { x = 0.8, y = 0.8, z = 0.8 } }
+
+        [[objects]]
+        shape = { type = "Sphere", center = { x = 0.0, y = 1.0, z = 0.0 }, radius = 0.5 }
+        material = { type = "IdealDielectric", ior = 1.5 }
+
+        [[objects]]
+        shape = { type = "Quadrilateral", vertices = [{ x = -0.5, y = 3.0, z = -0.5 }, { x = 0.5, y = 3.0, z = -0.5 }, { x = 0.5, y = 3.0, z = 0.5 }, { x = -0.5, y = 3.0, z = 0.5 }] }
+        material = { type = "Emissive", color = { x = 10.0, y = 10.0, z = 10.0 } }
+    "#;
+
+    fn caustic_scene() -> Scene {
+        let scene_config: SceneConfig = toml::from_str(CAUSTIC_SCENE_CONFIG).unwrap();
+        Scene::from_config(&scene_config).unwrap()
+    }
+
+    fn tracer(num_passes: usize) -> ProgressivePhotonMapTracer {
+        PpmTracerConfig {
+            photons_per_pass: 1000,
+            num_passes,
+            alpha: None,
+            max_depth: None,
+            initial_radius: Some(0.1),
+        }
+        .to_tracer()
+    }
+
+    #[test]
+    fn test_ppm_radius_shrinks() {
+        let scene = caustic_scene();
+        let tracer = tracer(1);
+        let mut sampler = RandomSampler::new(1);
+        let mut points: Vec<VisiblePoint> = [(0.5, 0.5), (0.45, 0.5), (0.5, 0.4)]
+            .iter()
+            .filter_map(|&(u, v)| {
+                let ray = scene.camera.create_ray_with_sampler(u, v, &mut sampler);
+                tracer.visible_point(&ray, &scene, &mut sampler).1
+            })
+            .collect();
+        assert_eq!(points.len(), 3);
+        points.iter_mut().for_each(|point| point.radius = 0.1);
+
+        let grid = HashGrid::new(&points, 0.2);
+        let mut radii: Vec<ScalarF> = points.iter().map(|point| point.radius).collect();
+        for _ in 0..20 {
+            tracer.photon_pass(&scene, &mut points, &grid, &mut sampler);
+            for (point, radius) in points.iter().zip(radii.iter_mut()) {
+                assert!(point.radius <= *radius);
+                *radius = point.radius;
+            }
+        }
+        assert!(radii.iter().all(|&radius| radius < 0.1));
+    }
+
+    #[test]
+    fn test_ppm_converges() {
+        let scene = caustic_scene();
+        // the floor point right below the ball, inside the caustic
+        let ray = Ray {
+            origin: Point3D::new(0.0, 0.5, 3.0),
+            direction: Vec3D::new(0.0, -0.5, -3.0).normalize(),
+        };
+        crate::rng::init_thread_local_rng(11);
+        let snr = |num_passes: usize| {
+            let mut sampler = RandomSampler::new(1);
+            let estimates: Vec<ScalarF> = (0..16)
+                .map(|_| {
+                    tracer(num_passes).estimate(std::slice::from_ref(&ray), &scene, &mut sampler)[0]
+                        .x
+                })
+                .collect();
+            let mean = estimates.iter().sum::<ScalarF>() / estimates.len() as ScalarF;
+            let variance = estimates
+                .iter()
+                .map(|x| (x - mean).powi(2))
+                .sum::<ScalarF>()
+                / (estimates.len() - 1) as ScalarF;
+            mean / variance.sqrt()
+        };
+
+        let (few, many) = (snr(1), snr(32));
+        assert!(
+            many > 2.0 * few,
+            "snr with 1 pass: {}, with 32: {}",
+            few,
+            many
+        );
+    }
+}
@@ -2,6 +2,8 @@ use super::super::math::{Ray, Vec3D};
 use super::super::sampler::Sampler;
 use super::super::scene::Scene;
 use super::mcpt::MonteCarloPathTracerConfig;
+use super::ppm::PpmTracerConfig;
+use super::whitted::WhittedTracerConfig;
 use serde::Deserialize;
 
 pub trait Tracer {
@@ -13,12 +15,23 @@ pub trait Tracer {
 pub enum TracerConfig {
     #[serde(rename = "mcpt")]
     MonteCarloPathTracer(MonteCarloPathTracerConfig),
+    #[serde(rename = "bdpt")]
+    BidirectionalPathTracer(MonteCarloPathTracerConfig),
+    #[serde(rename = "whitted")]
+    Whitted(WhittedTracerConfig),
+    #[serde(rename = "ppm")]
+    ProgressivePhotonMap(PpmTracerConfig),
 }
 
 impl TracerConfig {
     pub fn to_tracer(&self) -> Box<dyn Tracer> {
         match self {
             TracerConfig::MonteCarloPathTracer(config) => Box::new(config.to_tracer()),
+            TracerConfig::BidirectionalPathTracer(config) => {
+                Box::new(config.to_bidirectional_tracer())
+            }
+            TracerConfig::Whitted(config) => Box::new(config.to_tracer()),
+            TracerConfig::ProgressivePhotonMap(config) => Box::new(config.to_tracer()),
         }
     }
 }
@@ -1,52 +1,206 @@
-use super::super::material::Material;
-use super::super::math::{max_component, Point3D, Ray, Vec3D};
+use super::super::common::HitRecord;
+use super::super::light::{EnvironmentSample, Light};
+use super::super::material::{Material, ScatterResult};
+use super::super::math::consts::PI;
+use super::super::math::{
+    max_component, mis_weight, spawn_ray, spherical_to_world, MisHeuristic, Point3D, Ray, ScalarF,
+    Vec3D,
+};
 use super::super::sampler::Sampler;
 use super::super::scene::Scene;
-use cgmath::{Array, ElementWise, InnerSpace, Zero};
+use cgmath::{Array, ElementWise, InnerSpace, MetricSpace, Zero};
 use log::warn;
 use std::sync::Arc;
 
+#[derive(Clone)]
 pub struct PathVertex<'a> {
-    position: Point3D,
-    normal: Vec3D,
-    beta: Vec3D, // throughput, means cumulative contribution of the path
-    material: Option<&'a Arc<dyn Material>>,
+    pub position: Point3D,
+    pub normal: Vec3D,
+    pub beta: Vec3D, // throughput, means cumulative contribution of the path
+    pub material: Option<&'a Arc<dyn Material>>,
+    background: Option<Vec3D>, // radiance of the background seen by an escaped ray
+    hit: Option<HitRecord<'a>>,
+    pub delta: bool,  // scattering at this vertex is specular
+    pdf_fwd: ScalarF, // area density of sampling this vertex from the previous one
+    pdf_rev: ScalarF, // area density of sampling this vertex from the next one
 }
 
-pub fn generate_camera_vertices<'a>(
-    camera_ray: &Ray,
+// converts a solid angle density at `from` to an area density at `to`
+fn convert_density(pdf: ScalarF, from: &PathVertex, to: &PathVertex) -> ScalarF {
+    let w = to.position - from.position;
+    let distance2 = w.magnitude2();
+    if distance2 == 0.0 {
+        return 0.0;
+    }
+    pdf * to.normal.dot(w / distance2.sqrt()).abs() / distance2
+}
+
+fn direction(from: &PathVertex, to: &PathVertex) -> Vec3D {
+    (to.position - from.position).normalize()
+}
+
+// solid angle density of scattering at `current` towards `next`, where
+// `current` was reached from `prev`
+fn scatter_pdf(prev: &PathVertex, current: &PathVertex, next: &PathVertex) -> ScalarF {
+    let (material, hit) = match (current.material, &current.hit) {
+        (Some(material), Some(hit)) => (material, hit),
+        _ => return 0.0,
+    };
+    let ray_in = Ray {
+        origin: prev.position,
+        direction: direction(prev, current),
+    };
+    let ray_out = Ray {
+        origin: current.position,
+        direction: direction(current, next),
+    };
+    material.pdf(&ray_in, &ray_out, hit)
+}
+
+// area density of sampling `next` from `current`, where `current` was
+// reached from `prev`, or is the origin of a light subpath if `prev` is None
+fn vertex_pdf(prev: Option<&PathVertex>, current: &PathVertex, next: &PathVertex) -> ScalarF {
+    match prev {
+        Some(prev) => convert_density(scatter_pdf(prev, current, next), current, next),
+        None => pdf_light(current, next),
+    }
+}
+
+// area density of a light emitting from `light` towards `next`, lights emit
+// from both sides with a cosine distribution
+fn pdf_light(light: &PathVertex, next: &PathVertex) -> ScalarF {
+    let pdf = light.normal.dot(direction(light, next)).abs() / (2.0 * PI);
+    convert_density(pdf, light, next)
+}
+
+// area density of choosing `vertex` as the origin of a light subpath
+fn pdf_light_origin(scene: &Scene, vertex: &PathVertex) -> ScalarF {
+    let object = match vertex.hit.as_ref().and_then(|hit| hit.object) {
+        Some(object) => object,
+        None => return 0.0,
+    };
+    match scene
+        .lights
+        .iter()
+        .position(|&i| std::ptr::eq(&scene.objects[i], object))
+    {
+        Some(index) => scene.light_selection_pdf(index) / object.shape.area(),
+        None => 0.0,
+    }
+}
+
+// dielectric objects a ray is inside of and their indices of refraction,
+// innermost last
+#[derive(Debug, Default)]
+pub struct MediaStack(Vec<(usize, ScalarF)>);
+
+impl MediaStack {
+    // index of refraction on the other side of the surface of `object_id`,
+    // the innermost medium other than the object itself
+    pub fn exterior_ior(&self, object_id: usize) -> ScalarF {
+        self.0
+            .iter()
+            .rev()
+            .find(|(id, _)| *id != object_id)
+            .map_or(1.0, |(_, ior)| *ior)
+    }
+
+    pub fn enter(&mut self, object_id: usize, ior: ScalarF) {
+        self.0.push((object_id, ior));
+    }
+
+    pub fn exit(&mut self, object_id: usize) {
+        if let Some(i) = self.0.iter().rposition(|(id, _)| *id == object_id) {
+            self.0.remove(i);
+        }
+    }
+}
+
+// extends `path` by tracing the sampled ray through the scene, its pdf is the
+// solid angle density at the last vertex of `path`
+#[allow(clippy::too_many_arguments)]
+fn random_walk<'a>(
+    start: ScatterResult,
     scene: &'a Scene,
     sampler: &mut dyn Sampler,
+    beta: Vec3D,
     min_depth: usize,
     max_depth: usize,
-) -> Vec<PathVertex<'a>> {
-    let mut path: Vec<PathVertex> = Vec::new();
-    let mut beta = Vec3D::new(1.0, 1.0, 1.0);
-    let mut ray = camera_ray.clone();
-
-    let path_vertex = PathVertex {
-        position: ray.origin,
-        normal: Vec3D::zero(),
-        beta: beta,
-        material: None,
-    };
-    path.push(path_vertex);
+    media: &mut MediaStack,
+    path: &mut Vec<PathVertex<'a>>,
+) {
+    let mut beta = beta;
+    let mut pdf_fwd = start.pdf;
+    let mut ray = start.ray;
 
     for depth in 0..max_depth {
         let hit = scene.intersect(&ray);
+
+        // the medium may scatter the ray before it gets to the surface,
+        // medium vertices are treated like specular ones and aren't
+        // connected to
+        if let Some(medium) = &scene.medium {
+            let length = ray.direction.magnitude();
+            let t_max = hit.as_ref().map_or(ScalarF::INFINITY, |hit| hit.t * length);
+            match medium.sample_distance(t_max, sampler) {
+                Some((distance, weight)) => {
+                    beta = beta.mul_element_wise(weight);
+                    let position = ray.origin + ray.direction * (distance / length);
+                    path.push(PathVertex {
+                        position,
+                        normal: Vec3D::zero(),
+                        beta,
+                        material: None,
+                        background: None,
+                        hit: None,
+                        delta: true,
+                        pdf_fwd: 0.0,
+                        pdf_rev: 0.0,
+                    });
+                    ray = Ray {
+                        origin: position,
+                        direction: medium.phase_function().sample(&ray, sampler).0,
+                    };
+                    pdf_fwd = 0.0;
+                    continue;
+                }
+                None => beta = beta.mul_element_wise(medium.pass_weight(t_max)),
+            }
+        }
+
         if hit.is_none() {
+            path.push(PathVertex {
+                position: ray.origin + ray.direction,
+                normal: Vec3D::zero(),
+                beta,
+                material: None,
+                background: Some(scene.background_radiance(ray.direction)),
+                hit: None,
+                delta: false,
+                pdf_fwd: 0.0,
+                pdf_rev: 0.0,
+            });
             break;
         }
 
-        let hit = hit.unwrap();
+        let mut hit = hit.unwrap();
         let material = &hit.object.unwrap().material;
+        if material.ior().is_some() {
+            hit.exterior_ior = Some(media.exterior_ior(hit.object_id));
+        }
 
-        let path_vertex = PathVertex {
+        let mut path_vertex = PathVertex {
             position: hit.p,
             normal: hit.normal,
             beta: beta,
             material: Some(material),
+            background: None,
+            hit: Some(hit.clone()),
+            delta: material.is_specular(),
+            pdf_fwd: 0.0,
+            pdf_rev: 0.0,
         };
+        path_vertex.pdf_fwd = convert_density(pdf_fwd, path.last().unwrap(), &path_vertex);
         path.push(path_vertex);
 
         if material.emission().magnitude() > 1e-6 {
@@ -63,7 +217,7 @@ pub fn generate_camera_vertices<'a>(
         }
         beta /= continue_prob;
 
-        let scatter_result = material.scatter(&ray, hit.p, hit.normal, sampler);
+        let scatter_result = material.scatter(&ray, &hit, sampler);
         if scatter_result.is_none() {
             break;
         }
@@ -73,8 +227,20 @@ pub fn generate_camera_vertices<'a>(
             break;
         }
 
+        // refracted rays move into or out of the object's medium
+        if let Some(ior) = material.ior() {
+            let cos_in = ray.direction.dot(hit.normal);
+            if cos_in * scatter_result.ray.direction.dot(hit.normal) > 0.0 {
+                if hit.front_face {
+                    media.enter(hit.object_id, ior);
+                } else {
+                    media.exit(hit.object_id);
+                }
+            }
+        }
+
         let cos_theta = scatter_result.ray.direction.dot(hit.normal).abs();
-        let bxdf = material.bxdf(&ray, &scatter_result.ray, hit.p, hit.normal);
+        let bxdf = material.bxdf(&ray, &scatter_result.ray, &hit);
         if !bxdf.is_finite() {
             warn!("bxdf not finite, hit.material: {:?}", material);
         }
@@ -84,12 +250,161 @@ pub fn generate_camera_vertices<'a>(
             warn!("beta not finite");
         }
 
+        // specular vertices can't be connected to, their densities are unused
+        let pdf_rev = if material.is_specular() {
+            pdf_fwd = 0.0;
+            0.0
+        } else {
+            pdf_fwd = scatter_result.pdf;
+            let reversed_in = Ray {
+                origin: scatter_result.ray.origin,
+                direction: -scatter_result.ray.direction,
+            };
+            let reversed_out = Ray {
+                origin: hit.p,
+                direction: -ray.direction,
+            };
+            material.pdf(&reversed_in, &reversed_out, &hit)
+        };
+        let n = path.len();
+        let (prev, current) = path.split_at_mut(n - 1);
+        let prev = prev.last_mut().unwrap();
+        prev.pdf_rev = convert_density(pdf_rev, &current[0], prev);
+
         ray = scatter_result.ray.clone();
     }
+}
+
+pub fn generate_camera_vertices<'a>(
+    camera_ray: &Ray,
+    scene: &'a Scene,
+    sampler: &mut dyn Sampler,
+    min_depth: usize,
+    max_depth: usize,
+) -> Vec<PathVertex<'a>> {
+    let mut path: Vec<PathVertex> = Vec::new();
+
+    let path_vertex = PathVertex {
+        position: camera_ray.origin,
+        normal: Vec3D::zero(),
+        beta: Vec3D::new(1.0, 1.0, 1.0),
+        material: None,
+        background: None,
+        hit: None,
+        delta: false,
+        pdf_fwd: 0.0,
+        pdf_rev: 0.0,
+    };
+    path.push(path_vertex);
+
+    // strategies with a single camera vertex are not used, so the density of
+    // the camera ray never enters the MIS weights
+    random_walk(
+        ScatterResult::new(camera_ray.clone(), 0.0),
+        scene,
+        sampler,
+        Vec3D::new(1.0, 1.0, 1.0),
+        min_depth,
+        max_depth,
+        &mut MediaStack::default(),
+        &mut path,
+    );
 
     return path;
 }
 
+// samples a point on a light uniformly by light count and area, the
+// returned vertex carries Le / pdf as its throughput
+fn sample_light<'a>(scene: &'a Scene, sampler: &mut dyn Sampler) -> Option<PathVertex<'a>> {
+    let (index, selection_pdf) = scene.sample_light_index(sampler.get_1d())?;
+    if index >= scene.lights.len() {
+        // analytic lights have no surface to start a light subpath on
+        return None;
+    }
+    sample_area_light(scene, index, selection_pdf, sampler)
+}
+
+// point on the surface of `scene.lights[index]`, which was chosen with
+// probability `selection_pdf`
+fn sample_area_light<'a>(
+    scene: &'a Scene,
+    index: usize,
+    selection_pdf: ScalarF,
+    sampler: &mut dyn Sampler,
+) -> Option<PathVertex<'a>> {
+    let object = &scene.objects[scene.lights[index]];
+    let sample = object.shape.sample(sampler)?;
+    let pdf = sample.pdf * selection_pdf;
+
+    Some(PathVertex {
+        position: sample.p,
+        normal: sample.normal,
+        beta: object.material.emission() / pdf,
+        material: Some(&object.material),
+        background: None,
+        hit: Some(HitRecord {
+            t: 0.0,
+            p: sample.p,
+            normal: sample.normal,
+            front_face: true, // not hit by a ray, the normal points outwards
+            uv: None,
+            tangent: None,
+            bitangent: None,
+            vertex_color: None,
+            shape: Some(object.shape.as_ref()),
+            object: Some(object),
+            object_id: object.id,
+            exterior_ior: None,
+        }),
+        delta: false,
+        pdf_fwd: pdf,
+        pdf_rev: 0.0,
+    })
+}
+
+pub fn generate_light_vertices<'a>(
+    scene: &'a Scene,
+    sampler: &mut dyn Sampler,
+    min_depth: usize,
+    max_depth: usize,
+) -> Vec<PathVertex<'a>> {
+    let mut path: Vec<PathVertex> = Vec::new();
+    let light_vertex = match sample_light(scene, sampler) {
+        Some(light_vertex) => light_vertex,
+        None => return path,
+    };
+
+    // cosine distributed emission on a randomly chosen side
+    let normal = if sampler.get_1d() < 0.5 {
+        light_vertex.normal
+    } else {
+        -light_vertex.normal
+    };
+    let (u, v) = sampler.get_2d();
+    let theta = (1.0 - u).sqrt().acos();
+    let phi = 2.0 * PI * v;
+    let direction = spherical_to_world(theta, phi, normal);
+    let cos_theta = direction.dot(normal);
+    let pdf = cos_theta / (2.0 * PI);
+
+    let ray = spawn_ray(light_vertex.position, normal, direction);
+    let beta = light_vertex.beta * cos_theta / pdf;
+    path.push(light_vertex);
+    if pdf > 1e-6 {
+        let start = ScatterResult::new(ray, pdf);
+        let mut media = MediaStack::default();
+        random_walk(
+            start, scene, sampler, beta, min_depth, max_depth, &mut media, &mut path,
+        );
+    }
+
+    // the background is not a vertex light can be scattered at
+    if path.last().unwrap().background.is_some() {
+        path.pop();
+    }
+    path
+}
+
 pub fn emissive_material(material: &Option<&Arc<dyn Material>>) -> bool {
     if material.is_none() {
         return false;
@@ -98,30 +413,878 @@ pub fn emissive_material(material: &Option<&Arc<dyn Material>>) -> bool {
     material.emission().magnitude() > 1e-6
 }
 
+// radiance the surface at `vertex` emits back towards `prev`
+fn emitted_towards(prev: &PathVertex, vertex: &PathVertex) -> Vec3D {
+    let material = vertex.material.unwrap();
+    match &vertex.hit {
+        Some(hit) => {
+            let ray = Ray {
+                origin: prev.position,
+                direction: direction(prev, vertex),
+            };
+            material.emitted(&ray, hit)
+        }
+        None => material.emission(),
+    }
+}
+
+fn remap_mis_pdf(pdf: ScalarF) -> ScalarF {
+    // delta vertices have zero densities, they cancel out in the ratios
+    if pdf != 0.0 {
+        pdf
+    } else {
+        1.0
+    }
+}
+
+// power heuristic weight of the (s, t) strategy against all other strategies
+// with t >= 2 that generate a path of the same length, following Veach
+pub fn mis_weight_bdpt(
+    scene: &Scene,
+    camera_vertices: &[PathVertex],
+    light_vertices: &[PathVertex],
+    s: usize,
+    t: usize,
+) -> ScalarF {
+    let pt = &camera_vertices[t - 1];
+    if s + t == 2 || pt.background.is_some() {
+        return 1.0;
+    }
+    if light_vertices.is_empty() {
+        // without a light subpath the only strategy is s = 0
+        return 1.0;
+    }
+
+    let qs = if s > 0 {
+        Some(&light_vertices[s - 1])
+    } else {
+        None
+    };
+    let pt_minus = &camera_vertices[t - 2];
+    let qs_minus = if s > 1 {
+        Some(&light_vertices[s - 2])
+    } else {
+        None
+    };
+
+    let mut camera_fwd: Vec<ScalarF> = camera_vertices[..t].iter().map(|v| v.pdf_fwd).collect();
+    let mut camera_rev: Vec<ScalarF> = camera_vertices[..t].iter().map(|v| v.pdf_rev).collect();
+    let mut camera_delta: Vec<bool> = camera_vertices[..t].iter().map(|v| v.delta).collect();
+    let light_fwd: Vec<ScalarF> = light_vertices[..s].iter().map(|v| v.pdf_fwd).collect();
+    let mut light_rev: Vec<ScalarF> = light_vertices[..s].iter().map(|v| v.pdf_rev).collect();
+    let mut light_delta: Vec<bool> = light_vertices[..s].iter().map(|v| v.delta).collect();
+
+    // densities around the connection depend on the strategy
+    camera_delta[t - 1] = false;
+    match qs {
+        Some(qs) => {
+            camera_rev[t - 1] = vertex_pdf(qs_minus, qs, pt);
+            camera_rev[t - 2] = vertex_pdf(Some(qs), pt, pt_minus);
+            light_rev[s - 1] = vertex_pdf(Some(pt_minus), pt, qs);
+            light_delta[s - 1] = false;
+            if let Some(qs_minus) = qs_minus {
+                light_rev[s - 2] = vertex_pdf(Some(pt), qs, qs_minus);
+            }
+        }
+        None => {
+            camera_rev[t - 1] = pdf_light_origin(scene, pt);
+            camera_rev[t - 2] = pdf_light(pt, pt_minus);
+        }
+    }
+    for pdf in camera_fwd
+        .iter_mut()
+        .chain(camera_rev.iter_mut())
+        .chain(light_rev.iter_mut())
+    {
+        *pdf = remap_mis_pdf(*pdf);
+    }
+    let light_fwd: Vec<ScalarF> = light_fwd.into_iter().map(remap_mis_pdf).collect();
+
+    let mut sum_ri = 0.0;
+    let mut ri = 1.0;
+    for i in (2..t).rev() {
+        ri *= camera_rev[i] / camera_fwd[i];
+        if !camera_delta[i] && !camera_delta[i - 1] {
+            sum_ri += ri * ri;
+        }
+    }
+
+    let mut ri = 1.0;
+    for i in (0..s).rev() {
+        ri *= light_rev[i] / light_fwd[i];
+        let prev_delta = i > 0 && light_delta[i - 1];
+        if !light_delta[i] && !prev_delta {
+            sum_ri += ri * ri;
+        }
+    }
+
+    1.0 / (1.0 + sum_ri)
+}
+
+// fraction of the light that makes it `distance` through the scene's medium
+fn medium_transmittance(scene: &Scene, distance: ScalarF) -> Vec3D {
+    match &scene.medium {
+        Some(medium) => medium.transmittance(distance),
+        None => Vec3D::from_value(1.0),
+    }
+}
+
+fn visible(scene: &Scene, from: &PathVertex, to: &PathVertex) -> bool {
+    // both ends are moved off their surfaces towards each other
+    let direction = to.position - from.position;
+    let origin = spawn_ray(from.position, from.normal, direction).origin;
+    let target = spawn_ray(to.position, to.normal, -direction).origin;
+    let w = target - origin;
+    let distance = w.magnitude();
+    if w.dot(direction) <= 0.0 {
+        return false; // the ends touch
+    }
+    let ray = Ray {
+        origin,
+        direction: w / distance,
+    };
+    scene.intersect_bounded(&ray, 0.0, distance).is_none()
+}
+
+// bxdf at `current` for light arriving from `from` and leaving to `to`
+fn vertex_bxdf(from: &PathVertex, current: &PathVertex, to: &PathVertex) -> Vec3D {
+    let (material, hit) = match (current.material, &current.hit) {
+        (Some(material), Some(hit)) => (material, hit),
+        _ => return Vec3D::zero(),
+    };
+    let ray_in = Ray {
+        origin: from.position,
+        direction: direction(from, current),
+    };
+    let ray_out = Ray {
+        origin: current.position,
+        direction: direction(current, to),
+    };
+    material.bxdf(&ray_in, &ray_out, hit)
+}
+
+fn geometry_term(a: &PathVertex, b: &PathVertex) -> ScalarF {
+    let w = b.position - a.position;
+    let distance2 = w.magnitude2();
+    let w = w / distance2.sqrt();
+    a.normal.dot(w).abs() * b.normal.dot(w).abs() / distance2
+}
+
 pub fn connect(
-    _scene: &Scene,
-    camera_vertices: &Vec<PathVertex>,
-    _light_vertices: &Vec<PathVertex>,
+    scene: &Scene,
+    camera_vertices: &[PathVertex],
+    light_vertices: &[PathVertex],
     s: usize,
     t: usize,
-    _sampler: &mut dyn Sampler,
+    sampler: &mut dyn Sampler,
 ) -> Vec3D {
     let mut color = Vec3D::zero();
 
-    if t > 1 && s > 0 && emissive_material(&camera_vertices[t - 1].material) {
+    if t > 1
+        && s > 0
+        && (emissive_material(&camera_vertices[t - 1].material)
+            || camera_vertices[t - 1].background.is_some())
+    {
         return color;
     }
 
     if s == 0 {
         let vertex = &camera_vertices[t - 1];
-        if emissive_material(&vertex.material) {
+        if let Some(background) = vertex.background {
+            color += vertex.beta.mul_element_wise(background);
+        } else if emissive_material(&vertex.material) {
             color += vertex
                 .beta
-                .mul_element_wise(vertex.material.as_ref().unwrap().emission());
+                .mul_element_wise(emitted_towards(&camera_vertices[t - 2], vertex));
+        } else {
+            return color;
         }
-    } else {
-        panic!("not implemented");
+        return color * mis_weight_bdpt(scene, camera_vertices, light_vertices, s, t);
+    }
+
+    let pt = &camera_vertices[t - 1];
+    let pt_minus = &camera_vertices[t - 2];
+    if pt.delta {
+        return color;
+    }
+
+    if s == 1 {
+        // connect to a freshly sampled point on a light
+        let light_vertex = match sample_light(scene, sampler) {
+            Some(light_vertex) => light_vertex,
+            None => return color,
+        };
+        if !visible(scene, pt, &light_vertex) {
+            return color;
+        }
+        color = pt
+            .beta
+            .mul_element_wise(vertex_bxdf(pt_minus, pt, &light_vertex))
+            .mul_element_wise(light_vertex.beta)
+            .mul_element_wise(medium_transmittance(
+                scene,
+                pt.position.distance(light_vertex.position),
+            ))
+            * geometry_term(pt, &light_vertex);
+        if color.is_zero() {
+            return color;
+        }
+        let light_vertices = std::slice::from_ref(&light_vertex);
+        return color * mis_weight_bdpt(scene, camera_vertices, light_vertices, s, t);
+    }
+
+    let qs = &light_vertices[s - 1];
+    let qs_minus = &light_vertices[s - 2];
+    if qs.delta {
+        return color;
+    }
+    color = qs
+        .beta
+        .mul_element_wise(vertex_bxdf(qs_minus, qs, pt))
+        .mul_element_wise(vertex_bxdf(pt_minus, pt, qs))
+        .mul_element_wise(pt.beta)
+        .mul_element_wise(medium_transmittance(
+            scene,
+            pt.position.distance(qs.position),
+        ))
+        * geometry_term(qs, pt);
+    if color.is_zero() || !visible(scene, pt, qs) {
+        return Vec3D::zero();
+    }
+
+    color * mis_weight_bdpt(scene, camera_vertices, light_vertices, s, t)
+}
+
+// solid angle density at `from` of next event estimation choosing the
+// point `light`
+fn pdf_light_solid_angle(scene: &Scene, from: &PathVertex, light: &PathVertex) -> ScalarF {
+    let w = light.position - from.position;
+    let distance2 = w.magnitude2();
+    let cos_theta = light.normal.dot(w / distance2.sqrt()).abs();
+    if cos_theta < 1e-12 {
+        return 0.0;
     }
+    pdf_light_origin(scene, light) * distance2 / cos_theta
+}
 
+// radiance the last of the first `t` camera vertices emits towards the
+// previous one, weighted against next event estimation from that vertex
+// finding the same light
+pub fn connect_emission_nee(
+    scene: &Scene,
+    camera_vertices: &[PathVertex],
+    t: usize,
+    heuristic: MisHeuristic,
+) -> Vec3D {
+    let vertex = &camera_vertices[t - 1];
+    let prev = &camera_vertices[t - 2];
+    if let Some(background) = vertex.background {
+        let color = vertex.beta.mul_element_wise(background);
+        return match scene.environment() {
+            Some(environment) if t > 2 && !prev.delta => {
+                let pdf_scatter = scatter_pdf(&camera_vertices[t - 3], prev, vertex);
+                let pdf_light = environment.pdf(direction(prev, vertex));
+                color * mis_weight(heuristic, 1, pdf_scatter, 1, pdf_light)
+            }
+            _ => color,
+        };
+    }
+    if !emissive_material(&vertex.material) {
+        return Vec3D::zero();
+    }
+
+    let emitted = vertex.beta.mul_element_wise(emitted_towards(prev, vertex));
+    if t == 2 || prev.delta {
+        // seen straight from the camera or through a mirror, lights can't
+        // be connected to from there
+        return emitted;
+    }
+    let pdf_scatter = scatter_pdf(&camera_vertices[t - 3], prev, vertex);
+    let pdf_light = pdf_light_solid_angle(scene, prev, vertex);
+    emitted * mis_weight(heuristic, 1, pdf_scatter, 1, pdf_light)
+}
+
+// direct lighting at the last of the first `t` camera vertices, through a
+// shadow ray to a sampled point on a light and another one towards the
+// environment
+pub fn next_event_estimation(
+    scene: &Scene,
+    camera_vertices: &[PathVertex],
+    t: usize,
+    sampler: &mut dyn Sampler,
+    heuristic: MisHeuristic,
+) -> Vec3D {
+    let pt = &camera_vertices[t - 1];
+    let pt_minus = &camera_vertices[t - 2];
+    if pt.delta
+        || pt.material.is_none()
+        || pt.background.is_some()
+        || emissive_material(&pt.material)
+    {
+        return Vec3D::zero();
+    }
+
+    let mut color = Vec3D::zero();
+    if let Some((index, selection_pdf)) = scene.sample_light_index(sampler.get_1d()) {
+        color += match index.checked_sub(scene.lights.len()) {
+            None => match sample_area_light(scene, index, selection_pdf, sampler) {
+                Some(light_vertex) => {
+                    area_light_contribution(scene, pt_minus, pt, &light_vertex, heuristic)
+                }
+                None => Vec3D::zero(),
+            },
+            Some(i) => {
+                let light = scene.analytic_lights[i].as_ref();
+                analytic_light_contribution(scene, pt_minus, pt, light, selection_pdf, sampler)
+            }
+        };
+    }
+    if let Some(environment) = scene.environment() {
+        if let Some(sample) = environment.sample_li(sampler) {
+            color += environment_contribution(scene, pt_minus, pt, &sample, heuristic);
+        }
+    }
     color
 }
+
+fn area_light_contribution(
+    scene: &Scene,
+    pt_minus: &PathVertex,
+    pt: &PathVertex,
+    light_vertex: &PathVertex,
+    heuristic: MisHeuristic,
+) -> Vec3D {
+    if !visible(scene, pt, light_vertex) {
+        return Vec3D::zero();
+    }
+    let color = pt
+        .beta
+        .mul_element_wise(vertex_bxdf(pt_minus, pt, light_vertex))
+        .mul_element_wise(light_vertex.beta)
+        .mul_element_wise(medium_transmittance(
+            scene,
+            pt.position.distance(light_vertex.position),
+        ))
+        * geometry_term(pt, light_vertex);
+    if color.is_zero() {
+        return color;
+    }
+
+    let pdf_light = pdf_light_solid_angle(scene, pt, light_vertex);
+    let pdf_scatter = scatter_pdf(pt_minus, pt, light_vertex);
+    color * mis_weight(heuristic, 1, pdf_light, 1, pdf_scatter)
+}
+
+// stands in for the vertex a ray leaving `pt` along `direction` would reach
+// when there is no surface to sample there
+fn direction_vertex<'a>(
+    pt: &PathVertex,
+    direction: Vec3D,
+    background: Option<Vec3D>,
+) -> PathVertex<'a> {
+    PathVertex {
+        position: pt.position + direction,
+        normal: Vec3D::zero(),
+        beta: Vec3D::zero(),
+        material: None,
+        background,
+        hit: None,
+        delta: false,
+        pdf_fwd: 0.0,
+        pdf_rev: 0.0,
+    }
+}
+
+// analytic lights are delta lights, scattering can never find them so the
+// shadow ray takes the full weight
+fn analytic_light_contribution(
+    scene: &Scene,
+    pt_minus: &PathVertex,
+    pt: &PathVertex,
+    light: &dyn Light,
+    selection_pdf: ScalarF,
+    sampler: &mut dyn Sampler,
+) -> Vec3D {
+    let sample = light.sample_li(pt.position, sampler);
+    if sample.pdf <= 0.0 {
+        return Vec3D::zero();
+    }
+    let ray = spawn_ray(pt.position, pt.normal, sample.direction);
+    if scene
+        .intersect_bounded(&ray, 0.0, sample.distance.min(ScalarF::MAX))
+        .is_some()
+    {
+        return Vec3D::zero();
+    }
+
+    let light_vertex = direction_vertex(pt, sample.direction, None);
+    let cos_theta = pt.normal.dot(sample.direction).abs();
+    pt.beta
+        .mul_element_wise(vertex_bxdf(pt_minus, pt, &light_vertex))
+        .mul_element_wise(sample.radiance)
+        .mul_element_wise(medium_transmittance(scene, sample.distance))
+        * (cos_theta / (sample.pdf * selection_pdf))
+}
+
+fn environment_contribution(
+    scene: &Scene,
+    pt_minus: &PathVertex,
+    pt: &PathVertex,
+    sample: &EnvironmentSample,
+    heuristic: MisHeuristic,
+) -> Vec3D {
+    let ray = spawn_ray(pt.position, pt.normal, sample.direction);
+    if scene.intersect_bounded(&ray, 0.0, ScalarF::MAX).is_some() {
+        return Vec3D::zero();
+    }
+
+    let background_vertex = direction_vertex(pt, sample.direction, Some(sample.radiance));
+    let cos_theta = pt.normal.dot(sample.direction).abs();
+    let color = pt
+        .beta
+        .mul_element_wise(vertex_bxdf(pt_minus, pt, &background_vertex))
+        .mul_element_wise(sample.radiance)
+        .mul_element_wise(medium_transmittance(scene, ScalarF::INFINITY))
+        * (cos_theta / sample.pdf);
+    if color.is_zero() {
+        return color;
+    }
+
+    let pdf_scatter = scatter_pdf(pt_minus, pt, &background_vertex);
+    color * mis_weight(heuristic, 1, sample.pdf, 1, pdf_scatter)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::light::luminance;
+    use crate::math::tolerance;
+    use crate::sampler::RandomSampler;
+    use crate::scene::SceneConfig;
+    use crate::tracers::mcpt::MonteCarloPathTracerConfig;
+    use crate::tracers::tracer::Tracer;
+    use crate::tracers::whitted::WhittedTracerConfig;
+
+    // closed unit box with a small light under the ceiling
+    const BOX_SCENE_CONFIG: &str = r#"
+        [camera]
+        type = "Perspective"
+        look_from = { x = 0.5, y = 0.5, z = 0.9 }
+        look_at = { x = 0.5, y = 0.5, z = 0.0 }
+        vup = { x = 0.0, y = 1.0, z = 0.0 }
+        vfov = 90.0
+        aspect = 1.0
+
+        [[objects]]
+        shape = { type = "Quadrilateral", vertices = [{ x = 0.0, y = 0.0, z = 0.0 }, { x = 1.0, y = 0.0, z = 0.0 }, { x = 1.0, y = 0.0, z = 1.0 }, { x = 0.0, y = 0.0, z = 1.0 }] }
+        material = { type = "Lambertian", albedo = { x = 0.8, y = 0.8, z = 0.8 } }
+
+        [[objects]]
+        shape = { type = "Quadrilateral", vertices = [{ x = 0.0, y = 1.0, z = 0.0 }, { x = 1.0, y = 1.0, z = 0.0 }, { x = 1.0, y = 1.0, z = 1.0 }, { x = 0.0, y = 1.0, z = 1.0 }] }
+        material = { type = "Lambertian", albedo = { x = 0.8, y = 0.8, z = 0.8 } }
+
+        [[objects]]
+        shape = { type = "Quadrilateral", vertices = [{ x = 0.0, y = 0.0, z = 0.0 }, { x = 1.0, y = 0.0, z = 0.0 }, { x = 1.0, y = 1.0, z = 0.0 }, { x = 0.0, y = 1.0, z = 0.0 }] }
+        material = { type = "Lambertian", albedo = { x = 0.8, y = 0.8, z = 0.8 } }
+
+        [[objects]]
+        shape = { type = "Quadrilateral", vertices = [{ x = 0.0, y = 0.0, z = 1.0 }, { x = 1.0, y = 0.0, z = 1.0 }, { x = 1.0, y = 1.0, z = 1.0 }, { x = 0.0, y = 1.0, z = 1.0 }] }
+        material = { type = "Lambertian", albedo = { x = 0.8, y = 0.8, z = 0.8 } }
+
+        [[objects]]
+        shape = { type = "Quadrilateral", vertices = [{ x = 0.0, y = 0.0, z = 0.0 }, { x = 0.0, y = 1.0, z = 0.0 }, { x = 0.0, y = 1.0, z = 1.0 }, { x = 0.0, y = 0.0, z = 1.0 }] }
+        material = { type = "Lambertian", albedo = { x = 0.8, y = 0.2, z = 0.2 } }
+
+        [[objects]]
+        shape = { type = "Quadrilateral", vertices = [{ x = 1.0, y = 0.0, z = 0.0 }, { x = 1.0, y = 1.0, z = 0.0 }, { x = 1.0, y = 1.0, z = 1.0 }, { x = 1.0, y = 0.0, z = 1.0 }] }
+        material = { type = "Lambertian", albedo = { x = 0.2, y = 0.8, z = 0.2 } }
+
+        [[objects]]
+        shape = { type = "Quadrilateral", vertices = [{ x = 0.3, y = 0.99, z = 0.3 }, { x = 0.7, y = 0.99, z = 0.3 }, { x = 0.7, y = 0.99, z = 0.7 }, { x = 0.3, y = 0.99, z = 0.7 }] }
+        material = { type = "Emissive", color = { x = 5.0, y = 5.0, z = 5.0 } }
+    "#;
+
+    // reverses a camera subpath ending on a light into the light subpath
+    // that would have produced the same vertices
+    fn reverse_path<'a>(scene: &Scene, path: &[PathVertex<'a>]) -> Vec<PathVertex<'a>> {
+        let n = path.len();
+        let mut light_vertices: Vec<PathVertex> = path[1..]
+            .iter()
+            .rev()
+            .map(|v| PathVertex {
+                pdf_fwd: v.pdf_rev,
+                pdf_rev: v.pdf_fwd,
+                ..v.clone()
+            })
+            .collect();
+        light_vertices[0].pdf_fwd = pdf_light_origin(scene, &path[n - 1]);
+        light_vertices[1].pdf_fwd = pdf_light(&path[n - 1], &path[n - 2]);
+        light_vertices
+    }
+
+    #[test]
+    fn test_mis_weights_sum_to_one() {
+        let scene_config: SceneConfig = toml::from_str(BOX_SCENE_CONFIG).unwrap();
+        let scene = Scene::from_config(&scene_config).unwrap();
+        assert_eq!(scene.lights.len(), 1);
+
+        let mut sampler = RandomSampler::new(1);
+        let mut checked = 0;
+        while checked < 20 {
+            let (u, v) = sampler.get_2d();
+            let ray = scene.camera.create_ray(u, v);
+            let path = generate_camera_vertices(&ray, &scene, &mut sampler, 100, 6);
+            let n = path.len();
+            if n < 4 || !emissive_material(&path[n - 1].material) {
+                continue;
+            }
+
+            let light_vertices = reverse_path(&scene, &path);
+            let sum: ScalarF = (2..=n)
+                .map(|t| mis_weight_bdpt(&scene, &path[..t], &light_vertices, n - t, t))
+                .sum();
+            // rays start slightly off their vertices, the densities don't
+            // follow the vertex positions exactly. f32 math moves them further
+            let epsilon = if cfg!(feature = "f32_math") {
+                1e-3
+            } else {
+                1e-6
+            };
+            assert!(
+                (sum - 1.0).abs() < epsilon,
+                "weights sum to {} for {} vertices",
+                sum,
+                n
+            );
+            checked += 1;
+        }
+    }
+
+    // mean and standard error of the radiance along `ray`
+    fn estimate(tracer: &mut dyn Tracer, scene: &Scene, ray: &Ray, n: usize) -> (ScalarF, ScalarF) {
+        let mut sampler = RandomSampler::new(1);
+        let samples: Vec<ScalarF> = (0..n)
+            .map(|_| tracer.trace(ray, scene, &mut sampler).x)
+            .collect();
+        let mean = samples.iter().sum::<ScalarF>() / n as ScalarF;
+        let variance =
+            samples.iter().map(|x| (x - mean).powi(2)).sum::<ScalarF>() / (n - 1) as ScalarF;
+        (mean, (variance / n as ScalarF).sqrt())
+    }
+
+    #[test]
+    fn test_next_event_estimation() {
+        let scene_config: SceneConfig = toml::from_str(BOX_SCENE_CONFIG).unwrap();
+        let scene = Scene::from_config(&scene_config).unwrap();
+        let ray = scene.camera.create_ray(0.4, 0.3);
+
+        let config = |next_event_estimation| MonteCarloPathTracerConfig {
+            min_depth: 3,
+            max_depth: 5,
+            next_event_estimation,
+            mis_heuristic: MisHeuristic::Balance,
+        };
+        let (mean, error) = estimate(&mut config(false).to_tracer(), &scene, &ray, 40_000);
+        let (mean_nee, error_nee) = estimate(&mut config(true).to_tracer(), &scene, &ray, 40_000);
+
+        let tolerance = 4.0 * (error * error + error_nee * error_nee).sqrt();
+        assert!(
+            (mean - mean_nee).abs() < tolerance,
+            "without nee: {} +- {}, with nee: {} +- {}",
+            mean,
+            error,
+            mean_nee,
+            error_nee
+        );
+        assert!(error_nee < 0.5 * error);
+    }
+
+    #[test]
+    fn test_bidirectional_matches_unidirectional() {
+        let scene_config: SceneConfig = toml::from_str(BOX_SCENE_CONFIG).unwrap();
+        let scene = Scene::from_config(&scene_config).unwrap();
+        let config = MonteCarloPathTracerConfig {
+            min_depth: 3,
+            max_depth: 5,
+            next_event_estimation: false,
+            mis_heuristic: MisHeuristic::Balance,
+        };
+
+        // 512 samples in each of 4 x 4 pixels spread over the image
+        let render = |tracer: &mut dyn Tracer| {
+            let mut mean = 0.0;
+            let mut variance = 0.0;
+            for i in 0..16 {
+                let ray = scene.camera.create_ray(
+                    0.125 + 0.25 * (i % 4) as ScalarF,
+                    0.125 + 0.25 * (i / 4) as ScalarF,
+                );
+                let (pixel_mean, pixel_error) = estimate(tracer, &scene, &ray, 512);
+                mean += pixel_mean / 16.0;
+                variance += pixel_error * pixel_error / 256.0;
+            }
+            (mean, variance.sqrt())
+        };
+        crate::rng::init_thread_local_rng(5);
+        let (mean, error) = render(&mut config.to_tracer());
+        let (mean_bdpt, error_bdpt) = render(&mut config.to_bidirectional_tracer());
+
+        let tolerance = 3.0 * (error * error + error_bdpt * error_bdpt).sqrt();
+        assert!(
+            (mean - mean_bdpt).abs() < tolerance,
+            "unidirectional: {} +- {}, bidirectional: {} +- {}",
+            mean,
+            error,
+            mean_bdpt,
+            error_bdpt
+        );
+    }
+
+    // lambertian floor lit by a point light above it and a directional light
+    // shining straight down
+    const ANALYTIC_LIGHTS_SCENE_CONFIG: &str = r#"
+        [camera]
+        type = "Perspective"
+        look_from = { x = 0.0, y = 1.0, z = 1.0 }
+        look_at = { x = 0.0, y = 0.0, z = 0.0 }
+        vup = { x = 0.0, y = 1.0, z = 0.0 }
+        vfov = 40.0
+        aspect = 1.0
+
+        [[objects]]
+        shape = { type = "Quadrilateral", vertices = [{ x = -5.0, y = 0.0, z = -5.0 }, { x = -5.0, y = 0.0, z = 5.0 }, { x = 5.0, y = 0.0, z = 5.0 }, { x = 5.0, y = 0.0, z = -5.0 }] }
+        material = { type = "Lambertian", albedo = { x = 0.5, y = 0.5, z = 0.5 } }
+
+        [[lights]]
+        type = "Point"
+        position = { x = 1.0, y = 2.0, z = 0.0 }
+        intensity = { x = 10.0, y = 10.0, z = 10.0 }
+
+        [[lights]]
+        type = "Directional"
+        direction = { x = 0.0, y = -1.0, z = 0.0 }
+        irradiance = { x = 0.2, y = 0.2, z = 0.2 }
+    "#;
+
+    #[test]
+    fn test_analytic_lights() {
+        let scene_config: SceneConfig = toml::from_str(ANALYTIC_LIGHTS_SCENE_CONFIG).unwrap();
+        let scene = Scene::from_config(&scene_config).unwrap();
+        assert_eq!(scene.analytic_lights.len(), 2);
+        let total_power = luminance(scene.analytic_lights[0].power())
+            + luminance(scene.analytic_lights[1].power());
+        let point_power = luminance(scene.analytic_lights[0].power());
+        assert!((scene.light_selection_pdf(0) - point_power / total_power).abs() < tolerance(1e-9));
+
+        // the camera looks at the origin, 5 / sqrt(5) and cos = 2 / sqrt(5)
+        // from the point light
+        let ray = Ray {
+            origin: Point3D::new(0.0, 1.0, 1.0),
+            direction: Vec3D::new(0.0, -1.0, -1.0).normalize(),
+        };
+        let point_irradiance = 10.0 / 5.0 * 2.0 / ScalarF::sqrt(5.0);
+        let expected = 0.5 / PI * (point_irradiance + 0.2);
+
+        let mut tracer = MonteCarloPathTracerConfig {
+            min_depth: 2,
+            max_depth: 2,
+            next_event_estimation: true,
+            mis_heuristic: MisHeuristic::Balance,
+        }
+        .to_tracer();
+        let (mean, error) = estimate(&mut tracer, &scene, &ray, 20_000);
+        assert!(
+            (mean - expected).abs() < 4.0 * error + tolerance(1e-9),
+            "nee: {} +- {}, expected: {}",
+            mean,
+            error,
+            expected
+        );
+
+        let mut tracer = WhittedTracerConfig { max_depth: 1 }.to_tracer();
+        let color = tracer.trace(&ray, &scene, &mut RandomSampler::new(1));
+        assert!((color.x - expected).abs() < tolerance(1e-9));
+    }
+
+    #[test]
+    fn test_nested_dielectrics() {
+        // glass ball in a larger ball of water
+        let scene_config: SceneConfig = toml::from_str(
+            r#"
+            [camera]
+            type = "Perspective"
+            look_from = { x = -5.0, y = 0.0, z = 0.0 }
+            look_at = { x = 0.0, y = 0.0, z = 0.0 }
+            vup = { x = 0.0, y = 1.0, z = 0.0 }
+            vfov = 40.0
+            aspect = 1.0
+
+            [[objects]]
+            shape = { type = "Sphere", center = { x = 0.0, y = 0.0, z = 0.0 }, radius = 2.0 }
+            material = { type = "IdealDielectric", ior = 1.33 }
+
+            [[objects]]
+            shape = { type = "Sphere", center = { x = 0.0, y = 0.0, z = 0.0 }, radius = 1.0 }
+            material = { type = "IdealDielectric", ior = 1.5 }
+        "#,
+        )
+        .unwrap();
+        let scene = Scene::from_config(&scene_config).unwrap();
+        let ray = Ray {
+            origin: Point3D::new(-5.0, 0.5, 0.0),
+            direction: Vec3D::new(1.0, 0.0, 0.0),
+        };
+
+        // the sampled paths that refract at the first four surfaces cross
+        // air -> water -> glass -> water -> air
+        let iors = [(1.0, 1.33), (1.33, 1.5), (1.5, 1.33), (1.33, 1.0)];
+        let mut sampler = RandomSampler::new(1);
+        let mut checked = 0;
+        while checked < 10 {
+            let path = generate_camera_vertices(&ray, &scene, &mut sampler, 100, 5);
+            let refracted = |i: usize| {
+                let (d_in, d_out) = (
+                    direction(&path[i - 1], &path[i]),
+                    direction(&path[i], &path[i + 1]),
+                );
+                (
+                    d_in,
+                    d_out,
+                    d_in.dot(path[i].normal) * d_out.dot(path[i].normal) > 0.0,
+                )
+            };
+            if path.len() < 6 || !(1..5).all(|i| refracted(i).2) {
+                continue;
+            }
+            for (i, (eta_i, eta_t)) in (1..5).zip(iors) {
+                let (d_in, d_out, _) = refracted(i);
+                let normal = path[i].normal;
+                let sin_i = d_in.cross(normal).magnitude();
+                let sin_t = d_out.cross(normal).magnitude();
+                assert!(
+                    (eta_i * sin_i - eta_t * sin_t).abs() < tolerance(1e-9),
+                    "boundary {}: {} sin {} != {} sin {}",
+                    i,
+                    eta_i,
+                    sin_i,
+                    eta_t,
+                    sin_t
+                );
+            }
+            checked += 1;
+        }
+    }
+
+    #[test]
+    fn test_absorbing_medium() {
+        // light 2 units away behind fog that absorbs half of it per unit
+        let scene_config: SceneConfig = toml::from_str(
+            r#"
+            [camera]
+            type = "Perspective"
+            look_from = { x = 0.0, y = 0.0, z = 0.0 }
+            look_at = { x = 0.0, y = 0.0, z = -1.0 }
+            vup = { x = 0.0, y = 1.0, z = 0.0 }
+            vfov = 40.0
+            aspect = 1.0
+
+            [[objects]]
+            shape = { type = "Quadrilateral", vertices = [{ x = -5.0, y = -5.0, z = -2.0 }, { x = 5.0, y = -5.0, z = -2.0 }, { x = 5.0, y = 5.0, z = -2.0 }, { x = -5.0, y = 5.0, z = -2.0 }] }
+            material = { type = "Emissive", color = { x = 1.0, y = 1.0, z = 1.0 } }
+
+            [medium]
+            sigma_a = { x = 0.5, y = 0.5, z = 0.5 }
+            sigma_s = { x = 0.0, y = 0.0, z = 0.0 }
+        "#,
+        )
+        .unwrap();
+        let scene = Scene::from_config(&scene_config).unwrap();
+        let ray = Ray {
+            origin: Point3D::new(0.0, 0.0, 0.0),
+            direction: Vec3D::new(0.0, 0.0, -1.0),
+        };
+        let expected = ScalarF::exp(-1.0);
+
+        crate::rng::init_thread_local_rng(6);
+        for next_event_estimation in [false, true] {
+            let mut tracer = MonteCarloPathTracerConfig {
+                min_depth: 2,
+                max_depth: 2,
+                next_event_estimation,
+                mis_heuristic: MisHeuristic::Balance,
+            }
+            .to_tracer();
+            let (mean, error) = estimate(&mut tracer, &scene, &ray, 20_000);
+            assert!(
+                (mean - expected).abs() < 4.0 * error,
+                "{} +- {}, expected: {}",
+                mean,
+                error,
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn test_shadow_rays_reach_light() {
+        let scene_config: SceneConfig = toml::from_str(BOX_SCENE_CONFIG).unwrap();
+        let scene = Scene::from_config(&scene_config).unwrap();
+        let light_id = scene.lights[0];
+
+        // nothing stands between the walls and the light, every shadow ray
+        // leaving a wall must get to it without hitting its own wall first
+        crate::rng::init_thread_local_rng(6);
+        let mut sampler = RandomSampler::new(1);
+        // shadow rays start a few ulps off the wall, with f32 math that is
+        // enough to move the hit on the light at grazing angles
+        let position_tolerance = if cfg!(feature = "f32_math") {
+            1e-2
+        } else {
+            1e-6
+        };
+        let mut checked = 0;
+        while checked < 1000 {
+            let (u, v) = sampler.get_2d();
+            let ray = scene.camera.create_ray(u, v);
+            let path = generate_camera_vertices(&ray, &scene, &mut sampler, 100, 5);
+            for vertex in &path[1..] {
+                if vertex
+                    .hit
+                    .as_ref()
+                    .is_none_or(|hit| hit.object_id == light_id)
+                {
+                    continue;
+                }
+                let light_vertex = sample_area_light(&scene, 0, 1.0, &mut sampler).unwrap();
+                assert!(visible(&scene, vertex, &light_vertex));
+
+                let direction = light_vertex.position - vertex.position;
+                let shadow_ray = spawn_ray(vertex.position, vertex.normal, direction);
+                let hit = scene.intersect(&shadow_ray).unwrap();
+                assert_eq!(hit.object_id, light_id);
+                assert!((hit.p - light_vertex.position).magnitude() < position_tolerance);
+                checked += 1;
+            }
+        }
+    }
+
+    #[test]
+    fn test_nee_skips_delta_vertices() {
+        let scene_config: SceneConfig = toml::from_str(BOX_SCENE_CONFIG).unwrap();
+        let scene = Scene::from_config(&scene_config).unwrap();
+        let mut sampler = RandomSampler::new(1);
+        let ray = scene.camera.create_ray(0.4, 0.3);
+        let mut path = generate_camera_vertices(&ray, &scene, &mut sampler, 100, 2);
+        assert!(path.len() >= 2);
+
+        let total = |path: &[PathVertex], sampler: &mut RandomSampler| {
+            (0..100)
+                .map(|_| next_event_estimation(&scene, path, 2, sampler, MisHeuristic::Power).x)
+                .sum::<ScalarF>()
+        };
+        assert!(total(&path, &mut sampler) > 0.0);
+        // a mirror's delta lobe never lines up with a sampled light point
+        path[1].delta = true;
+        assert_eq!(total(&path, &mut sampler), 0.0);
+    }
+}
@@ -0,0 +1,277 @@
+use super::super::common::HitRecord;
+use super::super::math::{spawn_ray, Ray, ScalarF, Vec3D};
+use super::super::sampler::Sampler;
+use super::super::scene::Scene;
+use super::tracer::Tracer;
+use cgmath::{ElementWise, InnerSpace, Zero};
+use serde::Deserialize;
+
+// classic whitted style tracer: specular surfaces are followed, every other
+// surface terminates the path with emission plus direct lighting
+pub struct WhittedTracer {
+    max_depth: u32,
+}
+
+#[derive(Deserialize)]
+pub struct WhittedTracerConfig {
+    pub max_depth: u32,
+}
+
+impl WhittedTracer {
+    // radiance reaching `hit` straight from every light, one shadow ray per
+    // light, area and analytic alike
+    fn direct_lighting(
+        &self,
+        ray: &Ray,
+        hit: &HitRecord,
+        scene: &Scene,
+        sampler: &mut dyn Sampler,
+    ) -> Vec3D {
+        let material = &hit.object.unwrap().material;
+        let mut color = Vec3D::zero();
+
+        for &i in &scene.lights {
+            let light = &scene.objects[i];
+            let sample = match light.shape.sample(sampler) {
+                Some(sample) => sample,
+                None => continue,
+            };
+
+            let w = sample.p - hit.p;
+            let distance2 = w.magnitude2();
+            let distance = distance2.sqrt();
+            let direction = w / distance;
+            let shadow_ray = spawn_ray(hit.p, hit.normal, direction);
+            // stop short of the light, its far end is moved off the light too
+            let target = spawn_ray(sample.p, sample.normal, -direction).origin;
+            let t_max = (target - shadow_ray.origin).dot(direction);
+            if t_max <= 0.0 || scene.intersect_bounded(&shadow_ray, 0.0, t_max).is_some() {
+                continue;
+            }
+
+            let bxdf = material.bxdf(ray, &shadow_ray, hit);
+            let g =
+                hit.normal.dot(direction).abs() * sample.normal.dot(direction).abs() / distance2;
+            color += light.material.emission().mul_element_wise(bxdf) * (g / sample.pdf);
+        }
+
+        for light in &scene.analytic_lights {
+            let sample = light.sample_li(hit.p, sampler);
+            if sample.pdf <= 0.0 {
+                continue;
+            }
+            let shadow_ray = spawn_ray(hit.p, hit.normal, sample.direction);
+            let t_max = sample.distance.min(ScalarF::MAX);
+            if scene.intersect_bounded(&shadow_ray, 0.0, t_max).is_some() {
+                continue;
+            }
+
+            let bxdf = material.bxdf(ray, &shadow_ray, hit);
+            let cos_theta = hit.normal.dot(sample.direction).abs();
+            color += sample.radiance.mul_element_wise(bxdf) * (cos_theta / sample.pdf);
+        }
+
+        color
+    }
+}
+
+impl Tracer for WhittedTracer {
+    // every specular direction is followed, reflection and refraction alike.
+    // pending rays live on an explicit stack so that facing mirrors can't
+    // overflow the call stack, each path is cut after `max_depth` surfaces
+    // and the remaining light is taken as black. this loses energy on deep
+    // mirror chains but the missing contribution is rarely visible.
+    fn trace(&mut self, ray: &Ray, scene: &Scene, sampler: &mut dyn Sampler) -> Vec3D {
+        let mut color = Vec3D::zero();
+        let mut stack = vec![(ray.clone(), Vec3D::new(1.0, 1.0, 1.0), 0)];
+
+        while let Some((ray, beta, depth)) = stack.pop() {
+            if depth >= self.max_depth {
+                continue;
+            }
+            let hit = match scene.intersect(&ray) {
+                Some(hit) => hit,
+                None => {
+                    color += beta.mul_element_wise(scene.background_radiance(ray.direction));
+                    continue;
+                }
+            };
+
+            let material = &hit.object.unwrap().material;
+            color += beta.mul_element_wise(material.emitted(&ray, &hit));
+
+            if !material.is_specular() {
+                color += beta.mul_element_wise(self.direct_lighting(&ray, &hit, scene, sampler));
+                continue;
+            }
+
+            let directions = material.specular_directions(&ray, &hit);
+            if directions.is_empty() {
+                // specular materials that can only be sampled
+                if let Some(scatter_result) = material.scatter(&ray, &hit, sampler) {
+                    if scatter_result.pdf > 1e-6 {
+                        let cos_theta = scatter_result.ray.direction.dot(hit.normal).abs();
+                        let bxdf = material.bxdf(&ray, &scatter_result.ray, &hit);
+                        let weight = cos_theta * bxdf / scatter_result.pdf;
+                        stack.push((scatter_result.ray, beta.mul_element_wise(weight), depth + 1));
+                    }
+                }
+                continue;
+            }
+            for direction in directions {
+                let ray_out = spawn_ray(hit.p, hit.normal, direction);
+                let cos_theta = direction.dot(hit.normal).abs();
+                let weight = cos_theta * material.bxdf(&ray, &ray_out, &hit);
+                if !weight.is_zero() {
+                    stack.push((ray_out, beta.mul_element_wise(weight), depth + 1));
+                }
+            }
+        }
+
+        color
+    }
+}
+
+impl WhittedTracerConfig {
+    pub fn to_tracer(&self) -> WhittedTracer {
+        WhittedTracer {
+            max_depth: self.max_depth,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::{tolerance, vec3_approx_eq, Point3D};
+    use crate::sampler::RandomSampler;
+    use crate::scene::SceneConfig;
+    use std::sync::atomic::Ordering;
+
+    // two mirrors facing each other, rays between them never escape
+    const FACING_MIRRORS_SCENE_CONFIG: &str = r#"
+        [camera]
+        type = "Perspective"
+        look_from = { x = 0.0, y = 0.0, z = 5.0 }
+        look_at = { x = 0.0, y = 0.0, z = 0.0 }
+        vup = { x = 0.0, y = 1.0, z = 0.0 }
+        vfov = 40.0
+        aspect = 1.0
+
+        [[objects]]
+        shape = { type = "Plane", point = { x = 0.0, y = -1.0, z = 0.0 }, normal = { x = 0.0, y = 1.0, z = 0.0 } }
+        material = { type = "IdealReflector" }
+
+        [[objects]]
+        shape = { type = "Plane", point = { x = 0.0, y = 1.0, z = 0.0 }, normal = { x = 0.0, y = -1.0, z = 0.0 } }
+        material = { type = "IdealReflector" }
+    "#;
+
+    // unit box with a mirror as back wall, a blue front wall and a glass ball,
+    // lit by a point light so that whitted tracing is deterministic
+    const MIRROR_BOX_SCENE_CONFIG: &str = r#"
+        [camera]
+        type = "Perspective"
+        look_from = { x = 0.5, y = 0.5, z = 0.9 }
+        look_at = { x = 0.5, y = 0.5, z = 0.0 }
+        vup = { x = 0.0, y = 1.0, z = 0.0 }
+        vfov = 90.0
+        aspect = 1.0
+
+        [[objects]]
+        shape = { type = "Quadrilateral", vertices = [{ x = 0.0, y = 0.0, z = 0.0 }, { x = 1.0, y = 0.0, z = 0.0 }, { x = 1.0, y = 1.0, z = 0.0 }, { x = 0.0, y = 1.0, z = 0.0 }] }
+        material = { type = "IdealReflector" }
+
+        [[objects]]
+        shape = { type = "Quadrilateral", vertices = [{ x = 0.0, y = 0.0, z = 1.0 }, { x = 1.0, y = 0.0, z = 1.0 }, { x = 1.0, y = 1.0, z = 1.0 }, { x = 0.0, y = 1.0, z = 1.0 }] }
+        material = { type = "Lambertian", albedo = { x = 0.1, y = 0.1, z = 0.8 } }
+
+        [[objects]]
+        shape = { type = "Quadrilateral", vertices = [{ x = 0.0, y = 0.0, z = 0.0 }, { x = 1.0, y = 0.0, z = 0.0 }, { x = 1.0, y = 0.0, z = 1.0 }, { x = 0.0, y = 0.0, z = 1.0 }] }
+        material = { type = "Lambertian", albedo = { x = 0.8, y = 0.8, z = 0.8 } }
+
+        [[objects]]
+        shape = { type = "Quadrilateral", vertices = [{ x = 0.0, y = 1.0, z = 0.0 }, { x = 1.0, y = 1.0, z = 0.0 }, { x = 1.0, y = 1.0, z = 1.0 }, { x = 0.0, y = 1.0, z = 1.0 }] }
+        material = { type = "Lambertian", albedo = { x = 0.8, y = 0.8, z = 0.8 } }
+
+        [[objects]]
+        shape = { type = "Quadrilateral", vertices = [{ x = 0.0, y = 0.0, z = 0.0 }, { x = 0.0, y = 1.0, z = 0.0 }, { x = 0.0, y = 1.0, z = 1.0 }, { x = 0.0, y = 0.0, z = 1.0 }] }
+        material = { type = "Lambertian", albedo = { x = 0.8, y = 0.2, z = 0.2 } }
+
+        [[objects]]
+        shape = { type = "Quadrilateral", vertices = [{ x = 1.0, y = 0.0, z = 0.0 }, { x = 1.0, y = 1.0, z = 0.0 }, { x = 1.0, y = 1.0, z = 1.0 }, { x = 1.0, y = 0.0, z = 1.0 }] }
+        material = { type = "Lambertian", albedo = { x = 0.2, y = 0.8, z = 0.2 } }
+
+        [[objects]]
+        shape = { type = "Sphere", center = { x = 0.7, y = 0.2, z = 0.5 }, radius = 0.15 }
+        material = { type = "IdealDielectric", ior = 1.5 }
+
+        [[lights]]
+        type = "Point"
+        position = { x = 0.5, y = 0.9, z = 0.5 }
+        intensity = { x = 1.0, y = 1.0, z = 1.0 }
+    "#;
+
+    #[test]
+    fn test_whitted_max_depth() {
+        let scene_config: SceneConfig = toml::from_str(FACING_MIRRORS_SCENE_CONFIG).unwrap();
+        let scene = Scene::from_config(&scene_config).unwrap();
+        let mut sampler = RandomSampler::new(1);
+
+        let ray = Ray {
+            origin: Point3D::new(0.1, 0.2, 0.0),
+            direction: Vec3D::new(0.3, 1.0, 0.5).normalize(),
+        };
+        for max_depth in [1, 5, 50] {
+            let mut tracer = WhittedTracerConfig { max_depth }.to_tracer();
+            scene.intersect_count.store(0, Ordering::Relaxed);
+            let color = tracer.trace(&ray, &scene, &mut sampler);
+            assert_eq!(
+                scene.intersect_count.load(Ordering::Relaxed),
+                max_depth as usize
+            );
+            assert!(color.is_zero());
+        }
+    }
+
+    #[test]
+    fn test_whitted_mirror_box() {
+        let scene_config: SceneConfig = toml::from_str(MIRROR_BOX_SCENE_CONFIG).unwrap();
+        let scene = Scene::from_config(&scene_config).unwrap();
+        let mut sampler = RandomSampler::new(1);
+        let mut tracer = WhittedTracerConfig { max_depth: 5 }.to_tracer();
+
+        // the mirror shows the front wall behind the camera
+        let origin = Point3D::new(0.5, 0.5, 0.9);
+        let mirror_point = Point3D::new(0.3, 0.4, 0.0);
+        let direction = (mirror_point - origin).normalize();
+        let reflected = spawn_ray(
+            mirror_point,
+            Vec3D::new(0.0, 0.0, 1.0),
+            Vec3D::new(direction.x, direction.y, -direction.z),
+        );
+        let color = tracer.trace(&Ray { origin, direction }, &scene, &mut sampler);
+        let expected = WhittedTracerConfig { max_depth: 4 }.to_tracer().trace(
+            &reflected,
+            &scene,
+            &mut sampler,
+        );
+        assert!(
+            vec3_approx_eq(color, expected, tolerance(1e-9)),
+            "seen in the mirror: {:?}, front wall: {:?}",
+            color,
+            expected
+        );
+        assert!(color.z > color.x && color.z > color.y);
+
+        // reflection and refraction through the glass ball are both followed
+        // rather than sampled, so tracing it is deterministic
+        let ray = Ray {
+            origin,
+            direction: (Point3D::new(0.7, 0.2, 0.5) - origin).normalize(),
+        };
+        let color = tracer.trace(&ray, &scene, &mut sampler);
+        assert!(!color.is_zero());
+        assert_eq!(color, tracer.trace(&ray, &scene, &mut sampler));
+    }
+}
@@ -0,0 +1,266 @@
+use super::math::consts::{FRAC_1_PI, PI};
+use super::math::{spherical_to_world, Ray, ScalarF, Vec3D, Vec3DConfig};
+use super::sampler::Sampler;
+use cgmath::{Array, ElementWise, InnerSpace};
+use serde::Deserialize;
+
+// medium with the same density everywhere, e.g. fog filling the scene.
+// light is absorbed with `sigma_a` and scattered with `sigma_s` per unit
+// length, `g` is the asymmetry of the Henyey-Greenstein phase function
+#[derive(Debug, Clone)]
+pub struct HomogeneousVolume {
+    pub sigma_a: Vec3D,
+    pub sigma_s: Vec3D,
+    pub g: ScalarF,
+}
+
+#[derive(Deserialize)]
+pub struct HomogeneousVolumeConfig {
+    pub sigma_a: Vec3DConfig,
+    pub sigma_s: Vec3DConfig,
+    #[serde(default)]
+    pub g: f64,
+}
+
+impl HomogeneousVolume {
+    pub fn sigma_t(&self) -> Vec3D {
+        self.sigma_a + self.sigma_s
+    }
+
+    // Beer-Lambert law, fraction of light left after a distance `t`
+    pub fn transmittance(&self, t: ScalarF) -> Vec3D {
+        let sigma_t = self.sigma_t();
+        Vec3D::new(
+            (-sigma_t.x * t).exp(),
+            (-sigma_t.y * t).exp(),
+            (-sigma_t.z * t).exp(),
+        )
+    }
+
+    // distances are sampled with the average of the channels' extinction
+    fn sampling_density(&self) -> ScalarF {
+        self.sigma_t().sum() / 3.0
+    }
+
+    // distance a ray travels before it scatters, and the weight of the
+    // scattered path. None when it reaches `t_max` first, its weight is
+    // `pass_weight` then
+    pub fn sample_distance(
+        &self,
+        t_max: ScalarF,
+        sampler: &mut dyn Sampler,
+    ) -> Option<(ScalarF, Vec3D)> {
+        let density = self.sampling_density();
+        if density <= 0.0 {
+            return None;
+        }
+        let t = -(1.0 - sampler.get_1d()).ln() / density;
+        if t >= t_max {
+            return None;
+        }
+        let pdf = density * (-density * t).exp();
+        Some((
+            t,
+            self.sigma_s.mul_element_wise(self.transmittance(t)) / pdf,
+        ))
+    }
+
+    // weight of a ray reaching `t_max` without scattering
+    pub fn pass_weight(&self, t_max: ScalarF) -> Vec3D {
+        let density = self.sampling_density();
+        if t_max.is_infinite() {
+            // always scatters before
+            return Vec3D::from_value(if density > 0.0 { 0.0 } else { 1.0 });
+        }
+        self.transmittance(t_max) / (-density * t_max).exp()
+    }
+
+    pub fn phase_function(&self) -> Box<dyn PhaseFunction> {
+        if self.g == 0.0 {
+            Box::new(IsotropicPhase {})
+        } else {
+            Box::new(HenyeyGreenstein { g: self.g })
+        }
+    }
+}
+
+// angular distribution of light scattered inside a medium, directions are
+// those of travel so `wi` points towards the scattering point
+pub trait PhaseFunction {
+    // new direction of `ray_in` and its solid angle density
+    fn sample(&self, ray_in: &Ray, sampler: &mut dyn Sampler) -> (Vec3D, ScalarF);
+    // density of scattering `wi` into `wo`, integrates to one over `wo`.
+    // sampling follows it exactly, so paths don't evaluate it yet
+    #[allow(dead_code)]
+    fn eval(&self, wi: Vec3D, wo: Vec3D) -> ScalarF;
+}
+
+// positive `g` scatters forwards, negative backwards
+#[derive(Debug, Clone)]
+pub struct HenyeyGreenstein {
+    pub g: ScalarF,
+}
+
+impl HenyeyGreenstein {
+    fn density(&self, cos_theta: ScalarF) -> ScalarF {
+        let g = self.g;
+        let denominator = 1.0 + g * g - 2.0 * g * cos_theta;
+        (1.0 - g * g) / (4.0 * PI * denominator * denominator.sqrt())
+    }
+
+    // inverse of the cumulative distribution of the angle's cosine
+    fn sample_cos_theta(&self, u: ScalarF) -> ScalarF {
+        let g = self.g;
+        if g.abs() < 1e-3 {
+            return 1.0 - 2.0 * u;
+        }
+        let s = (1.0 - g * g) / (1.0 - g + 2.0 * g * u);
+        ((1.0 + g * g - s * s) / (2.0 * g)).clamp(-1.0, 1.0)
+    }
+}
+
+impl PhaseFunction for HenyeyGreenstein {
+    fn sample(&self, ray_in: &Ray, sampler: &mut dyn Sampler) -> (Vec3D, ScalarF) {
+        let (u, v) = sampler.get_2d();
+        let cos_theta = self.sample_cos_theta(u);
+        let direction =
+            spherical_to_world(cos_theta.acos(), 2.0 * PI * v, ray_in.direction.normalize());
+        (direction, self.density(cos_theta))
+    }
+
+    fn eval(&self, wi: Vec3D, wo: Vec3D) -> ScalarF {
+        self.density(wi.normalize().dot(wo.normalize()))
+    }
+}
+
+// scatters evenly into every direction
+#[derive(Debug, Clone)]
+pub struct IsotropicPhase {}
+
+impl PhaseFunction for IsotropicPhase {
+    fn sample(&self, ray_in: &Ray, sampler: &mut dyn Sampler) -> (Vec3D, ScalarF) {
+        let (u, v) = sampler.get_2d();
+        let direction = spherical_to_world(
+            (1.0 - 2.0 * u).acos(),
+            2.0 * PI * v,
+            ray_in.direction.normalize(),
+        );
+        (direction, 0.25 * FRAC_1_PI)
+    }
+
+    fn eval(&self, _wi: Vec3D, _wo: Vec3D) -> ScalarF {
+        0.25 * FRAC_1_PI
+    }
+}
+
+impl HomogeneousVolumeConfig {
+    pub fn to_volume(&self) -> HomogeneousVolume {
+        HomogeneousVolume {
+            sigma_a: self.sigma_a.to_vec3(),
+            sigma_s: self.sigma_s.to_vec3(),
+            g: self.g as ScalarF,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::tolerance;
+    use crate::sampler::RandomSampler;
+
+    #[test]
+    fn test_transmittance() {
+        let volume = HomogeneousVolume {
+            sigma_a: Vec3D::new(0.1, 0.2, 0.3),
+            sigma_s: Vec3D::new(0.4, 0.3, 0.2),
+            g: 0.0,
+        };
+        // a slab of thickness 2 keeps exp(-1) of the light in every channel
+        let transmittance = volume.transmittance(2.0);
+        for c in [transmittance.x, transmittance.y, transmittance.z] {
+            assert!((c - ScalarF::exp(-1.0)).abs() < tolerance(1e-12));
+        }
+
+        // as many rays get through the slab without scattering, and the
+        // scattered ones carry the single scattering albedo
+        crate::rng::init_thread_local_rng(3);
+        let mut sampler = RandomSampler::new(1);
+        let n = 100_000;
+        let mut passed = 0;
+        for _ in 0..n {
+            match volume.sample_distance(2.0, &mut sampler) {
+                Some((t, weight)) => {
+                    assert!(t < 2.0);
+                    assert!((weight.x - 0.8).abs() < tolerance(1e-9));
+                }
+                None => passed += 1,
+            }
+        }
+        let expected = ScalarF::exp(-1.0);
+        assert!((passed as ScalarF / n as ScalarF - expected).abs() < 0.005);
+        assert!((volume.pass_weight(2.0) - Vec3D::from_value(1.0)).magnitude() < tolerance(1e-12));
+    }
+
+    #[test]
+    fn test_henyey_greenstein() {
+        let ray_in = Ray {
+            origin: crate::math::Point3D::new(0.0, 0.0, 0.0),
+            direction: Vec3D::new(0.3, -1.0, 0.5).normalize(),
+        };
+
+        // without asymmetry it is the isotropic phase function
+        let (hg, isotropic) = (HenyeyGreenstein { g: 0.0 }, IsotropicPhase {});
+        crate::rng::init_thread_local_rng(4);
+        let mut sampler = RandomSampler::new(1);
+        for _ in 0..100 {
+            let wo = isotropic.sample(&ray_in, &mut sampler).0;
+            assert!(
+                (hg.eval(ray_in.direction, wo) - isotropic.eval(ray_in.direction, wo)).abs()
+                    < tolerance(1e-12)
+            );
+        }
+        crate::rng::init_thread_local_rng(5);
+        let mut sampler = RandomSampler::new(1);
+        let expected: Vec<_> = (0..100)
+            .map(|_| isotropic.sample(&ray_in, &mut sampler))
+            .collect();
+        crate::rng::init_thread_local_rng(5);
+        let mut sampler = RandomSampler::new(1);
+        for (direction, pdf) in expected {
+            let (hg_direction, hg_pdf) = hg.sample(&ray_in, &mut sampler);
+            assert!((hg_direction - direction).magnitude() < tolerance(1e-12));
+            assert!((hg_pdf - pdf).abs() < tolerance(1e-12));
+        }
+
+        for g in [-0.6, 0.3, 0.8] {
+            let phase = HenyeyGreenstein { g };
+            // integrates to one over the sphere
+            let n = 10_000;
+            let integral = (0..n)
+                .map(|_| {
+                    let wo = isotropic.sample(&ray_in, &mut sampler).0;
+                    phase.eval(ray_in.direction, wo) * 4.0 * PI
+                })
+                .sum::<ScalarF>()
+                / n as ScalarF;
+            assert!(
+                (integral - 1.0).abs() < 0.01 * (1.0 + 4.0 * g * g),
+                "g = {}: {}",
+                g,
+                integral
+            );
+
+            // samples carry their density, and average to a cosine of g
+            let mut mean_cos = 0.0;
+            for _ in 0..n {
+                let (wo, pdf) = phase.sample(&ray_in, &mut sampler);
+                assert!(
+                    (pdf - phase.eval(ray_in.direction, wo)).abs() < tolerance(1e-9) * pdf.max(1.0)
+                );
+                mean_cos += wo.dot(ray_in.direction) / n as ScalarF;
+            }
+            assert!((mean_cos - g).abs() < 0.02, "g = {}: {}", g, mean_cos);
+        }
+    }
+}